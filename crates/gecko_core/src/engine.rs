@@ -44,9 +44,17 @@ use crate::config::EngineConfig;
 use crate::device::AudioDevice;
 use crate::error::{EngineError, EngineResult};
 use crate::message::{Command, Event};
+use crate::settings::remap_gains;
 use crate::stream::AudioStream;
+use gecko_dsp::{
+    AutoLevelSettings, BandParams, ChainConfig, ChainScope, CrossfeedSettings, EqChannelMode,
+    ImpulseResponse, LimiterSettings, OutputStage, PhaseMode, MAX_SYNC_OFFSET_MS,
+};
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use gecko_dsp::{LoudnessStats, LOUDNESS_FLOOR_LUFS};
 
 // Platform backend for audio routing
+use gecko_platform::app_name_from_stream_id;
 #[cfg(target_os = "linux")]
 use gecko_platform::{PlatformBackend, VirtualSinkConfig};
 
@@ -81,6 +89,26 @@ fn is_process_running(pid: u32) -> bool {
     unsafe { libc::kill(pid as i32, 0) == 0 }
 }
 
+/// Standard-layout band shapes for `band_count` bands
+fn default_band_params(band_count: usize) -> Vec<BandParams> {
+    gecko_dsp::band_layout_params(band_count)[..band_count].to_vec()
+}
+
+/// Whether a loudness reading reflects audio (silence reads at the floor)
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn is_audible(stats: &LoudnessStats) -> bool {
+    stats.momentary_lufs > LOUDNESS_FLOOR_LUFS
+}
+
+/// Crossfeed for the output device, or `None` (off) for devices without it
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn crossfeed_for_device(
+    devices: &std::collections::HashMap<String, CrossfeedSettings>,
+    device_name: Option<&str>,
+) -> Option<CrossfeedSettings> {
+    device_name.and_then(|name| devices.get(name)).copied()
+}
+
 /// The main audio engine controller
 ///
 /// This struct lives on the UI/main thread and communicates with the
@@ -163,6 +191,119 @@ impl AudioEngine {
         self.send_command(Command::SetBandGain { band, gain_db })
     }
 
+    /// Set frequency, Q, filter type and enable state for a master EQ band
+    ///
+    /// Shapes are validated here so the caller gets an error instead of the
+    /// audio thread silently dropping them.
+    pub fn set_band_params(&self, band: usize, params: BandParams) -> EngineResult<()> {
+        params.validate()?;
+        self.send_command(Command::SetBandParams { band, params })
+    }
+
+    /// Set frequency, Q, filter type and enable state for a per-app EQ band
+    pub fn set_stream_band_params(&self, stream_id: String, band: usize, params: BandParams) -> EngineResult<()> {
+        params.validate()?;
+        self.send_command(Command::SetStreamBandParams { stream_id, band, params })
+    }
+
+    /// Change the number of EQ bands (1-31) for master and all per-app EQs
+    ///
+    /// Gains are interpolated onto the new layout, as
+    /// `GeckoSettings::set_band_count` does; band shapes reset to the standard layout.
+    pub fn set_eq_band_count(&self, band_count: usize) -> EngineResult<()> {
+        if band_count == 0 || band_count > gecko_dsp::MAX_BANDS {
+            return Err(gecko_dsp::DspError::InvalidBandCount(band_count).into());
+        }
+        self.send_command(Command::SetEqBandCount(band_count))
+    }
+
+    /// Set how the master EQ treats the front left/right pair
+    ///
+    /// Per-app EQs always stay linked.
+    pub fn set_eq_channel_mode(&self, mode: EqChannelMode) -> EngineResult<()> {
+        self.send_command(Command::SetEqChannelMode(mode))
+    }
+
+    /// Set a secondary (right or side) master EQ band gain
+    pub fn set_secondary_band_gain(&self, band: usize, gain_db: f32) -> EngineResult<()> {
+        self.send_command(Command::SetSecondaryBandGain { band, gain_db })
+    }
+
+    /// Set frequency, Q, filter type and enable state for a secondary master EQ band
+    pub fn set_secondary_band_params(&self, band: usize, params: BandParams) -> EngineResult<()> {
+        params.validate()?;
+        self.send_command(Command::SetSecondaryBandParams { band, params })
+    }
+
+    /// Set master stereo width (0.0 mono, 1.0 unchanged, up to 2.0)
+    pub fn set_stereo_width(&self, width: f32) -> EngineResult<()> {
+        if !(0.0..=gecko_dsp::MAX_STEREO_WIDTH).contains(&width) {
+            let error = gecko_dsp::DspError::InvalidParameter { name: "stereo_width", value: width };
+            return Err(error.into());
+        }
+        self.send_command(Command::SetStereoWidth(width))
+    }
+
+    /// Enable or disable automatic preamp
+    ///
+    /// Master and per-app EQs lower their output by their largest boost so
+    /// boosted bands do not clip.
+    pub fn set_auto_preamp(&self, enabled: bool) -> EngineResult<()> {
+        self.send_command(Command::SetAutoPreamp(enabled))
+    }
+
+    /// Set the master EQ preamp (dB, ±24), e.g. the preamp of an AutoEQ preset
+    ///
+    /// Applies while auto preamp is off; auto preamp replaces it otherwise.
+    pub fn set_eq_preamp(&self, preamp_db: f32) -> EngineResult<()> {
+        if !(-24.0..=24.0).contains(&preamp_db) {
+            return Err(gecko_dsp::DspError::InvalidParameter {
+                name: "preamp",
+                value: preamp_db,
+            }
+            .into());
+        }
+        self.send_command(Command::SetEqPreamp(preamp_db))
+    }
+
+    /// Preamp (dB) the master EQ applies, or an app's EQ when `app_name` is given
+    ///
+    /// Read back from the running equalizers: the manual preamp (master) or
+    /// 0 dB (apps) while auto preamp is off, and 0 dB while the engine is stopped.
+    pub fn eq_preamp_db(&self, app_name: Option<String>) -> EngineResult<f32> {
+        let (reply, response) = bounded(1);
+        self.send_command(Command::GetEqPreamp { app_name, reply })?;
+        response
+            .recv_timeout(std::time::Duration::from_secs(1))
+            .map_err(|_| EngineError::ChannelRecvError)
+    }
+
+    /// Sample rate (Hz) the output runs at
+    ///
+    /// The rate negotiated with the output device once the backend is
+    /// running (it can differ from `config().stream.sample_rate`), else the
+    /// configured rate.
+    pub fn output_rate(&self) -> EngineResult<u32> {
+        let (reply, response) = bounded(1);
+        self.send_command(Command::GetOutputRate { reply })?;
+        response
+            .recv_timeout(std::time::Duration::from_secs(1))
+            .map_err(|_| EngineError::ChannelRecvError)
+    }
+
+    /// Latency (samples at `output_rate`) the master chain adds, plus
+    /// `app_name`'s chain when given
+    ///
+    /// Sums the linear-phase EQs and the limiter; 0 while no backend is
+    /// running or every stage is minimum phase / soft clipping.
+    pub fn output_latency_samples(&self, app_name: Option<String>) -> EngineResult<usize> {
+        let (reply, response) = bounded(1);
+        self.send_command(Command::GetOutputLatency { app_name, reply })?;
+        response
+            .recv_timeout(std::time::Duration::from_secs(1))
+            .map_err(|_| EngineError::ChannelRecvError)
+    }
+
     /// Set per-app EQ band gain (TRUE per-app EQ, NOT additive to master)
     ///
     /// Each app has its own independent EQ instance that processes audio BEFORE mixing.
@@ -179,6 +320,64 @@ impl AudioEngine {
         self.send_command(Command::SetAppBypass { app_name, bypassed })
     }
 
+    /// Enable or disable auto-leveling for a specific application
+    ///
+    /// A leveled app's gain drifts slowly toward the shared target loudness.
+    /// Its per-app volume is still applied on top.
+    pub fn set_app_auto_level(&self, app_name: String, enabled: bool) -> EngineResult<()> {
+        self.send_command(Command::SetAppAutoLevel { app_name, enabled })
+    }
+
+    /// Set the A/V sync offset for a specific application (±MAX_SYNC_OFFSET_MS)
+    ///
+    /// A positive offset delays the app's audio, e.g. to match a video player
+    /// on Bluetooth headphones. A negative one plays it early by delaying
+    /// every other app instead.
+    pub fn set_app_delay(&self, app_name: String, delay_ms: f32) -> EngineResult<()> {
+        if !(-MAX_SYNC_OFFSET_MS..=MAX_SYNC_OFFSET_MS).contains(&delay_ms) {
+            return Err(gecko_dsp::DspError::InvalidParameter {
+                name: "app delay",
+                value: delay_ms,
+            }
+            .into());
+        }
+        self.send_command(Command::SetAppDelay { app_name, delay_ms })
+    }
+
+    /// Replace the master (`app_name: None`) or an app's processor chain
+    ///
+    /// Stages can be added, removed, reordered or bypassed while audio
+    /// plays; the audio thread crossfades to the rebuilt chain.
+    ///
+    /// On macOS only the master chain is available: the CoreAudio mixer sums
+    /// the captured apps before processing, so an app's chain is rejected.
+    pub fn set_processor_chain(
+        &self,
+        app_name: Option<String>,
+        chain: ChainConfig,
+    ) -> EngineResult<()> {
+        #[cfg(target_os = "macos")]
+        if app_name.is_some() {
+            return Err(gecko_platform::PlatformError::FeatureNotAvailable(
+                "per-app processor chains".into(),
+            )
+            .into());
+        }
+        let scope = if app_name.is_some() {
+            ChainScope::App
+        } else {
+            ChainScope::Master
+        };
+        chain.validate(scope)?;
+        self.send_command(Command::SetProcessorChain { app_name, chain })
+    }
+
+    /// Set the auto-leveling target, max boost/cut and speed shared by all apps
+    pub fn set_auto_level_settings(&self, settings: AutoLevelSettings) -> EngineResult<()> {
+        settings.validate()?;
+        self.send_command(Command::SetAutoLevelSettings(settings))
+    }
+
     /// Start capturing audio from a specific application (macOS only)
     ///
     /// Uses the Process Tap API (macOS 14.4+) to capture the app's audio stream.
@@ -407,6 +606,80 @@ impl AudioEngine {
         self.send_command(Command::SetSoftClipEnabled(enabled))
     }
 
+    /// Select the final output stage (soft clipper or lookahead limiter)
+    pub fn set_output_stage(&self, stage: OutputStage) -> EngineResult<()> {
+        self.send_command(Command::SetOutputStage(stage))
+    }
+
+    /// Set the lookahead limiter's ceiling and release
+    pub fn set_limiter_settings(&self, settings: LimiterSettings) -> EngineResult<()> {
+        settings.validate()?;
+        self.send_command(Command::SetLimiterSettings(settings))
+    }
+
+    /// Run the master (`app_name: None`) or an app's EQ minimum or linear phase
+    ///
+    /// Linear phase keeps the EQ phase-true at the cost of latency, reported
+    /// by [`Self::output_latency_samples`]. On macOS only the master EQ can
+    /// switch: the captured apps are summed before processing.
+    pub fn set_phase_mode(&self, app_name: Option<String>, mode: PhaseMode) -> EngineResult<()> {
+        #[cfg(target_os = "macos")]
+        if app_name.is_some() {
+            return Err(gecko_platform::PlatformError::FeatureNotAvailable(
+                "per-app linear phase EQ".into(),
+            )
+            .into());
+        }
+        self.send_command(Command::SetPhaseMode { app_name, mode })
+    }
+
+    /// Load a WAV impulse response for the master's (`app_name: None`) or an
+    /// app's Convolver stage
+    ///
+    /// The file is read here, so a missing or invalid IR is reported to the
+    /// caller; it is heard wherever the chain has a Convolver stage. On macOS
+    /// only the master chain can convolve.
+    pub fn load_impulse_response(
+        &self,
+        app_name: Option<String>,
+        path: impl AsRef<std::path::Path>,
+    ) -> EngineResult<()> {
+        #[cfg(target_os = "macos")]
+        if app_name.is_some() {
+            return Err(gecko_platform::PlatformError::FeatureNotAvailable(
+                "per-app convolution".into(),
+            )
+            .into());
+        }
+        let ir = ImpulseResponse::from_wav_file(path)?;
+        self.send_command(Command::SetImpulseResponse { app_name, ir: Some(ir) })
+    }
+
+    /// Remove the master's (`app_name: None`) or an app's impulse response
+    ///
+    /// Its Convolver stage is skipped until another IR is loaded.
+    pub fn clear_impulse_response(&self, app_name: Option<String>) -> EngineResult<()> {
+        self.send_command(Command::SetImpulseResponse { app_name, ir: None })
+    }
+
+    /// Set headphone crossfeed for an output device (`None` turns it off)
+    ///
+    /// Applies immediately when `device_name` is the current output, and
+    /// whenever Gecko switches to that device later (see `Event::OutputDeviceChanged`).
+    pub fn set_crossfeed(&self, device_name: String, settings: Option<CrossfeedSettings>) -> EngineResult<()> {
+        if let Some(ref settings) = settings {
+            settings.validate()?;
+        }
+        self.send_command(Command::SetCrossfeed { device_name, settings })
+    }
+
+    /// Restart integrated loudness, range and true peak
+    ///
+    /// Resets one app, or the master and every app when `app_name` is `None`.
+    pub fn reset_loudness(&self, app_name: Option<String>) -> EngineResult<()> {
+        self.send_command(Command::ResetLoudness { app_name })
+    }
+
     /// Request state update
     pub fn request_state(&self) -> EngineResult<()> {
         self.send_command(Command::RequestState)
@@ -458,12 +731,36 @@ impl AudioEngine {
         let mut master_volume = 1.0_f32;
         let mut bypassed = false;
         // Track Master EQ gains locally so we can restore them when creating a new backend
-        let mut master_eq_gains = [0.0f32; 10];
+        // Its length is the active band count (shared by master and per-app EQs)
+        let mut master_eq_gains = vec![0.0f32; gecko_dsp::EQ_BANDS.len()];
+        // Master band shapes (frequency, Q, type, enabled), restored alongside gains
+        let mut master_band_params = default_band_params(master_eq_gains.len());
+        // Left/right and mid/side modes: the secondary (right or side) config and stereo width
+        let mut eq_channel_mode = EqChannelMode::Linked;
+        let mut secondary_eq_gains = vec![0.0f32; master_eq_gains.len()];
+        let mut secondary_band_params = default_band_params(master_eq_gains.len());
+        let mut stereo_width = 1.0_f32;
+        let mut auto_preamp = false;
+        let mut manual_preamp_db = 0.0_f32;
         
         // Track per-app state for persistence across engine restarts
         let mut app_volumes: std::collections::HashMap<String, f32> = std::collections::HashMap::new();
         let mut app_bypassed: std::collections::HashMap<String, bool> = std::collections::HashMap::new();
-        let mut app_eq_gains: std::collections::HashMap<String, [f32; 10]> = std::collections::HashMap::new();
+        let mut app_auto_level: std::collections::HashMap<String, bool> = std::collections::HashMap::new();
+        let mut app_delays: std::collections::HashMap<String, f32> = std::collections::HashMap::new();
+        let mut app_chains: std::collections::HashMap<String, ChainConfig> = std::collections::HashMap::new();
+        let mut master_chain = ChainConfig::master_default();
+        let mut master_phase_mode = PhaseMode::default();
+        let mut app_phase_modes: std::collections::HashMap<String, PhaseMode> = std::collections::HashMap::new();
+        let mut master_ir: Option<ImpulseResponse> = None;
+        let mut app_irs: std::collections::HashMap<String, ImpulseResponse> = std::collections::HashMap::new();
+
+        // Crossfeed per output device, applied when that device is the output
+        let mut crossfeed_devices: std::collections::HashMap<String, CrossfeedSettings> = std::collections::HashMap::new();
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        let mut output_device_name: Option<String> = None;
+        let mut app_eq_gains: std::collections::HashMap<String, Vec<f32>> = std::collections::HashMap::new();
+        let mut app_band_params: std::collections::HashMap<String, Vec<BandParams>> = std::collections::HashMap::new();
 
         // Linux: Store PipeWire backend for command forwarding
         #[cfg(target_os = "linux")]
@@ -478,7 +775,7 @@ impl AudioEngine {
         #[cfg(target_os = "macos")]
         let mut macos_state: Option<Arc<AudioProcessingState>> = None;
         #[cfg(target_os = "macos")]
-        let mut _macos_output: Option<AudioOutputStream> = None;
+        let mut macos_output: Option<AudioOutputStream> = None;
         // macOS: Timer for periodic app scanning (every 2 seconds for new apps)
         #[cfg(target_os = "macos")]
         let mut last_app_scan = std::time::Instant::now();
@@ -512,6 +809,18 @@ impl AudioEngine {
         #[cfg(target_os = "linux")]
         let mut current_output_sink_id: Option<u32> = None;
 
+        // Loudness meters update every 100ms; events follow that rate
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        let mut last_loudness_update = std::time::Instant::now();
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        let loudness_interval = std::time::Duration::from_millis(100);
+
+        // Clock drift of per-app captures changes slowly; report it once a second
+        #[cfg(target_os = "linux")]
+        let mut last_drift_update = std::time::Instant::now();
+        #[cfg(target_os = "linux")]
+        let drift_interval = std::time::Duration::from_secs(1);
+
         // Main command processing loop
         while !shutdown_flag.load(Ordering::SeqCst) {
             // Use timeout to periodically check shutdown flag and send level/spectrum updates
@@ -544,6 +853,12 @@ impl AudioEngine {
                                     Ok(mut backend) => {
                                         info!("PipeWire backend connected: {}", backend.is_connected());
 
+                                        // Per-app sinks, capture streams and the mixer all use the
+                                        // configured layout (stereo, 5.1, 7.1, ...)
+                                        if let Err(e) = backend.set_channel_count(config.stream.channels as u32) {
+                                            warn!("Unsupported channel count, keeping stereo: {}", e);
+                                        }
+
                                         // CONSTANT: Create the virtual sink in ALL modes.
                                         // In Per-App Mode, this acts as a "Honeypot" or "Catch-all".
                                         // - We set this as the system Default Sink.
@@ -552,7 +867,7 @@ impl AudioEngine {
                                         // - If we DON'T create this, apps crash when trying to connect to a non-existent default sink.
                                         let sink_config = VirtualSinkConfig {
                                             name: "Gecko Audio".to_string(),
-                                            channels: 2,
+                                            channels: config.stream.channels as u32,
                                             sample_rate: config.stream.sample_rate,
                                             persistent: false,
                                         };
@@ -585,6 +900,7 @@ impl AudioEngine {
                                             // Set initial state on backend
                                             backend.set_volume(master_volume);
                                             backend.set_bypass(bypassed);
+                                            backend.set_eq_band_count(master_eq_gains.len());
                                             
                                             // Apply stored Master EQ gains
                                             for (band, &gain_db) in master_eq_gains.iter().enumerate() {
@@ -603,6 +919,48 @@ impl AudioEngine {
                                                 backend.set_app_bypass(app_name, bypass);
                                             }
 
+                                            // Apply stored App Auto-Level
+                                            for (app_name, &enabled) in &app_auto_level {
+                                                backend.set_app_auto_level(app_name, enabled);
+                                            }
+
+                                            // Apply stored App Delays
+                                            for (app_name, &delay_ms) in &app_delays {
+                                                backend.set_app_delay(app_name, delay_ms);
+                                            }
+
+                                            // Apply stored processor chains
+                                            if master_chain != ChainConfig::master_default() {
+                                                if let Err(e) = backend.set_processor_chain(None, master_chain.clone()) {
+                                                    warn!("Failed to set master processor chain: {}", e);
+                                                }
+                                            }
+                                            for (app_name, chain) in &app_chains {
+                                                if let Err(e) = backend.set_processor_chain(Some(app_name), chain.clone()) {
+                                                    warn!("Failed to set processor chain for '{}': {}", app_name, e);
+                                                }
+                                            }
+
+                                            // Apply stored EQ phase modes
+                                            if master_phase_mode != PhaseMode::default() {
+                                                backend.set_phase_mode(None, master_phase_mode);
+                                            }
+                                            for (app_name, &mode) in &app_phase_modes {
+                                                backend.set_phase_mode(Some(app_name), mode);
+                                            }
+
+                                            // Apply stored impulse responses
+                                            if master_ir.is_some() {
+                                                if let Err(e) = backend.set_impulse_response(None, master_ir.clone()) {
+                                                    warn!("Failed to set master impulse response: {}", e);
+                                                }
+                                            }
+                                            for (app_name, ir) in &app_irs {
+                                                if let Err(e) = backend.set_impulse_response(Some(app_name), Some(ir.clone())) {
+                                                    warn!("Failed to set impulse response for '{}': {}", app_name, e);
+                                                }
+                                            }
+
                                             // Apply stored App EQ gains
                                             for (app_name, gains) in &app_eq_gains {
                                                 for (band, &gain_db) in gains.iter().enumerate() {
@@ -612,6 +970,35 @@ impl AudioEngine {
                                                 }
                                             }
 
+                                            // Apply stored band shapes (only those edited away from the layout)
+                                            let layout = default_band_params(master_band_params.len());
+                                            for (band, &params) in master_band_params.iter().enumerate() {
+                                                if params != layout[band] {
+                                                    backend.update_eq_band_params(band, params);
+                                                }
+                                            }
+                                            for (app_name, shapes) in &app_band_params {
+                                                for (band, &params) in shapes.iter().enumerate() {
+                                                    if params != layout[band] {
+                                                        backend.update_stream_band_params(app_name, band, params);
+                                                    }
+                                                }
+                                            }
+
+                                            // Apply stored channel mode, secondary config and width
+                                            backend.set_eq_channel_mode(eq_channel_mode);
+                                            for (band, &gain_db) in secondary_eq_gains.iter().enumerate() {
+                                                backend.update_secondary_eq_band(band, gain_db);
+                                            }
+                                            for (band, &params) in secondary_band_params.iter().enumerate() {
+                                                backend.update_secondary_eq_band_params(band, params);
+                                            }
+                                            if let Err(e) = backend.set_stereo_width(stereo_width) {
+                                                warn!("Failed to set stereo width: {}", e);
+                                            }
+                                            backend.set_manual_preamp(manual_preamp_db);
+                                            backend.set_auto_preamp(auto_preamp);
+
                                             // Store backend and mark as running
                                             linux_backend = Some(backend);
                                             is_running.store(true, Ordering::SeqCst);
@@ -631,6 +1018,7 @@ impl AudioEngine {
                                             // Resolve the current default sink to use as the actual output
                                             // This prevents the loop where we output to ourselves
                                             let mut playback_target_id = None;
+                                            let mut playback_target_name = None;
                                             if let Ok(Some(def_sink_name)) = backend.get_default_sink_name() {
                                                 info!("Current default sink: {}", def_sink_name);
                                                 
@@ -644,6 +1032,7 @@ impl AudioEngine {
                                                         if let Some(fallback) = nodes.iter().find(|n| n.media_class == "Audio/Sink" && n.name != "Gecko Audio") {
                                                             info!("Found fallback hardware sink: {} (ID: {})", fallback.name, fallback.id);
                                                             playback_target_id = Some(fallback.id);
+                                                            playback_target_name = Some(fallback.name.clone());
                                                         } else {
                                                             warn!("No fallback hardware sink found! Audio might be silent.");
                                                         }
@@ -653,6 +1042,7 @@ impl AudioEngine {
                                                     if let Ok(Some(id)) = backend.get_node_id_by_name(&def_sink_name) {
                                                         info!("Resolved default sink '{}' to ID {}", def_sink_name, id);
                                                         playback_target_id = Some(id);
+                                                        playback_target_name = Some(def_sink_name.clone());
                                                     }
                                                 }
                                             }
@@ -667,6 +1057,16 @@ impl AudioEngine {
                                                     // Track current output sink for enforce_routing
                                                     current_output_sink_id = playback_target_id;
 
+                                                    // Apply this output's crossfeed (off for devices without it)
+                                                    output_device_name = playback_target_name;
+                                                    if let Some(ref name) = output_device_name {
+                                                        let _ = event_sender.send(Event::OutputDeviceChanged { device_name: name.clone() });
+                                                    }
+                                                    let crossfeed = crossfeed_for_device(&crossfeed_devices, output_device_name.as_deref());
+                                                    if let Err(e) = backend.set_crossfeed(crossfeed) {
+                                                        warn!("Failed to set crossfeed: {}", e);
+                                                    }
+
                                                     // Set Gecko Audio as the default sink so all apps automatically route to it
                                                     match backend.set_default_sink("Gecko Audio") {
                                                         Ok(prev_sink) => {
@@ -697,6 +1097,7 @@ impl AudioEngine {
                                                     // Set initial state on backend
                                                     backend.set_volume(master_volume);
                                                     backend.set_bypass(bypassed);
+                                                    backend.set_eq_band_count(master_eq_gains.len());
 
                                                     // Store the backend for command forwarding
                                                     linux_backend = Some(backend);
@@ -748,6 +1149,7 @@ impl AudioEngine {
                                         // Set initial state
                                         state.set_master_volume(master_volume);
                                         state.set_bypassed(bypassed);
+                                        state.set_band_count(master_eq_gains.len());
 
                                         // Apply stored Master EQ gains to the processing state
                                         for (band, &gain_db) in master_eq_gains.iter().enumerate() {
@@ -756,6 +1158,40 @@ impl AudioEngine {
                                             }
                                         }
 
+                                        // Apply stored band shapes to the processing state
+                                        for (band, &params) in master_band_params.iter().enumerate() {
+                                            state.set_eq_band_params(band, params);
+                                        }
+
+                                        // Apply stored channel mode, secondary config and width
+                                        state.set_eq_channel_mode(eq_channel_mode);
+                                        for (band, &gain_db) in secondary_eq_gains.iter().enumerate() {
+                                            state.set_secondary_eq_band(band, gain_db);
+                                        }
+                                        for (band, &params) in secondary_band_params.iter().enumerate() {
+                                            state.set_secondary_eq_band_params(band, params);
+                                        }
+                                        if let Err(e) = state.set_stereo_width(stereo_width) {
+                                            warn!("Failed to set stereo width: {}", e);
+                                        }
+                                        state.set_manual_preamp(manual_preamp_db);
+                                        state.set_auto_preamp(auto_preamp);
+                                        for (app_name, shapes) in &app_band_params {
+                                            for (band, &params) in shapes.iter().enumerate() {
+                                                state.set_app_band_params(app_name, band, params);
+                                            }
+                                        }
+
+                                        state.set_phase_mode(master_phase_mode);
+                                        if let Err(e) = state.set_impulse_response(master_ir.clone()) {
+                                            warn!("Failed to set master impulse response: {}", e);
+                                        }
+
+                                        // The output stream builds its master chain from the state
+                                        if let Err(e) = state.set_master_chain(master_chain.clone()) {
+                                            warn!("Failed to set master processor chain: {}", e);
+                                        }
+
                                         // Create audio output stream (cpal-based)
                                         match AudioOutputStream::new_with_mixer(
                                             Arc::clone(&state),
@@ -771,6 +1207,7 @@ impl AudioEngine {
                                                 // Set initial state on backend (for per-app tracking)
                                                 backend.set_volume(master_volume);
                                                 backend.set_bypass(bypassed);
+                                                backend.set_eq_band_count(master_eq_gains.len());
 
                                                 // Apply stored Master EQ gains to backend
                                                 for (band, &gain_db) in master_eq_gains.iter().enumerate() {
@@ -845,11 +1282,21 @@ impl AudioEngine {
                                                     }
                                                 }
 
+                                                // Apply the output device's crossfeed (off for devices without it)
+                                                output_device_name = AudioDevice::default_output().ok().map(|d| d.name);
+                                                if let Some(ref name) = output_device_name {
+                                                    let _ = event_sender.send(Event::OutputDeviceChanged { device_name: name.clone() });
+                                                }
+                                                let crossfeed = crossfeed_for_device(&crossfeed_devices, output_device_name.as_deref());
+                                                if let Err(e) = state.set_crossfeed(crossfeed) {
+                                                    warn!("Failed to set crossfeed: {}", e);
+                                                }
+
                                                 // Store all components
                                                 macos_backend = Some(backend);
                                                 macos_mixer = Some(mixer);
                                                 macos_state = Some(state);
-                                                _macos_output = Some(output);
+                                                macos_output = Some(output);
 
                                                 is_running.store(true, Ordering::SeqCst);
                                                 let _ = event_sender.send(Event::Started);
@@ -965,7 +1412,7 @@ impl AudioEngine {
                             #[cfg(target_os = "macos")]
                             {
                                 // Stop output stream first (drops cpal stream)
-                                _macos_output = None;
+                                macos_output = None;
                                 // Clear mixer and state
                                 macos_mixer = None;
                                 macos_state = None;
@@ -1060,12 +1507,189 @@ impl AudioEngine {
                             }
                         }
 
+                        Command::SetOutputStage(stage) => {
+                            debug!("Set output stage: {:?}", stage);
+
+                            // Linux: Forward to PipeWire backend
+                            #[cfg(target_os = "linux")]
+                            if let Some(ref backend) = linux_backend {
+                                backend.set_output_stage(stage);
+                            }
+
+                            // macOS: Forward to CoreAudio processing state
+                            #[cfg(target_os = "macos")]
+                            if let Some(ref state) = macos_state {
+                                state.set_output_stage(stage);
+                            }
+                        }
+
+                        Command::ResetLoudness { app_name } => {
+                            debug!("Reset loudness: {:?}", app_name);
+
+                            // Linux: Forward to PipeWire backend
+                            #[cfg(target_os = "linux")]
+                            if let Some(ref backend) = linux_backend {
+                                backend.reset_loudness(app_name.as_deref());
+                            }
+
+                            // macOS: Only the master output is measured
+                            #[cfg(target_os = "macos")]
+                            if let Some(ref state) = macos_state {
+                                if app_name.is_none() {
+                                    state.reset_loudness();
+                                }
+                            }
+
+                            #[cfg(all(not(target_os = "linux"), not(target_os = "macos")))]
+                            let _ = app_name;
+                        }
+
+                        Command::SetLimiterSettings(settings) => {
+                            debug!("Set limiter settings: {:?}", settings);
+
+                            // Linux: Forward to PipeWire backend
+                            #[cfg(target_os = "linux")]
+                            if let Some(ref backend) = linux_backend {
+                                if let Err(e) = backend.set_limiter_settings(settings) {
+                                    warn!("Failed to set limiter settings: {}", e);
+                                }
+                            }
+
+                            // macOS: Forward to CoreAudio processing state
+                            #[cfg(target_os = "macos")]
+                            if let Some(ref state) = macos_state {
+                                if let Err(e) = state.set_limiter_settings(settings) {
+                                    warn!("Failed to set limiter settings: {}", e);
+                                }
+                            }
+                        }
+
+                        Command::SetPhaseMode { app_name, mode } => {
+                            debug!("Set EQ phase mode for {:?}: {:?}", app_name, mode);
+                            match app_name {
+                                Some(ref app_name) => {
+                                    app_phase_modes.insert(app_name.clone(), mode);
+                                }
+                                None => master_phase_mode = mode,
+                            }
+
+                            // Linux: Forward to PipeWire backend
+                            #[cfg(target_os = "linux")]
+                            if let Some(ref backend) = linux_backend {
+                                backend.set_phase_mode(app_name.as_deref(), mode);
+                            }
+
+                            // macOS: Only the master EQ (rejected in set_phase_mode)
+                            #[cfg(target_os = "macos")]
+                            if let Some(ref state) = macos_state {
+                                if app_name.is_none() {
+                                    state.set_phase_mode(mode);
+                                }
+                            }
+                        }
+
+                        Command::SetImpulseResponse { app_name, ir } => {
+                            // The IR itself is too large to log
+                            debug!("Set impulse response for {:?} (loaded: {})", app_name, ir.is_some());
+                            match (&app_name, &ir) {
+                                (Some(app_name), Some(ir)) => {
+                                    app_irs.insert(app_name.clone(), ir.clone());
+                                }
+                                (Some(app_name), None) => {
+                                    app_irs.remove(app_name);
+                                }
+                                (None, _) => master_ir = ir.clone(),
+                            }
+
+                            // Linux: Forward to PipeWire backend
+                            #[cfg(target_os = "linux")]
+                            if let Some(ref backend) = linux_backend {
+                                if let Err(e) = backend.set_impulse_response(app_name.as_deref(), ir) {
+                                    warn!("Failed to set impulse response: {}", e);
+                                }
+                            }
+
+                            // macOS: Master only (rejected in load_impulse_response);
+                            // rebuild the output stream's chain around the new IR
+                            #[cfg(target_os = "macos")]
+                            if let Some(ref state) = macos_state {
+                                if app_name.is_none() {
+                                    match state.set_impulse_response(ir) {
+                                        Ok(()) => {
+                                            if let Some(ref mut output) = macos_output {
+                                                if let Err(e) = output.set_master_chain(master_chain.clone()) {
+                                                    warn!("Failed to rebuild master processor chain: {}", e);
+                                                }
+                                            }
+                                        }
+                                        Err(e) => warn!("Failed to set impulse response: {}", e),
+                                    }
+                                }
+                            }
+                        }
+
+                        Command::SetAutoLevelSettings(settings) => {
+                            debug!("Set auto-level settings: {:?}", settings);
+
+                            // Linux: Forward to PipeWire backend
+                            #[cfg(target_os = "linux")]
+                            if let Some(ref backend) = linux_backend {
+                                if let Err(e) = backend.set_auto_level_settings(settings) {
+                                    warn!("Failed to set auto-level settings: {}", e);
+                                }
+                            }
+
+                            // macOS: TODO - Per-app auto-leveling in the CoreAudio mixer
+                            #[cfg(target_os = "macos")]
+                            {
+                                let _ = settings;
+                            }
+                        }
+
+                        Command::SetCrossfeed { device_name, settings } => {
+                            debug!("Set crossfeed for '{}': {:?}", device_name, settings);
+
+                            // Remember per device so it follows output switches
+                            match settings {
+                                Some(settings) => {
+                                    crossfeed_devices.insert(device_name.clone(), settings);
+                                }
+                                None => {
+                                    crossfeed_devices.remove(&device_name);
+                                }
+                            }
+
+                            // Apply now only if it's the device we're playing to
+                            #[cfg(any(target_os = "linux", target_os = "macos"))]
+                            let is_current = output_device_name.as_deref() == Some(device_name.as_str());
+
+                            // Linux: Forward to PipeWire backend
+                            #[cfg(target_os = "linux")]
+                            if is_current {
+                                if let Some(ref backend) = linux_backend {
+                                    if let Err(e) = backend.set_crossfeed(settings) {
+                                        warn!("Failed to set crossfeed: {}", e);
+                                    }
+                                }
+                            }
+
+                            // macOS: Forward to the output processing state
+                            #[cfg(target_os = "macos")]
+                            if is_current {
+                                if let Some(ref state) = macos_state {
+                                    if let Err(e) = state.set_crossfeed(settings) {
+                                        warn!("Failed to set crossfeed: {}", e);
+                                    }
+                                }
+                            }
+                        }
+
                         Command::SetBandGain { band, gain_db } => {
                             debug!("Set band {} gain to {}dB", band, gain_db);
 
                             // Update local state
-                            if band < 10 {
-                                master_eq_gains[band] = gain_db;
+                            if let Some(gain) = master_eq_gains.get_mut(band) {
+                                *gain = gain_db;
                             }
 
                             // Linux: Forward to PipeWire backend EQ
@@ -1087,34 +1711,303 @@ impl AudioEngine {
                             }
                         }
 
-                        Command::SetStreamBandGain { stream_id, band, gain_db } => {
-                            debug!("Set stream '{}' band {} gain to {}dB", stream_id, band, gain_db);
+                        Command::SetEqBandCount(band_count) => {
+                            debug!("Set EQ band count to {}", band_count);
 
-                            // Extract app name from stream_id
-                            // Format varies by platform:
-                            //   - Linux: "PID:Name" (name is after colon)
-                            //   - macOS: "Name:PID" (name is before colon)
+                            if band_count == 0 || band_count > gecko_dsp::MAX_BANDS {
+                                warn!("Ignoring invalid EQ band count {}", band_count);
+                                continue;
+                            }
+
+                            // Update local state - curves are remapped onto the new
+                            // layout, shapes start from the standard one
+                            let old_count = master_eq_gains.len();
+                            let remap = |gains: &[f32]| {
+                                remap_gains(gains, old_count, band_count)
+                                    .unwrap_or_else(|_| vec![0.0; band_count])
+                            };
+                            master_eq_gains = remap(&master_eq_gains);
+                            secondary_eq_gains = remap(&secondary_eq_gains);
+                            for gains in app_eq_gains.values_mut() {
+                                *gains = remap(gains);
+                            }
+                            master_band_params = default_band_params(band_count);
+                            app_band_params.clear();
+                            secondary_band_params = default_band_params(band_count);
+
+                            // Linux: Forward to PipeWire backend (master + per-app EQs),
+                            // then re-send the remapped curves
                             #[cfg(target_os = "linux")]
-                            let app_name = if let Some((_, name)) = stream_id.split_once(':') {
-                                name.to_string()
-                            } else {
-                                stream_id.clone()
+                            if let Some(ref backend) = linux_backend {
+                                backend.set_eq_band_count(band_count);
+                                for (band, &gain_db) in master_eq_gains.iter().enumerate() {
+                                    backend.update_eq_band(band, gain_db);
+                                }
+                                for (band, &gain_db) in secondary_eq_gains.iter().enumerate() {
+                                    backend.update_secondary_eq_band(band, gain_db);
+                                }
+                                for (app_name, gains) in &app_eq_gains {
+                                    for (band, &gain_db) in gains.iter().enumerate() {
+                                        backend.update_stream_eq_band(app_name, band, gain_db);
+                                    }
+                                }
+                            }
+
+                            // macOS: Forward to CoreAudio backend AND processing state
+                            #[cfg(target_os = "macos")]
+                            {
+                                if let Some(ref mut backend) = macos_backend {
+                                    backend.set_eq_band_count(band_count);
+                                    for (band, &gain_db) in master_eq_gains.iter().enumerate() {
+                                        backend.update_eq_band(band, gain_db);
+                                    }
+                                    for (app_name, gains) in &app_eq_gains {
+                                        for (band, &gain_db) in gains.iter().enumerate() {
+                                            backend.update_stream_eq_band(app_name, band, gain_db);
+                                        }
+                                    }
+                                }
+                                if let Some(ref state) = macos_state {
+                                    state.set_band_count(band_count);
+                                    for (band, &gain_db) in master_eq_gains.iter().enumerate() {
+                                        state.set_eq_band(band, gain_db);
+                                    }
+                                    for (band, &gain_db) in secondary_eq_gains.iter().enumerate() {
+                                        state.set_secondary_eq_band(band, gain_db);
+                                    }
+                                    for (app_name, gains) in &app_eq_gains {
+                                        for (band, &gain_db) in gains.iter().enumerate() {
+                                            state.set_app_eq_offset(app_name, band, gain_db);
+                                        }
+                                    }
+                                }
+                                if let Some(ref mixer) = macos_mixer {
+                                    mixer.set_band_count(band_count);
+                                }
+                            }
+                        }
+
+                        Command::SetBandParams { band, params } => {
+                            debug!("Set band {} shape to {:?}", band, params);
+
+                            // Update local state
+                            if let Some(slot) = master_band_params.get_mut(band) {
+                                *slot = params;
+                            }
+
+                            // Linux: Forward to PipeWire backend (lock-free via shared state)
+                            #[cfg(target_os = "linux")]
+                            if let Some(ref backend) = linux_backend {
+                                backend.update_eq_band_params(band, params);
+                            }
+
+                            // macOS: Update the EQ processor in processing state
+                            #[cfg(target_os = "macos")]
+                            if let Some(ref state) = macos_state {
+                                state.set_eq_band_params(band, params);
+                            }
+                        }
+
+                        Command::SetEqChannelMode(mode) => {
+                            debug!("Set EQ channel mode to {:?}", mode);
+
+                            eq_channel_mode = mode;
+
+                            #[cfg(target_os = "linux")]
+                            if let Some(ref backend) = linux_backend {
+                                backend.set_eq_channel_mode(mode);
+                            }
+
+                            #[cfg(target_os = "macos")]
+                            if let Some(ref state) = macos_state {
+                                state.set_eq_channel_mode(mode);
+                            }
+                        }
+
+                        Command::SetSecondaryBandGain { band, gain_db } => {
+                            debug!("Set secondary band {} gain to {}dB", band, gain_db);
+
+                            if let Some(gain) = secondary_eq_gains.get_mut(band) {
+                                *gain = gain_db;
+                            }
+
+                            #[cfg(target_os = "linux")]
+                            if let Some(ref backend) = linux_backend {
+                                backend.update_secondary_eq_band(band, gain_db);
+                            }
+
+                            #[cfg(target_os = "macos")]
+                            if let Some(ref state) = macos_state {
+                                state.set_secondary_eq_band(band, gain_db);
+                            }
+                        }
+
+                        Command::SetSecondaryBandParams { band, params } => {
+                            debug!("Set secondary band {} shape to {:?}", band, params);
+
+                            if let Some(slot) = secondary_band_params.get_mut(band) {
+                                *slot = params;
+                            }
+
+                            #[cfg(target_os = "linux")]
+                            if let Some(ref backend) = linux_backend {
+                                backend.update_secondary_eq_band_params(band, params);
+                            }
+
+                            #[cfg(target_os = "macos")]
+                            if let Some(ref state) = macos_state {
+                                state.set_secondary_eq_band_params(band, params);
+                            }
+                        }
+
+                        Command::SetStereoWidth(width) => {
+                            debug!("Set stereo width to {}", width);
+
+                            stereo_width = width;
+
+                            #[cfg(target_os = "linux")]
+                            if let Some(ref backend) = linux_backend {
+                                if let Err(e) = backend.set_stereo_width(width) {
+                                    warn!("Failed to set stereo width: {}", e);
+                                }
+                            }
+
+                            #[cfg(target_os = "macos")]
+                            if let Some(ref state) = macos_state {
+                                if let Err(e) = state.set_stereo_width(width) {
+                                    warn!("Failed to set stereo width: {}", e);
+                                }
+                            }
+                        }
+
+                        Command::SetAutoPreamp(enabled) => {
+                            debug!("Set auto preamp to {}", enabled);
+
+                            auto_preamp = enabled;
+
+                            #[cfg(target_os = "linux")]
+                            if let Some(ref backend) = linux_backend {
+                                backend.set_auto_preamp(enabled);
+                            }
+
+                            #[cfg(target_os = "macos")]
+                            if let Some(ref state) = macos_state {
+                                state.set_auto_preamp(enabled);
+                            }
+                        }
+
+                        Command::SetEqPreamp(preamp_db) => {
+                            debug!("Set EQ preamp to {}dB", preamp_db);
+
+                            manual_preamp_db = preamp_db;
+
+                            #[cfg(target_os = "linux")]
+                            if let Some(ref backend) = linux_backend {
+                                backend.set_manual_preamp(preamp_db);
+                            }
+
+                            #[cfg(target_os = "macos")]
+                            if let Some(ref state) = macos_state {
+                                state.set_manual_preamp(preamp_db);
+                            }
+                        }
+
+                        Command::GetEqPreamp { app_name, reply } => {
+                            #[cfg(target_os = "linux")]
+                            let preamp_db = linux_backend
+                                .as_ref()
+                                .map_or(0.0, |backend| backend.eq_preamp_db(app_name.as_deref()));
+
+                            #[cfg(target_os = "macos")]
+                            let preamp_db = macos_state
+                                .as_ref()
+                                .map_or(0.0, |state| state.eq_preamp_db(app_name.as_deref()));
+
+                            #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+                            let preamp_db = {
+                                let _ = app_name; // Suppress unused warnings
+                                0.0
                             };
 
+                            let _ = reply.send(preamp_db);
+                        }
+
+                        Command::GetOutputRate { reply } => {
+                            #[cfg(target_os = "linux")]
+                            let rate = linux_backend
+                                .as_ref()
+                                .map_or(config.stream.sample_rate, |backend| backend.output_rate());
+
                             #[cfg(target_os = "macos")]
-                            let app_name = if let Some((name, _)) = stream_id.split_once(':') {
-                                name.to_string()
-                            } else {
-                                stream_id.clone()
+                            let rate = macos_state.as_ref().map_or(config.stream.sample_rate, |state| {
+                                state.sample_rate() as u32
+                            });
+
+                            #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+                            let rate = config.stream.sample_rate;
+
+                            let _ = reply.send(rate);
+                        }
+
+                        Command::GetOutputLatency { app_name, reply } => {
+                            #[cfg(target_os = "linux")]
+                            let latency = linux_backend.as_ref().map_or(0, |backend| {
+                                backend.output_latency_samples(app_name.as_deref())
+                            });
+
+                            // macOS: Apps share the master chain, so only it adds latency
+                            #[cfg(target_os = "macos")]
+                            let latency = {
+                                let _ = app_name;
+                                macos_state
+                                    .as_ref()
+                                    .map_or(0, |state| state.output_latency_samples())
                             };
 
                             #[cfg(not(any(target_os = "linux", target_os = "macos")))]
-                            let app_name = stream_id.clone();
+                            let latency = {
+                                let _ = app_name;
+                                0
+                            };
+
+                            let _ = reply.send(latency);
+                        }
+
+                        Command::SetStreamBandParams { stream_id, band, params } => {
+                            debug!("Set stream '{}' band {} shape to {:?}", stream_id, band, params);
+
+                            let app_name = app_name_from_stream_id(&stream_id).to_string();
+
+                            // Update local state
+                            let band_count = master_eq_gains.len();
+                            if band < band_count {
+                                app_band_params.entry(app_name.clone())
+                                    .or_insert_with(|| default_band_params(band_count))[band] = params;
+                            }
+
+                            // Linux: Forward to PipeWire backend per-app EQ
+                            #[cfg(target_os = "linux")]
+                            if let Some(ref backend) = linux_backend {
+                                backend.update_stream_band_params(&stream_id, band, params);
+                            }
+
+                            // macOS: Processing state feeds the mixer's per-app EQs
+                            #[cfg(target_os = "macos")]
+                            if let Some(ref state) = macos_state {
+                                state.set_app_band_params(&app_name, band, params);
+                            }
+                        }
+
+                        Command::SetStreamBandGain { stream_id, band, gain_db } => {
+                            debug!("Set stream '{}' band {} gain to {}dB", stream_id, band, gain_db);
+
+                            let app_name = app_name_from_stream_id(&stream_id).to_string();
 
                             // Update local state
-                            if band < 10 {
+                            if band < master_eq_gains.len() {
+                                let band_count = master_eq_gains.len();
                                 app_eq_gains.entry(app_name.clone())
-                                    .or_insert([0.0; 10])[band] = gain_db;
+                                    .or_insert_with(|| vec![0.0; band_count])[band] = gain_db;
                             }
 
                             // Linux: Forward to PipeWire backend per-app EQ
@@ -1162,27 +2055,78 @@ impl AudioEngine {
                             }
                         }
 
-                        Command::SetStreamVolume { stream_id, volume } => {
-                            // Extract app name from stream_id
-                            // Format varies by platform:
-                            //   - Linux: "PID:Name" (name is after colon)
-                            //   - macOS: "Name:PID" (name is before colon)
+                        Command::SetAppAutoLevel { app_name, enabled } => {
+                            debug!("Set app '{}' auto-level to {}", app_name, enabled);
+
+                            // Update local state
+                            app_auto_level.insert(app_name.clone(), enabled);
+
+                            // Linux: Forward to PipeWire backend
                             #[cfg(target_os = "linux")]
-                            let app_name = if let Some((_, name)) = stream_id.split_once(':') {
-                                name.to_string()
-                            } else {
-                                stream_id.clone()
-                            };
+                            if let Some(ref backend) = linux_backend {
+                                backend.set_app_auto_level(&app_name, enabled);
+                            }
 
+                            // macOS: TODO - Per-app auto-leveling in the CoreAudio mixer
                             #[cfg(target_os = "macos")]
-                            let app_name = if let Some((name, _)) = stream_id.split_once(':') {
-                                name.to_string()
-                            } else {
-                                stream_id.clone()
-                            };
+                            {
+                                let _ = (&app_name, enabled);
+                            }
+                        }
 
-                            #[cfg(not(any(target_os = "linux", target_os = "macos")))]
-                            let app_name = stream_id.clone();
+                        Command::SetAppDelay { app_name, delay_ms } => {
+                            debug!("Set app '{}' sync offset to {} ms", app_name, delay_ms);
+
+                            // Update local state
+                            app_delays.insert(app_name.clone(), delay_ms);
+
+                            // Linux: Forward to PipeWire backend
+                            #[cfg(target_os = "linux")]
+                            if let Some(ref backend) = linux_backend {
+                                backend.set_app_delay(&app_name, delay_ms);
+                            }
+
+                            // macOS: TODO - Per-app delay lines in the CoreAudio mixer
+                            #[cfg(target_os = "macos")]
+                            {
+                                let _ = (&app_name, delay_ms);
+                            }
+                        }
+
+                        Command::SetProcessorChain { app_name, chain } => {
+                            debug!("Set processor chain for {:?}: {:?}", app_name, chain);
+
+                            // Update local state
+                            match app_name {
+                                Some(ref app_name) => {
+                                    app_chains.insert(app_name.clone(), chain.clone());
+                                }
+                                None => master_chain = chain.clone(),
+                            }
+
+                            // Linux: Forward to PipeWire backend
+                            #[cfg(target_os = "linux")]
+                            if let Some(ref backend) = linux_backend {
+                                if let Err(e) = backend.set_processor_chain(app_name.as_deref(), chain) {
+                                    warn!("Failed to set processor chain: {}", e);
+                                }
+                            }
+
+                            // macOS: Rebuild the output stream's master chain
+                            // (per-app chains are rejected by set_processor_chain)
+                            #[cfg(target_os = "macos")]
+                            if app_name.is_none() {
+                                if let Some(ref mut output) = macos_output {
+                                    if let Err(e) = output.set_master_chain(chain) {
+                                        warn!("Failed to set master processor chain: {}", e);
+                                    }
+                                }
+                            }
+                        }
+
+                        Command::SetStreamVolume { stream_id, volume } => {
+                            // Extract app name from stream_id
+                            let app_name = app_name_from_stream_id(&stream_id).to_string();
 
                             debug!("Set app '{}' volume to {:.2} (stream_id: {})", app_name, volume, stream_id);
 
@@ -1359,6 +2303,44 @@ impl AudioEngine {
                             tracing::debug!("Sending SpectrumUpdate event, bins[0-2]: {:?}", &bins[0..3.min(bins.len())]);
                             let _ = event_sender.try_send(Event::SpectrumUpdate { bins });
                         }
+
+                        // Loudness of the master output and each captured app
+                        if last_loudness_update.elapsed() >= loudness_interval {
+                            last_loudness_update = std::time::Instant::now();
+                            let stats = backend.get_loudness();
+                            if is_audible(&stats) {
+                                let _ = event_sender.try_send(Event::LoudnessUpdate(stats));
+                            }
+                            for (app_name, stats) in backend.get_stream_loudness() {
+                                if is_audible(&stats) {
+                                    let _ = event_sender.try_send(Event::AppLoudnessUpdate { app_name, stats });
+                                }
+                            }
+
+                            // Compressor gain reduction, alongside loudness
+                            if let Some(reduction) = backend.get_gain_reduction() {
+                                let _ = event_sender.try_send(Event::GainReduction {
+                                    app_name: None,
+                                    db: reduction.db,
+                                    bands: reduction.bands,
+                                });
+                            }
+                            for (app_name, reduction) in backend.get_stream_gain_reduction() {
+                                let _ = event_sender.try_send(Event::GainReduction {
+                                    app_name: Some(app_name),
+                                    db: reduction.db,
+                                    bands: reduction.bands,
+                                });
+                            }
+                        }
+
+                        // Drift compensation between each app's capture and the output
+                        if last_drift_update.elapsed() >= drift_interval {
+                            last_drift_update = std::time::Instant::now();
+                            for (app_name, stats) in backend.get_stream_drift() {
+                                let _ = event_sender.try_send(Event::AppClockDrift { app_name, stats });
+                            }
+                        }
                     }
 
                     // macOS: Get peaks and spectrum from processing state
@@ -1377,6 +2359,23 @@ impl AudioEngine {
                             let bins = spectrum.to_vec();
                             let _ = event_sender.try_send(Event::SpectrumUpdate { bins });
                         }
+
+                        // Loudness of the master output
+                        // TODO: per-app loudness on macOS
+                        if last_loudness_update.elapsed() >= loudness_interval {
+                            last_loudness_update = std::time::Instant::now();
+                            let stats = state.master_loudness();
+                            if is_audible(&stats) {
+                                let _ = event_sender.try_send(Event::LoudnessUpdate(stats));
+                            }
+                            if let Some(reduction) = state.master_gain_reduction() {
+                                let _ = event_sender.try_send(Event::GainReduction {
+                                    app_name: None,
+                                    db: reduction.db,
+                                    bands: reduction.bands,
+                                });
+                            }
+                        }
                     }
 
                     // macOS: Periodic scanning for new audio-active processes
@@ -1517,6 +2516,14 @@ impl AudioEngine {
                                                 match backend.switch_playback_target(&current_default) {
                                                     Ok(()) => {
                                                         info!("Successfully switched output to '{}'", current_default);
+                                                        // Follow the new device's crossfeed setting
+                                                        let _ = event_sender.send(Event::OutputDeviceChanged { device_name: current_default.clone() });
+                                                        let crossfeed = crossfeed_for_device(&crossfeed_devices, Some(&current_default));
+                                                        if let Err(e) = backend.set_crossfeed(crossfeed) {
+                                                            warn!("Failed to set crossfeed: {}", e);
+                                                        }
+                                                        output_device_name = Some(current_default.clone());
+
                                                         // Update the tracked output sink ID
                                                         if let Ok(Some(id)) = backend.get_node_id_by_name(&current_default) {
                                                             current_output_sink_id = Some(id);
@@ -1572,7 +2579,7 @@ impl AudioEngine {
         #[cfg(target_os = "macos")]
         {
             // Drop output stream first (stops cpal playback)
-            drop(_macos_output);
+            drop(macos_output);
             // Drop mixer and state
             drop(macos_mixer);
             drop(macos_state);
@@ -1777,6 +2784,42 @@ mod tests {
         // The command send itself always succeeds
     }
 
+    #[test]
+    fn test_set_eq_band_count() {
+        let engine = AudioEngine::new().unwrap();
+        assert!(engine.set_eq_band_count(31).is_ok());
+        // Band 30 is valid once the 31-band layout is active
+        assert!(engine.set_band_gain(30, 2.0).is_ok());
+        assert!(engine.set_stream_band_gain("Firefox:1234".to_string(), 30, 3.0).is_ok());
+        assert!(matches!(engine.set_eq_band_count(0), Err(EngineError::DspError(_))));
+        assert!(matches!(engine.set_eq_band_count(32), Err(EngineError::DspError(_))));
+    }
+
+    #[test]
+    fn test_set_band_params() {
+        let engine = AudioEngine::new().unwrap();
+        let params = BandParams {
+            frequency: 2500.0,
+            q: 1.4,
+            band_type: gecko_dsp::BandType::Peaking,
+            slope: gecko_dsp::FilterSlope::Db12,
+            enabled: true,
+            dynamics: Some(gecko_dsp::BandDynamics::default()),
+        };
+        assert!(engine.set_band_params(4, params).is_ok());
+        assert!(engine.set_stream_band_params("Firefox:1234".to_string(), 4, params).is_ok());
+
+        // Invalid shapes are rejected before reaching the audio thread
+        let bad = BandParams { q: -1.0, ..params };
+        assert!(matches!(engine.set_band_params(4, bad), Err(EngineError::DspError(_))));
+        assert!(engine.set_stream_band_params("Firefox:1234".to_string(), 4, bad).is_err());
+
+        // So are out-of-range dynamics
+        let dynamics = gecko_dsp::BandDynamics { ratio: 0.0, ..Default::default() };
+        let bad = BandParams { dynamics: Some(dynamics), ..params };
+        assert!(matches!(engine.set_band_params(4, bad), Err(EngineError::DspError(_))));
+    }
+
     #[test]
     fn test_set_stream_volume() {
         let engine = AudioEngine::new().unwrap();
@@ -1792,6 +2835,119 @@ mod tests {
         assert!(engine.set_app_bypass("Firefox".to_string(), false).is_ok());
     }
 
+    #[test]
+    fn test_set_app_auto_level() {
+        let engine = AudioEngine::new().unwrap();
+        assert!(engine.set_app_auto_level("Firefox".to_string(), true).is_ok());
+        assert!(engine.set_auto_level_settings(AutoLevelSettings::default()).is_ok());
+
+        let invalid = AutoLevelSettings {
+            target_lufs: 0.0,
+            ..AutoLevelSettings::default()
+        };
+        assert!(engine.set_auto_level_settings(invalid).is_err());
+    }
+
+    #[test]
+    fn test_set_app_delay() {
+        let engine = AudioEngine::new().unwrap();
+        assert!(engine.set_app_delay("mpv".to_string(), 120.0).is_ok());
+        assert!(engine.set_app_delay("Firefox".to_string(), -MAX_SYNC_OFFSET_MS).is_ok());
+        assert!(engine.set_app_delay("Firefox".to_string(), 501.0).is_err());
+        assert!(engine.set_app_delay("Firefox".to_string(), f32::NAN).is_err());
+    }
+
+    #[test]
+    fn test_set_processor_chain() {
+        let engine = AudioEngine::new().unwrap();
+        assert!(engine.set_processor_chain(None, ChainConfig::master_default()).is_ok());
+        let app = Some("mpv".to_string());
+        #[cfg(not(target_os = "macos"))]
+        assert!(engine.set_processor_chain(app.clone(), ChainConfig::app_default()).is_ok());
+        // The CoreAudio mixer has no per-app chains
+        #[cfg(target_os = "macos")]
+        assert!(engine.set_processor_chain(app.clone(), ChainConfig::app_default()).is_err());
+        // Crossfeed only belongs in the master chain
+        assert!(engine.set_processor_chain(app, ChainConfig::master_default()).is_err());
+    }
+
+    #[test]
+    fn test_set_crossfeed() {
+        let engine = AudioEngine::new().unwrap();
+        let settings = gecko_dsp::CrossfeedPreset::ChuMoy.settings();
+        assert!(engine.set_crossfeed("Headphones".to_string(), Some(settings)).is_ok());
+        assert!(engine.set_crossfeed("Headphones".to_string(), None).is_ok());
+
+        let invalid = CrossfeedSettings { cutoff_hz: 50.0, ..settings };
+        assert!(engine.set_crossfeed("Headphones".to_string(), Some(invalid)).is_err());
+    }
+
+    #[test]
+    fn test_stereo_eq_commands() {
+        let engine = AudioEngine::new().unwrap();
+        assert!(engine.set_eq_channel_mode(EqChannelMode::MidSide).is_ok());
+        assert!(engine.set_secondary_band_gain(0, -3.0).is_ok());
+        assert!(engine.set_stereo_width(1.4).is_ok());
+        assert!(matches!(engine.set_stereo_width(2.5), Err(EngineError::DspError(_))));
+
+        let mut params = gecko_dsp::band_layout_params(10)[3];
+        assert!(engine.set_secondary_band_params(3, params).is_ok());
+        params.q = 0.0;
+        assert!(engine.set_secondary_band_params(3, params).is_err());
+    }
+
+    #[test]
+    fn test_set_auto_preamp() {
+        let engine = AudioEngine::new().unwrap();
+        assert!(engine.set_auto_preamp(true).is_ok());
+        assert!(engine.set_auto_preamp(false).is_ok());
+        // Nothing is playing yet, so no EQ applies a preamp
+        assert_eq!(engine.eq_preamp_db(None).unwrap(), 0.0);
+        assert_eq!(engine.eq_preamp_db(Some("Firefox".into())).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_output_rate() {
+        let engine = AudioEngine::new().unwrap();
+        // No backend yet: the configured rate
+        assert_eq!(engine.output_rate().unwrap(), engine.config().stream.sample_rate);
+    }
+
+    #[test]
+    fn test_output_latency() {
+        let engine = AudioEngine::new().unwrap();
+        assert!(engine.set_output_stage(OutputStage::Limiter).is_ok());
+        // No backend yet: nothing adds latency
+        assert_eq!(engine.output_latency_samples(None).unwrap(), 0);
+        assert_eq!(engine.output_latency_samples(Some("Firefox".into())).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_set_phase_mode() {
+        let engine = AudioEngine::new().unwrap();
+        assert!(engine.set_phase_mode(None, PhaseMode::Linear).is_ok());
+        assert!(engine.set_phase_mode(None, PhaseMode::Minimum).is_ok());
+    }
+
+    #[test]
+    fn test_impulse_response() {
+        let engine = AudioEngine::new().unwrap();
+        // The file is read before anything is sent
+        assert!(matches!(
+            engine.load_impulse_response(None, "/nonexistent/ir.wav"),
+            Err(EngineError::DspError(_))
+        ));
+        assert!(engine.clear_impulse_response(None).is_ok());
+    }
+
+    #[test]
+    fn test_set_eq_preamp() {
+        let engine = AudioEngine::new().unwrap();
+        assert!(engine.set_eq_preamp(-6.4).is_ok());
+        assert!(matches!(engine.set_eq_preamp(-30.0), Err(EngineError::DspError(_))));
+        assert!(matches!(engine.set_eq_preamp(f32::NAN), Err(EngineError::DspError(_))));
+    }
+
     #[test]
     fn test_per_app_state_persistence_in_memory() {
         let engine = AudioEngine::new().unwrap();
@@ -40,6 +40,9 @@ pub use message::{Command, Event};
 pub use settings::{GeckoSettings, UiSettings, UserPreset};
 pub use stream::AudioStream;
 
+// Stream IDs are built by the platform backends; parse them the same way everywhere
+pub use gecko_platform::app_name_from_stream_id;
+
 // Re-export DSP types for convenience
 pub use gecko_dsp::{Equalizer, EqConfig, Band, BandType, EQ_BANDS};
 
@@ -6,7 +6,11 @@
 use serde::{Deserialize, Serialize};
 
 use crate::config::StreamConfig;
-use gecko_dsp::EqConfig;
+use gecko_dsp::{
+    AutoLevelSettings, BandParams, ChainConfig, CrossfeedSettings, DriftStats, EqChannelMode,
+    EqConfig,
+    ImpulseResponse, LimiterSettings, LoudnessStats, OutputStage, PhaseMode,
+};
 
 /// Commands sent from UI thread to Audio engine
 #[derive(Debug, Clone)]
@@ -23,6 +27,49 @@ pub enum Command {
     /// Set gain for a single master EQ band (band_index, gain_db)
     SetBandGain { band: usize, gain_db: f32 },
 
+    /// Set frequency, Q, filter type and enable state for a master EQ band
+    SetBandParams { band: usize, params: BandParams },
+
+    /// Set frequency, Q, filter type and enable state for a per-app EQ band
+    SetStreamBandParams { stream_id: String, band: usize, params: BandParams },
+
+    /// Change the number of EQ bands (1-31) for master and all per-app EQs
+    /// Bands switch to the standard layout for that count and reset to flat
+    SetEqBandCount(usize),
+
+    /// Set how the master EQ treats the front left/right pair
+    /// Linked uses one config; left/right and mid/side add a secondary config
+    SetEqChannelMode(EqChannelMode),
+
+    /// Set gain for a secondary (right or side) master EQ band
+    SetSecondaryBandGain { band: usize, gain_db: f32 },
+
+    /// Set frequency, Q, filter type and enable state for a secondary master EQ band
+    SetSecondaryBandParams { band: usize, params: BandParams },
+
+    /// Set master stereo width (0.0 mono, 1.0 unchanged, up to 2.0)
+    SetStereoWidth(f32),
+
+    /// Enable/disable automatic preamp that cancels the peak EQ boost
+    /// Applies to the master and every per-app EQ
+    SetAutoPreamp(bool),
+
+    /// Set the master EQ preamp (dB, ±24) used while auto preamp is off
+    SetEqPreamp(f32),
+
+    /// Report the preamp (dB) the master EQ, or an app's EQ, currently applies
+    /// The answer goes to `reply` (0 dB while no backend is running)
+    GetEqPreamp { app_name: Option<String>, reply: crossbeam_channel::Sender<f32> },
+
+    /// Report the sample rate (Hz) the output runs at
+    /// The answer goes to `reply` (the configured rate while no backend is running)
+    GetOutputRate { reply: crossbeam_channel::Sender<u32> },
+
+    /// Report the latency (samples at the output rate) the master chain adds,
+    /// plus an app's chain when `app_name` is given
+    /// The answer goes to `reply` (0 while no backend is running)
+    GetOutputLatency { app_name: Option<String>, reply: crossbeam_channel::Sender<usize> },
+
     /// Set per-app EQ band gain (TRUE per-app EQ, NOT additive to master)
     /// Each app has its own independent EQ instance that processes audio BEFORE mixing
     SetStreamBandGain { stream_id: String, band: usize, gain_db: f32 },
@@ -31,6 +78,21 @@ pub enum Command {
     /// When bypassed, the app's audio passes through without EQ processing
     SetAppBypass { app_name: String, bypassed: bool },
 
+    /// Enable or disable auto-leveling for a specific application
+    /// A leveled app's gain drifts slowly toward the shared target LUFS
+    SetAppAutoLevel { app_name: String, enabled: bool },
+
+    /// Set the A/V sync offset (ms) for a specific application
+    /// Positive delays the app; negative delays every other app instead
+    SetAppDelay { app_name: String, delay_ms: f32 },
+
+    /// Replace the master (`app_name: None`) or an app's processor chain
+    /// The audio thread crossfades to the rebuilt chain
+    SetProcessorChain { app_name: Option<String>, chain: ChainConfig },
+
+    /// Set the auto-leveling target, max boost/cut and speed shared by all apps
+    SetAutoLevelSettings(AutoLevelSettings),
+
     /// Start capturing audio from a specific application (macOS only)
     /// Uses Process Tap API to capture the app's audio stream
     StartAppCapture { pid: u32, app_name: String },
@@ -51,6 +113,28 @@ pub enum Command {
     /// Enable/disable soft clipping (limiter to prevent harsh distortion)
     SetSoftClipEnabled(bool),
 
+    /// Select the final output stage (soft clipper or lookahead limiter)
+    SetOutputStage(OutputStage),
+
+    /// Restart integrated loudness, range and true peak
+    /// `None` resets the master and every app
+    ResetLoudness { app_name: Option<String> },
+
+    /// Set the lookahead limiter's ceiling (dBTP) and release
+    SetLimiterSettings(LimiterSettings),
+
+    /// Run the master EQ (`app_name: None`) or an app's EQ minimum or linear phase
+    /// Linear phase adds latency (see `GetOutputLatency`)
+    SetPhaseMode { app_name: Option<String>, mode: PhaseMode },
+
+    /// Play an impulse response through the master's (`app_name: None`) or an
+    /// app's Convolver stage (`None` clears it; the stage is then skipped)
+    SetImpulseResponse { app_name: Option<String>, ir: Option<ImpulseResponse> },
+
+    /// Set headphone crossfeed for an output device (`None` turns it off)
+    /// Applied whenever that device is the output, so speakers stay untouched
+    SetCrossfeed { device_name: String, settings: Option<CrossfeedSettings> },
+
     /// Change input device
     SetInputDevice(String),
 
@@ -84,6 +168,37 @@ pub enum Event {
     /// Contains peak levels: (left, right) in range 0.0 - 1.0
     LevelUpdate { left: f32, right: f32 },
 
+    /// Loudness update for the master output (EBU R128)
+    /// Momentary, short-term and integrated LUFS, loudness range and true peak.
+    /// Sent every 100ms while audio is playing.
+    LoudnessUpdate(LoudnessStats),
+
+    /// Loudness update for one app, measured after its EQ and volume
+    /// Sent every 100ms per captured app while it is playing (per-app mode).
+    AppLoudnessUpdate {
+        app_name: String,
+        stats: LoudnessStats,
+    },
+
+    /// Compressor gain reduction of the master chain (`app_name: None`) or an app's
+    /// Largest reduction (dB, positive) since the previous update, and each
+    /// multiband band's (lowest first, empty without one). Sent every 100ms
+    /// for each running chain that has a compressor.
+    GainReduction {
+        app_name: Option<String>,
+        db: f32,
+        #[serde(default)]
+        bands: Vec<f32>,
+    },
+
+    /// Clock drift compensation for one app against the output device
+    /// Resampling ratio and ring buffer fill vs target. Sent every second per
+    /// captured app (per-app mode).
+    AppClockDrift {
+        app_name: String,
+        stats: DriftStats,
+    },
+
     /// Current state snapshot
     StateUpdate {
         is_running: bool,
@@ -96,6 +211,10 @@ pub enum Event {
     /// Device list changed (hot-plug)
     DevicesChanged,
 
+    /// Gecko started playing to an output device (at start or after a switch)
+    /// Per-device options such as crossfeed follow this device.
+    OutputDeviceChanged { device_name: String },
+
     /// Buffer underrun detected (audio glitch)
     BufferUnderrun,
 
@@ -191,4 +310,73 @@ mod tests {
             panic!("Wrong variant");
         }
     }
+
+    #[test]
+    fn test_loudness_event_serialization() {
+        let stats = LoudnessStats {
+            momentary_lufs: -18.5,
+            integrated_lufs: -23.0,
+            ..LoudnessStats::default()
+        };
+        let event = Event::AppLoudnessUpdate {
+            app_name: "Firefox".to_string(),
+            stats,
+        };
+
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains("integrated_lufs"));
+        let deserialized: Event = serde_json::from_str(&json).unwrap();
+        if let Event::AppLoudnessUpdate { app_name, stats: received } = deserialized {
+            assert_eq!(app_name, "Firefox");
+            assert_eq!(received, stats);
+        } else {
+            panic!("Wrong variant");
+        }
+
+        // Silence reports a finite floor, which survives JSON (unlike -inf)
+        let json = serde_json::to_string(&Event::LoudnessUpdate(LoudnessStats::default())).unwrap();
+        assert!(!json.contains("null"));
+    }
+
+    #[test]
+    fn test_gain_reduction_event_serialization() {
+        let event = Event::GainReduction {
+            app_name: Some("mpv".to_string()),
+            db: 4.5,
+            bands: vec![4.5, 1.0, 0.0],
+        };
+
+        let json = serde_json::to_string(&event).unwrap();
+        let deserialized: Event = serde_json::from_str(&json).unwrap();
+        if let Event::GainReduction { app_name, db, bands } = deserialized {
+            assert_eq!(app_name.as_deref(), Some("mpv"));
+            assert_eq!(db, 4.5);
+            assert_eq!(bands, [4.5, 1.0, 0.0]);
+        } else {
+            panic!("Wrong variant");
+        }
+    }
+
+    #[test]
+    fn test_clock_drift_event_serialization() {
+        let stats = DriftStats {
+            ratio: 0.9997,
+            buffer_ms: 42.5,
+            target_ms: 42.7,
+        };
+        let event = Event::AppClockDrift {
+            app_name: "Firefox".to_string(),
+            stats,
+        };
+
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains("ratio"));
+        let deserialized: Event = serde_json::from_str(&json).unwrap();
+        if let Event::AppClockDrift { app_name, stats: received } = deserialized {
+            assert_eq!(app_name, "Firefox");
+            assert_eq!(received, stats);
+        } else {
+            panic!("Wrong variant");
+        }
+    }
 }
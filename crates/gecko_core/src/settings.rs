@@ -12,15 +12,87 @@ use std::path::PathBuf;
 
 use chrono::{DateTime, Utc};
 use directories::ProjectDirs;
+use gecko_dsp::{
+    AutoLevelSettings, BandParams, ChainConfig, CrossfeedSettings, EqChannelMode, EqConfig,
+    LimiterSettings, OutputStage, PhaseMode,
+};
 use serde::{Deserialize, Serialize};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 /// User-defined EQ preset
+///
+/// `gains` holds one value per band at the time the preset was saved, so older
+/// 10-band presets still load after the band count changes.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserPreset {
     pub name: String,
-    pub gains: [f32; 10],
+    pub gains: Vec<f32>,
     pub created_at: DateTime<Utc>,
+    /// Band shapes; empty means the standard layout for `gains.len()` bands
+    #[serde(default)]
+    pub band_params: Vec<BandParams>,
+    /// Preamp applied ahead of the bands (e.g. from an AutoEQ import)
+    #[serde(default)]
+    pub preamp_db: f32,
+}
+
+impl UserPreset {
+    /// Capture an EQ config as a preset
+    pub fn from_eq_config(name: &str, config: &EqConfig) -> Self {
+        Self {
+            name: name.to_string(),
+            gains: config.get_gains(),
+            created_at: Utc::now(),
+            band_params: config.bands.iter().map(|band| band.params()).collect(),
+            preamp_db: config.master_gain_db,
+        }
+    }
+
+    /// Band shapes to restore, or `None` when the preset uses the standard layout
+    ///
+    /// Presets saved with the standard shapes spelled out are treated as
+    /// having none, so their gains can still be interpolated onto another
+    /// band count.
+    pub fn custom_band_params(&self) -> Option<&[BandParams]> {
+        let count = self.band_params.len();
+        let standard = gecko_dsp::band_layout_params(count);
+        let is_standard = standard
+            .get(..count)
+            .is_some_and(|standard| self.band_params[..] == *standard);
+        (count > 0 && !is_standard).then_some(&self.band_params[..])
+    }
+
+    /// Rebuild the EQ config this preset describes
+    pub fn to_eq_config(&self) -> Result<EqConfig, gecko_dsp::DspError> {
+        let mut config = EqConfig::with_band_count(self.gains.len())?;
+        for (band, &gain_db) in self.gains.iter().enumerate() {
+            config.set_band_gain(band, gain_db)?;
+        }
+        if self.band_params.len() == self.gains.len() {
+            for (band, &params) in self.band_params.iter().enumerate() {
+                config.set_band_params(band, params)?;
+            }
+        }
+        config.master_gain_db = self.preamp_db;
+        Ok(config)
+    }
+
+    /// Import an EqualizerAPO / AutoEQ config onto `band_count` bands
+    ///
+    /// Returns the preset and any warnings about skipped lines.
+    pub fn from_apo(
+        name: &str,
+        text: &str,
+        band_count: usize,
+    ) -> Result<(Self, Vec<String>), gecko_dsp::DspError> {
+        let import = EqConfig::from_apo(text, band_count)?;
+        Ok((Self::from_eq_config(name, &import.config), import.warnings))
+    }
+
+    /// Export as an EqualizerAPO parametric config
+    pub fn to_apo(&self) -> Result<String, gecko_dsp::DspError> {
+        Ok(self.to_eq_config()?.to_apo())
+    }
 }
 
 /// UI-specific settings
@@ -33,6 +105,12 @@ pub struct UiSettings {
     /// Enable soft clipping (limiter) to prevent harsh digital distortion
     #[serde(default = "default_soft_clip")]
     pub soft_clip_enabled: bool,
+    /// Final output stage: soft clipper or lookahead true-peak limiter
+    #[serde(default)]
+    pub output_stage: OutputStage,
+    /// Lookahead limiter ceiling and release
+    #[serde(default)]
+    pub limiter: LimiterSettings,
 }
 
 fn default_soft_clip() -> bool {
@@ -47,6 +125,8 @@ impl Default for UiSettings {
             start_minimized: false,
             eq_bands_ui: 10,
             soft_clip_enabled: true,
+            output_stage: OutputStage::SoftClip,
+            limiter: LimiterSettings::default(),
         }
     }
 }
@@ -55,10 +135,37 @@ impl Default for UiSettings {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GeckoSettings {
     pub master_volume: f32,
-    pub master_eq: [f32; 10],
+    /// Master EQ gains, one per band. The length defines the band count
+    /// shared by the master and per-app EQs (3, 10, 31 or custom).
+    pub master_eq: Vec<f32>,
     /// Per-app EQ settings (keyed by app name for stability across sessions)
     #[serde(default)]
     pub app_eq: std::collections::HashMap<String, Vec<f32>>,
+    /// Master EQ band shapes (frequency, Q, type, enabled), one per band.
+    /// Empty means the standard layout for the band count.
+    #[serde(default)]
+    pub master_band_params: Vec<BandParams>,
+    /// Per-app EQ band shapes (keyed by app name, same rules as `master_band_params`)
+    #[serde(default)]
+    pub app_band_params: std::collections::HashMap<String, Vec<BandParams>>,
+    /// How the master EQ treats the front left/right pair
+    #[serde(default)]
+    pub eq_channel_mode: EqChannelMode,
+    /// Secondary (right or side) master EQ gains. Empty or a stale length means flat.
+    #[serde(default)]
+    pub secondary_eq: Vec<f32>,
+    /// Secondary master EQ band shapes (same rules as `master_band_params`)
+    #[serde(default)]
+    pub secondary_band_params: Vec<BandParams>,
+    /// Master stereo width (0.0 mono, 1.0 unchanged, up to 2.0)
+    #[serde(default = "default_stereo_width")]
+    pub stereo_width: f32,
+    /// Lower every EQ by its largest boost so boosted bands don't clip
+    #[serde(default)]
+    pub auto_preamp: bool,
+    /// Master EQ preamp (dB) used while `auto_preamp` is off
+    #[serde(default)]
+    pub master_preamp_db: f32,
     /// Global bypass state (bypasses ALL processing)
     pub bypassed: bool,
     /// Set of apps that have per-app bypass enabled (EQ bypassed for these apps only)
@@ -70,21 +177,74 @@ pub struct GeckoSettings {
     /// Per-app volume settings (keyed by app name, 0.0-2.0, default 1.0)
     #[serde(default)]
     pub app_volumes: std::collections::HashMap<String, f32>,
+    /// Per-app A/V sync offsets (keyed by app name, ±500 ms, default 0)
+    /// Negative offsets delay every other app instead
+    #[serde(default)]
+    pub app_delays: std::collections::HashMap<String, f32>,
+    /// Set of apps whose gain is leveled toward `auto_level.target_lufs`
+    #[serde(default)]
+    pub auto_level_apps: std::collections::HashSet<String>,
+    /// Auto-leveling target, max boost/cut and speed shared by all leveled apps
+    #[serde(default)]
+    pub auto_level: AutoLevelSettings,
+    /// Headphone crossfeed per output device name (absent = off, e.g. speakers)
+    #[serde(default)]
+    pub crossfeed_devices: std::collections::HashMap<String, CrossfeedSettings>,
+    /// Master processor chain (ordered stages, each optionally bypassed)
+    #[serde(default = "ChainConfig::master_default")]
+    pub master_chain: ChainConfig,
+    /// Per-app processor chains (keyed by app name, absent = the app default)
+    #[serde(default)]
+    pub app_chains: std::collections::HashMap<String, ChainConfig>,
+    /// Master EQ phase mode (linear phase adds latency)
+    #[serde(default)]
+    pub master_phase_mode: PhaseMode,
+    /// Per-app EQ phase modes (absent = minimum phase)
+    #[serde(default)]
+    pub app_phase_modes: std::collections::HashMap<String, PhaseMode>,
+    /// WAV impulse response for the master Convolver stage (absent = none)
+    #[serde(default)]
+    pub master_ir_path: Option<PathBuf>,
+    /// Per-app WAV impulse responses for their Convolver stages
+    #[serde(default)]
+    pub app_ir_paths: std::collections::HashMap<String, PathBuf>,
     pub active_preset: Option<String>,
     pub user_presets: Vec<UserPreset>,
     pub ui_settings: UiSettings,
 }
 
+fn default_stereo_width() -> f32 {
+    1.0
+}
+
 impl Default for GeckoSettings {
     fn default() -> Self {
         Self {
             master_volume: 1.0,
-            master_eq: [0.0; 10],
+            master_eq: vec![0.0; gecko_dsp::EQ_BANDS.len()],
             app_eq: std::collections::HashMap::new(),
+            master_band_params: Vec::new(),
+            app_band_params: std::collections::HashMap::new(),
+            eq_channel_mode: EqChannelMode::Linked,
+            secondary_eq: Vec::new(),
+            secondary_band_params: Vec::new(),
+            stereo_width: 1.0,
+            auto_preamp: false,
+            master_preamp_db: 0.0,
             bypassed: false,
             bypassed_apps: std::collections::HashSet::new(),
             hidden_apps: std::collections::HashSet::new(),
             app_volumes: std::collections::HashMap::new(),
+            app_delays: std::collections::HashMap::new(),
+            auto_level_apps: std::collections::HashSet::new(),
+            auto_level: AutoLevelSettings::default(),
+            crossfeed_devices: std::collections::HashMap::new(),
+            master_chain: ChainConfig::master_default(),
+            app_chains: std::collections::HashMap::new(),
+            master_phase_mode: PhaseMode::Minimum,
+            app_phase_modes: std::collections::HashMap::new(),
+            master_ir_path: None,
+            app_ir_paths: std::collections::HashMap::new(),
             active_preset: Some("Flat".to_string()),
             user_presets: Vec::new(),
             ui_settings: UiSettings::default(),
@@ -101,9 +261,12 @@ impl GeckoSettings {
             if path.exists() {
                 match fs::File::open(&path) {
                     Ok(file) => {
-                        match serde_json::from_reader(file) {
-                            Ok(settings) => {
+                        match serde_json::from_reader::<_, Self>(file) {
+                            Ok(mut settings) => {
                                 info!("Settings loaded from {:?}", path);
+                                if settings.repair_band_counts() {
+                                    warn!("Repaired EQ band counts in {:?}", path);
+                                }
                                 return settings;
                             }
                             Err(e) => {
@@ -138,6 +301,181 @@ impl GeckoSettings {
         Ok(())
     }
 
+    /// Number of EQ bands (shared by master and per-app EQs)
+    pub fn band_count(&self) -> usize {
+        self.master_eq.len()
+    }
+
+    /// Master band shapes, falling back to the standard layout
+    pub fn band_params(&self) -> Vec<BandParams> {
+        Self::resolve_band_params(&self.master_band_params, self.band_count())
+    }
+
+    /// Band shapes for an app, falling back to the standard layout
+    pub fn app_band_params(&self, app_name: &str) -> Vec<BandParams> {
+        let stored = self.app_band_params.get(app_name).map_or(&[][..], |p| p.as_slice());
+        Self::resolve_band_params(stored, self.band_count())
+    }
+
+    /// Secondary master gains, flat if missing or stored for another band count
+    pub fn secondary_gains(&self) -> Vec<f32> {
+        if self.secondary_eq.len() == self.band_count() {
+            self.secondary_eq.clone()
+        } else {
+            vec![0.0; self.band_count()]
+        }
+    }
+
+    /// Secondary master band shapes, falling back to the standard layout
+    pub fn secondary_band_params(&self) -> Vec<BandParams> {
+        Self::resolve_band_params(&self.secondary_band_params, self.band_count())
+    }
+
+    /// Master EQ as a config (stored gains, shapes and preamp; disabled while bypassed)
+    ///
+    /// `auto_preamp` is copied in; call `EqConfig::apply_auto_preamp` with
+    /// the stream's sample rate to get the master gain that is heard.
+    pub fn master_eq_config(&self) -> Result<EqConfig, gecko_dsp::DspError> {
+        let shapes = self.band_params();
+        let mut config = self.build_eq_config(&self.master_eq, &shapes, !self.bypassed)?;
+        config.master_gain_db = self.master_preamp_db;
+        Ok(config)
+    }
+
+    /// Secondary (right or side) master EQ as a config, same rules as `master_eq_config`
+    pub fn secondary_eq_config(&self) -> Result<EqConfig, gecko_dsp::DspError> {
+        let gains = self.secondary_gains();
+        let shapes = self.secondary_band_params();
+        let mut config = self.build_eq_config(&gains, &shapes, !self.bypassed)?;
+        config.master_gain_db = self.master_preamp_db;
+        Ok(config)
+    }
+
+    /// EQ config for an app (flat if never adjusted; disabled while bypassed)
+    pub fn app_eq_config(&self, app_name: &str) -> Result<EqConfig, gecko_dsp::DspError> {
+        let flat = vec![0.0; self.band_count()];
+        let gains = self.app_eq.get(app_name).unwrap_or(&flat);
+        let enabled = !self.bypassed_apps.contains(app_name);
+        self.build_eq_config(gains, &self.app_band_params(app_name), enabled)
+    }
+
+    fn build_eq_config(&self, gains: &[f32], shapes: &[BandParams], enabled: bool) -> Result<EqConfig, gecko_dsp::DspError> {
+        let mut config = EqConfig::with_band_count(gains.len())?;
+        for (band, (&gain_db, &params)) in gains.iter().zip(shapes).enumerate() {
+            config.set_band_gain(band, gain_db)?;
+            config.set_band_params(band, params)?;
+        }
+        config.enabled = enabled;
+        config.auto_preamp = self.auto_preamp;
+        Ok(config)
+    }
+
+    /// Set the shape of one master band
+    pub fn set_band_params(&mut self, band: usize, params: BandParams) -> Result<(), gecko_dsp::DspError> {
+        Self::check_band_params(band, self.band_count(), &params)?;
+        self.master_band_params = self.band_params();
+        self.master_band_params[band] = params;
+        Ok(())
+    }
+
+    /// Set the shape of one secondary master band
+    pub fn set_secondary_band_params(&mut self, band: usize, params: BandParams) -> Result<(), gecko_dsp::DspError> {
+        Self::check_band_params(band, self.band_count(), &params)?;
+        self.secondary_band_params = self.secondary_band_params();
+        self.secondary_band_params[band] = params;
+        Ok(())
+    }
+
+    /// Set the shape of one band for an app
+    pub fn set_app_band_params(
+        &mut self,
+        app_name: &str,
+        band: usize,
+        params: BandParams,
+    ) -> Result<(), gecko_dsp::DspError> {
+        Self::check_band_params(band, self.band_count(), &params)?;
+        let mut shapes = self.app_band_params(app_name);
+        shapes[band] = params;
+        self.app_band_params.insert(app_name.to_string(), shapes);
+        Ok(())
+    }
+
+    fn check_band_params(band: usize, band_count: usize, params: &BandParams) -> Result<(), gecko_dsp::DspError> {
+        if band >= band_count {
+            return Err(gecko_dsp::DspError::InvalidBandIndex(band));
+        }
+        params.validate()
+    }
+
+    /// Stored shapes if they match the band count, otherwise the standard layout
+    fn resolve_band_params(stored: &[BandParams], band_count: usize) -> Vec<BandParams> {
+        if stored.len() == band_count {
+            stored.to_vec()
+        } else {
+            (0..band_count)
+                .map(|i| gecko_dsp::band_layout(band_count, i).params())
+                .collect()
+        }
+    }
+
+    /// Change the EQ band count, remapping master and per-app curves
+    ///
+    /// Existing gains are interpolated onto the standard layout for
+    /// `band_count` so a tuned curve survives the switch as closely as possible.
+    /// Custom band shapes don't carry over and revert to the standard layout.
+    /// The engine remaps its own curves the same way.
+    pub fn set_band_count(&mut self, band_count: usize) -> Result<(), gecko_dsp::DspError> {
+        let old_count = self.band_count();
+        self.master_eq = remap_gains(&self.master_eq, old_count, band_count)?;
+        self.secondary_eq = remap_gains(&self.secondary_eq, old_count, band_count)?;
+        for gains in self.app_eq.values_mut() {
+            *gains = remap_gains(gains, old_count, band_count)?;
+        }
+        self.master_band_params.clear();
+        self.secondary_band_params.clear();
+        self.app_band_params.clear();
+        Ok(())
+    }
+
+    /// Bring a hand-edited or older file back to a band layout the EQ accepts
+    ///
+    /// A master curve with no bands gets the default layout and one with more
+    /// than `MAX_BANDS` is remapped onto `MAX_BANDS`. Per-app curves of another
+    /// length are remapped onto the master's count. Presets keep their own
+    /// count unless it is out of range. Returns whether anything changed.
+    pub fn repair_band_counts(&mut self) -> bool {
+        let mut repaired = false;
+        if self.master_eq.is_empty() {
+            self.master_eq = vec![0.0; gecko_dsp::EQ_BANDS.len()];
+            repaired = true;
+        }
+        let band_count = self.band_count().min(gecko_dsp::MAX_BANDS);
+        // Rust pattern: the closure borrows `repaired` mutably until its last use
+        let mut repair = |gains: &mut Vec<f32>, target: usize| {
+            if gains.len() != target {
+                if let Ok(remapped) = remap_gains(gains, gains.len(), target) {
+                    *gains = remapped;
+                    repaired = true;
+                }
+            }
+        };
+
+        repair(&mut self.master_eq, band_count);
+        for gains in self.app_eq.values_mut() {
+            repair(gains, band_count);
+        }
+        for preset in &mut self.user_presets {
+            if preset.gains.len() > gecko_dsp::MAX_BANDS {
+                repair(&mut preset.gains, band_count);
+                preset.band_params.clear();
+            }
+        }
+        let presets = self.user_presets.len();
+        self.user_presets.retain(|preset| !preset.gains.is_empty());
+        repaired |= self.user_presets.len() != presets;
+        repaired
+    }
+
     /// Get the platform-specific configuration file path
     fn get_config_path() -> Option<PathBuf> {
         ProjectDirs::from("com", "gecko", "gecko")
@@ -145,6 +483,25 @@ impl GeckoSettings {
     }
 }
 
+/// Interpolate a curve laid out for `from_count` bands onto `band_count` bands
+///
+/// Both counts use the standard layouts. A curve of any other length is
+/// stale and comes back flat.
+pub(crate) fn remap_gains(
+    gains: &[f32],
+    from_count: usize,
+    band_count: usize,
+) -> Result<Vec<f32>, gecko_dsp::DspError> {
+    let mut config = EqConfig::with_band_count(band_count)?;
+    if gains.len() == from_count {
+        let frequencies: Vec<f32> = (0..from_count)
+            .map(|i| gecko_dsp::band_layout_frequency(from_count, i))
+            .collect();
+        config.set_gains_interpolated(&frequencies, gains);
+    }
+    Ok(config.get_gains())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -172,8 +529,31 @@ mod tests {
         // Add per-app settings
         settings.app_eq.insert("Firefox".to_string(), vec![1.0, 2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, -1.0]);
         settings.app_volumes.insert("Firefox".to_string(), 1.5);
+        settings.app_delays.insert("mpv".to_string(), -120.0);
+        settings
+            .master_chain
+            .insert(1, gecko_dsp::ProcessorKind::Compressor(Default::default()))
+            .unwrap();
+        settings.master_chain.set_bypassed(0, true).unwrap();
+        let mut app_chain = ChainConfig::app_default();
+        app_chain.move_processor(2, 0).unwrap();
+        settings.app_chains.insert("mpv".to_string(), app_chain.clone());
+        settings.master_phase_mode = PhaseMode::Linear;
+        settings.app_phase_modes.insert("mpv".to_string(), PhaseMode::Linear);
+        settings.master_ir_path = Some(PathBuf::from("/home/user/headphones.wav"));
+        settings.app_ir_paths.insert("mpv".to_string(), PathBuf::from("/home/user/room.wav"));
         settings.bypassed_apps.insert("Spotify".to_string());
         settings.hidden_apps.insert("systemsounds".to_string());
+        settings.auto_level_apps.insert("Firefox".to_string());
+        settings.auto_level.target_lufs = -23.0;
+        settings
+            .crossfeed_devices
+            .insert("Headphones".to_string(), CrossfeedSettings::default());
+        settings.eq_channel_mode = EqChannelMode::MidSide;
+        settings.secondary_eq = vec![0.0; 10];
+        settings.secondary_eq[4] = -6.0;
+        settings.stereo_width = 1.25;
+        settings.auto_preamp = true;
 
         // Serialize to JSON
         let json = serde_json::to_string_pretty(&settings).unwrap();
@@ -187,8 +567,26 @@ mod tests {
         assert!(deserialized.bypassed);
         assert_eq!(deserialized.app_eq.get("Firefox").unwrap()[0], 1.0);
         assert_eq!(deserialized.app_volumes.get("Firefox").unwrap(), &1.5);
+        assert_eq!(deserialized.app_delays.get("mpv").unwrap(), &-120.0);
+        assert_eq!(deserialized.master_chain, settings.master_chain);
+        assert!(deserialized.master_chain.processors[0].bypassed);
+        assert_eq!(deserialized.master_phase_mode, PhaseMode::Linear);
+        assert_eq!(deserialized.app_phase_modes.get("mpv"), Some(&PhaseMode::Linear));
+        assert_eq!(deserialized.master_ir_path, settings.master_ir_path);
+        assert_eq!(deserialized.app_ir_paths, settings.app_ir_paths);
+        assert_eq!(deserialized.app_chains.get("mpv"), Some(&app_chain));
         assert!(deserialized.bypassed_apps.contains("Spotify"));
         assert!(deserialized.hidden_apps.contains("systemsounds"));
+        assert!(deserialized.auto_level_apps.contains("Firefox"));
+        assert_eq!(deserialized.auto_level.target_lufs, -23.0);
+        assert_eq!(
+            deserialized.crossfeed_devices.get("Headphones"),
+            Some(&CrossfeedSettings::default())
+        );
+        assert_eq!(deserialized.eq_channel_mode, EqChannelMode::MidSide);
+        assert_eq!(deserialized.secondary_gains()[4], -6.0);
+        assert_eq!(deserialized.stereo_width, 1.25);
+        assert!(deserialized.auto_preamp);
     }
 
     #[test]
@@ -213,16 +611,32 @@ mod tests {
         // New fields should default properly
         assert!(settings.app_eq.is_empty());
         assert!(settings.app_volumes.is_empty());
+        assert!(settings.app_delays.is_empty());
+        assert_eq!(settings.master_chain, ChainConfig::master_default());
+        assert!(settings.app_chains.is_empty());
+        assert_eq!(settings.master_phase_mode, PhaseMode::Minimum);
+        assert!(settings.app_phase_modes.is_empty());
+        assert_eq!(settings.master_ir_path, None);
+        assert!(settings.app_ir_paths.is_empty());
         assert!(settings.bypassed_apps.is_empty());
         assert!(settings.hidden_apps.is_empty());
+        assert!(settings.auto_level_apps.is_empty());
+        assert_eq!(settings.auto_level, AutoLevelSettings::default());
+        assert!(settings.crossfeed_devices.is_empty());
+        assert_eq!(settings.eq_channel_mode, EqChannelMode::Linked);
+        assert_eq!(settings.secondary_gains(), vec![0.0; 10]);
+        assert_eq!(settings.stereo_width, 1.0);
+        assert!(!settings.auto_preamp);
     }
 
     #[test]
     fn test_user_preset_serialization() {
         let preset = UserPreset {
             name: "Bass Boost".to_string(),
-            gains: [6.0, 4.0, 2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            gains: vec![6.0, 4.0, 2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
             created_at: Utc::now(),
+            band_params: Vec::new(),
+            preamp_db: 0.0,
         };
 
         let json = serde_json::to_string(&preset).unwrap();
@@ -232,6 +646,91 @@ mod tests {
         assert_eq!(deserialized.gains[0], 6.0);
     }
 
+    #[test]
+    fn test_eq_configs_from_settings() {
+        let mut settings = GeckoSettings::default();
+        settings.master_eq[2] = 3.0;
+        settings.app_eq.insert("Firefox".to_string(), vec![-2.0; 10]);
+        settings.bypassed_apps.insert("Firefox".to_string());
+
+        let master = settings.master_eq_config().unwrap();
+        assert_eq!(master.bands[2].gain_db, 3.0);
+        assert!(master.enabled);
+
+        let app = settings.app_eq_config("Firefox").unwrap();
+        assert_eq!(app.bands[0].gain_db, -2.0);
+        assert!(!app.enabled);
+        assert_eq!(settings.app_eq_config("Spotify").unwrap().get_gains(), vec![0.0; 10]);
+
+        settings.master_preamp_db = -6.4;
+        assert_eq!(settings.master_eq_config().unwrap().master_gain_db, -6.4);
+        let secondary = settings.secondary_eq_config().unwrap();
+        assert_eq!(secondary.get_gains(), vec![0.0; 10]);
+        assert_eq!(secondary.master_gain_db, -6.4);
+        assert_eq!(settings.app_eq_config("Firefox").unwrap().master_gain_db, 0.0);
+
+        settings.auto_preamp = true;
+        let mut master = settings.master_eq_config().unwrap();
+        assert!(master.auto_preamp);
+        master.apply_auto_preamp(48000.0);
+        assert!((master.master_gain_db + 3.0).abs() < 0.01, "{}", master.master_gain_db);
+    }
+
+    #[test]
+    fn test_user_preset_legacy_json() {
+        // Presets saved before band shapes and preamp were stored
+        let json = r#"{"name":"Old","gains":[1.0,2.0,3.0],"created_at":"2024-01-01T00:00:00Z"}"#;
+        let preset: UserPreset = serde_json::from_str(json).unwrap();
+        assert!(preset.band_params.is_empty());
+        assert_eq!(preset.preamp_db, 0.0);
+
+        let config = preset.to_eq_config().unwrap();
+        assert_eq!(config.get_gains(), vec![1.0, 2.0, 3.0]);
+        assert_eq!(config.bands[1].frequency, gecko_dsp::band_layout_frequency(3, 1));
+    }
+
+    #[test]
+    fn test_user_preset_custom_band_params() {
+        let mut preset = UserPreset {
+            name: "Saved".to_string(),
+            gains: vec![1.0; 10],
+            created_at: Utc::now(),
+            band_params: Vec::new(),
+            preamp_db: 0.0,
+        };
+        assert!(preset.custom_band_params().is_none());
+
+        // The standard layout spelled out is no custom shape
+        preset.band_params = GeckoSettings::default().band_params();
+        assert!(preset.custom_band_params().is_none());
+
+        preset.band_params[3].frequency = 700.0;
+        assert_eq!(preset.custom_band_params().map(<[_]>::len), Some(10));
+    }
+
+    #[test]
+    fn test_user_preset_apo_round_trip() {
+        let text = "Preamp: -4.5 dB\n\
+                    Filter 1: ON LSC Fc 105 Hz Gain 4.0 dB Q 0.70\n\
+                    Filter 2: ON PK Fc 2500 Hz Gain -3.0 dB Q 2.00\n\
+                    Channel: L\n";
+        let (preset, warnings) = UserPreset::from_apo("HD 650", text, 10).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(preset.preamp_db, -4.5);
+        assert_eq!(preset.band_params[1].frequency, 2500.0);
+        assert!(!preset.band_params[2].enabled);
+
+        // Survives a trip through settings.json and back out to APO text
+        let json = serde_json::to_string(&preset).unwrap();
+        let loaded: UserPreset = serde_json::from_str(&json).unwrap();
+        let (reimported, _) = UserPreset::from_apo("HD 650", &loaded.to_apo().unwrap(), 10).unwrap();
+        assert_eq!(reimported.gains, preset.gains);
+        // Unused bands come back disabled; their Q is only written to 3 decimals
+        assert_eq!(reimported.band_params[..2], preset.band_params[..2]);
+        assert!(reimported.band_params[2..].iter().all(|params| !params.enabled));
+        assert_eq!(reimported.preamp_db, -4.5);
+    }
+
     #[test]
     fn test_ui_settings_default() {
         let ui = UiSettings::default();
@@ -240,6 +739,8 @@ mod tests {
         assert!(!ui.start_minimized);
         assert_eq!(ui.eq_bands_ui, 10);
         assert!(ui.soft_clip_enabled);
+        assert_eq!(ui.output_stage, OutputStage::SoftClip);
+        assert_eq!(ui.limiter, LimiterSettings::default());
     }
 
     #[test]
@@ -269,4 +770,111 @@ mod tests {
         assert_eq!(settings.app_volumes.get("muted").unwrap(), &0.0);
         assert_eq!(settings.app_volumes.get("loud").unwrap(), &2.0);
     }
+
+    #[test]
+    fn test_old_fixed_array_settings_load_as_vec() {
+        // Settings written before band count was dynamic stored fixed 10-element arrays;
+        // a 31-band curve must load through the same field
+        let json = r#"{
+            "master_volume": 1.0,
+            "master_eq": [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+                          0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 2.0],
+            "bypassed": false,
+            "active_preset": null,
+            "user_presets": [{"name": "Old", "gains": [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0],
+                              "created_at": "2024-01-01T00:00:00Z"}],
+            "ui_settings": {
+                "theme": "Dark",
+                "show_level_meters": true,
+                "start_minimized": false,
+                "eq_bands_ui": 10
+            }
+        }"#;
+
+        let settings: GeckoSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.band_count(), 31);
+        assert_eq!(settings.master_eq[30], 2.0);
+        assert_eq!(settings.user_presets[0].gains.len(), 10);
+    }
+
+    #[test]
+    fn test_set_band_count_remaps_curves() {
+        let mut settings = GeckoSettings {
+            master_eq: vec![6.0, 6.0, 6.0, 0.0, 0.0, 0.0, 0.0, 0.0, -6.0, -6.0],
+            ..Default::default()
+        };
+        settings.app_eq.insert("Firefox".to_string(), vec![3.0; 10]);
+
+        settings.set_band_count(3).unwrap();
+        assert_eq!(settings.band_count(), 3);
+        assert!((settings.master_eq[0] - 6.0).abs() < 1e-4);
+        assert!((settings.master_eq[2] + 6.0).abs() < 1e-4);
+        assert_eq!(settings.app_eq["Firefox"].len(), 3);
+
+        assert!(settings.set_band_count(0).is_err());
+        assert_eq!(settings.band_count(), 3);
+    }
+
+    #[test]
+    fn test_repair_band_counts() {
+        let mut settings = GeckoSettings {
+            master_eq: vec![3.0; 40],
+            ..Default::default()
+        };
+        settings.app_eq.insert("Firefox".to_string(), vec![-3.0; 10]);
+        let mut empty = UserPreset::from_eq_config("Empty", &EqConfig::default());
+        empty.gains.clear();
+        settings.user_presets = vec![UserPreset::from_eq_config("Ten", &EqConfig::default()), empty];
+
+        assert!(settings.repair_band_counts());
+        assert_eq!(settings.band_count(), gecko_dsp::MAX_BANDS);
+        assert!(settings.master_eq.iter().all(|&gain| (gain - 3.0).abs() < 1e-4));
+        assert_eq!(settings.app_eq["Firefox"].len(), gecko_dsp::MAX_BANDS);
+        // Presets keep their own valid count; unusable ones are dropped
+        assert_eq!(settings.user_presets.len(), 1);
+        assert_eq!(settings.user_presets[0].gains.len(), 10);
+        assert!(settings.master_eq_config().is_ok());
+
+        assert!(!settings.repair_band_counts());
+        let mut empty = GeckoSettings { master_eq: Vec::new(), ..Default::default() };
+        assert!(empty.repair_band_counts());
+        assert_eq!(empty.band_count(), gecko_dsp::EQ_BANDS.len());
+    }
+
+    #[test]
+    fn test_band_params_roundtrip() {
+        let mut settings = GeckoSettings::default();
+        assert_eq!(settings.band_params()[0], gecko_dsp::band_layout(10, 0).params());
+
+        let params = BandParams {
+            frequency: 3500.0,
+            q: 4.0,
+            band_type: gecko_dsp::BandType::HighPass,
+            slope: gecko_dsp::FilterSlope::Db24,
+            enabled: false,
+            dynamics: Some(gecko_dsp::BandDynamics { threshold_db: -20.0, ..Default::default() }),
+        };
+        settings.set_band_params(6, params).unwrap();
+        settings.set_app_band_params("Firefox", 2, params).unwrap();
+        assert!(settings.set_band_params(10, params).is_err());
+        assert!(settings.set_band_params(0, BandParams { q: 0.0, ..params }).is_err());
+
+        let json = serde_json::to_string(&settings).unwrap();
+        assert!(json.contains("\"band_type\":\"peaking\""));
+        assert!(json.contains("\"band_type\":\"high_pass\",\"slope\":\"db24\""));
+        let loaded: GeckoSettings = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.band_params()[6], params);
+        assert_eq!(loaded.app_band_params("Firefox")[2], params);
+        assert_eq!(loaded.app_band_params("Spotify").len(), 10);
+
+        // Shapes saved before slopes existed default to 12 dB/oct
+        let legacy: BandParams =
+            serde_json::from_str(r#"{"frequency":80.0,"q":0.7,"band_type":"low_shelf","enabled":true}"#).unwrap();
+        assert_eq!(legacy.slope, gecko_dsp::FilterSlope::Db12);
+
+        // Shapes revert to the standard layout on band count change
+        settings.set_band_count(3).unwrap();
+        assert!(settings.master_band_params.is_empty());
+        assert_eq!(settings.band_params().len(), 3);
+    }
 }
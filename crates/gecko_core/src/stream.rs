@@ -332,7 +332,7 @@ impl AudioStream {
 
                     // Process through DSP chain if not bypassed
                     if !shared.bypassed.load(Ordering::Relaxed) {
-                        eq.process_interleaved(data);
+                        eq.process_block(data);
                     }
 
                     // Apply master volume
@@ -42,6 +42,62 @@ fn benchmark_eq_processing(c: &mut Criterion) {
     group.finish();
 }
 
+/// Every band boosted or cut, so no band can be skipped
+fn shaped_eq(channels: usize) -> Equalizer {
+    let mut eq = Equalizer::new(48000.0);
+    eq.set_channel_count(channels).unwrap();
+    for band in 0..eq.band_count() {
+        let gain = if band % 2 == 0 { 4.0 } else { -3.0 };
+        eq.set_band_gain(band, gain).unwrap();
+    }
+    eq.finish_smoothing();
+    eq
+}
+
+/// Scalar vs SIMD block path on the same EQ
+///
+/// `shaped` runs the full cascade; `flat` shows skipping of unity bands.
+fn benchmark_eq_block_processing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("equalizer_block");
+
+    for channels in [2, 6] {
+        for size in [128, 512, 2048] {
+            group.throughput(Throughput::Elements(size as u64));
+            let input: Vec<f32> = (0..size * channels)
+                .map(|i| (i as f32 * 0.001).sin())
+                .collect();
+
+            for (shape, make_eq) in [
+                ("shaped", shaped_eq as fn(usize) -> Equalizer),
+                ("flat", |channels| {
+                    let mut eq = Equalizer::new(48000.0);
+                    eq.set_channel_count(channels).unwrap();
+                    eq
+                }),
+            ] {
+                group.bench_function(
+                    format!("interleaved_{}_{}ch_{}_frames", shape, channels, size),
+                    |b| {
+                        let mut eq = make_eq(channels);
+                        let mut buffer = input.clone();
+                        b.iter(|| eq.process_interleaved(black_box(&mut buffer)));
+                    },
+                );
+                group.bench_function(
+                    format!("block_{}_{}ch_{}_frames", shape, channels, size),
+                    |b| {
+                        let mut eq = make_eq(channels);
+                        let mut buffer = input.clone();
+                        b.iter(|| eq.process_block(black_box(&mut buffer)));
+                    },
+                );
+            }
+        }
+    }
+
+    group.finish();
+}
+
 fn benchmark_eq_coefficient_update(c: &mut Criterion) {
     c.bench_function("eq_set_band_gain", |b| {
         let mut eq = Equalizer::new(48000.0);
@@ -70,6 +126,7 @@ fn benchmark_eq_sample_single(c: &mut Criterion) {
 criterion_group!(
     benches,
     benchmark_eq_processing,
+    benchmark_eq_block_processing,
     benchmark_eq_coefficient_update,
     benchmark_eq_sample_single
 );
@@ -0,0 +1,554 @@
+//! EqualizerAPO / AutoEQ Config Import and Export
+//!
+//! Reads and writes the plain-text config format used by EqualizerAPO,
+//! which is also what AutoEQ publishes for headphone correction:
+//!
+//! ```text
+//! Preamp: -6.2 dB
+//! Filter 1: ON LSC Fc 105 Hz Gain 5.8 dB Q 0.70
+//! Filter 2: ON PK Fc 2364 Hz Gain -3.1 dB Q 1.41
+//! GraphicEQ: 20 -2.1; 21 -2.0; 22 -1.9; ...
+//! ```
+//!
+//! Parametric filters map one-to-one onto bands, keeping type, frequency,
+//! gain and Q. A `GraphicEQ:` curve is interpolated onto the standard band
+//! layout instead. Commands without an equivalent (`Channel:`, `Delay:`,
+//! `Include:`, ...) are skipped and listed in [`ApoImport::warnings`].
+
+use std::fmt::Write;
+
+use crate::eq::{
+    band_layout, Band, BandType, EqConfig, FilterSlope, BUTTERWORTH_Q_4, BUTTERWORTH_Q_8, MAX_BANDS,
+};
+use crate::error::DspError;
+
+/// Points per octave for [`EqConfig::to_apo_graphic_eq`] (20 Hz - 20 kHz)
+const GRAPHIC_EQ_POINTS_PER_OCTAVE: usize = 12;
+
+/// Result of parsing an EqualizerAPO config
+#[derive(Debug, Clone)]
+pub struct ApoImport {
+    /// Imported bands, preamp as `master_gain_db`
+    pub config: EqConfig,
+    /// Lines that were skipped or adjusted, one human-readable note each
+    pub warnings: Vec<String>,
+}
+
+fn parse_error(line: usize, reason: impl Into<String>) -> DspError {
+    DspError::InvalidApoConfig {
+        line,
+        reason: reason.into(),
+    }
+}
+
+/// Parse a number, ignoring a glued-on unit ("6dB", "105Hz")
+fn parse_number(token: &str) -> Option<f32> {
+    let trimmed = token.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    trimmed.parse().ok()
+}
+
+/// Q of an RBJ shelf with slope `slope_db` per octave (12 dB/oct = S of 1)
+fn shelf_q(gain_db: f32, slope_db: f32) -> f32 {
+    let a = 10.0_f32.powf(gain_db / 40.0);
+    let s = (slope_db / 12.0).clamp(0.01, 1.0);
+    1.0 / ((a + 1.0 / a) * (1.0 / s - 1.0) + 2.0).sqrt()
+}
+
+/// Parse the part of a `Filter:` line after the colon
+///
+/// Returns `Ok(None)` for filter types with no band equivalent.
+fn parse_filter(
+    body: &str,
+    line: usize,
+    warnings: &mut Vec<String>,
+) -> Result<Option<Band>, DspError> {
+    let tokens: Vec<&str> = body.split_whitespace().collect();
+    let enabled = match tokens.first().map(|t| t.to_ascii_uppercase()) {
+        Some(state) if state == "ON" => true,
+        Some(state) if state == "OFF" => false,
+        _ => return Err(parse_error(line, "expected ON or OFF")),
+    };
+    let kind = tokens
+        .get(1)
+        .map(|t| t.to_ascii_uppercase())
+        .ok_or_else(|| parse_error(line, "missing filter type"))?;
+
+    let band_type = match kind.as_str() {
+        "PK" | "PEQ" | "MODAL" => BandType::Peaking,
+        "LS" | "LSC" => BandType::LowShelf,
+        "HS" | "HSC" => BandType::HighShelf,
+        "LP" | "LPQ" => BandType::LowPass,
+        "HP" | "HPQ" => BandType::HighPass,
+        "BP" => BandType::BandPass,
+        "NO" => BandType::Notch,
+        "AP" => BandType::AllPass,
+        _ => {
+            warnings.push(format!(
+                "Line {}: unsupported filter type '{}' skipped",
+                line, kind
+            ));
+            return Ok(None);
+        }
+    };
+
+    let mut frequency = None;
+    let mut gain_db = 0.0;
+    let mut q = None;
+    let mut bandwidth_octaves = None;
+    let mut shelf_slope_db = None;
+
+    let mut i = 2;
+    while i < tokens.len() {
+        let key = tokens[i].to_ascii_uppercase();
+        let value = |offset: usize| {
+            tokens
+                .get(i + offset)
+                .and_then(|t| parse_number(t))
+                .ok_or_else(|| parse_error(line, format!("missing value after '{}'", tokens[i])))
+        };
+        match key.as_str() {
+            "FC" => {
+                frequency = Some(value(1)?);
+                i += 2;
+            }
+            "GAIN" => {
+                gain_db = value(1)?;
+                i += 2;
+            }
+            "Q" => {
+                q = Some(value(1)?);
+                i += 2;
+            }
+            "BW" => {
+                // "BW Oct 1.0"
+                bandwidth_octaves = Some(value(2)?);
+                i += 3;
+            }
+            "HZ" | "DB" => i += 1,
+            _ => {
+                // "LS 6dB Fc ...": shelf slope in dB per octave
+                match parse_number(&key) {
+                    Some(slope) if key.ends_with("DB") => shelf_slope_db = Some(slope),
+                    _ => return Err(parse_error(line, format!("unexpected '{}'", tokens[i]))),
+                }
+                i += 1;
+            }
+        }
+    }
+
+    let frequency = frequency.ok_or_else(|| parse_error(line, "missing Fc"))?;
+    let q = match (q, bandwidth_octaves, shelf_slope_db) {
+        (Some(q), _, _) => q,
+        // Bandwidth in octaves to Q: sqrt(2^N) / (2^N - 1)
+        (None, Some(bw), _) => 2.0_f32.powf(bw).sqrt() / (2.0_f32.powf(bw) - 1.0),
+        (None, None, Some(slope)) => shelf_q(gain_db, slope),
+        (None, None, None) => biquad::Q_BUTTERWORTH_F32,
+    };
+
+    if gain_db.abs() > 24.0 && band_type.uses_gain() {
+        warnings.push(format!(
+            "Line {}: gain {} dB clamped to ±24 dB",
+            line, gain_db
+        ));
+    }
+
+    let band = Band {
+        frequency,
+        gain_db: gain_db.clamp(-24.0, 24.0),
+        q,
+        band_type,
+        slope: FilterSlope::Db12,
+        enabled,
+        dynamics: None,
+    };
+    band.params()
+        .validate()
+        .map_err(|e| parse_error(line, e.to_string()))?;
+    Ok(Some(band))
+}
+
+/// Parse `GraphicEQ: f0 g0; f1 g1; ...` into sorted frequencies and gains
+fn parse_graphic_eq(body: &str, line: usize) -> Result<(Vec<f32>, Vec<f32>), DspError> {
+    let mut points: Vec<(f32, f32)> = Vec::new();
+    for point in body.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+        let mut parts = point.split_whitespace();
+        let frequency = parts.next().and_then(parse_number);
+        let gain = parts.next().and_then(parse_number);
+        match (frequency, gain) {
+            (Some(frequency), Some(gain)) if frequency > 0.0 => points.push((frequency, gain)),
+            _ => {
+                return Err(parse_error(
+                    line,
+                    format!("invalid GraphicEQ point '{}'", point),
+                ))
+            }
+        }
+    }
+    if points.is_empty() {
+        return Err(parse_error(line, "GraphicEQ has no points"));
+    }
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Ok(points.into_iter().unzip())
+}
+
+/// Shortest decimal form with at most `decimals` places ("1000", "0.7", "-3.25")
+fn format_number(value: f32, decimals: usize) -> String {
+    let text = format!("{:.*}", decimals, value);
+    let text = if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &text
+    };
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+impl EqConfig {
+    /// Import an EqualizerAPO / AutoEQ config onto `band_count` bands
+    ///
+    /// Parametric filters fill bands in file order; bands left over keep the
+    /// standard layout but are disabled. A `GraphicEQ:` curve (used only when
+    /// the file has no parametric filters) is interpolated onto the standard
+    /// layout. `Preamp:` lines add up into `master_gain_db`.
+    ///
+    /// Fails with [`DspError::TooManyFilters`] rather than dropping filters
+    /// when the file has more than `band_count`.
+    pub fn from_apo(text: &str, band_count: usize) -> Result<ApoImport, DspError> {
+        if band_count == 0 || band_count > MAX_BANDS {
+            return Err(DspError::InvalidBandCount(band_count));
+        }
+
+        let mut warnings = Vec::new();
+        let mut filters = Vec::new();
+        let mut graphic = None;
+        let mut preamp_db = 0.0;
+
+        for (index, raw) in text.lines().enumerate() {
+            let line = index + 1;
+            let content = raw.split('#').next().unwrap_or("").trim();
+            if content.is_empty() {
+                continue;
+            }
+            let Some((command, body)) = content.split_once(':') else {
+                return Err(parse_error(
+                    line,
+                    format!("expected 'Command: ...', got '{}'", content),
+                ));
+            };
+            let command = command.trim();
+            // "Filter", "Filter 1", "Filter 12"
+            let name = command
+                .split_whitespace()
+                .next()
+                .unwrap_or("")
+                .to_ascii_lowercase();
+
+            match name.as_str() {
+                "preamp" => {
+                    let gain = body
+                        .split_whitespace()
+                        .next()
+                        .and_then(parse_number)
+                        .ok_or_else(|| parse_error(line, "invalid Preamp value"))?;
+                    preamp_db += gain;
+                }
+                "filter" => {
+                    if let Some(band) = parse_filter(body, line, &mut warnings)? {
+                        filters.push(band);
+                    }
+                }
+                "graphiceq" => {
+                    if graphic.is_some() {
+                        warnings.push(format!("Line {}: only the first GraphicEQ is used", line));
+                    } else {
+                        graphic = Some(parse_graphic_eq(body, line)?);
+                    }
+                }
+                _ => warnings.push(format!(
+                    "Line {}: '{}' is not supported and was skipped",
+                    line, command
+                )),
+            }
+        }
+
+        if filters.len() > band_count {
+            return Err(DspError::TooManyFilters {
+                filters: filters.len(),
+                bands: band_count,
+            });
+        }
+
+        let mut config = EqConfig::with_band_count(band_count)?;
+        config.master_gain_db = preamp_db;
+
+        if !filters.is_empty() {
+            if graphic.is_some() {
+                warnings.push(
+                    "GraphicEQ ignored because the file also has parametric filters".to_string(),
+                );
+            }
+            for (i, band) in config.bands.iter_mut().enumerate() {
+                *band = match filters.get(i) {
+                    Some(filter) => *filter,
+                    None => Band {
+                        enabled: false,
+                        ..band_layout(band_count, i)
+                    },
+                };
+            }
+        } else if let Some((frequencies, gains)) = graphic {
+            config.set_gains_interpolated(&frequencies, &gains);
+        } else {
+            warnings.push("No filters or GraphicEQ found; EQ is flat".to_string());
+        }
+
+        Ok(ApoImport { config, warnings })
+    }
+
+    /// Export as EqualizerAPO parametric config (AutoEQ ParametricEQ.txt style)
+    ///
+    /// Every band becomes one or more `Filter` lines (disabled bands as OFF).
+    /// Steep high/low-pass slopes are written as cascaded Butterworth stages
+    /// and tilt as its two opposing shelves, so the exported response matches.
+    pub fn to_apo(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Preamp: {} dB", format_number(self.master_gain_db, 2));
+
+        let mut index = 1;
+        let mut filter = |out: &mut String, enabled: bool, spec: String| {
+            let state = if enabled && self.enabled { "ON" } else { "OFF" };
+            let _ = writeln!(out, "Filter {}: {} {}", index, state, spec);
+            index += 1;
+        };
+
+        for band in &self.bands {
+            let fc = format_number(band.frequency, 2);
+            let gain = format_number(band.gain_db, 2);
+            let q = format_number(band.q, 3);
+            let pass =
+                |kind: &str, q: f32| format!("{} Fc {} Hz Q {}", kind, fc, format_number(q, 4));
+
+            match band.band_type {
+                BandType::Peaking => filter(
+                    &mut out,
+                    band.enabled,
+                    format!("PK Fc {} Hz Gain {} dB Q {}", fc, gain, q),
+                ),
+                BandType::LowShelf => filter(
+                    &mut out,
+                    band.enabled,
+                    format!("LSC Fc {} Hz Gain {} dB Q {}", fc, gain, q),
+                ),
+                BandType::HighShelf => filter(
+                    &mut out,
+                    band.enabled,
+                    format!("HSC Fc {} Hz Gain {} dB Q {}", fc, gain, q),
+                ),
+                BandType::Tilt => {
+                    let half = format_number(band.gain_db / 2.0, 2);
+                    let low = format_number(-band.gain_db / 2.0, 2);
+                    filter(
+                        &mut out,
+                        band.enabled,
+                        format!("LSC Fc {} Hz Gain {} dB Q {}", fc, low, q),
+                    );
+                    filter(
+                        &mut out,
+                        band.enabled,
+                        format!("HSC Fc {} Hz Gain {} dB Q {}", fc, half, q),
+                    );
+                }
+                BandType::HighPass | BandType::LowPass => {
+                    let kind = if band.band_type == BandType::HighPass {
+                        "HPQ"
+                    } else {
+                        "LPQ"
+                    };
+                    match band.slope {
+                        FilterSlope::Db12 => filter(&mut out, band.enabled, pass(kind, band.q)),
+                        FilterSlope::Db24 => {
+                            for stage_q in BUTTERWORTH_Q_4 {
+                                filter(&mut out, band.enabled, pass(kind, stage_q));
+                            }
+                        }
+                        FilterSlope::Db48 => {
+                            for stage_q in BUTTERWORTH_Q_8 {
+                                filter(&mut out, band.enabled, pass(kind, stage_q));
+                            }
+                        }
+                    }
+                }
+                BandType::BandPass => filter(&mut out, band.enabled, pass("BP", band.q)),
+                BandType::Notch => filter(&mut out, band.enabled, pass("NO", band.q)),
+                BandType::AllPass => filter(&mut out, band.enabled, pass("AP", band.q)),
+            }
+        }
+        out
+    }
+
+    /// Export the combined response as an EqualizerAPO `GraphicEQ:` line
+    ///
+    /// Samples the magnitude at 1/12-octave steps from 20 Hz to 20 kHz, as
+    /// heard at `sample_rate`. The preamp goes on its own `Preamp:` line.
+    pub fn to_apo_graphic_eq(&self, sample_rate: f32) -> String {
+        let octaves = (20000.0_f32 / 20.0).log2();
+        let count = (octaves * GRAPHIC_EQ_POINTS_PER_OCTAVE as f32).round() as usize + 1;
+        let frequencies: Vec<f32> = (0..count)
+            .map(|i| 20.0 * 2.0_f32.powf(i as f32 / GRAPHIC_EQ_POINTS_PER_OCTAVE as f32))
+            .collect();
+        let cos_w: Vec<f64> = frequencies
+            .iter()
+            .map(|&f| (std::f64::consts::TAU * f as f64 / sample_rate as f64).cos())
+            .collect();
+
+        let mut magnitude = vec![1.0; count];
+        if self.enabled {
+            self.magnitude_into(sample_rate, &cos_w, &mut magnitude);
+        }
+
+        let points: Vec<String> = frequencies
+            .iter()
+            .zip(&magnitude)
+            .map(|(&f, &m)| {
+                let gain = 20.0 * m.max(1e-6).log10();
+                format!("{} {}", format_number(f, 0), format_number(gain, 1))
+            })
+            .collect();
+        format!(
+            "Preamp: {} dB\nGraphicEQ: {}\n",
+            format_number(self.master_gain_db, 2),
+            points.join("; ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AUTOEQ: &str = "\
+Preamp: -6.4 dB
+Filter 1: ON LSC Fc 105 Hz Gain 5.8 dB Q 0.70
+Filter 2: ON PK Fc 2364 Hz Gain -3.1 dB Q 1.41
+Filter 3: ON PK Fc 5000 Hz Gain 2 dB BW Oct 1.0
+Filter 4: OFF HSC Fc 10000 Hz Gain -4.2 dB Q 0.70
+Filter 5: ON HPQ Fc 20 Hz Q 0.5
+";
+
+    #[test]
+    fn test_import_autoeq_parametric() {
+        let import = EqConfig::from_apo(AUTOEQ, 10).unwrap();
+        let config = import.config;
+        assert!(import.warnings.is_empty(), "{:?}", import.warnings);
+        assert_eq!(config.band_count(), 10);
+        assert_eq!(config.master_gain_db, -6.4);
+
+        let first = config.bands[0];
+        assert_eq!(first.band_type, BandType::LowShelf);
+        assert_eq!(first.frequency, 105.0);
+        assert_eq!(first.gain_db, 5.8);
+        assert_eq!(first.q, 0.70);
+
+        assert_eq!(config.bands[1].band_type, BandType::Peaking);
+        assert_eq!(config.bands[1].gain_db, -3.1);
+        // 1 octave bandwidth is Q ~1.414
+        assert!((config.bands[2].q - 1.414).abs() < 0.01);
+        assert!(!config.bands[3].enabled);
+        assert_eq!(config.bands[4].band_type, BandType::HighPass);
+        assert_eq!(config.bands[4].q, 0.5);
+
+        // Unused bands are disabled, not left as stray flat filters
+        assert!(config.bands[5..].iter().all(|band| !band.enabled));
+    }
+
+    #[test]
+    fn test_too_many_filters_is_reported() {
+        let err = EqConfig::from_apo(AUTOEQ, 3).unwrap_err();
+        assert!(matches!(
+            err,
+            DspError::TooManyFilters {
+                filters: 5,
+                bands: 3
+            }
+        ));
+        assert!(err.to_string().contains("5 filters"));
+    }
+
+    #[test]
+    fn test_import_graphic_eq() {
+        let text = "Preamp: -3 dB\nGraphicEQ: 20 6; 100 6; 1000 0; 20000 -6\n";
+        let import = EqConfig::from_apo(text, 10).unwrap();
+        let gains = import.config.get_gains();
+        assert_eq!(import.config.master_gain_db, -3.0);
+        assert_eq!(gains[0], 6.0); // 31 Hz
+        assert_eq!(gains[5], 0.0); // 1 kHz
+        assert!(gains[9] < -5.0); // 16 kHz
+    }
+
+    #[test]
+    fn test_unsupported_lines_are_reported() {
+        let text = "Channel: L\nFilter: ON IIR Order 2\nFilter: ON PK Fc 1000 Hz Gain 3 dB Q 1\n# comment\n";
+        let import = EqConfig::from_apo(text, 3).unwrap();
+        assert_eq!(import.warnings.len(), 2);
+        assert!(import.warnings[0].contains("Channel"));
+        assert!(import.warnings[1].contains("IIR"));
+        assert_eq!(import.config.bands[0].gain_db, 3.0);
+    }
+
+    #[test]
+    fn test_parse_errors_carry_line_numbers() {
+        let err =
+            EqConfig::from_apo("Preamp: 0 dB\nFilter 1: ON PK Gain 3 dB Q 1\n", 10).unwrap_err();
+        assert!(matches!(err, DspError::InvalidApoConfig { line: 2, .. }));
+        assert!(EqConfig::from_apo("Filter 1: MAYBE PK Fc 100 Hz", 10).is_err());
+        assert!(EqConfig::from_apo("Filter 1: ON PK Fc -100 Hz Gain 1 dB Q 1", 10).is_err());
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let original = EqConfig::from_apo(AUTOEQ, 10).unwrap().config;
+        let text = original.to_apo();
+        assert!(text.starts_with("Preamp: -6.4 dB\n"));
+        assert!(text.contains("Filter 1: ON LSC Fc 105 Hz Gain 5.8 dB Q 0.7\n"));
+        assert!(text.contains("Filter 4: OFF HSC Fc 10000 Hz"));
+
+        let reimported = EqConfig::from_apo(&text, 10).unwrap().config;
+        for (a, b) in original.bands.iter().zip(&reimported.bands) {
+            assert_eq!(a.band_type, b.band_type);
+            assert!((a.frequency - b.frequency).abs() < 0.01);
+            assert!((a.gain_db - b.gain_db).abs() < 0.01);
+            assert!((a.q - b.q).abs() < 0.001);
+            assert_eq!(a.enabled, b.enabled);
+        }
+    }
+
+    #[test]
+    fn test_export_expands_steep_slopes_and_tilt() {
+        let mut config = EqConfig::with_band_count(2).unwrap();
+        config.bands[0].band_type = BandType::HighPass;
+        config.bands[0].slope = FilterSlope::Db24;
+        config.bands[1].band_type = BandType::Tilt;
+        config.bands[1].gain_db = 4.0;
+
+        let text = config.to_apo();
+        assert_eq!(text.lines().filter(|l| l.starts_with("Filter")).count(), 4);
+        assert!(text.contains("HPQ Fc 31.25 Hz Q 0.5412"));
+        assert!(text.contains("LSC Fc 16000 Hz Gain -2 dB"));
+        assert!(text.contains("HSC Fc 16000 Hz Gain 2 dB"));
+    }
+
+    #[test]
+    fn test_export_graphic_eq() {
+        let mut config = EqConfig::default();
+        config.set_band_gain(5, 6.0).unwrap();
+        let text = config.to_apo_graphic_eq(48000.0);
+        assert!(text.contains("GraphicEQ: 20 0; "));
+
+        // Our own GraphicEQ export imports back onto the same curve
+        let reimported = EqConfig::from_apo(&text, 10).unwrap().config;
+        assert!((reimported.bands[5].gain_db - 6.0).abs() < 0.2);
+    }
+}
@@ -0,0 +1,373 @@
+//! Automatic Loudness Leveling
+//!
+//! Apps play at very different levels: a YouTube video, a Spotify track and
+//! a game can be 10 LU apart. The auto-leveler measures the short-term
+//! loudness (3 s, BS.1770) of its input and slowly moves a gain so the
+//! output sits at a shared target loudness.
+//!
+//! # Behaviour
+//!
+//! - Loudness is averaged over the last 3 s of 100 ms blocks that pass
+//!   [`AUTO_LEVEL_GATE_LUFS`]; pauses and silence don't count.
+//! - The gain needed is `target - loudness`, limited to the configured
+//!   maximum boost and cut.
+//! - The gain moves toward it at a fixed rate (dB per second), ramped
+//!   sample by sample, so changes are slow and never click.
+//! - During pauses the gain is held, so silence isn't pumped up to the target.
+//! - When disabled the gain glides back to 0 dB at the same rate.
+
+use crate::error::DspError;
+use crate::loudness::{loudness, LoudnessAnalyzer, LOUDNESS_FLOOR_LUFS};
+use crate::processor::{AudioProcessor, ProcessContext};
+
+/// 100 ms blocks quieter than this are treated as pauses
+pub const AUTO_LEVEL_GATE_LUFS: f32 = -50.0;
+
+/// Gated blocks (100 ms) averaged for the loudness estimate
+const WINDOW_BLOCKS: usize = 30;
+
+/// Auto-leveling parameters, shared by every leveled app
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AutoLevelSettings {
+    /// Loudness to level toward (-40 to -6 LUFS)
+    pub target_lufs: f32,
+    /// Largest gain applied to quiet input (0 to 24 dB)
+    pub max_boost_db: f32,
+    /// Largest attenuation applied to loud input (0 to 40 dB)
+    pub max_cut_db: f32,
+    /// How fast the gain moves toward its goal (0.1 to 20 dB per second)
+    pub speed_db_per_s: f32,
+}
+
+impl Default for AutoLevelSettings {
+    fn default() -> Self {
+        Self {
+            target_lufs: -18.0,
+            max_boost_db: 6.0,
+            max_cut_db: 12.0,
+            speed_db_per_s: 1.0,
+        }
+    }
+}
+
+impl AutoLevelSettings {
+    /// Check every parameter is in range
+    pub fn validate(&self) -> Result<(), DspError> {
+        let ranges = [
+            ("target_lufs", self.target_lufs, -40.0..=-6.0),
+            ("max_boost_db", self.max_boost_db, 0.0..=24.0),
+            ("max_cut_db", self.max_cut_db, 0.0..=40.0),
+            ("speed_db_per_s", self.speed_db_per_s, 0.1..=20.0),
+        ];
+        for (name, value, range) in ranges {
+            if !range.contains(&value) {
+                return Err(DspError::InvalidParameter { name, value });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Slow gain rider toward a target loudness
+///
+/// Allocates its loudness analyzer up front; nothing allocates after `new`.
+pub struct AutoLeveler {
+    settings: AutoLevelSettings,
+    enabled: bool,
+    analyzer: LoudnessAnalyzer,
+    // Energy of the last WINDOW_BLOCKS blocks that passed the gate (ring)
+    gated_blocks: [f64; WINDOW_BLOCKS],
+    gated_index: usize,
+    gated_count: usize,
+    blocks_seen: u64,
+    /// Gain applied at the end of the last buffer
+    gain_db: f32,
+    /// Gain the leveler is moving toward
+    goal_db: f32,
+}
+
+impl AutoLeveler {
+    /// Create a disabled leveler at `sample_rate` for 2 channels
+    pub fn new(sample_rate: f32, settings: AutoLevelSettings) -> Result<Self, DspError> {
+        settings.validate()?;
+        Ok(Self {
+            settings,
+            enabled: false,
+            analyzer: LoudnessAnalyzer::new(sample_rate)?,
+            gated_blocks: [0.0; WINDOW_BLOCKS],
+            gated_index: 0,
+            gated_count: 0,
+            blocks_seen: 0,
+            gain_db: 0.0,
+            goal_db: 0.0,
+        })
+    }
+
+    /// Replace the parameters; the current gain carries over
+    pub fn set_settings(&mut self, settings: AutoLevelSettings) -> Result<(), DspError> {
+        settings.validate()?;
+        self.settings = settings;
+        Ok(())
+    }
+
+    /// Current parameters
+    pub fn settings(&self) -> &AutoLevelSettings {
+        &self.settings
+    }
+
+    /// Enable or disable leveling (disabled glides back to 0 dB)
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Set the channel layout by loudness weight (see [`LoudnessAnalyzer::set_channel_weights`])
+    ///
+    /// # Real-time Safety
+    /// No allocations. Safe to call from an audio callback between buffers.
+    pub fn set_channel_weights(&mut self, weights: &[f32]) -> Result<(), DspError> {
+        let changed = weights.len() != self.channel_count();
+        self.analyzer.set_channel_weights(weights)?;
+        if changed {
+            // The analyzer restarted; so does the loudness estimate
+            self.clear_measurement();
+        }
+        Ok(())
+    }
+
+    /// Number of interleaved channels processed
+    pub fn channel_count(&self) -> usize {
+        self.analyzer.channel_count()
+    }
+
+    /// Gain (dB) applied at the end of the last buffer
+    pub fn gain_db(&self) -> f32 {
+        self.gain_db
+    }
+
+    /// Gated loudness of the input over the last 3 s of sound (LUFS, before leveling)
+    ///
+    /// Reads [`LOUDNESS_FLOOR_LUFS`] until a block passes the gate.
+    pub fn input_loudness_lufs(&self) -> f32 {
+        if self.gated_count == 0 {
+            return LOUDNESS_FLOOR_LUFS;
+        }
+        let energy = self.gated_blocks[..self.gated_count].iter().sum::<f64>();
+        loudness(energy / self.gated_count as f64) as f32
+    }
+
+    /// Measure and level an interleaved buffer in place
+    ///
+    /// # Real-time Safety
+    /// No allocations, no locks. O(n) where n = buffer length.
+    pub fn process_interleaved(&mut self, buffer: &mut [f32]) {
+        // Measure before the gain so the leveler doesn't chase itself,
+        // at most one block per slice so no block is missed
+        let slice = self.analyzer.block_frames() * self.channel_count();
+        for chunk in buffer.chunks(slice) {
+            self.analyzer.process_interleaved(chunk);
+            if self.analyzer.blocks_measured() != self.blocks_seen {
+                self.blocks_seen = self.analyzer.blocks_measured();
+                self.add_block(self.analyzer.last_block_energy());
+            }
+        }
+
+        if !self.enabled {
+            self.goal_db = 0.0;
+        } else if self.gated_count > 0 {
+            self.goal_db = (self.settings.target_lufs - self.input_loudness_lufs())
+                .clamp(-self.settings.max_cut_db, self.settings.max_boost_db);
+        }
+
+        let frames = buffer.len() / self.channel_count();
+        if frames == 0 || (self.gain_db == 0.0 && self.goal_db == 0.0) {
+            return;
+        }
+
+        // Slew toward the goal, ramping the linear gain across the buffer
+        let max_step = self.settings.speed_db_per_s * frames as f32 / self.analyzer.sample_rate();
+        let next_db = self.gain_db + (self.goal_db - self.gain_db).clamp(-max_step, max_step);
+        let start = 10.0_f32.powf(self.gain_db / 20.0);
+        let end = 10.0_f32.powf(next_db / 20.0);
+        let step = (end - start) / frames as f32;
+        for (i, frame) in buffer.chunks_exact_mut(self.channel_count()).enumerate() {
+            let gain = start + step * (i + 1) as f32;
+            for sample in frame.iter_mut() {
+                *sample *= gain;
+            }
+        }
+        self.gain_db = next_db;
+    }
+
+    fn clear_measurement(&mut self) {
+        self.gated_blocks = [0.0; WINDOW_BLOCKS];
+        self.gated_index = 0;
+        self.gated_count = 0;
+        self.blocks_seen = 0;
+    }
+
+    /// Count a finished 100 ms block if it passes the gate
+    fn add_block(&mut self, energy: f64) {
+        if loudness(energy) >= AUTO_LEVEL_GATE_LUFS as f64 {
+            self.gated_blocks[self.gated_index] = energy;
+            self.gated_index = (self.gated_index + 1) % WINDOW_BLOCKS;
+            self.gated_count = (self.gated_count + 1).min(WINDOW_BLOCKS);
+        }
+    }
+}
+
+impl AudioProcessor for AutoLeveler {
+    fn process(&mut self, buffer: &mut [f32], context: &ProcessContext) {
+        if context.channels != self.channel_count() {
+            let weights = [1.0; crate::eq::MAX_CHANNELS];
+            let channels = context.channels.clamp(1, crate::eq::MAX_CHANNELS);
+            let _ = self.set_channel_weights(&weights[..channels]);
+        }
+        self.process_interleaved(buffer);
+    }
+
+    fn reset(&mut self) {
+        self.analyzer.reset();
+        self.clear_measurement();
+        self.gain_db = 0.0;
+        self.goal_db = 0.0;
+    }
+
+    fn name(&self) -> &'static str {
+        "Auto Level"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run a stereo 1 kHz sine at `dbfs` through the leveler for `seconds`
+    fn run(leveler: &mut AutoLeveler, dbfs: f32, seconds: f32) -> Vec<f32> {
+        let amplitude = 10.0_f32.powf(dbfs / 20.0);
+        let mut output = Vec::new();
+        let mut n = 0usize;
+        for _ in 0..(seconds * 100.0) as usize {
+            // 10 ms buffers, as a callback would see them
+            let mut buffer: Vec<f32> = (0..480)
+                .flat_map(|i| {
+                    let t = (n + i) as f32 / 48000.0;
+                    let x = amplitude * (std::f32::consts::TAU * 1000.0 * t).sin();
+                    [x, x]
+                })
+                .collect();
+            n += 480;
+            leveler.process_interleaved(&mut buffer);
+            output.extend_from_slice(&buffer);
+        }
+        output
+    }
+
+    fn leveler(settings: AutoLevelSettings) -> AutoLeveler {
+        let mut leveler = AutoLeveler::new(48000.0, settings).unwrap();
+        leveler.set_enabled(true);
+        leveler
+    }
+
+    #[test]
+    fn test_levels_toward_target() {
+        // -30 dBFS sine reads -30 LUFS; the target is 6 dB above
+        let settings = AutoLevelSettings {
+            target_lufs: -24.0,
+            speed_db_per_s: 3.0,
+            ..AutoLevelSettings::default()
+        };
+        let mut leveler = leveler(settings);
+        run(&mut leveler, -30.0, 10.0);
+        assert!(
+            (leveler.gain_db() - 6.0).abs() < 0.1,
+            "Gain {}",
+            leveler.gain_db()
+        );
+        assert!((leveler.input_loudness_lufs() + 30.0).abs() < 0.1);
+
+        // Loud input is cut, limited by max_cut_db
+        run(&mut leveler, -3.0, 20.0);
+        assert!(
+            (leveler.gain_db() + 12.0).abs() < 0.1,
+            "Gain {}",
+            leveler.gain_db()
+        );
+    }
+
+    #[test]
+    fn test_gain_moves_slowly_and_smoothly() {
+        let settings = AutoLevelSettings {
+            target_lufs: -24.0,
+            max_cut_db: 20.0,
+            speed_db_per_s: 2.0,
+            ..AutoLevelSettings::default()
+        };
+        let mut leveler = leveler(settings);
+        run(&mut leveler, -10.0, 1.0);
+        // Never faster than the configured rate
+        assert!(
+            leveler.gain_db() >= -2.0 - 1e-3,
+            "Gain {}",
+            leveler.gain_db()
+        );
+        assert!(leveler.gain_db() < -1.0);
+
+        // Consecutive samples of a constant input change by a tiny factor
+        let mut leveler = leveler_with_dc();
+        let mut buffer = vec![0.1_f32; 9600];
+        leveler.process_interleaved(&mut buffer);
+        let steps = buffer.windows(2).map(|w| (w[1] - w[0]).abs());
+        assert!(steps.fold(0.0_f32, f32::max) < 1e-5);
+    }
+
+    fn leveler_with_dc() -> AutoLeveler {
+        let mut leveler = leveler(AutoLevelSettings::default());
+        leveler.goal_db = 6.0;
+        leveler.set_channel_weights(&[1.0]).unwrap();
+        leveler
+    }
+
+    #[test]
+    fn test_holds_in_silence_and_releases_when_disabled() {
+        let settings = AutoLevelSettings {
+            target_lufs: -24.0,
+            speed_db_per_s: 5.0,
+            ..AutoLevelSettings::default()
+        };
+        let mut leveler = leveler(settings);
+        run(&mut leveler, -28.0, 5.0);
+        let held = leveler.gain_db();
+        assert!((held - 4.0).abs() < 0.1);
+
+        // Silence: no boost toward the target, the gain stays put
+        let mut silence = vec![0.0_f32; 96000 * 2];
+        leveler.process_interleaved(&mut silence);
+        assert_eq!(leveler.gain_db(), held);
+
+        leveler.set_enabled(false);
+        run(&mut leveler, -28.0, 2.0);
+        assert_eq!(leveler.gain_db(), 0.0);
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        let bad = AutoLevelSettings {
+            target_lufs: 0.0,
+            ..AutoLevelSettings::default()
+        };
+        assert!(AutoLeveler::new(48000.0, bad).is_err());
+        let mut leveler = leveler(AutoLevelSettings::default());
+        assert!(leveler
+            .set_settings(AutoLevelSettings {
+                speed_db_per_s: 0.0,
+                ..AutoLevelSettings::default()
+            })
+            .is_err());
+        assert!(AutoLeveler::new(0.0, AutoLevelSettings::default()).is_err());
+    }
+}
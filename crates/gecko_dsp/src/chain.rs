@@ -0,0 +1,628 @@
+//! Configurable Effect Chains
+//!
+//! The master output and each app run an ordered list of processors. The
+//! list is described by a serializable [`ChainConfig`], edited on the UI side
+//! (add, remove, reorder, bypass), and turned into a [`ProcessorChain`] by the
+//! platform backend, which knows where each engine-bound processor (the EQ,
+//! volume, output stage...) gets its live settings.
+//!
+//! # Swapping Chains
+//!
+//! ```text
+//! control thread                         audio thread
+//! build ProcessorChain ──→ [pending] ──→ ChainReceiver::process
+//!   (allocates)                            crossfades old → new
+//! drop old chains ←────── [retired] ←──── old chain
+//! ```
+//!
+//! Both queues are lock-free SPSC rings, and the audio thread never drops a
+//! chain, so swapping neither locks nor allocates or frees on the audio
+//! thread. The new chain starts from fresh filter state, so the output
+//! crossfades from the old chain to the new one over [`CHAIN_FADE_MS`].
+
+use crate::compressor::{Compressor, CompressorSettings};
+use crate::error::DspError;
+use crate::multiband::{MultibandCompressor, MultibandSettings};
+use crate::processor::{AudioProcessor, ProcessContext, ProcessorChain};
+
+/// Most processors in one chain
+pub const MAX_CHAIN_LENGTH: usize = 16;
+
+/// Crossfade from the old chain to the new one after a swap (ms)
+pub const CHAIN_FADE_MS: f32 = 20.0;
+
+/// Largest boost or cut of a [`ProcessorKind::Gain`] stage (dB)
+const MAX_GAIN_DB: f32 = 24.0;
+
+/// Chains waiting for the audio thread (edits faster than this are refused)
+const PENDING_CHAINS: usize = 4;
+
+/// Where a chain runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ChainScope {
+    /// After the apps are mixed, before the output device
+    Master,
+    /// One app's audio, before mixing
+    App,
+}
+
+/// One kind of processor, with its parameters
+///
+/// Engine-bound kinds follow the engine's own controls (EQ bands, volume,
+/// crossfeed per output device...); the chain only decides where they run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum ProcessorKind {
+    /// The master or app parametric EQ
+    Equalizer,
+    /// Headphone crossfeed for the current output device (master only)
+    Crossfeed,
+    /// Feed-forward compressor
+    Compressor(CompressorSettings),
+    /// Compressor with separate settings for 3 to 5 frequency bands
+    MultibandCompressor(MultibandSettings),
+    /// Auto-leveling toward the shared loudness target (apps only)
+    AutoLevel,
+    /// Fixed gain (±24 dB)
+    Gain { gain_db: f32 },
+    /// Master or app volume
+    Volume,
+    /// Soft clipper or lookahead limiter, whichever is selected (master only)
+    OutputStage,
+    /// The impulse response loaded for the master or app (skipped while none is)
+    Convolver,
+}
+
+impl ProcessorKind {
+    /// Display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProcessorKind::Equalizer => "Equalizer",
+            ProcessorKind::Crossfeed => "Crossfeed",
+            ProcessorKind::Compressor(_) => "Compressor",
+            ProcessorKind::MultibandCompressor(_) => "Multiband Compressor",
+            ProcessorKind::AutoLevel => "Auto Level",
+            ProcessorKind::Gain { .. } => "Gain",
+            ProcessorKind::Volume => "Volume",
+            ProcessorKind::OutputStage => "Output Stage",
+            ProcessorKind::Convolver => "Convolver",
+        }
+    }
+
+    /// Whether this kind can run in a chain at `scope`
+    pub fn supports(&self, scope: ChainScope) -> bool {
+        match self {
+            ProcessorKind::Crossfeed | ProcessorKind::OutputStage => scope == ChainScope::Master,
+            ProcessorKind::AutoLevel => scope == ChainScope::App,
+            _ => true,
+        }
+    }
+
+    /// Whether the kind is bound to engine state, so a chain holds it at most once
+    pub fn is_engine_bound(&self) -> bool {
+        !matches!(
+            self,
+            ProcessorKind::Compressor(_)
+                | ProcessorKind::MultibandCompressor(_)
+                | ProcessorKind::Gain { .. }
+        )
+    }
+
+    /// Check the parameters are in range
+    pub fn validate(&self) -> Result<(), DspError> {
+        match self {
+            ProcessorKind::Compressor(settings) => settings.validate(),
+            ProcessorKind::MultibandCompressor(settings) => settings.validate(),
+            ProcessorKind::Gain { gain_db } if !(-MAX_GAIN_DB..=MAX_GAIN_DB).contains(gain_db) => {
+                Err(DspError::InvalidParameter {
+                    name: "gain",
+                    value: *gain_db,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Build a processor that needs nothing from the engine
+    ///
+    /// `None` for engine-bound kinds, which the backend builds. Allocates.
+    pub fn build(
+        &self,
+        context: &ProcessContext,
+    ) -> Option<Result<Box<dyn AudioProcessor>, DspError>> {
+        match *self {
+            ProcessorKind::Compressor(settings) => Some(
+                Compressor::new(context.sample_rate, settings)
+                    .map(|compressor| Box::new(compressor) as Box<dyn AudioProcessor>),
+            ),
+            ProcessorKind::MultibandCompressor(settings) => Some(
+                MultibandCompressor::new(context.sample_rate, settings)
+                    .map(|multiband| Box::new(multiband) as Box<dyn AudioProcessor>),
+            ),
+            ProcessorKind::Gain { gain_db } => Some(self.validate().map(|()| {
+                Box::new(Gain {
+                    gain: 10.0_f32.powf(gain_db / 20.0),
+                }) as Box<dyn AudioProcessor>
+            })),
+            _ => None,
+        }
+    }
+}
+
+/// A processor's place in a chain
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProcessorSlot {
+    pub kind: ProcessorKind,
+    /// Skipped while set; the processor keeps its place and settings
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bypassed: bool,
+}
+
+impl ProcessorSlot {
+    pub fn new(kind: ProcessorKind) -> Self {
+        Self {
+            kind,
+            bypassed: false,
+        }
+    }
+}
+
+/// Ordered list of processors for the master output or one app
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChainConfig {
+    pub processors: Vec<ProcessorSlot>,
+}
+
+impl ChainConfig {
+    /// EQ → crossfeed → volume → output stage, the fixed master path before chains
+    pub fn master_default() -> Self {
+        Self::from_kinds(&[
+            ProcessorKind::Equalizer,
+            ProcessorKind::Crossfeed,
+            ProcessorKind::Volume,
+            ProcessorKind::OutputStage,
+        ])
+    }
+
+    /// EQ → auto-level → volume, the fixed per-app path before chains
+    pub fn app_default() -> Self {
+        Self::from_kinds(&[
+            ProcessorKind::Equalizer,
+            ProcessorKind::AutoLevel,
+            ProcessorKind::Volume,
+        ])
+    }
+
+    /// Default chain for `scope`
+    pub fn default_for(scope: ChainScope) -> Self {
+        match scope {
+            ChainScope::Master => Self::master_default(),
+            ChainScope::App => Self::app_default(),
+        }
+    }
+
+    fn from_kinds(kinds: &[ProcessorKind]) -> Self {
+        Self {
+            processors: kinds.iter().copied().map(ProcessorSlot::new).collect(),
+        }
+    }
+
+    /// Check the chain can run at `scope`
+    ///
+    /// At most [`MAX_CHAIN_LENGTH`] processors, each supported at `scope`
+    /// with valid parameters, and each engine-bound kind at most once.
+    pub fn validate(&self, scope: ChainScope) -> Result<(), DspError> {
+        if self.processors.len() > MAX_CHAIN_LENGTH {
+            return Err(DspError::InvalidChain(format!(
+                "{} processors (at most {})",
+                self.processors.len(),
+                MAX_CHAIN_LENGTH
+            )));
+        }
+        for (i, slot) in self.processors.iter().enumerate() {
+            if !slot.kind.supports(scope) {
+                return Err(DspError::InvalidChain(format!(
+                    "{} can't run in a {:?} chain",
+                    slot.kind.name(),
+                    scope
+                )));
+            }
+            slot.kind.validate()?;
+            let repeated = self.processors[..i].iter().any(|earlier| {
+                std::mem::discriminant(&earlier.kind) == std::mem::discriminant(&slot.kind)
+            });
+            if repeated && slot.kind.is_engine_bound() {
+                return Err(DspError::InvalidChain(format!(
+                    "{} appears twice",
+                    slot.kind.name()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Insert a processor at `index` (the end when `index` is the length)
+    pub fn insert(&mut self, index: usize, kind: ProcessorKind) -> Result<(), DspError> {
+        if index > self.processors.len() {
+            return Err(invalid_index(index));
+        }
+        self.processors.insert(index, ProcessorSlot::new(kind));
+        Ok(())
+    }
+
+    /// Remove and return the processor at `index`
+    pub fn remove(&mut self, index: usize) -> Result<ProcessorSlot, DspError> {
+        if index >= self.processors.len() {
+            return Err(invalid_index(index));
+        }
+        Ok(self.processors.remove(index))
+    }
+
+    /// Move the processor at `from` so it ends up at `to`
+    pub fn move_processor(&mut self, from: usize, to: usize) -> Result<(), DspError> {
+        let len = self.processors.len();
+        if from >= len || to >= len {
+            return Err(invalid_index(from.max(to)));
+        }
+        let slot = self.processors.remove(from);
+        self.processors.insert(to, slot);
+        Ok(())
+    }
+
+    /// Bypass or re-enable the processor at `index`
+    pub fn set_bypassed(&mut self, index: usize, bypassed: bool) -> Result<(), DspError> {
+        let slot = self
+            .processors
+            .get_mut(index)
+            .ok_or_else(|| invalid_index(index))?;
+        slot.bypassed = bypassed;
+        Ok(())
+    }
+
+    /// Position of the first processor of `kind`'s variant, if any
+    pub fn position(&self, kind: &ProcessorKind) -> Option<usize> {
+        self.processors
+            .iter()
+            .position(|slot| std::mem::discriminant(&slot.kind) == std::mem::discriminant(kind))
+    }
+}
+
+fn invalid_index(index: usize) -> DspError {
+    DspError::InvalidChain(format!("no processor at position {}", index))
+}
+
+/// Fixed gain stage
+struct Gain {
+    gain: f32,
+}
+
+impl AudioProcessor for Gain {
+    fn process(&mut self, buffer: &mut [f32], _context: &ProcessContext) {
+        for sample in buffer.iter_mut() {
+            *sample *= self.gain;
+        }
+    }
+
+    fn reset(&mut self) {}
+
+    fn name(&self) -> &'static str {
+        "Gain"
+    }
+}
+
+/// Create the two ends of a chain swap: `initial` runs until the first send
+///
+/// `max_buffer_samples` is the longest buffer the audio thread will process
+/// in one call without splitting a crossfade (longer buffers still work).
+/// Allocates.
+pub fn chain_channel(
+    initial: ProcessorChain,
+    max_buffer_samples: usize,
+) -> (ChainSender, ChainReceiver) {
+    let (pending_tx, pending_rx) = rtrb::RingBuffer::new(PENDING_CHAINS);
+    // Every chain sent retires at most one, and the sender empties this ring
+    // before each send, so it never fills
+    let (retired_tx, retired_rx) = rtrb::RingBuffer::new(PENDING_CHAINS + 1);
+    let fade_frames = fade_frames(initial.context());
+    let sender = ChainSender {
+        pending: pending_tx,
+        retired: retired_rx,
+    };
+    let receiver = ChainReceiver {
+        current: initial,
+        previous: None,
+        pending: pending_rx,
+        retired: retired_tx,
+        fade_pos: 0,
+        fade_frames,
+        scratch: vec![0.0; max_buffer_samples.max(1)],
+    };
+    (sender, receiver)
+}
+
+fn fade_frames(context: &ProcessContext) -> usize {
+    ((CHAIN_FADE_MS * 0.001 * context.sample_rate) as usize).max(1)
+}
+
+/// Control-thread end of a chain swap
+pub struct ChainSender {
+    pending: rtrb::Producer<ProcessorChain>,
+    retired: rtrb::Consumer<ProcessorChain>,
+}
+
+impl ChainSender {
+    /// Queue `chain` to replace the running one
+    ///
+    /// Also drops chains the audio thread has finished with. Gives the chain
+    /// back if [`PENDING_CHAINS`] are already waiting (the audio thread has
+    /// stalled).
+    pub fn send(&mut self, chain: ProcessorChain) -> Result<(), ProcessorChain> {
+        self.collect();
+        self.pending
+            .push(chain)
+            .map_err(|rtrb::PushError::Full(chain)| chain)
+    }
+
+    /// Drop chains the audio thread has swapped out
+    pub fn collect(&mut self) {
+        while self.retired.pop().is_ok() {}
+    }
+}
+
+/// Audio-thread end of a chain swap: runs the current chain
+pub struct ChainReceiver {
+    current: ProcessorChain,
+    /// Chain being faded out after a swap
+    previous: Option<ProcessorChain>,
+    pending: rtrb::Consumer<ProcessorChain>,
+    retired: rtrb::Producer<ProcessorChain>,
+    fade_pos: usize,
+    fade_frames: usize,
+    /// The old chain's output during a crossfade
+    scratch: Vec<f32>,
+}
+
+impl ChainReceiver {
+    /// Run `buffer` through the chain, picking up a newly sent one
+    ///
+    /// # Real-time Safety
+    /// No allocations, frees or locks. During a crossfade both chains run.
+    pub fn process(&mut self, buffer: &mut [f32]) {
+        if self.previous.is_none() && self.retired.slots() > 0 {
+            if let Ok(next) = self.pending.pop() {
+                self.fade_frames = fade_frames(next.context());
+                self.previous = Some(std::mem::replace(&mut self.current, next));
+                self.fade_pos = 0;
+            }
+        }
+        let Some(previous) = self.previous.as_mut() else {
+            self.current.process(buffer);
+            return;
+        };
+
+        let channels = self.current.context().channels.max(1);
+        let chunk_len = (self.scratch.len() / channels).max(1) * channels;
+        for chunk in buffer.chunks_mut(chunk_len) {
+            let old = &mut self.scratch[..chunk.len()];
+            old.copy_from_slice(chunk);
+            previous.process(old);
+            self.current.process(chunk);
+            for (new_frame, old_frame) in chunk.chunks_mut(channels).zip(old.chunks(channels)) {
+                let t = (self.fade_pos as f32 / self.fade_frames as f32).min(1.0);
+                for (new, old) in new_frame.iter_mut().zip(old_frame) {
+                    *new = old + (*new - old) * t;
+                }
+                self.fade_pos += 1;
+            }
+        }
+        if self.fade_pos >= self.fade_frames {
+            if let Some(previous) = self.previous.take() {
+                // Room was checked before the swap; the sender drops it
+                let _ = self.retired.push(previous);
+            }
+        }
+    }
+
+    /// Replace the chain at once, dropping any queued or fading chain
+    ///
+    /// For rebuilding outside the audio callback (e.g. on a sample rate
+    /// change); allocates and frees.
+    pub fn replace(&mut self, chain: ProcessorChain) {
+        while self.pending.pop().is_ok() {}
+        self.previous = None;
+        self.fade_frames = fade_frames(chain.context());
+        self.current = chain;
+    }
+
+    /// The running chain
+    pub fn chain(&self) -> &ProcessorChain {
+        &self.current
+    }
+
+    /// Whether a crossfade to a new chain is in progress
+    pub fn is_fading(&self) -> bool {
+        self.previous.is_some()
+    }
+
+    /// Delay in samples added by the running chain
+    pub fn latency_samples(&self) -> usize {
+        self.current.latency_samples()
+    }
+
+    /// Reset the running chain's processors
+    pub fn reset(&mut self) {
+        self.current.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stereo chain of fixed gains
+    fn gain_chain(gains_db: &[f32]) -> ProcessorChain {
+        let mut chain = ProcessorChain::new(1000.0, 2, 64);
+        for &gain_db in gains_db {
+            let kind = ProcessorKind::Gain { gain_db };
+            chain.add_boxed(kind.build(chain.context()).unwrap().unwrap());
+        }
+        chain
+    }
+
+    #[test]
+    fn test_default_chains_are_valid() {
+        assert!(ChainConfig::master_default()
+            .validate(ChainScope::Master)
+            .is_ok());
+        assert!(ChainConfig::app_default().validate(ChainScope::App).is_ok());
+        // Master-only and app-only stages are refused elsewhere
+        assert!(ChainConfig::master_default()
+            .validate(ChainScope::App)
+            .is_err());
+        assert!(ChainConfig::app_default()
+            .validate(ChainScope::Master)
+            .is_err());
+    }
+
+    #[test]
+    fn test_edit_chain() {
+        let mut chain = ChainConfig::master_default();
+        let compressor = ProcessorKind::Compressor(CompressorSettings::default());
+        chain.insert(1, compressor).unwrap();
+        chain
+            .insert(5, ProcessorKind::Gain { gain_db: -3.0 })
+            .unwrap();
+        assert!(chain.validate(ChainScope::Master).is_ok());
+        assert_eq!(chain.position(&compressor), Some(1));
+
+        // Volume before the compressor
+        chain.move_processor(3, 1).unwrap();
+        let kinds: Vec<_> = chain
+            .processors
+            .iter()
+            .map(|slot| slot.kind.name())
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "Equalizer",
+                "Volume",
+                "Compressor",
+                "Crossfeed",
+                "Output Stage",
+                "Gain"
+            ]
+        );
+
+        chain.set_bypassed(2, true).unwrap();
+        assert!(chain.processors[2].bypassed);
+        assert_eq!(
+            chain.remove(5).unwrap().kind,
+            ProcessorKind::Gain { gain_db: -3.0 }
+        );
+
+        assert!(chain.insert(7, ProcessorKind::Volume).is_err());
+        assert!(chain.remove(5).is_err());
+        assert!(chain.move_processor(0, 5).is_err());
+        assert!(chain.set_bypassed(9, true).is_err());
+    }
+
+    #[test]
+    fn test_invalid_chains() {
+        let mut twice = ChainConfig::app_default();
+        twice.insert(0, ProcessorKind::Equalizer).unwrap();
+        assert!(twice.validate(ChainScope::App).is_err());
+
+        // Standalone processors may repeat, within their ranges
+        let mut gains = ChainConfig {
+            processors: Vec::new(),
+        };
+        gains
+            .insert(0, ProcessorKind::Gain { gain_db: 6.0 })
+            .unwrap();
+        gains
+            .insert(0, ProcessorKind::Gain { gain_db: 6.0 })
+            .unwrap();
+        assert!(gains.validate(ChainScope::App).is_ok());
+        gains
+            .insert(0, ProcessorKind::Gain { gain_db: 30.0 })
+            .unwrap();
+        assert!(gains.validate(ChainScope::App).is_err());
+
+        let long = ChainConfig {
+            processors: vec![
+                ProcessorSlot::new(ProcessorKind::Gain { gain_db: 0.0 });
+                MAX_CHAIN_LENGTH + 1
+            ],
+        };
+        assert!(long.validate(ChainScope::Master).is_err());
+    }
+
+    #[test]
+    fn test_engine_bound_kinds_are_not_built() {
+        let context = ProcessContext::new(48000.0, 2, 512);
+        assert!(ProcessorKind::Equalizer.build(&context).is_none());
+        assert!(ProcessorKind::Convolver.is_engine_bound());
+        assert!(ProcessorKind::Convolver.build(&context).is_none());
+        assert!(ProcessorKind::Compressor(CompressorSettings::default())
+            .build(&context)
+            .unwrap()
+            .is_ok());
+        let multiband = ProcessorKind::MultibandCompressor(MultibandSettings::default());
+        assert!(!multiband.is_engine_bound());
+        assert!(multiband.build(&context).unwrap().is_ok());
+        assert!(ProcessorKind::Gain { gain_db: 40.0 }
+            .build(&context)
+            .unwrap()
+            .is_err());
+    }
+
+    #[test]
+    fn test_swap_crossfades_to_new_chain() {
+        let (mut sender, mut receiver) = chain_channel(gain_chain(&[]), 64);
+        let fade = (CHAIN_FADE_MS * 0.001 * 1000.0) as usize;
+
+        // Unity until the swap, then a smooth move to -6 dB
+        let mut buffer = vec![1.0; 2 * fade];
+        receiver.process(&mut buffer);
+        assert!(buffer.iter().all(|&x| x == 1.0));
+        assert!(sender.send(gain_chain(&[-6.0])).is_ok());
+
+        let mut buffer = vec![1.0; 2 * 3 * fade];
+        receiver.process(&mut buffer);
+        let target = 10.0_f32.powf(-6.0 / 20.0);
+        let left: Vec<f32> = buffer.iter().step_by(2).copied().collect();
+        assert_eq!(left[0], 1.0);
+        assert!(left.windows(2).all(|pair| pair[1] <= pair[0]));
+        assert!(left
+            .windows(2)
+            .all(|pair| pair[0] - pair[1] < 2.0 / fade as f32));
+        assert!(left[fade..].iter().all(|&x| (x - target).abs() < 1e-6));
+        assert!(!receiver.is_fading());
+
+        // The old chain comes back to be dropped here, not on the audio thread
+        assert_eq!(receiver.chain().len(), 1);
+        assert_eq!(sender.retired.slots(), 1);
+        sender.collect();
+        assert_eq!(sender.retired.slots(), 0);
+    }
+
+    #[test]
+    fn test_sender_refuses_when_audio_thread_stalls() {
+        let (mut sender, mut receiver) = chain_channel(gain_chain(&[]), 64);
+        for _ in 0..PENDING_CHAINS {
+            assert!(sender.send(gain_chain(&[1.0])).is_ok());
+        }
+        assert!(sender.send(gain_chain(&[1.0])).is_err());
+
+        // Replacing outright clears the queue
+        receiver.replace(gain_chain(&[2.0, 2.0]));
+        assert_eq!(receiver.chain().len(), 2);
+        assert!(sender.send(gain_chain(&[1.0])).is_ok());
+    }
+}
@@ -0,0 +1,474 @@
+//! Feed-Forward Compressor
+//!
+//! Reduces the dynamic range of a signal: levels above the threshold are
+//! scaled down by the ratio, so quiet voices in a chat get closer to loud
+//! ones and movie explosions don't wake the neighbours.
+//!
+//! # Algorithm
+//!
+//! ```text
+//! input ─┬──────────────────────────────────────────► × ──► output
+//!        └─► detector ─► gain computer ─► smoothing ─┘
+//!            (peak/RMS)   (dB, soft knee)  (attack/release)
+//! ```
+//!
+//! - Detection is linked across channels (the loudest channel drives all),
+//!   so the stereo image doesn't shift under compression.
+//! - The gain computer works in dB with a quadratic soft knee.
+//! - Gain reduction is smoothed with separate attack and release times.
+//!
+//! Gain reduction is published through a [`CompressorMeter`] that the UI
+//! thread can read without locks.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use crate::error::DspError;
+use crate::processor::{AudioProcessor, ProcessContext};
+
+/// Averaging window of the RMS detector
+const RMS_WINDOW_MS: f32 = 10.0;
+
+/// Level detector used to drive the gain computer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DetectionMode {
+    /// Instantaneous sample peak: reacts to every transient
+    Peak,
+    /// Average power over ~10 ms: follows perceived loudness more closely
+    #[default]
+    Rms,
+}
+
+/// Compressor parameters
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompressorSettings {
+    /// Level where compression starts (-60 to 0 dBFS)
+    pub threshold_db: f32,
+    /// Input dB above threshold per output dB (1 to 100; 1 = no compression)
+    pub ratio: f32,
+    /// Width of the soft knee centred on the threshold (0 to 24 dB)
+    pub knee_db: f32,
+    /// Time to reach ~63% of a gain reduction increase (0.1 to 500 ms)
+    pub attack_ms: f32,
+    /// Time to recover ~63% of a gain reduction decrease (5 to 5000 ms)
+    pub release_ms: f32,
+    /// Gain applied after compression (-24 to 24 dB)
+    pub makeup_gain_db: f32,
+    pub detection: DetectionMode,
+    pub enabled: bool,
+}
+
+impl Default for CompressorSettings {
+    fn default() -> Self {
+        Self {
+            threshold_db: -18.0,
+            ratio: 4.0,
+            knee_db: 6.0,
+            attack_ms: 10.0,
+            release_ms: 150.0,
+            makeup_gain_db: 0.0,
+            detection: DetectionMode::Rms,
+            enabled: true,
+        }
+    }
+}
+
+impl CompressorSettings {
+    /// Check every parameter is in range
+    pub fn validate(&self) -> Result<(), DspError> {
+        let checks = [
+            ("threshold_db", self.threshold_db, -60.0, 0.0),
+            ("ratio", self.ratio, 1.0, 100.0),
+            ("knee_db", self.knee_db, 0.0, 24.0),
+            ("attack_ms", self.attack_ms, 0.1, 500.0),
+            ("release_ms", self.release_ms, 5.0, 5000.0),
+            ("makeup_gain_db", self.makeup_gain_db, -24.0, 24.0),
+        ];
+        for (name, value, min, max) in checks {
+            // Rust pattern: `contains` on a range is false for NaN too
+            if !(min..=max).contains(&value) {
+                return Err(DspError::InvalidParameter { name, value });
+            }
+        }
+        Ok(())
+    }
+
+    /// Static gain reduction (dB, positive) for a detector level in dB
+    ///
+    /// Below the knee: none. Inside it: a quadratic blend. Above it: the
+    /// excess over threshold shrinks by the ratio.
+    #[inline]
+    fn gain_reduction_db(&self, level_db: f32) -> f32 {
+        let over = level_db - self.threshold_db;
+        let slope = 1.0 - 1.0 / self.ratio;
+        if 2.0 * over <= -self.knee_db {
+            0.0
+        } else if 2.0 * over < self.knee_db {
+            let x = over + self.knee_db / 2.0;
+            slope * x * x / (2.0 * self.knee_db)
+        } else {
+            slope * over
+        }
+    }
+}
+
+/// Lock-free gain reduction readout shared between audio and UI threads
+///
+/// Cheap to clone; all clones read the same compressor.
+#[derive(Debug, Clone, Default)]
+pub struct CompressorMeter {
+    /// Largest gain reduction (dB, >= 0) since the last `take`, as f32 bits
+    max_bits: Arc<AtomicU32>,
+    /// Gain reduction at the end of the last processed buffer, as f32 bits
+    current_bits: Arc<AtomicU32>,
+}
+
+impl CompressorMeter {
+    /// Gain reduction (dB, positive) at the end of the last buffer
+    pub fn gain_reduction_db(&self) -> f32 {
+        f32::from_bits(self.current_bits.load(Ordering::Relaxed))
+    }
+
+    /// Largest gain reduction (dB) since the previous call, then reset
+    ///
+    /// Poll at the UI refresh rate so short peaks between polls still show.
+    pub fn take_max_gain_reduction_db(&self) -> f32 {
+        f32::from_bits(self.max_bits.swap(0, Ordering::Relaxed))
+    }
+
+    fn publish(&self, current_db: f32, max_db: f32) {
+        self.current_bits.store(current_db.to_bits(), Ordering::Relaxed);
+        // Non-negative f32s order the same as their bit patterns,
+        // so an integer max is a float max here
+        self.max_bits.fetch_max(max_db.max(0.0).to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// Gain reduction of one chain's compressors since the previous reading
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GainReduction {
+    /// Largest reduction (dB, positive) of any compressor or band
+    pub db: f32,
+    /// Reduction (dB) of each multiband band, lowest first; empty without one
+    pub bands: Vec<f32>,
+}
+
+impl GainReduction {
+    /// Read and reset the meters of a chain's stages (one meter per band)
+    ///
+    /// `None` when there are no stages (no compressor to report on).
+    pub fn take(stages: &[Vec<CompressorMeter>]) -> Option<Self> {
+        if stages.is_empty() {
+            return None;
+        }
+        let mut reduction = Self::default();
+        for meters in stages {
+            let readings = meters.iter().map(CompressorMeter::take_max_gain_reduction_db);
+            if meters.len() > 1 {
+                reduction.bands.extend(readings);
+            } else {
+                reduction.db = readings.fold(reduction.db, f32::max);
+            }
+        }
+        reduction.db = reduction.bands.iter().copied().fold(reduction.db, f32::max);
+        Some(reduction)
+    }
+}
+
+/// Feed-forward dynamic range compressor
+pub struct Compressor {
+    settings: CompressorSettings,
+    sample_rate: f32,
+    // One-pole smoothing coefficients derived from the settings
+    attack_coeff: f32,
+    release_coeff: f32,
+    rms_coeff: f32,
+    makeup_linear: f32,
+    // Detector and gain reduction state
+    mean_square: f32,
+    envelope_db: f32,
+    meter: CompressorMeter,
+}
+
+impl Compressor {
+    /// Create a compressor at `sample_rate`
+    pub fn new(sample_rate: f32, settings: CompressorSettings) -> Result<Self, DspError> {
+        if sample_rate <= 0.0 {
+            return Err(DspError::InvalidSampleRate(sample_rate));
+        }
+        settings.validate()?;
+
+        let mut compressor = Self {
+            settings,
+            sample_rate,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            rms_coeff: 0.0,
+            makeup_linear: 1.0,
+            mean_square: 0.0,
+            envelope_db: 0.0,
+            meter: CompressorMeter::default(),
+        };
+        compressor.update_coefficients();
+        Ok(compressor)
+    }
+
+    /// Replace all parameters
+    ///
+    /// Detector and envelope state carry over, so threshold or ratio changes
+    /// glide in at the attack/release speed instead of jumping.
+    pub fn set_settings(&mut self, settings: CompressorSettings) -> Result<(), DspError> {
+        settings.validate()?;
+        self.settings = settings;
+        self.update_coefficients();
+        Ok(())
+    }
+
+    /// Current parameters
+    pub fn settings(&self) -> &CompressorSettings {
+        &self.settings
+    }
+
+    /// Enable or disable (disabled passes audio untouched)
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.settings.enabled = enabled;
+    }
+
+    /// Change the sample rate (recomputes time constants)
+    pub fn set_sample_rate(&mut self, sample_rate: f32) -> Result<(), DspError> {
+        if sample_rate <= 0.0 {
+            return Err(DspError::InvalidSampleRate(sample_rate));
+        }
+        self.sample_rate = sample_rate;
+        self.update_coefficients();
+        Ok(())
+    }
+
+    /// Handle for reading gain reduction from another thread
+    pub fn meter(&self) -> CompressorMeter {
+        self.meter.clone()
+    }
+
+    /// Gain reduction (dB, positive) currently applied
+    pub fn gain_reduction_db(&self) -> f32 {
+        self.envelope_db
+    }
+
+    fn update_coefficients(&mut self) {
+        let coeff = |ms: f32| (-1000.0 / (ms * self.sample_rate)).exp();
+        self.attack_coeff = coeff(self.settings.attack_ms);
+        self.release_coeff = coeff(self.settings.release_ms);
+        self.rms_coeff = coeff(RMS_WINDOW_MS);
+        self.makeup_linear = 10.0_f32.powf(self.settings.makeup_gain_db / 20.0);
+    }
+
+    /// Process an interleaved buffer of `channels` channels in-place
+    ///
+    /// A trailing partial frame is left untouched.
+    ///
+    /// # Real-time Safety
+    /// No allocations, no locks. O(n) where n = buffer length.
+    pub fn process_interleaved(&mut self, buffer: &mut [f32], channels: usize) {
+        if !self.settings.enabled || channels == 0 {
+            return;
+        }
+
+        let mut max_reduction = 0.0_f32;
+        for frame in buffer.chunks_exact_mut(channels) {
+            // Linked detection: the loudest channel drives every channel
+            let peak_square = frame.iter().fold(0.0_f32, |acc, &s| acc.max(s * s));
+            let detector_square = match self.settings.detection {
+                DetectionMode::Peak => peak_square,
+                DetectionMode::Rms => {
+                    self.mean_square = peak_square + self.rms_coeff * (self.mean_square - peak_square);
+                    self.mean_square
+                }
+            };
+            // 10 log10 of power = 20 log10 of amplitude; floor avoids -inf
+            let level_db = 10.0 * detector_square.max(1e-12).log10();
+            let target = self.settings.gain_reduction_db(level_db);
+
+            let coeff = if target > self.envelope_db {
+                self.attack_coeff
+            } else {
+                self.release_coeff
+            };
+            self.envelope_db = target + coeff * (self.envelope_db - target);
+            max_reduction = max_reduction.max(self.envelope_db);
+
+            let gain = 10.0_f32.powf(-self.envelope_db / 20.0) * self.makeup_linear;
+            for sample in frame.iter_mut() {
+                *sample *= gain;
+            }
+        }
+
+        self.meter.publish(self.envelope_db, max_reduction);
+    }
+}
+
+impl AudioProcessor for Compressor {
+    fn process(&mut self, buffer: &mut [f32], context: &ProcessContext) {
+        if context.sample_rate != self.sample_rate {
+            let _ = self.set_sample_rate(context.sample_rate);
+        }
+        self.process_interleaved(buffer, context.channels);
+    }
+
+    fn reset(&mut self) {
+        self.mean_square = 0.0;
+        self.envelope_db = 0.0;
+        self.meter.publish(0.0, 0.0);
+    }
+
+    fn name(&self) -> &'static str {
+        "Compressor"
+    }
+
+    fn gain_reduction_meters(&self) -> Vec<CompressorMeter> {
+        vec![self.meter()]
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.settings.enabled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hard_knee(threshold_db: f32, ratio: f32) -> CompressorSettings {
+        CompressorSettings {
+            threshold_db,
+            ratio,
+            knee_db: 0.0,
+            attack_ms: 1.0,
+            release_ms: 50.0,
+            detection: DetectionMode::Peak,
+            ..CompressorSettings::default()
+        }
+    }
+
+    /// Steady-state output level (dBFS) of a constant-level stereo signal
+    fn output_db(compressor: &mut Compressor, level: f32) -> f32 {
+        let mut buffer = vec![level; 2 * 48000];
+        compressor.process_interleaved(&mut buffer, 2);
+        20.0 * buffer.last().unwrap().abs().log10()
+    }
+
+    #[test]
+    fn test_below_threshold_is_unity() {
+        let mut compressor = Compressor::new(48000.0, hard_knee(-18.0, 4.0)).unwrap();
+        let level = 10.0_f32.powf(-24.0 / 20.0);
+        assert!((output_db(&mut compressor, level) + 24.0).abs() < 1e-3);
+        assert_eq!(compressor.gain_reduction_db(), 0.0);
+    }
+
+    #[test]
+    fn test_ratio_above_threshold() {
+        // -6 dBFS is 12 dB over a -18 dB threshold; 4:1 leaves 3 dB over
+        let mut compressor = Compressor::new(48000.0, hard_knee(-18.0, 4.0)).unwrap();
+        let level = 10.0_f32.powf(-6.0 / 20.0);
+        let out = output_db(&mut compressor, level);
+        assert!((out + 15.0).abs() < 0.01, "Expected -15 dBFS, got {}", out);
+        assert!((compressor.gain_reduction_db() - 9.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_soft_knee() {
+        let settings = CompressorSettings {
+            knee_db: 12.0,
+            ..hard_knee(-18.0, 4.0)
+        };
+        // Continuous at both edges, and at the threshold half the knee's worth
+        let edge = settings.gain_reduction_db(-12.0);
+        assert!((edge - 0.75 * 6.0).abs() < 1e-4);
+        assert_eq!(settings.gain_reduction_db(-24.0), 0.0);
+        let at_threshold = settings.gain_reduction_db(-18.0);
+        assert!((at_threshold - 0.75 * 12.0 / 8.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_attack_and_release_times() {
+        let settings = CompressorSettings {
+            attack_ms: 10.0,
+            release_ms: 100.0,
+            ..hard_knee(-20.0, 100.0)
+        };
+        let mut compressor = Compressor::new(48000.0, settings).unwrap();
+
+        // A 0 dBFS step wants ~19.8 dB of reduction; after one attack time ~63% of it
+        let mut loud = vec![1.0; 480];
+        compressor.process_interleaved(&mut loud, 1);
+        let target = settings.gain_reduction_db(0.0);
+        let ratio = compressor.gain_reduction_db() / target;
+        assert!((ratio - 0.632).abs() < 0.01, "Attack reached {}", ratio);
+
+        // Settle, then drop to silence: ~37% left after one release time
+        let mut loud = vec![1.0; 48000];
+        compressor.process_interleaved(&mut loud, 1);
+        let mut quiet = vec![0.0; 4800];
+        compressor.process_interleaved(&mut quiet, 1);
+        let ratio = compressor.gain_reduction_db() / target;
+        assert!((ratio - 0.368).abs() < 0.01, "Release left {}", ratio);
+    }
+
+    #[test]
+    fn test_rms_detection_ignores_short_peaks() {
+        let settings = CompressorSettings {
+            detection: DetectionMode::Rms,
+            ..hard_knee(-12.0, 10.0)
+        };
+        let mut rms = Compressor::new(48000.0, settings).unwrap();
+        let mut peak = Compressor::new(48000.0, hard_knee(-12.0, 10.0)).unwrap();
+
+        // A 0.5 ms full-scale click: loud to a peak detector, but it barely
+        // moves the 10 ms power average
+        let mut click = vec![0.0; 480];
+        click[..24].fill(1.0);
+        rms.process_interleaved(&mut click.clone(), 1);
+        peak.process_interleaved(&mut click, 1);
+        assert!(rms.meter().take_max_gain_reduction_db() < 1.0);
+        assert!(peak.meter().take_max_gain_reduction_db() > 1.0);
+    }
+
+    #[test]
+    fn test_meter_and_makeup() {
+        let settings = CompressorSettings {
+            makeup_gain_db: 6.0,
+            ..hard_knee(-18.0, 4.0)
+        };
+        let mut compressor = Compressor::new(48000.0, settings).unwrap();
+        let meter = compressor.meter();
+
+        let level = 10.0_f32.powf(-6.0 / 20.0);
+        let out = output_db(&mut compressor, level);
+        assert!((out + 9.0).abs() < 0.01, "Expected -9 dBFS, got {}", out);
+        assert!((meter.gain_reduction_db() - 9.0).abs() < 0.01);
+
+        // Max holds until read, then resets
+        assert!((meter.take_max_gain_reduction_db() - 9.0).abs() < 0.01);
+        assert_eq!(meter.take_max_gain_reduction_db(), 0.0);
+    }
+
+    #[test]
+    fn test_disabled_and_invalid() {
+        let mut compressor = Compressor::new(48000.0, hard_knee(-30.0, 8.0)).unwrap();
+        compressor.set_enabled(false);
+        let mut buffer = vec![0.9, -0.9];
+        compressor.process_interleaved(&mut buffer, 2);
+        assert_eq!(buffer, vec![0.9, -0.9]);
+
+        for invalid in [
+            CompressorSettings { ratio: 0.5, ..Default::default() },
+            CompressorSettings { attack_ms: 0.0, ..Default::default() },
+            CompressorSettings { threshold_db: f32::NAN, ..Default::default() },
+        ] {
+            assert!(compressor.set_settings(invalid).is_err());
+        }
+        assert!(Compressor::new(0.0, CompressorSettings::default()).is_err());
+    }
+}
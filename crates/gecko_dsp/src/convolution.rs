@@ -0,0 +1,333 @@
+//! Uniformly Partitioned Convolution
+//!
+//! Shared engine behind the linear-phase EQ and the impulse response
+//! [`Convolver`](crate::Convolver). The kernel is split into equal
+//! partitions and run with overlap-save: each input block is transformed
+//! once, kept in a frequency-domain delay line and multiplied with every
+//! partition's spectrum. Cost per sample stays flat no matter how long the
+//! kernel is.
+//!
+//! ```text
+//! input ──► [block buffer] ──► FFT ──► delay line ─┬─ × H0 ─┐
+//!                                                  ├─ × H1 ─┼─► IFFT ──► output
+//!                                                  └─ × Hn ─┘
+//! ```
+//!
+//! Output lags input by one block. Kernels are double-buffered: a new one is
+//! written next to the playing one and can be crossfaded in over one block.
+
+use std::sync::Arc;
+
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+
+use crate::eq::MAX_CHANNELS;
+
+/// Partitioned FFT convolver for up to [`MAX_CHANNELS`] interleaved channels
+///
+/// Each channel uses kernel `channel % kernel_channels`, so one mono kernel
+/// serves every channel and a stereo kernel alternates L/R across a
+/// surround layout. All buffers are allocated in `new`; nothing after that
+/// allocates.
+pub(crate) struct PartitionedConvolver {
+    block: usize,
+    partitions: usize,
+    channels: usize,
+    kernel_channels: usize,
+
+    // Partition spectra (block + 1 bins each) per kernel channel; two sets
+    // so a new kernel can be prepared while the old one plays
+    kernels: [Vec<Complex<f32>>; 2],
+    active: usize,
+    fading: bool,
+
+    // Streaming state per channel
+    input: Vec<f32>,
+    output: Vec<f32>,
+    delay_line: Vec<Complex<f32>>,
+    delay_pos: usize,
+    pos: usize,
+
+    // Block transforms and workspaces
+    forward: Arc<dyn Fft<f32>>,
+    inverse: Arc<dyn Fft<f32>>,
+    work: Vec<Complex<f32>>,
+    fade_work: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+}
+
+impl PartitionedConvolver {
+    /// Create a convolver for kernels of up to `taps` samples (allocates)
+    ///
+    /// `block` is the partition size and must be a power of two.
+    pub(crate) fn new(block: usize, taps: usize, kernel_channels: usize, channels: usize) -> Self {
+        let partitions = ((taps + block - 1) / block).max(1);
+        let kernel_channels = kernel_channels.max(1);
+        let span = partitions * (block + 1);
+
+        let mut planner = FftPlanner::new();
+        let forward = planner.plan_fft_forward(2 * block);
+        let inverse = planner.plan_fft_inverse(2 * block);
+        let scratch_len = forward
+            .get_inplace_scratch_len()
+            .max(inverse.get_inplace_scratch_len());
+
+        let zero = Complex::new(0.0, 0.0);
+        Self {
+            block,
+            partitions,
+            channels: channels.clamp(1, MAX_CHANNELS),
+            kernel_channels,
+            kernels: [vec![zero; kernel_channels * span], vec![zero; kernel_channels * span]],
+            active: 0,
+            fading: false,
+            input: vec![0.0; MAX_CHANNELS * 2 * block],
+            output: vec![0.0; MAX_CHANNELS * block],
+            delay_line: vec![zero; MAX_CHANNELS * span],
+            delay_pos: 0,
+            pos: 0,
+            forward,
+            inverse,
+            work: vec![zero; 2 * block],
+            fade_work: vec![zero; 2 * block],
+            scratch: vec![zero; scratch_len],
+        }
+    }
+
+    /// Partition size, which is also the delay through the convolver
+    pub(crate) fn block(&self) -> usize {
+        self.block
+    }
+
+    /// Longest kernel (in taps) this convolver holds
+    pub(crate) fn capacity(&self) -> usize {
+        self.partitions * self.block
+    }
+
+    /// Number of interleaved channels convolved
+    pub(crate) fn channel_count(&self) -> usize {
+        self.channels
+    }
+
+    /// Change the number of channels convolved (state is cleared)
+    pub(crate) fn set_channel_count(&mut self, channels: usize) {
+        self.channels = channels.clamp(1, MAX_CHANNELS);
+        self.reset();
+    }
+
+    /// Clear all delay lines
+    pub(crate) fn reset(&mut self) {
+        self.input.fill(0.0);
+        self.output.fill(0.0);
+        self.delay_line.fill(Complex::new(0.0, 0.0));
+        self.delay_pos = 0;
+        self.pos = 0;
+        self.fading = false;
+    }
+
+    /// Write one kernel channel into the pending set
+    ///
+    /// `tap(n)` is called for every n in `0..capacity()`; return 0 past the
+    /// end of the kernel. The pending set is heard after [`Self::swap_kernels`].
+    ///
+    /// # Real-time Safety
+    /// No allocations; one FFT per partition.
+    pub(crate) fn write_kernel(&mut self, kernel_channel: usize, mut tap: impl FnMut(usize) -> f32) {
+        let bins = self.block + 1;
+        let span = self.partitions * bins;
+        let offset = kernel_channel.min(self.kernel_channels - 1) * span;
+        // The inverse transform is unnormalised; fold its scaling in here
+        let scale = 1.0 / (2 * self.block) as f32;
+
+        for partition in 0..self.partitions {
+            for (i, bin) in self.work.iter_mut().enumerate() {
+                *bin = if i < self.block {
+                    Complex::new(tap(partition * self.block + i) * scale, 0.0)
+                } else {
+                    Complex::new(0.0, 0.0)
+                };
+            }
+            self.forward.process_with_scratch(&mut self.work, &mut self.scratch);
+            let start = offset + partition * bins;
+            self.kernels[1 - self.active][start..start + bins].copy_from_slice(&self.work[..bins]);
+        }
+    }
+
+    /// Make the pending kernels the playing ones
+    ///
+    /// With `crossfade` the next block fades from the old kernels to the new.
+    pub(crate) fn swap_kernels(&mut self, crossfade: bool) {
+        self.active = 1 - self.active;
+        self.fading = crossfade;
+    }
+
+    /// Push one interleaved frame and replace it with the delayed output
+    ///
+    /// Channels beyond the configured count pass through untouched. Returns
+    /// true when a block is complete; call [`Self::process_block`] before
+    /// pushing the next frame.
+    #[inline]
+    pub(crate) fn push_frame(&mut self, frame: &mut [f32]) -> bool {
+        let pos = self.pos;
+        for (channel, sample) in frame.iter_mut().take(self.channels).enumerate() {
+            self.input[channel * 2 * self.block + self.block + pos] = *sample;
+            *sample = self.output[channel * self.block + pos];
+        }
+
+        self.pos += 1;
+        if self.pos == self.block {
+            self.pos = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Convolve the block just collected into the output buffers
+    ///
+    /// # Real-time Safety
+    /// No allocations; two FFTs per channel (three while crossfading).
+    pub(crate) fn process_block(&mut self) {
+        let block = self.block;
+        let bins = block + 1;
+        let span = self.partitions * bins;
+
+        for channel in 0..self.channels {
+            // Transform the last two blocks (overlap-save) into the delay line
+            let input = &mut self.input[channel * 2 * block..(channel + 1) * 2 * block];
+            for (bin, &sample) in self.work.iter_mut().zip(input.iter()) {
+                *bin = Complex::new(sample, 0.0);
+            }
+            input.copy_within(block.., 0);
+            self.forward.process_with_scratch(&mut self.work, &mut self.scratch);
+
+            let delay_line = &mut self.delay_line[channel * span..(channel + 1) * span];
+            let slot = self.delay_pos * bins;
+            delay_line[slot..slot + bins].copy_from_slice(&self.work[..bins]);
+
+            let kernel = (channel % self.kernel_channels) * span;
+            let output = &mut self.output[channel * block..(channel + 1) * block];
+            accumulate(
+                &mut self.work,
+                delay_line,
+                &self.kernels[self.active][kernel..kernel + span],
+                self.delay_pos,
+                block,
+            );
+            self.inverse.process_with_scratch(&mut self.work, &mut self.scratch);
+
+            if self.fading {
+                accumulate(
+                    &mut self.fade_work,
+                    delay_line,
+                    &self.kernels[1 - self.active][kernel..kernel + span],
+                    self.delay_pos,
+                    block,
+                );
+                self.inverse.process_with_scratch(&mut self.fade_work, &mut self.scratch);
+
+                let step = 1.0 / block as f32;
+                for (i, sample) in output.iter_mut().enumerate() {
+                    let fade_in = (i as f32 + 0.5) * step;
+                    let new = self.work[block + i].re;
+                    let old = self.fade_work[block + i].re;
+                    *sample = old + (new - old) * fade_in;
+                }
+            } else {
+                for (sample, bin) in output.iter_mut().zip(&self.work[block..]) {
+                    *sample = bin.re;
+                }
+            }
+        }
+
+        self.fading = false;
+        self.delay_pos = (self.delay_pos + 1) % self.partitions;
+    }
+}
+
+/// Sum every delayed input spectrum times its partition into `out`
+///
+/// Only bins 0..=block are computed; the rest are filled in as complex
+/// conjugates since the time signal is real.
+fn accumulate(
+    out: &mut [Complex<f32>],
+    delay_line: &[Complex<f32>],
+    kernel: &[Complex<f32>],
+    newest: usize,
+    block: usize,
+) {
+    let bins = block + 1;
+    let partitions = kernel.len() / bins;
+    out[..bins].fill(Complex::new(0.0, 0.0));
+    for partition in 0..partitions {
+        // Partition 0 pairs with the newest block, 1 with the one before, ...
+        let slot = (newest + partitions - partition) % partitions;
+        let input = &delay_line[slot * bins..(slot + 1) * bins];
+        let coeffs = &kernel[partition * bins..(partition + 1) * bins];
+        for ((acc, x), h) in out[..bins].iter_mut().zip(input).zip(coeffs) {
+            *acc += x * h;
+        }
+    }
+    for k in 1..block {
+        out[2 * block - k] = out[k].conj();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run a mono signal through the convolver
+    fn run(convolver: &mut PartitionedConvolver, signal: &[f32]) -> Vec<f32> {
+        signal
+            .iter()
+            .map(|&x| {
+                let mut frame = [x];
+                if convolver.push_frame(&mut frame) {
+                    convolver.process_block();
+                }
+                frame[0]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_direct_convolution() {
+        let kernel: Vec<f32> = (0..100).map(|n| ((n * 7 % 13) as f32 - 6.0) / 10.0).collect();
+        let signal: Vec<f32> = (0..400).map(|n| ((n * 5 % 11) as f32 - 5.0) / 5.0).collect();
+
+        let mut convolver = PartitionedConvolver::new(32, kernel.len(), 1, 1);
+        convolver.write_kernel(0, |n| kernel.get(n).copied().unwrap_or(0.0));
+        convolver.swap_kernels(false);
+
+        let mut padded = signal.clone();
+        padded.extend(std::iter::repeat(0.0).take(32));
+        let output = run(&mut convolver, &padded);
+
+        for n in 0..signal.len() {
+            let expected: f32 = (0..=n.min(kernel.len() - 1)).map(|k| kernel[k] * signal[n - k]).sum();
+            let got = output[n + 32];
+            assert!((got - expected).abs() < 1e-4, "Sample {}: {} vs {}", n, got, expected);
+        }
+    }
+
+    #[test]
+    fn test_kernel_per_channel() {
+        // Stereo kernel: left passes, right is inverted and delayed by 3
+        let mut convolver = PartitionedConvolver::new(16, 16, 2, 2);
+        convolver.write_kernel(0, |n| if n == 0 { 1.0 } else { 0.0 });
+        convolver.write_kernel(1, |n| if n == 3 { -1.0 } else { 0.0 });
+        convolver.swap_kernels(false);
+
+        let mut outputs = Vec::new();
+        for n in 0..48 {
+            let mut frame = if n == 0 { [1.0, 1.0] } else { [0.0, 0.0] };
+            if convolver.push_frame(&mut frame) {
+                convolver.process_block();
+            }
+            outputs.push(frame);
+        }
+        assert!((outputs[16][0] - 1.0).abs() < 1e-5);
+        assert!((outputs[19][1] + 1.0).abs() < 1e-5);
+        assert!(outputs[16][1].abs() < 1e-5);
+    }
+}
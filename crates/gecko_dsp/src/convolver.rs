@@ -0,0 +1,383 @@
+//! Impulse Response Convolution
+//!
+//! Applies measured filters (headphone correction, room correction exported
+//! from REW, cabinet or reverb IRs) by convolving the signal with an impulse
+//! response loaded from a WAV file.
+//!
+//! # Usage
+//!
+//! Load the IR once with [`ImpulseResponse::from_wav_file`], then build one
+//! [`Convolver`] per processing path (master, or each app). The IR is
+//! resampled to the stream rate when they differ, so one file works at
+//! 44.1, 48 or 96 kHz.
+//!
+//! # Channel Mapping
+//!
+//! A mono IR is applied to every channel. A multichannel IR assigns its
+//! channels in order (L, R, ...) and wraps around for wider layouts.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::convolution::PartitionedConvolver;
+use crate::error::DspError;
+use crate::processor::{AudioProcessor, ProcessContext};
+use crate::wav;
+
+/// Partition size for IR convolution (~5 ms at 48 kHz)
+pub const IR_BLOCK: usize = 256;
+
+/// Longest impulse response accepted (~11 s at 48 kHz)
+pub const MAX_IR_LENGTH: usize = 1 << 19;
+
+/// Zero crossings on each side of the resampling kernel
+const RESAMPLE_ZERO_CROSSINGS: usize = 32;
+
+/// Decoded impulse response, one `Vec` per channel
+///
+/// Cheap to clone: channel data is shared.
+#[derive(Debug, Clone)]
+pub struct ImpulseResponse {
+    sample_rate: f32,
+    channels: Arc<[Vec<f32>]>,
+}
+
+impl ImpulseResponse {
+    /// Create from raw channel data
+    ///
+    /// All channels must have the same, non-zero length of at most [`MAX_IR_LENGTH`].
+    pub fn new(sample_rate: f32, channels: Vec<Vec<f32>>) -> Result<Self, DspError> {
+        if sample_rate <= 0.0 {
+            return Err(DspError::InvalidSampleRate(sample_rate));
+        }
+        let length = channels.first().map_or(0, Vec::len);
+        if length == 0 {
+            return Err(DspError::InvalidImpulseResponse("no samples".to_string()));
+        }
+        if length > MAX_IR_LENGTH {
+            return Err(DspError::InvalidImpulseResponse(format!(
+                "{} samples is longer than the {} sample limit",
+                length, MAX_IR_LENGTH
+            )));
+        }
+        if channels.iter().any(|channel| channel.len() != length) {
+            return Err(DspError::InvalidImpulseResponse(
+                "channels have different lengths".to_string(),
+            ));
+        }
+        if channels.iter().flatten().any(|sample| !sample.is_finite()) {
+            return Err(DspError::InvalidImpulseResponse(
+                "contains NaN or infinite samples".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            sample_rate,
+            channels: channels.into(),
+        })
+    }
+
+    /// Decode a WAV file held in memory (PCM 8-32 bit or float)
+    pub fn from_wav_bytes(bytes: &[u8]) -> Result<Self, DspError> {
+        let wav = wav::decode(bytes)?;
+        Self::new(wav.sample_rate as f32, wav.channels)
+    }
+
+    /// Load a WAV file from disk
+    ///
+    /// Note: Reads the whole file; call during setup, not in the audio callback.
+    pub fn from_wav_file(path: impl AsRef<Path>) -> Result<Self, DspError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|e| {
+            DspError::InvalidImpulseResponse(format!("failed to read {}: {}", path.display(), e))
+        })?;
+        Self::from_wav_bytes(&bytes)
+    }
+
+    /// Sample rate the IR was recorded at
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Number of channels in the IR
+    pub fn channel_count(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// Length in samples
+    pub fn len(&self) -> usize {
+        self.channels[0].len()
+    }
+
+    /// Always false: an IR has at least one sample
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Samples of one channel
+    pub fn channel(&self, index: usize) -> Option<&[f32]> {
+        self.channels.get(index).map(Vec::as_slice)
+    }
+
+    /// This IR at another sample rate (band-limited sinc interpolation)
+    ///
+    /// Tap values are scaled by the rate ratio so the filter's gain is kept.
+    /// Returns a shared copy when the rates already match.
+    pub fn resampled(&self, sample_rate: f32) -> Result<Self, DspError> {
+        if sample_rate <= 0.0 {
+            return Err(DspError::InvalidSampleRate(sample_rate));
+        }
+        if (sample_rate - self.sample_rate).abs() < 0.5 {
+            return Ok(self.clone());
+        }
+        let channels = self
+            .channels
+            .iter()
+            .map(|channel| resample(channel, self.sample_rate, sample_rate))
+            .collect();
+        Self::new(sample_rate, channels)
+    }
+}
+
+/// Windowed-sinc resampling of a finite signal
+///
+/// When downsampling the cutoff drops to the new Nyquist to avoid aliasing.
+fn resample(input: &[f32], from_rate: f32, to_rate: f32) -> Vec<f32> {
+    let ratio = to_rate as f64 / from_rate as f64;
+    let cutoff = ratio.min(1.0);
+    // Kernel half-width in input samples
+    let half_width = RESAMPLE_ZERO_CROSSINGS as f64 / cutoff;
+    let length = (input.len() as f64 * ratio).ceil() as usize;
+
+    (0..length)
+        .map(|m| {
+            let center = m as f64 / ratio;
+            let first = (center - half_width).ceil().max(0.0) as usize;
+            let last = ((center + half_width).floor() as usize).min(input.len() - 1);
+            let mut sum = 0.0_f64;
+            for (n, &sample) in input.iter().enumerate().take(last + 1).skip(first) {
+                let t = n as f64 - center;
+                let x = std::f64::consts::PI * cutoff * t;
+                let sinc = if x.abs() < 1e-9 { 1.0 } else { x.sin() / x };
+                // Blackman window over [-half_width, half_width]
+                let phase = std::f64::consts::PI * (t / half_width + 1.0);
+                let window = 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos();
+                sum += sample as f64 * cutoff * sinc * window;
+            }
+            // Each input tap is spread over `ratio` output taps; keep the DC gain
+            (sum / ratio) as f32
+        })
+        .collect()
+}
+
+/// Convolution processor for an [`ImpulseResponse`]
+///
+/// Uses uniformly partitioned FFT convolution: cost per sample grows with
+/// the IR length only through the spectrum multiply, and `process()` never
+/// allocates. Adds [`IR_BLOCK`] samples of latency.
+pub struct Convolver {
+    convolver: PartitionedConvolver,
+    sample_rate: f32,
+    enabled: bool,
+}
+
+impl Convolver {
+    /// Create a convolver for `ir` at the stream `sample_rate`
+    ///
+    /// The IR is resampled if its rate differs. Processes 2 channels until
+    /// told otherwise (see [`Convolver::set_channel_count`]).
+    ///
+    /// Note: Allocates the partition buffers; call during setup.
+    pub fn new(ir: &ImpulseResponse, sample_rate: f32) -> Result<Self, DspError> {
+        let ir = ir.resampled(sample_rate)?;
+        let mut convolver = PartitionedConvolver::new(IR_BLOCK, ir.len(), ir.channel_count(), 2);
+        for (index, channel) in ir.channels.iter().enumerate() {
+            convolver.write_kernel(index, |n| channel.get(n).copied().unwrap_or(0.0));
+        }
+        convolver.swap_kernels(false);
+
+        Ok(Self {
+            convolver,
+            sample_rate,
+            enabled: true,
+        })
+    }
+
+    /// Set the number of interleaved channels (1 to [`MAX_CHANNELS`](crate::MAX_CHANNELS))
+    ///
+    /// Convolution state is reset when the count changes.
+    ///
+    /// # Real-time Safety
+    /// No allocations. Safe to call from an audio callback between buffers.
+    pub fn set_channel_count(&mut self, channels: usize) -> Result<(), DspError> {
+        if channels == 0 || channels > crate::MAX_CHANNELS {
+            return Err(DspError::InvalidChannelCount(channels));
+        }
+        if channels != self.convolver.channel_count() {
+            self.convolver.set_channel_count(channels);
+        }
+        Ok(())
+    }
+
+    /// Number of interleaved channels processed
+    pub fn channel_count(&self) -> usize {
+        self.convolver.channel_count()
+    }
+
+    /// Stream sample rate this convolver was built for
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// IR length in samples after resampling, rounded up to whole partitions
+    pub fn length(&self) -> usize {
+        self.convolver.capacity()
+    }
+
+    /// Enable or disable the convolver (disabled passes audio untouched)
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Process an interleaved buffer in-place
+    ///
+    /// A trailing partial frame is left untouched.
+    ///
+    /// # Real-time Safety
+    /// No allocations. O(n) where n = buffer length.
+    pub fn process_interleaved(&mut self, buffer: &mut [f32]) {
+        for frame in buffer.chunks_exact_mut(self.convolver.channel_count()) {
+            if self.convolver.push_frame(frame) {
+                self.convolver.process_block();
+            }
+        }
+    }
+}
+
+impl AudioProcessor for Convolver {
+    fn process(&mut self, buffer: &mut [f32], context: &ProcessContext) {
+        // Follows the stream layout; the IR itself is fixed at the build rate
+        let _ = self.set_channel_count(context.channels);
+        self.process_interleaved(buffer);
+    }
+
+    fn reset(&mut self) {
+        self.convolver.reset();
+    }
+
+    fn name(&self) -> &'static str {
+        "Convolver"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn latency_samples(&self) -> usize {
+        self.convolver.block()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wav::tests::float_wav;
+
+    fn sine(frequency: f32, sample_rate: f32, frames: usize) -> Vec<f32> {
+        (0..frames)
+            .map(|n| (std::f32::consts::TAU * frequency * n as f32 / sample_rate).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_rejects_invalid_ir() {
+        assert!(ImpulseResponse::new(48000.0, vec![]).is_err());
+        assert!(ImpulseResponse::new(48000.0, vec![vec![1.0], vec![1.0, 0.0]]).is_err());
+        assert!(ImpulseResponse::new(0.0, vec![vec![1.0]]).is_err());
+        assert!(ImpulseResponse::new(48000.0, vec![vec![f32::NAN]]).is_err());
+    }
+
+    #[test]
+    fn test_stereo_ir_from_wav() {
+        // Left: unity, right: half gain delayed by 10 samples
+        let mut right = vec![0.0; 64];
+        right[10] = 0.5;
+        let mut left = vec![0.0; 64];
+        left[0] = 1.0;
+        let ir = ImpulseResponse::from_wav_bytes(&float_wav(48000, &[left, right])).unwrap();
+        assert_eq!(ir.channel_count(), 2);
+        assert_eq!(ir.len(), 64);
+
+        let mut convolver = Convolver::new(&ir, 48000.0).unwrap();
+        let mut buffer = vec![0.0; 2 * 1024];
+        buffer[0] = 1.0;
+        buffer[1] = 1.0;
+        let context = ProcessContext::new(48000.0, 2, 1024);
+        convolver.process(&mut buffer, &context);
+
+        let latency = convolver.latency_samples();
+        assert!((buffer[2 * latency] - 1.0).abs() < 1e-5);
+        assert!((buffer[2 * (latency + 10) + 1] - 0.5).abs() < 1e-5);
+        assert!(buffer[2 * latency + 1].abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_mono_ir_applies_to_all_channels() {
+        let ir = ImpulseResponse::new(48000.0, vec![vec![0.5]]).unwrap();
+        let mut convolver = Convolver::new(&ir, 48000.0).unwrap();
+        convolver.set_channel_count(6).unwrap();
+
+        let mut buffer = vec![1.0; 6 * 2 * IR_BLOCK];
+        convolver.process_interleaved(&mut buffer);
+        for sample in &buffer[6 * IR_BLOCK..] {
+            assert!((sample - 0.5).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_resampled_ir_keeps_gain() {
+        // Simple 8-tap average at 44.1 kHz: unity DC gain, gentle lowpass
+        let ir = ImpulseResponse::new(44100.0, vec![vec![0.125; 8]]).unwrap();
+        let resampled = ir.resampled(48000.0).unwrap();
+        assert_eq!(resampled.sample_rate(), 48000.0);
+        let dc: f32 = resampled.channel(0).unwrap().iter().sum();
+        assert!((dc - 1.0).abs() < 0.01, "DC gain should be kept, got {}", dc);
+
+        // A 44.1 kHz unit impulse played at 48 kHz should still pass 1 kHz at unity
+        let impulse = ImpulseResponse::new(44100.0, vec![vec![1.0]]).unwrap();
+        let mut convolver = Convolver::new(&impulse, 48000.0).unwrap();
+        convolver.set_channel_count(1).unwrap();
+        let mut signal = sine(1000.0, 48000.0, 9600);
+        convolver.process_interleaved(&mut signal);
+        let peak = signal[4800..].iter().fold(0.0_f32, |m, s| m.max(s.abs()));
+        assert!((peak - 1.0).abs() < 0.01, "Expected unity gain, got {}", peak);
+    }
+
+    #[test]
+    fn test_downsampling_removes_content_above_nyquist() {
+        // 30 kHz cosine at 96 kHz has nothing to say below 24 kHz
+        let tone: Vec<f32> = (0..256)
+            .map(|n| (std::f32::consts::TAU * 30000.0 * n as f32 / 96000.0).cos())
+            .collect();
+        let ir = ImpulseResponse::new(96000.0, vec![tone]).unwrap();
+        let resampled = ir.resampled(48000.0).unwrap();
+        let energy: f32 = resampled.channel(0).unwrap()[40..90].iter().map(|s| s * s).sum();
+        assert!(energy < 1e-3, "Aliased energy {}", energy);
+    }
+
+    #[test]
+    fn test_long_ir_spans_partitions() {
+        let mut taps = vec![0.0; 3 * IR_BLOCK + 17];
+        taps[3 * IR_BLOCK + 10] = 1.0;
+        let ir = ImpulseResponse::new(48000.0, vec![taps]).unwrap();
+        let mut convolver = Convolver::new(&ir, 48000.0).unwrap();
+        convolver.set_channel_count(1).unwrap();
+
+        let mut buffer = vec![0.0; 6 * IR_BLOCK];
+        buffer[0] = 1.0;
+        convolver.process_interleaved(&mut buffer);
+        let expected = IR_BLOCK + 3 * IR_BLOCK + 10;
+        assert!((buffer[expected] - 1.0).abs() < 1e-5);
+        assert!(buffer.iter().enumerate().all(|(n, s)| n == expected || s.abs() < 1e-5));
+    }
+}
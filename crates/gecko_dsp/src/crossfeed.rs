@@ -0,0 +1,391 @@
+//! Headphone Crossfeed
+//!
+//! On speakers each ear hears both channels; on headphones a hard-panned
+//! instrument reaches one ear only, which is unnatural and tiring on old
+//! stereo recordings. Crossfeed feeds a low-passed copy of each channel into
+//! the other, the way the head shadows high frequencies from the far speaker.
+//!
+//! # Algorithm
+//!
+//! The Bauer stereophonic-to-binaural (bs2b) filter:
+//!
+//! ```text
+//! L' = gain × (high_boost(L) + low_pass(R))
+//! R' = gain × (high_boost(R) + low_pass(L))
+//! ```
+//!
+//! - Below the cutoff, the opposite channel is fed at `feed_db` below the
+//!   direct one; above it, the feed falls away at 6 dB/octave.
+//! - The direct path gets a matching first-order high boost, and the sum is
+//!   normalised so centred (mono) low frequencies keep unity gain.
+//!
+//! Only the front pair (first two channels) is crossfed; any other channels
+//! pass through untouched.
+
+use crate::eq::MAX_CHANNELS;
+use crate::error::DspError;
+use crate::processor::{AudioProcessor, ProcessContext};
+
+/// Classic bs2b parameter sets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum CrossfeedPreset {
+    /// 700 Hz, 4.5 dB: closest to speakers in a normal room
+    #[default]
+    Default,
+    /// 700 Hz, 6 dB: Chu Moy's headphone amplifier
+    ChuMoy,
+    /// 650 Hz, 9.5 dB: Jan Meier's crossfeed, the most subtle
+    JanMeier,
+}
+
+impl CrossfeedPreset {
+    /// All presets, in display order
+    pub const ALL: [CrossfeedPreset; 3] = [
+        CrossfeedPreset::Default,
+        CrossfeedPreset::ChuMoy,
+        CrossfeedPreset::JanMeier,
+    ];
+
+    /// Display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            CrossfeedPreset::Default => "Default",
+            CrossfeedPreset::ChuMoy => "Chu Moy",
+            CrossfeedPreset::JanMeier => "Jan Meier",
+        }
+    }
+
+    /// Cutoff and feed level of this preset
+    pub fn settings(&self) -> CrossfeedSettings {
+        let (cutoff_hz, feed_db) = match self {
+            CrossfeedPreset::Default => (700.0, 4.5),
+            CrossfeedPreset::ChuMoy => (700.0, 6.0),
+            CrossfeedPreset::JanMeier => (650.0, 9.5),
+        };
+        CrossfeedSettings { cutoff_hz, feed_db }
+    }
+}
+
+/// Crossfeed parameters
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CrossfeedSettings {
+    /// Frequency where the feed starts to roll off (300 to 2000 Hz)
+    pub cutoff_hz: f32,
+    /// How far below the direct signal the opposite channel is fed (1 to 15 dB)
+    pub feed_db: f32,
+}
+
+impl Default for CrossfeedSettings {
+    fn default() -> Self {
+        CrossfeedPreset::Default.settings()
+    }
+}
+
+impl CrossfeedSettings {
+    /// Check every parameter is in range
+    pub fn validate(&self) -> Result<(), DspError> {
+        if !(300.0..=2000.0).contains(&self.cutoff_hz) {
+            return Err(DspError::InvalidParameter {
+                name: "cutoff_hz",
+                value: self.cutoff_hz,
+            });
+        }
+        if !(1.0..=15.0).contains(&self.feed_db) {
+            return Err(DspError::InvalidParameter {
+                name: "feed_db",
+                value: self.feed_db,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Filter coefficients derived from the settings and sample rate
+#[derive(Debug, Clone, Copy, Default)]
+struct Coefficients {
+    // One-pole low-pass for the crossed path
+    a0_lo: f64,
+    b1_lo: f64,
+    // First-order high boost for the direct path
+    a0_hi: f64,
+    a1_hi: f64,
+    b1_hi: f64,
+    // Normalisation for unity gain of centred low frequencies
+    gain: f64,
+}
+
+impl Coefficients {
+    fn new(settings: &CrossfeedSettings, sample_rate: f32) -> Self {
+        let feed = settings.feed_db as f64;
+        let cutoff_lo = settings.cutoff_hz as f64;
+
+        // Low-pass and high-boost levels differ by the feed level
+        let gain_lo_db = feed * -5.0 / 6.0 - 3.0;
+        let gain_hi_db = feed / 6.0 - 3.0;
+        let gain_lo = 10.0_f64.powf(gain_lo_db / 20.0);
+        let gain_hi = 1.0 - 10.0_f64.powf(gain_hi_db / 20.0);
+        let cutoff_hi = cutoff_lo * 2.0_f64.powf((gain_lo_db - 20.0 * gain_hi.log10()) / 12.0);
+
+        let x_lo = (-std::f64::consts::TAU * cutoff_lo / sample_rate as f64).exp();
+        let x_hi = (-std::f64::consts::TAU * cutoff_hi / sample_rate as f64).exp();
+        Self {
+            a0_lo: gain_lo * (1.0 - x_lo),
+            b1_lo: x_lo,
+            a0_hi: 1.0 - gain_hi * (1.0 - x_hi),
+            a1_hi: -x_hi,
+            b1_hi: x_hi,
+            gain: 1.0 / (1.0 - gain_hi + gain_lo),
+        }
+    }
+}
+
+/// bs2b-style headphone crossfeed
+///
+/// Starts disabled; headphone outputs turn it on with [`Crossfeed::set_enabled`].
+pub struct Crossfeed {
+    settings: CrossfeedSettings,
+    sample_rate: f32,
+    channels: usize,
+    enabled: bool,
+    coefficients: Coefficients,
+
+    // Filter state for L and R: low-pass output, high-boost output, last input
+    low: [f64; 2],
+    high: [f64; 2],
+    last: [f64; 2],
+}
+
+impl Crossfeed {
+    /// Create a disabled crossfeed at `sample_rate` for 2 channels
+    pub fn new(sample_rate: f32, settings: CrossfeedSettings) -> Result<Self, DspError> {
+        if sample_rate <= 0.0 {
+            return Err(DspError::InvalidSampleRate(sample_rate));
+        }
+        settings.validate()?;
+        Ok(Self {
+            settings,
+            sample_rate,
+            channels: 2,
+            enabled: false,
+            coefficients: Coefficients::new(&settings, sample_rate),
+            low: [0.0; 2],
+            high: [0.0; 2],
+            last: [0.0; 2],
+        })
+    }
+
+    /// Replace the cutoff and feed level; filter state carries over
+    ///
+    /// # Real-time Safety
+    /// No allocations. Safe to call from an audio callback between buffers.
+    pub fn set_settings(&mut self, settings: CrossfeedSettings) -> Result<(), DspError> {
+        settings.validate()?;
+        self.settings = settings;
+        self.coefficients = Coefficients::new(&settings, self.sample_rate);
+        Ok(())
+    }
+
+    /// Current parameters
+    pub fn settings(&self) -> &CrossfeedSettings {
+        &self.settings
+    }
+
+    /// Enable or disable (disabled passes audio untouched)
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled && !self.enabled {
+            self.reset_state();
+        }
+        self.enabled = enabled;
+    }
+
+    /// Sample rate this crossfeed was built for
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Set the number of interleaved channels (1 to [`MAX_CHANNELS`])
+    ///
+    /// Mono passes through; with more than two channels only the first two
+    /// (front left and right) are crossfed. State is reset when the count changes.
+    pub fn set_channel_count(&mut self, channels: usize) -> Result<(), DspError> {
+        if channels == 0 || channels > MAX_CHANNELS {
+            return Err(DspError::InvalidChannelCount(channels));
+        }
+        if channels != self.channels {
+            self.channels = channels;
+            self.reset_state();
+        }
+        Ok(())
+    }
+
+    /// Number of interleaved channels processed
+    pub fn channel_count(&self) -> usize {
+        self.channels
+    }
+
+    fn reset_state(&mut self) {
+        self.low = [0.0; 2];
+        self.high = [0.0; 2];
+        self.last = [0.0; 2];
+    }
+
+    /// Process an interleaved buffer in-place
+    ///
+    /// A trailing partial frame is left untouched.
+    ///
+    /// # Real-time Safety
+    /// No allocations, no locks. O(n) where n = buffer length.
+    pub fn process_interleaved(&mut self, buffer: &mut [f32]) {
+        if !self.enabled || self.channels < 2 {
+            return;
+        }
+
+        let c = self.coefficients;
+        for frame in buffer.chunks_exact_mut(self.channels) {
+            let input = [frame[0] as f64, frame[1] as f64];
+            for (side, &x) in input.iter().enumerate() {
+                self.low[side] = c.a0_lo * x + c.b1_lo * self.low[side];
+                self.high[side] =
+                    c.a0_hi * x + c.a1_hi * self.last[side] + c.b1_hi * self.high[side];
+            }
+            self.last = input;
+
+            frame[0] = ((self.high[0] + self.low[1]) * c.gain) as f32;
+            frame[1] = ((self.high[1] + self.low[0]) * c.gain) as f32;
+        }
+    }
+}
+
+impl AudioProcessor for Crossfeed {
+    fn process(&mut self, buffer: &mut [f32], context: &ProcessContext) {
+        let _ = self.set_channel_count(context.channels);
+        self.process_interleaved(buffer);
+    }
+
+    fn reset(&mut self) {
+        self.reset_state();
+    }
+
+    fn name(&self) -> &'static str {
+        "Crossfeed"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    fn enabled_crossfeed(settings: CrossfeedSettings) -> Crossfeed {
+        let mut crossfeed = Crossfeed::new(SAMPLE_RATE, settings).unwrap();
+        crossfeed.set_enabled(true);
+        crossfeed
+    }
+
+    /// Peak of each output channel for a sine fed to the left and/or right input
+    fn sine_peaks(crossfeed: &mut Crossfeed, freq: f32, left: f32, right: f32) -> (f32, f32) {
+        let mut peaks = (0.0_f32, 0.0_f32);
+        for block in 0..20 {
+            let mut buffer: Vec<f32> = (0..4800)
+                .flat_map(|i| {
+                    let t = (block * 4800 + i) as f32 / SAMPLE_RATE;
+                    let x = (std::f32::consts::TAU * freq * t).sin();
+                    [x * left, x * right]
+                })
+                .collect();
+            crossfeed.process_interleaved(&mut buffer);
+            // Skip the first half second while the filters settle
+            if block >= 5 {
+                for frame in buffer.chunks_exact(2) {
+                    peaks.0 = peaks.0.max(frame[0].abs());
+                    peaks.1 = peaks.1.max(frame[1].abs());
+                }
+            }
+        }
+        peaks
+    }
+
+    #[test]
+    fn test_settings_validation() {
+        for preset in CrossfeedPreset::ALL {
+            assert!(preset.settings().validate().is_ok(), "{}", preset.name());
+        }
+        let settings = CrossfeedSettings::default();
+        assert!(CrossfeedSettings {
+            cutoff_hz: 100.0,
+            ..settings
+        }
+        .validate()
+        .is_err());
+        assert!(CrossfeedSettings {
+            feed_db: 20.0,
+            ..settings
+        }
+        .validate()
+        .is_err());
+        assert!(Crossfeed::new(0.0, settings).is_err());
+    }
+
+    #[test]
+    fn test_low_frequency_feed_level() {
+        // A hard-left low tone reaches the right ear `feed_db` below the left
+        for preset in CrossfeedPreset::ALL {
+            let settings = preset.settings();
+            let mut crossfeed = enabled_crossfeed(settings);
+            let (left, right) = sine_peaks(&mut crossfeed, 30.0, 1.0, 0.0);
+            let feed_db = 20.0 * (left / right).log10();
+            assert!(
+                (feed_db - settings.feed_db).abs() < 0.3,
+                "{}: feed {} dB",
+                preset.name(),
+                feed_db
+            );
+        }
+    }
+
+    #[test]
+    fn test_high_frequencies_stay_separated() {
+        let mut crossfeed = enabled_crossfeed(CrossfeedSettings::default());
+        let (left, right) = sine_peaks(&mut crossfeed, 8000.0, 1.0, 0.0);
+        let separation_db = 20.0 * (left / right).log10();
+        assert!(separation_db > 20.0, "Separation {} dB", separation_db);
+    }
+
+    #[test]
+    fn test_centred_bass_keeps_unity_gain() {
+        let mut crossfeed = enabled_crossfeed(CrossfeedSettings::default());
+        let (left, right) = sine_peaks(&mut crossfeed, 50.0, 0.5, 0.5);
+        assert!((left - 0.5).abs() < 0.01, "Left {}", left);
+        assert!((right - 0.5).abs() < 0.01, "Right {}", right);
+    }
+
+    #[test]
+    fn test_disabled_and_extra_channels_pass_through() {
+        let mut crossfeed = Crossfeed::new(SAMPLE_RATE, CrossfeedSettings::default()).unwrap();
+        let original: Vec<f32> = (0..600).map(|i| (i as f32 * 0.01).sin()).collect();
+
+        let mut buffer = original.clone();
+        crossfeed.process_interleaved(&mut buffer);
+        assert_eq!(buffer, original);
+
+        // 5.1: only FL/FR are mixed
+        crossfeed.set_enabled(true);
+        crossfeed.set_channel_count(6).unwrap();
+        let mut buffer = original.clone();
+        crossfeed.process_interleaved(&mut buffer);
+        for (frame, input) in buffer.chunks_exact(6).zip(original.chunks_exact(6)) {
+            assert_eq!(frame[2..], input[2..]);
+        }
+        assert_ne!(buffer, original);
+
+        assert!(crossfeed.set_channel_count(0).is_err());
+    }
+}
@@ -0,0 +1,353 @@
+//! Delay Line
+//!
+//! Holds a stream back by a whole number of frames, to line an app's audio
+//! up with its video (A/V sync) or with other apps.
+//!
+//! Changing the delay jumps the read position, which would click; instead
+//! the output crossfades from the old position to the new one over
+//! [`DELAY_FADE_MS`]. A change that arrives mid-fade is queued until the
+//! fade finishes, so the output never jumps.
+
+use crate::error::DspError;
+use crate::processor::{AudioProcessor, ProcessContext};
+
+/// Largest A/V sync offset either way for one stream (ms)
+///
+/// A negative offset is applied by delaying every other stream, so a
+/// stream's delay line can need up to twice this.
+pub const MAX_SYNC_OFFSET_MS: f32 = 500.0;
+
+/// Crossfade length when the delay changes (ms)
+pub const DELAY_FADE_MS: f32 = 20.0;
+
+/// Multichannel interleaved delay line
+///
+/// The buffer is allocated for `max_delay_ms` in `new`, so changing the
+/// delay never allocates.
+pub struct Delay {
+    sample_rate: f32,
+    channels: usize,
+    max_delay_ms: f32,
+    /// Interleaved ring of `capacity` frames
+    buffer: Vec<f32>,
+    capacity: usize,
+    write_pos: usize,
+    delay_ms: f32,
+    delay_frames: usize,
+    /// Delay being faded out, and how far the fade has got
+    previous_frames: usize,
+    fade_pos: usize,
+    fade_frames: usize,
+    /// Delay to fade to once the current fade finishes
+    pending_frames: Option<usize>,
+}
+
+impl Delay {
+    /// Create a delay line of up to `max_delay_ms` for `channels` interleaved channels
+    ///
+    /// Starts at zero delay. Allocates.
+    pub fn new(sample_rate: f32, channels: usize, max_delay_ms: f32) -> Result<Self, DspError> {
+        if sample_rate <= 0.0 || !sample_rate.is_finite() {
+            return Err(DspError::InvalidSampleRate(sample_rate));
+        }
+        if channels == 0 {
+            return Err(DspError::InvalidParameter {
+                name: "channels",
+                value: 0.0,
+            });
+        }
+        if max_delay_ms < 0.0 || !max_delay_ms.is_finite() {
+            return Err(DspError::InvalidParameter {
+                name: "max delay",
+                value: max_delay_ms,
+            });
+        }
+        let capacity = (max_delay_ms * 0.001 * sample_rate).round() as usize + 1;
+        let fade_frames = ((DELAY_FADE_MS * 0.001 * sample_rate) as usize).max(1);
+        Ok(Self {
+            sample_rate,
+            channels,
+            max_delay_ms,
+            buffer: vec![0.0; capacity * channels],
+            capacity,
+            write_pos: 0,
+            delay_ms: 0.0,
+            delay_frames: 0,
+            previous_frames: 0,
+            fade_pos: fade_frames,
+            fade_frames,
+            pending_frames: None,
+        })
+    }
+
+    /// Set the delay (0 to the maximum given to `new`, in ms)
+    ///
+    /// # Real-time Safety
+    /// No allocations. The change is crossfaded over [`DELAY_FADE_MS`];
+    /// during a fade it waits for that fade to finish (the latest change wins).
+    pub fn set_delay_ms(&mut self, delay_ms: f32) -> Result<(), DspError> {
+        if !(0.0..=self.max_delay_ms).contains(&delay_ms) {
+            return Err(DspError::InvalidParameter {
+                name: "delay",
+                value: delay_ms,
+            });
+        }
+        let frames =
+            ((delay_ms * 0.001 * self.sample_rate).round() as usize).min(self.capacity - 1);
+        self.delay_ms = delay_ms;
+        if self.fade_pos < self.fade_frames {
+            // Restarting the fade would jump the output; finish it first
+            self.pending_frames = (frames != self.delay_frames).then_some(frames);
+        } else if frames != self.delay_frames {
+            self.start_fade(frames);
+        }
+        Ok(())
+    }
+
+    /// Current delay (ms)
+    pub fn delay_ms(&self) -> f32 {
+        self.delay_ms
+    }
+
+    /// Current delay (frames), including a change still waiting on a fade
+    pub fn delay_frames(&self) -> usize {
+        self.pending_frames.unwrap_or(self.delay_frames)
+    }
+
+    /// Sample rate (Hz) the delay line was built for
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Follow a new sample rate, keeping the delay in ms
+    ///
+    /// The history is cleared. The buffer is not resized, so this fails if
+    /// it can't hold the maximum delay at `sample_rate`: build the line at
+    /// the highest rate it has to follow.
+    ///
+    /// # Real-time Safety
+    /// No allocations, but clears the whole buffer; call when a stream's
+    /// rate changes, not per buffer.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) -> Result<(), DspError> {
+        if sample_rate <= 0.0
+            || !sample_rate.is_finite()
+            || (self.max_delay_ms * 0.001 * sample_rate).round() as usize >= self.capacity
+        {
+            return Err(DspError::InvalidSampleRate(sample_rate));
+        }
+        self.sample_rate = sample_rate;
+        self.fade_frames = ((DELAY_FADE_MS * 0.001 * sample_rate) as usize).max(1);
+        self.delay_frames =
+            ((self.delay_ms * 0.001 * sample_rate).round() as usize).min(self.capacity - 1);
+        self.pending_frames = None;
+        self.reset();
+        Ok(())
+    }
+
+    /// Number of interleaved channels processed
+    pub fn channel_count(&self) -> usize {
+        self.channels
+    }
+
+    /// Delay an interleaved buffer in place
+    ///
+    /// # Real-time Safety
+    /// No allocations. O(n) where n = samples.
+    pub fn process_interleaved(&mut self, buffer: &mut [f32]) {
+        if self.delay_frames == 0 && self.fade_pos >= self.fade_frames {
+            // Keep the history current so a later delay starts from real audio
+            for frame in buffer.chunks_exact(self.channels) {
+                self.push(frame);
+                self.write_pos = (self.write_pos + 1) % self.capacity;
+            }
+            return;
+        }
+        for frame in buffer.chunks_exact_mut(self.channels) {
+            self.push(frame);
+            let current = self.frame_start(self.delay_frames);
+            if self.fade_pos < self.fade_frames {
+                let previous = self.frame_start(self.previous_frames);
+                let t = self.fade_pos as f32 / self.fade_frames as f32;
+                for (c, sample) in frame.iter_mut().enumerate() {
+                    let old = self.buffer[previous + c];
+                    *sample = old + (self.buffer[current + c] - old) * t;
+                }
+                self.fade_pos += 1;
+                if self.fade_pos == self.fade_frames {
+                    if let Some(frames) = self.pending_frames.take() {
+                        self.start_fade(frames);
+                    }
+                }
+            } else {
+                frame.copy_from_slice(&self.buffer[current..current + self.channels]);
+            }
+            self.write_pos = (self.write_pos + 1) % self.capacity;
+        }
+    }
+
+    /// Clear the history (the delayed output starts from silence)
+    pub fn reset(&mut self) {
+        self.buffer.fill(0.0);
+        self.write_pos = 0;
+        if let Some(frames) = self.pending_frames.take() {
+            self.delay_frames = frames;
+        }
+        self.fade_pos = self.fade_frames;
+    }
+
+    /// Crossfade from the current delay to `frames`
+    fn start_fade(&mut self, frames: usize) {
+        self.previous_frames = self.delay_frames;
+        self.delay_frames = frames;
+        self.fade_pos = 0;
+    }
+
+    /// Store a frame at the write position (advanced by the caller)
+    fn push(&mut self, frame: &[f32]) {
+        let start = self.write_pos * self.channels;
+        self.buffer[start..start + self.channels].copy_from_slice(frame);
+    }
+
+    /// Sample index of the frame written `frames` ago
+    fn frame_start(&self, frames: usize) -> usize {
+        (self.write_pos + self.capacity - frames) % self.capacity * self.channels
+    }
+}
+
+impl AudioProcessor for Delay {
+    fn process(&mut self, buffer: &mut [f32], _context: &ProcessContext) {
+        self.process_interleaved(buffer);
+    }
+
+    fn reset(&mut self) {
+        Delay::reset(self);
+    }
+
+    fn name(&self) -> &'static str {
+        "Delay"
+    }
+
+    fn latency_samples(&self) -> usize {
+        self.delay_frames()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stereo ramp (frame index in both channels) of `frames` frames from `start`
+    fn ramp(start: usize, frames: usize) -> Vec<f32> {
+        (start..start + frames)
+            .flat_map(|n| [n as f32, -(n as f32)])
+            .collect()
+    }
+
+    #[test]
+    fn test_delays_by_whole_frames() {
+        let mut delay = Delay::new(1000.0, 2, 100.0).unwrap();
+        let mut buffer = ramp(0, 50);
+        delay.process_interleaved(&mut buffer);
+        assert_eq!(buffer, ramp(0, 50));
+
+        // 10 ms at 1 kHz is 10 frames; the fade has run out after 20
+        delay.set_delay_ms(10.0).unwrap();
+        assert_eq!(delay.delay_frames(), 10);
+        assert_eq!(delay.latency_samples(), 10);
+        let mut buffer = ramp(50, 20);
+        delay.process_interleaved(&mut buffer);
+        let mut buffer = ramp(70, 30);
+        delay.process_interleaved(&mut buffer);
+        assert_eq!(buffer, ramp(60, 30));
+    }
+
+    #[test]
+    fn test_change_is_crossfaded() {
+        let mut delay = Delay::new(48000.0, 2, MAX_SYNC_OFFSET_MS).unwrap();
+        let mut buffer = ramp(0, 48000);
+        delay.process_interleaved(&mut buffer);
+
+        // Jumping back 100 ms moves the output smoothly, never by a whole jump
+        delay.set_delay_ms(100.0).unwrap();
+        let mut buffer = ramp(48000, 2400);
+        delay.process_interleaved(&mut buffer);
+        let fade = (DELAY_FADE_MS * 48.0) as usize;
+        let max_step = buffer
+            .chunks_exact(2)
+            .map(|frame| frame[0])
+            .collect::<Vec<_>>()
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).abs())
+            .fold(0.0, f32::max);
+        assert!(max_step < 4800.0 / fade as f32 + 2.0, "Step {}", max_step);
+        assert_eq!(
+            buffer[2 * fade..],
+            ramp(48000 + fade - 4800, 2400 - fade)[..]
+        );
+    }
+
+    #[test]
+    fn test_change_mid_fade_is_queued() {
+        let mut delay = Delay::new(1000.0, 2, 100.0).unwrap();
+        let mut buffer = ramp(0, 100);
+        delay.process_interleaved(&mut buffer);
+
+        // Two changes within one 20-frame fade: the second waits for the first
+        delay.set_delay_ms(40.0).unwrap();
+        let mut first = ramp(100, 5);
+        delay.process_interleaved(&mut first);
+        delay.set_delay_ms(10.0).unwrap();
+        assert_eq!(delay.delay_frames(), 10);
+        let mut buffer = ramp(105, 55);
+        delay.process_interleaved(&mut buffer);
+
+        let max_step = first
+            .iter()
+            .chain(&buffer)
+            .step_by(2)
+            .copied()
+            .collect::<Vec<_>>()
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).abs())
+            .fold(0.0, f32::max);
+        // The fades move the output at most 2.5 frames per frame; a restart jumps ~30
+        assert!(max_step <= 2.5, "Step {}", max_step);
+
+        // Both fades are done after 40 frames: 10 ms behind from then on
+        assert_eq!(buffer[2 * 35..], ramp(140 - 10, 20)[..]);
+    }
+
+    #[test]
+    fn test_set_sample_rate() {
+        let mut delay = Delay::new(2000.0, 2, 100.0).unwrap();
+        delay.set_sample_rate(1000.0).unwrap();
+        delay.set_delay_ms(10.0).unwrap();
+        assert_eq!(delay.delay_frames(), 10);
+
+        // The delay stays at 10 ms, and starts from silence
+        delay.set_sample_rate(2000.0).unwrap();
+        assert_eq!(delay.delay_frames(), 20);
+        let mut buffer = ramp(1, 40);
+        delay.process_interleaved(&mut buffer);
+        assert_eq!(buffer[..40], [0.0; 40]);
+        assert_eq!(buffer[40..], ramp(1, 20)[..]);
+
+        // 100 ms at 4 kHz doesn't fit a line built for 2 kHz
+        assert!(delay.set_sample_rate(4000.0).is_err());
+        assert!(delay.set_sample_rate(0.0).is_err());
+        assert_eq!(delay.sample_rate(), 2000.0);
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        assert!(Delay::new(0.0, 2, 100.0).is_err());
+        assert!(Delay::new(48000.0, 0, 100.0).is_err());
+        assert!(Delay::new(48000.0, 2, -1.0).is_err());
+
+        let mut delay = Delay::new(48000.0, 2, 100.0).unwrap();
+        assert!(delay.set_delay_ms(-1.0).is_err());
+        assert!(delay.set_delay_ms(100.5).is_err());
+        assert!(delay.set_delay_ms(100.0).is_ok());
+        assert_eq!(delay.delay_frames(), 4800);
+    }
+}
@@ -0,0 +1,321 @@
+//! Clock Drift Compensation
+//!
+//! Two streams on different clocks (an app captured at the graph rate and a
+//! USB DAC or Bluetooth output, say) never run at exactly the same speed, so
+//! the ring buffer between them slowly fills or drains until it overruns or
+//! underruns and clicks. [`DriftCompensator`] watches the buffer's fill level
+//! and returns a resampling ratio that holds it at a target latency.
+//!
+//! # Algorithm
+//!
+//! ```text
+//! e = (smoothed fill - target) / sample_rate           (seconds)
+//! ratio = 1 - clamp(Kp × e + Ki × ∫e dt, ±MAX_DRIFT)
+//! ```
+//!
+//! - The fill level is smoothed over ~1 s to ignore quantum-sized jitter.
+//! - The integral settles on the clocks' steady-state offset; it is frozen
+//!   while the ratio is clamped so it can't wind up.
+//! - Gains give a damping of ~0.8 and settle in about a minute, slow enough
+//!   that the pitch change (< 2000 ppm, a few cents at most) is inaudible.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use crate::error::DspError;
+
+/// Proportional gain (ratio change per second of fill error)
+const KP: f64 = 0.05;
+
+/// Integral gain (ratio change per second² of fill error)
+const KI: f64 = 0.001;
+
+/// Largest correction applied (2000 ppm)
+pub const MAX_DRIFT: f64 = 0.002;
+
+/// Time constant of the fill level smoothing (seconds)
+const FILL_SMOOTHING_S: f64 = 1.0;
+
+/// Snapshot of a drift compensator's state
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DriftStats {
+    /// Output frames produced per nominal frame (> 1 when the source clock is slow)
+    pub ratio: f32,
+    /// Smoothed ring buffer fill (ms of audio)
+    pub buffer_ms: f32,
+    /// Fill level being held (ms of audio)
+    pub target_ms: f32,
+}
+
+impl DriftStats {
+    /// Deviation of the ratio from 1, in parts per million
+    pub fn drift_ppm(&self) -> f32 {
+        (self.ratio - 1.0) * 1e6
+    }
+}
+
+impl Default for DriftStats {
+    fn default() -> Self {
+        Self {
+            ratio: 1.0,
+            buffer_ms: 0.0,
+            target_ms: 0.0,
+        }
+    }
+}
+
+/// Lock-free handle to a [`DriftCompensator`]'s state
+///
+/// Cheap to clone; values update on every `DriftCompensator::update`.
+#[derive(Debug, Clone)]
+pub struct DriftMeter {
+    /// DriftStats fields as f32 bits, in declaration order
+    values: Arc<[AtomicU32; 3]>,
+}
+
+impl Default for DriftMeter {
+    fn default() -> Self {
+        let stats = DriftStats::default();
+        Self {
+            values: Arc::new([
+                AtomicU32::new(stats.ratio.to_bits()),
+                AtomicU32::new(stats.buffer_ms.to_bits()),
+                AtomicU32::new(stats.target_ms.to_bits()),
+            ]),
+        }
+    }
+}
+
+impl DriftMeter {
+    /// Latest state
+    pub fn stats(&self) -> DriftStats {
+        let value = |i: usize| f32::from_bits(self.values[i].load(Ordering::Relaxed));
+        DriftStats {
+            ratio: value(0),
+            buffer_ms: value(1),
+            target_ms: value(2),
+        }
+    }
+
+    fn publish(&self, stats: DriftStats) {
+        let values = [stats.ratio, stats.buffer_ms, stats.target_ms];
+        for (atomic, value) in self.values.iter().zip(values) {
+            atomic.store(value.to_bits(), Ordering::Relaxed);
+        }
+    }
+}
+
+/// PI controller holding a ring buffer's fill level at a target
+///
+/// Call [`update`](Self::update) once per buffer from the side that writes
+/// the ring, and apply the returned ratio with `Resampler::set_drift_ratio`.
+pub struct DriftCompensator {
+    sample_rate: f32,
+    target_frames: f32,
+    // Smoothed fill level (frames); None until the first update
+    smoothed_fill: Option<f64>,
+    integral: f64,
+    ratio: f64,
+    meter: DriftMeter,
+}
+
+impl DriftCompensator {
+    /// Create a compensator for a buffer of `sample_rate` audio, holding `target_frames`
+    pub fn new(sample_rate: f32, target_frames: f32) -> Result<Self, DspError> {
+        Self::with_meter(sample_rate, target_frames, DriftMeter::default())
+    }
+
+    /// Like [`DriftCompensator::new`], publishing to an existing meter
+    pub fn with_meter(
+        sample_rate: f32,
+        target_frames: f32,
+        meter: DriftMeter,
+    ) -> Result<Self, DspError> {
+        let mut compensator = Self {
+            sample_rate: 0.0,
+            target_frames: 0.0,
+            smoothed_fill: None,
+            integral: 0.0,
+            ratio: 1.0,
+            meter,
+        };
+        compensator.set_sample_rate(sample_rate)?;
+        compensator.set_target_frames(target_frames)?;
+        Ok(compensator)
+    }
+
+    /// Follow a change of the buffer's sample rate (forgets the fill history)
+    pub fn set_sample_rate(&mut self, sample_rate: f32) -> Result<(), DspError> {
+        if sample_rate <= 0.0 || !sample_rate.is_finite() {
+            return Err(DspError::InvalidSampleRate(sample_rate));
+        }
+        self.sample_rate = sample_rate;
+        self.reset();
+        Ok(())
+    }
+
+    /// Sample rate of the buffer's audio (Hz)
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Change the fill level to hold (frames)
+    pub fn set_target_frames(&mut self, target_frames: f32) -> Result<(), DspError> {
+        if target_frames < 0.0 || !target_frames.is_finite() {
+            return Err(DspError::InvalidParameter {
+                name: "drift target",
+                value: target_frames,
+            });
+        }
+        self.target_frames = target_frames;
+        Ok(())
+    }
+
+    /// Fill level being held (frames)
+    pub fn target_frames(&self) -> f32 {
+        self.target_frames
+    }
+
+    /// Feed the current fill level and return the ratio to resample at
+    ///
+    /// The reader drains the ring in whole buffers, so the raw fill level
+    /// seen by the writer jumps by a buffer as the two clocks' callbacks slip
+    /// past each other. Pass the fill minus the frames the reader has consumed
+    /// in spirit since its last read (time since that read × sample rate),
+    /// which removes the sawtooth; it may dip below zero.
+    ///
+    /// `elapsed_frames` is how much audio (at the buffer's rate) passed since
+    /// the previous update, normally the frames just written.
+    ///
+    /// # Real-time Safety
+    /// No allocations, no locks.
+    pub fn update(&mut self, fill_frames: f64, elapsed_frames: usize) -> f64 {
+        let rate = self.sample_rate as f64;
+        let dt = elapsed_frames as f64 / rate;
+        let smoothed = match self.smoothed_fill {
+            Some(previous) => {
+                previous + (fill_frames - previous) * (1.0 - (-dt / FILL_SMOOTHING_S).exp())
+            }
+            None => fill_frames,
+        };
+        self.smoothed_fill = Some(smoothed);
+
+        let error = (smoothed - self.target_frames as f64) / rate;
+        let proportional = KP * error;
+        let integral = self.integral + KI * error * dt;
+        if (proportional + integral).abs() < MAX_DRIFT {
+            self.integral = integral;
+        }
+        let correction = (proportional + self.integral).clamp(-MAX_DRIFT, MAX_DRIFT);
+        self.ratio = 1.0 - correction;
+
+        self.meter.publish(DriftStats {
+            ratio: self.ratio as f32,
+            buffer_ms: (smoothed * 1000.0 / rate) as f32,
+            target_ms: (self.target_frames as f64 * 1000.0 / rate) as f32,
+        });
+        self.ratio
+    }
+
+    /// Latest ratio (1.0 before the first update)
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    /// Handle for reading the state from other threads
+    pub fn meter(&self) -> &DriftMeter {
+        &self.meter
+    }
+
+    /// Forget the fill history and learned offset
+    pub fn reset(&mut self) {
+        self.smoothed_fill = None;
+        self.integral = 0.0;
+        self.ratio = 1.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUANTUM: usize = 1024;
+
+    /// Run a producer whose clock is `offset` fast against a 48 kHz consumer,
+    /// each moving 1024-frame quanta on its own clock, and return the
+    /// compensator plus the consumer's underruns after the first minute
+    fn simulate(offset: f64, seconds: f64) -> (DriftCompensator, usize) {
+        let mut compensator = DriftCompensator::new(48000.0, 2.0 * QUANTUM as f32).unwrap();
+        let producer_period = QUANTUM as f64 / (48000.0 * (1.0 + offset));
+        let consumer_period = QUANTUM as f64 / 48000.0;
+        let (mut producer_time, mut consumer_time) = (0.0, consumer_period / 2.0);
+        let mut last_read = 0.0;
+        let mut fill = 0.0_f64;
+        let mut underruns = 0;
+        while consumer_time < seconds {
+            if producer_time <= consumer_time {
+                // Producer writes one quantum of its clock, resampled
+                fill += QUANTUM as f64 * compensator.ratio();
+                let drained = (producer_time - last_read) * 48000.0;
+                let ratio = compensator.update(fill.floor() - drained, QUANTUM);
+                assert!((ratio - 1.0).abs() <= MAX_DRIFT + 1e-12);
+                producer_time += producer_period;
+            } else {
+                // Consumer reads a whole quantum or nothing
+                if fill >= QUANTUM as f64 {
+                    fill -= QUANTUM as f64;
+                    last_read = consumer_time;
+                } else if consumer_time > 60.0 {
+                    underruns += 1;
+                }
+                consumer_time += consumer_period;
+            }
+        }
+        (compensator, underruns)
+    }
+
+    #[test]
+    fn test_tracks_fast_and_slow_sources() {
+        for offset in [300e-6, -300e-6] {
+            let (compensator, underruns) = simulate(offset, 600.0);
+            // Ratio cancels the clock offset
+            let expected = 1.0 / (1.0 + offset);
+            assert!(
+                (compensator.ratio() - expected).abs() < 20e-6,
+                "offset {} ratio {}",
+                offset,
+                compensator.ratio()
+            );
+            // Buffer settles at the target instead of slowly draining or filling
+            assert_eq!(underruns, 0, "offset {}", offset);
+            let stats = compensator.meter().stats();
+            assert!(
+                (stats.buffer_ms - stats.target_ms).abs() < 5.0,
+                "{:?}",
+                stats
+            );
+        }
+    }
+
+    #[test]
+    fn test_correction_is_limited() {
+        let mut compensator = DriftCompensator::new(48000.0, 1024.0).unwrap();
+        // A full buffer far above target slows output by at most MAX_DRIFT
+        for _ in 0..1000 {
+            compensator.update(48000.0, 1024);
+        }
+        assert_eq!(compensator.ratio(), 1.0 - MAX_DRIFT);
+        assert!((compensator.meter().stats().drift_ppm() + 2000.0).abs() < 1.0);
+
+        compensator.reset();
+        assert_eq!(compensator.ratio(), 1.0);
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        assert!(DriftCompensator::new(0.0, 1024.0).is_err());
+        assert!(DriftCompensator::new(48000.0, -1.0).is_err());
+        assert_eq!(DriftMeter::default().stats(), DriftStats::default());
+    }
+}
@@ -0,0 +1,396 @@
+//! Dynamic EQ Bands
+//!
+//! A dynamic band only applies its gain when the signal in its own frequency
+//! range crosses a threshold, e.g. "cut 4 kHz by up to 6 dB only when it gets
+//! harsh" or "boost the bass only at low levels".
+//!
+//! # Algorithm
+//!
+//! ```text
+//! input ─┬──────────────────────► band biquad(s) ──► output
+//!        │                              ▲ gain (every 32 samples)
+//!        └─► sidechain filter ─► RMS ─► gain computer ─► attack/release
+//! ```
+//!
+//! - The sidechain isolates the band: band-pass around peaking and tilt
+//!   bands, low-pass under low shelves, high-pass over high shelves.
+//! - Detection is linked across channels (the loudest channel drives all).
+//! - Past the threshold the gain moves by `1 - 1/ratio` dB per dB, up to the
+//!   band's own gain, which is therefore the most it will ever apply.
+//!
+//! Only bands whose type uses a gain (shelves, peaking, tilt) can be dynamic.
+//! Linear-phase mode and the EQ response curve show dynamic bands at full gain.
+
+use biquad::{Biquad, Coefficients, DirectForm2Transposed, ToHertz, Type, Q_BUTTERWORTH_F32};
+
+use crate::eq::{unity_coefficients, Band, BandType, MAX_CHANNELS};
+use crate::error::DspError;
+
+/// Averaging window of the sidechain RMS detector
+const RMS_WINDOW_MS: f32 = 10.0;
+
+/// Which side of the threshold engages a dynamic band
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DynamicDirection {
+    /// Acts when the band gets louder than the threshold (taming harshness)
+    #[default]
+    Above,
+    /// Acts when the band falls below the threshold (lifting quiet passages)
+    Below,
+}
+
+impl DynamicDirection {
+    /// Compact encoding for lock-free storage in an atomic
+    pub(crate) fn to_u8(self) -> u8 {
+        match self {
+            DynamicDirection::Above => 0,
+            DynamicDirection::Below => 1,
+        }
+    }
+
+    /// Inverse of `to_u8` (unknown values fall back to above)
+    pub(crate) fn from_u8(value: u8) -> Self {
+        match value {
+            1 => DynamicDirection::Below,
+            _ => DynamicDirection::Above,
+        }
+    }
+}
+
+/// Threshold, ratio and timing of a dynamic EQ band
+///
+/// The band's gain is the maximum it applies once fully engaged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BandDynamics {
+    /// Band level where the gain starts to apply (-80 to 0 dBFS)
+    pub threshold_db: f32,
+    /// Level change past the threshold per dB of gain (1 to 20; 1 = static off)
+    pub ratio: f32,
+    /// Time to reach ~63% of a gain increase (0.1 to 500 ms)
+    pub attack_ms: f32,
+    /// Time to fall back ~63% toward flat (5 to 5000 ms)
+    pub release_ms: f32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub direction: DynamicDirection,
+}
+
+impl Default for BandDynamics {
+    fn default() -> Self {
+        Self {
+            threshold_db: -30.0,
+            ratio: 4.0,
+            attack_ms: 5.0,
+            release_ms: 100.0,
+            direction: DynamicDirection::Above,
+        }
+    }
+}
+
+impl BandDynamics {
+    /// Check every parameter is in range
+    pub fn validate(&self) -> Result<(), DspError> {
+        let checks = [
+            ("threshold_db", self.threshold_db, -80.0, 0.0),
+            ("ratio", self.ratio, 1.0, 20.0),
+            ("attack_ms", self.attack_ms, 0.1, 500.0),
+            ("release_ms", self.release_ms, 5.0, 5000.0),
+        ];
+        for (name, value, min, max) in checks {
+            if !(min..=max).contains(&value) {
+                return Err(DspError::InvalidParameter { name, value });
+            }
+        }
+        Ok(())
+    }
+
+    /// Static gain (dB) for a sidechain level, limited to `max_gain_db`
+    ///
+    /// Zero on the quiet side of the threshold; past it the magnitude grows
+    /// by `1 - 1/ratio` per dB until it reaches `max_gain_db`, whose sign
+    /// decides between cut and boost.
+    #[inline]
+    pub fn gain_db(&self, level_db: f32, max_gain_db: f32) -> f32 {
+        let past = match self.direction {
+            DynamicDirection::Above => level_db - self.threshold_db,
+            DynamicDirection::Below => self.threshold_db - level_db,
+        };
+        if past <= 0.0 {
+            return 0.0;
+        }
+        let amount = past * (1.0 - 1.0 / self.ratio);
+        amount.min(max_gain_db.abs()).copysign(max_gain_db)
+    }
+}
+
+/// Sidechain detector and gain envelope of one dynamic band
+///
+/// Owned by the equalizer, one per band slot, so nothing is allocated when
+/// a band becomes dynamic.
+pub(crate) struct DynamicDetector {
+    filters: [DirectForm2Transposed<f32>; MAX_CHANNELS],
+    attack_coeff: f32,
+    release_coeff: f32,
+    rms_coeff: f32,
+    mean_square: f32,
+    /// Gain currently applied (dB)
+    gain_db: f32,
+}
+
+impl DynamicDetector {
+    pub(crate) fn new() -> Self {
+        Self {
+            filters: core::array::from_fn(|_| {
+                DirectForm2Transposed::<f32>::new(unity_coefficients())
+            }),
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            rms_coeff: 0.0,
+            mean_square: 0.0,
+            gain_db: 0.0,
+        }
+    }
+
+    /// Follow a band's shape and timing
+    ///
+    /// Filter and envelope state are kept, so edits while the band is engaged
+    /// don't make it jump back to flat. A sidechain that cannot be built at
+    /// this sample rate keeps its previous shape, as the band itself does.
+    ///
+    /// # Real-time Safety
+    /// No allocations.
+    pub(crate) fn configure(&mut self, band: &Band, dynamics: &BandDynamics, sample_rate: f32) {
+        let coeff = |ms: f32| (-1000.0 / (ms * sample_rate)).exp();
+        self.attack_coeff = coeff(dynamics.attack_ms);
+        self.release_coeff = coeff(dynamics.release_ms);
+        self.rms_coeff = coeff(RMS_WINDOW_MS);
+
+        let (filter, q) = match band.band_type {
+            BandType::LowShelf => (Type::LowPass, Q_BUTTERWORTH_F32),
+            BandType::HighShelf => (Type::HighPass, Q_BUTTERWORTH_F32),
+            _ => (Type::BandPass, band.q),
+        };
+        let Ok(mut coeffs) =
+            Coefficients::<f32>::from_params(filter, sample_rate.hz(), band.frequency.hz(), q)
+        else {
+            return;
+        };
+        if matches!(filter, Type::BandPass) {
+            // 0 dB peak, so the threshold reads as the level inside the band
+            coeffs.b0 /= q;
+            coeffs.b2 /= q;
+        }
+        for filter in self.filters.iter_mut() {
+            filter.update_coefficients(coeffs);
+        }
+    }
+
+    /// Feed one interleaved frame to the sidechain and update the gain
+    ///
+    /// # Real-time Safety
+    /// No allocations, no locks.
+    #[inline]
+    pub(crate) fn process_frame(
+        &mut self,
+        frame: &[f32],
+        dynamics: &BandDynamics,
+        max_gain_db: f32,
+    ) {
+        // Linked detection: the loudest channel drives the band
+        let peak_square =
+            frame
+                .iter()
+                .zip(self.filters.iter_mut())
+                .fold(0.0_f32, |acc, (&sample, filter)| {
+                    let band = filter.run(sample);
+                    acc.max(band * band)
+                });
+        self.mean_square = peak_square + self.rms_coeff * (self.mean_square - peak_square);
+        let level_db = 10.0 * self.mean_square.max(1e-12).log10();
+
+        let target = dynamics.gain_db(level_db, max_gain_db);
+        let coeff = if target.abs() > self.gain_db.abs() {
+            self.attack_coeff
+        } else {
+            self.release_coeff
+        };
+        self.gain_db = target + coeff * (self.gain_db - target);
+    }
+
+    /// Gain (dB) the band currently applies
+    pub(crate) fn gain_db(&self) -> f32 {
+        self.gain_db
+    }
+
+    /// Clear sidechain state and return to flat
+    pub(crate) fn reset(&mut self) {
+        for filter in self.filters.iter_mut() {
+            filter.reset_state();
+        }
+        self.mean_square = 0.0;
+        self.gain_db = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eq::{BandParams, Equalizer, FilterSlope};
+
+    #[test]
+    fn test_gain_computer() {
+        let cut = BandDynamics::default();
+        // Quiet side of the threshold: flat
+        assert_eq!(cut.gain_db(-40.0, -6.0), 0.0);
+        // 4 dB over at 4:1 cuts 3 dB
+        assert!((cut.gain_db(-26.0, -6.0) + 3.0).abs() < 1e-5);
+        // Never beyond the band gain
+        assert_eq!(cut.gain_db(0.0, -6.0), -6.0);
+
+        let lift = BandDynamics {
+            direction: DynamicDirection::Below,
+            ..cut
+        };
+        assert_eq!(lift.gain_db(-20.0, 8.0), 0.0);
+        assert_eq!(lift.gain_db(-70.0, 8.0), 8.0);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(BandDynamics::default().validate().is_ok());
+        let bad = [
+            BandDynamics {
+                threshold_db: 3.0,
+                ..Default::default()
+            },
+            BandDynamics {
+                ratio: 0.5,
+                ..Default::default()
+            },
+            BandDynamics {
+                attack_ms: 0.0,
+                ..Default::default()
+            },
+            BandDynamics {
+                release_ms: f32::NAN,
+                ..Default::default()
+            },
+        ];
+        for dynamics in bad {
+            assert!(matches!(
+                dynamics.validate(),
+                Err(DspError::InvalidParameter { .. })
+            ));
+        }
+
+        // Invalid dynamics make the whole band shape invalid
+        let mut params = crate::eq::band_layout(10, 5).params();
+        params.dynamics = Some(bad[1]);
+        assert!(params.validate().is_err());
+    }
+
+    /// One dynamic 4 kHz peaking band cutting up to 6 dB above -30 dBFS
+    fn harshness_eq() -> Equalizer {
+        let mut eq = Equalizer::with_band_count(48000.0, 1).unwrap();
+        let params = BandParams {
+            frequency: 4000.0,
+            q: 1.0,
+            band_type: BandType::Peaking,
+            slope: FilterSlope::Db12,
+            enabled: true,
+            dynamics: Some(BandDynamics {
+                ratio: 20.0,
+                ..Default::default()
+            }),
+        };
+        eq.set_band_params(0, params).unwrap();
+        eq.set_band_gain(0, -6.0).unwrap();
+        eq.finish_smoothing();
+        eq
+    }
+
+    /// Output/input peak ratio (dB) over the last 10 ms of a 0.5 s sine
+    fn sine_gain_db(eq: &mut Equalizer, freq: f32, amplitude: f32) -> f32 {
+        let mut out_peak = 0.0_f32;
+        for i in 0..24000 {
+            let x = amplitude * (2.0 * std::f32::consts::PI * freq * i as f32 / 48000.0).sin();
+            let (y, _) = eq.process_sample(x, x);
+            if i >= 23520 {
+                out_peak = out_peak.max(y.abs());
+            }
+        }
+        20.0 * (out_peak / amplitude).log10()
+    }
+
+    #[test]
+    fn test_cut_only_when_loud() {
+        let mut eq = harshness_eq();
+        let quiet = sine_gain_db(&mut eq, 4000.0, 0.01);
+        assert!(
+            quiet.abs() < 0.3,
+            "Quiet 4 kHz should pass flat, got {quiet} dB"
+        );
+        assert!(eq.dynamic_gain_db(0).unwrap().abs() < 0.1);
+
+        let mut eq = harshness_eq();
+        let loud = sine_gain_db(&mut eq, 4000.0, 0.8);
+        assert!(
+            (loud + 6.0).abs() < 0.5,
+            "Loud 4 kHz should be cut ~6 dB, got {loud} dB"
+        );
+
+        // Loud content outside the band doesn't trigger it
+        let mut eq = harshness_eq();
+        let bass = sine_gain_db(&mut eq, 100.0, 0.8);
+        assert!(
+            bass.abs() < 0.3,
+            "Loud bass should pass flat, got {bass} dB"
+        );
+    }
+
+    #[test]
+    fn test_boost_only_when_quiet() {
+        let mut eq = Equalizer::with_band_count(48000.0, 1).unwrap();
+        let mut params = band_params_low_shelf();
+        params.dynamics = Some(BandDynamics {
+            threshold_db: -30.0,
+            ratio: 20.0,
+            direction: DynamicDirection::Below,
+            ..Default::default()
+        });
+        eq.set_band_params(0, params).unwrap();
+        eq.set_band_gain(0, 6.0).unwrap();
+        eq.finish_smoothing();
+
+        let quiet = sine_gain_db(&mut eq, 50.0, 0.001);
+        assert!(
+            (quiet - 6.0).abs() < 0.5,
+            "Quiet bass should get ~6 dB, got {quiet} dB"
+        );
+        let loud = sine_gain_db(&mut eq, 50.0, 0.8);
+        assert!(
+            loud.abs() < 0.3,
+            "Loud bass should pass flat, got {loud} dB"
+        );
+
+        // Dropping the dynamics makes it an ordinary shelf again
+        params.dynamics = None;
+        eq.set_band_params(0, params).unwrap();
+        assert_eq!(eq.dynamic_gain_db(0), None);
+        let static_gain = sine_gain_db(&mut eq, 50.0, 0.8);
+        assert!((static_gain - 6.0).abs() < 0.5);
+    }
+
+    fn band_params_low_shelf() -> BandParams {
+        BandParams {
+            frequency: 120.0,
+            q: Q_BUTTERWORTH_F32,
+            band_type: BandType::LowShelf,
+            slope: FilterSlope::Db12,
+            enabled: true,
+            dynamics: None,
+        }
+    }
+}
@@ -1,11 +1,65 @@
-//! 10-Band Parametric Equalizer
+//! N-Band Parametric Equalizer
 //!
 //! Implements a cascade of BiQuad filters for audio equalization.
 //! Based on the RBJ (Robert Bristow-Johnson) Audio EQ Cookbook.
+//!
+//! The band count is dynamic (1 to [`MAX_BANDS`]). Filter state is stored in
+//! fixed-size arrays sized for the maximum, so changing the band count at
+//! runtime never allocates:
+//!
+//! ```text
+//! filters: [F0][F1][F2] ... [Fn-1][ unused ... ][F30]
+//!          |<--- band_count --->|
+//! ```
+//!
+//! Every channel (up to [`MAX_CHANNELS`], enough for 7.1) has its own
+//! filter state, so interleaved buffers of any supported layout can be
+//! processed in place.
+//!
+//! Each band owns up to [`MAX_STAGES`] biquads. Most filter types use one;
+//! tilt uses two and steep high/low-pass slopes cascade up to four.
+//!
+//! Coefficients are designed in f64. Bands run in f32 unless their
+//! frequency is tiny next to the sample rate (see [`FilterPrecision`]): a
+//! 31 Hz shelf at 192 kHz has its poles so close to z = 1 that f32
+//! coefficients and state add audible noise and skew the response.
+//!
+//! Gain, frequency and Q changes are ramped rather than applied instantly:
+//! the heard value moves toward the target over [`DEFAULT_SMOOTHING_MS`] and
+//! coefficients are recomputed every 32 samples, which avoids zipper noise
+//! while a slider is dragged and clicks when a preset is applied. Filter type,
+//! slope and enable changes cannot be interpolated and take effect at once.
+//!
+//! Any gain band can also be dynamic (see the `dynamic_eq` module): a
+//! sidechain follows the level of its frequency range and the band's gain
+//! only applies past a threshold. Dynamic gains are refreshed on the same
+//! 32-sample grid as the ramps.
+//!
+//! [`EqConfig::auto_preamp_db`] finds the master gain that cancels the peak
+//! of the combined response, so boosts never push the output past 0 dB. The
+//! search is too slow for an audio callback: run it where the config is
+//! edited and hand the result to [`Equalizer::set_master_gain_db`].
+//!
+//! With [`PhaseMode::Linear`] the same response is realised as a linear-phase
+//! FIR instead (see the `linear_phase` module), trading latency for zero phase
+//! shift. Configs are shared between both modes unchanged.
+//!
+//! [`Equalizer::process_block`] is the fast path for whole buffers: it works
+//! through each 32-frame smoothing block with the channels and several
+//! pipelined cascade stages in SIMD lanes, and skips bands that are disabled
+//! or flat.
+//! Output is bit-identical to [`Equalizer::process_interleaved`].
+
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
 
 use biquad::{Biquad, Coefficients, DirectForm2Transposed, ToHertz, Type, Q_BUTTERWORTH_F32};
+use rustfft::num_complex::Complex;
 
+use crate::dynamic_eq::{BandDynamics, DynamicDetector, DynamicDirection};
 use crate::error::DspError;
+use crate::linear_phase::{LinearPhaseFir, PhaseMode};
+use crate::matched::{matched_coefficients, FilterDesign};
+use crate::simd::{biquad_pipeline, LaneCoefficients, Lanes};
 
 /// Standard EQ band frequencies (Hz) - ISO standard octave centers
 pub const EQ_BANDS: [f32; 10] = [
@@ -21,22 +75,514 @@ pub const EQ_BANDS: [f32; 10] = [
     16000.0, // Air
 ];
 
+/// Maximum number of bands supported by a single [`Equalizer`]
+///
+/// Large enough for a full 1/3-octave ISO graphic EQ.
+pub const MAX_BANDS: usize = 31;
+
+/// Maximum number of interleaved channels (7.1 surround)
+pub const MAX_CHANNELS: usize = 8;
+
+/// Maximum number of cascaded biquads per band (enough for 48 dB/oct)
+pub const MAX_STAGES: usize = 4;
+
+/// Default ramp time (ms) for gain, frequency and Q changes
+pub const DEFAULT_SMOOTHING_MS: f32 = 20.0;
+
+/// Sample rate to band frequency ratio above which [`FilterPrecision::Auto`]
+/// runs a band in f64 (48 Hz at 48 kHz, 192 Hz at 192 kHz)
+pub const DOUBLE_PRECISION_RATIO: f32 = 1000.0;
+
+/// Samples between coefficient updates while a ramp is running
+const SMOOTHING_BLOCK: usize = 32;
+
+/// Lanes in the block path's vectors (two f32x8 AVX or four f32x4 SSE/NEON registers)
+const SIMD_LANES: usize = 16;
+
+/// Filter state below this (-120 dB) is cleared once a band has gone flat,
+/// so the block path can start skipping it. Above f32 rounding noise, which
+/// would otherwise keep a flat band's state from ever settling under signal.
+const FLUSH_THRESHOLD: f32 = 1e-6;
+
+/// Log-spaced frequencies searched for the response peak by auto preamp
+/// (band centres are checked as well, so narrow peaks are not missed)
+const PREAMP_SEARCH_POINTS: usize = 128;
+
+/// Stage Qs for a 4th-order (24 dB/oct) Butterworth cascade
+pub(crate) const BUTTERWORTH_Q_4: [f32; 2] = [0.541_196_1, 1.306_563];
+
+/// Stage Qs for an 8th-order (48 dB/oct) Butterworth cascade
+pub(crate) const BUTTERWORTH_Q_8: [f32; 4] = [0.509_795_6, 0.601_344_9, 0.899_976_2, 2.562_915_5];
+
+/// 3-band tone control layout (bass / mid / treble)
+pub const TONE_BANDS: [f32; 3] = [100.0, 1000.0, 10000.0];
+
+/// ISO 266 1/3-octave band centers (Hz) for a 31-band graphic EQ
+pub const ISO_31_BANDS: [f32; 31] = [
+    20.0, 25.0, 31.5, 40.0, 50.0, 63.0, 80.0, 100.0, 125.0, 160.0, 200.0, 250.0, 315.0, 400.0,
+    500.0, 630.0, 800.0, 1000.0, 1250.0, 1600.0, 2000.0, 2500.0, 3150.0, 4000.0, 5000.0, 6300.0,
+    8000.0, 10000.0, 12500.0, 16000.0, 20000.0,
+];
+
+/// Q for bands of a 31-band (1/3-octave) layout
+///
+/// Wider octave-spaced Q would make neighbouring 1/3-octave bands overlap heavily.
+const Q_THIRD_OCTAVE: f32 = 4.318;
+
+/// Center frequency of `index` in the standard layout for `band_count` bands
+///
+/// 3, 10 and 31 bands use [`TONE_BANDS`], [`EQ_BANDS`] and [`ISO_31_BANDS`].
+/// Any other count is spaced logarithmically between 31.25 Hz and 16 kHz.
+///
+/// Pure function with no allocation, so it can be used from the audio thread.
+pub fn band_layout_frequency(band_count: usize, index: usize) -> f32 {
+    match band_count {
+        3 => TONE_BANDS[index],
+        10 => EQ_BANDS[index],
+        31 => ISO_31_BANDS[index],
+        1 => 1000.0,
+        _ => {
+            // Geometric spacing: f = low * (high/low)^(i / (n-1))
+            let low = 31.25_f32;
+            let high = 16000.0_f32;
+            let t = index as f32 / (band_count - 1) as f32;
+            low * (high / low).powf(t)
+        }
+    }
+}
+
+/// Filter type for `index` in the standard layout for `band_count` bands
+///
+/// The outermost bands are shelves, everything in between is peaking.
+fn band_layout_type(band_count: usize, index: usize) -> BandType {
+    if band_count == 1 {
+        BandType::Peaking
+    } else if index == 0 {
+        BandType::LowShelf
+    } else if index == band_count - 1 {
+        BandType::HighShelf
+    } else {
+        BandType::Peaking
+    }
+}
+
+/// Band in the standard layout for `band_count` bands (flat gain)
+pub fn band_layout(band_count: usize, index: usize) -> Band {
+    let mut band = Band::new(
+        band_layout_frequency(band_count, index),
+        band_layout_type(band_count, index),
+    );
+    if band_count == 31 && band.band_type == BandType::Peaking {
+        band.q = Q_THIRD_OCTAVE;
+    }
+    band
+}
+
+/// Band shapes of the standard layout for `band_count` bands, one per filter slot
+///
+/// Used to seed lock-free per-slot storage. Slots past `band_count` repeat
+/// the last band; they are never processed.
+pub fn band_layout_params(band_count: usize) -> [BandParams; MAX_BANDS] {
+    let last = band_count.clamp(1, MAX_BANDS) - 1;
+    core::array::from_fn(|i| band_layout(last + 1, i.min(last)).params())
+}
+
+/// Biquad that passes audio through unchanged (used for unused filter slots)
+pub(crate) fn unity_coefficients<T: From<u8>>() -> Coefficients<T> {
+    Coefficients {
+        a1: T::from(0),
+        a2: T::from(0),
+        b0: T::from(1),
+        b1: T::from(0),
+        b2: T::from(0),
+    }
+}
+
+/// Round f64 coefficients to f32
+fn coefficients_f32(coeffs: &Coefficients<f64>) -> Coefficients<f32> {
+    Coefficients {
+        a1: coeffs.a1 as f32,
+        a2: coeffs.a2 as f32,
+        b0: coeffs.b0 as f32,
+        b1: coeffs.b1 as f32,
+        b2: coeffs.b2 as f32,
+    }
+}
+
+/// Whether a biquad's numerator equals its denominator (a flat response)
+///
+/// True for [`unity_coefficients`] and for shelf and peaking filters at 0 dB,
+/// whose cookbook numerator and denominator come out identical.
+fn is_unity(coeffs: &Coefficients<f32>) -> bool {
+    coeffs.b0 == 1.0 && coeffs.b1 == coeffs.a1 && coeffs.b2 == coeffs.a2
+}
+
+/// Run one sample through a band's f64 stages
+#[inline]
+fn run_f64(stages: &mut [DirectForm2Transposed<f64>], sample: f32) -> f32 {
+    stages
+        .iter_mut()
+        .fold(sample as f64, |x, filter| filter.run(x)) as f32
+}
+
 /// Filter type for each EQ band
-#[derive(Debug, Clone, Copy, PartialEq)]
+///
+/// Only the shelf, peaking and tilt types use the band gain; the others
+/// are fixed-response filters shaped by frequency, Q and (for high/low-pass)
+/// slope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum BandType {
     LowShelf,
     Peaking,
     HighShelf,
+    /// Removes content below the frequency (rumble filter)
+    HighPass,
+    /// Removes content above the frequency (hiss cut)
+    LowPass,
+    /// Narrow cut at the frequency, for hums and resonances
+    Notch,
+    /// Passes a band around the frequency at 0 dB peak gain
+    BandPass,
+    /// Flat magnitude, phase shift around the frequency
+    AllPass,
+    /// Tilts the spectrum around the frequency: positive gain boosts
+    /// highs and cuts lows by half the gain each
+    Tilt,
+}
+
+impl BandType {
+    /// Whether the band gain affects this filter type
+    pub fn uses_gain(self) -> bool {
+        matches!(
+            self,
+            BandType::LowShelf | BandType::Peaking | BandType::HighShelf | BandType::Tilt
+        )
+    }
+
+    /// Compact encoding for lock-free storage in an atomic
+    fn to_u8(self) -> u8 {
+        match self {
+            BandType::LowShelf => 0,
+            BandType::Peaking => 1,
+            BandType::HighShelf => 2,
+            BandType::HighPass => 3,
+            BandType::LowPass => 4,
+            BandType::Notch => 5,
+            BandType::BandPass => 6,
+            BandType::AllPass => 7,
+            BandType::Tilt => 8,
+        }
+    }
+
+    /// Inverse of `to_u8` (unknown values fall back to peaking)
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => BandType::LowShelf,
+            2 => BandType::HighShelf,
+            3 => BandType::HighPass,
+            4 => BandType::LowPass,
+            5 => BandType::Notch,
+            6 => BandType::BandPass,
+            7 => BandType::AllPass,
+            8 => BandType::Tilt,
+            _ => BandType::Peaking,
+        }
+    }
+}
+
+/// Roll-off steepness for high-pass and low-pass bands
+///
+/// Steeper slopes cascade Butterworth biquads, so the band's Q only shapes
+/// the knee at 12 dB/oct; 24 and 48 dB/oct are always maximally flat.
+/// Other filter types ignore the slope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum FilterSlope {
+    /// One biquad (2nd order)
+    #[default]
+    Db12,
+    /// Two biquads (4th order)
+    Db24,
+    /// Four biquads (8th order)
+    Db48,
+}
+
+impl FilterSlope {
+    /// Compact encoding for lock-free storage in an atomic
+    fn to_u8(self) -> u8 {
+        match self {
+            FilterSlope::Db12 => 0,
+            FilterSlope::Db24 => 1,
+            FilterSlope::Db48 => 2,
+        }
+    }
+
+    /// Inverse of `to_u8` (unknown values fall back to 12 dB/oct)
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => FilterSlope::Db24,
+            2 => FilterSlope::Db48,
+            _ => FilterSlope::Db12,
+        }
+    }
+}
+
+/// Arithmetic an equalizer's biquads run in
+///
+/// f64 costs roughly twice as much per band, so by default only the bands
+/// that need it use it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum FilterPrecision {
+    /// f64 for bands below sample rate / [`DOUBLE_PRECISION_RATIO`], f32 otherwise
+    #[default]
+    Auto,
+    /// f32 for every band
+    Single,
+    /// f64 for every band
+    Double,
+}
+
+impl FilterPrecision {
+    /// Whether a band at `frequency` runs in f64 at `sample_rate`
+    pub fn uses_double(self, frequency: f32, sample_rate: f32) -> bool {
+        match self {
+            FilterPrecision::Auto => sample_rate > frequency * DOUBLE_PRECISION_RATIO,
+            FilterPrecision::Single => false,
+            FilterPrecision::Double => true,
+        }
+    }
+}
+
+/// Shape of an EQ band: everything except its gain
+///
+/// Gains change constantly while a slider is dragged and travel on their own
+/// path. Shape edits are sent as one unit so frequency, Q and type always
+/// land together.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BandParams {
+    pub frequency: f32,
+    pub q: f32,
+    pub band_type: BandType,
+    /// Only used by high-pass and low-pass bands
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub slope: FilterSlope,
+    pub enabled: bool,
+    /// Makes the gain level-dependent; `None` for an ordinary static band
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dynamics: Option<BandDynamics>,
+}
+
+impl BandParams {
+    /// Check that frequency and Q are positive and finite, and any dynamics in range
+    ///
+    /// The Nyquist limit depends on the sample rate, so it is checked later
+    /// when coefficients are built.
+    pub fn validate(&self) -> Result<(), DspError> {
+        if !self.frequency.is_finite() || self.frequency <= 0.0 {
+            return Err(DspError::InvalidFrequency(self.frequency));
+        }
+        if !self.q.is_finite() || self.q <= 0.0 {
+            return Err(DspError::InvalidQ(self.q));
+        }
+        if let Some(dynamics) = &self.dynamics {
+            dynamics.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// Lock-free storage for [`BandParams`]
+///
+/// Used to hand band shapes to audio callbacks without locks. Each field is
+/// its own atomic, so a reader racing a writer may briefly see a mix of old
+/// and new fields; writers bump an update counter after storing and readers
+/// re-read on the next change, so a torn read lasts at most one buffer.
+pub struct AtomicBandParams {
+    frequency: AtomicU32,
+    q: AtomicU32,
+    band_type: AtomicU8,
+    slope: AtomicU8,
+    enabled: AtomicBool,
+    dynamic: AtomicBool,
+    threshold: AtomicU32,
+    ratio: AtomicU32,
+    attack: AtomicU32,
+    release: AtomicU32,
+    direction: AtomicU8,
+}
+
+impl AtomicBandParams {
+    pub fn new(params: BandParams) -> Self {
+        let atomic = Self {
+            frequency: AtomicU32::new(0),
+            q: AtomicU32::new(0),
+            band_type: AtomicU8::new(0),
+            slope: AtomicU8::new(0),
+            enabled: AtomicBool::new(false),
+            dynamic: AtomicBool::new(false),
+            threshold: AtomicU32::new(0),
+            ratio: AtomicU32::new(0),
+            attack: AtomicU32::new(0),
+            release: AtomicU32::new(0),
+            direction: AtomicU8::new(0),
+        };
+        atomic.store(params);
+        atomic
+    }
+
+    /// Read the current parameters (no allocation, safe in audio callbacks)
+    pub fn load(&self) -> BandParams {
+        let dynamics = self.dynamic.load(Ordering::Relaxed).then(|| BandDynamics {
+            threshold_db: f32::from_bits(self.threshold.load(Ordering::Relaxed)),
+            ratio: f32::from_bits(self.ratio.load(Ordering::Relaxed)),
+            attack_ms: f32::from_bits(self.attack.load(Ordering::Relaxed)),
+            release_ms: f32::from_bits(self.release.load(Ordering::Relaxed)),
+            direction: DynamicDirection::from_u8(self.direction.load(Ordering::Relaxed)),
+        });
+        BandParams {
+            frequency: f32::from_bits(self.frequency.load(Ordering::Relaxed)),
+            q: f32::from_bits(self.q.load(Ordering::Relaxed)),
+            band_type: BandType::from_u8(self.band_type.load(Ordering::Relaxed)),
+            slope: FilterSlope::from_u8(self.slope.load(Ordering::Relaxed)),
+            enabled: self.enabled.load(Ordering::Relaxed),
+            dynamics,
+        }
+    }
+
+    /// Store new parameters
+    ///
+    /// Callers should validate first and signal readers afterwards.
+    pub fn store(&self, params: BandParams) {
+        self.frequency.store(params.frequency.to_bits(), Ordering::Relaxed);
+        self.q.store(params.q.to_bits(), Ordering::Relaxed);
+        self.band_type.store(params.band_type.to_u8(), Ordering::Relaxed);
+        self.slope.store(params.slope.to_u8(), Ordering::Relaxed);
+        self.enabled.store(params.enabled, Ordering::Relaxed);
+        // Dynamics fields keep their last values while off; only the flag matters
+        if let Some(dynamics) = params.dynamics {
+            self.threshold.store(dynamics.threshold_db.to_bits(), Ordering::Relaxed);
+            self.ratio.store(dynamics.ratio.to_bits(), Ordering::Relaxed);
+            self.attack.store(dynamics.attack_ms.to_bits(), Ordering::Relaxed);
+            self.release.store(dynamics.release_ms.to_bits(), Ordering::Relaxed);
+            self.direction.store(dynamics.direction.to_u8(), Ordering::Relaxed);
+        }
+        self.dynamic.store(params.dynamics.is_some(), Ordering::Relaxed);
+    }
+}
+
+impl std::fmt::Debug for AtomicBandParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.load().fmt(f)
+    }
 }
 
 /// Single EQ band configuration
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Band {
     pub frequency: f32,
     pub gain_db: f32,
     pub q: f32,
     pub band_type: BandType,
+    pub slope: FilterSlope,
     pub enabled: bool,
+    pub dynamics: Option<BandDynamics>,
+}
+
+/// Biquad stages for one band, as produced by [`Band::to_coefficients`]
+///
+/// Only the first `len` stages are used; the rest are unity.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BandCoefficients {
+    stages: [Coefficients<f64>; MAX_STAGES],
+    len: usize,
+    /// Frequency the band was designed at (picks its precision)
+    frequency: f32,
+}
+
+impl BandCoefficients {
+    /// The stages in use
+    pub(crate) fn stages(&self) -> &[Coefficients<f64>] {
+        &self.stages[..self.len]
+    }
+
+    /// The stages rounded to f32
+    fn stages_f32(&self) -> [Coefficients<f32>; MAX_STAGES] {
+        self.stages.map(|stage| coefficients_f32(&stage))
+    }
+
+    /// Squared magnitude of the cascade at the frequency where cos(w) = `cos_w`
+    ///
+    /// |b0 + b1 z^-1 + b2 z^-2|^2 on the unit circle expands to
+    /// b0^2 + b1^2 + b2^2 + 2 b1 (b0 + b2) cos w + 2 b0 b2 cos 2w (a0 = 1).
+    fn magnitude_squared(&self, cos_w: f64) -> f64 {
+        let cos_2w = 2.0 * cos_w * cos_w - 1.0;
+        self.stages[..self.len]
+            .iter()
+            .map(|c| {
+                let (b0, b1, b2, a1, a2) = (c.b0, c.b1, c.b2, c.a1, c.a2);
+                let num = b0 * b0 + b1 * b1 + b2 * b2 + 2.0 * b1 * (b0 + b2) * cos_w + 2.0 * b0 * b2 * cos_2w;
+                let den = 1.0 + a1 * a1 + a2 * a2 + 2.0 * a1 * (1.0 + a2) * cos_w + 2.0 * a2 * cos_2w;
+                num / den
+            })
+            .product()
+    }
+
+    /// Complex response and group delay (in samples) of the cascade at `w` rad/sample
+    ///
+    /// For a polynomial P(z) = sum p_k z^-k the group delay is
+    /// Re(sum k p_k e^-jwk / P(e^jw)); a stage's delay is that of its
+    /// numerator minus that of its denominator.
+    pub(crate) fn response(&self, w: f64) -> (Complex<f64>, f64) {
+        let z1 = Complex::from_polar(1.0, -w);
+        let z2 = z1 * z1;
+        let mut response = Complex::new(1.0, 0.0);
+        let mut group_delay = 0.0;
+        for c in &self.stages[..self.len] {
+            let (b0, b1, b2, a1, a2) = (c.b0, c.b1, c.b2, c.a1, c.a2);
+            let num = z1 * b1 + z2 * b2 + b0;
+            let den = z1 * a1 + z2 * a2 + 1.0;
+            response *= num / den;
+
+            let num_delay = ((z1 * b1 + z2 * (2.0 * b2)) / num).re;
+            let den_delay = ((z1 * a1 + z2 * (2.0 * a2)) / den).re;
+            // A zero exactly on the unit circle (notch centre) has no defined delay
+            if num_delay.is_finite() {
+                group_delay += num_delay;
+            }
+            group_delay -= den_delay;
+        }
+        (response, group_delay)
+    }
+}
+
+/// Progress of one band's parameter ramp
+#[derive(Debug, Clone, Copy)]
+struct BandRamp {
+    /// Band as currently heard; coefficients are built from this
+    current: Band,
+    gain_step: f32,
+    log_frequency_step: f32,
+    log_q_step: f32,
+    blocks_left: u32,
+}
+
+impl BandRamp {
+    /// A ramp that has already reached `band`
+    fn settled(band: Band) -> Self {
+        Self {
+            current: band,
+            gain_step: 0.0,
+            log_frequency_step: 0.0,
+            log_q_step: 0.0,
+            blocks_left: 0,
+        }
+    }
 }
 
 impl Band {
@@ -46,91 +592,232 @@ impl Band {
             gain_db: 0.0,
             q: Q_BUTTERWORTH_F32, // ~0.707, gives smooth response
             band_type,
+            slope: FilterSlope::Db12,
             enabled: true,
+            dynamics: None,
         }
     }
 
-    /// Convert dB gain to linear amplitude
-    /// Formula: amplitude = 10^(dB/20)
-    fn db_to_amplitude(db: f32) -> f32 {
-        10.0_f32.powf(db / 20.0)
+    /// Shape of this band (frequency, Q, type, enabled, dynamics)
+    pub fn params(&self) -> BandParams {
+        BandParams {
+            frequency: self.frequency,
+            q: self.q,
+            band_type: self.band_type,
+            slope: self.slope,
+            enabled: self.enabled,
+            dynamics: self.dynamics,
+        }
     }
 
-    /// Generate BiQuad coefficients for this band
+    /// Replace the shape of this band, keeping its gain
+    pub fn set_params(&mut self, params: BandParams) {
+        self.frequency = params.frequency;
+        self.q = params.q;
+        self.band_type = params.band_type;
+        self.slope = params.slope;
+        self.enabled = params.enabled;
+        self.dynamics = params.dynamics;
+    }
+
+    /// Dynamics that are actually heard: enabled bands of a gain type only
+    pub fn active_dynamics(&self) -> Option<&BandDynamics> {
+        self.dynamics
+            .as_ref()
+            .filter(|_| self.enabled && self.band_type.uses_gain())
+    }
+
+    /// Number of biquads this band cascades (1 to [`MAX_STAGES`])
+    pub fn stage_count(&self) -> usize {
+        match (self.band_type, self.slope) {
+            (BandType::HighPass | BandType::LowPass, FilterSlope::Db24) => 2,
+            (BandType::HighPass | BandType::LowPass, FilterSlope::Db48) => 4,
+            (BandType::Tilt, _) => 2,
+            _ => 1,
+        }
+    }
+
+    /// Generate BiQuad coefficients for every stage of this band
+    ///
+    /// Note: biquad's shelf/peaking types take the gain in dB directly
     /// Rust pattern: `to_*` methods on Copy types take self by value since Copy is cheap
-    fn to_coefficients(self, sample_rate: f32) -> Result<Coefficients<f32>, DspError> {
-        // Rust pattern: `?` operator propagates errors up the call stack
-        // This is idiomatic Rust error handling - no exceptions, explicit Result types
-        let freq = self.frequency.hz();
-        let fs = sample_rate.hz();
-
-        let coeffs = match self.band_type {
-            BandType::LowShelf => {
-                Coefficients::<f32>::from_params(
-                    Type::LowShelf(Self::db_to_amplitude(self.gain_db)),
-                    fs,
-                    freq,
-                    self.q,
-                )
-            }
-            BandType::Peaking => {
-                Coefficients::<f32>::from_params(
-                    Type::PeakingEQ(Self::db_to_amplitude(self.gain_db)),
-                    fs,
-                    freq,
-                    self.q,
-                )
-            }
-            BandType::HighShelf => {
-                Coefficients::<f32>::from_params(
-                    Type::HighShelf(Self::db_to_amplitude(self.gain_db)),
-                    fs,
-                    freq,
-                    self.q,
-                )
-            }
+    pub(crate) fn to_coefficients(
+        self,
+        sample_rate: f32,
+        design: FilterDesign,
+    ) -> Result<BandCoefficients, DspError> {
+        let mut out = BandCoefficients {
+            stages: [unity_coefficients(); MAX_STAGES],
+            len: self.stage_count(),
+            frequency: self.frequency,
         };
+        let (q, gain_db) = (self.q as f64, self.gain_db as f64);
 
-        coeffs.map_err(|_| DspError::InvalidCoefficients {
-            frequency: self.frequency,
-            sample_rate,
+        match (self.band_type, self.slope) {
+            (BandType::HighPass | BandType::LowPass, FilterSlope::Db24) => {
+                for (stage, q) in out.stages.iter_mut().zip(BUTTERWORTH_Q_4) {
+                    *stage = self.biquad(self.pass_type(), q as f64, sample_rate, design)?;
+                }
+            }
+            (BandType::HighPass | BandType::LowPass, FilterSlope::Db48) => {
+                for (stage, q) in out.stages.iter_mut().zip(BUTTERWORTH_Q_8) {
+                    *stage = self.biquad(self.pass_type(), q as f64, sample_rate, design)?;
+                }
+            }
+            (BandType::HighPass | BandType::LowPass, FilterSlope::Db12) => {
+                out.stages[0] = self.biquad(self.pass_type(), q, sample_rate, design)?;
+            }
+            (BandType::Tilt, _) => {
+                // Opposing half-gain shelves at the same corner pivot around it
+                let half = gain_db / 2.0;
+                out.stages[0] = self.biquad(Type::LowShelf(-half), q, sample_rate, design)?;
+                out.stages[1] = self.biquad(Type::HighShelf(half), q, sample_rate, design)?;
+            }
+            (BandType::BandPass, _) => {
+                // biquad's band-pass peaks at Q (constant skirt gain);
+                // dividing the numerator by Q gives the 0 dB peak variant
+                let mut coeffs = self.biquad(Type::BandPass, q, sample_rate, design)?;
+                coeffs.b0 /= q;
+                coeffs.b2 /= q;
+                out.stages[0] = coeffs;
+            }
+            (BandType::LowShelf, _) => {
+                out.stages[0] = self.biquad(Type::LowShelf(gain_db), q, sample_rate, design)?;
+            }
+            (BandType::Peaking, _) => {
+                out.stages[0] = self.biquad(Type::PeakingEQ(gain_db), q, sample_rate, design)?;
+            }
+            (BandType::HighShelf, _) => {
+                out.stages[0] = self.biquad(Type::HighShelf(gain_db), q, sample_rate, design)?;
+            }
+            (BandType::Notch, _) => {
+                out.stages[0] = self.biquad(Type::Notch, q, sample_rate, design)?;
+            }
+            (BandType::AllPass, _) => {
+                out.stages[0] = self.biquad(Type::AllPass, q, sample_rate, design)?;
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// biquad filter type for a high-pass or low-pass band
+    fn pass_type(self) -> Type<f64> {
+        if self.band_type == BandType::HighPass {
+            Type::HighPass
+        } else {
+            Type::LowPass
+        }
+    }
+
+    /// One biquad at this band's frequency
+    ///
+    /// Falls back to the bilinear design where the matched one doesn't apply.
+    fn biquad(
+        self,
+        filter: Type<f64>,
+        q: f64,
+        sample_rate: f32,
+        design: FilterDesign,
+    ) -> Result<Coefficients<f64>, DspError> {
+        let (fs, f0) = (sample_rate as f64, self.frequency as f64);
+        if design == FilterDesign::Matched && f0 < fs / 2.0 {
+            if let Some(coeffs) = matched_coefficients(filter, fs, f0, q) {
+                return Ok(coeffs);
+            }
+        }
+        // Rust pattern: `map_err` converts the library error into our own error type
+        Coefficients::<f64>::from_params(filter, fs.hz(), f0.hz(), q).map_err(|_| {
+            DspError::InvalidCoefficients {
+                frequency: self.frequency,
+                sample_rate,
+            }
         })
     }
 }
 
-/// Complete EQ configuration for all 10 bands
+/// Complete EQ configuration
+///
+/// Holds between 1 and [`MAX_BANDS`] bands. The default is the classic
+/// 10-band octave layout from [`EQ_BANDS`].
 #[derive(Debug, Clone)]
 pub struct EqConfig {
-    pub bands: [Band; 10],
+    pub bands: Vec<Band>,
     pub master_gain_db: f32,
     pub enabled: bool,
+    /// Keep `master_gain_db` at [`EqConfig::auto_preamp_db`] so the largest
+    /// boost is cancelled (dynamic bands count at full gain). Applied by
+    /// [`EqConfig::apply_auto_preamp`]; the [`Equalizer`] only plays the result.
+    pub auto_preamp: bool,
+    /// f32 or f64 biquads
+    pub precision: FilterPrecision,
+    /// Bilinear (cookbook) or matched-magnitude biquads
+    pub design: FilterDesign,
 }
 
 impl Default for EqConfig {
     fn default() -> Self {
-        // Rust pattern: array initialization with index-based logic
-        // `core::array::from_fn` creates array by calling closure with each index
-        let bands = core::array::from_fn(|i| {
-            let band_type = match i {
-                0 => BandType::LowShelf,      // 31 Hz - shelf for sub-bass
-                9 => BandType::HighShelf,     // 16 kHz - shelf for air
-                _ => BandType::Peaking,       // All others are peaking
-            };
-            Band::new(EQ_BANDS[i], band_type)
-        });
+        Self::with_band_count(EQ_BANDS.len()).expect("10 bands is always a valid layout")
+    }
+}
 
-        Self {
+impl EqConfig {
+    /// Create a flat config using the standard layout for `band_count` bands
+    ///
+    /// See [`band_layout_frequency`] for how frequencies are chosen.
+    pub fn with_band_count(band_count: usize) -> Result<Self, DspError> {
+        if band_count == 0 || band_count > MAX_BANDS {
+            return Err(DspError::InvalidBandCount(band_count));
+        }
+
+        // Rust pattern: `(0..n).map(...).collect()` builds a Vec from a range
+        let bands = (0..band_count)
+            .map(|i| band_layout(band_count, i))
+            .collect();
+
+        Ok(Self {
             bands,
             master_gain_db: 0.0,
             enabled: true,
+            auto_preamp: false,
+            precision: FilterPrecision::Auto,
+            design: FilterDesign::Bilinear,
+        })
+    }
+
+    /// Create a flat config with custom band center frequencies
+    ///
+    /// The first band is a low shelf, the last a high shelf, the rest peaking.
+    pub fn from_frequencies(frequencies: &[f32]) -> Result<Self, DspError> {
+        let band_count = frequencies.len();
+        if band_count == 0 || band_count > MAX_BANDS {
+            return Err(DspError::InvalidBandCount(band_count));
         }
+
+        let bands = frequencies
+            .iter()
+            .enumerate()
+            .map(|(i, &freq)| Band::new(freq, band_layout_type(band_count, i)))
+            .collect();
+
+        Ok(Self {
+            bands,
+            master_gain_db: 0.0,
+            enabled: true,
+            auto_preamp: false,
+            precision: FilterPrecision::Auto,
+            design: FilterDesign::Bilinear,
+        })
     }
-}
 
-impl EqConfig {
-    /// Set gain for a specific band (0-9)
+    /// Number of bands in this config
+    pub fn band_count(&self) -> usize {
+        self.bands.len()
+    }
+
+    /// Set gain for a specific band (0 to band_count - 1)
     pub fn set_band_gain(&mut self, band_index: usize, gain_db: f32) -> Result<(), DspError> {
-        if band_index >= 10 {
+        if band_index >= self.bands.len() {
             return Err(DspError::InvalidBandIndex(band_index));
         }
         // Clamp gain to reasonable range (-24dB to +24dB)
@@ -138,126 +825,1009 @@ impl EqConfig {
         Ok(())
     }
 
-    /// Get all gains as a slice (useful for UI serialization)
-    pub fn get_gains(&self) -> [f32; 10] {
-        core::array::from_fn(|i| self.bands[i].gain_db)
+    /// Set frequency, Q, type and enable state for a specific band
+    pub fn set_band_params(&mut self, band_index: usize, params: BandParams) -> Result<(), DspError> {
+        if band_index >= self.bands.len() {
+            return Err(DspError::InvalidBandIndex(band_index));
+        }
+        params.validate()?;
+        self.bands[band_index].set_params(params);
+        Ok(())
+    }
+
+    /// Linear magnitude of all enabled bands at each frequency given as cos(w)
+    ///
+    /// Master gain is not included. Bands that cannot be realised at
+    /// `sample_rate` (e.g. above Nyquist) are skipped, as in processing.
+    ///
+    /// # Real-time Safety
+    /// No allocations.
+    pub(crate) fn magnitude_into(&self, sample_rate: f32, cos_w: &[f64], out: &mut [f32]) {
+        out.fill(1.0);
+        for band in self.bands.iter().filter(|band| band.enabled) {
+            let Ok(coeffs) = band.to_coefficients(sample_rate, self.design) else {
+                continue;
+            };
+            for (magnitude, &cos) in out.iter_mut().zip(cos_w) {
+                *magnitude *= coeffs.magnitude_squared(cos).sqrt() as f32;
+            }
+        }
+    }
+
+    /// Preamp (dB, never positive) that cancels the peak of the band response
+    ///
+    /// Searches a log-spaced grid up to just below Nyquist plus every band
+    /// centre. A config that never boosts, or is disabled, needs 0 dB.
+    ///
+    /// No allocations, but costs ~160 response evaluations per band; call it
+    /// when the config changes, not from an audio callback.
+    pub fn auto_preamp_db(&self, sample_rate: f32) -> f32 {
+        if !self.enabled {
+            return 0.0;
+        }
+
+        let mut coeffs = [None; MAX_BANDS];
+        for (slot, band) in coeffs.iter_mut().zip(self.bands.iter()) {
+            if band.enabled {
+                *slot = band.to_coefficients(sample_rate, self.design).ok();
+            }
+        }
+        let peak_at = |frequency: f32| -> f64 {
+            let cos_w = (std::f64::consts::TAU * frequency as f64 / sample_rate as f64).cos();
+            coeffs
+                .iter()
+                .flatten()
+                .map(|c: &BandCoefficients| c.magnitude_squared(cos_w))
+                .product()
+        };
+
+        let (low, high) = (10.0_f32, sample_rate * 0.499);
+        let ratio = (high / low).ln() / (PREAMP_SEARCH_POINTS - 1) as f32;
+        let grid = (0..PREAMP_SEARCH_POINTS).map(|i| low * (ratio * i as f32).exp());
+        let centres = self
+            .bands
+            .iter()
+            .map(|band| band.frequency)
+            .filter(|&f| f < sample_rate / 2.0);
+        let peak_squared = grid.chain(centres).map(peak_at).fold(1.0, f64::max);
+
+        // 10 log10 of a squared magnitude is its level in dB
+        -(10.0 * peak_squared.log10()) as f32
+    }
+
+    /// Set `master_gain_db` to the auto preamp if `auto_preamp` is on
+    pub fn apply_auto_preamp(&mut self, sample_rate: f32) {
+        if self.auto_preamp {
+            self.master_gain_db = self.auto_preamp_db(sample_rate);
+        }
+    }
+
+    /// Get all gains as a Vec (useful for UI serialization)
+    pub fn get_gains(&self) -> Vec<f32> {
+        self.bands.iter().map(|band| band.gain_db).collect()
+    }
+
+    /// Apply a gain curve defined at other frequencies (e.g. a 10-band preset)
+    ///
+    /// Each band's gain is linearly interpolated on a log-frequency axis between
+    /// the two nearest curve points; bands outside the curve take the edge value.
+    /// This lets the 10-band built-in presets drive a 3-band or 31-band EQ.
+    pub fn set_gains_interpolated(&mut self, frequencies: &[f32], gains: &[f32]) {
+        let points = frequencies.len().min(gains.len());
+        if points == 0 {
+            return;
+        }
+
+        for band in self.bands.iter_mut() {
+            band.gain_db = interpolate_log(&frequencies[..points], &gains[..points], band.frequency)
+                .clamp(-24.0, 24.0);
+        }
     }
 }
 
+/// Linear interpolation of `values` at `freq` on a log-frequency axis
+///
+/// `frequencies` must be sorted ascending and the same length as `values`.
+fn interpolate_log(frequencies: &[f32], values: &[f32], freq: f32) -> f32 {
+    let last = frequencies.len() - 1;
+    if freq <= frequencies[0] {
+        return values[0];
+    }
+    if freq >= frequencies[last] {
+        return values[last];
+    }
+
+    // Rust pattern: `windows(2)` yields overlapping pairs [a, b], [b, c], ...
+    for (i, pair) in frequencies.windows(2).enumerate() {
+        if freq <= pair[1] {
+            let t = (freq.ln() - pair[0].ln()) / (pair[1].ln() - pair[0].ln());
+            return values[i] + t * (values[i + 1] - values[i]);
+        }
+    }
+    values[last]
+}
+
 /// The main equalizer processor
 ///
 /// Holds the filter state and processes audio samples.
 /// Designed for real-time use: no allocations in `process()`.
+///
+/// `config()` always reports the target settings; while a ramp is running
+/// the audible response lags behind it by up to the smoothing time.
 pub struct Equalizer {
     // DirectForm2Transposed: better numerical stability than DF1
-    // Each channel needs its own filter state (stereo = 2 channels)
-    filters_left: [DirectForm2Transposed<f32>; 10],
-    filters_right: [DirectForm2Transposed<f32>; 10],
+    // Each channel needs its own filter state, indexed [channel][band][stage].
+    // Sized for MAX_CHANNELS and MAX_BANDS so layout changes never reallocate;
+    // only the first `channels` x `config.bands.len()` slots are processed.
+    filters: [[[DirectForm2Transposed<f32>; MAX_STAGES]; MAX_BANDS]; MAX_CHANNELS],
+    // Same layout in f64, used instead for the bands marked in `double_bands`
+    filters_f64: [[[DirectForm2Transposed<f64>; MAX_STAGES]; MAX_BANDS]; MAX_CHANNELS],
+    double_bands: [bool; MAX_BANDS],
+    // Copy of the loaded coefficients (the filters keep theirs private) for
+    // the block path, and whether each band is currently flat
+    stage_coeffs: [[Coefficients<f32>; MAX_STAGES]; MAX_BANDS],
+    unity_bands: [bool; MAX_BANDS],
+    channels: usize,
     config: EqConfig,
     sample_rate: f32,
     master_gain_linear: f32,
+    // Smoothing state: per-band ramps plus the master gain ramp
+    ramps: [BandRamp; MAX_BANDS],
+    ramp_blocks: u32,
+    block_pos: usize,
+    master_gain_step: f32,
+    master_blocks_left: u32,
+    // Sidechain and gain envelope per band slot, used by dynamic bands only
+    detectors: [DynamicDetector; MAX_BANDS],
+    // Present only in linear-phase mode; replaces the biquads when set
+    linear_phase: Option<Box<LinearPhaseFir>>,
 }
 
 impl Equalizer {
-    /// Create a new equalizer with default flat response
+    /// Create a new equalizer with default flat response (10 bands)
     pub fn new(sample_rate: f32) -> Self {
-        let config = EqConfig::default();
+        Self::with_config(sample_rate, EqConfig::default())
+            .expect("Default config should always produce valid coefficients")
+    }
+
+    /// Create a new flat equalizer using the standard layout for `band_count` bands
+    pub fn with_band_count(sample_rate: f32, band_count: usize) -> Result<Self, DspError> {
+        Self::with_config(sample_rate, EqConfig::with_band_count(band_count)?)
+    }
 
+    /// Create a new equalizer from an existing configuration
+    pub fn with_config(sample_rate: f32, config: EqConfig) -> Result<Self, DspError> {
         // Rust pattern: creating arrays of non-Copy types requires explicit initialization
         // We use `core::array::from_fn` which calls the closure for each index
-        let filters_left = core::array::from_fn(|i| {
-            let coeffs = config.bands[i]
-                .to_coefficients(sample_rate)
-                .expect("Default config should always produce valid coefficients");
-            DirectForm2Transposed::<f32>::new(coeffs)
+        let filters = core::array::from_fn(|_| {
+            core::array::from_fn(|_| {
+                core::array::from_fn(|_| DirectForm2Transposed::<f32>::new(unity_coefficients()))
+            })
+        });
+        let filters_f64 = core::array::from_fn(|_| {
+            core::array::from_fn(|_| {
+                core::array::from_fn(|_| DirectForm2Transposed::<f64>::new(unity_coefficients()))
+            })
         });
 
-        let filters_right = core::array::from_fn(|i| {
-            let coeffs = config.bands[i]
-                .to_coefficients(sample_rate)
-                .expect("Default config should always produce valid coefficients");
-            DirectForm2Transposed::<f32>::new(coeffs)
-        });
+        // Reserve the maximum up front so `set_band_count` never reallocates later
+        let mut eq = Self {
+            filters,
+            filters_f64,
+            double_bands: [false; MAX_BANDS],
+            stage_coeffs: [[unity_coefficients(); MAX_STAGES]; MAX_BANDS],
+            unity_bands: [true; MAX_BANDS],
+            channels: 2,
+            config: EqConfig {
+                bands: Vec::with_capacity(MAX_BANDS),
+                master_gain_db: 0.0,
+                enabled: true,
+                auto_preamp: false,
+                precision: FilterPrecision::Auto,
+                design: FilterDesign::Bilinear,
+            },
+            sample_rate,
+            master_gain_linear: 1.0,
+            ramps: [BandRamp::settled(Band::new(1000.0, BandType::Peaking)); MAX_BANDS],
+            ramp_blocks: 0,
+            block_pos: 0,
+            master_gain_step: 0.0,
+            master_blocks_left: 0,
+            detectors: core::array::from_fn(|_| DynamicDetector::new()),
+            linear_phase: None,
+        };
+        // The initial config is applied unsmoothed
+        eq.update_config(config)?;
+        eq.set_smoothing_time(DEFAULT_SMOOTHING_MS);
+        Ok(eq)
+    }
+
+    /// Set the ramp time (ms) for gain, frequency and Q changes
+    ///
+    /// 0 disables smoothing so changes apply immediately.
+    pub fn set_smoothing_time(&mut self, smoothing_ms: f32) {
+        let samples = smoothing_ms.max(0.0) / 1000.0 * self.sample_rate;
+        self.ramp_blocks = (samples / SMOOTHING_BLOCK as f32).round() as u32;
+    }
+
+    /// Change the sample rate, keeping the config and smoothing time
+    ///
+    /// Coefficients and dynamic detectors are recomputed without ramping
+    /// and filter state is cleared. Fails, leaving the equalizer unchanged,
+    /// if a band can't be realised at the new rate (e.g. above Nyquist).
+    ///
+    /// Note: Rebuilds the FIR in linear-phase mode, which allocates; call
+    /// when a stream's format changes, not per buffer.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) -> Result<(), DspError> {
+        if sample_rate <= 0.0 || !sample_rate.is_finite() {
+            return Err(DspError::InvalidSampleRate(sample_rate));
+        }
+        let mut coeffs = [None; MAX_BANDS];
+        for (slot, band) in coeffs.iter_mut().zip(self.config.bands.iter()) {
+            if band.enabled {
+                *slot = Some(band.to_coefficients(sample_rate, self.config.design)?);
+            }
+        }
+
+        let ramp_samples = (self.ramp_blocks as usize * SMOOTHING_BLOCK) as f32;
+        let smoothing_ms = ramp_samples * 1000.0 / self.sample_rate;
+        self.sample_rate = sample_rate;
+        self.set_smoothing_time(smoothing_ms);
+        for (i, band_coeffs) in coeffs.iter().enumerate().take(self.config.bands.len()) {
+            let band = self.config.bands[i];
+            self.ramps[i] = BandRamp::settled(band);
+            self.configure_detector(i, &band, false);
+            if let Some(band_coeffs) = band_coeffs {
+                self.load_coefficients(i, band_coeffs, MAX_STAGES);
+            }
+        }
+
+        self.master_blocks_left = 0;
+        self.master_gain_linear = 10.0_f32.powf(self.config.master_gain_db / 20.0);
+
+        if self.linear_phase.is_some() {
+            let fir = LinearPhaseFir::new(sample_rate, self.channels, &self.config);
+            self.linear_phase = Some(Box::new(fir));
+        }
+        self.reset();
+        Ok(())
+    }
+
+    /// Jump every running ramp straight to its target
+    ///
+    /// For freshly created equalizers whose initial settings should not
+    /// fade in from flat.
+    pub fn finish_smoothing(&mut self) {
+        self.master_blocks_left = 0;
+        self.master_gain_linear = 10.0_f32.powf(self.config.master_gain_db / 20.0);
+        for i in 0..self.config.bands.len() {
+            if self.ramps[i].blocks_left > 0 {
+                let band = self.config.bands[i];
+                self.ramps[i] = BandRamp::settled(band);
+                if let Ok(coeffs) = band.to_coefficients(self.sample_rate, self.config.design) {
+                    self.load_coefficients(i, &coeffs, MAX_STAGES);
+                }
+            }
+        }
+    }
+
+    /// Set the number of interleaved channels (1 to [`MAX_CHANNELS`], default 2)
+    ///
+    /// Filter state is reset when the count changes.
+    ///
+    /// # Real-time Safety
+    /// No allocations. Safe to call from an audio callback between buffers.
+    pub fn set_channel_count(&mut self, channels: usize) -> Result<(), DspError> {
+        if channels == 0 || channels > MAX_CHANNELS {
+            return Err(DspError::InvalidChannelCount(channels));
+        }
+        if channels != self.channels {
+            self.channels = channels;
+            if let Some(fir) = self.linear_phase.as_deref_mut() {
+                fir.set_channel_count(channels);
+            }
+            self.reset();
+        }
+        Ok(())
+    }
+
+    /// Switch between minimum-phase biquads and a linear-phase FIR
+    ///
+    /// The config is kept; filter state of the new mode starts cleared.
+    /// Linear phase adds [`Equalizer::latency_samples`] of delay.
+    ///
+    /// Note: Switching to linear phase allocates the FIR buffers, so call
+    /// this during setup, not in the audio callback.
+    pub fn set_phase_mode(&mut self, mode: PhaseMode) {
+        if mode == self.phase_mode() {
+            return;
+        }
+        self.linear_phase = match mode {
+            PhaseMode::Minimum => None,
+            PhaseMode::Linear => Some(Box::new(LinearPhaseFir::new(
+                self.sample_rate,
+                self.channels,
+                &self.config,
+            ))),
+        };
+        self.reset();
+    }
+
+    /// Current phase mode
+    pub fn phase_mode(&self) -> PhaseMode {
+        if self.linear_phase.is_some() {
+            PhaseMode::Linear
+        } else {
+            PhaseMode::Minimum
+        }
+    }
+
+    /// Delay (in samples) this equalizer adds to the signal
+    ///
+    /// 0 in minimum-phase mode. In linear-phase mode it is constant, even
+    /// while the EQ is disabled, so it can be compensated elsewhere.
+    pub fn latency_samples(&self) -> usize {
+        self.linear_phase.as_ref().map_or(0, |fir| fir.latency())
+    }
+
+    /// Delay (in samples) linear-phase mode adds at `sample_rate`
+    ///
+    /// Lets callers report the delay before switching modes.
+    pub fn linear_phase_latency_at(sample_rate: f32) -> usize {
+        crate::linear_phase::latency_at(sample_rate)
+    }
+
+    /// Number of interleaved channels processed by `process_interleaved`
+    pub fn channel_count(&self) -> usize {
+        self.channels
+    }
+
+    /// Whether any band or the master gain is still ramping
+    pub fn is_smoothing(&self) -> bool {
+        self.master_blocks_left > 0
+            || self.ramps[..self.config.bands.len()]
+                .iter()
+                .any(|ramp| ramp.blocks_left > 0)
+    }
+
+    /// Update EQ configuration
+    ///
+    /// Call this between buffer processing, not during.
+    /// Recalculates all filter coefficients. If the band count changes,
+    /// filter state is reset so stale delay lines don't ring into new bands.
+    /// On error nothing changes.
+    pub fn update_config(&mut self, config: EqConfig) -> Result<(), DspError> {
+        let band_count = config.bands.len();
+        if band_count == 0 || band_count > MAX_BANDS {
+            return Err(DspError::InvalidBandCount(band_count));
+        }
+
+        // Design every band before touching any state, so a band that can't
+        // be built leaves the running config as it was
+        let mut designs = [None; MAX_BANDS];
+        for (design, band) in designs.iter_mut().zip(&config.bands) {
+            if band.enabled {
+                *design = Some(band.to_coefficients(self.sample_rate, config.design)?);
+            }
+        }
+
+        // A new band count means a new layout: nothing to ramp from
+        let count_changed = band_count != self.config.bands.len();
+        // Needed by `load_coefficients` below
+        self.config.precision = config.precision;
+        self.config.design = config.design;
+
+        for (i, (band, design)) in config.bands.iter().zip(designs).enumerate() {
+            let was_dynamic = !count_changed && self.config.bands[i].active_dynamics().is_some();
+            self.configure_detector(i, band, was_dynamic);
+            if let Some(coeffs) = design {
+                if count_changed {
+                    self.load_coefficients(i, &coeffs, MAX_STAGES);
+                    self.ramps[i] = BandRamp::settled(*band);
+                } else {
+                    // Stages a band did not use before start from clean state
+                    let old = self.config.bands[i];
+                    let old_stages = if old.enabled { old.stage_count() } else { 0 };
+                    self.retarget(i, band, &coeffs, old_stages);
+                }
+            } else {
+                self.ramps[i] = BandRamp::settled(*band);
+            }
+        }
+
+        if count_changed {
+            self.reset();
+        }
+
+        // Copy into our own Vec (capacity MAX_BANDS) instead of taking the
+        // caller's, so later `set_band_count` calls stay allocation-free
+        self.config.bands.clear();
+        self.config.bands.extend_from_slice(&config.bands);
+        self.config.master_gain_db = config.master_gain_db;
+        self.config.enabled = config.enabled;
+        self.config.auto_preamp = config.auto_preamp;
+        self.retarget_master_gain(!count_changed);
+        self.response_changed();
+        Ok(())
+    }
+
+    /// Ramp the master gain (preamp) to `gain_db`
+    ///
+    /// Used to apply an auto preamp computed off the audio thread with
+    /// [`EqConfig::auto_preamp_db`]. Non-finite gains are ignored.
+    ///
+    /// # Real-time Safety
+    /// No allocations. Safe to call from an audio callback every buffer;
+    /// it does nothing unless the gain changes.
+    pub fn set_master_gain_db(&mut self, gain_db: f32) {
+        if !gain_db.is_finite() || gain_db == self.config.master_gain_db {
+            return;
+        }
+        self.config.master_gain_db = gain_db;
+        self.retarget_master_gain(true);
+    }
+
+    /// Choose f32 or f64 biquads (see [`FilterPrecision`])
+    ///
+    /// Bands that change precision carry their filter state across, so
+    /// this can be changed while audio is playing.
+    pub fn set_filter_precision(&mut self, precision: FilterPrecision) {
+        if precision == self.config.precision {
+            return;
+        }
+        self.config.precision = precision;
+        self.reload_coefficients();
+    }
+
+    /// Current filter precision setting
+    pub fn filter_precision(&self) -> FilterPrecision {
+        self.config.precision
+    }
+
+    /// Choose the cookbook or matched-magnitude filter design (see [`FilterDesign`])
+    ///
+    /// Takes effect at once without clearing filter state: the response
+    /// only shifts near Nyquist, where the change is small.
+    pub fn set_filter_design(&mut self, design: FilterDesign) {
+        if design == self.config.design {
+            return;
+        }
+        self.config.design = design;
+        self.reload_coefficients();
+        self.response_changed();
+    }
+
+    /// Current filter design
+    pub fn filter_design(&self) -> FilterDesign {
+        self.config.design
+    }
+
+    /// Recompute every band's coefficients at its currently heard settings
+    fn reload_coefficients(&mut self) {
+        for i in 0..self.config.bands.len() {
+            let mut heard = self.ramps[i].current;
+            if heard.active_dynamics().is_some() {
+                heard.gain_db = self.detectors[i].gain_db();
+            }
+            if heard.enabled {
+                if let Ok(coeffs) = heard.to_coefficients(self.sample_rate, self.config.design) {
+                    self.load_coefficients(i, &coeffs, MAX_STAGES);
+                }
+            }
+        }
+    }
+
+    /// Whether a band currently runs in f64
+    pub fn band_uses_double(&self, band_index: usize) -> bool {
+        band_index < self.config.bands.len() && self.double_bands[band_index]
+    }
+
+    /// Move the heard master gain toward `config.master_gain_db`
+    fn retarget_master_gain(&mut self, ramp: bool) {
+        let master_target = 10.0_f32.powf(self.config.master_gain_db / 20.0);
+        if !ramp || self.ramp_blocks == 0 {
+            self.master_gain_linear = master_target;
+            self.master_blocks_left = 0;
+        } else {
+            self.master_gain_step = (master_target - self.master_gain_linear) / self.ramp_blocks as f32;
+            self.master_blocks_left = self.ramp_blocks;
+        }
+    }
+
+    /// Switch to the standard layout for `band_count` bands (all gains flat)
+    ///
+    /// # Real-time Safety
+    /// No allocations: the band Vec has capacity for [`MAX_BANDS`].
+    /// Safe to call from an audio callback when the band count changes.
+    pub fn set_band_count(&mut self, band_count: usize) -> Result<(), DspError> {
+        if band_count == 0 || band_count > MAX_BANDS {
+            return Err(DspError::InvalidBandCount(band_count));
+        }
+        if band_count == self.config.bands.len() {
+            return Ok(());
+        }
+
+        self.config.bands.clear();
+        for i in 0..band_count {
+            let band = band_layout(band_count, i);
+            let coeffs = band.to_coefficients(self.sample_rate, self.config.design)?;
+            self.load_coefficients(i, &coeffs, MAX_STAGES);
+            self.ramps[i] = BandRamp::settled(band);
+            self.config.bands.push(band);
+        }
+        self.reset();
+        self.response_changed();
+        Ok(())
+    }
+
+    /// Number of active bands
+    pub fn band_count(&self) -> usize {
+        self.config.bands.len()
+    }
+
+    /// Set gain for a single band (convenience method)
+    ///
+    /// The change is ramped over the smoothing time. Setting the gain a
+    /// band already has is a no-op, so callers may re-send every band.
+    pub fn set_band_gain(&mut self, band_index: usize, gain_db: f32) -> Result<(), DspError> {
+        let previous = self.config.bands.get(band_index).map(|band| band.gain_db);
+        self.config.set_band_gain(band_index, gain_db)?;
+
+        let band = self.config.bands[band_index];
+        if previous == Some(band.gain_db) {
+            return Ok(());
+        }
+        let coeffs = band.to_coefficients(self.sample_rate, self.config.design)?;
+        self.retarget(band_index, &band, &coeffs, MAX_STAGES);
+        self.response_changed();
+
+        Ok(())
+    }
+
+    /// Set frequency, Q, type and enable state for a single band
+    ///
+    /// Coefficients are computed before anything changes, so an invalid
+    /// shape (e.g. above Nyquist) leaves the band untouched.
+    ///
+    /// # Real-time Safety
+    /// No allocations. Safe to call from an audio callback between buffers.
+    pub fn set_band_params(&mut self, band_index: usize, params: BandParams) -> Result<(), DspError> {
+        if band_index >= self.config.bands.len() {
+            return Err(DspError::InvalidBandIndex(band_index));
+        }
+        params.validate()?;
+
+        let mut band = self.config.bands[band_index];
+        if band.params() == params {
+            return Ok(());
+        }
+        // A re-enabled band would otherwise start from stale delay lines,
+        // as would stages that a new type or slope starts using
+        let live_stages = if band.enabled { band.stage_count() } else { 0 };
+        let was_dynamic = band.active_dynamics().is_some();
+        band.set_params(params);
+        let coeffs = band.to_coefficients(self.sample_rate, self.config.design)?;
+
+        self.retarget(band_index, &band, &coeffs, live_stages);
+        self.configure_detector(band_index, &band, was_dynamic);
+        self.config.bands[band_index] = band;
+        self.response_changed();
+
+        Ok(())
+    }
+
+    /// Move a band toward `target`, ramping when the change allows it
+    ///
+    /// Gain, frequency and Q ramp from the currently heard values. A change
+    /// of type, slope or enable state is applied at once via `coeffs`, with
+    /// stages from `reset_from` onwards cleared.
+    fn retarget(&mut self, band_index: usize, target: &Band, coeffs: &BandCoefficients, reset_from: usize) {
+        let ramp = &mut self.ramps[band_index];
+        let current = ramp.current;
+        let can_ramp = self.ramp_blocks > 0
+            && current.enabled
+            && target.enabled
+            && current.band_type == target.band_type
+            && current.slope == target.slope;
+
+        if can_ramp {
+            let blocks = self.ramp_blocks as f32;
+            ramp.gain_step = (target.gain_db - current.gain_db) / blocks;
+            ramp.log_frequency_step = (target.frequency.ln() - current.frequency.ln()) / blocks;
+            ramp.log_q_step = (target.q.ln() - current.q.ln()) / blocks;
+            ramp.blocks_left = self.ramp_blocks;
+        } else {
+            *ramp = BandRamp::settled(*target);
+            self.load_coefficients(band_index, coeffs, reset_from);
+        }
+    }
+
+    /// Point a band's sidechain at its new shape and timing
+    ///
+    /// A band that just became dynamic starts from flat; one that already
+    /// was keeps its envelope so edits don't pump.
+    fn configure_detector(&mut self, band_index: usize, band: &Band, was_dynamic: bool) {
+        if let Some(dynamics) = band.active_dynamics() {
+            let detector = &mut self.detectors[band_index];
+            if !was_dynamic {
+                detector.reset();
+            }
+            detector.configure(band, dynamics, self.sample_rate);
+        }
+    }
+
+    /// Gain (dB) a dynamic band currently applies, or `None` for static bands
+    ///
+    /// Reads the audio-side envelope, so call it from the thread that processes.
+    pub fn dynamic_gain_db(&self, band_index: usize) -> Option<f32> {
+        let band = self.config.bands.get(band_index)?;
+        band.active_dynamics()?;
+        Some(self.detectors[band_index].gain_db())
+    }
+
+    /// Queue a linear-phase redesign after the target response changed
+    ///
+    /// The FIR is rebuilt at its next block boundary and crossfaded in, so
+    /// several edits within one block cost a single redesign.
+    fn response_changed(&mut self) {
+        if let Some(fir) = self.linear_phase.as_deref_mut() {
+            fir.invalidate();
+        }
+    }
+
+    /// Step every running ramp by one block and refresh its coefficients
+    ///
+    /// # Real-time Safety
+    /// No allocations; cost is one coefficient calculation per ramping band.
+    fn advance_ramps(&mut self) {
+        self.flush_unity_bands();
+        if self.master_blocks_left > 0 {
+            self.master_blocks_left -= 1;
+            if self.master_blocks_left == 0 {
+                self.master_gain_linear = 10.0_f32.powf(self.config.master_gain_db / 20.0);
+            } else {
+                self.master_gain_linear += self.master_gain_step;
+            }
+        }
+
+        for i in 0..self.config.bands.len() {
+            let dynamic = self.config.bands[i].active_dynamics().is_some();
+            let ramp = &mut self.ramps[i];
+            if ramp.blocks_left == 0 && !dynamic {
+                continue;
+            }
+            if ramp.blocks_left > 0 {
+                ramp.blocks_left -= 1;
+                if ramp.blocks_left == 0 {
+                    // Land exactly on the target instead of accumulating step error
+                    ramp.current = self.config.bands[i];
+                } else {
+                    ramp.current.gain_db += ramp.gain_step;
+                    ramp.current.frequency = (ramp.current.frequency.ln() + ramp.log_frequency_step).exp();
+                    ramp.current.q = (ramp.current.q.ln() + ramp.log_q_step).exp();
+                }
+            }
+            // Dynamic bands are heard at their envelope gain, not the set gain
+            let mut heard = ramp.current;
+            if dynamic {
+                heard.gain_db = self.detectors[i].gain_db();
+            }
+            // Intermediate values lie between two valid endpoints, so this
+            // only fails if the target itself was rejected
+            if let Ok(coeffs) = heard.to_coefficients(self.sample_rate, self.config.design) {
+                self.load_coefficients(i, &coeffs, MAX_STAGES);
+            }
+        }
+    }
+
+    /// Clear the last ringing of bands that have gone flat
+    ///
+    /// Runs on the smoothing grid in both processing paths, so they stay
+    /// bit-identical; also keeps decaying state out of the denormal range.
+    fn flush_unity_bands(&mut self) {
+        for band_index in 0..self.config.bands.len() {
+            if !self.unity_bands[band_index] {
+                continue;
+            }
+            let threshold = FLUSH_THRESHOLD as f64;
+            let quiet = |s1: f64, s2: f64| s1.abs() < threshold && s2.abs() < threshold;
+            if self.band_states_all(band_index, quiet) {
+                self.reset_band(band_index);
+            }
+        }
+    }
+
+    /// Load a band's stage coefficients into both channels
+    ///
+    /// Stages from `reset_from` onwards have their state cleared; unused
+    /// stages get unity coefficients.
+    fn load_coefficients(&mut self, band_index: usize, coeffs: &BandCoefficients, reset_from: usize) {
+        let stages = coeffs.stages_f32();
+        self.stage_coeffs[band_index] = stages;
+        self.unity_bands[band_index] = stages[..coeffs.len].iter().all(is_unity);
+        let double = self.config.precision.uses_double(coeffs.frequency, self.sample_rate);
+        let switched = double != self.double_bands[band_index];
+        self.double_bands[band_index] = double;
+
+        let channels = self.filters.iter_mut().zip(self.filters_f64.iter_mut());
+        for (channel, channel_f64) in channels {
+            let band_filters = channel[band_index].iter_mut().zip(&mut channel_f64[band_index]);
+            for (stage, (filter, filter_f64)) in band_filters.enumerate() {
+                if stage < coeffs.len {
+                    filter.update_coefficients(stages[stage]);
+                    filter_f64.update_coefficients(coeffs.stages[stage]);
+                } else {
+                    filter.update_coefficients(unity_coefficients());
+                    filter_f64.update_coefficients(unity_coefficients());
+                }
+                // Hand the state over so a band changing precision doesn't click
+                if switched && double {
+                    (filter_f64.s1, filter_f64.s2) = (filter.s1 as f64, filter.s2 as f64);
+                    filter.reset_state();
+                } else if switched {
+                    (filter.s1, filter.s2) = (filter_f64.s1 as f32, filter_f64.s2 as f32);
+                    filter_f64.reset_state();
+                }
+                if stage >= reset_from {
+                    filter.reset_state();
+                    filter_f64.reset_state();
+                }
+            }
+        }
+    }
+
+    /// Process one interleaved frame (one sample per channel) in place
+    ///
+    /// Channels beyond [`MAX_CHANNELS`] only get the master gain.
+    ///
+    /// # Real-time Safety
+    /// This function performs NO allocations and NO syscalls.
+    /// Safe to call from audio callback.
+    #[inline]
+    pub fn process_frame(&mut self, frame: &mut [f32]) {
+        if self.block_pos == 0 {
+            self.advance_ramps();
+        }
+        self.block_pos = (self.block_pos + 1) % SMOOTHING_BLOCK;
+
+        if let Some(fir) = self.linear_phase.as_deref_mut() {
+            // Runs while disabled too (as a pure delay) to keep latency fixed
+            fir.process_frame(frame, &self.config);
+            if self.config.enabled {
+                for sample in frame.iter_mut() {
+                    *sample *= self.master_gain_linear;
+                }
+            }
+            return;
+        }
+
+        if !self.config.enabled {
+            return;
+        }
+
+        // Dynamic bands listen to the input before any band has touched it
+        for (band, detector) in self.config.bands.iter().zip(self.detectors.iter_mut()) {
+            if let Some(dynamics) = band.active_dynamics() {
+                detector.process_frame(frame, dynamics, band.gain_db);
+            }
+        }
+
+        // Cascade through all enabled filters
+        for (i, band) in self.config.bands.iter().enumerate() {
+            if band.enabled {
+                let stages = band.stage_count();
+                if self.double_bands[i] {
+                    for (sample, channel) in frame.iter_mut().zip(self.filters_f64.iter_mut()) {
+                        *sample = run_f64(&mut channel[i][..stages], *sample);
+                    }
+                    continue;
+                }
+                // Rust pattern: `run()` processes one sample through the BiQuad
+                // This is the hot path - compiler will inline and potentially vectorize
+                for (sample, channel) in frame.iter_mut().zip(self.filters.iter_mut()) {
+                    for filter in channel[i][..stages].iter_mut() {
+                        *sample = filter.run(*sample);
+                    }
+                }
+            }
+        }
+
+        for sample in frame.iter_mut() {
+            *sample *= self.master_gain_linear;
+        }
+    }
+
+    /// Process an interleaved buffer in place, a block at a time with SIMD
+    ///
+    /// Same layout and bit-identical output as
+    /// [`process_interleaved`](Self::process_interleaved), but each 32-frame
+    /// smoothing block runs through the cascade in 16-lane SIMD vectors, the
+    /// channels side by side and several stages pipelined (eight for stereo,
+    /// two for 7.1). Disabled bands and bands that are currently flat
+    /// (0 dB, settled) are skipped for the whole block instead of being
+    /// checked every sample. The scalar path is still used in linear-phase
+    /// mode.
+    ///
+    /// # Real-time Safety
+    /// No allocations; the block is staged on the stack. O(n × active stages).
+    pub fn process_block(&mut self, buffer: &mut [f32]) {
+        if self.linear_phase.is_some() {
+            self.process_interleaved(buffer);
+            return;
+        }
+        let channels = self.channels;
+        let frames = buffer.len() / channels;
+        let mut start = 0;
+        while start < frames {
+            // Blocks follow the smoothing grid so ramps step exactly as in
+            // the scalar path, whatever the buffer size
+            if self.block_pos == 0 {
+                self.advance_ramps();
+            }
+            let len = (SMOOTHING_BLOCK - self.block_pos).min(frames - start);
+            self.block_pos = (self.block_pos + len) % SMOOTHING_BLOCK;
+            if self.config.enabled {
+                let block = &mut buffer[start * channels..(start + len) * channels];
+                match channels {
+                    1 => self.process_lanes::<1>(block),
+                    2 => self.process_lanes::<2>(block),
+                    3 | 4 => self.process_lanes::<4>(block),
+                    _ => self.process_lanes::<8>(block),
+                }
+            }
+            start += len;
+        }
+    }
+
+    /// Run up to one smoothing block of whole frames, `W` lanes per stage
+    ///
+    /// `W` is the channel count rounded up to a power of two, so
+    /// [`SIMD_LANES`] / `W` stages of the cascade run at once.
+    #[inline]
+    fn process_lanes<const W: usize>(&mut self, block: &mut [f32]) {
+        let channels = self.channels;
+
+        // Dynamic bands listen to the input before any band has touched it
+        for (band, detector) in self.config.bands.iter().zip(self.detectors.iter_mut()) {
+            if let Some(dynamics) = band.active_dynamics() {
+                for frame in block.chunks_exact(channels) {
+                    detector.process_frame(frame, dynamics, band.gain_db);
+                }
+            }
+        }
+
+        // Deinterleave into one vector per frame (structure of arrays)
+        let mut lanes = [Lanes::<SIMD_LANES>::splat(0.0); SMOOTHING_BLOCK];
+        let frames = block.len() / channels;
+        for (lane, frame) in lanes.iter_mut().zip(block.chunks_exact(channels)) {
+            lane.0[..channels].copy_from_slice(frame);
+        }
+        let lanes = &mut lanes[..frames];
+
+        // Stages to run, in cascade order, leaving out skipped bands
+        let mut active = [(0, 0); MAX_BANDS * MAX_STAGES];
+        let mut active_count = 0;
+        for (i, band) in self.config.bands.iter().enumerate() {
+            if !band.enabled || (self.unity_bands[i] && self.band_is_silent(i)) {
+                continue;
+            }
+            for stage in 0..band.stage_count() {
+                active[active_count] = (i, stage);
+                active_count += 1;
+            }
+        }
+
+        let mut pending = &active[..active_count];
+        while let Some(&(i, _)) = pending.first() {
+            if self.double_bands[i] {
+                // f64 bands run a frame at a time, in their place in the cascade
+                let stages = self.config.bands[i].stage_count();
+                for lane in lanes.iter_mut() {
+                    let samples = lane.0[..channels].iter_mut();
+                    for (sample, channel) in samples.zip(self.filters_f64.iter_mut()) {
+                        *sample = run_f64(&mut channel[i][..stages], *sample);
+                    }
+                }
+                pending = &pending[stages..];
+                continue;
+            }
+            let len = pending
+                .iter()
+                .take(SIMD_LANES / W)
+                .take_while(|&&(i, _)| !self.double_bands[i])
+                .count();
+            let (pass, rest) = pending.split_at(len);
+            pending = rest;
+
+            let mut coeffs = LaneCoefficients::zero();
+            let mut state = [Lanes::splat(0.0); 2];
+            for (p, &(i, stage)) in pass.iter().enumerate() {
+                coeffs.set(p * W..(p + 1) * W, &self.stage_coeffs[i][stage]);
+                for (c, channel) in self.filters[..channels].iter().enumerate() {
+                    state[0].0[p * W + c] = channel[i][stage].s1;
+                    state[1].0[p * W + c] = channel[i][stage].s2;
+                }
+            }
+            biquad_pipeline::<SIMD_LANES, W>(&coeffs, &mut state, pass.len(), lanes);
+            for (p, &(i, stage)) in pass.iter().enumerate() {
+                for (c, channel) in self.filters[..channels].iter_mut().enumerate() {
+                    channel[i][stage].s1 = state[0].0[p * W + c];
+                    channel[i][stage].s2 = state[1].0[p * W + c];
+                }
+            }
+        }
 
-        Self {
-            filters_left,
-            filters_right,
-            config,
-            sample_rate,
-            master_gain_linear: 1.0,
+        let gain = Lanes::<SIMD_LANES>::splat(self.master_gain_linear);
+        for (frame, lane) in block.chunks_exact_mut(channels).zip(lanes.iter()) {
+            frame.copy_from_slice(&(*lane * gain).0[..channels]);
         }
     }
 
-    /// Update EQ configuration
+    /// Whether a band's filters hold no state on any processed channel
     ///
-    /// Call this between buffer processing, not during.
-    /// Recalculates all filter coefficients.
-    pub fn update_config(&mut self, config: EqConfig) -> Result<(), DspError> {
-        for (i, band) in config.bands.iter().enumerate() {
-            if band.enabled {
-                let coeffs = band.to_coefficients(self.sample_rate)?;
-                self.filters_left[i].update_coefficients(coeffs);
-                self.filters_right[i].update_coefficients(coeffs);
-            }
-        }
-        self.master_gain_linear = 10.0_f32.powf(config.master_gain_db / 20.0);
-        self.config = config;
-        Ok(())
+    /// A flat band can only be skipped once the ringing from its previous
+    /// response has been flushed out.
+    fn band_is_silent(&self, band_index: usize) -> bool {
+        self.band_states_all(band_index, |s1, s2| s1 == 0.0 && s2 == 0.0)
     }
 
-    /// Set gain for a single band (convenience method)
-    pub fn set_band_gain(&mut self, band_index: usize, gain_db: f32) -> Result<(), DspError> {
-        self.config.set_band_gain(band_index, gain_db)?;
-
-        let band = &self.config.bands[band_index];
-        let coeffs = band.to_coefficients(self.sample_rate)?;
-        self.filters_left[band_index].update_coefficients(coeffs);
-        self.filters_right[band_index].update_coefficients(coeffs);
+    /// Whether `test(s1, s2)` holds for every stage of a band on the
+    /// processed channels, in whichever precision the band runs
+    fn band_states_all(&self, band_index: usize, test: impl Fn(f64, f64) -> bool) -> bool {
+        if self.double_bands[band_index] {
+            self.filters_f64[..self.channels]
+                .iter()
+                .flat_map(|channel| channel[band_index].iter())
+                .all(|filter| test(filter.s1, filter.s2))
+        } else {
+            self.filters[..self.channels]
+                .iter()
+                .flat_map(|channel| channel[band_index].iter())
+                .all(|filter| test(filter.s1 as f64, filter.s2 as f64))
+        }
+    }
 
-        Ok(())
+    /// Clear every stage of a band, in both precisions
+    fn reset_band(&mut self, band_index: usize) {
+        let channels = self.filters.iter_mut().zip(self.filters_f64.iter_mut());
+        for (channel, channel_f64) in channels {
+            for filter in channel[band_index].iter_mut() {
+                filter.reset_state();
+            }
+            for filter in channel_f64[band_index].iter_mut() {
+                filter.reset_state();
+            }
+        }
     }
 
     /// Process a stereo sample pair through the EQ chain
     ///
+    /// Uses the filter state of the first two channels.
+    ///
     /// # Real-time Safety
     /// This function performs NO allocations and NO syscalls.
     /// Safe to call from audio callback.
     #[inline]
     pub fn process_sample(&mut self, left: f32, right: f32) -> (f32, f32) {
-        if !self.config.enabled {
-            return (left, right);
-        }
-
-        let mut l = left;
-        let mut r = right;
-
-        // Cascade through all enabled filters
-        for i in 0..10 {
-            if self.config.bands[i].enabled {
-                // Rust pattern: `run()` processes one sample through the BiQuad
-                // This is the hot path - compiler will inline and potentially vectorize
-                l = self.filters_left[i].run(l);
-                r = self.filters_right[i].run(r);
-            }
-        }
-
-        (l * self.master_gain_linear, r * self.master_gain_linear)
+        let mut frame = [left, right];
+        self.process_frame(&mut frame);
+        (frame[0], frame[1])
     }
 
-    /// Process an interleaved stereo buffer in-place
+    /// Process an interleaved buffer in-place
     ///
-    /// Buffer format: [L0, R0, L1, R1, L2, R2, ...]
+    /// Buffer format: one sample per channel per frame, e.g. for stereo
+    /// [L0, R0, L1, R1, ...] or for 5.1 [FL0, FR0, FC0, LFE0, RL0, RR0, FL1, ...].
+    /// The layout is set with [`Equalizer::set_channel_count`]; a trailing
+    /// partial frame is left untouched.
     ///
     /// # Real-time Safety
     /// No allocations. O(n) where n = buffer length.
     #[inline]
     pub fn process_interleaved(&mut self, buffer: &mut [f32]) {
-        // Rust pattern: `chunks_exact_mut(2)` gives us mutable 2-element slices
-        // This is bounds-checked at compile time for the slice access
-        for frame in buffer.chunks_exact_mut(2) {
-            let (l, r) = self.process_sample(frame[0], frame[1]);
-            frame[0] = l;
-            frame[1] = r;
+        // Rust pattern: `chunks_exact_mut(n)` gives us mutable n-element slices
+        for frame in buffer.chunks_exact_mut(self.channels) {
+            self.process_frame(frame);
         }
     }
 
@@ -292,9 +1862,17 @@ impl Equalizer {
     ///
     /// Call when switching audio sources to prevent filter ringing
     pub fn reset(&mut self) {
-        for i in 0..10 {
-            self.filters_left[i].reset_state();
-            self.filters_right[i].reset_state();
+        for filter in self.filters.iter_mut().flatten().flatten() {
+            filter.reset_state();
+        }
+        for filter in self.filters_f64.iter_mut().flatten().flatten() {
+            filter.reset_state();
+        }
+        for detector in self.detectors.iter_mut() {
+            detector.reset();
+        }
+        if let Some(fir) = self.linear_phase.as_deref_mut() {
+            fir.reset();
         }
     }
 }
@@ -482,4 +2060,742 @@ mod tests {
         // Output should be louder than input for boosted frequency
         assert!(max_output > max_input, "Boost should increase amplitude");
     }
+
+    #[test]
+    fn test_tone_control_layout() {
+        let config = EqConfig::with_band_count(3).unwrap();
+        assert_eq!(config.band_count(), 3);
+        assert_eq!(config.bands[0].band_type, BandType::LowShelf);
+        assert_eq!(config.bands[1].band_type, BandType::Peaking);
+        assert_eq!(config.bands[2].band_type, BandType::HighShelf);
+        assert_eq!(config.bands[1].frequency, 1000.0);
+    }
+
+    #[test]
+    fn test_iso_31_band_layout() {
+        let config = EqConfig::with_band_count(31).unwrap();
+        for (band, freq) in config.bands.iter().zip(ISO_31_BANDS.iter()) {
+            assert_eq!(band.frequency, *freq);
+        }
+        assert!(Equalizer::with_config(44100.0, config).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_band_count() {
+        assert!(EqConfig::with_band_count(0).is_err());
+        assert!(EqConfig::with_band_count(MAX_BANDS + 1).is_err());
+        assert!(EqConfig::from_frequencies(&[]).is_err());
+    }
+
+    #[test]
+    fn test_custom_layout_is_log_spaced() {
+        let config = EqConfig::with_band_count(5).unwrap();
+        for pair in config.bands.windows(2) {
+            assert!(pair[1].frequency > pair[0].frequency);
+        }
+        // Each step should be the same ratio
+        let ratio = config.bands[1].frequency / config.bands[0].frequency;
+        let ratio_last = config.bands[4].frequency / config.bands[3].frequency;
+        assert!((ratio - ratio_last).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_band_index_bounded_by_count() {
+        let mut eq = Equalizer::with_band_count(48000.0, 3).unwrap();
+        assert!(eq.set_band_gain(2, 6.0).is_ok());
+        assert!(eq.set_band_gain(3, 6.0).is_err());
+
+        let mut eq = Equalizer::with_band_count(48000.0, 31).unwrap();
+        assert!(eq.set_band_gain(30, 6.0).is_ok());
+    }
+
+    #[test]
+    fn test_set_band_count_does_not_reallocate() {
+        let mut eq = Equalizer::new(48000.0);
+        let ptr = eq.config().bands.as_ptr();
+
+        eq.set_band_count(31).unwrap();
+        assert_eq!(eq.band_count(), 31);
+        eq.set_band_count(3).unwrap();
+        assert_eq!(eq.band_count(), 3);
+
+        // Same backing buffer means no allocation happened
+        assert_eq!(eq.config().bands.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_update_config_changes_band_count() {
+        let mut eq = Equalizer::new(48000.0);
+        eq.update_config(EqConfig::with_band_count(3).unwrap()).unwrap();
+        assert_eq!(eq.band_count(), 3);
+        assert!(eq.config().bands.capacity() >= MAX_BANDS);
+
+        let (l, r) = eq.process_sample(0.5, -0.5);
+        assert!(l.is_finite() && r.is_finite());
+    }
+
+    #[test]
+    fn test_update_config_is_all_or_nothing() {
+        let mut eq = Equalizer::new(48000.0);
+        let mut config = EqConfig::default();
+        config.bands[0].gain_db = 6.0;
+        // Above Nyquist: this band can't be designed
+        config.bands[9].frequency = 30000.0;
+        assert!(eq.update_config(config.clone()).is_err());
+        assert_eq!(eq.config().bands[0].gain_db, 0.0);
+        assert_eq!(eq.config().bands[9].frequency, 16000.0);
+
+        // Nothing was retargeted either: the EQ is still flat
+        let mut buffer = vec![0.5; 1024];
+        eq.process_block(&mut buffer);
+        assert!(buffer.iter().all(|&x| (x - 0.5).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_interpolated_preset_gains() {
+        let mut config = EqConfig::with_band_count(3).unwrap();
+        let gains = [6.0, 6.0, 6.0, 0.0, 0.0, 0.0, 0.0, 0.0, -6.0, -6.0];
+        config.set_gains_interpolated(&EQ_BANDS, &gains);
+
+        // 100 Hz sits between 62 Hz (+6) and 125 Hz (+6)
+        assert!((config.bands[0].gain_db - 6.0).abs() < 1e-4);
+        // 1 kHz matches a curve point exactly
+        assert!(config.bands[1].gain_db.abs() < 1e-4);
+        // 10 kHz sits between 8 kHz (-6) and 16 kHz (-6)
+        assert!((config.bands[2].gain_db + 6.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_flat_eq_is_unity() {
+        let mut eq = Equalizer::new(48000.0);
+        for i in 0..100 {
+            let sample = (i as f32 * 0.1).sin() * 0.5;
+            let (l, r) = eq.process_sample(sample, sample);
+            assert!((l - sample).abs() < 1e-4);
+            assert!((r - sample).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_set_band_params_moves_center() {
+        let mut eq = Equalizer::new(48000.0);
+        let params = BandParams {
+            frequency: 3000.0,
+            q: 2.0,
+            band_type: BandType::Peaking,
+            slope: FilterSlope::Db12,
+            enabled: true,
+            dynamics: None,
+        };
+        eq.set_band_params(5, params).unwrap();
+        eq.set_band_gain(5, 12.0).unwrap();
+
+        // Gain is preserved when the shape changes and vice versa
+        let band = eq.config().bands[5];
+        assert_eq!(band.params(), params);
+        assert_eq!(band.gain_db, 12.0);
+    }
+
+    #[test]
+    fn test_set_band_params_rejects_invalid() {
+        let mut eq = Equalizer::new(48000.0);
+        let before = eq.config().bands[5];
+        let mut params = before.params();
+
+        params.q = 0.0;
+        assert!(matches!(eq.set_band_params(5, params), Err(DspError::InvalidQ(_))));
+
+        params.q = 1.0;
+        params.frequency = -20.0;
+        assert!(matches!(eq.set_band_params(5, params), Err(DspError::InvalidFrequency(_))));
+
+        // Above Nyquist fails when coefficients are built
+        params.frequency = 30000.0;
+        assert!(eq.set_band_params(5, params).is_err());
+        assert!(eq.set_band_params(10, before.params()).is_err());
+
+        assert_eq!(eq.config().bands[5], before);
+    }
+
+    #[test]
+    fn test_disabled_band_is_bypassed() {
+        let mut eq = Equalizer::new(48000.0);
+        eq.set_band_gain(5, 12.0).unwrap();
+        let mut params = eq.config().bands[5].params();
+        params.enabled = false;
+        eq.set_band_params(5, params).unwrap();
+
+        // Should match an EQ whose band 5 is simply flat
+        let mut flat = Equalizer::new(48000.0);
+        for i in 0..100 {
+            let sample = (i as f32 * 0.1).sin() * 0.5;
+            let (l, _) = eq.process_sample(sample, sample);
+            let (expected, _) = flat.process_sample(sample, sample);
+            assert!((l - expected).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_atomic_band_params_round_trip() {
+        let params = BandParams {
+            frequency: 125.0,
+            q: 0.5,
+            band_type: BandType::HighPass,
+            slope: FilterSlope::Db48,
+            enabled: false,
+            dynamics: Some(BandDynamics {
+                direction: DynamicDirection::Below,
+                ..Default::default()
+            }),
+        };
+        let atomic = AtomicBandParams::new(band_layout(10, 0).params());
+        atomic.store(params);
+        assert_eq!(atomic.load(), params);
+        atomic.store(BandParams { dynamics: None, ..params });
+        assert_eq!(atomic.load().dynamics, None);
+    }
+
+    /// Single-band EQ with the given shape and gain
+    fn single_band(band_type: BandType, slope: FilterSlope, gain_db: f32) -> Equalizer {
+        let mut eq = Equalizer::with_band_count(48000.0, 1).unwrap();
+        let params = BandParams {
+            frequency: 1000.0,
+            q: Q_BUTTERWORTH_F32,
+            band_type,
+            slope,
+            enabled: true,
+            dynamics: None,
+        };
+        eq.set_band_params(0, params).unwrap();
+        eq.set_band_gain(0, gain_db).unwrap();
+        eq
+    }
+
+    /// Steady-state gain (dB) of a sine at `freq` through `eq`
+    fn measure_gain_db(eq: &mut Equalizer, freq: f32) -> f32 {
+        eq.reset();
+        let sample_rate = eq.sample_rate();
+        let (mut energy_in, mut energy_out) = (0.0_f64, 0.0_f64);
+        for i in 0..48000 {
+            let x = (2.0 * std::f32::consts::PI * freq * i as f32 / sample_rate).sin() * 0.5;
+            let (y, _) = eq.process_sample(x, x);
+            // Skip the first half so transients have settled
+            if i >= 24000 {
+                energy_in += (x as f64).powi(2);
+                energy_out += (y as f64).powi(2);
+            }
+        }
+        (10.0 * (energy_out / energy_in).log10()) as f32
+    }
+
+    #[test]
+    fn test_high_pass_slopes() {
+        // One octave below the corner: -6 dB/oct per filter order
+        let expected = [
+            (FilterSlope::Db12, -12.3),
+            (FilterSlope::Db24, -24.1),
+            (FilterSlope::Db48, -48.2),
+        ];
+        for (slope, attenuation) in expected {
+            let mut eq = single_band(BandType::HighPass, slope, 0.0);
+            assert_eq!(eq.config().bands[0].stage_count(), match slope {
+                FilterSlope::Db12 => 1,
+                FilterSlope::Db24 => 2,
+                FilterSlope::Db48 => 4,
+            });
+            let stop = measure_gain_db(&mut eq, 500.0);
+            assert!((stop - attenuation).abs() < 1.0, "{:?}: {} dB at 500 Hz", slope, stop);
+            let pass = measure_gain_db(&mut eq, 8000.0);
+            assert!(pass.abs() < 0.5, "{:?}: {} dB at 8 kHz", slope, pass);
+        }
+    }
+
+    #[test]
+    fn test_low_pass_slope() {
+        let mut eq = single_band(BandType::LowPass, FilterSlope::Db24, 0.0);
+        let stop = measure_gain_db(&mut eq, 2000.0);
+        assert!((stop + 24.1).abs() < 1.0, "{} dB at 2 kHz", stop);
+        assert!(measure_gain_db(&mut eq, 125.0).abs() < 0.5);
+        // Butterworth: -3 dB at the corner regardless of order
+        assert!((measure_gain_db(&mut eq, 1000.0) + 3.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_notch_band_pass_and_all_pass() {
+        let mut notch = single_band(BandType::Notch, FilterSlope::Db12, 0.0);
+        assert!(measure_gain_db(&mut notch, 1000.0) < -30.0);
+        assert!(measure_gain_db(&mut notch, 8000.0).abs() < 1.0);
+
+        let mut band_pass = single_band(BandType::BandPass, FilterSlope::Db12, 0.0);
+        assert!(measure_gain_db(&mut band_pass, 1000.0).abs() < 0.5);
+        assert!(measure_gain_db(&mut band_pass, 100.0) < -15.0);
+
+        let mut all_pass = single_band(BandType::AllPass, FilterSlope::Db12, 0.0);
+        for freq in [100.0, 1000.0, 8000.0] {
+            assert!(measure_gain_db(&mut all_pass, freq).abs() < 0.1);
+        }
+    }
+
+    #[test]
+    fn test_tilt_pivots_around_frequency() {
+        let mut eq = single_band(BandType::Tilt, FilterSlope::Db12, 6.0);
+        assert!((measure_gain_db(&mut eq, 30.0) + 3.0).abs() < 0.5);
+        assert!((measure_gain_db(&mut eq, 16000.0) - 3.0).abs() < 0.5);
+        assert!(measure_gain_db(&mut eq, 1000.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_gain_ignored_for_fixed_filters() {
+        let mut boosted = single_band(BandType::HighPass, FilterSlope::Db12, 12.0);
+        assert!(measure_gain_db(&mut boosted, 8000.0).abs() < 0.5);
+        assert!(!BandType::Notch.uses_gain());
+        assert!(BandType::Tilt.uses_gain());
+    }
+
+    #[test]
+    fn test_switching_to_steeper_slope_is_stable() {
+        let mut eq = single_band(BandType::Peaking, FilterSlope::Db12, 6.0);
+        for i in 0..512 {
+            eq.process_sample((i as f32 * 0.3).sin(), 0.0);
+        }
+        let mut params = eq.config().bands[0].params();
+        params.band_type = BandType::HighPass;
+        params.slope = FilterSlope::Db48;
+        eq.set_band_params(0, params).unwrap();
+        for i in 0..512 {
+            let (l, r) = eq.process_sample((i as f32 * 0.3).sin(), 0.0);
+            assert!(l.is_finite() && l.abs() < 4.0);
+            assert!(r.is_finite());
+        }
+    }
+
+    #[test]
+    fn test_gain_change_is_ramped() {
+        let mut eq = Equalizer::new(48000.0);
+        eq.set_band_gain(1, 12.0).unwrap();
+
+        // Target is reported immediately, the heard gain follows
+        assert_eq!(eq.config().bands[1].gain_db, 12.0);
+        assert!(eq.is_smoothing());
+        for _ in 0..480 {
+            eq.process_sample(0.1, 0.1);
+        }
+        let halfway = eq.ramps[1].current.gain_db;
+        assert!(halfway > 3.0 && halfway < 9.0, "after 10 ms: {}", halfway);
+
+        for _ in 0..720 {
+            eq.process_sample(0.1, 0.1);
+        }
+        assert!(!eq.is_smoothing());
+        assert_eq!(eq.ramps[1].current, eq.config().bands[1]);
+    }
+
+    #[test]
+    fn test_smoothing_disabled_applies_immediately() {
+        let mut eq = Equalizer::new(48000.0);
+        eq.set_smoothing_time(0.0);
+        eq.set_band_gain(1, 12.0).unwrap();
+        assert!(!eq.is_smoothing());
+        assert_eq!(eq.ramps[1].current.gain_db, 12.0);
+    }
+
+    #[test]
+    fn test_type_change_is_not_ramped() {
+        let mut eq = Equalizer::new(48000.0);
+        let mut params = eq.config().bands[4].params();
+        params.band_type = BandType::Notch;
+        eq.set_band_params(4, params).unwrap();
+        assert!(!eq.is_smoothing());
+        assert_eq!(eq.ramps[4].current.band_type, BandType::Notch);
+    }
+
+    #[test]
+    fn test_finish_smoothing() {
+        let mut eq = Equalizer::new(48000.0);
+        eq.set_band_gain(3, -6.0).unwrap();
+        eq.finish_smoothing();
+        assert!(!eq.is_smoothing());
+        assert_eq!(eq.ramps[3].current.gain_db, -6.0);
+    }
+
+    #[test]
+    fn test_frequency_ramp_is_logarithmic() {
+        let mut eq = Equalizer::new(48000.0);
+        let mut params = eq.config().bands[5].params();
+        params.frequency = 4000.0;
+        eq.set_band_params(5, params).unwrap();
+
+        // Halfway between 1 kHz and 4 kHz on a log axis is 2 kHz
+        for _ in 0..480 {
+            eq.process_sample(0.0, 0.0);
+        }
+        let freq = eq.ramps[5].current.frequency;
+        assert!((freq - 2000.0).abs() < 150.0, "after 10 ms: {} Hz", freq);
+    }
+
+    /// Largest second difference (a click detector: near zero for a smooth
+    /// low sine) while switching band 0 from -12 to +12 dB
+    fn max_curvature_during_shelf_change(smoothing_ms: f32) -> f32 {
+        let mut eq = Equalizer::new(48000.0);
+        eq.set_smoothing_time(smoothing_ms);
+        eq.set_band_gain(0, -12.0).unwrap();
+        let sine = |i: usize| (2.0 * std::f32::consts::PI * 40.0 * i as f32 / 48000.0).sin() * 0.25;
+        let (mut y1, mut y2) = (0.0, 0.0);
+        for i in 0..9600 {
+            y2 = y1;
+            y1 = eq.process_sample(sine(i), 0.0).0;
+        }
+        eq.set_band_gain(0, 12.0).unwrap();
+        let mut max_curvature = 0.0_f32;
+        for i in 9600..12000 {
+            let y = eq.process_sample(sine(i), 0.0).0;
+            max_curvature = max_curvature.max((y - 2.0 * y1 + y2).abs());
+            y2 = y1;
+            y1 = y;
+        }
+        max_curvature
+    }
+
+    #[test]
+    fn test_ramp_reduces_clicks() {
+        let instant = max_curvature_during_shelf_change(0.0);
+        let smoothed = max_curvature_during_shelf_change(DEFAULT_SMOOTHING_MS);
+        assert!(smoothed < instant / 4.0, "instant {}, smoothed {}", instant, smoothed);
+    }
+
+    #[test]
+    fn test_master_gain_is_ramped() {
+        let mut eq = Equalizer::new(48000.0);
+        let mut config = eq.config().clone();
+        config.master_gain_db = -6.0;
+        eq.update_config(config).unwrap();
+        assert!(eq.is_smoothing());
+        let (first, _) = eq.process_sample(1.0, 1.0);
+        assert!(first > 0.9, "master gain should not jump: {}", first);
+        for _ in 0..1200 {
+            eq.process_sample(0.0, 0.0);
+        }
+        assert!((eq.master_gain_linear - 0.501_187).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_auto_preamp_cancels_peak_boost() {
+        let (_, gains) = crate::PRESETS.iter().find(|(name, _)| *name == "Bass Boost").unwrap();
+        let mut config = EqConfig::default();
+        config.set_gains_interpolated(&EQ_BANDS, gains);
+        let preamp = config.auto_preamp_db(48000.0);
+
+        let frequencies = crate::log_frequencies(10.0, 23900.0, 2000);
+        let response = config.frequency_response(48000.0, &frequencies).unwrap();
+        let peak = response.magnitude_db.iter().copied().fold(f32::MIN, f32::max);
+        assert!(preamp < -5.0, "preamp {}", preamp);
+        assert!((preamp + peak).abs() < 0.05, "preamp {}, peak {}", preamp, peak);
+
+        assert_eq!(EqConfig::default().auto_preamp_db(48000.0), 0.0);
+        config.enabled = false;
+        assert_eq!(config.auto_preamp_db(48000.0), 0.0);
+    }
+
+    #[test]
+    fn test_set_master_gain_applies_preamp() {
+        let mut eq = Equalizer::new(48000.0);
+        eq.set_band_gain(4, 6.0).unwrap();
+        let preamp = eq.config().auto_preamp_db(48000.0);
+        assert!(preamp < -5.0);
+
+        // Ramped like any other master gain change
+        eq.set_master_gain_db(preamp);
+        assert!(eq.is_smoothing());
+        eq.finish_smoothing();
+        assert_eq!(eq.config().master_gain_db, preamp);
+        assert!((eq.master_gain_linear - 10.0_f32.powf(preamp / 20.0)).abs() < 1e-6);
+
+        eq.set_master_gain_db(f32::NAN);
+        assert_eq!(eq.config().master_gain_db, preamp);
+        eq.set_master_gain_db(0.0);
+        eq.finish_smoothing();
+        assert!((eq.master_gain_linear - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_multichannel_state_is_independent() {
+        let mut surround = Equalizer::new(48000.0);
+        surround.set_channel_count(6).unwrap();
+        surround.set_band_gain(5, 9.0).unwrap();
+        let mut stereo = Equalizer::new(48000.0);
+        stereo.set_band_gain(5, 9.0).unwrap();
+
+        // Signal only on the center channel (index 2 in 5.1)
+        let mut buffer = vec![0.0_f32; 6 * 256];
+        for (i, frame) in buffer.chunks_exact_mut(6).enumerate() {
+            frame[2] = (i as f32 * 0.13).sin() * 0.5;
+        }
+        let input = buffer.clone();
+        surround.process_interleaved(&mut buffer);
+
+        for (frame, original) in buffer.chunks_exact(6).zip(input.chunks_exact(6)) {
+            let (expected, _) = stereo.process_sample(original[2], 0.0);
+            assert!((frame[2] - expected).abs() < 1e-6);
+            for ch in [0, 1, 3, 4, 5] {
+                assert_eq!(frame[ch], 0.0, "channel {} should stay silent", ch);
+            }
+        }
+    }
+
+    #[test]
+    fn test_channel_count_bounds() {
+        let mut eq = Equalizer::new(48000.0);
+        assert_eq!(eq.channel_count(), 2);
+        assert!(matches!(eq.set_channel_count(0), Err(DspError::InvalidChannelCount(0))));
+        assert!(eq.set_channel_count(MAX_CHANNELS + 1).is_err());
+        eq.set_channel_count(8).unwrap();
+        assert_eq!(eq.channel_count(), 8);
+
+        // 7.1 buffer with a trailing partial frame
+        let mut buffer = vec![0.25_f32; 8 * 4 + 3];
+        eq.process_interleaved(&mut buffer);
+        assert!(buffer.iter().all(|s| s.is_finite()));
+        assert_eq!(&buffer[32..], &[0.25, 0.25, 0.25]);
+    }
+
+    /// Several band types, a dynamic band and a disabled band
+    fn mixed_eq(channels: usize) -> Equalizer {
+        let mut eq = Equalizer::new(48000.0);
+        eq.set_channel_count(channels).unwrap();
+        eq.set_band_gain(0, 4.0).unwrap();
+        eq.set_band_gain(5, -3.0).unwrap();
+        eq.set_band_gain(7, -6.0).unwrap();
+        let mut params = eq.config().bands[7].params();
+        params.dynamics = Some(BandDynamics::default());
+        eq.set_band_params(7, params).unwrap();
+        let mut params = eq.config().bands[1].params();
+        params.band_type = BandType::HighPass;
+        params.slope = FilterSlope::Db48;
+        eq.set_band_params(1, params).unwrap();
+        let mut params = eq.config().bands[9].params();
+        params.enabled = false;
+        eq.set_band_params(9, params).unwrap();
+        eq
+    }
+
+    #[test]
+    fn test_process_block_matches_interleaved() {
+        // Uneven buffers, with a gain and master ramp started mid-stream
+        let sizes = [37, 5, 256, 1, 100, 64];
+        for channels in [1, 2, 3, 6, 8] {
+            let mut block = mixed_eq(channels);
+            let mut scalar = mixed_eq(channels);
+            let mut n = 0;
+            for (round, frames) in sizes.iter().cycle().take(24).enumerate() {
+                if round == 6 {
+                    for eq in [&mut block, &mut scalar] {
+                        eq.set_band_gain(3, -7.5).unwrap();
+                        let mut config = eq.config().clone();
+                        config.master_gain_db = -2.0;
+                        eq.update_config(config).unwrap();
+                    }
+                }
+                let mut buffer: Vec<f32> = (0..frames * channels)
+                    .map(|_| {
+                        n += 1;
+                        (n as f32 * 0.037).sin() * 0.8
+                    })
+                    .collect();
+                // Trailing partial frame is left alone by both
+                buffer.push(0.5);
+                let mut expected = buffer.clone();
+                block.process_block(&mut buffer);
+                scalar.process_interleaved(&mut expected);
+                assert_eq!(buffer, expected, "{} channels, round {}", channels, round);
+            }
+        }
+    }
+
+    #[test]
+    fn test_process_block_disabled_passthrough() {
+        let mut eq = mixed_eq(2);
+        let mut config = eq.config().clone();
+        config.enabled = false;
+        eq.update_config(config).unwrap();
+        let mut buffer: Vec<f32> = (0..512).map(|n| (n as f32 * 0.1).sin()).collect();
+        let input = buffer.clone();
+        eq.process_block(&mut buffer);
+        assert_eq!(buffer, input);
+    }
+
+    #[test]
+    fn test_flat_band_is_flushed_under_signal() {
+        let mut eq = single_band(BandType::Peaking, FilterSlope::Db12, 6.0);
+        let mut buffer: Vec<f32> = (0..2 * 4800).map(|n| (n as f32 * 0.05).sin()).collect();
+        eq.process_block(&mut buffer);
+        assert!(!eq.unity_bands[0]);
+
+        // Back to 0 dB: coefficients go flat after the ramp, the ringing
+        // decays to the flush threshold and the band is skipped from then on
+        eq.set_band_gain(0, 0.0).unwrap();
+        for _ in 0..10 {
+            let mut buffer: Vec<f32> = (0..2 * 4800).map(|n| (n as f32 * 0.05).sin()).collect();
+            eq.process_block(&mut buffer);
+        }
+        assert!(eq.unity_bands[0]);
+        assert!(eq.band_is_silent(0));
+    }
+
+    /// Gain (dB) of a settled EQ at `freq`, from the first channel's
+    /// correlation with the input tone over one second (whole periods for
+    /// integer frequencies) after one second of settling
+    fn measure_tone_db(eq: &mut Equalizer, freq: f32) -> f64 {
+        const CHUNK: usize = 1024;
+        let sample_rate = eq.sample_rate() as usize;
+        let phase = |n: usize| std::f64::consts::TAU * freq as f64 * n as f64 / sample_rate as f64;
+        let (mut re, mut im) = (0.0_f64, 0.0_f64);
+        let mut buffer = [0.0_f32; 2 * CHUNK];
+        for start in (0..2 * sample_rate).step_by(CHUNK) {
+            for (n, frame) in buffer.chunks_exact_mut(2).enumerate() {
+                frame.fill(phase(start + n).sin() as f32 * 0.5);
+            }
+            eq.process_block(&mut buffer);
+            for (n, frame) in buffer.chunks_exact(2).enumerate() {
+                if (sample_rate..2 * sample_rate).contains(&(start + n)) {
+                    re += frame[0] as f64 * phase(start + n).sin();
+                    im += frame[0] as f64 * phase(start + n).cos();
+                }
+            }
+        }
+        let amplitude = 2.0 * re.hypot(im) / sample_rate as f64;
+        20.0 * (amplitude / 0.5).log10()
+    }
+
+    #[test]
+    fn test_low_bands_match_theory_at_192k() {
+        // The bottom of the 10-band layout, boosted
+        let mut eq = Equalizer::with_band_count(192000.0, 2).unwrap();
+        let mut params = eq.config().bands[1].params();
+        params.frequency = 62.0;
+        params.band_type = BandType::Peaking;
+        eq.set_band_params(1, params).unwrap();
+        eq.set_band_gain(0, 6.0).unwrap();
+        eq.set_band_gain(1, -4.0).unwrap();
+        eq.finish_smoothing();
+        assert!(eq.band_uses_double(0) && eq.band_uses_double(1));
+
+        let frequencies = [10.0, 20.0, 31.0, 62.0, 125.0];
+        let theory = eq.config().frequency_response(192000.0, &frequencies).unwrap();
+        let mut worst_f32 = 0.0_f64;
+        for (&freq, &expected) in frequencies.iter().zip(&theory.magnitude_db) {
+            eq.set_filter_precision(FilterPrecision::Auto);
+            let measured = measure_tone_db(&mut eq, freq);
+            assert!(
+                (measured - expected as f64).abs() < 0.001,
+                "{} Hz: measured {:.4} dB, expected {:.4} dB",
+                freq,
+                measured,
+                expected
+            );
+
+            eq.set_filter_precision(FilterPrecision::Single);
+            let error = measure_tone_db(&mut eq, freq) - expected as f64;
+            worst_f32 = worst_f32.max(error.abs());
+        }
+        // What f64 fixes: f32 is audibly off somewhere in the bass
+        assert!(worst_f32 > 0.1, "f32 worst error {:.4} dB", worst_f32);
+    }
+
+    #[test]
+    fn test_auto_precision_follows_ratio() {
+        let mut eq = Equalizer::new(48000.0);
+        // 31 Hz is below 48 Hz (48 kHz / 1000), 62 Hz is not
+        assert!(eq.band_uses_double(0));
+        assert!(!eq.band_uses_double(1));
+        eq.set_sample_rate(192000.0).unwrap();
+        assert!((0..3).all(|band| eq.band_uses_double(band)));
+        assert!(!eq.band_uses_double(3));
+
+        eq.set_filter_precision(FilterPrecision::Double);
+        assert!((0..10).all(|band| eq.band_uses_double(band)));
+        eq.set_filter_precision(FilterPrecision::Single);
+        assert!((0..10).all(|band| !eq.band_uses_double(band)));
+        assert!(!eq.band_uses_double(10));
+    }
+
+    #[test]
+    fn test_precision_change_keeps_state() {
+        let tone = |n: usize| (n as f32 * 0.004).sin() * 0.5;
+        let mut switched = Equalizer::new(48000.0);
+        let mut reference = Equalizer::new(48000.0);
+        for eq in [&mut switched, &mut reference] {
+            eq.set_band_gain(0, 9.0).unwrap();
+            eq.set_band_gain(1, -6.0).unwrap();
+            eq.finish_smoothing();
+        }
+        for n in 0..4800 {
+            switched.process_sample(tone(n), tone(n));
+            reference.process_sample(tone(n), tone(n));
+        }
+
+        // Mid-signal the bands move between f32 and f64 without a click.
+        // f32 strays a little from f64; dropping the state would be off by
+        // about the signal itself.
+        for precision in [FilterPrecision::Single, FilterPrecision::Double] {
+            switched.set_filter_precision(precision);
+            for n in 4800..9600 {
+                let (a, _) = switched.process_sample(tone(n), tone(n));
+                let (b, _) = reference.process_sample(tone(n), tone(n));
+                assert!((a - b).abs() < 0.01, "{:?} at {}: {} vs {}", precision, n, a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_matched_design_follows_analog_shelf() {
+        // +6 dB high shelf at 16 kHz, 48 kHz: the analog curve, A (A s² + √A/Q s + 1)
+        // / (s² + √A/Q s + A) with s = j f / 16k
+        let analog_db = |freq: f32| {
+            let (a, x) = (10.0_f64.powf(6.0 / 40.0), freq as f64 / 16000.0);
+            let d = (a.sqrt() * x / Q_BUTTERWORTH_F32 as f64).powi(2);
+            10.0 * (a * a * ((1.0 - a * x * x).powi(2) + d) / ((a - x * x).powi(2) + d)).log10()
+        };
+        let mut eq = single_band(BandType::HighShelf, FilterSlope::Db12, 6.0);
+        let mut params = eq.config().bands[0].params();
+        params.frequency = 16000.0;
+        eq.set_band_params(0, params).unwrap();
+        eq.finish_smoothing();
+
+        let bilinear_error = (measure_tone_db(&mut eq, 20000.0) - analog_db(20000.0)).abs();
+        assert!(bilinear_error > 1.0, "bilinear error {:.3} dB", bilinear_error);
+
+        eq.set_filter_design(FilterDesign::Matched);
+        assert_eq!(eq.filter_design(), FilterDesign::Matched);
+        let frequencies = [4000.0, 12000.0, 16000.0, 20000.0, 23000.0];
+        let predicted = eq.config().frequency_response(48000.0, &frequencies).unwrap();
+        for (&freq, &expected) in frequencies.iter().zip(&predicted.magnitude_db) {
+            let measured = measure_tone_db(&mut eq, freq);
+            assert!(
+                (measured - analog_db(freq)).abs() < 0.3,
+                "{} Hz: measured {:.3} dB, analog {:.3} dB",
+                freq,
+                measured,
+                analog_db(freq)
+            );
+            // The response curve shows the design actually running
+            assert!((measured - expected as f64).abs() < 0.01, "{} Hz", freq);
+        }
+    }
+
+    #[test]
+    fn test_set_sample_rate_keeps_response() {
+        let mut eq = single_band(BandType::Peaking, FilterSlope::Db12, 6.0);
+        eq.set_sample_rate(96000.0).unwrap();
+        assert_eq!(eq.sample_rate(), 96000.0);
+        assert!(!eq.is_smoothing());
+        assert!((measure_gain_db(&mut eq, 1000.0) - 6.0).abs() < 0.1);
+
+        // A band above the new Nyquist is rejected and nothing changes
+        let mut params = eq.config().bands[0].params();
+        params.frequency = 20000.0;
+        eq.set_band_params(0, params).unwrap();
+        assert!(eq.set_sample_rate(32000.0).is_err());
+        assert!(eq.set_sample_rate(0.0).is_err());
+        assert_eq!(eq.sample_rate(), 96000.0);
+    }
 }
@@ -5,15 +5,45 @@ use thiserror::Error;
 /// Errors that can occur during DSP operations
 #[derive(Error, Debug)]
 pub enum DspError {
-    #[error("Invalid band index: {0} (must be 0-9)")]
+    #[error("Invalid band index: {0} (must be less than the band count)")]
     InvalidBandIndex(usize),
 
+    #[error("Invalid band count: {0} (must be 1-31)")]
+    InvalidBandCount(usize),
+
+    #[error("Invalid channel count: {0} (must be 1-8)")]
+    InvalidChannelCount(usize),
+
+    #[error("Invalid frequency: {0}Hz (must be positive)")]
+    InvalidFrequency(f32),
+
+    #[error("Invalid Q: {0} (must be positive)")]
+    InvalidQ(f32),
+
     #[error("Invalid filter coefficients for frequency {frequency}Hz at sample rate {sample_rate}Hz")]
     InvalidCoefficients { frequency: f32, sample_rate: f32 },
 
     #[error("Sample rate must be positive, got {0}")]
     InvalidSampleRate(f32),
 
+    #[error("Invalid {name}: {value} (out of range)")]
+    InvalidParameter { name: &'static str, value: f32 },
+
+    #[error("Invalid impulse response: {0}")]
+    InvalidImpulseResponse(String),
+
+    #[error("Invalid EqualizerAPO config at line {line}: {reason}")]
+    InvalidApoConfig { line: usize, reason: String },
+
+    #[error("Config has {filters} filters but the EQ has {bands} bands; raise the band count to import all of them")]
+    TooManyFilters { filters: usize, bands: usize },
+
+    #[error("Invalid processor chain: {0}")]
+    InvalidChain(String),
+
+    #[error("Frequency responses were evaluated at different frequencies")]
+    ResponseMismatch,
+
     #[error("Buffer size mismatch: expected {expected}, got {got}")]
     BufferSizeMismatch { expected: usize, got: usize },
 }
@@ -32,5 +62,11 @@ mod tests {
             sample_rate: 48000.0,
         };
         assert!(err.to_string().contains("1000"));
+
+        let err = DspError::InvalidBandCount(64);
+        assert!(err.to_string().contains("64"));
+
+        let err = DspError::InvalidQ(-1.5);
+        assert!(err.to_string().contains("-1.5"));
     }
 }
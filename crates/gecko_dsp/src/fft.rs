@@ -57,7 +57,9 @@ pub struct SpectrumAnalyzer {
     /// Number of samples written since last FFT
     samples_since_fft: AtomicU32,
     /// Samples needed before computing next FFT (~30fps at 48kHz)
-    samples_per_fft: u32,
+    samples_per_fft: AtomicU32,
+    /// Target update rate, to recompute `samples_per_fft` on rate changes
+    fps: u32,
     /// Flag indicating new spectrum data is available
     spectrum_ready: AtomicBool,
     /// Output spectrum (magnitude in dB, 0.0 to 1.0 normalized)
@@ -99,7 +101,8 @@ impl SpectrumAnalyzer {
             sample_buffer: vec![0.0; FFT_SIZE],
             write_pos: AtomicU32::new(0),
             samples_since_fft: AtomicU32::new(0),
-            samples_per_fft,
+            samples_per_fft: AtomicU32::new(samples_per_fft),
+            fps,
             spectrum_ready: AtomicBool::new(false),
             spectrum: parking_lot::RwLock::new([0.0; NUM_BINS]),
             smoothed_spectrum: parking_lot::RwLock::new([0.0; NUM_BINS]),
@@ -110,6 +113,14 @@ impl SpectrumAnalyzer {
         }
     }
 
+    /// Update the sample rate so the update rate stays at the target fps
+    ///
+    /// Safe to call from the audio thread (single atomic store).
+    pub fn set_sample_rate(&self, sample_rate: f32) {
+        let samples_per_fft = (sample_rate / self.fps as f32) as u32;
+        self.samples_per_fft.store(samples_per_fft.max(1), Ordering::Relaxed);
+    }
+
     /// Push a stereo sample pair to the analyzer
     ///
     /// # Real-time Safety
@@ -142,7 +153,7 @@ impl SpectrumAnalyzer {
         // Note: We don't reset samples_since_fft here - update() handles that
         // This prevents race conditions where update() clears spectrum_ready
         // but samples_since_fft was already reset, causing dropped frames
-        if count >= self.samples_per_fft {
+        if count >= self.samples_per_fft.load(Ordering::Relaxed) {
             self.spectrum_ready.store(true, Ordering::Release);
         }
     }
@@ -1,9 +1,26 @@
 //! Gecko DSP - Digital Signal Processing Module
 //!
 //! This crate provides the audio processing pipeline for Gecko, including:
-//! - 10-band parametric equalizer using BiQuad filters
+//! - N-band parametric equalizer using BiQuad filters (3, 10, 31 bands or custom)
+//! - Optional linear-phase FIR mode for the equalizer (partitioned FFT convolution)
+//! - Linked, independent left/right and mid/side EQ modes with stereo width
+//! - Dynamic EQ bands whose gain follows the level of their frequency range
+//! - EqualizerAPO / AutoEQ config import and export
+//! - Exact frequency, phase and group delay response of EQ curves
+//! - Optional matched-magnitude (decramped) biquad design near Nyquist
+//! - Impulse response convolution (headphone/room correction) from WAV files
+//! - bs2b-style headphone crossfeed with classic presets
 //! - FFT spectrum analyzer for real-time visualization
+//! - Feed-forward compressor with gain reduction metering
+//! - 3-5 band multiband compressor with Linkwitz-Riley crossovers
 //! - Soft clipping/limiter to prevent harsh digital distortion
+//! - Lookahead true-peak brickwall limiter
+//! - EBU R128 / ITU-R BS.1770 loudness metering (LUFS, loudness range, true peak)
+//! - Automatic loudness leveling toward a target LUFS
+//! - Windowed-sinc sample-rate conversion between capture and output rates
+//! - Clock drift compensation holding a ring buffer at a target latency
+//! - Crossfaded delay line for per-app A/V sync offsets
+//! - Configurable master and per-app effect chains, swapped without locks
 //! - Lock-free coefficient updates for real-time safety
 //! - Zero-allocation processing path
 //!
@@ -12,19 +29,69 @@
 //! The DSP chain follows a strict "no allocation in audio callback" rule.
 //! Filter coefficients are updated atomically between buffer processing calls.
 
+mod apo;
+mod auto_level;
+mod chain;
+mod compressor;
+mod convolution;
+mod convolver;
+mod crossfeed;
+mod delay;
+mod drift;
+mod dynamic_eq;
 mod eq;
 mod error;
 mod fft;
+mod limiter;
+mod linear_phase;
+mod loudness;
+mod matched;
+mod multiband;
 mod presets;
 mod processor;
+mod resampler;
+mod response;
+mod simd;
 mod soft_clip;
+mod stereo_eq;
+mod true_peak;
+mod wav;
 
-pub use eq::{Band, BandType, Equalizer, EqConfig, EQ_BANDS};
+pub use eq::{
+    band_layout, band_layout_frequency, band_layout_params, AtomicBandParams, Band, BandParams,
+    BandType, Equalizer, EqConfig, FilterPrecision, FilterSlope, DOUBLE_PRECISION_RATIO, EQ_BANDS,
+    ISO_31_BANDS, MAX_BANDS, MAX_CHANNELS, MAX_STAGES, TONE_BANDS,
+};
+pub use apo::ApoImport;
+pub use auto_level::{AutoLevelSettings, AutoLeveler, AUTO_LEVEL_GATE_LUFS};
+pub use chain::{
+    chain_channel, ChainConfig, ChainReceiver, ChainScope, ChainSender, ProcessorKind,
+    ProcessorSlot, CHAIN_FADE_MS, MAX_CHAIN_LENGTH,
+};
+pub use compressor::{Compressor, CompressorMeter, CompressorSettings, DetectionMode, GainReduction};
+pub use convolver::{Convolver, ImpulseResponse, IR_BLOCK, MAX_IR_LENGTH};
+pub use crossfeed::{Crossfeed, CrossfeedPreset, CrossfeedSettings};
+pub use delay::{Delay, DELAY_FADE_MS, MAX_SYNC_OFFSET_MS};
+pub use drift::{DriftCompensator, DriftMeter, DriftStats, MAX_DRIFT};
+pub use dynamic_eq::{BandDynamics, DynamicDirection};
 pub use error::DspError;
 pub use fft::{SpectrumAnalyzer, FFT_SIZE, NUM_BINS};
+pub use limiter::{Limiter, LimiterSettings, OutputStage, LIMITER_LOOKAHEAD_MS};
+pub use loudness::{
+    loudness_channel_weight, LoudnessAnalyzer, LoudnessMeter, LoudnessStats, LOUDNESS_FLOOR_LUFS,
+};
+pub use linear_phase::{PhaseMode, FIR_BLOCK, LINEAR_PHASE_TAPS};
+pub use matched::FilterDesign;
+pub use multiband::{
+    MultibandCompressor, MultibandPreset, MultibandSettings, MAX_MULTIBAND_BANDS,
+    MIN_MULTIBAND_BANDS,
+};
 pub use presets::{Preset, PRESETS};
-pub use processor::{AudioProcessor, ProcessContext};
+pub use processor::{AudioProcessor, ProcessContext, ProcessorChain};
+pub use resampler::Resampler;
+pub use response::{log_frequencies, FrequencyResponse, MIN_RESPONSE_DB};
 pub use soft_clip::SoftClipper;
+pub use stereo_eq::{EqChannelMode, StereoEqualizer, MAX_STEREO_WIDTH};
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,489 @@
+//! Lookahead True-Peak Limiter
+//!
+//! A brickwall limiter for the end of the chain. Unlike [`SoftClipper`](crate::SoftClipper)
+//! it doesn't waveshape: it delays the audio slightly and lowers the gain
+//! smoothly *before* a peak arrives, so the output stays clean and below the
+//! ceiling, including peaks that fall between samples.
+//!
+//! # Algorithm
+//!
+//! ```text
+//! input ─┬────────────── delay (lookahead) ──────────────► × ──► clamp ──► output
+//!        └─► 4x true-peak ─► gain needed ─► hold min ─► release ─► average ─┘
+//! ```
+//!
+//! - True peak: the signal is 4x oversampled with a polyphase FIR (as in
+//!   ITU-R BS.1770) and the largest interpolated value is used.
+//! - The gain needed for each peak is held for the lookahead window, then
+//!   released with a one-pole and averaged over the window. The average
+//!   ramps down ahead of a peak and always reaches the needed gain in time.
+//! - A final clamp at the ceiling catches rounding at the sample level.
+//!
+//! Adds a fixed latency; see [`Limiter::latency_samples`].
+
+use crate::eq::MAX_CHANNELS;
+use crate::error::DspError;
+use crate::processor::{AudioProcessor, ProcessContext};
+use crate::true_peak::{TruePeakDetector, INTERPOLATOR_DELAY};
+
+/// Lookahead window; gain reduction ramps in over this time
+pub const LIMITER_LOOKAHEAD_MS: f32 = 1.5;
+
+/// Which processor runs as the last stage of the output path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum OutputStage {
+    /// tanh soft clipper: no latency, colours loud peaks
+    #[default]
+    SoftClip,
+    /// Lookahead true-peak limiter: transparent, adds a small latency
+    Limiter,
+}
+
+/// Limiter parameters
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LimiterSettings {
+    /// Maximum true peak of the output (-24 to 0 dBTP)
+    pub ceiling_db: f32,
+    /// Time to recover ~63% of the gain reduction (1 to 2000 ms)
+    pub release_ms: f32,
+    pub enabled: bool,
+}
+
+impl Default for LimiterSettings {
+    fn default() -> Self {
+        Self {
+            ceiling_db: -1.0,
+            release_ms: 100.0,
+            enabled: true,
+        }
+    }
+}
+
+impl LimiterSettings {
+    /// Check every parameter is in range
+    pub fn validate(&self) -> Result<(), DspError> {
+        if !(-24.0..=0.0).contains(&self.ceiling_db) {
+            return Err(DspError::InvalidParameter {
+                name: "ceiling_db",
+                value: self.ceiling_db,
+            });
+        }
+        if !(1.0..=2000.0).contains(&self.release_ms) {
+            return Err(DspError::InvalidParameter {
+                name: "release_ms",
+                value: self.release_ms,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Lookahead samples at a sample rate
+fn lookahead_samples(sample_rate: f32) -> usize {
+    ((LIMITER_LOOKAHEAD_MS * sample_rate / 1000.0).ceil() as usize).max(1)
+}
+
+/// Lookahead brickwall limiter with true-peak detection
+///
+/// Channels are linked: the loudest channel sets the gain for all. Buffers
+/// are sized for [`MAX_CHANNELS`] up front, so nothing allocates after `new`.
+pub struct Limiter {
+    settings: LimiterSettings,
+    sample_rate: f32,
+    channels: usize,
+    ceiling: f32,
+    release_coeff: f32,
+    lookahead: usize,
+    true_peak: TruePeakDetector,
+
+    // Sliding minimum of the needed gain over lookahead + 1 frames:
+    // a monotonic queue of (frame, gain) in a ring buffer
+    min_queue: Vec<(u64, f32)>,
+    min_head: usize,
+    min_len: usize,
+    frame: u64,
+
+    // Released envelope and its running average over the lookahead
+    envelope: f32,
+    average_ring: Vec<f32>,
+    average_pos: usize,
+    average_sum: f64,
+
+    // Audio delay line per channel (lookahead + interpolator delay)
+    delay: Vec<f32>,
+    delay_len: usize,
+    delay_pos: usize,
+    gain: f32,
+}
+
+impl Limiter {
+    /// Create a limiter at `sample_rate` for 2 channels
+    ///
+    /// Note: Allocates the delay lines; call during setup.
+    pub fn new(sample_rate: f32, settings: LimiterSettings) -> Result<Self, DspError> {
+        if sample_rate <= 0.0 {
+            return Err(DspError::InvalidSampleRate(sample_rate));
+        }
+        settings.validate()?;
+
+        let lookahead = lookahead_samples(sample_rate);
+        let delay_len = lookahead + INTERPOLATOR_DELAY;
+        let mut limiter = Self {
+            settings,
+            sample_rate,
+            channels: 2,
+            ceiling: 1.0,
+            release_coeff: 0.0,
+            lookahead,
+            true_peak: TruePeakDetector::new(),
+            min_queue: vec![(0, 1.0); lookahead + 1],
+            min_head: 0,
+            min_len: 0,
+            frame: 0,
+            envelope: 1.0,
+            average_ring: vec![1.0; lookahead],
+            average_pos: 0,
+            average_sum: lookahead as f64,
+            delay: vec![0.0; MAX_CHANNELS * delay_len],
+            delay_len,
+            delay_pos: 0,
+            gain: 1.0,
+        };
+        limiter.update_coefficients();
+        Ok(limiter)
+    }
+
+    /// Latency in samples the limiter adds at `sample_rate`
+    ///
+    /// Lets callers report the delay before building a limiter.
+    pub fn latency_at(sample_rate: f32) -> usize {
+        lookahead_samples(sample_rate) + INTERPOLATOR_DELAY
+    }
+
+    /// Delay in samples between input and output
+    pub fn latency_samples(&self) -> usize {
+        self.delay_len
+    }
+
+    /// Replace all parameters; the current gain reduction carries over
+    pub fn set_settings(&mut self, settings: LimiterSettings) -> Result<(), DspError> {
+        settings.validate()?;
+        self.settings = settings;
+        self.update_coefficients();
+        Ok(())
+    }
+
+    /// Current parameters
+    pub fn settings(&self) -> &LimiterSettings {
+        &self.settings
+    }
+
+    /// Enable or disable (disabled passes audio untouched, without delay)
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.settings.enabled = enabled;
+    }
+
+    /// Sample rate this limiter was built for
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Set the number of interleaved channels (1 to [`MAX_CHANNELS`])
+    ///
+    /// State is reset when the count changes.
+    ///
+    /// # Real-time Safety
+    /// No allocations. Safe to call from an audio callback between buffers.
+    pub fn set_channel_count(&mut self, channels: usize) -> Result<(), DspError> {
+        if channels == 0 || channels > MAX_CHANNELS {
+            return Err(DspError::InvalidChannelCount(channels));
+        }
+        if channels != self.channels {
+            self.channels = channels;
+            self.reset_state();
+        }
+        Ok(())
+    }
+
+    /// Number of interleaved channels processed
+    pub fn channel_count(&self) -> usize {
+        self.channels
+    }
+
+    /// Gain reduction (dB, positive) applied to the last output sample
+    pub fn gain_reduction_db(&self) -> f32 {
+        -20.0 * self.gain.log10()
+    }
+
+    fn update_coefficients(&mut self) {
+        self.ceiling = 10.0_f32.powf(self.settings.ceiling_db / 20.0);
+        self.release_coeff = (-1000.0 / (self.settings.release_ms * self.sample_rate)).exp();
+    }
+
+    fn reset_state(&mut self) {
+        self.true_peak.reset();
+        self.min_head = 0;
+        self.min_len = 0;
+        self.frame = 0;
+        self.envelope = 1.0;
+        self.average_ring.fill(1.0);
+        self.average_pos = 0;
+        self.average_sum = self.lookahead as f64;
+        self.delay.fill(0.0);
+        self.delay_pos = 0;
+        self.gain = 1.0;
+    }
+
+    /// Push the needed gain for the newest frame; returns the minimum over the window
+    #[inline]
+    fn hold_minimum(&mut self, needed: f32) -> f32 {
+        let capacity = self.min_queue.len();
+        // Drop entries that can never be the minimum again
+        while self.min_len > 0 {
+            let back = (self.min_head + self.min_len - 1) % capacity;
+            if self.min_queue[back].1 >= needed {
+                self.min_len -= 1;
+            } else {
+                break;
+            }
+        }
+        let back = (self.min_head + self.min_len) % capacity;
+        self.min_queue[back] = (self.frame, needed);
+        self.min_len += 1;
+
+        // Expire the front once it leaves the window of lookahead + 1 frames
+        if self.frame - self.min_queue[self.min_head].0 > self.lookahead as u64 {
+            self.min_head = (self.min_head + 1) % capacity;
+            self.min_len -= 1;
+        }
+        self.frame += 1;
+        self.min_queue[self.min_head].1
+    }
+
+    /// Process an interleaved buffer in-place
+    ///
+    /// A trailing partial frame is left untouched.
+    ///
+    /// # Real-time Safety
+    /// No allocations, no locks. O(n) where n = buffer length.
+    pub fn process_interleaved(&mut self, buffer: &mut [f32]) {
+        if !self.settings.enabled {
+            return;
+        }
+
+        let channels = self.channels;
+        for frame in buffer.chunks_exact_mut(channels) {
+            let peak = self.true_peak.process_frame(frame);
+            let needed = if peak > self.ceiling {
+                self.ceiling / peak
+            } else {
+                1.0
+            };
+
+            // Hold, then release: never above the held minimum
+            let held = self.hold_minimum(needed);
+            self.envelope = if held < self.envelope {
+                held
+            } else {
+                held + self.release_coeff * (self.envelope - held)
+            };
+
+            // Average over the lookahead window ramps the gain in ahead of the peak
+            self.average_sum += (self.envelope - self.average_ring[self.average_pos]) as f64;
+            self.average_ring[self.average_pos] = self.envelope;
+            self.average_pos = (self.average_pos + 1) % self.lookahead;
+            self.gain = (self.average_sum / self.lookahead as f64) as f32;
+
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                let slot = channel * self.delay_len + self.delay_pos;
+                let delayed = self.delay[slot];
+                self.delay[slot] = *sample;
+                *sample = (delayed * self.gain).clamp(-self.ceiling, self.ceiling);
+            }
+            self.delay_pos = (self.delay_pos + 1) % self.delay_len;
+        }
+    }
+}
+
+impl AudioProcessor for Limiter {
+    fn process(&mut self, buffer: &mut [f32], context: &ProcessContext) {
+        let _ = self.set_channel_count(context.channels);
+        self.process_interleaved(buffer);
+    }
+
+    fn reset(&mut self) {
+        self.reset_state();
+    }
+
+    fn name(&self) -> &'static str {
+        "Limiter"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.settings.enabled
+    }
+
+    fn latency_samples(&self) -> usize {
+        self.delay_len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(ceiling_db: f32) -> Limiter {
+        let settings = LimiterSettings {
+            ceiling_db,
+            ..LimiterSettings::default()
+        };
+        let mut limiter = Limiter::new(48000.0, settings).unwrap();
+        limiter.set_channel_count(1).unwrap();
+        limiter
+    }
+
+    /// Peak of a signal reconstructed at 16x with a long windowed sinc
+    fn reconstructed_peak(signal: &[f32]) -> f32 {
+        let half = 64;
+        let mut peak = 0.0_f32;
+        for n in half..signal.len() - half {
+            for k in 0..16 {
+                let t = n as f64 + k as f64 / 16.0;
+                let mut sum = 0.0;
+                for (m, &sample) in signal.iter().enumerate().take(n + half).skip(n - half) {
+                    let x = t - m as f64;
+                    let sinc = if x == 0.0 {
+                        1.0
+                    } else {
+                        (std::f64::consts::PI * x).sin() / (std::f64::consts::PI * x)
+                    };
+                    let window = 0.5 + 0.5 * (std::f64::consts::PI * x / half as f64).cos();
+                    sum += sample as f64 * sinc * window;
+                }
+                peak = peak.max(sum.abs() as f32);
+            }
+        }
+        peak
+    }
+
+    #[test]
+    fn test_quiet_signal_only_delayed() {
+        let mut limiter = limiter(-1.0);
+        let latency = limiter.latency_samples();
+        assert_eq!(latency, 72 + INTERPOLATOR_DELAY);
+        assert_eq!(Limiter::latency_at(48000.0), latency);
+
+        let input: Vec<f32> = (0..1000).map(|n| 0.5 * (n as f32 * 0.05).sin()).collect();
+        let mut output = input.clone();
+        limiter.process_interleaved(&mut output);
+        for n in latency..input.len() {
+            assert_eq!(output[n], input[n - latency]);
+        }
+    }
+
+    #[test]
+    fn test_sample_peaks_held_at_ceiling() {
+        let mut limiter = limiter(-3.0);
+        let ceiling = 10.0_f32.powf(-3.0 / 20.0);
+        // Loud noise-like signal with sudden jumps
+        let mut buffer: Vec<f32> = (0..48000)
+            .map(|n| {
+                ((n * 7919 % 1013) as f32 / 506.5 - 1.0) * if n % 4000 < 2000 { 4.0 } else { 0.2 }
+            })
+            .collect();
+        limiter.process_interleaved(&mut buffer);
+        assert!(buffer.iter().all(|s| s.abs() <= ceiling));
+    }
+
+    #[test]
+    fn test_inter_sample_peaks_limited() {
+        // fs/4 sine at 45 degrees: samples sit at 0.707 of the true peak, so
+        // a 1.2 amplitude never clips a sample detector (0.85 < 0.89)
+        let mut limiter = limiter(-1.0);
+        let ceiling = 10.0_f32.powf(-1.0 / 20.0);
+        let input: Vec<f32> = (0..4800)
+            .map(|n| {
+                1.2 * (std::f32::consts::FRAC_PI_2 * n as f32 + std::f32::consts::FRAC_PI_4).sin()
+            })
+            .collect();
+        assert!(input.iter().all(|s| s.abs() < ceiling));
+
+        let mut output = input.clone();
+        limiter.process_interleaved(&mut output);
+        let peak = reconstructed_peak(&output[1000..]);
+        assert!(
+            peak <= ceiling * 1.005,
+            "True peak {} exceeds ceiling {}",
+            peak,
+            ceiling
+        );
+        assert!(peak > ceiling * 0.95, "Over-limited to {}", peak);
+    }
+
+    #[test]
+    fn test_gain_ramps_in_before_peak() {
+        let mut limiter = limiter(-6.0);
+        let latency = limiter.latency_samples();
+        let ceiling = 10.0_f32.powf(-6.0 / 20.0);
+
+        // Steady 0.25 with a single full-scale spike at 500
+        let mut buffer = vec![0.25; 1500];
+        buffer[500] = 1.0;
+        limiter.process_interleaved(&mut buffer);
+
+        let spike = 500 + latency;
+        assert!((buffer[spike] - ceiling).abs() < 1e-3);
+        // Before the spike the gain eases down rather than jumping
+        let steps: Vec<f32> = buffer[spike - 80..spike]
+            .windows(2)
+            .map(|w| (w[1] - w[0]).abs())
+            .collect();
+        assert!(
+            steps.iter().all(|&s| s < 0.01),
+            "Gain should ramp in smoothly"
+        );
+        assert!(buffer[spike - 80] > 0.249);
+    }
+
+    #[test]
+    fn test_release() {
+        let mut limiter = limiter(-6.0);
+        // Long enough for the step's interpolation overshoot to release too
+        let mut loud = vec![1.0; 48000];
+        limiter.process_interleaved(&mut loud);
+        let reduction = limiter.gain_reduction_db();
+        assert!((reduction - 6.0).abs() < 0.01);
+
+        // Once the hold has passed, each release time (100 ms) leaves ~37% of
+        // the remaining reduction (in gain)
+        let mut quiet = vec![0.1; 960];
+        limiter.process_interleaved(&mut quiet);
+        let before = 1.0 - limiter.gain;
+        let mut quiet = vec![0.1; 4800];
+        limiter.process_interleaved(&mut quiet);
+        let ratio = (1.0 - limiter.gain) / before;
+        assert!((ratio - 0.368).abs() < 0.01, "Release left {}", ratio);
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        let mut limiter = limiter(-1.0);
+        assert!(limiter
+            .set_settings(LimiterSettings {
+                ceiling_db: 1.0,
+                ..Default::default()
+            })
+            .is_err());
+        assert!(limiter
+            .set_settings(LimiterSettings {
+                release_ms: 0.0,
+                ..Default::default()
+            })
+            .is_err());
+        assert!(limiter.set_channel_count(0).is_err());
+        assert!(Limiter::new(-1.0, LimiterSettings::default()).is_err());
+    }
+}
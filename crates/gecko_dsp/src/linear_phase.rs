@@ -0,0 +1,316 @@
+//! Linear-Phase FIR Equalization
+//!
+//! Cascaded RBJ biquads are minimum phase: every boost or cut also shifts
+//! the phase around its frequency. For mastering work that smearing is
+//! unwanted, so an [`Equalizer`](crate::Equalizer) can instead run a
+//! linear-phase FIR designed from the magnitude response of its `EqConfig`.
+//!
+//! # Design
+//!
+//! The band magnitudes are sampled on an FFT grid, given zero phase, inverse
+//! transformed and shifted by half the length, then windowed. The result is
+//! a symmetric kernel whose only phase effect is a constant delay.
+//!
+//! # Convolution
+//!
+//! The kernel runs through the shared partitioned FFT convolver in
+//! [`FIR_BLOCK`]-sample partitions, so the cost per sample stays flat
+//! despite the long kernel.
+//!
+//! Latency is half the kernel plus one block; see [`LinearPhaseFir::latency`].
+//! When the response changes, the next block is crossfaded from the old
+//! kernel to the new one so edits don't click.
+
+use std::sync::Arc;
+
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+
+use crate::convolution::PartitionedConvolver;
+use crate::eq::{EqConfig, MAX_CHANNELS};
+
+/// Kernel length at 48 kHz (~170 ms, ~6 Hz resolution)
+///
+/// Scaled up for higher sample rates so bass bands keep the same resolution.
+pub const LINEAR_PHASE_TAPS: usize = 8192;
+
+/// Partition size for the convolution (also the processing block)
+pub const FIR_BLOCK: usize = 512;
+
+/// How an equalizer realises its magnitude response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum PhaseMode {
+    /// Cascaded biquads: no latency, phase shift around each band
+    #[default]
+    Minimum,
+    /// Linear-phase FIR: no phase distortion, adds a fixed latency
+    Linear,
+}
+
+/// Kernel length for a sample rate (power of two, at least [`LINEAR_PHASE_TAPS`])
+fn kernel_length(sample_rate: f32) -> usize {
+    let scale = (sample_rate / 48000.0).ceil().max(1.0) as usize;
+    (LINEAR_PHASE_TAPS * scale).next_power_of_two()
+}
+
+/// Delay in samples of a linear-phase FIR at `sample_rate`
+pub(crate) fn latency_at(sample_rate: f32) -> usize {
+    kernel_length(sample_rate) / 2 + FIR_BLOCK
+}
+
+/// Linear-phase FIR designed from an [`EqConfig`]
+///
+/// All buffers are allocated up front for [`MAX_CHANNELS`] channels, so
+/// redesigns and channel count changes never allocate.
+pub(crate) struct LinearPhaseFir {
+    taps: usize,
+    sample_rate: f32,
+    dirty: bool,
+
+    // Design: cos(w) per FFT bin, zero-phase workspace, window
+    cos_w: Vec<f64>,
+    magnitude: Vec<f32>,
+    design_buffer: Vec<Complex<f32>>,
+    design_fft: Arc<dyn Fft<f32>>,
+    design_scratch: Vec<Complex<f32>>,
+    window: Vec<f32>,
+
+    convolver: PartitionedConvolver,
+}
+
+impl LinearPhaseFir {
+    /// Create a filter for `config` (allocates; call outside the audio callback)
+    pub(crate) fn new(sample_rate: f32, channels: usize, config: &EqConfig) -> Self {
+        let taps = kernel_length(sample_rate);
+        let design_fft = FftPlanner::new().plan_fft_inverse(taps);
+
+        let cos_w = (0..=taps / 2)
+            .map(|k| (std::f64::consts::TAU * k as f64 / taps as f64).cos())
+            .collect();
+        // Periodic Blackman: zero at n = 0 and symmetric around taps / 2,
+        // which keeps the delay at exactly half the kernel
+        let window = (0..taps)
+            .map(|n| {
+                let phase = std::f32::consts::TAU * n as f32 / taps as f32;
+                0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos()
+            })
+            .collect();
+
+        let zero = Complex::new(0.0, 0.0);
+        let mut fir = Self {
+            taps,
+            sample_rate,
+            dirty: false,
+            cos_w,
+            magnitude: vec![1.0; taps / 2 + 1],
+            design_buffer: vec![zero; taps],
+            design_scratch: vec![zero; design_fft.get_inplace_scratch_len()],
+            design_fft,
+            window,
+            convolver: PartitionedConvolver::new(FIR_BLOCK, taps, 1, channels.clamp(1, MAX_CHANNELS)),
+        };
+        fir.design(config);
+        fir.convolver.swap_kernels(false);
+        fir
+    }
+
+    /// Delay in samples: half the kernel plus one block of buffering
+    pub(crate) fn latency(&self) -> usize {
+        self.taps / 2 + self.convolver.block()
+    }
+
+    /// Redesign from the current config at the next block boundary
+    pub(crate) fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Change the number of channels convolved (state is cleared)
+    pub(crate) fn set_channel_count(&mut self, channels: usize) {
+        self.convolver.set_channel_count(channels);
+    }
+
+    /// Clear all delay lines
+    pub(crate) fn reset(&mut self) {
+        self.convolver.reset();
+    }
+
+    /// Push one interleaved frame and replace it with the delayed, filtered frame
+    ///
+    /// Channels beyond the configured count pass through untouched.
+    ///
+    /// # Real-time Safety
+    /// No allocations. Every [`FIR_BLOCK`] frames one block is convolved,
+    /// and a pending redesign from `config` is applied first.
+    #[inline]
+    pub(crate) fn process_frame(&mut self, frame: &mut [f32], config: &EqConfig) {
+        if self.convolver.push_frame(frame) {
+            if self.dirty {
+                self.dirty = false;
+                self.design(config);
+                self.convolver.swap_kernels(true);
+            }
+            self.convolver.process_block();
+        }
+    }
+
+    /// Build the kernel for `config` into the convolver's pending set
+    ///
+    /// # Real-time Safety
+    /// No allocations; costs one magnitude evaluation per band and bin
+    /// plus one FFT per partition.
+    fn design(&mut self, config: &EqConfig) {
+        if config.enabled {
+            config.magnitude_into(self.sample_rate, &self.cos_w, &mut self.magnitude);
+        } else {
+            // Disabled stays in the path as a pure delay so latency is constant
+            self.magnitude.fill(1.0);
+        }
+
+        // Zero-phase spectrum: real and even
+        let half = self.taps / 2;
+        for (k, bin) in self.design_buffer.iter_mut().enumerate() {
+            let mirrored = if k <= half { k } else { self.taps - k };
+            *bin = Complex::new(self.magnitude[mirrored], 0.0);
+        }
+        self.design_fft
+            .process_with_scratch(&mut self.design_buffer, &mut self.design_scratch);
+
+        // Rotate the zero-phase response to centre it at taps / 2 and window it
+        let (taps, design, window) = (self.taps, &self.design_buffer, &self.window);
+        let scale = 1.0 / taps as f32;
+        self.convolver
+            .write_kernel(0, |n| design[(n + half) % taps].re * window[n] * scale);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eq::{BandType, Equalizer};
+
+    /// Mono impulse response of a linear-phase equalizer
+    fn impulse_response(eq: &mut Equalizer, length: usize) -> Vec<f32> {
+        (0..length)
+            .map(|n| {
+                let mut frame = [if n == 0 { 1.0 } else { 0.0 }];
+                eq.process_frame(&mut frame);
+                frame[0]
+            })
+            .collect()
+    }
+
+    fn linear_eq(config: EqConfig) -> Equalizer {
+        let mut eq = Equalizer::with_config(48000.0, config).unwrap();
+        eq.set_channel_count(1).unwrap();
+        eq.set_phase_mode(PhaseMode::Linear);
+        eq
+    }
+
+    #[test]
+    fn test_kernel_length_scales_with_sample_rate() {
+        assert_eq!(kernel_length(44100.0), 8192);
+        assert_eq!(kernel_length(48000.0), 8192);
+        assert_eq!(kernel_length(96000.0), 16384);
+        assert_eq!(kernel_length(192000.0), 32768);
+    }
+
+    #[test]
+    fn test_flat_response_is_pure_delay() {
+        let mut eq = linear_eq(EqConfig::default());
+        let latency = eq.latency_samples();
+        assert_eq!(latency, 8192 / 2 + FIR_BLOCK);
+        assert_eq!(Equalizer::linear_phase_latency_at(48000.0), latency);
+
+        let response = impulse_response(&mut eq, latency + FIR_BLOCK);
+        for (n, &sample) in response.iter().enumerate() {
+            if n == latency {
+                assert!((sample - 1.0).abs() < 1e-3, "Peak should be unity, got {}", sample);
+            } else {
+                assert!(sample.abs() < 1e-3, "Flat EQ leaked {} at sample {}", sample, n);
+            }
+        }
+    }
+
+    #[test]
+    fn test_impulse_response_is_symmetric() {
+        let mut config = EqConfig::default();
+        config.set_band_gain(2, 9.0).unwrap();
+        config.set_band_gain(7, -6.0).unwrap();
+        let mut eq = linear_eq(config);
+        let latency = eq.latency_samples();
+
+        let response = impulse_response(&mut eq, 2 * latency);
+        let peak = response[latency];
+        for offset in 1..(LINEAR_PHASE_TAPS / 2) {
+            let before = response[latency - offset];
+            let after = response[latency + offset];
+            assert!(
+                (before - after).abs() <= 1e-4 * peak.abs().max(1.0),
+                "Asymmetric at offset {}: {} vs {}",
+                offset,
+                before,
+                after
+            );
+        }
+    }
+
+    #[test]
+    fn test_magnitude_matches_minimum_phase() {
+        let mut config = EqConfig::default();
+        config.bands[5].gain_db = 6.0;
+        config.bands[5].band_type = BandType::Peaking;
+        let mut eq = linear_eq(config);
+        let latency = eq.latency_samples();
+
+        // Steady-state 1 kHz sine: the peak band should add 6 dB
+        let frequency = 1000.0;
+        let length = latency + 9600;
+        let mut peak = 0.0_f32;
+        for n in 0..length {
+            let mut frame = [(std::f32::consts::TAU * frequency * n as f32 / 48000.0).sin()];
+            eq.process_frame(&mut frame);
+            if n > latency + 4800 {
+                peak = peak.max(frame[0].abs());
+            }
+        }
+        let gain_db = 20.0 * peak.log10();
+        assert!((gain_db - 6.0).abs() < 0.2, "Expected ~6 dB, got {}", gain_db);
+    }
+
+    #[test]
+    fn test_response_change_is_crossfaded() {
+        let mut eq = linear_eq(EqConfig::default());
+        let latency = eq.latency_samples();
+
+        // Constant input settles to a constant output; a 12 dB bass cut
+        // should then move it without a step between consecutive samples
+        let mut previous = 0.0_f32;
+        let mut max_step = 0.0_f32;
+        for n in 0..(latency + 8 * FIR_BLOCK) {
+            if n == latency + 2 * FIR_BLOCK {
+                eq.set_band_gain(0, -12.0).unwrap();
+            }
+            let mut frame = [0.5];
+            eq.process_frame(&mut frame);
+            if n > latency + FIR_BLOCK {
+                max_step = max_step.max((frame[0] - previous).abs());
+            }
+            previous = frame[0];
+        }
+        assert!(max_step < 0.01, "Kernel swap should be smooth, max step {}", max_step);
+        assert!(previous < 0.2, "DC should be cut by the low shelf, got {}", previous);
+    }
+
+    #[test]
+    fn test_disabled_keeps_latency() {
+        let mut config = EqConfig::default();
+        config.set_band_gain(0, 12.0).unwrap();
+        config.enabled = false;
+        let mut eq = linear_eq(config);
+
+        let latency = eq.latency_samples();
+        let response = impulse_response(&mut eq, latency + 1);
+        assert!((response[latency] - 1.0).abs() < 1e-3);
+    }
+}
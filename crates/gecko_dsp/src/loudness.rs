@@ -0,0 +1,685 @@
+//! EBU R128 / ITU-R BS.1770 Loudness Metering
+//!
+//! Sample peaks say little about how loud something sounds: a compressed
+//! podcast and a dynamic film score can peak at the same level yet differ
+//! by 15 dB in perceived loudness. This meter measures loudness the way
+//! broadcast does, in LUFS (loudness units relative to full scale).
+//!
+//! # Measurement
+//!
+//! ```text
+//! input ─┬─► K-weighting ─► mean square per channel ─► weighted sum ─► 100 ms blocks
+//!        └─► 4x true peak                                               │
+//!                         momentary (400 ms) ◄──────────────────────────┤
+//!                         short-term (3 s)   ◄──────────────────────────┤
+//!                         integrated (gated) ◄── histogram of 400 ms ───┤
+//!                         loudness range     ◄── histogram of 3 s ──────┘
+//! ```
+//!
+//! - K-weighting is the BS.1770 pre-filter (a high shelf modelling the head)
+//!   followed by the RLB high-pass, designed for any sample rate.
+//! - Integrated loudness gates 400 ms blocks (75% overlap) at -70 LUFS and
+//!   then 10 LU below the ungated mean.
+//! - Loudness range (EBU Tech 3342) is the spread between the 10th and 95th
+//!   percentiles of short-term loudness, gated at -70 LUFS and 20 LU below.
+//!
+//! The gating histograms have 0.1 LU bins and are allocated up front, so the
+//! whole measurement runs in the audio callback without allocating. Results
+//! are published through a [`LoudnessMeter`] that the UI thread reads
+//! without locks.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+
+use crate::eq::MAX_CHANNELS;
+use crate::error::DspError;
+use crate::true_peak::TruePeakDetector;
+
+/// Reported value for silence (loudness and true peak are otherwise -inf)
+pub const LOUDNESS_FLOOR_LUFS: f32 = -120.0;
+
+/// Absolute gate for integrated loudness and loudness range
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+
+/// Relative gate for integrated loudness (below the ungated mean)
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+/// Relative gate for loudness range (below the ungated mean)
+const RANGE_GATE_LU: f64 = -20.0;
+
+/// Sub-blocks (100 ms) per momentary window
+const MOMENTARY_BLOCKS: usize = 4;
+
+/// Sub-blocks (100 ms) per short-term window
+const SHORT_TERM_BLOCKS: usize = 30;
+
+/// Gating histogram: 0.1 LU bins from the absolute gate up to +10 LUFS
+const HISTOGRAM_BINS: usize = 800;
+const BINS_PER_LU: f64 = 10.0;
+
+/// Loudness of a BS.1770 weighted mean square
+pub(crate) fn loudness(energy: f64) -> f64 {
+    -0.691 + 10.0 * energy.log10()
+}
+
+/// Clamp a loudness or dB value for reporting
+fn report(value: f64) -> f32 {
+    if value.is_finite() {
+        (value as f32).max(LOUDNESS_FLOOR_LUFS)
+    } else {
+        LOUDNESS_FLOOR_LUFS
+    }
+}
+
+/// BS.1770 channel weight for a speaker position name ("FL", "LFE", "RL", ...)
+///
+/// Front and centre channels count once, surrounds +1.5 dB, LFE not at all.
+pub fn loudness_channel_weight(position: &str) -> f32 {
+    match position {
+        "LFE" => 0.0,
+        "RL" | "RR" | "RC" | "SL" | "SR" => 1.41,
+        _ => 1.0,
+    }
+}
+
+/// Snapshot of all loudness measurements
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LoudnessStats {
+    /// Loudness over the last 400 ms (LUFS)
+    pub momentary_lufs: f32,
+    /// Loudness over the last 3 s (LUFS)
+    pub short_term_lufs: f32,
+    /// Gated loudness since the last reset (LUFS)
+    pub integrated_lufs: f32,
+    /// Loudness range since the last reset (LU)
+    pub loudness_range_lu: f32,
+    /// Highest true peak since the last reset (dBTP)
+    pub true_peak_dbtp: f32,
+}
+
+impl Default for LoudnessStats {
+    fn default() -> Self {
+        Self {
+            momentary_lufs: LOUDNESS_FLOOR_LUFS,
+            short_term_lufs: LOUDNESS_FLOOR_LUFS,
+            integrated_lufs: LOUDNESS_FLOOR_LUFS,
+            loudness_range_lu: 0.0,
+            true_peak_dbtp: LOUDNESS_FLOOR_LUFS,
+        }
+    }
+}
+
+/// Lock-free handle to a [`LoudnessAnalyzer`]'s measurements
+///
+/// Cheap to clone; values update every 100 ms of processed audio.
+#[derive(Debug, Clone)]
+pub struct LoudnessMeter {
+    shared: Arc<MeterShared>,
+}
+
+#[derive(Debug)]
+struct MeterShared {
+    /// LoudnessStats fields as f32 bits, in declaration order
+    values: [AtomicU32; 5],
+    reset_requested: AtomicBool,
+}
+
+impl Default for LoudnessMeter {
+    fn default() -> Self {
+        let stats = LoudnessStats::default();
+        Self {
+            shared: Arc::new(MeterShared {
+                values: [
+                    AtomicU32::new(stats.momentary_lufs.to_bits()),
+                    AtomicU32::new(stats.short_term_lufs.to_bits()),
+                    AtomicU32::new(stats.integrated_lufs.to_bits()),
+                    AtomicU32::new(stats.loudness_range_lu.to_bits()),
+                    AtomicU32::new(stats.true_peak_dbtp.to_bits()),
+                ],
+                reset_requested: AtomicBool::new(false),
+            }),
+        }
+    }
+}
+
+impl LoudnessMeter {
+    /// Latest measurements
+    pub fn stats(&self) -> LoudnessStats {
+        let value = |i: usize| f32::from_bits(self.shared.values[i].load(Ordering::Relaxed));
+        LoudnessStats {
+            momentary_lufs: value(0),
+            short_term_lufs: value(1),
+            integrated_lufs: value(2),
+            loudness_range_lu: value(3),
+            true_peak_dbtp: value(4),
+        }
+    }
+
+    /// Restart integrated loudness, range and true peak
+    ///
+    /// Picked up by the analyzer at the start of its next buffer.
+    pub fn request_reset(&self) {
+        self.shared.reset_requested.store(true, Ordering::Relaxed);
+    }
+
+    fn take_reset_request(&self) -> bool {
+        self.shared.reset_requested.swap(false, Ordering::Relaxed)
+    }
+
+    fn publish(&self, stats: LoudnessStats) {
+        let values = [
+            stats.momentary_lufs,
+            stats.short_term_lufs,
+            stats.integrated_lufs,
+            stats.loudness_range_lu,
+            stats.true_peak_dbtp,
+        ];
+        for (atomic, value) in self.shared.values.iter().zip(values) {
+            atomic.store(value.to_bits(), Ordering::Relaxed);
+        }
+    }
+}
+
+/// Biquad coefficients `[b0, b1, b2, a1, a2]`
+type Coefficients = [f64; 5];
+
+/// The two K-weighting stages at `sample_rate` (BS.1770, any rate)
+fn k_weighting(sample_rate: f64) -> [Coefficients; 2] {
+    // Stage 1: high shelf, +4 dB above ~1.7 kHz
+    let f0 = 1681.974450955533;
+    let gain_db = 3.999843853973347;
+    let q = 0.7071752369554196;
+    let k = (std::f64::consts::PI * f0 / sample_rate).tan();
+    let vh = 10.0_f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = [
+        (vh + vb * k / q + k * k) / a0,
+        2.0 * (k * k - vh) / a0,
+        (vh - vb * k / q + k * k) / a0,
+        2.0 * (k * k - 1.0) / a0,
+        (1.0 - k / q + k * k) / a0,
+    ];
+
+    // Stage 2: RLB high-pass at ~38 Hz
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+    let k = (std::f64::consts::PI * f0 / sample_rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = [
+        1.0,
+        -2.0,
+        1.0,
+        2.0 * (k * k - 1.0) / a0,
+        (1.0 - k / q + k * k) / a0,
+    ];
+
+    [shelf, high_pass]
+}
+
+/// Gating histogram of block loudness (count and summed energy per bin)
+struct GateHistogram {
+    counts: Vec<u32>,
+    energy: Vec<f64>,
+}
+
+impl GateHistogram {
+    fn new() -> Self {
+        Self {
+            counts: vec![0; HISTOGRAM_BINS],
+            energy: vec![0.0; HISTOGRAM_BINS],
+        }
+    }
+
+    fn clear(&mut self) {
+        self.counts.fill(0);
+        self.energy.fill(0.0);
+    }
+
+    fn bin(lufs: f64) -> usize {
+        (((lufs - ABSOLUTE_GATE_LUFS) * BINS_PER_LU) as usize).min(HISTOGRAM_BINS - 1)
+    }
+
+    fn bin_center(bin: usize) -> f64 {
+        ABSOLUTE_GATE_LUFS + (bin as f64 + 0.5) / BINS_PER_LU
+    }
+
+    /// Add a block if it passes the absolute gate
+    fn add(&mut self, block_energy: f64) {
+        let lufs = loudness(block_energy);
+        if lufs >= ABSOLUTE_GATE_LUFS {
+            let bin = Self::bin(lufs);
+            self.counts[bin] += 1;
+            self.energy[bin] += block_energy;
+        }
+    }
+
+    /// First bin at or above `gate_lu` relative to the mean of all blocks
+    fn relative_gate_bin(&self, gate_lu: f64) -> Option<usize> {
+        let count: u64 = self.counts.iter().map(|&c| c as u64).sum();
+        if count == 0 {
+            return None;
+        }
+        let mean = self.energy.iter().sum::<f64>() / count as f64;
+        let gate = loudness(mean) + gate_lu;
+        Some(if gate < ABSOLUTE_GATE_LUFS {
+            0
+        } else {
+            Self::bin(gate)
+        })
+    }
+
+    /// Integrated loudness: mean energy of blocks passing both gates
+    fn integrated(&self) -> f64 {
+        let Some(gate_bin) = self.relative_gate_bin(RELATIVE_GATE_LU) else {
+            return f64::NEG_INFINITY;
+        };
+        let count: u64 = self.counts[gate_bin..].iter().map(|&c| c as u64).sum();
+        let energy: f64 = self.energy[gate_bin..].iter().sum();
+        if count == 0 {
+            return f64::NEG_INFINITY;
+        }
+        loudness(energy / count as f64)
+    }
+
+    /// Loudness range: 95th minus 10th percentile of blocks passing both gates
+    fn range(&self) -> f64 {
+        let Some(gate_bin) = self.relative_gate_bin(RANGE_GATE_LU) else {
+            return 0.0;
+        };
+        let counts = &self.counts[gate_bin..];
+        let total: u64 = counts.iter().map(|&c| c as u64).sum();
+        if total == 0 {
+            return 0.0;
+        }
+        let percentile = |fraction: f64| {
+            let target = ((total - 1) as f64 * fraction).round() as u64;
+            let mut seen = 0;
+            for (i, &c) in counts.iter().enumerate() {
+                seen += c as u64;
+                if seen > target {
+                    return Self::bin_center(gate_bin + i);
+                }
+            }
+            Self::bin_center(HISTOGRAM_BINS - 1)
+        };
+        percentile(0.95) - percentile(0.10)
+    }
+}
+
+/// BS.1770 loudness analyzer
+///
+/// Owned by the audio callback; read the results through [`LoudnessAnalyzer::meter`].
+/// All state is sized for [`MAX_CHANNELS`] up front, so nothing allocates after `new`.
+pub struct LoudnessAnalyzer {
+    sample_rate: f32,
+    channels: usize,
+    weights: [f64; MAX_CHANNELS],
+    filters: [Coefficients; 2],
+    // Per channel: [x1, x2, y1, y2] for each K-weighting stage
+    filter_state: [[[f64; 4]; 2]; MAX_CHANNELS],
+
+    // Current 100 ms sub-block: weighted sum of squares so far
+    block_length: usize,
+    block_position: usize,
+    block_sum: f64,
+    // Weighted mean square of the last SHORT_TERM_BLOCKS sub-blocks (ring)
+    sub_blocks: [f64; SHORT_TERM_BLOCKS],
+    sub_block_index: usize,
+    sub_block_count: usize,
+    blocks_measured: u64,
+
+    integrated: GateHistogram,
+    range: GateHistogram,
+    true_peak: TruePeakDetector,
+    max_true_peak: f32,
+
+    meter: LoudnessMeter,
+}
+
+impl LoudnessAnalyzer {
+    /// Create an analyzer at `sample_rate` for 2 equally weighted channels
+    ///
+    /// Note: Allocates the gating histograms; call during setup.
+    pub fn new(sample_rate: f32) -> Result<Self, DspError> {
+        Self::with_meter(sample_rate, LoudnessMeter::default())
+    }
+
+    /// Like [`LoudnessAnalyzer::new`], publishing to an existing meter
+    ///
+    /// Lets a meter outlive the stream it measures: readers keep their handle
+    /// when the stream is rebuilt. The measurements restart.
+    pub fn with_meter(sample_rate: f32, meter: LoudnessMeter) -> Result<Self, DspError> {
+        if sample_rate <= 0.0 || !sample_rate.is_finite() {
+            return Err(DspError::InvalidSampleRate(sample_rate));
+        }
+        let mut weights = [0.0; MAX_CHANNELS];
+        weights[..2].fill(1.0);
+        Ok(Self {
+            sample_rate,
+            channels: 2,
+            weights,
+            filters: k_weighting(sample_rate as f64),
+            filter_state: [[[0.0; 4]; 2]; MAX_CHANNELS],
+            block_length: ((sample_rate / 10.0).round() as usize).max(1),
+            block_position: 0,
+            block_sum: 0.0,
+            sub_blocks: [0.0; SHORT_TERM_BLOCKS],
+            sub_block_index: 0,
+            sub_block_count: 0,
+            blocks_measured: 0,
+            integrated: GateHistogram::new(),
+            range: GateHistogram::new(),
+            true_peak: TruePeakDetector::new(),
+            max_true_peak: 0.0,
+            meter,
+        })
+    }
+
+    /// Handle for reading the measurements from another thread
+    pub fn meter(&self) -> LoudnessMeter {
+        self.meter.clone()
+    }
+
+    /// Sample rate this analyzer was built for
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Follow a new sample rate
+    ///
+    /// All measurements restart; the meter stays the same.
+    ///
+    /// # Real-time Safety
+    /// No allocations. Safe to call from an audio callback between buffers.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) -> Result<(), DspError> {
+        if sample_rate <= 0.0 || !sample_rate.is_finite() {
+            return Err(DspError::InvalidSampleRate(sample_rate));
+        }
+        self.sample_rate = sample_rate;
+        self.filters = k_weighting(sample_rate as f64);
+        self.block_length = ((sample_rate / 10.0).round() as usize).max(1);
+        self.reset();
+        Ok(())
+    }
+
+    /// Number of interleaved channels measured
+    pub fn channel_count(&self) -> usize {
+        self.channels
+    }
+
+    /// Set the interleaved layout by per-channel weight (see [`loudness_channel_weight`])
+    ///
+    /// The number of weights is the channel count (1 to [`MAX_CHANNELS`]).
+    /// All measurements restart when the count changes.
+    ///
+    /// # Real-time Safety
+    /// No allocations. Safe to call from an audio callback between buffers.
+    pub fn set_channel_weights(&mut self, weights: &[f32]) -> Result<(), DspError> {
+        if weights.is_empty() || weights.len() > MAX_CHANNELS {
+            return Err(DspError::InvalidChannelCount(weights.len()));
+        }
+        if let Some(&bad) = weights.iter().find(|w| !(0.0..=2.0).contains(*w)) {
+            return Err(DspError::InvalidParameter {
+                name: "channel_weight",
+                value: bad,
+            });
+        }
+        self.weights = [0.0; MAX_CHANNELS];
+        for (weight, &w) in self.weights.iter_mut().zip(weights) {
+            *weight = w as f64;
+        }
+        if weights.len() != self.channels {
+            self.channels = weights.len();
+            self.reset();
+        }
+        Ok(())
+    }
+
+    /// Clear all measurements and filter state
+    pub fn reset(&mut self) {
+        self.filter_state = [[[0.0; 4]; 2]; MAX_CHANNELS];
+        self.block_position = 0;
+        self.block_sum = 0.0;
+        self.sub_blocks = [0.0; SHORT_TERM_BLOCKS];
+        self.sub_block_index = 0;
+        self.sub_block_count = 0;
+        self.blocks_measured = 0;
+        self.integrated.clear();
+        self.range.clear();
+        self.true_peak.reset();
+        self.max_true_peak = 0.0;
+        self.meter.publish(LoudnessStats::default());
+    }
+
+    /// Measure an interleaved buffer (the audio is not modified)
+    ///
+    /// A trailing partial frame is ignored.
+    ///
+    /// # Real-time Safety
+    /// No allocations, no locks. Every 100 ms of audio the gating
+    /// histograms are scanned (a few thousand operations).
+    pub fn process_interleaved(&mut self, buffer: &[f32]) {
+        if self.meter.take_reset_request() {
+            self.reset();
+        }
+
+        let channels = self.channels;
+        for frame in buffer.chunks_exact(channels) {
+            self.max_true_peak = self.max_true_peak.max(self.true_peak.process_frame(frame));
+
+            let mut sum = 0.0;
+            for (channel, &sample) in frame.iter().enumerate() {
+                let weighted = self.k_weight(channel, sample as f64);
+                sum += self.weights[channel] * weighted * weighted;
+            }
+            self.block_sum += sum;
+            self.block_position += 1;
+            if self.block_position == self.block_length {
+                self.finish_block();
+            }
+        }
+    }
+
+    /// Latest measurements (same as reading the [`LoudnessMeter`])
+    pub fn stats(&self) -> LoudnessStats {
+        self.meter.stats()
+    }
+
+    /// Frames per 100 ms sub-block
+    pub(crate) fn block_frames(&self) -> usize {
+        self.block_length
+    }
+
+    /// Sub-blocks completed since the last reset
+    pub(crate) fn blocks_measured(&self) -> u64 {
+        self.blocks_measured
+    }
+
+    /// Weighted mean square of the newest completed sub-block
+    pub(crate) fn last_block_energy(&self) -> f64 {
+        self.sub_blocks[(self.sub_block_index + SHORT_TERM_BLOCKS - 1) % SHORT_TERM_BLOCKS]
+    }
+
+    #[inline]
+    fn k_weight(&mut self, channel: usize, sample: f64) -> f64 {
+        let mut x = sample;
+        for (coeffs, state) in self
+            .filters
+            .iter()
+            .zip(self.filter_state[channel].iter_mut())
+        {
+            let [b0, b1, b2, a1, a2] = *coeffs;
+            let y = b0 * x + b1 * state[0] + b2 * state[1] - a1 * state[2] - a2 * state[3];
+            *state = [x, state[0], y, state[2]];
+            x = y;
+        }
+        x
+    }
+
+    /// Mean energy of the newest `blocks` sub-blocks
+    fn window_energy(&self, blocks: usize) -> f64 {
+        let available = blocks.min(self.sub_block_count);
+        let sum: f64 = (0..available)
+            .map(|i| {
+                self.sub_blocks
+                    [(self.sub_block_index + SHORT_TERM_BLOCKS - 1 - i) % SHORT_TERM_BLOCKS]
+            })
+            .sum();
+        sum / available.max(1) as f64
+    }
+
+    /// Close a 100 ms sub-block, update the gates and publish
+    fn finish_block(&mut self) {
+        self.sub_blocks[self.sub_block_index] = self.block_sum / self.block_length as f64;
+        self.sub_block_index = (self.sub_block_index + 1) % SHORT_TERM_BLOCKS;
+        self.sub_block_count = (self.sub_block_count + 1).min(SHORT_TERM_BLOCKS);
+        self.blocks_measured += 1;
+        self.block_position = 0;
+        self.block_sum = 0.0;
+
+        let momentary = self.window_energy(MOMENTARY_BLOCKS);
+        let short_term = self.window_energy(SHORT_TERM_BLOCKS);
+        if self.sub_block_count >= MOMENTARY_BLOCKS {
+            self.integrated.add(momentary);
+        }
+        if self.sub_block_count >= SHORT_TERM_BLOCKS {
+            self.range.add(short_term);
+        }
+
+        self.meter.publish(LoudnessStats {
+            momentary_lufs: report(loudness(momentary)),
+            short_term_lufs: report(loudness(short_term)),
+            integrated_lufs: report(self.integrated.integrated()),
+            loudness_range_lu: self.range.range() as f32,
+            true_peak_dbtp: report(20.0 * (self.max_true_peak as f64).log10()),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stereo sine at `dbfs` peak for `seconds`
+    fn sine(frequency: f32, dbfs: f32, seconds: f32) -> Vec<f32> {
+        let amplitude = 10.0_f32.powf(dbfs / 20.0);
+        let frames = (48000.0 * seconds) as usize;
+        (0..frames)
+            .flat_map(|n| {
+                let x = amplitude * (std::f32::consts::TAU * frequency * n as f32 / 48000.0).sin();
+                [x, x]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_k_weighting_at_48k() {
+        // Coefficients published in ITU-R BS.1770
+        let [shelf, high_pass] = k_weighting(48000.0);
+        let expected_shelf = [
+            1.53512485958697,
+            -2.69169618940638,
+            1.19839281085285,
+            -1.69065929318241,
+            0.73248077421585,
+        ];
+        for (a, b) in shelf.iter().zip(expected_shelf) {
+            assert!((a - b).abs() < 1e-9, "{} vs {}", a, b);
+        }
+        assert!((high_pass[3] + 1.99004745483398).abs() < 1e-9);
+        assert!((high_pass[4] - 0.99007225036621).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_reference_tone() {
+        // EBU Tech 3341 case 1: a 1 kHz stereo sine at -23 dBFS reads -23 LUFS
+        let mut analyzer = LoudnessAnalyzer::new(48000.0).unwrap();
+        analyzer.process_interleaved(&sine(1000.0, -23.0, 5.0));
+        let stats = analyzer.stats();
+        assert!((stats.momentary_lufs + 23.0).abs() < 0.1, "{:?}", stats);
+        assert!((stats.short_term_lufs + 23.0).abs() < 0.1, "{:?}", stats);
+        assert!((stats.integrated_lufs + 23.0).abs() < 0.1, "{:?}", stats);
+        assert!((stats.true_peak_dbtp + 23.0).abs() < 0.1, "{:?}", stats);
+        assert!(stats.loudness_range_lu < 0.5, "{:?}", stats);
+    }
+
+    #[test]
+    fn test_set_sample_rate() {
+        let mut analyzer = LoudnessAnalyzer::new(44100.0).unwrap();
+        analyzer.process_interleaved(&sine(1000.0, -10.0, 1.0));
+        analyzer.set_sample_rate(48000.0).unwrap();
+        assert_eq!(analyzer.stats(), LoudnessStats::default());
+
+        analyzer.process_interleaved(&sine(1000.0, -23.0, 5.0));
+        let integrated = analyzer.stats().integrated_lufs;
+        assert!((integrated + 23.0).abs() < 0.1, "Expected -23 LUFS, got {}", integrated);
+        assert!(analyzer.set_sample_rate(0.0).is_err());
+        assert_eq!(analyzer.sample_rate(), 48000.0);
+    }
+
+    #[test]
+    fn test_gating() {
+        // EBU Tech 3341 case 3: -36, -23, -36 dBFS for 10, 60, 10 s
+        // gates the quiet parts away and reads -23 LUFS
+        let mut analyzer = LoudnessAnalyzer::new(48000.0).unwrap();
+        analyzer.process_interleaved(&sine(1000.0, -36.0, 10.0));
+        analyzer.process_interleaved(&sine(1000.0, -23.0, 60.0));
+        analyzer.process_interleaved(&sine(1000.0, -36.0, 10.0));
+        let integrated = analyzer.stats().integrated_lufs;
+        assert!(
+            (integrated + 23.0).abs() < 0.1,
+            "Expected -23 LUFS, got {}",
+            integrated
+        );
+
+        // Silence never passes the absolute gate
+        let mut analyzer = LoudnessAnalyzer::new(48000.0).unwrap();
+        analyzer.process_interleaved(&vec![0.0; 96000]);
+        assert_eq!(analyzer.stats(), LoudnessStats::default());
+    }
+
+    #[test]
+    fn test_loudness_range() {
+        // EBU Tech 3342 case 1: -20 then -30 dBFS for 20 s each reads 10 LU
+        let mut analyzer = LoudnessAnalyzer::new(48000.0).unwrap();
+        analyzer.process_interleaved(&sine(1000.0, -20.0, 20.0));
+        analyzer.process_interleaved(&sine(1000.0, -30.0, 20.0));
+        let range = analyzer.stats().loudness_range_lu;
+        assert!((range - 10.0).abs() < 0.2, "Expected 10 LU, got {}", range);
+    }
+
+    #[test]
+    fn test_channel_weights_and_reset() {
+        let mut analyzer = LoudnessAnalyzer::new(48000.0).unwrap();
+        let meter = analyzer.meter();
+        analyzer.process_interleaved(&sine(1000.0, -20.0, 1.0));
+        assert!(meter.stats().integrated_lufs > -24.0);
+
+        // Only the LFE channel carries signal: it doesn't count
+        let weights = ["FL", "FR", "FC", "LFE", "RL", "RR"].map(loudness_channel_weight);
+        analyzer.set_channel_weights(&weights).unwrap();
+        assert_eq!(analyzer.channel_count(), 6);
+        let lfe_only: Vec<f32> = (0..48000)
+            .flat_map(|n| {
+                let x = 0.5 * (std::f32::consts::TAU * 60.0 * n as f32 / 48000.0).sin();
+                [0.0, 0.0, 0.0, x, 0.0, 0.0]
+            })
+            .collect();
+        analyzer.process_interleaved(&lfe_only);
+        assert_eq!(meter.stats().momentary_lufs, LOUDNESS_FLOOR_LUFS);
+
+        // A requested reset lands at the next buffer
+        analyzer.set_channel_weights(&[1.0, 1.0]).unwrap();
+        analyzer.process_interleaved(&sine(1000.0, -20.0, 1.0));
+        assert!(meter.stats().integrated_lufs > -24.0);
+        meter.request_reset();
+        analyzer.process_interleaved(&[]);
+        assert_eq!(meter.stats(), LoudnessStats::default());
+
+        assert!(analyzer.set_channel_weights(&[]).is_err());
+        assert!(analyzer.set_channel_weights(&[1.0, -1.0]).is_err());
+        assert!(LoudnessAnalyzer::new(0.0).is_err());
+    }
+}
@@ -0,0 +1,600 @@
+//! Multiband Compressor
+//!
+//! Splits the signal into 3 to 5 frequency bands and compresses each one on
+//! its own, so a booming explosion can be held down without pumping the
+//! dialogue above it.
+//!
+//! # Algorithm
+//!
+//! ```text
+//! input ─► f1 ─┬─ low ──► all-pass f2 ─► compressor 0 ─┐
+//!              └─ high ─► f2 ─┬─ low ──► compressor 1 ─┼─► sum ─► output
+//!                             └─ high ─► compressor 2 ─┘
+//! ```
+//!
+//! - Each crossover is a 4th-order Linkwitz-Riley pair: two cascaded
+//!   Butterworth biquads per side, designed like an EQ high/low-pass band.
+//!   Its low and high halves sum to an all-pass at the crossover frequency.
+//! - Bands split off below a crossover run through that same all-pass, so
+//!   every band leaves with the same phase. Uncompressed, the bands sum back
+//!   to a flat magnitude response.
+//! - Each band runs a full [`Compressor`] (linked detection, soft knee,
+//!   attack/release, makeup) and publishes its own [`CompressorMeter`].
+//!
+//! Crossovers run in f64: the lowest one sits near 100 Hz, where f32
+//! biquads add noise at high sample rates (see `FilterPrecision`).
+
+use biquad::{Biquad, Coefficients, DirectForm2Transposed};
+
+use crate::compressor::{Compressor, CompressorMeter, CompressorSettings, DetectionMode};
+use crate::eq::{unity_coefficients, Band, BandType, MAX_CHANNELS};
+use crate::error::DspError;
+use crate::matched::FilterDesign;
+use crate::processor::{AudioProcessor, ProcessContext};
+
+/// Fewest bands of a [`MultibandCompressor`]
+pub const MIN_MULTIBAND_BANDS: usize = 3;
+
+/// Most bands of a [`MultibandCompressor`]
+pub const MAX_MULTIBAND_BANDS: usize = 5;
+
+const MAX_CROSSOVERS: usize = MAX_MULTIBAND_BANDS - 1;
+
+/// Lowest and highest crossover frequency (Hz)
+const MIN_CROSSOVER_HZ: f32 = 20.0;
+const MAX_CROSSOVER_HZ: f32 = 20000.0;
+
+/// Frames split and compressed per pass through the band buffers
+const BLOCK_FRAMES: usize = 256;
+
+/// Samples of one band's buffer
+const BAND_STRIDE: usize = BLOCK_FRAMES * MAX_CHANNELS;
+
+/// Ready-made multiband settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum MultibandPreset {
+    /// Holds down booming lows (game explosions), barely touches the rest
+    #[default]
+    TameBass,
+    /// Heavy compression with makeup: quiet dialogue up, loud effects down
+    NightMode,
+    /// Fast peak limiting of the lows and highs small speakers can't take
+    SpeakerProtection,
+}
+
+impl MultibandPreset {
+    /// All presets, in display order
+    pub const ALL: [MultibandPreset; 3] = [
+        MultibandPreset::TameBass,
+        MultibandPreset::NightMode,
+        MultibandPreset::SpeakerProtection,
+    ];
+
+    /// Display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            MultibandPreset::TameBass => "Tame Bass",
+            MultibandPreset::NightMode => "Night Mode",
+            MultibandPreset::SpeakerProtection => "Speaker Protection",
+        }
+    }
+
+    /// Band layout and compressors of this preset
+    pub fn settings(&self) -> MultibandSettings {
+        let band =
+            |threshold_db, ratio, attack_ms, release_ms, makeup_gain_db| CompressorSettings {
+                threshold_db,
+                ratio,
+                attack_ms,
+                release_ms,
+                makeup_gain_db,
+                ..CompressorSettings::default()
+            };
+        let peak = |settings: CompressorSettings| CompressorSettings {
+            detection: DetectionMode::Peak,
+            knee_db: 0.0,
+            ..settings
+        };
+        let unused = CompressorSettings::default();
+
+        let (band_count, crossovers_hz, bands) = match self {
+            MultibandPreset::TameBass => (
+                3,
+                [150.0, 2500.0, 6000.0, 12000.0],
+                [
+                    band(-24.0, 4.0, 20.0, 250.0, 0.0),
+                    band(-18.0, 1.5, 10.0, 150.0, 0.0),
+                    band(-18.0, 1.5, 5.0, 100.0, 0.0),
+                    unused,
+                    unused,
+                ],
+            ),
+            MultibandPreset::NightMode => (
+                4,
+                [120.0, 1000.0, 5000.0, 12000.0],
+                [
+                    band(-36.0, 8.0, 10.0, 300.0, 3.0),
+                    band(-30.0, 4.0, 10.0, 250.0, 6.0),
+                    band(-30.0, 4.0, 5.0, 200.0, 6.0),
+                    band(-30.0, 6.0, 2.0, 150.0, 3.0),
+                    unused,
+                ],
+            ),
+            MultibandPreset::SpeakerProtection => (
+                3,
+                [100.0, 4000.0, 8000.0, 12000.0],
+                [
+                    peak(band(-18.0, 20.0, 1.0, 100.0, 0.0)),
+                    CompressorSettings {
+                        enabled: false,
+                        ..unused
+                    },
+                    peak(band(-12.0, 10.0, 1.0, 80.0, 0.0)),
+                    unused,
+                    unused,
+                ],
+            ),
+        };
+        MultibandSettings {
+            band_count,
+            crossovers_hz,
+            bands,
+            enabled: true,
+        }
+    }
+}
+
+/// Multiband compressor parameters
+///
+/// Unused crossovers and bands keep their values, so raising the band count
+/// brings back the previous layout.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MultibandSettings {
+    /// Bands in use (3 to 5)
+    pub band_count: usize,
+    /// Crossover between band `i` and `i + 1` (20 Hz to 20 kHz, ascending);
+    /// the first `band_count - 1` are used
+    pub crossovers_hz: [f32; MAX_MULTIBAND_BANDS - 1],
+    /// Compressor of each band, lowest first; the first `band_count` are used
+    pub bands: [CompressorSettings; MAX_MULTIBAND_BANDS],
+    pub enabled: bool,
+}
+
+impl Default for MultibandSettings {
+    fn default() -> Self {
+        MultibandPreset::TameBass.settings()
+    }
+}
+
+impl MultibandSettings {
+    /// Check the band count, the crossovers in use and every band's settings
+    pub fn validate(&self) -> Result<(), DspError> {
+        if !(MIN_MULTIBAND_BANDS..=MAX_MULTIBAND_BANDS).contains(&self.band_count) {
+            return Err(DspError::InvalidParameter {
+                name: "band_count",
+                value: self.band_count as f32,
+            });
+        }
+        let mut previous = 0.0;
+        for &frequency in &self.crossovers_hz[..self.band_count - 1] {
+            // Also false for NaN
+            let in_range = (MIN_CROSSOVER_HZ..=MAX_CROSSOVER_HZ).contains(&frequency);
+            if !in_range || frequency <= previous {
+                return Err(DspError::InvalidParameter {
+                    name: "crossover_hz",
+                    value: frequency,
+                });
+            }
+            previous = frequency;
+        }
+        for band in &self.bands {
+            band.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// One Linkwitz-Riley crossover, per channel
+struct Crossover {
+    low: [[DirectForm2Transposed<f64>; 2]; MAX_CHANNELS],
+    high: [[DirectForm2Transposed<f64>; 2]; MAX_CHANNELS],
+    /// Matching all-pass for each band split off below this crossover
+    allpass: [[DirectForm2Transposed<f64>; MAX_CHANNELS]; MAX_CROSSOVERS],
+}
+
+/// Low-pass, high-pass and all-pass biquads of one crossover
+type CrossoverCoefficients = [Coefficients<f64>; 3];
+
+impl Crossover {
+    fn new() -> Self {
+        let unity = || DirectForm2Transposed::<f64>::new(unity_coefficients());
+        Self {
+            low: core::array::from_fn(|_| core::array::from_fn(|_| unity())),
+            high: core::array::from_fn(|_| core::array::from_fn(|_| unity())),
+            allpass: core::array::from_fn(|_| core::array::from_fn(|_| unity())),
+        }
+    }
+
+    /// Biquads for a crossover at `frequency`
+    ///
+    /// The bilinear design keeps the low and high halves exactly
+    /// complementary; the matched one would not.
+    fn design(frequency: f32, sample_rate: f32) -> Result<CrossoverCoefficients, DspError> {
+        // A fresh band has a Butterworth Q, which an LR4 half squares
+        let stage = |band_type| {
+            Band::new(frequency, band_type)
+                .to_coefficients(sample_rate, FilterDesign::Bilinear)
+                .map(|coeffs| coeffs.stages()[0])
+        };
+        Ok([
+            stage(BandType::LowPass)?,
+            stage(BandType::HighPass)?,
+            stage(BandType::AllPass)?,
+        ])
+    }
+
+    /// Switch to new coefficients, keeping filter state
+    fn set_coefficients(&mut self, [low, high, allpass]: CrossoverCoefficients) {
+        for filter in self.low.iter_mut().flatten() {
+            filter.update_coefficients(low);
+        }
+        for filter in self.high.iter_mut().flatten() {
+            filter.update_coefficients(high);
+        }
+        for filter in self.allpass.iter_mut().flatten() {
+            filter.update_coefficients(allpass);
+        }
+    }
+
+    fn reset(&mut self) {
+        let filters = self.low.iter_mut().flatten();
+        let filters = filters.chain(self.high.iter_mut().flatten());
+        for filter in filters.chain(self.allpass.iter_mut().flatten()) {
+            filter.reset_state();
+        }
+    }
+}
+
+/// Compressor with independent settings for 3 to 5 frequency bands
+pub struct MultibandCompressor {
+    settings: MultibandSettings,
+    sample_rate: f32,
+    crossovers: [Crossover; MAX_CROSSOVERS],
+    /// One per band slot, used or not, so band count changes never allocate
+    compressors: Vec<Compressor>,
+    /// Interleaved audio of each band for the current block, `BAND_STRIDE` apart
+    band_buffers: Vec<f32>,
+}
+
+impl MultibandCompressor {
+    /// Create a multiband compressor at `sample_rate`
+    pub fn new(sample_rate: f32, settings: MultibandSettings) -> Result<Self, DspError> {
+        if sample_rate <= 0.0 {
+            return Err(DspError::InvalidSampleRate(sample_rate));
+        }
+        settings.validate()?;
+        let compressors = settings
+            .bands
+            .iter()
+            .map(|&band| Compressor::new(sample_rate, band))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut multiband = Self {
+            settings,
+            sample_rate,
+            crossovers: core::array::from_fn(|_| Crossover::new()),
+            compressors,
+            band_buffers: vec![0.0; MAX_MULTIBAND_BANDS * BAND_STRIDE],
+        };
+        multiband.update_crossovers(&settings, sample_rate)?;
+        Ok(multiband)
+    }
+
+    /// Replace all parameters
+    ///
+    /// Filter and envelope state carry over unless the band count changes,
+    /// which changes how the bands are split.
+    pub fn set_settings(&mut self, settings: MultibandSettings) -> Result<(), DspError> {
+        settings.validate()?;
+        self.update_crossovers(&settings, self.sample_rate)?;
+        for (compressor, &band) in self.compressors.iter_mut().zip(&settings.bands) {
+            compressor.set_settings(band)?;
+        }
+        if settings.band_count != self.settings.band_count {
+            self.reset();
+        }
+        self.settings = settings;
+        Ok(())
+    }
+
+    /// Current parameters
+    pub fn settings(&self) -> &MultibandSettings {
+        &self.settings
+    }
+
+    /// Enable or disable (disabled passes audio untouched)
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.settings.enabled = enabled;
+    }
+
+    /// Change the sample rate (redesigns the crossovers)
+    ///
+    /// Fails if a crossover is above the new Nyquist frequency, leaving the
+    /// crossovers, compressors and sample rate unchanged.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) -> Result<(), DspError> {
+        if sample_rate <= 0.0 {
+            return Err(DspError::InvalidSampleRate(sample_rate));
+        }
+        let settings = self.settings;
+        self.update_crossovers(&settings, sample_rate)?;
+        self.sample_rate = sample_rate;
+        for compressor in &mut self.compressors {
+            compressor.set_sample_rate(sample_rate)?;
+        }
+        Ok(())
+    }
+
+    /// Gain reduction handles for the bands in use, lowest first
+    pub fn meters(&self) -> Vec<CompressorMeter> {
+        self.compressors[..self.settings.band_count]
+            .iter()
+            .map(Compressor::meter)
+            .collect()
+    }
+
+    /// Design every crossover in use, then apply them all or none
+    fn update_crossovers(
+        &mut self,
+        settings: &MultibandSettings,
+        sample_rate: f32,
+    ) -> Result<(), DspError> {
+        let mut designs = [[unity_coefficients(); 3]; MAX_CROSSOVERS];
+        let used = settings.band_count - 1;
+        for (design, &frequency) in designs.iter_mut().zip(&settings.crossovers_hz[..used]) {
+            *design = Crossover::design(frequency, sample_rate)?;
+        }
+        for (crossover, design) in self.crossovers.iter_mut().zip(designs).take(used) {
+            crossover.set_coefficients(design);
+        }
+        Ok(())
+    }
+
+    /// Split a block of whole frames into the band buffers
+    fn split(&mut self, block: &[f32], channels: usize) {
+        let bands = self.settings.band_count;
+        for (index, &sample) in block.iter().enumerate() {
+            let channel = index % channels;
+            let mut split = [0.0_f64; MAX_MULTIBAND_BANDS];
+            let mut rest = sample as f64;
+            for (i, crossover) in self.crossovers[..bands - 1].iter_mut().enumerate() {
+                // Bands already split off get this crossover's phase shift
+                for (band, allpass) in split[..i].iter_mut().zip(&mut crossover.allpass) {
+                    *band = allpass[channel].run(*band);
+                }
+                split[i] = crossover.low[channel]
+                    .iter_mut()
+                    .fold(rest, |x, filter| filter.run(x));
+                rest = crossover.high[channel]
+                    .iter_mut()
+                    .fold(rest, |x, filter| filter.run(x));
+            }
+            split[bands - 1] = rest;
+
+            for (band, &value) in split[..bands].iter().enumerate() {
+                self.band_buffers[band * BAND_STRIDE + index] = value as f32;
+            }
+        }
+    }
+
+    /// Process an interleaved buffer of `channels` channels in-place
+    ///
+    /// A trailing partial frame is left untouched, as are buffers of more
+    /// than [`MAX_CHANNELS`] channels.
+    ///
+    /// # Real-time Safety
+    /// No allocations, no locks. O(n * bands).
+    pub fn process_interleaved(&mut self, buffer: &mut [f32], channels: usize) {
+        if !self.settings.enabled || channels == 0 || channels > MAX_CHANNELS {
+            return;
+        }
+
+        let bands = self.settings.band_count;
+        let whole_frames = buffer.len() - buffer.len() % channels;
+        for block in buffer[..whole_frames].chunks_mut(BLOCK_FRAMES * channels) {
+            self.split(block, channels);
+            for (band, compressor) in self.compressors[..bands].iter_mut().enumerate() {
+                let start = band * BAND_STRIDE;
+                let band_buffer = &mut self.band_buffers[start..start + block.len()];
+                compressor.process_interleaved(band_buffer, channels);
+            }
+            for (index, sample) in block.iter_mut().enumerate() {
+                *sample = (0..bands)
+                    .map(|band| self.band_buffers[band * BAND_STRIDE + index])
+                    .sum();
+            }
+        }
+    }
+}
+
+impl AudioProcessor for MultibandCompressor {
+    fn process(&mut self, buffer: &mut [f32], context: &ProcessContext) {
+        if context.sample_rate != self.sample_rate {
+            let _ = self.set_sample_rate(context.sample_rate);
+        }
+        self.process_interleaved(buffer, context.channels);
+    }
+
+    fn reset(&mut self) {
+        for crossover in &mut self.crossovers {
+            crossover.reset();
+        }
+        for compressor in &mut self.compressors {
+            AudioProcessor::reset(compressor);
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Multiband Compressor"
+    }
+
+    fn gain_reduction_meters(&self) -> Vec<CompressorMeter> {
+        self.meters()
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.settings.enabled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Settings whose bands all pass audio through uncompressed
+    fn uncompressed(band_count: usize) -> MultibandSettings {
+        let bands = [CompressorSettings {
+            ratio: 1.0,
+            ..CompressorSettings::default()
+        }; MAX_MULTIBAND_BANDS];
+        MultibandSettings {
+            band_count,
+            crossovers_hz: [100.0, 800.0, 3000.0, 10000.0],
+            bands,
+            enabled: true,
+        }
+    }
+
+    fn sine(frequency: f32, amplitude: f32, frames: usize) -> Vec<f32> {
+        (0..frames)
+            .flat_map(|i| {
+                let phase = 2.0 * std::f32::consts::PI * frequency * i as f32 / 48000.0;
+                let sample = amplitude * phase.sin();
+                [sample, sample]
+            })
+            .collect()
+    }
+
+    /// RMS level (dB) of the second half of a buffer, past the filters' settling
+    fn tail_rms_db(buffer: &[f32]) -> f32 {
+        let tail = &buffer[buffer.len() / 2..];
+        let mean_square = tail.iter().map(|s| s * s).sum::<f32>() / tail.len() as f32;
+        10.0 * mean_square.log10()
+    }
+
+    #[test]
+    fn test_crossovers_sum_flat() {
+        for band_count in MIN_MULTIBAND_BANDS..=MAX_MULTIBAND_BANDS {
+            let mut multiband =
+                MultibandCompressor::new(48000.0, uncompressed(band_count)).unwrap();
+            // Around and between every crossover
+            for frequency in [40.0, 100.0, 300.0, 800.0, 1500.0, 3000.0, 10000.0, 16000.0] {
+                let input = sine(frequency, 0.5, 9600);
+                let mut buffer = input.clone();
+                multiband.process_interleaved(&mut buffer, 2);
+                let change = tail_rms_db(&buffer) - tail_rms_db(&input);
+                assert!(
+                    change.abs() < 0.05,
+                    "{} bands at {} Hz: {} dB",
+                    band_count,
+                    frequency,
+                    change
+                );
+                AudioProcessor::reset(&mut multiband);
+            }
+        }
+    }
+
+    #[test]
+    fn test_compresses_only_its_band() {
+        let mut settings = uncompressed(3);
+        settings.bands[0] = CompressorSettings {
+            threshold_db: -30.0,
+            ratio: 10.0,
+            knee_db: 0.0,
+            ..CompressorSettings::default()
+        };
+        let mut multiband = MultibandCompressor::new(48000.0, settings).unwrap();
+        let meters = multiband.meters();
+        assert_eq!(meters.len(), 3);
+
+        // A loud bass tone is pulled down and shows on the low band's meter
+        let mut bass = sine(40.0, 0.5, 48000);
+        multiband.process_interleaved(&mut bass, 2);
+        assert!(
+            tail_rms_db(&bass) < -12.0,
+            "Bass at {} dB",
+            tail_rms_db(&bass)
+        );
+        assert!(meters[0].take_max_gain_reduction_db() > 10.0);
+        assert_eq!(meters[1].take_max_gain_reduction_db(), 0.0);
+
+        // The same level well above the crossover goes through untouched
+        AudioProcessor::reset(&mut multiband);
+        let mut treble = sine(2000.0, 0.5, 48000);
+        multiband.process_interleaved(&mut treble, 2);
+        assert!((tail_rms_db(&treble) + 9.03).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_presets_are_valid() {
+        for preset in MultibandPreset::ALL {
+            let settings = preset.settings();
+            assert!(settings.validate().is_ok(), "{} is invalid", preset.name());
+            assert!(MultibandCompressor::new(44100.0, settings).is_ok());
+        }
+        assert_eq!(
+            MultibandSettings::default(),
+            MultibandPreset::TameBass.settings()
+        );
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        let valid = uncompressed(4);
+        let mut descending = valid;
+        descending.crossovers_hz[2] = 500.0;
+        let mut too_few = valid;
+        too_few.band_count = 2;
+        let mut nan = valid;
+        nan.crossovers_hz[0] = f32::NAN;
+        let mut bad_band = valid;
+        bad_band.bands[4].ratio = 0.5;
+        for invalid in [descending, too_few, nan, bad_band] {
+            assert!(invalid.validate().is_err());
+        }
+
+        // Crossovers past Nyquist can't be built; the compressor is left as it was
+        let mut compressed = valid;
+        for band in &mut compressed.bands {
+            band.threshold_db = -30.0;
+            band.ratio = 4.0;
+        }
+        let mut multiband = MultibandCompressor::new(48000.0, compressed).unwrap();
+        let mut untouched = MultibandCompressor::new(48000.0, compressed).unwrap();
+        assert!(multiband.set_sample_rate(4000.0).is_err());
+        let mut buffer = sine(1000.0, 0.9, 4800);
+        let mut expected = buffer.clone();
+        multiband.process_interleaved(&mut buffer, 2);
+        untouched.process_interleaved(&mut expected, 2);
+        assert_eq!(buffer, expected);
+        assert!(MultibandCompressor::new(4000.0, valid).is_err());
+
+        // Unused crossovers don't matter
+        let mut three = uncompressed(3);
+        three.crossovers_hz[3] = f32::NAN;
+        assert!(three.validate().is_ok());
+    }
+
+    #[test]
+    fn test_disabled_passes_through() {
+        let mut multiband =
+            MultibandCompressor::new(48000.0, MultibandSettings::default()).unwrap();
+        multiband.set_enabled(false);
+        let mut buffer = vec![0.9, -0.9, 0.5];
+        multiband.process_interleaved(&mut buffer, 2);
+        assert_eq!(buffer, vec![0.9, -0.9, 0.5]);
+    }
+}
@@ -1,6 +1,10 @@
 //! Built-in EQ Presets
 
 /// Named EQ preset with 10 band gains
+///
+/// Gains are defined at the [`EQ_BANDS`](crate::EQ_BANDS) frequencies. Use
+/// [`EqConfig::set_gains_interpolated`](crate::EqConfig::set_gains_interpolated)
+/// to apply them to an EQ with a different band count.
 pub type Preset = (&'static str, [f32; 10]);
 
 /// List of built-in presets
@@ -3,6 +3,8 @@
 //! Defines the interface for chainable audio processors.
 //! Allows building modular DSP pipelines (EQ -> Compressor -> Limiter).
 
+use crate::compressor::CompressorMeter;
+
 /// Context passed to processors containing stream metadata
 #[derive(Debug, Clone, Copy)]
 pub struct ProcessContext {
@@ -48,21 +50,36 @@ pub trait AudioProcessor: Send {
     fn is_enabled(&self) -> bool {
         true
     }
+
+    /// Delay in samples this processor adds (e.g. lookahead, linear phase)
+    fn latency_samples(&self) -> usize {
+        0
+    }
+
+    /// Gain reduction readouts this processor publishes, one per band (empty if none)
+    ///
+    /// Allocates; call when building a chain, not from `process`.
+    fn gain_reduction_meters(&self) -> Vec<CompressorMeter> {
+        Vec::new()
+    }
 }
 
 /// A chain of processors applied sequentially
-/// Note: This will be used when we implement the full DSP pipeline in the engine
-#[allow(dead_code)]
+///
+/// Built off the audio thread (adding allocates), then handed to the
+/// audio thread whole; see [`chain_channel`](crate::chain_channel).
 pub struct ProcessorChain {
     processors: Vec<Box<dyn AudioProcessor>>,
+    /// Per processor: skipped by `process` while set
+    bypassed: Vec<bool>,
     context: ProcessContext,
 }
 
-#[allow(dead_code)]
 impl ProcessorChain {
     pub fn new(sample_rate: f32, channels: usize, buffer_size: usize) -> Self {
         Self {
             processors: Vec::new(),
+            bypassed: Vec::new(),
             context: ProcessContext::new(sample_rate, channels, buffer_size),
         }
     }
@@ -71,19 +88,47 @@ impl ProcessorChain {
     ///
     /// Note: This allocates. Only call during setup, not in audio callback.
     pub fn add<P: AudioProcessor + 'static>(&mut self, processor: P) {
-        self.processors.push(Box::new(processor));
+        self.add_boxed(Box::new(processor));
+    }
+
+    /// Add an already boxed processor to the end of the chain
+    ///
+    /// Note: This allocates. Only call during setup, not in audio callback.
+    pub fn add_boxed(&mut self, processor: Box<dyn AudioProcessor>) {
+        self.processors.push(processor);
+        self.bypassed.push(false);
     }
 
     /// Process buffer through all enabled processors
     #[inline]
     pub fn process(&mut self, buffer: &mut [f32]) {
-        for processor in &mut self.processors {
-            if processor.is_enabled() {
+        for (processor, &bypassed) in self.processors.iter_mut().zip(&self.bypassed) {
+            if !bypassed && processor.is_enabled() {
                 processor.process(buffer, &self.context);
             }
         }
     }
 
+    /// Skip (or stop skipping) the processor at `index`; out of range is ignored
+    ///
+    /// # Real-time Safety
+    /// No allocations. A bypassed processor keeps its state.
+    pub fn set_bypassed(&mut self, index: usize, bypassed: bool) {
+        if let Some(flag) = self.bypassed.get_mut(index) {
+            *flag = bypassed;
+        }
+    }
+
+    /// Whether the processor at `index` is bypassed (false if out of range)
+    pub fn is_bypassed(&self, index: usize) -> bool {
+        self.bypassed.get(index).copied().unwrap_or(false)
+    }
+
+    /// Names of the processors, in order
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.processors.iter().map(|processor| processor.name())
+    }
+
     /// Reset all processors
     pub fn reset(&mut self) {
         for processor in &mut self.processors {
@@ -96,6 +141,33 @@ impl ProcessorChain {
         self.context = context;
     }
 
+    /// Stream format the chain processes
+    pub fn context(&self) -> &ProcessContext {
+        &self.context
+    }
+
+    /// Total delay in samples added by the enabled processors
+    pub fn latency_samples(&self) -> usize {
+        self.processors
+            .iter()
+            .zip(&self.bypassed)
+            .filter(|(processor, &bypassed)| !bypassed && processor.is_enabled())
+            .map(|(processor, _)| processor.latency_samples())
+            .sum()
+    }
+
+    /// Gain reduction readouts of each metered processor, in chain order
+    ///
+    /// Take them before handing the chain to the audio thread; they keep
+    /// reading the processors afterwards; see [`crate::GainReduction::take`].
+    pub fn gain_reduction_meters(&self) -> Vec<Vec<CompressorMeter>> {
+        self.processors
+            .iter()
+            .map(|processor| processor.gain_reduction_meters())
+            .filter(|meters| !meters.is_empty())
+            .collect()
+    }
+
     /// Get number of processors in chain
     pub fn len(&self) -> usize {
         self.processors.len()
@@ -124,6 +196,10 @@ impl AudioProcessor for crate::Equalizer {
     fn is_enabled(&self) -> bool {
         self.config().enabled
     }
+
+    fn latency_samples(&self) -> usize {
+        crate::Equalizer::latency_samples(self)
+    }
 }
 
 #[cfg(test)]
@@ -204,6 +280,84 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_chain_latency() {
+        let mut chain = ProcessorChain::new(48000.0, 2, 512);
+        chain.add(InvertProcessor);
+        chain.add(Equalizer::new(48000.0));
+        assert_eq!(chain.latency_samples(), 0);
+
+        let mut eq = Equalizer::new(48000.0);
+        eq.set_phase_mode(crate::PhaseMode::Linear);
+        let latency = eq.latency_samples();
+        assert!(latency > 0);
+        chain.add(eq);
+        assert_eq!(chain.latency_samples(), latency);
+    }
+
+    #[test]
+    fn test_bypassed_processor_is_skipped() {
+        let mut chain = ProcessorChain::new(48000.0, 2, 512);
+        chain.add(InvertProcessor);
+        let mut eq = Equalizer::new(48000.0);
+        eq.set_phase_mode(crate::PhaseMode::Linear);
+        chain.add(eq);
+        assert!(chain.latency_samples() > 0);
+
+        chain.set_bypassed(0, true);
+        chain.set_bypassed(1, true);
+        chain.set_bypassed(5, true);
+        assert!(chain.is_bypassed(0) && !chain.is_bypassed(5));
+        assert_eq!(chain.latency_samples(), 0);
+        assert_eq!(chain.names().collect::<Vec<_>>(), ["Inverter", "10-Band Equalizer"]);
+
+        let mut buffer = vec![0.5, -0.5];
+        chain.process(&mut buffer);
+        assert_eq!(buffer, [0.5, -0.5]);
+
+        chain.set_bypassed(0, false);
+        chain.process(&mut buffer);
+        assert_eq!(buffer, [-0.5, 0.5]);
+    }
+
+    #[test]
+    fn test_chain_gain_reduction_meters() {
+        let mut chain = ProcessorChain::new(48000.0, 2, 512);
+        chain.add(InvertProcessor);
+        assert!(chain.gain_reduction_meters().is_empty());
+
+        let compressor =
+            crate::Compressor::new(48000.0, crate::CompressorSettings::default()).unwrap();
+        chain.add(compressor);
+        let multiband = crate::MultibandCompressor::new(
+            48000.0,
+            crate::MultibandSettings::default(),
+        )
+        .unwrap();
+        let bands = multiband.meters().len();
+        chain.add(multiband);
+        let meters = chain.gain_reduction_meters();
+        assert_eq!(meters.len(), 2);
+        assert_eq!(meters[1].len(), bands);
+
+        // A loud tone is compressed, and the meter handed out sees it
+        let mut buffer: Vec<f32> = (0..9600)
+            .flat_map(|n| {
+                let x = (std::f32::consts::TAU * 1000.0 * n as f32 / 48000.0).sin();
+                [x, x]
+            })
+            .collect();
+        chain.process(&mut buffer);
+        let reduction = crate::GainReduction::take(&meters).unwrap();
+        assert!(reduction.db > 0.0);
+        assert_eq!(reduction.bands.len(), bands);
+        assert!(reduction.bands.iter().all(|&db| db <= reduction.db));
+
+        // Readings reset once taken
+        assert_eq!(crate::GainReduction::take(&meters).unwrap().db, 0.0);
+        assert_eq!(crate::GainReduction::take(&[]), None);
+    }
+
     #[test]
     fn test_chain_reset() {
         let mut chain = ProcessorChain::new(48000.0, 2, 512);
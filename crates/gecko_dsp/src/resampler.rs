@@ -0,0 +1,450 @@
+//! Sample-Rate Conversion
+//!
+//! Converts interleaved audio between two sample rates, for when a capture
+//! stream and the output device don't run at the same rate.
+//!
+//! # Algorithm
+//!
+//! Band-limited interpolation with a Kaiser-windowed sinc:
+//!
+//! ```text
+//! y(t) = Σ x[n] × h(t - n)        over the `taps` input frames nearest t
+//! ```
+//!
+//! - Kernels are tabulated at [`PHASES`] fractional positions; the kernel for
+//!   an output frame is linearly interpolated between the two nearest rows.
+//! - The cutoff sits just below the lower of the two Nyquist frequencies.
+//!   When downsampling, the kernel widens by the rate ratio (up to
+//!   [`MAX_TAPS`]) so the transition band stays as narrow as at 1:1.
+//! - The passband is flat to ~42% of the lower rate (20 kHz at 48 kHz) and
+//!   images/aliases are attenuated by about 80 dB.
+//!
+//! Equal rates pass audio through untouched, with no delay, unless a drift
+//! ratio is set: clock drift compensation fine-tunes the conversion ratio
+//! around the nominal one without redesigning the kernel.
+
+use crate::eq::MAX_CHANNELS;
+use crate::error::DspError;
+
+/// Fractional positions the kernel is tabulated at
+const PHASES: usize = 128;
+
+/// Kernel length at 1:1 and when upsampling
+const BASE_TAPS: usize = 64;
+
+/// Longest kernel, reached when downsampling by 4:1 or more
+const MAX_TAPS: usize = 256;
+
+/// Cutoff as a fraction of the lower Nyquist frequency
+const ROLLOFF: f64 = 0.92;
+
+/// Kaiser window shape (higher = deeper stopband, wider transition)
+const KAISER_BETA: f64 = 8.6;
+
+/// Drift ratio limits (±5%, far beyond any real clock mismatch)
+const MIN_DRIFT_RATIO: f64 = 0.95;
+const MAX_DRIFT_RATIO: f64 = 1.05;
+
+/// Streaming sample-rate converter for up to [`MAX_CHANNELS`] interleaved channels
+///
+/// The kernel table and history are allocated for the worst case in `new`,
+/// so changing rates or channel count never allocates.
+pub struct Resampler {
+    input_rate: f32,
+    output_rate: f32,
+    channels: usize,
+    taps: usize,
+    // Input frames per output frame
+    step: f64,
+    // Output frames per nominal output frame, once drift compensation is on
+    drift_ratio: Option<f64>,
+    // Position of the next output frame after the centre of the history, in input frames
+    phase: f64,
+    // Kernels at PHASES + 1 positions, `taps` per row
+    table: Vec<f32>,
+    // Kernel for the current output frame
+    kernel: Vec<f32>,
+    // Input history per channel: a ring of `taps` frames stored twice, so the
+    // newest `taps` frames are always contiguous
+    history: Vec<f32>,
+    write_pos: usize,
+}
+
+impl Resampler {
+    /// Create a stereo resampler from `input_rate` to `output_rate` (Hz)
+    pub fn new(input_rate: f32, output_rate: f32) -> Result<Self, DspError> {
+        let mut resampler = Self {
+            input_rate: 0.0,
+            output_rate: 0.0,
+            channels: 2,
+            taps: BASE_TAPS,
+            step: 1.0,
+            drift_ratio: None,
+            phase: 0.0,
+            table: vec![0.0; (PHASES + 1) * MAX_TAPS],
+            kernel: vec![0.0; MAX_TAPS],
+            history: vec![0.0; MAX_CHANNELS * 2 * MAX_TAPS],
+            write_pos: 0,
+        };
+        resampler.set_rates(input_rate, output_rate)?;
+        Ok(resampler)
+    }
+
+    /// Change both rates; history is cleared when either changes
+    ///
+    /// # Real-time Safety
+    /// No allocations, but redesigns the kernel table (about a millisecond),
+    /// so only call it when a rate actually changes.
+    pub fn set_rates(&mut self, input_rate: f32, output_rate: f32) -> Result<(), DspError> {
+        for rate in [input_rate, output_rate] {
+            if rate <= 0.0 || !rate.is_finite() {
+                return Err(DspError::InvalidSampleRate(rate));
+            }
+        }
+        if input_rate == self.input_rate && output_rate == self.output_rate {
+            return Ok(());
+        }
+        self.input_rate = input_rate;
+        self.output_rate = output_rate;
+        self.update_step();
+        self.design();
+        self.reset();
+        Ok(())
+    }
+
+    /// Rate (Hz) of the audio passed to `process_interleaved`
+    pub fn input_rate(&self) -> f32 {
+        self.input_rate
+    }
+
+    /// Rate (Hz) of the audio `process_interleaved` produces
+    pub fn output_rate(&self) -> f32 {
+        self.output_rate
+    }
+
+    /// Whether audio is copied unchanged (equal rates, no drift ratio)
+    pub fn is_passthrough(&self) -> bool {
+        self.input_rate == self.output_rate && self.drift_ratio.is_none()
+    }
+
+    /// Produce `ratio` times the nominal number of output frames
+    ///
+    /// For clock drift compensation; `ratio` stays within a fraction of a
+    /// percent of 1. Once set, audio is always filtered (even at exactly 1)
+    /// so the delay doesn't jump as the ratio wanders.
+    ///
+    /// # Real-time Safety
+    /// No allocations; cheap enough to call every buffer.
+    pub fn set_drift_ratio(&mut self, ratio: f64) -> Result<(), DspError> {
+        if !(MIN_DRIFT_RATIO..=MAX_DRIFT_RATIO).contains(&ratio) {
+            return Err(DspError::InvalidParameter {
+                name: "drift ratio",
+                value: ratio as f32,
+            });
+        }
+        self.drift_ratio = Some(ratio);
+        self.update_step();
+        Ok(())
+    }
+
+    /// Current drift ratio (1.0 until one is set)
+    pub fn drift_ratio(&self) -> f64 {
+        self.drift_ratio.unwrap_or(1.0)
+    }
+
+    /// Set the number of interleaved channels (1 to [`MAX_CHANNELS`], default 2)
+    ///
+    /// History is cleared when the count changes.
+    pub fn set_channel_count(&mut self, channels: usize) -> Result<(), DspError> {
+        if channels == 0 || channels > MAX_CHANNELS {
+            return Err(DspError::InvalidChannelCount(channels));
+        }
+        if channels != self.channels {
+            self.channels = channels;
+            self.reset();
+        }
+        Ok(())
+    }
+
+    /// Number of interleaved channels processed
+    pub fn channel_count(&self) -> usize {
+        self.channels
+    }
+
+    /// Delay the conversion adds, in input frames (0 when passing through)
+    pub fn latency_frames(&self) -> usize {
+        if self.is_passthrough() {
+            0
+        } else {
+            self.taps / 2
+        }
+    }
+
+    /// Most output frames `input_frames` of input can produce
+    ///
+    /// Size the output buffer of `process_interleaved` with this.
+    pub fn max_output_frames(&self, input_frames: usize) -> usize {
+        (input_frames as f64 / self.step).ceil() as usize + 1
+    }
+
+    /// Convert an interleaved buffer, returning the number of samples written
+    ///
+    /// Consumes every whole frame of `input`. Output frames that don't fit in
+    /// `output` are dropped, so size it with
+    /// [`max_output_frames`](Self::max_output_frames).
+    ///
+    /// # Real-time Safety
+    /// No allocations. O(n × taps) where n = output samples.
+    pub fn process_interleaved(&mut self, input: &[f32], output: &mut [f32]) -> usize {
+        let channels = self.channels;
+        if self.is_passthrough() {
+            let len = input.len().min(output.len()) / channels * channels;
+            output[..len].copy_from_slice(&input[..len]);
+            return len;
+        }
+
+        let taps = self.taps;
+        let stride = 2 * MAX_TAPS;
+        let mut written = 0;
+        for frame in input.chunks_exact(channels) {
+            for (c, &sample) in frame.iter().enumerate() {
+                self.history[c * stride + self.write_pos] = sample;
+                self.history[c * stride + self.write_pos + taps] = sample;
+            }
+            self.write_pos = (self.write_pos + 1) % taps;
+
+            while self.phase < 1.0 {
+                if written + channels <= output.len() {
+                    self.load_kernel();
+                    let kernel = &self.kernel[..taps];
+                    for (c, out) in output[written..written + channels].iter_mut().enumerate() {
+                        let start = c * stride + self.write_pos;
+                        let window = &self.history[start..start + taps];
+                        *out = window.iter().zip(kernel).map(|(x, h)| x * h).sum();
+                    }
+                    written += channels;
+                }
+                self.phase += self.step;
+            }
+            self.phase -= 1.0;
+        }
+        written
+    }
+
+    /// Clear history (the next output starts from silence)
+    pub fn reset(&mut self) {
+        self.history.fill(0.0);
+        self.write_pos = 0;
+        self.phase = 0.0;
+    }
+
+    fn update_step(&mut self) {
+        let nominal = self.input_rate as f64 / self.output_rate as f64;
+        self.step = nominal / self.drift_ratio();
+    }
+
+    /// Interpolate the kernel for the current phase from the table
+    #[inline]
+    fn load_kernel(&mut self) {
+        let position = self.phase * PHASES as f64;
+        let row = (position as usize).min(PHASES - 1);
+        let t = (position - row as f64) as f32;
+        let taps = self.taps;
+        let (a, b) = self.table[row * taps..(row + 2) * taps].split_at(taps);
+        for ((k, &a), &b) in self.kernel.iter_mut().zip(a).zip(b) {
+            *k = a + t * (b - a);
+        }
+    }
+
+    /// Tabulate the windowed-sinc kernel for the current rates
+    fn design(&mut self) {
+        // Nominal ratio: drift is too small to move the cutoff
+        let scale = (self.output_rate as f64 / self.input_rate as f64).min(1.0);
+        // Even length, so the centre falls between the middle two taps
+        let taps = (((BASE_TAPS as f64 / scale).ceil() as usize + 1) & !1).min(MAX_TAPS);
+        let half = taps as f64 / 2.0;
+        // Cutoff in cycles per input sample
+        let cutoff = 0.5 * ROLLOFF * scale;
+        let window_norm = bessel_i0(KAISER_BETA);
+
+        self.taps = taps;
+        for phase in 0..=PHASES {
+            let frac = phase as f64 / PHASES as f64;
+            let row = &mut self.table[phase * taps..(phase + 1) * taps];
+            // Tap k holds the k-th oldest frame; the output sits `half - 1 + frac` after the oldest
+            for (k, tap) in row.iter_mut().enumerate() {
+                let x = half - 1.0 + frac - k as f64;
+                let arg = 2.0 * std::f64::consts::PI * cutoff * x;
+                let sinc = if x == 0.0 { 1.0 } else { arg.sin() / arg };
+                let r = (x / half).clamp(-1.0, 1.0);
+                let window = bessel_i0(KAISER_BETA * (1.0 - r * r).sqrt()) / window_norm;
+                *tap = (2.0 * cutoff * sinc * window) as f32;
+            }
+            // Unity DC gain at every position
+            let sum: f32 = row.iter().sum();
+            for tap in row.iter_mut() {
+                *tap /= sum;
+            }
+        }
+    }
+}
+
+/// Zeroth-order modified Bessel function of the first kind (Kaiser window)
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half_x = x / 2.0;
+    for k in 1..50 {
+        term *= (half_x / k as f64).powi(2);
+        sum += term;
+        if term < sum * 1e-12 {
+            break;
+        }
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Interleaved sine of `frames` frames, the same in every channel
+    fn sine(freq: f32, rate: f32, frames: usize, channels: usize) -> Vec<f32> {
+        (0..frames)
+            .flat_map(|n| {
+                // f64 phase: f32 loses precision over long buffers
+                let phase = std::f64::consts::TAU * freq as f64 * n as f64 / rate as f64;
+                std::iter::repeat(0.5 * phase.sin() as f32).take(channels)
+            })
+            .collect()
+    }
+
+    /// RMS of one channel, skipping the first `skip` frames
+    fn rms(buffer: &[f32], channels: usize, skip: usize) -> f32 {
+        let samples: Vec<f32> = buffer
+            .iter()
+            .step_by(channels)
+            .skip(skip)
+            .copied()
+            .collect();
+        (samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    fn convert(resampler: &mut Resampler, input: &[f32]) -> Vec<f32> {
+        let frames = input.len() / resampler.channel_count();
+        let mut output = vec![0.0; resampler.max_output_frames(frames) * resampler.channel_count()];
+        let written = resampler.process_interleaved(input, &mut output);
+        output.truncate(written);
+        output
+    }
+
+    #[test]
+    fn test_equal_rates_pass_through() {
+        let mut resampler = Resampler::new(48000.0, 48000.0).unwrap();
+        assert!(resampler.is_passthrough());
+        assert_eq!(resampler.latency_frames(), 0);
+        let input = sine(1000.0, 48000.0, 256, 2);
+        assert_eq!(convert(&mut resampler, &input), input);
+    }
+
+    #[test]
+    fn test_upsampling_preserves_level_and_length() {
+        let mut resampler = Resampler::new(44100.0, 48000.0).unwrap();
+        let input = sine(1000.0, 44100.0, 44100, 2);
+        let output = convert(&mut resampler, &input);
+
+        let frames = output.len() / 2;
+        assert!((frames as i64 - 48000).abs() <= 1, "{} frames", frames);
+        let level = rms(&output, 2, 1000);
+        assert!(
+            (level - 0.5 / 2.0_f32.sqrt()).abs() < 0.002,
+            "rms {}",
+            level
+        );
+    }
+
+    #[test]
+    fn test_output_matches_ideal_sine() {
+        let mut resampler = Resampler::new(48000.0, 96000.0).unwrap();
+        resampler.set_channel_count(1).unwrap();
+        let output = convert(&mut resampler, &sine(5000.0, 48000.0, 4800, 1));
+        // The output lags by the kernel delay; compare against a delayed sine
+        let delay = resampler.latency_frames() as f32 / 48000.0;
+        let error = output
+            .iter()
+            .enumerate()
+            .skip(1000)
+            .map(|(n, &y)| {
+                let t = n as f32 / 96000.0 - delay;
+                (y - 0.5 * (std::f32::consts::TAU * 5000.0 * t).sin()).abs()
+            })
+            .fold(0.0_f32, f32::max);
+        assert!(error < 1e-3, "max error {}", error);
+    }
+
+    #[test]
+    fn test_downsampling_rejects_aliases() {
+        let mut resampler = Resampler::new(96000.0, 48000.0).unwrap();
+        assert!(resampler.latency_frames() > BASE_TAPS / 2);
+        // 30 kHz is above the output Nyquist and would alias to 18 kHz
+        let output = convert(&mut resampler, &sine(30000.0, 96000.0, 96000, 2));
+        let level_db = 20.0 * (rms(&output, 2, 1000) / (0.5 / 2.0_f32.sqrt())).log10();
+        assert!(level_db < -75.0, "alias at {} dB", level_db);
+
+        // Passband is flat
+        let output = convert(&mut resampler, &sine(18000.0, 96000.0, 96000, 2));
+        let level_db = 20.0 * (rms(&output, 2, 1000) / (0.5 / 2.0_f32.sqrt())).log10();
+        assert!(level_db.abs() < 0.1, "18 kHz at {} dB", level_db);
+    }
+
+    #[test]
+    fn test_streaming_matches_single_call() {
+        let input = sine(440.0, 44100.0, 4410, 6);
+        let mut whole = Resampler::new(44100.0, 96000.0).unwrap();
+        whole.set_channel_count(6).unwrap();
+        let expected = convert(&mut whole, &input);
+
+        let mut chunked = Resampler::new(44100.0, 96000.0).unwrap();
+        chunked.set_channel_count(6).unwrap();
+        let mut output = Vec::new();
+        for chunk in input.chunks(6 * 37) {
+            output.extend(convert(&mut chunked, chunk));
+        }
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_rates_and_channels_are_validated() {
+        assert!(Resampler::new(0.0, 48000.0).is_err());
+        assert!(Resampler::new(48000.0, f32::NAN).is_err());
+
+        let mut resampler = Resampler::new(48000.0, 44100.0).unwrap();
+        assert!(resampler.set_channel_count(0).is_err());
+        assert!(resampler.set_channel_count(MAX_CHANNELS + 1).is_err());
+        resampler.set_rates(88200.0, 48000.0).unwrap();
+        assert_eq!(resampler.input_rate(), 88200.0);
+        assert_eq!(resampler.output_rate(), 48000.0);
+    }
+
+    #[test]
+    fn test_drift_ratio_scales_output() {
+        let mut resampler = Resampler::new(48000.0, 48000.0).unwrap();
+        resampler.set_drift_ratio(1.0).unwrap();
+        assert!(!resampler.is_passthrough());
+        assert!(resampler.set_drift_ratio(1.5).is_err());
+
+        resampler.set_drift_ratio(1.001).unwrap();
+        let output = convert(&mut resampler, &sine(1000.0, 48000.0, 48000, 2));
+        assert_eq!(output.len() / 2, 48048);
+        let level = rms(&output, 2, 1000);
+        assert!((level - 0.5 / 2.0_f32.sqrt()).abs() < 0.002, "rms {}", level);
+    }
+
+    #[test]
+    fn test_short_output_drops_frames() {
+        let mut resampler = Resampler::new(48000.0, 96000.0).unwrap();
+        let mut output = [0.0; 10];
+        let written = resampler.process_interleaved(&sine(1000.0, 48000.0, 64, 2), &mut output);
+        assert_eq!(written, 10);
+    }
+}
@@ -0,0 +1,312 @@
+//! Frequency Response Evaluation
+//!
+//! Computes the exact response of an [`EqConfig`] from the same biquad
+//! coefficients the [`Equalizer`] runs, so a plotted curve matches what is
+//! heard: overlapping shelves, steep slopes, tilt and master gain included.
+//!
+//! Each point reports magnitude (dB), phase (radians, wrapped to ±π) and
+//! group delay (ms). Responses of stages in series combine with
+//! [`FrequencyResponse::cascade`], e.g. a per-app EQ followed by the master EQ.
+
+use std::f64::consts::{PI, TAU};
+
+use rustfft::num_complex::Complex;
+
+use crate::eq::{EqConfig, Equalizer};
+use crate::error::DspError;
+use crate::linear_phase::PhaseMode;
+
+/// Floor for reported magnitudes (a notch centre is otherwise -inf dB)
+pub const MIN_RESPONSE_DB: f32 = -120.0;
+
+/// Response of a filter at a list of frequencies
+///
+/// All vectors have one entry per frequency.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrequencyResponse {
+    /// Frequencies in Hz
+    pub frequencies: Vec<f32>,
+    /// Magnitude in dB, floored at [`MIN_RESPONSE_DB`]
+    pub magnitude_db: Vec<f32>,
+    /// Phase in radians, wrapped to [-π, π]
+    pub phase: Vec<f32>,
+    /// Group delay in milliseconds
+    pub group_delay_ms: Vec<f32>,
+}
+
+impl FrequencyResponse {
+    /// A 0 dB, zero-phase response at `frequencies`
+    pub fn flat(frequencies: &[f32]) -> Self {
+        Self {
+            frequencies: frequencies.to_vec(),
+            magnitude_db: vec![0.0; frequencies.len()],
+            phase: vec![0.0; frequencies.len()],
+            group_delay_ms: vec![0.0; frequencies.len()],
+        }
+    }
+
+    /// Number of points
+    pub fn len(&self) -> usize {
+        self.frequencies.len()
+    }
+
+    /// True if there are no points
+    pub fn is_empty(&self) -> bool {
+        self.frequencies.is_empty()
+    }
+
+    /// Response of `self` followed by `next` (gains and delays add)
+    ///
+    /// Both must have been evaluated at the same frequencies.
+    pub fn cascade(&self, next: &FrequencyResponse) -> Result<Self, DspError> {
+        if self.frequencies != next.frequencies {
+            return Err(DspError::ResponseMismatch);
+        }
+        Ok(Self {
+            frequencies: self.frequencies.clone(),
+            magnitude_db: self
+                .magnitude_db
+                .iter()
+                .zip(&next.magnitude_db)
+                .map(|(a, b)| (a + b).max(MIN_RESPONSE_DB))
+                .collect(),
+            phase: self
+                .phase
+                .iter()
+                .zip(&next.phase)
+                .map(|(a, b)| wrap_phase(*a as f64 + *b as f64) as f32)
+                .collect(),
+            group_delay_ms: self
+                .group_delay_ms
+                .iter()
+                .zip(&next.group_delay_ms)
+                .map(|(a, b)| a + b)
+                .collect(),
+        })
+    }
+}
+
+/// `points` frequencies spaced evenly on a log scale from `min` to `max` Hz
+///
+/// Suits plotting an EQ curve; 20 Hz to 20 kHz at a few hundred points is typical.
+pub fn log_frequencies(min: f32, max: f32, points: usize) -> Vec<f32> {
+    if points < 2 {
+        return vec![min; points];
+    }
+    let ratio = (max / min).ln();
+    (0..points)
+        .map(|i| min * (ratio * i as f32 / (points - 1) as f32).exp())
+        .collect()
+}
+
+/// Wrap a phase in radians to [-π, π]
+fn wrap_phase(phase: f64) -> f64 {
+    phase - TAU * ((phase + PI) / TAU).floor()
+}
+
+impl EqConfig {
+    /// Exact response of this config at `sample_rate` for each of `frequencies`
+    ///
+    /// Evaluated from the minimum-phase biquad coefficients. Mirrors
+    /// processing: disabled bands and bands that can't be realised at this
+    /// rate (e.g. above Nyquist) are skipped, and a disabled config is flat.
+    ///
+    /// Fails with [`DspError::InvalidFrequency`] for a frequency outside
+    /// 0 Hz to Nyquist.
+    pub fn frequency_response(
+        &self,
+        sample_rate: f32,
+        frequencies: &[f32],
+    ) -> Result<FrequencyResponse, DspError> {
+        if sample_rate <= 0.0 || !sample_rate.is_finite() {
+            return Err(DspError::InvalidSampleRate(sample_rate));
+        }
+        if let Some(&bad) = frequencies
+            .iter()
+            .find(|&&f| !(0.0..=sample_rate / 2.0).contains(&f))
+        {
+            return Err(DspError::InvalidFrequency(bad));
+        }
+
+        let mut response = FrequencyResponse::flat(frequencies);
+        if !self.enabled {
+            return Ok(response);
+        }
+
+        let stages: Vec<_> = self
+            .bands
+            .iter()
+            .filter(|band| band.enabled)
+            .filter_map(|band| band.to_coefficients(sample_rate, self.design).ok())
+            .collect();
+        let master_gain = 10.0_f64.powf(self.master_gain_db as f64 / 20.0);
+        let ms_per_sample = 1000.0 / sample_rate as f64;
+
+        for (i, &frequency) in frequencies.iter().enumerate() {
+            let w = TAU * frequency as f64 / sample_rate as f64;
+            let mut total = Complex::new(master_gain, 0.0);
+            let mut delay = 0.0;
+            for coeffs in &stages {
+                let (h, d) = coeffs.response(w);
+                total *= h;
+                delay += d;
+            }
+            response.magnitude_db[i] = (20.0 * total.norm().log10()).max(MIN_RESPONSE_DB as f64) as f32;
+            response.phase[i] = total.arg() as f32;
+            response.group_delay_ms[i] = (delay * ms_per_sample) as f32;
+        }
+
+        Ok(response)
+    }
+}
+
+impl Equalizer {
+    /// Response of this equalizer at each of `frequencies` (see [`EqConfig::frequency_response`])
+    ///
+    /// Reports the target config, not a ramp in progress. In linear-phase
+    /// mode the magnitude is that of the design target; the phase is the
+    /// FIR's constant delay.
+    pub fn frequency_response(&self, frequencies: &[f32]) -> Result<FrequencyResponse, DspError> {
+        let mut response = self.config().frequency_response(self.sample_rate(), frequencies)?;
+        if self.phase_mode() == PhaseMode::Linear {
+            let latency = self.latency_samples() as f64;
+            let delay_ms = (latency * 1000.0 / self.sample_rate() as f64) as f32;
+            for ((phase, group_delay), &frequency) in response
+                .phase
+                .iter_mut()
+                .zip(response.group_delay_ms.iter_mut())
+                .zip(frequencies)
+            {
+                let w = TAU * frequency as f64 / self.sample_rate() as f64;
+                *phase = wrap_phase(-w * latency) as f32;
+                *group_delay = delay_ms;
+            }
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eq::{BandType, FilterSlope};
+
+    /// Measured steady-state gain (dB) of a sine through the equalizer
+    fn measured_gain_db(eq: &mut Equalizer, frequency: f32) -> f32 {
+        let sample_rate = eq.sample_rate();
+        let length = 48000;
+        let mut peak = 0.0_f32;
+        for n in 0..length {
+            let x = (std::f32::consts::TAU * frequency * n as f32 / sample_rate).sin();
+            let (left, _) = eq.process_sample(x, x);
+            if n > length / 2 {
+                peak = peak.max(left.abs());
+            }
+        }
+        20.0 * peak.log10()
+    }
+
+    #[test]
+    fn test_matches_processed_signal() {
+        let mut config = EqConfig::default();
+        // Overlapping shelves, a steep high-pass and master gain
+        config.set_band_gain(0, 6.0).unwrap();
+        config.set_band_gain(1, 4.0).unwrap();
+        config.bands[1].band_type = BandType::LowShelf;
+        config.bands[9].band_type = BandType::HighPass;
+        config.bands[9].frequency = 40.0;
+        config.bands[9].slope = FilterSlope::Db24;
+        config.master_gain_db = -3.0;
+
+        let frequencies = [30.0, 100.0, 1000.0, 5000.0];
+        let response = config.frequency_response(48000.0, &frequencies).unwrap();
+        let mut eq = Equalizer::with_config(48000.0, config).unwrap();
+        for (&frequency, &expected) in frequencies.iter().zip(&response.magnitude_db) {
+            eq.reset();
+            let measured = measured_gain_db(&mut eq, frequency);
+            assert!(
+                (measured - expected).abs() < 0.1,
+                "{} Hz: measured {} dB, predicted {} dB",
+                frequency,
+                measured,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_phase_and_group_delay() {
+        let mut config = EqConfig::with_band_count(1).unwrap();
+        config.bands[0] = crate::Band::new(1000.0, BandType::AllPass);
+        let response = config
+            .frequency_response(48000.0, &[10.0, 1000.0, 20000.0])
+            .unwrap();
+
+        // All-pass: flat magnitude, -180 degrees at its centre
+        assert!(response.magnitude_db.iter().all(|db| db.abs() < 1e-3));
+        assert!((response.phase[1].abs() - std::f32::consts::PI).abs() < 1e-3);
+        // Group delay peaks at the centre: 2Q / (pi f) for a 2nd-order all-pass
+        let expected_ms = 2.0 * 0.707 / (std::f32::consts::PI * 1000.0) * 1000.0;
+        assert!((response.group_delay_ms[1] - expected_ms).abs() < 0.01);
+        assert!(response.group_delay_ms[0] < response.group_delay_ms[1]);
+        assert!(response.group_delay_ms[2] < response.group_delay_ms[1]);
+    }
+
+    #[test]
+    fn test_notch_depth_and_validation() {
+        let mut config = EqConfig::with_band_count(1).unwrap();
+        config.bands[0] = crate::Band::new(1000.0, BandType::Notch);
+        let response = config.frequency_response(48000.0, &[1000.0]).unwrap();
+        // f32 coefficients leave the zero just off the unit circle
+        assert!(response.magnitude_db[0] < -100.0);
+        assert!(response.magnitude_db[0] >= MIN_RESPONSE_DB);
+        assert!(response.group_delay_ms[0].is_finite());
+
+        assert!(config.frequency_response(48000.0, &[30000.0]).is_err());
+        assert!(config.frequency_response(0.0, &[100.0]).is_err());
+
+        config.enabled = false;
+        let response = config.frequency_response(48000.0, &[1000.0]).unwrap();
+        assert_eq!(response, FrequencyResponse::flat(&[1000.0]));
+    }
+
+    #[test]
+    fn test_cascade() {
+        let frequencies = log_frequencies(20.0, 20000.0, 64);
+        assert_eq!(frequencies.len(), 64);
+        assert!((frequencies[0] - 20.0).abs() < 1e-3);
+        assert!((frequencies[63] - 20000.0).abs() < 0.1);
+
+        let mut app = EqConfig::default();
+        app.set_band_gain(5, 4.0).unwrap();
+        let mut master = EqConfig::default();
+        master.set_band_gain(5, 2.0).unwrap();
+        master.master_gain_db = -1.0;
+
+        let a = app.frequency_response(48000.0, &frequencies).unwrap();
+        let b = master.frequency_response(48000.0, &frequencies).unwrap();
+        let combined = a.cascade(&b).unwrap();
+        for i in 0..frequencies.len() {
+            let sum = a.magnitude_db[i] + b.magnitude_db[i];
+            assert!((combined.magnitude_db[i] - sum).abs() < 1e-5);
+        }
+        let peak = combined.magnitude_db.iter().cloned().fold(f32::MIN, f32::max);
+        assert!((peak - 5.0).abs() < 0.1, "Expected ~5 dB peak, got {}", peak);
+
+        assert!(a.cascade(&FrequencyResponse::flat(&[1.0])).is_err());
+    }
+
+    #[test]
+    fn test_linear_phase_response() {
+        let mut eq = Equalizer::new(48000.0);
+        eq.set_band_gain(3, 6.0).unwrap();
+        let minimum = eq.frequency_response(&[100.0, 1000.0]).unwrap();
+        eq.set_phase_mode(PhaseMode::Linear);
+        let linear = eq.frequency_response(&[100.0, 1000.0]).unwrap();
+
+        assert_eq!(minimum.magnitude_db, linear.magnitude_db);
+        let delay_ms = eq.latency_samples() as f32 / 48.0;
+        assert!(linear.group_delay_ms.iter().all(|&d| (d - delay_ms).abs() < 1e-3));
+    }
+}
@@ -0,0 +1,224 @@
+//! Portable SIMD Lanes
+//!
+//! Stable Rust has no portable SIMD type, so [`Lanes`] is a plain aligned
+//! array with element-wise operators. LLVM turns the fixed-length loops into
+//! SSE/AVX or NEON instructions, with no intrinsics and no `unsafe`.
+//!
+//! Used for structure-of-arrays processing: one lane per channel and
+//! cascade stage, so a biquad runs every channel of a frame in one vector
+//! operation.
+
+use std::ops::{Add, Mul, Sub};
+
+use biquad::Coefficients;
+
+/// `N` f32 lanes, aligned for vector loads
+///
+/// `Lanes<4>` (f32x4) fills one SSE / NEON register and `Lanes<8>` (f32x8)
+/// one AVX register; wider arrays are split across several registers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C, align(32))]
+pub(crate) struct Lanes<const N: usize>(pub [f32; N]);
+
+impl<const N: usize> Lanes<N> {
+    /// Every lane set to `value`
+    #[inline(always)]
+    pub fn splat(value: f32) -> Self {
+        Self([value; N])
+    }
+}
+
+impl<const N: usize> Add for Lanes<N> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn add(mut self, rhs: Self) -> Self {
+        for (a, b) in self.0.iter_mut().zip(rhs.0) {
+            *a += b;
+        }
+        self
+    }
+}
+
+impl<const N: usize> Sub for Lanes<N> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn sub(mut self, rhs: Self) -> Self {
+        for (a, b) in self.0.iter_mut().zip(rhs.0) {
+            *a -= b;
+        }
+        self
+    }
+}
+
+impl<const N: usize> Mul for Lanes<N> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn mul(mut self, rhs: Self) -> Self {
+        for (a, b) in self.0.iter_mut().zip(rhs.0) {
+            *a *= b;
+        }
+        self
+    }
+}
+
+/// Biquad coefficients with a value per lane
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LaneCoefficients<const N: usize> {
+    pub b0: Lanes<N>,
+    pub b1: Lanes<N>,
+    pub b2: Lanes<N>,
+    pub a1: Lanes<N>,
+    pub a2: Lanes<N>,
+}
+
+impl<const N: usize> LaneCoefficients<N> {
+    /// All zero: a lane left like this outputs silence
+    pub fn zero() -> Self {
+        let zero = Lanes::splat(0.0);
+        Self {
+            b0: zero,
+            b1: zero,
+            b2: zero,
+            a1: zero,
+            a2: zero,
+        }
+    }
+
+    /// Load `coeffs` into `lanes`
+    pub fn set(&mut self, lanes: std::ops::Range<usize>, coeffs: &Coefficients<f32>) {
+        for lane in lanes {
+            self.b0.0[lane] = coeffs.b0;
+            self.b1.0[lane] = coeffs.b1;
+            self.b2.0[lane] = coeffs.b2;
+            self.a1.0[lane] = coeffs.a1;
+            self.a2.0[lane] = coeffs.a2;
+        }
+    }
+}
+
+/// Run a cascade of `stages` Direct Form II Transposed biquads over a block
+///
+/// One biquad is a recurrence, so a vector running a single stage spends
+/// most of its time waiting on the previous sample. Instead the stages are
+/// pipelined: lanes `p×W..(p+1)×W` hold stage `p` for `W` channels, and
+/// each step stage `p` filters the frame stage `p - 1` finished the step
+/// before. Up to `N / W` stages run in one vector operation.
+///
+/// `block` holds one frame per vector in lanes `0..W`; it is filtered in
+/// place. `state` is `[s1, s2]` per lane, in the same layout as `coeffs`.
+/// The arithmetic matches `biquad::DirectForm2Transposed::run` operation
+/// for operation, so the output is bit-identical to running each stage and
+/// channel through a scalar filter.
+///
+/// # Real-time Safety
+/// No allocations. O(n + stages) where n = block length.
+#[inline]
+pub(crate) fn biquad_pipeline<const N: usize, const W: usize>(
+    coeffs: &LaneCoefficients<N>,
+    state: &mut [Lanes<N>; 2],
+    stages: usize,
+    block: &mut [Lanes<N>],
+) {
+    debug_assert!(stages >= 1 && stages * W <= N);
+    let len = block.len();
+    let last = (stages - 1) * W;
+    let [mut s1, mut s2] = *state;
+    let mut out = Lanes::<N>::splat(0.0);
+    for step in 0..len + stages - 1 {
+        // Each stage takes what the stage before produced last step; the
+        // first stage takes the next frame
+        let mut input = [0.0; N];
+        input[W..].copy_from_slice(&out.0[..N - W]);
+        if step < len {
+            input[..W].copy_from_slice(&block[step].0[..W]);
+        }
+        let input = Lanes(input);
+        out = s1 + coeffs.b0 * input;
+        let mut next1 = s2 + coeffs.b1 * input - coeffs.a1 * out;
+        let mut next2 = coeffs.b2 * input - coeffs.a2 * out;
+        if step + 1 < stages || step >= len {
+            // Filling or draining the pipeline: stages without a frame this
+            // step keep their state
+            for stage in 0..stages {
+                if stage > step || step - stage >= len {
+                    let lanes = stage * W..(stage + 1) * W;
+                    next1.0[lanes.clone()].copy_from_slice(&s1.0[lanes.clone()]);
+                    next2.0[lanes.clone()].copy_from_slice(&s2.0[lanes]);
+                }
+            }
+        }
+        s1 = next1;
+        s2 = next2;
+        if step + 1 >= stages {
+            block[step + 1 - stages].0[..W].copy_from_slice(&out.0[last..last + W]);
+        }
+    }
+    *state = [s1, s2];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use biquad::{Biquad, DirectForm2Transposed, ToHertz, Type};
+
+    #[test]
+    fn test_biquad_pipeline_matches_scalar() {
+        let coeffs = [
+            Type::PeakingEQ(6.0),
+            Type::HighPass,
+            Type::LowShelf(-4.0),
+            Type::PeakingEQ(-9.0),
+        ]
+        .map(|kind| {
+            Coefficients::<f32>::from_params(kind, 48000.0.hz(), 1000.0.hz(), 1.4).unwrap()
+        });
+        // Blocks shorter and longer than the pipeline
+        for (stages, len) in [(4, 64), (3, 2), (1, 17)] {
+            let mut scalar: Vec<[DirectForm2Transposed<f32>; 2]> = coeffs[..stages]
+                .iter()
+                .map(|&c| [DirectForm2Transposed::<f32>::new(c); 2])
+                .collect();
+            let mut lane_coeffs = LaneCoefficients::<8>::zero();
+            for (stage, c) in coeffs[..stages].iter().enumerate() {
+                lane_coeffs.set(stage * 2..stage * 2 + 2, c);
+            }
+            let mut state = [Lanes::<8>::splat(0.0); 2];
+
+            for round in 0..3 {
+                let mut block: Vec<Lanes<8>> = (0..len)
+                    .map(|n| {
+                        let x = ((n + round * len) as f32 * 0.3).sin();
+                        Lanes([x, -0.5 * x, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0])
+                    })
+                    .collect();
+                let input = block.clone();
+                biquad_pipeline::<8, 2>(&lane_coeffs, &mut state, stages, &mut block);
+                for (x, y) in input.iter().zip(&block) {
+                    for channel in 0..2 {
+                        let expected = scalar
+                            .iter_mut()
+                            .fold(x.0[channel], |sample, filters| filters[channel].run(sample));
+                        assert_eq!(expected, y.0[channel], "{} stages", stages);
+                    }
+                }
+            }
+            for (stage, filters) in scalar.iter().enumerate() {
+                assert_eq!(state[0].0[stage * 2 + 1], filters[1].s1);
+                assert_eq!(state[1].0[stage * 2], filters[0].s2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lane_arithmetic() {
+        let a = Lanes::<8>::splat(2.0);
+        let b = Lanes([1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+        assert_eq!(
+            (a * b - a + b).0,
+            [1.0, 4.0, 7.0, 10.0, 13.0, 16.0, 19.0, 22.0]
+        );
+    }
+}
@@ -0,0 +1,544 @@
+//! Stereo EQ Channel Modes
+//!
+//! [`Equalizer`] applies one config to every channel. [`StereoEqualizer`]
+//! pairs two equalizers so the front left/right pair can be treated three ways:
+//!
+//! - **Linked**: one config for every channel, exactly like a plain `Equalizer`.
+//! - **Left/Right**: the primary config EQs left and the secondary config
+//!   right, to correct headphones whose two drivers don't match.
+//! - **Mid/Side**: the pair is encoded to mid (L+R)/2 and side (L-R)/2, the
+//!   primary config EQs mid and the secondary side, and the result is decoded
+//!   back. A mid presence boost brings dialog forward without touching
+//!   effects and music panned wide.
+//!
+//! ```text
+//! L ──┐            ┌─► primary EQ ───┐            ┌──► L'
+//!     ├─ encode* ──┤                 ├─ decode* ──┤
+//! R ──┘            └─► secondary EQ ─┘  (width)   └──► R'
+//!
+//! * mid/side mode only
+//! ```
+//!
+//! A stereo width control scales the side signal in every mode: 0 folds the
+//! pair to mono, 1 leaves the image unchanged and 2 doubles the side level.
+//!
+//! Channels beyond the front pair (centre, LFE, surrounds) always use the
+//! primary config.
+
+use crate::eq::{Equalizer, MAX_CHANNELS};
+use crate::error::DspError;
+use crate::linear_phase::PhaseMode;
+use crate::processor::{AudioProcessor, ProcessContext};
+
+/// Largest stereo width (side level relative to the input)
+pub const MAX_STEREO_WIDTH: f32 = 2.0;
+
+/// Frames staged on the stack per chunk by [`StereoEqualizer::process_block`]
+const STAGING_FRAMES: usize = 256;
+
+/// How the front left/right pair is equalised
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum EqChannelMode {
+    /// One config for both channels
+    #[default]
+    Linked,
+    /// Primary config on left, secondary config on right
+    LeftRight,
+    /// Primary config on mid, secondary config on side
+    MidSide,
+}
+
+/// Equalizer with linked, left/right or mid/side configs and a width control
+///
+/// Both equalizers are allocated up front, so switching modes, editing
+/// either config and changing the width never allocate.
+pub struct StereoEqualizer {
+    mode: EqChannelMode,
+    primary: Equalizer,
+    // Always mono: it only ever sees the right or side signal
+    secondary: Equalizer,
+    channels: usize,
+    // Target width, and the width as heard (ramped toward it over each buffer)
+    width: f32,
+    current_width: f32,
+}
+
+impl StereoEqualizer {
+    /// Create a linked, flat equalizer (10 bands per config)
+    pub fn new(sample_rate: f32) -> Self {
+        Self::with_band_count(sample_rate, crate::EQ_BANDS.len())
+            .expect("10 bands is always a valid layout")
+    }
+
+    /// Create a linked, flat equalizer using the standard layout for `band_count` bands
+    pub fn with_band_count(sample_rate: f32, band_count: usize) -> Result<Self, DspError> {
+        let primary = Equalizer::with_band_count(sample_rate, band_count)?;
+        let mut secondary = Equalizer::with_band_count(sample_rate, band_count)?;
+        secondary.set_channel_count(1)?;
+        Ok(Self {
+            mode: EqChannelMode::Linked,
+            primary,
+            secondary,
+            channels: 2,
+            width: 1.0,
+            current_width: 1.0,
+        })
+    }
+
+    /// Current channel mode
+    pub fn mode(&self) -> EqChannelMode {
+        self.mode
+    }
+
+    /// Switch channel mode; filter state is cleared when it changes
+    ///
+    /// # Real-time Safety
+    /// No allocations. Safe to call from an audio callback between buffers.
+    pub fn set_mode(&mut self, mode: EqChannelMode) {
+        if mode != self.mode {
+            self.mode = mode;
+            self.reset();
+        }
+    }
+
+    /// Config for every channel (linked), left (L/R) or mid (M/S)
+    pub fn primary(&self) -> &Equalizer {
+        &self.primary
+    }
+
+    /// Mutable access to the primary equalizer
+    pub fn primary_mut(&mut self) -> &mut Equalizer {
+        &mut self.primary
+    }
+
+    /// Config for right (L/R) or side (M/S); unused when linked
+    pub fn secondary(&self) -> &Equalizer {
+        &self.secondary
+    }
+
+    /// Mutable access to the secondary equalizer
+    pub fn secondary_mut(&mut self) -> &mut Equalizer {
+        &mut self.secondary
+    }
+
+    /// Target stereo width (0 = mono, 1 = unchanged, 2 = doubled side)
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    /// Set the stereo width (0 to [`MAX_STEREO_WIDTH`])
+    ///
+    /// The change is ramped over the next buffer to avoid clicks.
+    pub fn set_width(&mut self, width: f32) -> Result<(), DspError> {
+        if !(0.0..=MAX_STEREO_WIDTH).contains(&width) {
+            return Err(DspError::InvalidParameter {
+                name: "width",
+                value: width,
+            });
+        }
+        self.width = width;
+        Ok(())
+    }
+
+    /// Set the number of interleaved channels (1 to [`MAX_CHANNELS`])
+    ///
+    /// Mono input always uses the primary config. Filter state is reset when
+    /// the count changes.
+    ///
+    /// # Real-time Safety
+    /// No allocations. Safe to call from an audio callback between buffers.
+    pub fn set_channel_count(&mut self, channels: usize) -> Result<(), DspError> {
+        if channels == 0 || channels > MAX_CHANNELS {
+            return Err(DspError::InvalidChannelCount(channels));
+        }
+        self.primary.set_channel_count(channels)?;
+        if channels != self.channels {
+            self.channels = channels;
+            self.secondary.reset();
+        }
+        Ok(())
+    }
+
+    /// Number of interleaved channels processed
+    pub fn channel_count(&self) -> usize {
+        self.channels
+    }
+
+    /// Switch both configs to the standard layout for `band_count` bands (flat)
+    ///
+    /// # Real-time Safety
+    /// No allocations. Safe to call from an audio callback.
+    pub fn set_band_count(&mut self, band_count: usize) -> Result<(), DspError> {
+        self.primary.set_band_count(band_count)?;
+        self.secondary.set_band_count(band_count)
+    }
+
+    /// Change the sample rate of both configs (see [`Equalizer::set_sample_rate`])
+    pub fn set_sample_rate(&mut self, sample_rate: f32) -> Result<(), DspError> {
+        self.primary.set_sample_rate(sample_rate)?;
+        self.secondary.set_sample_rate(sample_rate)
+    }
+
+    /// Switch both configs between minimum and linear phase
+    ///
+    /// Note: Linear phase allocates FIR buffers; call during setup.
+    pub fn set_phase_mode(&mut self, mode: PhaseMode) {
+        self.primary.set_phase_mode(mode);
+        self.secondary.set_phase_mode(mode);
+    }
+
+    /// Delay (in samples) the equalizer adds; the same for both configs
+    pub fn latency_samples(&self) -> usize {
+        self.primary.latency_samples()
+    }
+
+    /// Clear the filter state of both configs
+    pub fn reset(&mut self) {
+        self.primary.reset();
+        self.secondary.reset();
+    }
+
+    /// Process an interleaved buffer in-place
+    ///
+    /// A trailing partial frame is left untouched.
+    ///
+    /// # Real-time Safety
+    /// No allocations. O(n) where n = buffer length.
+    pub fn process_interleaved(&mut self, buffer: &mut [f32]) {
+        let channels = self.channels;
+        let frames = buffer.len() / channels;
+        let unchanged_width = self.current_width == 1.0 && self.width == 1.0;
+        if channels < 2 || (self.mode == EqChannelMode::Linked && unchanged_width) {
+            self.primary.process_block(buffer);
+            self.current_width = self.width;
+            return;
+        }
+        if frames == 0 {
+            return;
+        }
+
+        let start = self.current_width;
+        let step = (self.width - start) / frames as f32;
+        for (n, frame) in buffer.chunks_exact_mut(channels).enumerate() {
+            let width = start + step * (n + 1) as f32;
+            let side = match self.mode {
+                EqChannelMode::Linked => {
+                    self.primary.process_frame(frame);
+                    (frame[0] - frame[1]) * 0.5
+                }
+                EqChannelMode::LeftRight => {
+                    let mut right = [frame[1]];
+                    self.primary.process_frame(frame);
+                    self.secondary.process_frame(&mut right);
+                    frame[1] = right[0];
+                    (frame[0] - frame[1]) * 0.5
+                }
+                EqChannelMode::MidSide => {
+                    let mut side = [(frame[0] - frame[1]) * 0.5];
+                    frame[0] = (frame[0] + frame[1]) * 0.5;
+                    frame[1] = frame[0];
+                    self.primary.process_frame(frame);
+                    self.secondary.process_frame(&mut side);
+                    // Decode from the equalised mid
+                    frame[1] = frame[0];
+                    frame[0] += side[0] * width;
+                    frame[1] -= side[0] * width;
+                    continue;
+                }
+            };
+            if width != 1.0 {
+                let mid = (frame[0] + frame[1]) * 0.5;
+                frame[0] = mid + side * width;
+                frame[1] = mid - side * width;
+            }
+        }
+        self.current_width = self.width;
+    }
+
+    /// Process an interleaved buffer in place using the SIMD block path
+    ///
+    /// Same output as [`process_interleaved`](Self::process_interleaved), but
+    /// both configs run [`Equalizer::process_block`] in every mode: the right
+    /// (L/R) or side (M/S) channel is split out into a stack buffer for the
+    /// secondary config, a chunk at a time.
+    ///
+    /// # Real-time Safety
+    /// No allocations. O(n) where n = buffer length.
+    pub fn process_block(&mut self, buffer: &mut [f32]) {
+        let channels = self.channels;
+        let frames = buffer.len() / channels;
+        let unchanged_width = self.current_width == 1.0 && self.width == 1.0;
+        if channels < 2 || (self.mode == EqChannelMode::Linked && unchanged_width) {
+            self.primary.process_block(buffer);
+            self.current_width = self.width;
+            return;
+        }
+        if frames == 0 {
+            return;
+        }
+
+        let start = self.current_width;
+        let step = (self.width - start) / frames as f32;
+        let mut staged = [0.0_f32; STAGING_FRAMES];
+        let chunks = buffer[..frames * channels].chunks_mut(STAGING_FRAMES * channels);
+        for (index, chunk) in chunks.enumerate() {
+            let secondary = &mut staged[..chunk.len() / channels];
+            match self.mode {
+                EqChannelMode::Linked => self.primary.process_block(chunk),
+                EqChannelMode::LeftRight => {
+                    for (right, frame) in secondary.iter_mut().zip(chunk.chunks_exact(channels)) {
+                        *right = frame[1];
+                    }
+                    self.primary.process_block(chunk);
+                    self.secondary.process_block(secondary);
+                    for (&right, frame) in secondary.iter().zip(chunk.chunks_exact_mut(channels)) {
+                        frame[1] = right;
+                    }
+                }
+                EqChannelMode::MidSide => {
+                    let encode = secondary.iter_mut().zip(chunk.chunks_exact_mut(channels));
+                    for (side, frame) in encode {
+                        *side = (frame[0] - frame[1]) * 0.5;
+                        frame[0] = (frame[0] + frame[1]) * 0.5;
+                        frame[1] = frame[0];
+                    }
+                    self.primary.process_block(chunk);
+                    self.secondary.process_block(secondary);
+                }
+            }
+
+            let first = index * STAGING_FRAMES;
+            let decode = chunk.chunks_exact_mut(channels).zip(&*secondary);
+            for (n, (frame, &side)) in decode.enumerate() {
+                let width = start + step * (first + n + 1) as f32;
+                if self.mode == EqChannelMode::MidSide {
+                    // Decode from the equalised mid
+                    frame[1] = frame[0];
+                    frame[0] += side * width;
+                    frame[1] -= side * width;
+                } else if width != 1.0 {
+                    let side = (frame[0] - frame[1]) * 0.5;
+                    let mid = (frame[0] + frame[1]) * 0.5;
+                    frame[0] = mid + side * width;
+                    frame[1] = mid - side * width;
+                }
+            }
+        }
+        self.current_width = self.width;
+    }
+}
+
+impl AudioProcessor for StereoEqualizer {
+    fn process(&mut self, buffer: &mut [f32], context: &ProcessContext) {
+        let _ = self.set_channel_count(context.channels);
+        self.process_interleaved(buffer);
+    }
+
+    fn reset(&mut self) {
+        StereoEqualizer::reset(self);
+    }
+
+    fn name(&self) -> &'static str {
+        "Stereo Equalizer"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.primary.config().enabled
+    }
+
+    fn latency_samples(&self) -> usize {
+        StereoEqualizer::latency_samples(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// Interleaved 1 kHz sine with per-channel amplitudes
+    fn sine(amplitudes: &[f32], frames: usize) -> Vec<f32> {
+        (0..frames)
+            .flat_map(|i| {
+                let x = (std::f32::consts::TAU * 1000.0 * i as f32 / SAMPLE_RATE).sin();
+                amplitudes.iter().map(move |a| a * x)
+            })
+            .collect()
+    }
+
+    /// Peak of each channel over the second half of the buffer
+    fn peaks(buffer: &[f32], channels: usize) -> Vec<f32> {
+        let mut peaks = vec![0.0_f32; channels];
+        let frames = buffer.len() / channels;
+        for frame in buffer.chunks_exact(channels).skip(frames / 2) {
+            for (peak, sample) in peaks.iter_mut().zip(frame) {
+                *peak = peak.max(sample.abs());
+            }
+        }
+        peaks
+    }
+
+    fn db(ratio: f32) -> f32 {
+        20.0 * ratio.log10()
+    }
+
+    /// 10-band stereo EQ with the 1 kHz band (index 5) boosted on one config
+    fn boosted(mode: EqChannelMode, secondary: bool) -> StereoEqualizer {
+        let mut eq = StereoEqualizer::new(SAMPLE_RATE);
+        eq.set_mode(mode);
+        let target = if secondary {
+            eq.secondary_mut()
+        } else {
+            eq.primary_mut()
+        };
+        target.set_band_gain(5, 6.0).unwrap();
+        target.finish_smoothing();
+        eq
+    }
+
+    #[test]
+    fn test_linked_matches_equalizer() {
+        let mut stereo = boosted(EqChannelMode::Linked, false);
+        let mut plain = Equalizer::new(SAMPLE_RATE);
+        plain.set_band_gain(5, 6.0).unwrap();
+        plain.finish_smoothing();
+
+        let mut a = sine(&[0.5, 0.2], 4800);
+        let mut b = a.clone();
+        stereo.process_interleaved(&mut a);
+        plain.process_interleaved(&mut b);
+        assert_eq!(a, b);
+
+        // The secondary config is ignored while linked
+        stereo.secondary_mut().set_band_gain(5, -12.0).unwrap();
+        let mut a = sine(&[0.5, 0.2], 4800);
+        let mut b = a.clone();
+        stereo.process_interleaved(&mut a);
+        plain.process_interleaved(&mut b);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_left_right_configs() {
+        let mut eq = boosted(EqChannelMode::LeftRight, true);
+        eq.set_channel_count(6).unwrap();
+        let mut buffer = sine(&[0.25; 6], 9600);
+        eq.process_interleaved(&mut buffer);
+        let peaks = peaks(&buffer, 6);
+
+        // Only the right channel gets the secondary boost
+        assert!(db(peaks[0] / 0.25).abs() < 0.2, "Left {}", peaks[0]);
+        assert!(
+            (db(peaks[1] / 0.25) - 6.0).abs() < 0.3,
+            "Right {}",
+            peaks[1]
+        );
+        for peak in &peaks[2..] {
+            assert!(db(peak / 0.25).abs() < 0.2, "Surround {}", peak);
+        }
+    }
+
+    #[test]
+    fn test_mid_side_configs() {
+        // Mid boost: centred (mono) content is boosted, pure side isn't
+        let mut eq = boosted(EqChannelMode::MidSide, false);
+        let mut centred = sine(&[0.25, 0.25], 9600);
+        eq.process_interleaved(&mut centred);
+        let centred = peaks(&centred, 2);
+        assert!(
+            (db(centred[0] / 0.25) - 6.0).abs() < 0.3,
+            "Mid {}",
+            centred[0]
+        );
+        assert!((centred[0] - centred[1]).abs() < 1e-4);
+
+        eq.reset();
+        let mut wide = sine(&[0.25, -0.25], 9600);
+        eq.process_interleaved(&mut wide);
+        let wide = peaks(&wide, 2);
+        assert!(db(wide[0] / 0.25).abs() < 0.2, "Side {}", wide[0]);
+
+        // A flat mid/side EQ reconstructs the input
+        let mut flat = StereoEqualizer::new(SAMPLE_RATE);
+        flat.set_mode(EqChannelMode::MidSide);
+        let original = sine(&[0.5, 0.1], 480);
+        let mut buffer = original.clone();
+        flat.process_interleaved(&mut buffer);
+        for (a, b) in buffer.iter().zip(&original) {
+            assert!((a - b).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_process_block_matches_interleaved() {
+        // Uneven buffers longer and shorter than a staging chunk, width ramps mid-stream
+        let sizes = [37, 600, 1, 256, 100];
+        for mode in [
+            EqChannelMode::Linked,
+            EqChannelMode::LeftRight,
+            EqChannelMode::MidSide,
+        ] {
+            for channels in [2, 6] {
+                let mut block = boosted(mode, true);
+                block.primary_mut().set_band_gain(2, -4.0).unwrap();
+                block.set_channel_count(channels).unwrap();
+                let mut scalar = boosted(mode, true);
+                scalar.primary_mut().set_band_gain(2, -4.0).unwrap();
+                scalar.set_channel_count(channels).unwrap();
+                let mut start = 0;
+                for (round, &frames) in sizes.iter().cycle().take(15).enumerate() {
+                    if round % 5 == 2 {
+                        let width = [0.3, 1.8, 1.0][round / 5];
+                        block.set_width(width).unwrap();
+                        scalar.set_width(width).unwrap();
+                    }
+                    // A different tone per channel so left, right, mid and side all differ
+                    let mut buffer: Vec<f32> = (start..start + frames)
+                        .flat_map(|i| {
+                            (1..=channels).map(move |c| (i as f32 * 0.037 * c as f32).sin() * 0.5)
+                        })
+                        .collect();
+                    start += frames;
+                    // Trailing partial frame is left alone by both
+                    buffer.push(0.5);
+                    let mut expected = buffer.clone();
+                    block.process_block(&mut buffer);
+                    scalar.process_interleaved(&mut expected);
+                    let context = format!("{:?}, {} channels, round {}", mode, channels, round);
+                    assert_eq!(buffer, expected, "{}", context);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_width() {
+        let mut eq = StereoEqualizer::new(SAMPLE_RATE);
+        assert!(eq.set_width(-0.1).is_err());
+        assert!(eq.set_width(2.5).is_err());
+
+        // Width 0 folds to mono once the ramp has finished
+        eq.set_width(0.0).unwrap();
+        let mut ramp = sine(&[0.5, 0.0], 480);
+        eq.process_interleaved(&mut ramp);
+        let mut buffer = sine(&[0.5, 0.0], 480);
+        eq.process_interleaved(&mut buffer);
+        for frame in buffer.chunks_exact(2) {
+            assert!((frame[0] - frame[1]).abs() < 1e-6);
+        }
+
+        // Width 2 doubles the side of a hard-panned signal: L = 1.5x, R = -0.5x
+        eq.set_mode(EqChannelMode::MidSide);
+        eq.set_width(2.0).unwrap();
+        let mut ramp = sine(&[0.2, 0.0], 480);
+        eq.process_interleaved(&mut ramp);
+        let mut buffer = sine(&[0.2, 0.0], 960);
+        eq.process_interleaved(&mut buffer);
+        let peaks = peaks(&buffer, 2);
+        assert!((peaks[0] - 0.3).abs() < 1e-3, "Left {}", peaks[0]);
+        assert!((peaks[1] - 0.1).abs() < 1e-3, "Right {}", peaks[1]);
+    }
+}
@@ -0,0 +1,134 @@
+//! True-Peak Detection
+//!
+//! Sample peaks miss the overs that appear between samples once a DAC
+//! reconstructs the waveform. As in ITU-R BS.1770 Annex 2, the signal is
+//! oversampled 4x with a polyphase FIR and the largest interpolated
+//! magnitude is taken as the true peak.
+//!
+//! Shared by the [`Limiter`](crate::Limiter) and the
+//! [`LoudnessAnalyzer`](crate::LoudnessAnalyzer).
+
+use crate::eq::MAX_CHANNELS;
+
+/// Oversampling factor
+const OVERSAMPLING: usize = 4;
+
+/// Input samples per polyphase branch of the interpolator
+const TAPS_PER_PHASE: usize = 12;
+
+/// Delay of the interpolator: phase 3 reproduces the input this many samples back
+pub(crate) const INTERPOLATOR_DELAY: usize = 5;
+
+/// Polyphase taps of the 4x interpolator, `[phase][tap]`
+///
+/// A Blackman-windowed sinc whose phase 3 is a pure delay of
+/// [`INTERPOLATOR_DELAY`] samples; each phase is normalised to unity DC gain.
+fn interpolator_phases() -> [[f32; TAPS_PER_PHASE]; OVERSAMPLING] {
+    let length = OVERSAMPLING * TAPS_PER_PHASE - 1;
+    let center = (length - 1) as f64 / 2.0;
+    let mut phases = [[0.0; TAPS_PER_PHASE]; OVERSAMPLING];
+    for (phase, taps) in phases.iter_mut().enumerate() {
+        for (j, tap) in taps.iter_mut().enumerate() {
+            let m = OVERSAMPLING * j + phase;
+            if m >= length {
+                continue;
+            }
+            let x = (m as f64 - center) / OVERSAMPLING as f64;
+            let sinc = if x == 0.0 {
+                1.0
+            } else {
+                (std::f64::consts::PI * x).sin() / (std::f64::consts::PI * x)
+            };
+            let w = std::f64::consts::TAU * m as f64 / (length - 1) as f64;
+            let window = 0.42 - 0.5 * w.cos() + 0.08 * (2.0 * w).cos();
+            *tap = (sinc * window) as f32;
+        }
+        let sum: f32 = taps.iter().sum();
+        for tap in taps.iter_mut() {
+            *tap /= sum;
+        }
+    }
+    phases
+}
+
+/// 4x oversampling true-peak detector for up to [`MAX_CHANNELS`] channels
+///
+/// History is allocated up front, so nothing allocates after `new`.
+pub(crate) struct TruePeakDetector {
+    phases: [[f32; TAPS_PER_PHASE]; OVERSAMPLING],
+    // Interpolator input history per channel (ring of TAPS_PER_PHASE)
+    history: Vec<f32>,
+    position: usize,
+}
+
+impl TruePeakDetector {
+    pub(crate) fn new() -> Self {
+        Self {
+            phases: interpolator_phases(),
+            history: vec![0.0; MAX_CHANNELS * TAPS_PER_PHASE],
+            position: 0,
+        }
+    }
+
+    /// Clear the interpolator history
+    pub(crate) fn reset(&mut self) {
+        self.history.fill(0.0);
+        self.position = 0;
+    }
+
+    /// Largest 4x-interpolated magnitude of the newest frame across channels
+    ///
+    /// The interpolated points lag the input by up to [`INTERPOLATOR_DELAY`] samples.
+    #[inline]
+    pub(crate) fn process_frame(&mut self, frame: &[f32]) -> f32 {
+        self.position = (self.position + 1) % TAPS_PER_PHASE;
+        let mut peak = 0.0_f32;
+        for (channel, &sample) in frame.iter().enumerate().take(MAX_CHANNELS) {
+            let history =
+                &mut self.history[channel * TAPS_PER_PHASE..(channel + 1) * TAPS_PER_PHASE];
+            history[self.position] = sample;
+            for taps in &self.phases {
+                // taps[j] multiplies the input j samples back
+                let mut sum = 0.0;
+                for (j, tap) in taps.iter().enumerate() {
+                    sum += tap * history[(self.position + TAPS_PER_PHASE - j) % TAPS_PER_PHASE];
+                }
+                peak = peak.max(sum.abs());
+            }
+        }
+        peak
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interpolator_phase_is_pure_delay() {
+        let phases = interpolator_phases();
+        for (j, &tap) in phases[OVERSAMPLING - 1].iter().enumerate() {
+            let expected = if j == INTERPOLATOR_DELAY { 1.0 } else { 0.0 };
+            assert!((tap - expected).abs() < 1e-6, "Tap {} is {}", j, tap);
+        }
+    }
+
+    #[test]
+    fn test_finds_inter_sample_peak() {
+        // fs/4 sine at 45 degrees: samples sit at ±0.707 of the true amplitude
+        let mut detector = TruePeakDetector::new();
+        let mut peak = 0.0_f32;
+        let mut sample_peak = 0.0_f32;
+        for n in 0..480 {
+            let x = (std::f32::consts::FRAC_PI_2 * n as f32 + std::f32::consts::FRAC_PI_4).sin();
+            sample_peak = sample_peak.max(x.abs());
+            peak = peak.max(detector.process_frame(&[x, 0.0]));
+        }
+        assert!(sample_peak < 0.71);
+        assert!(
+            (peak - 1.0).abs() < 0.02,
+            "True peak should be ~1.0, got {}",
+            peak
+        );
+    }
+}
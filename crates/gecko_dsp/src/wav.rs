@@ -0,0 +1,172 @@
+//! Minimal WAV Decoder
+//!
+//! Just enough RIFF/WAVE parsing to load impulse responses as exported by
+//! REW, Audacity and most measurement tools: integer PCM (8/16/24/32-bit)
+//! and IEEE float (32/64-bit), including WAVE_FORMAT_EXTENSIBLE headers.
+
+use crate::error::DspError;
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_FLOAT: u16 = 3;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Decoded audio: sample rate and one `Vec` per channel
+pub(crate) struct WavData {
+    pub sample_rate: u32,
+    pub channels: Vec<Vec<f32>>,
+}
+
+fn invalid(reason: &str) -> DspError {
+    DspError::InvalidImpulseResponse(reason.to_string())
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+/// Decode a complete WAV file held in memory
+pub(crate) fn decode(bytes: &[u8]) -> Result<WavData, DspError> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(invalid("not a RIFF/WAVE file"));
+    }
+
+    // Walk the chunks: we need "fmt " before "data"
+    let mut format = None;
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let size = read_u32(bytes, pos + 4) as usize;
+        let body = pos + 8;
+        let end = body.saturating_add(size).min(bytes.len());
+
+        if id == b"fmt " {
+            if size < 16 || end - body < 16 {
+                return Err(invalid("truncated fmt chunk"));
+            }
+            let mut tag = read_u16(bytes, body);
+            let channels = read_u16(bytes, body + 2) as usize;
+            let sample_rate = read_u32(bytes, body + 4);
+            let bits = read_u16(bytes, body + 14);
+            if tag == FORMAT_EXTENSIBLE {
+                // The real format tag is the first two bytes of the subformat GUID
+                if end - body < 26 {
+                    return Err(invalid("truncated extensible fmt chunk"));
+                }
+                tag = read_u16(bytes, body + 24);
+            }
+            format = Some((tag, channels, sample_rate, bits));
+        } else if id == b"data" {
+            let (tag, channels, sample_rate, bits) = format.ok_or_else(|| invalid("data chunk before fmt chunk"))?;
+            if channels == 0 || sample_rate == 0 {
+                return Err(invalid("zero channels or sample rate"));
+            }
+            let decode_sample: fn(&[u8]) -> f32 = match (tag, bits) {
+                (FORMAT_PCM, 8) => |b| (b[0] as f32 - 128.0) / 128.0,
+                (FORMAT_PCM, 16) => |b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0,
+                (FORMAT_PCM, 24) => |b| i32::from_le_bytes([0, b[0], b[1], b[2]]) as f32 / 2_147_483_648.0,
+                (FORMAT_PCM, 32) => |b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0,
+                (FORMAT_FLOAT, 32) => |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+                (FORMAT_FLOAT, 64) => |b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32,
+                _ => {
+                    return Err(DspError::InvalidImpulseResponse(format!(
+                        "unsupported sample format (tag {}, {} bits)",
+                        tag, bits
+                    )))
+                }
+            };
+
+            let frame_size = channels * bits as usize / 8;
+            let frames = (end - body) / frame_size;
+            let mut data = vec![Vec::with_capacity(frames); channels];
+            for frame in bytes[body..body + frames * frame_size].chunks_exact(frame_size) {
+                for (channel, sample) in data.iter_mut().zip(frame.chunks_exact(bits as usize / 8)) {
+                    channel.push(decode_sample(sample));
+                }
+            }
+            return Ok(WavData {
+                sample_rate,
+                channels: data,
+            });
+        }
+
+        // Chunks are padded to an even size
+        pos = body.saturating_add(size + (size & 1));
+    }
+
+    Err(invalid("no data chunk"))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Build a 32-bit float WAV file in memory
+    pub(crate) fn float_wav(sample_rate: u32, channels: &[Vec<f32>]) -> Vec<u8> {
+        let frames = channels[0].len();
+        let data_size = (frames * channels.len() * 4) as u32;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&FORMAT_FLOAT.to_le_bytes());
+        bytes.extend_from_slice(&(channels.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(sample_rate * channels.len() as u32 * 4).to_le_bytes());
+        bytes.extend_from_slice(&(channels.len() as u16 * 4).to_le_bytes());
+        bytes.extend_from_slice(&32u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_size.to_le_bytes());
+        for n in 0..frames {
+            for channel in channels {
+                bytes.extend_from_slice(&channel[n].to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_decode_float() {
+        let left = vec![1.0, 0.5, -0.25];
+        let right = vec![0.0, -1.0, 0.75];
+        let wav = decode(&float_wav(44100, &[left.clone(), right.clone()])).unwrap();
+        assert_eq!(wav.sample_rate, 44100);
+        assert_eq!(wav.channels, vec![left, right]);
+    }
+
+    #[test]
+    fn test_decode_pcm16_with_extra_chunk() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF\0\0\0\0WAVE");
+        // An odd-sized chunk before fmt must be skipped with its pad byte
+        bytes.extend_from_slice(b"LIST");
+        bytes.extend_from_slice(&3u32.to_le_bytes());
+        bytes.extend_from_slice(&[1, 2, 3, 0]);
+        bytes.extend_from_slice(b"fmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&FORMAT_PCM.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&48000u32.to_le_bytes());
+        bytes.extend_from_slice(&96000u32.to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&4u32.to_le_bytes());
+        bytes.extend_from_slice(&16384i16.to_le_bytes());
+        bytes.extend_from_slice(&(-32768i16).to_le_bytes());
+
+        let wav = decode(&bytes).unwrap();
+        assert_eq!(wav.sample_rate, 48000);
+        assert_eq!(wav.channels, vec![vec![0.5, -1.0]]);
+    }
+
+    #[test]
+    fn test_rejects_garbage() {
+        assert!(decode(b"not a wav file at all").is_err());
+        assert!(decode(b"RIFF\0\0\0\0WAVE").is_err());
+    }
+}
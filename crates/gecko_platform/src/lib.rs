@@ -33,7 +33,8 @@ pub mod macos;
 
 pub use error::PlatformError;
 pub use traits::{
-    ApplicationInfo, AudioNode, AudioPort, LinkInfo, PlatformBackend, VirtualSinkConfig,
+    channel_positions, ApplicationInfo, AudioNode, AudioPort, LinkInfo, PlatformBackend,
+    VirtualSinkConfig,
 };
 
 /// Get the platform backend for the current OS
@@ -68,6 +69,27 @@ pub fn get_backend() -> Result<Box<dyn PlatformBackend>, PlatformError> {
     }
 }
 
+/// Extract the app name from a stream ID
+///
+/// Format varies by platform:
+///   - Linux: "PID:Name" (name is after the first colon)
+///   - macOS: "Name:PID" (name is before the last colon)
+///
+/// Either way the name itself may contain colons. An ID without a PID is
+/// taken to be the name.
+pub fn app_name_from_stream_id(stream_id: &str) -> &str {
+    #[cfg(target_os = "linux")]
+    let app_name = stream_id.split_once(':').map_or(stream_id, |(_, name)| name);
+
+    #[cfg(target_os = "macos")]
+    let app_name = stream_id.rsplit_once(':').map_or(stream_id, |(name, _)| name);
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    let app_name = stream_id;
+
+    app_name
+}
+
 /// Check if the current platform supports virtual audio devices
 pub fn supports_virtual_devices() -> bool {
     #[cfg(target_os = "linux")]
@@ -112,6 +134,14 @@ pub fn supports_per_app_capture() -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_app_name_from_stream_id() {
+        assert_eq!(app_name_from_stream_id("1234:Firefox"), "Firefox");
+        assert_eq!(app_name_from_stream_id("1234:Foo: Bar"), "Foo: Bar");
+        assert_eq!(app_name_from_stream_id("Firefox"), "Firefox");
+    }
+
     #[test]
     fn test_platform_capabilities() {
         // These should compile and return reasonable values
@@ -10,7 +10,7 @@
 //!       ↓ (user routes to virtual sink)
 //! Virtual Sink (created by PipeWireBackend)
 //!       ↓ (monitor port)
-//! Capture Stream ──→ DSP Processing ──→ Soft Clip ──→ Playback Stream
+//! Capture Stream ──→ DSP Processing ──→ Soft Clip/Limiter ──→ Playback Stream
 //!                          │                               ↓
 //!                          └──→ FFT Analyzer         Real Speakers
 //!                                    ↓
@@ -20,14 +20,24 @@
 //! IMPORTANT: This does NOT use microphone input! Audio comes from applications
 //! routed through the virtual sink.
 
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, AtomicUsize, Ordering};
 use std::sync::Arc;
 
 // Note: These imports will be used when we implement actual streaming
 #[allow(unused_imports)]
 use pipewire as pw;
 
-use gecko_dsp::{SoftClipper, SpectrumAnalyzer, NUM_BINS};
+use gecko_dsp::{
+    band_layout_params, loudness_channel_weight, AtomicBandParams, AutoLevelSettings, AutoLeveler,
+    BandParams, ChainConfig, ChainScope, CompressorMeter, Convolver, Crossfeed, CrossfeedSettings,
+    Delay, DriftMeter, DriftStats, EqChannelMode, GainReduction, ImpulseResponse, IR_BLOCK,
+    EqConfig, Equalizer, Limiter, LimiterSettings, LoudnessAnalyzer, LoudnessMeter, LoudnessStats,
+    OutputStage, PhaseMode, ProcessorKind, SoftClipper, SpectrumAnalyzer, StereoEqualizer, EQ_BANDS, MAX_BANDS, MAX_CHANNELS,
+    MAX_STEREO_WIDTH, MAX_SYNC_OFFSET_MS, NUM_BINS,
+};
+
+/// Highest rate a stream's delay line can follow after a renegotiation
+const MAX_STREAM_RATE: f32 = 192000.0;
 
 /// Audio format configuration
 #[derive(Debug, Clone, Copy)]
@@ -62,17 +72,67 @@ pub struct AudioProcessingState {
     /// Whether streams are running
     pub running: AtomicBool,
 
-    /// Master EQ band gains (10 bands, stored as u32 bits interpreted as f32)
+    /// Interleaved channel count of the per-app pipeline (sinks, captures, mixer, output)
+    /// Read when sinks and streams are created; existing ones keep their layout
+    channels: AtomicUsize,
+
+    /// Number of active EQ bands (1 to MAX_BANDS), shared by master and per-app EQs
+    /// Audio callbacks compare this to their local Equalizer and re-layout without allocating
+    band_count: AtomicUsize,
+
+    /// Master EQ band gains (stored as u32 bits interpreted as f32)
+    /// Sized for MAX_BANDS; only the first `band_count` entries are used.
     /// These are the base/global EQ settings
-    master_eq_gains: [AtomicU32; 10],
+    master_eq_gains: [AtomicU32; MAX_BANDS],
+
+    /// Master EQ band shapes (frequency, Q, type, enabled), lock-free
+    /// Reset to the standard layout whenever the band count changes
+    master_band_params: [AtomicBandParams; MAX_BANDS],
+
+    /// Master EQ channel mode (EqChannelMode as u8): linked, left/right or mid/side
+    eq_channel_mode: AtomicU8,
+
+    /// Secondary master EQ gains and shapes (right or side), as for the primary config
+    /// Only heard in left/right and mid/side modes
+    secondary_eq_gains: [AtomicU32; MAX_BANDS],
+    secondary_band_params: [AtomicBandParams; MAX_BANDS],
+
+    /// Master stereo width (0.0 mono, 1.0 unchanged, up to MAX_STEREO_WIDTH), as f32 bits
+    stereo_width_bits: AtomicU32,
+
+    /// Cancel the peak EQ boost with a matching preamp (master and per-app EQs)
+    auto_preamp: AtomicBool,
+
+    /// Master preamp (dB) set by the user, as f32 bits; used while auto preamp is off
+    manual_preamp_bits: AtomicU32,
+
+    /// Preamps (dB, f32 bits) for the master primary and secondary configs
+    /// Computed by the setters whenever the response changes, so audio
+    /// callbacks never run the peak search; the manual preamp while auto
+    /// preamp is off
+    master_preamp_bits: AtomicU32,
+    secondary_preamp_bits: AtomicU32,
+
+    /// Preamp (dB) each app's EQ applies, for UI reads
+    /// The capture callbacks get theirs from `AppControls`
+    stream_preamps: parking_lot::RwLock<std::collections::HashMap<String, f32>>,
+
+    /// Set by the audio threads when an EQ update was rejected
+    /// Logged and cleared by the PipeWire thread, so callbacks never format
+    eq_error: AtomicBool,
+
+    /// Per-stream band shapes (stream_id → shape per band)
+    /// Read when a per-app capture is created; live updates go through
+    /// the capture's own atomics
+    stream_band_params: parking_lot::RwLock<std::collections::HashMap<String, [BandParams; MAX_BANDS]>>,
 
-    /// Per-stream EQ offsets (stream_id → [10 bands of offset_db])
+    /// Per-stream EQ offsets (stream_id → [MAX_BANDS bands of offset_db])
     /// These offsets are ADDED to master EQ to get final gains
-    stream_eq_offsets: parking_lot::RwLock<std::collections::HashMap<String, [f32; 10]>>,
+    stream_eq_offsets: parking_lot::RwLock<std::collections::HashMap<String, [f32; MAX_BANDS]>>,
 
     /// Combined EQ gains (master + sum of all active stream offsets)
     /// This is what the audio callback actually uses
-    combined_eq_gains: [AtomicU32; 10],
+    combined_eq_gains: [AtomicU32; MAX_BANDS],
 
     /// Counter that increments whenever EQ changes (master or stream)
     /// The audio callback checks this to know when to update its local EQ state
@@ -89,6 +149,10 @@ pub struct AudioProcessingState {
     /// Per-stream volume (stream_id → volume 0.0-2.0)
     stream_volumes: parking_lot::RwLock<std::collections::HashMap<String, f32>>,
 
+    /// Per-stream A/V sync offsets in ms (stream_id → offset, ±MAX_SYNC_OFFSET_MS)
+    /// Negative offsets are applied by delaying every other stream
+    stream_delays: parking_lot::RwLock<std::collections::HashMap<String, f32>>,
+
     /// Per-stream bypass state (stream_id → bypassed)
     stream_bypassed: parking_lot::RwLock<std::collections::HashMap<String, bool>>,
 
@@ -102,6 +166,79 @@ pub struct AudioProcessingState {
 
     /// Whether soft clipping is enabled
     soft_clip_enabled: AtomicBool,
+
+    /// Final output stage (OutputStage as u8): soft clipper or limiter
+    output_stage: AtomicU8,
+
+    /// Limiter ceiling (dBTP) and release (ms), as f32 bits
+    /// Each mixing callback owns its Limiter and picks these up between buffers
+    limiter_ceiling_bits: AtomicU32,
+    limiter_release_bits: AtomicU32,
+
+    /// Whether headphone crossfeed runs on the master output
+    /// Set per output device by the engine; off for speakers
+    crossfeed_enabled: AtomicBool,
+
+    /// Crossfeed cutoff (Hz) and feed level (dB), as f32 bits
+    crossfeed_cutoff_bits: AtomicU32,
+    crossfeed_feed_bits: AtomicU32,
+
+    /// Sample rate (Hz) negotiated by the mixing stream with the output device
+    /// Capture streams running at another rate resample to it
+    output_rate: AtomicU32,
+
+    /// Loudness (LUFS) of the master output, published by the mixing callback
+    master_loudness: LoudnessMeter,
+
+    /// Per-stream loudness meters (stream_id → meter), published by capture callbacks
+    /// Meters outlive their streams so the UI keeps its readings across rebuilds
+    stream_loudness: parking_lot::RwLock<std::collections::HashMap<String, LoudnessMeter>>,
+
+    /// Per-stream clock drift meters (stream_id → meter), published by capture callbacks
+    stream_drift: parking_lot::RwLock<std::collections::HashMap<String, DriftMeter>>,
+
+    /// Gain reduction meters of the master chain's compressor stages (one per
+    /// band), replaced on each rebuild
+    master_gain_reduction: parking_lot::RwLock<Vec<Vec<CompressorMeter>>>,
+
+    /// Per-stream compressor gain reduction meters (stream_id → meters of its chain)
+    stream_gain_reduction:
+        parking_lot::RwLock<std::collections::HashMap<String, Vec<Vec<CompressorMeter>>>>,
+
+    /// Auto-leveling target, max boost, max cut and speed, as f32 bits
+    /// Shared by every leveled stream; callbacks pick these up between buffers
+    auto_level_bits: [AtomicU32; 4],
+
+    /// Streams with auto-leveling enabled
+    stream_auto_level: parking_lot::RwLock<std::collections::HashSet<String>>,
+
+    /// Processor chain run by the mixing stream after mixing
+    master_chain: parking_lot::RwLock<ChainConfig>,
+
+    /// Per-stream processor chains (stream_id → chain); others use the app default
+    stream_chains: parking_lot::RwLock<std::collections::HashMap<String, ChainConfig>>,
+
+    /// Whether the master EQ runs linear phase (read when its chain is built)
+    master_linear_phase: AtomicBool,
+
+    /// Streams whose EQ runs linear phase; others run minimum phase
+    stream_linear_phase: parking_lot::RwLock<std::collections::HashSet<String>>,
+
+    /// Impulse response for the master chain's convolver (`None` skips it)
+    master_ir: parking_lot::RwLock<Option<ImpulseResponse>>,
+
+    /// Per-stream impulse responses (stream_id → IR); others skip their convolver
+    stream_irs: parking_lot::RwLock<std::collections::HashMap<String, ImpulseResponse>>,
+}
+
+/// Auto-level settings as f32 bits, in field order
+fn auto_level_bits(settings: AutoLevelSettings) -> [u32; 4] {
+    [
+        settings.target_lufs.to_bits(),
+        settings.max_boost_db.to_bits(),
+        settings.max_cut_db.to_bits(),
+        settings.speed_db_per_s.to_bits(),
+    ]
 }
 
 impl AudioProcessingState {
@@ -109,6 +246,9 @@ impl AudioProcessingState {
         // Rust pattern: Initialize array of atomics with default 0dB gain
         let master_eq_gains = core::array::from_fn(|_| AtomicU32::new(0.0_f32.to_bits()));
         let combined_eq_gains = core::array::from_fn(|_| AtomicU32::new(0.0_f32.to_bits()));
+        let master_band_params = band_layout_params(EQ_BANDS.len()).map(AtomicBandParams::new);
+        let secondary_eq_gains = core::array::from_fn(|_| AtomicU32::new(0.0_f32.to_bits()));
+        let secondary_band_params = band_layout_params(EQ_BANDS.len()).map(AtomicBandParams::new);
 
         Self {
             bypassed: AtomicBool::new(false),
@@ -116,19 +256,55 @@ impl AudioProcessingState {
             peak_left_bits: AtomicU32::new(0.0_f32.to_bits()),
             peak_right_bits: AtomicU32::new(0.0_f32.to_bits()),
             running: AtomicBool::new(false),
+            channels: AtomicUsize::new(2),
+            band_count: AtomicUsize::new(EQ_BANDS.len()),
             master_eq_gains,
+            master_band_params,
+            eq_channel_mode: AtomicU8::new(EqChannelMode::Linked as u8),
+            secondary_eq_gains,
+            secondary_band_params,
+            stereo_width_bits: AtomicU32::new(1.0_f32.to_bits()),
+            auto_preamp: AtomicBool::new(false),
+            manual_preamp_bits: AtomicU32::new(0.0_f32.to_bits()),
+            master_preamp_bits: AtomicU32::new(0.0_f32.to_bits()),
+            secondary_preamp_bits: AtomicU32::new(0.0_f32.to_bits()),
+            stream_preamps: parking_lot::RwLock::new(std::collections::HashMap::new()),
+            eq_error: AtomicBool::new(false),
+            stream_band_params: parking_lot::RwLock::new(std::collections::HashMap::new()),
             stream_eq_offsets: parking_lot::RwLock::new(std::collections::HashMap::new()),
             combined_eq_gains,
             eq_update_counter: AtomicU32::new(0),
             captured_apps: parking_lot::RwLock::new(std::collections::HashSet::new()),
             captured_apps_version: AtomicU32::new(0),
             stream_volumes: parking_lot::RwLock::new(std::collections::HashMap::new()),
+            stream_delays: parking_lot::RwLock::new(std::collections::HashMap::new()),
             stream_bypassed: parking_lot::RwLock::new(std::collections::HashMap::new()),
-            // FFT spectrum analyzer: 48kHz sample rate, ~60fps updates for smoother visuals
+            // FFT spectrum analyzer: ~60fps updates for smoother visuals
+            // Starts at 48kHz; set_output_rate() follows the negotiated rate
             spectrum_analyzer: SpectrumAnalyzer::new(48000.0, 60),
             // Soft clipper: -3dB threshold (starts limiting at ~0.71)
             soft_clipper: SoftClipper::new(-3.0),
             soft_clip_enabled: AtomicBool::new(true),
+            output_stage: AtomicU8::new(OutputStage::SoftClip as u8),
+            limiter_ceiling_bits: AtomicU32::new(LimiterSettings::default().ceiling_db.to_bits()),
+            crossfeed_enabled: AtomicBool::new(false),
+            crossfeed_cutoff_bits: AtomicU32::new(CrossfeedSettings::default().cutoff_hz.to_bits()),
+            crossfeed_feed_bits: AtomicU32::new(CrossfeedSettings::default().feed_db.to_bits()),
+            limiter_release_bits: AtomicU32::new(LimiterSettings::default().release_ms.to_bits()),
+            output_rate: AtomicU32::new(AudioFormat::default().sample_rate),
+            master_loudness: LoudnessMeter::default(),
+            stream_loudness: parking_lot::RwLock::new(std::collections::HashMap::new()),
+            stream_drift: parking_lot::RwLock::new(std::collections::HashMap::new()),
+            master_gain_reduction: parking_lot::RwLock::new(Vec::new()),
+            stream_gain_reduction: parking_lot::RwLock::new(std::collections::HashMap::new()),
+            auto_level_bits: auto_level_bits(AutoLevelSettings::default()).map(AtomicU32::new),
+            stream_auto_level: parking_lot::RwLock::new(std::collections::HashSet::new()),
+            master_chain: parking_lot::RwLock::new(ChainConfig::master_default()),
+            stream_chains: parking_lot::RwLock::new(std::collections::HashMap::new()),
+            master_linear_phase: AtomicBool::new(false),
+            stream_linear_phase: parking_lot::RwLock::new(std::collections::HashSet::new()),
+            master_ir: parking_lot::RwLock::new(None),
+            stream_irs: parking_lot::RwLock::new(std::collections::HashMap::new()),
         }
     }
 
@@ -152,19 +328,156 @@ impl AudioProcessingState {
         )
     }
 
+    /// Interleaved channel count used for new sinks and streams
+    pub fn channel_count(&self) -> usize {
+        self.channels.load(Ordering::Relaxed)
+    }
+
+    /// Set the channel count for sinks and streams created from now on
+    ///
+    /// Must be a layout with standard speaker positions (1 to MAX_CHANNELS,
+    /// e.g. 2 for stereo, 6 for 5.1, 8 for 7.1). Returns false otherwise.
+    pub fn set_channel_count(&self, channels: usize) -> bool {
+        if channels > MAX_CHANNELS || crate::channel_positions(channels as u32).is_none() {
+            return false;
+        }
+        self.channels.store(channels, Ordering::Relaxed);
+        true
+    }
+
+    /// Update peak meters and the spectrum analyzer from an interleaved buffer
+    ///
+    /// Multichannel audio is folded onto the stereo meters: left-side speakers
+    /// (FL, RL, SL) feed the left meter, right-side ones the right meter, and
+    /// center/LFE/mono feed both.
+    ///
+    /// # Real-time Safety
+    /// No allocations, safe to call from audio callbacks.
+    pub fn update_meters(&self, samples: &[f32], channels: usize) {
+        let positions = crate::channel_positions(channels as u32).unwrap_or(&["FL", "FR"]);
+        let mut peak_l = 0.0_f32;
+        let mut peak_r = 0.0_f32;
+
+        for frame in samples.chunks_exact(channels.max(1)) {
+            let mut left = 0.0_f32;
+            let mut right = 0.0_f32;
+            for (&sample, position) in frame.iter().zip(positions) {
+                match position.chars().last() {
+                    Some('L') => {
+                        peak_l = peak_l.max(sample.abs());
+                        left += sample;
+                    }
+                    Some('R') => {
+                        peak_r = peak_r.max(sample.abs());
+                        right += sample;
+                    }
+                    _ => {
+                        peak_l = peak_l.max(sample.abs());
+                        peak_r = peak_r.max(sample.abs());
+                        left += sample * std::f32::consts::FRAC_1_SQRT_2;
+                        right += sample * std::f32::consts::FRAC_1_SQRT_2;
+                    }
+                }
+            }
+            // Push samples to spectrum analyzer for FFT visualization
+            self.spectrum_analyzer.push_sample(left, right);
+        }
+
+        self.set_peaks(peak_l, peak_r);
+    }
+
+    /// Number of active EQ bands
+    pub fn band_count(&self) -> usize {
+        self.band_count.load(Ordering::Acquire)
+    }
+
+    /// Change the number of EQ bands (1 to MAX_BANDS)
+    ///
+    /// All master gains and stream offsets are reset to flat and band shapes
+    /// to the standard layout; the caller re-applies them for the new layout.
+    /// Returns false if the count is invalid.
+    pub fn set_band_count(&self, band_count: usize) -> bool {
+        if band_count == 0 || band_count > MAX_BANDS {
+            return false;
+        }
+
+        for gain in &self.master_eq_gains {
+            gain.store(0.0_f32.to_bits(), Ordering::Relaxed);
+        }
+        for (slot, params) in self.master_band_params.iter().zip(band_layout_params(band_count)) {
+            slot.store(params);
+        }
+        for gain in &self.secondary_eq_gains {
+            gain.store(0.0_f32.to_bits(), Ordering::Relaxed);
+        }
+        let secondary_params = self.secondary_band_params.iter();
+        for (slot, params) in secondary_params.zip(band_layout_params(band_count)) {
+            slot.store(params);
+        }
+        self.stream_eq_offsets.write().clear();
+        self.stream_band_params.write().clear();
+        self.band_count.store(band_count, Ordering::Release);
+        self.recalculate_combined_eq();
+        true
+    }
+
     /// Set master EQ band gain and recalculate combined
     pub fn set_eq_band_gain(&self, band: usize, gain_db: f32) {
-        if band < 10 {
+        if band < self.band_count() {
             self.master_eq_gains[band].store(gain_db.to_bits(), Ordering::Relaxed);
             self.recalculate_combined_eq();
         }
     }
 
+    /// Set master EQ band shape (frequency, Q, type, enabled)
+    ///
+    /// Invalid shapes are ignored; callers validate first to report errors.
+    pub fn set_eq_band_params(&self, band: usize, params: BandParams) {
+        if band < self.band_count() && params.validate().is_ok() {
+            self.master_band_params[band].store(params);
+            self.refresh_preamps();
+            // Same counter as gains: the callback re-syncs its local EQ
+            self.eq_update_counter.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    /// Get master EQ band shape
+    pub fn get_eq_band_params(&self, band: usize) -> Option<BandParams> {
+        if band < self.band_count() {
+            Some(self.master_band_params[band].load())
+        } else {
+            None
+        }
+    }
+
+    /// Set a stream's band shape (picked up when its capture is created)
+    pub fn set_stream_band_params(&self, stream_id: &str, band: usize, params: BandParams) {
+        let band_count = self.band_count();
+        if band < band_count && params.validate().is_ok() {
+            let mut shapes = self.stream_band_params.write();
+            let entry = shapes
+                .entry(stream_id.to_string())
+                .or_insert_with(|| band_layout_params(band_count));
+            entry[band] = params;
+        }
+    }
+
+    /// Get all band shapes for a stream (standard layout if never edited)
+    ///
+    /// Only the first `band_count()` entries are meaningful.
+    pub fn get_stream_band_params_all(&self, stream_id: &str) -> [BandParams; MAX_BANDS] {
+        let shapes = self.stream_band_params.read();
+        shapes
+            .get(stream_id)
+            .copied()
+            .unwrap_or_else(|| band_layout_params(self.band_count()))
+    }
+
     /// Set per-stream EQ offset and recalculate combined
     pub fn set_stream_eq_offset(&self, stream_id: &str, band: usize, offset_db: f32) {
-        if band < 10 {
+        if band < self.band_count() {
             let mut offsets = self.stream_eq_offsets.write();
-            let entry = offsets.entry(stream_id.to_string()).or_insert([0.0; 10]);
+            let entry = offsets.entry(stream_id.to_string()).or_insert([0.0; MAX_BANDS]);
             entry[band] = offset_db;
             drop(offsets); // Release lock before recalculating
             self.recalculate_combined_eq();
@@ -172,9 +485,15 @@ impl AudioProcessingState {
     }
 
     /// Set all bands for a stream at once
-    pub fn set_stream_eq_all(&self, stream_id: &str, gains: [f32; 10]) {
+    ///
+    /// Extra values beyond the band count are ignored; missing bands are flat.
+    pub fn set_stream_eq_all(&self, stream_id: &str, gains: &[f32]) {
+        let mut padded = [0.0; MAX_BANDS];
+        for (dst, src) in padded.iter_mut().zip(gains.iter().take(self.band_count())) {
+            *dst = *src;
+        }
         let mut offsets = self.stream_eq_offsets.write();
-        offsets.insert(stream_id.to_string(), gains);
+        offsets.insert(stream_id.to_string(), padded);
         drop(offsets);
         self.recalculate_combined_eq();
     }
@@ -190,7 +509,7 @@ impl AudioProcessingState {
 
     /// Get master EQ band gain
     pub fn get_eq_band_gain(&self, band: usize) -> f32 {
-        if band < 10 {
+        if band < self.band_count() {
             f32::from_bits(self.master_eq_gains[band].load(Ordering::Relaxed))
         } else {
             0.0
@@ -199,7 +518,7 @@ impl AudioProcessingState {
 
     /// Get stream EQ offset for a specific band
     pub fn get_stream_eq_offset(&self, stream_id: &str, band: usize) -> f32 {
-        if band < 10 {
+        if band < self.band_count() {
             let offsets = self.stream_eq_offsets.read();
             offsets.get(stream_id).map(|o| o[band]).unwrap_or(0.0)
         } else {
@@ -208,21 +527,279 @@ impl AudioProcessingState {
     }
 
     /// Get all stream EQ offsets for a stream
-    pub fn get_stream_eq_all(&self, stream_id: &str) -> [f32; 10] {
+    ///
+    /// Only the first `band_count()` entries are meaningful.
+    pub fn get_stream_eq_all(&self, stream_id: &str) -> [f32; MAX_BANDS] {
         let offsets = self.stream_eq_offsets.read();
-        offsets.get(stream_id).copied().unwrap_or([0.0; 10])
+        offsets.get(stream_id).copied().unwrap_or([0.0; MAX_BANDS])
     }
 
     /// Get all master EQ band gains as an array
-    pub fn get_all_eq_gains(&self) -> [f32; 10] {
+    ///
+    /// Returns a fixed-size array (no allocation, safe in audio callbacks).
+    /// Only the first `band_count()` entries are meaningful.
+    pub fn get_all_eq_gains(&self) -> [f32; MAX_BANDS] {
         core::array::from_fn(|i| f32::from_bits(self.combined_eq_gains[i].load(Ordering::Relaxed)))
     }
 
+    /// Sync an audio callback's local equalizer with the master band shapes
+    /// and combined EQ gains
+    ///
+    /// Switches the equalizer to the current band layout first if the band
+    /// count changed, then applies every active band's shape and gain.
+    ///
+    /// # Real-time Safety
+    /// No allocations: reads atomics into a stack array and updates
+    /// the equalizer's preallocated filter state in place. Rejected updates
+    /// only set the EQ error flag (see `take_eq_error`).
+    pub fn apply_eq(&self, equalizer: &mut Equalizer) {
+        let band_count = self.band_count();
+        if equalizer.band_count() != band_count && equalizer.set_band_count(band_count).is_err() {
+            self.flag_eq_error();
+            return;
+        }
+
+        for (band, slot) in self.master_band_params.iter().enumerate().take(band_count) {
+            let params = slot.load();
+            // Only rebuild coefficients for shapes that actually changed
+            if equalizer.config().bands[band].params() != params
+                && equalizer.set_band_params(band, params).is_err()
+            {
+                self.flag_eq_error();
+            }
+        }
+
+        let gains = self.get_all_eq_gains();
+        for (band, &gain_db) in gains.iter().enumerate().take(band_count) {
+            if equalizer.set_band_gain(band, gain_db).is_err() {
+                self.flag_eq_error();
+            }
+        }
+        equalizer.set_master_gain_db(self.master_preamp_db());
+    }
+
+    // === Auto Preamp ===
+
+    /// Enable or disable automatic preamp for the master and per-app EQs
+    ///
+    /// Only the master preamps are refreshed here; per-app controls are
+    /// refreshed by the PipeWire thread (see `AppControls::refresh_preamp`).
+    pub fn set_auto_preamp(&self, enabled: bool) {
+        self.auto_preamp.store(enabled, Ordering::Relaxed);
+        self.refresh_preamps();
+        self.eq_update_counter.fetch_add(1, Ordering::Release);
+    }
+
+    /// Whether automatic preamp is enabled
+    pub fn auto_preamp(&self) -> bool {
+        self.auto_preamp.load(Ordering::Relaxed)
+    }
+
+    /// Set the master preamp (dB) applied while auto preamp is off
+    pub fn set_manual_preamp(&self, preamp_db: f32) {
+        self.manual_preamp_bits.store(preamp_db.to_bits(), Ordering::Relaxed);
+        self.refresh_preamps();
+        self.eq_update_counter.fetch_add(1, Ordering::Release);
+    }
+
+    /// Master preamp (dB) set by the user
+    pub fn manual_preamp(&self) -> f32 {
+        f32::from_bits(self.manual_preamp_bits.load(Ordering::Relaxed))
+    }
+
+    /// Preamp (dB) the master EQ applies: the auto preamp of the combined
+    /// gains, or the manual preamp while auto preamp is off
+    pub fn master_preamp_db(&self) -> f32 {
+        f32::from_bits(self.master_preamp_bits.load(Ordering::Relaxed))
+    }
+
+    /// Auto preamp (dB) for an EQ with these band shapes and gains, at the
+    /// output rate; 0 dB while auto preamp is off
+    ///
+    /// Runs the peak search, so call it where EQ settings change, never
+    /// from an audio callback.
+    pub fn preamp_for(&self, params: &[BandParams], gains: &[f32]) -> f32 {
+        if !self.auto_preamp() {
+            return 0.0;
+        }
+        let Ok(mut config) = EqConfig::with_band_count(params.len()) else {
+            return 0.0;
+        };
+        for (band, (&params, &gain_db)) in params.iter().zip(gains).enumerate() {
+            let _ = config.set_band_params(band, params);
+            let _ = config.set_band_gain(band, gain_db);
+        }
+        config.auto_preamp_db(self.output_rate() as f32)
+    }
+
+    /// Record the preamp an app's EQ applies (see `get_stream_preamp`)
+    pub fn set_stream_preamp(&self, stream_id: &str, preamp_db: f32) {
+        self.stream_preamps.write().insert(stream_id.to_string(), preamp_db);
+    }
+
+    /// Preamp (dB) an app's EQ applies; 0 dB for apps without a capture
+    pub fn get_stream_preamp(&self, stream_id: &str) -> f32 {
+        self.stream_preamps.read().get(stream_id).copied().unwrap_or(0.0)
+    }
+
+    /// Recompute the master preamps before the callbacks are signalled
+    fn refresh_preamps(&self) {
+        if !self.auto_preamp() {
+            // One manual preamp for the whole signal, whatever the channel mode
+            let manual_bits = self.manual_preamp_bits.load(Ordering::Relaxed);
+            self.master_preamp_bits.store(manual_bits, Ordering::Relaxed);
+            self.secondary_preamp_bits.store(manual_bits, Ordering::Relaxed);
+            return;
+        }
+        let band_count = self.band_count();
+        let load_params = |slots: &[AtomicBandParams]| -> Vec<BandParams> {
+            slots[..band_count].iter().map(AtomicBandParams::load).collect()
+        };
+
+        let gains = &self.get_all_eq_gains()[..band_count];
+        let master_db = self.preamp_for(&load_params(&self.master_band_params), gains);
+        self.master_preamp_bits.store(master_db.to_bits(), Ordering::Relaxed);
+
+        let gains: Vec<f32> = self.secondary_eq_gains[..band_count]
+            .iter()
+            .map(|gain| f32::from_bits(gain.load(Ordering::Relaxed)))
+            .collect();
+        let secondary_db = self.preamp_for(&load_params(&self.secondary_band_params), &gains);
+        self.secondary_preamp_bits.store(secondary_db.to_bits(), Ordering::Relaxed);
+    }
+
+    // === EQ Errors ===
+
+    /// Record that an audio thread could not apply an EQ update
+    ///
+    /// # Real-time Safety
+    /// A single atomic store; the error is logged later by `take_eq_error`'s caller.
+    pub fn flag_eq_error(&self) {
+        self.eq_error.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether an EQ update failed since the last call, clearing the flag
+    pub fn take_eq_error(&self) -> bool {
+        self.eq_error.swap(false, Ordering::Relaxed)
+    }
+
+    // === Master EQ Channel Mode ===
+
+    /// Set how the master EQ treats the front left/right pair
+    pub fn set_eq_channel_mode(&self, mode: EqChannelMode) {
+        self.eq_channel_mode.store(mode as u8, Ordering::Relaxed);
+        self.eq_update_counter.fetch_add(1, Ordering::Release);
+    }
+
+    /// Current master EQ channel mode
+    pub fn eq_channel_mode(&self) -> EqChannelMode {
+        match self.eq_channel_mode.load(Ordering::Relaxed) {
+            m if m == EqChannelMode::LeftRight as u8 => EqChannelMode::LeftRight,
+            m if m == EqChannelMode::MidSide as u8 => EqChannelMode::MidSide,
+            _ => EqChannelMode::Linked,
+        }
+    }
+
+    /// Set master stereo width (0.0 to MAX_STEREO_WIDTH, 1.0 = unchanged)
+    pub fn set_stereo_width(&self, width: f32) -> Result<(), gecko_dsp::DspError> {
+        if !(0.0..=MAX_STEREO_WIDTH).contains(&width) {
+            return Err(gecko_dsp::DspError::InvalidParameter {
+                name: "stereo_width",
+                value: width,
+            });
+        }
+        self.stereo_width_bits.store(width.to_bits(), Ordering::Relaxed);
+        self.eq_update_counter.fetch_add(1, Ordering::Release);
+        Ok(())
+    }
+
+    /// Current master stereo width
+    pub fn stereo_width(&self) -> f32 {
+        f32::from_bits(self.stereo_width_bits.load(Ordering::Relaxed))
+    }
+
+    /// Set secondary (right or side) master EQ band gain
+    pub fn set_secondary_eq_band_gain(&self, band: usize, gain_db: f32) {
+        if band < self.band_count() {
+            let gain_db = gain_db.clamp(-24.0, 24.0);
+            self.secondary_eq_gains[band].store(gain_db.to_bits(), Ordering::Relaxed);
+            self.refresh_preamps();
+            self.eq_update_counter.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    /// Get secondary master EQ band gain
+    pub fn get_secondary_eq_band_gain(&self, band: usize) -> f32 {
+        if band < MAX_BANDS {
+            f32::from_bits(self.secondary_eq_gains[band].load(Ordering::Relaxed))
+        } else {
+            0.0
+        }
+    }
+
+    /// Set secondary (right or side) master EQ band shape
+    ///
+    /// Invalid shapes are ignored; callers validate first to report errors.
+    pub fn set_secondary_eq_band_params(&self, band: usize, params: BandParams) {
+        if band < self.band_count() && params.validate().is_ok() {
+            self.secondary_band_params[band].store(params);
+            self.refresh_preamps();
+            self.eq_update_counter.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    /// Get secondary master EQ band shape
+    pub fn get_secondary_eq_band_params(&self, band: usize) -> Option<BandParams> {
+        if band < self.band_count() {
+            Some(self.secondary_band_params[band].load())
+        } else {
+            None
+        }
+    }
+
+    /// Apply the full master EQ state to a stereo equalizer
+    ///
+    /// The primary config gets the same gains and shapes as `apply_eq`; the
+    /// secondary config, channel mode and width come from their own atomics.
+    ///
+    /// # Real-time Safety
+    /// No allocations, same as `apply_eq`.
+    pub fn apply_master_eq(&self, equalizer: &mut StereoEqualizer) {
+        self.apply_eq(equalizer.primary_mut());
+
+        let band_count = self.band_count();
+        let secondary = equalizer.secondary_mut();
+        if secondary.band_count() != band_count && secondary.set_band_count(band_count).is_err() {
+            self.flag_eq_error();
+            return;
+        }
+        for (band, slot) in self.secondary_band_params.iter().enumerate().take(band_count) {
+            let params = slot.load();
+            if secondary.config().bands[band].params() != params
+                && secondary.set_band_params(band, params).is_err()
+            {
+                self.flag_eq_error();
+            }
+        }
+        for (band, gain) in self.secondary_eq_gains.iter().enumerate().take(band_count) {
+            let gain_db = f32::from_bits(gain.load(Ordering::Relaxed));
+            if secondary.set_band_gain(band, gain_db).is_err() {
+                self.flag_eq_error();
+            }
+        }
+
+        let preamp_db = f32::from_bits(self.secondary_preamp_bits.load(Ordering::Relaxed));
+        equalizer.secondary_mut().set_master_gain_db(preamp_db);
+
+        equalizer.set_mode(self.eq_channel_mode());
+        let _ = equalizer.set_width(self.stereo_width());
+    }
+
     /// Recalculate combined EQ from master + all stream offsets
     fn recalculate_combined_eq(&self) {
         let offsets = self.stream_eq_offsets.read();
         
-        for band in 0..10 {
+        for band in 0..MAX_BANDS {
             let master = f32::from_bits(self.master_eq_gains[band].load(Ordering::Relaxed));
             
             // Sum all stream offsets for this band
@@ -232,7 +809,9 @@ impl AudioProcessingState {
             let combined = (master + total_offset).clamp(-24.0, 24.0);
             self.combined_eq_gains[band].store(combined.to_bits(), Ordering::Relaxed);
         }
-        
+        drop(offsets);
+        self.refresh_preamps();
+
         // Increment counter to signal change to the audio callback
         self.eq_update_counter.fetch_add(1, Ordering::Release);
     }
@@ -290,6 +869,58 @@ impl AudioProcessingState {
         volumes.get(stream_id).copied().unwrap_or(1.0)
     }
 
+    // === Per-Stream Delay ===
+
+    /// Set the A/V sync offset (ms) for a stream, clamped to ±MAX_SYNC_OFFSET_MS
+    ///
+    /// Positive offsets delay the stream; negative ones delay every other
+    /// stream instead (see [`stream_delays_ms`](Self::stream_delays_ms)).
+    pub fn set_stream_delay(&self, stream_id: &str, offset_ms: f32) {
+        let offset_ms = offset_ms.clamp(-MAX_SYNC_OFFSET_MS, MAX_SYNC_OFFSET_MS);
+        let mut delays = self.stream_delays.write();
+        if offset_ms == 0.0 {
+            delays.remove(stream_id);
+        } else {
+            delays.insert(stream_id.to_string(), offset_ms);
+        }
+    }
+
+    /// Get the A/V sync offset (ms) for a stream (defaults to 0)
+    pub fn get_stream_delay(&self, stream_id: &str) -> f32 {
+        self.stream_delays
+            .read()
+            .get(stream_id)
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// Delay (ms) to apply to each of `stream_ids`, in order
+    ///
+    /// Every stream is shifted by the most negative offset among them, so a
+    /// stream set to play early is left undelayed while the others wait.
+    pub fn stream_delays_ms(&self, stream_ids: &[&str]) -> Vec<f32> {
+        let offsets: Vec<f32> = stream_ids
+            .iter()
+            .map(|id| self.get_stream_delay(id))
+            .collect();
+        let base = offsets.iter().copied().fold(0.0_f32, f32::min);
+        offsets.into_iter().map(|offset| offset - base).collect()
+    }
+
+    /// Build a delay line for the current channel layout
+    ///
+    /// Holds up to twice MAX_SYNC_OFFSET_MS, with room to follow the stream
+    /// up to MAX_STREAM_RATE without reallocating. Allocates; call when
+    /// creating a stream, not in the process callback.
+    pub fn new_stream_delay(&self, sample_rate: f32) -> Delay {
+        let channels = self.channel_count().clamp(1, MAX_CHANNELS);
+        let max_rate = sample_rate.max(MAX_STREAM_RATE);
+        let mut delay = Delay::new(max_rate, channels, 2.0 * MAX_SYNC_OFFSET_MS)
+            .expect("negotiated sample rates are positive");
+        let _ = delay.set_sample_rate(sample_rate);
+        delay
+    }
+
     // === Per-Stream Bypass ===
 
     /// Set bypass for a specific stream
@@ -387,6 +1018,508 @@ impl AudioProcessingState {
     pub fn set_soft_clip_threshold(&self, threshold_db: f32) {
         self.soft_clipper.set_threshold_db(threshold_db);
     }
+
+    // === Output Stage ===
+
+    /// Choose the final stage: soft clipper or true-peak limiter
+    pub fn set_output_stage(&self, stage: OutputStage) {
+        self.output_stage.store(stage as u8, Ordering::Relaxed);
+    }
+
+    /// Current final stage
+    pub fn output_stage(&self) -> OutputStage {
+        if self.output_stage.load(Ordering::Relaxed) == OutputStage::Limiter as u8 {
+            OutputStage::Limiter
+        } else {
+            OutputStage::SoftClip
+        }
+    }
+
+    /// Set the limiter ceiling and release (validated; `enabled` is ignored)
+    pub fn set_limiter_settings(
+        &self,
+        settings: LimiterSettings,
+    ) -> Result<(), gecko_dsp::DspError> {
+        settings.validate()?;
+        self.limiter_ceiling_bits.store(settings.ceiling_db.to_bits(), Ordering::Relaxed);
+        self.limiter_release_bits.store(settings.release_ms.to_bits(), Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Current limiter settings
+    pub fn limiter_settings(&self) -> LimiterSettings {
+        LimiterSettings {
+            ceiling_db: f32::from_bits(self.limiter_ceiling_bits.load(Ordering::Relaxed)),
+            release_ms: f32::from_bits(self.limiter_release_bits.load(Ordering::Relaxed)),
+            enabled: true,
+        }
+    }
+
+    /// Latency (samples) the master chain adds at `sample_rate`
+    ///
+    /// From the linear-phase EQ, the convolver and the limiter, when they are
+    /// in the chain.
+    pub fn output_latency_samples(&self, sample_rate: f32) -> usize {
+        let chain = self.master_chain();
+        self.chain_latency_samples(&chain, None, sample_rate)
+    }
+
+    /// Latency (samples) a stream's chain adds at `sample_rate`
+    pub fn stream_latency_samples(&self, stream_id: &str, sample_rate: f32) -> usize {
+        self.chain_latency_samples(&self.stream_chain(stream_id), Some(stream_id), sample_rate)
+    }
+
+    /// Sum the latency of the active stages of the master's or a stream's `chain`
+    fn chain_latency_samples(
+        &self,
+        chain: &ChainConfig,
+        stream_id: Option<&str>,
+        sample_rate: f32,
+    ) -> usize {
+        let phase_mode = match stream_id {
+            Some(id) => self.stream_phase_mode(id),
+            None => self.master_phase_mode(),
+        };
+        let stage_latency = |kind: &ProcessorKind| match kind {
+            ProcessorKind::Equalizer if phase_mode == PhaseMode::Linear => {
+                Equalizer::linear_phase_latency_at(sample_rate)
+            }
+            ProcessorKind::OutputStage if self.output_stage() == OutputStage::Limiter => {
+                Limiter::latency_at(sample_rate)
+            }
+            ProcessorKind::Convolver if self.has_impulse_response(stream_id) => IR_BLOCK,
+            _ => 0,
+        };
+        chain
+            .processors
+            .iter()
+            .filter(|slot| !slot.bypassed)
+            .map(|slot| stage_latency(&slot.kind))
+            .sum()
+    }
+
+    /// Run the selected final stage over an interleaved buffer
+    ///
+    /// `limiter` is owned by the calling callback; it picks up settings
+    /// changes here. The soft clipper keeps its own enable switch.
+    ///
+    /// # Real-time Safety
+    /// No allocations, no locks.
+    #[inline]
+    pub fn process_output_stage(&self, limiter: &mut Limiter, buffer: &mut [f32]) {
+        match self.output_stage() {
+            OutputStage::Limiter => {
+                let settings = self.limiter_settings();
+                if *limiter.settings() != settings {
+                    let _ = limiter.set_settings(settings);
+                }
+                limiter.process_interleaved(buffer);
+            }
+            OutputStage::SoftClip => self.soft_clip_buffer(buffer),
+        }
+    }
+
+    // === Crossfeed ===
+
+    /// Turn master crossfeed on with `settings`, or off with `None`
+    pub fn set_crossfeed(
+        &self,
+        settings: Option<CrossfeedSettings>,
+    ) -> Result<(), gecko_dsp::DspError> {
+        if let Some(settings) = settings {
+            settings.validate()?;
+            self.crossfeed_cutoff_bits.store(settings.cutoff_hz.to_bits(), Ordering::Relaxed);
+            self.crossfeed_feed_bits.store(settings.feed_db.to_bits(), Ordering::Relaxed);
+        }
+        self.crossfeed_enabled.store(settings.is_some(), Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Current crossfeed settings, or `None` when crossfeed is off
+    pub fn crossfeed(&self) -> Option<CrossfeedSettings> {
+        self.crossfeed_enabled
+            .load(Ordering::Relaxed)
+            .then(|| self.crossfeed_settings())
+    }
+
+    fn crossfeed_settings(&self) -> CrossfeedSettings {
+        CrossfeedSettings {
+            cutoff_hz: f32::from_bits(self.crossfeed_cutoff_bits.load(Ordering::Relaxed)),
+            feed_db: f32::from_bits(self.crossfeed_feed_bits.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Build a crossfeed for the mixing callback (disabled until processed)
+    pub fn new_crossfeed(&self, sample_rate: f32) -> Crossfeed {
+        let mut crossfeed = Crossfeed::new(sample_rate, self.crossfeed_settings())
+            .expect("crossfeed settings are validated on store");
+        let _ = crossfeed.set_channel_count(self.channel_count());
+        crossfeed
+    }
+
+    /// Crossfeed an interleaved master buffer with the callback-owned processor
+    ///
+    /// Picks up settings changes and the on/off switch between buffers.
+    ///
+    /// # Real-time Safety
+    /// No allocations, no locks.
+    #[inline]
+    pub fn process_crossfeed(&self, crossfeed: &mut Crossfeed, buffer: &mut [f32]) {
+        let enabled = self.crossfeed_enabled.load(Ordering::Relaxed);
+        if enabled {
+            let settings = self.crossfeed_settings();
+            if *crossfeed.settings() != settings {
+                let _ = crossfeed.set_settings(settings);
+            }
+        }
+        crossfeed.set_enabled(enabled);
+        crossfeed.process_interleaved(buffer);
+    }
+
+    // === Sample Rate ===
+
+    /// Record the rate the mixing stream negotiated with the output device
+    pub fn set_output_rate(&self, rate: u32) {
+        let previous = self.output_rate.swap(rate, Ordering::Relaxed);
+        self.spectrum_analyzer.set_sample_rate(rate as f32);
+        // The response peak near Nyquist moves with the rate
+        if previous != rate && self.auto_preamp() {
+            self.refresh_preamps();
+            self.eq_update_counter.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    /// Sample rate (Hz) of the output device (48 kHz until negotiated)
+    pub fn output_rate(&self) -> u32 {
+        self.output_rate.load(Ordering::Relaxed)
+    }
+
+    // === Loudness ===
+
+    /// Build a loudness analyzer for the current channel layout
+    ///
+    /// Publishes to the master meter, or to `stream_id`'s meter (created on
+    /// first use). Allocates; call when creating a stream or on a format
+    /// change, not in the process callback.
+    pub fn new_loudness_analyzer(
+        &self,
+        stream_id: Option<&str>,
+        sample_rate: f32,
+    ) -> LoudnessAnalyzer {
+        let meter = match stream_id {
+            Some(id) => self
+                .stream_loudness
+                .write()
+                .entry(id.to_string())
+                .or_default()
+                .clone(),
+            None => self.master_loudness.clone(),
+        };
+        let mut analyzer = LoudnessAnalyzer::with_meter(sample_rate, meter)
+            .expect("negotiated sample rates are positive");
+        let (weights, channels) = self.loudness_weights();
+        let _ = analyzer.set_channel_weights(&weights[..channels]);
+        analyzer
+    }
+
+    /// BS.1770 weights for the current channel layout, and the channel count
+    fn loudness_weights(&self) -> ([f32; MAX_CHANNELS], usize) {
+        let channels = self.channel_count().clamp(1, MAX_CHANNELS);
+        let positions = crate::channel_positions(channels as u32).unwrap_or(&["FL", "FR"]);
+        let mut weights = [1.0_f32; MAX_CHANNELS];
+        for (weight, position) in weights.iter_mut().zip(positions) {
+            *weight = loudness_channel_weight(position);
+        }
+        (weights, channels)
+    }
+
+    /// Loudness of the master output
+    pub fn master_loudness(&self) -> LoudnessStats {
+        self.master_loudness.stats()
+    }
+
+    /// Loudness of each currently captured stream
+    pub fn stream_loudness(&self) -> Vec<(String, LoudnessStats)> {
+        let captured = self.captured_apps.read();
+        let meters = self.stream_loudness.read();
+        captured
+            .iter()
+            .filter_map(|app| meters.get(app).map(|meter| (app.clone(), meter.stats())))
+            .collect()
+    }
+
+    /// Restart integrated loudness, range and true peak
+    ///
+    /// Applies to one stream, or to the master and every stream when `None`.
+    pub fn reset_loudness(&self, stream_id: Option<&str>) {
+        let meters = self.stream_loudness.read();
+        match stream_id {
+            Some(id) => {
+                if let Some(meter) = meters.get(id) {
+                    meter.request_reset();
+                }
+            }
+            None => {
+                self.master_loudness.request_reset();
+                meters.values().for_each(LoudnessMeter::request_reset);
+            }
+        }
+    }
+
+    // === Clock Drift ===
+
+    /// Drift meter for `stream_id`, created on first use
+    ///
+    /// Allocates; call when creating a stream, not in the process callback.
+    pub fn drift_meter(&self, stream_id: &str) -> DriftMeter {
+        self.stream_drift
+            .write()
+            .entry(stream_id.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Clock drift compensation of each currently captured stream
+    pub fn stream_drift(&self) -> Vec<(String, DriftStats)> {
+        let captured = self.captured_apps.read();
+        let meters = self.stream_drift.read();
+        captured
+            .iter()
+            .filter_map(|app| meters.get(app).map(|meter| (app.clone(), meter.stats())))
+            .collect()
+    }
+
+    // === Gain Reduction ===
+
+    /// Point the master's (`stream_id: None`) or a stream's gain reduction
+    /// readout at the compressors of a freshly built chain
+    pub fn set_gain_reduction_meters(
+        &self,
+        stream_id: Option<&str>,
+        meters: Vec<Vec<CompressorMeter>>,
+    ) {
+        match stream_id {
+            Some(id) => {
+                self.stream_gain_reduction.write().insert(id.to_string(), meters);
+            }
+            None => *self.master_gain_reduction.write() = meters,
+        }
+    }
+
+    /// Gain reduction in the master chain since the previous call
+    ///
+    /// `None` while the chain has no compressor.
+    pub fn master_gain_reduction(&self) -> Option<GainReduction> {
+        GainReduction::take(&self.master_gain_reduction.read())
+    }
+
+    /// Gain reduction since the previous call of each currently captured
+    /// stream whose chain has a compressor
+    pub fn stream_gain_reduction(&self) -> Vec<(String, GainReduction)> {
+        let captured = self.captured_apps.read();
+        let meters = self.stream_gain_reduction.read();
+        captured
+            .iter()
+            .filter_map(|app| Some((app.clone(), GainReduction::take(meters.get(app)?)?)))
+            .collect()
+    }
+
+    // === Auto Leveling ===
+
+    /// Build a disabled auto-leveler for the current settings and channel layout
+    ///
+    /// Allocates; call when creating a stream or on a format change, not in
+    /// the process callback.
+    pub fn new_auto_leveler(&self, sample_rate: f32) -> AutoLeveler {
+        let mut leveler = AutoLeveler::new(sample_rate, self.auto_level_settings())
+            .expect("auto-level settings are validated on store");
+        let (weights, channels) = self.loudness_weights();
+        let _ = leveler.set_channel_weights(&weights[..channels]);
+        leveler
+    }
+
+    /// Set the shared auto-leveling target, limits and speed
+    pub fn set_auto_level_settings(
+        &self,
+        settings: AutoLevelSettings,
+    ) -> Result<(), gecko_dsp::DspError> {
+        settings.validate()?;
+        for (atomic, bits) in self.auto_level_bits.iter().zip(auto_level_bits(settings)) {
+            atomic.store(bits, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Current shared auto-leveling settings
+    pub fn auto_level_settings(&self) -> AutoLevelSettings {
+        let value = |i: usize| f32::from_bits(self.auto_level_bits[i].load(Ordering::Relaxed));
+        AutoLevelSettings {
+            target_lufs: value(0),
+            max_boost_db: value(1),
+            max_cut_db: value(2),
+            speed_db_per_s: value(3),
+        }
+    }
+
+    /// Enable or disable auto-leveling for a stream (kept for future streams)
+    pub fn set_stream_auto_level(&self, stream_id: &str, enabled: bool) {
+        let mut streams = self.stream_auto_level.write();
+        if enabled {
+            streams.insert(stream_id.to_string());
+        } else {
+            streams.remove(stream_id);
+        }
+    }
+
+    /// Whether a stream is auto-leveled (defaults to false)
+    pub fn is_stream_auto_level(&self, stream_id: &str) -> bool {
+        self.stream_auto_level.read().contains(stream_id)
+    }
+
+    /// Level a stream's interleaved buffer with its callback-owned leveler
+    ///
+    /// Picks up changes to the shared settings and the stream's toggle.
+    ///
+    /// # Real-time Safety
+    /// No allocations, no locks.
+    #[inline]
+    pub fn process_auto_level(
+        &self,
+        leveler: &mut AutoLeveler,
+        enabled: bool,
+        buffer: &mut [f32],
+    ) {
+        let settings = self.auto_level_settings();
+        if *leveler.settings() != settings {
+            let _ = leveler.set_settings(settings);
+        }
+        leveler.set_enabled(enabled);
+        leveler.process_interleaved(buffer);
+    }
+
+    // === Processor Chains ===
+
+    /// Set the master processor chain (validated)
+    ///
+    /// The mixing stream is rebuilt from this on format changes.
+    pub fn set_master_chain(&self, chain: ChainConfig) -> Result<(), gecko_dsp::DspError> {
+        chain.validate(ChainScope::Master)?;
+        *self.master_chain.write() = chain;
+        Ok(())
+    }
+
+    /// Current master processor chain
+    pub fn master_chain(&self) -> ChainConfig {
+        self.master_chain.read().clone()
+    }
+
+    /// Choose how the master EQ realises its response
+    ///
+    /// Takes effect when the master chain is next built.
+    pub fn set_master_phase_mode(&self, mode: PhaseMode) {
+        self.master_linear_phase.store(mode == PhaseMode::Linear, Ordering::Relaxed);
+    }
+
+    /// Phase mode of the master EQ
+    pub fn master_phase_mode(&self) -> PhaseMode {
+        if self.master_linear_phase.load(Ordering::Relaxed) {
+            PhaseMode::Linear
+        } else {
+            PhaseMode::Minimum
+        }
+    }
+
+    /// Choose how a stream's EQ realises its response (kept for future streams)
+    pub fn set_stream_phase_mode(&self, stream_id: &str, mode: PhaseMode) {
+        let mut linear = self.stream_linear_phase.write();
+        match mode {
+            PhaseMode::Linear => linear.insert(stream_id.to_string()),
+            PhaseMode::Minimum => linear.remove(stream_id),
+        };
+    }
+
+    /// Phase mode of a stream's EQ
+    pub fn stream_phase_mode(&self, stream_id: &str) -> PhaseMode {
+        if self.stream_linear_phase.read().contains(stream_id) {
+            PhaseMode::Linear
+        } else {
+            PhaseMode::Minimum
+        }
+    }
+
+    /// Set a stream's processor chain (validated; kept for future streams)
+    pub fn set_stream_chain(
+        &self,
+        stream_id: &str,
+        chain: ChainConfig,
+    ) -> Result<(), gecko_dsp::DspError> {
+        chain.validate(ChainScope::App)?;
+        let mut chains = self.stream_chains.write();
+        if chain == ChainConfig::app_default() {
+            chains.remove(stream_id);
+        } else {
+            chains.insert(stream_id.to_string(), chain);
+        }
+        Ok(())
+    }
+
+    /// A stream's processor chain (defaults to the app default)
+    pub fn stream_chain(&self, stream_id: &str) -> ChainConfig {
+        self.stream_chains
+            .read()
+            .get(stream_id)
+            .cloned()
+            .unwrap_or_else(ChainConfig::app_default)
+    }
+
+    // === Convolution ===
+
+    /// Set (`Some`) or clear (`None`) the master's (`stream_id: None`) or a
+    /// stream's impulse response, used by chains built from now on
+    pub fn set_impulse_response(&self, stream_id: Option<&str>, ir: Option<ImpulseResponse>) {
+        match (stream_id, ir) {
+            (Some(id), Some(ir)) => {
+                self.stream_irs.write().insert(id.to_string(), ir);
+            }
+            (Some(id), None) => {
+                self.stream_irs.write().remove(id);
+            }
+            (None, ir) => *self.master_ir.write() = ir,
+        }
+    }
+
+    /// Whether the master (`stream_id: None`) or a stream has an impulse response
+    pub fn has_impulse_response(&self, stream_id: Option<&str>) -> bool {
+        match stream_id {
+            Some(id) => self.stream_irs.read().contains_key(id),
+            None => self.master_ir.read().is_some(),
+        }
+    }
+
+    /// Build a convolver for the master's or a stream's impulse response
+    ///
+    /// `None` while no IR is loaded. Resamples the IR if it was loaded at
+    /// another rate and allocates; call when building a chain.
+    pub fn new_convolver(
+        &self,
+        stream_id: Option<&str>,
+        sample_rate: f32,
+        channels: usize,
+    ) -> Option<Convolver> {
+        let ir = match stream_id {
+            Some(id) => self.stream_irs.read().get(id).cloned(),
+            None => self.master_ir.read().clone(),
+        }?;
+        let mut convolver = match Convolver::new(&ir, sample_rate) {
+            Ok(convolver) => convolver,
+            Err(e) => {
+                tracing::warn!("Skipping convolver: {:?}", e);
+                return None;
+            }
+        };
+        let _ = convolver.set_channel_count(channels);
+        Some(convolver)
+    }
 }
 
 impl Default for AudioProcessingState {
@@ -523,6 +1656,314 @@ mod tests {
         assert_eq!(state.peaks(), (0.8, 0.6));
     }
 
+    #[test]
+    fn test_output_stage_selection() {
+        let state = AudioProcessingState::new();
+        assert_eq!(state.output_stage(), OutputStage::SoftClip);
+        assert_eq!(state.output_latency_samples(48000.0), 0);
+
+        let settings = LimiterSettings {
+            ceiling_db: -3.0,
+            ..LimiterSettings::default()
+        };
+        state.set_limiter_settings(settings).unwrap();
+        state.set_output_stage(OutputStage::Limiter);
+        assert_eq!(state.limiter_settings(), settings);
+        assert_eq!(state.output_latency_samples(48000.0), Limiter::latency_at(48000.0));
+        assert!(state
+            .set_limiter_settings(LimiterSettings {
+                ceiling_db: 6.0,
+                ..settings
+            })
+            .is_err());
+
+        // Nothing is added once the output stage is bypassed in the chain
+        let mut chain = state.master_chain();
+        let index = chain.position(&ProcessorKind::OutputStage).unwrap();
+        chain.set_bypassed(index, true).unwrap();
+        state.set_master_chain(chain).unwrap();
+        assert_eq!(state.output_latency_samples(48000.0), 0);
+
+        // The callback's limiter picks up the new ceiling
+        let mut limiter = Limiter::new(48000.0, LimiterSettings::default()).unwrap();
+        let mut buffer = vec![1.0_f32; 4096];
+        state.process_output_stage(&mut limiter, &mut buffer);
+        assert_eq!(*limiter.settings(), settings);
+        let ceiling = 10.0_f32.powf(-3.0 / 20.0);
+        assert!(buffer.iter().all(|s| s.abs() <= ceiling + 1e-6));
+    }
+
+    #[test]
+    fn test_processor_chains() {
+        let state = AudioProcessingState::new();
+        assert_eq!(state.master_chain(), ChainConfig::master_default());
+        assert_eq!(state.stream_chain("Firefox"), ChainConfig::app_default());
+
+        let mut chain = ChainConfig::app_default();
+        chain.move_processor(2, 0).unwrap();
+        state.set_stream_chain("Firefox", chain.clone()).unwrap();
+        assert_eq!(state.stream_chain("Firefox"), chain);
+        assert_eq!(state.stream_chain("mpv"), ChainConfig::app_default());
+
+        // Scope-specific stages are refused
+        chain.insert(0, ProcessorKind::OutputStage).unwrap();
+        assert!(state.set_stream_chain("Firefox", chain).is_err());
+        assert!(state.set_master_chain(ChainConfig::app_default()).is_err());
+        assert_eq!(state.master_chain(), ChainConfig::master_default());
+    }
+
+    #[test]
+    fn test_gain_reduction_meters() {
+        let state = AudioProcessingState::new();
+        assert_eq!(state.master_gain_reduction(), None);
+
+        let settings = gecko_dsp::CompressorSettings::default();
+        let master = gecko_dsp::Compressor::new(48000.0, settings).unwrap();
+        state.set_gain_reduction_meters(None, vec![vec![master.meter()]]);
+        assert_eq!(state.master_gain_reduction(), Some(GainReduction::default()));
+
+        // Stream readings are listed only while the app is captured
+        let firefox = gecko_dsp::Compressor::new(48000.0, settings).unwrap();
+        let multiband = gecko_dsp::MultibandCompressor::new(48000.0, Default::default()).unwrap();
+        let stages = vec![vec![firefox.meter()], multiband.meters()];
+        state.set_gain_reduction_meters(Some("Firefox"), stages);
+        assert!(state.stream_gain_reduction().is_empty());
+        state.add_captured_app("Firefox");
+        let streams = state.stream_gain_reduction();
+        assert_eq!(streams.len(), 1);
+        assert_eq!(streams[0].0, "Firefox");
+        assert_eq!(streams[0].1.bands.len(), multiband.meters().len());
+
+        // A rebuilt chain without a compressor stops reporting
+        state.set_gain_reduction_meters(Some("Firefox"), Vec::new());
+        assert!(state.stream_gain_reduction().is_empty());
+    }
+
+    #[test]
+    fn test_loudness_meters() {
+        let state = AudioProcessingState::new();
+        state.set_channel_count(6);
+        let mut master = state.new_loudness_analyzer(None, 48000.0);
+        assert_eq!(master.channel_count(), 6);
+
+        // 1 kHz at -23 dBFS on the front pair reads -23 LUFS
+        let buffer: Vec<f32> = (0..48000)
+            .flat_map(|n| {
+                let x = 10.0_f32.powf(-23.0 / 20.0)
+                    * (std::f32::consts::TAU * 1000.0 * n as f32 / 48000.0).sin();
+                [x, x, 0.0, 0.0, 0.0, 0.0]
+            })
+            .collect();
+        master.process_interleaved(&buffer);
+        assert!((state.master_loudness().momentary_lufs + 23.0).abs() < 0.1);
+
+        // Stream meters are listed only while the app is captured
+        let mut firefox = state.new_loudness_analyzer(Some("Firefox"), 48000.0);
+        firefox.process_interleaved(&buffer);
+        assert!(state.stream_loudness().is_empty());
+        state.add_captured_app("Firefox");
+        let streams = state.stream_loudness();
+        assert_eq!(streams.len(), 1);
+        assert!((streams[0].1.momentary_lufs + 23.0).abs() < 0.1);
+
+        state.reset_loudness(None);
+        firefox.process_interleaved(&[]);
+        assert_eq!(state.stream_loudness()[0].1, LoudnessStats::default());
+    }
+
+    #[test]
+    fn test_auto_level_state() {
+        let state = AudioProcessingState::new();
+        assert!(!state.is_stream_auto_level("Spotify"));
+        state.set_stream_auto_level("Spotify", true);
+        assert!(state.is_stream_auto_level("Spotify"));
+        state.set_stream_auto_level("Spotify", false);
+        assert!(!state.is_stream_auto_level("Spotify"));
+
+        let settings = AutoLevelSettings {
+            target_lufs: -30.0,
+            speed_db_per_s: 10.0,
+            ..AutoLevelSettings::default()
+        };
+        state.set_auto_level_settings(settings).unwrap();
+        assert_eq!(state.auto_level_settings(), settings);
+        assert!(state
+            .set_auto_level_settings(AutoLevelSettings {
+                max_boost_db: -1.0,
+                ..settings
+            })
+            .is_err());
+
+        // A loud app is pulled down toward the shared target
+        let mut leveler = state.new_auto_leveler(48000.0);
+        let amplitude = 10.0_f32.powf(-10.0 / 20.0);
+        for block in 0..200 {
+            let mut buffer: Vec<f32> = (0..480)
+                .flat_map(|i| {
+                    let t = (block * 480 + i) as f32 / 48000.0;
+                    let x = amplitude * (std::f32::consts::TAU * 1000.0 * t).sin();
+                    [x, x]
+                })
+                .collect();
+            state.process_auto_level(&mut leveler, true, &mut buffer);
+        }
+        assert_eq!(*leveler.settings(), settings);
+        assert!((leveler.gain_db() + 12.0).abs() < 0.1, "Gain {}", leveler.gain_db());
+    }
+
+    #[test]
+    fn test_crossfeed_state() {
+        let state = AudioProcessingState::new();
+        assert_eq!(state.crossfeed(), None);
+
+        // Off: the master buffer is untouched
+        let mut crossfeed = state.new_crossfeed(48000.0);
+        let mut buffer = vec![1.0, 0.0, 1.0, 0.0];
+        state.process_crossfeed(&mut crossfeed, &mut buffer);
+        assert_eq!(buffer, [1.0, 0.0, 1.0, 0.0]);
+
+        let settings = gecko_dsp::CrossfeedPreset::JanMeier.settings();
+        state.set_crossfeed(Some(settings)).unwrap();
+        assert_eq!(state.crossfeed(), Some(settings));
+        state.process_crossfeed(&mut crossfeed, &mut buffer);
+        assert_eq!(*crossfeed.settings(), settings);
+        assert!(buffer[3] > 0.0, "Left should feed into right");
+
+        assert!(state
+            .set_crossfeed(Some(CrossfeedSettings {
+                feed_db: 0.0,
+                ..settings
+            }))
+            .is_err());
+        assert_eq!(state.crossfeed(), Some(settings));
+
+        state.set_crossfeed(None).unwrap();
+        assert_eq!(state.crossfeed(), None);
+    }
+
+    #[test]
+    fn test_stereo_eq_state() {
+        let state = AudioProcessingState::new();
+        assert_eq!(state.eq_channel_mode(), EqChannelMode::Linked);
+        assert_eq!(state.stereo_width(), 1.0);
+
+        let counter = state.eq_update_counter();
+        state.set_eq_channel_mode(EqChannelMode::MidSide);
+        state.set_secondary_eq_band_gain(2, -4.0);
+        state.set_stereo_width(1.5).unwrap();
+        assert!(state.eq_update_counter() > counter);
+        assert!(state.set_stereo_width(MAX_STEREO_WIDTH + 0.1).is_err());
+        assert_eq!(state.stereo_width(), 1.5);
+
+        let mut eq = StereoEqualizer::new(48000.0);
+        state.set_eq_band_gain(1, 3.0);
+        state.apply_master_eq(&mut eq);
+        assert_eq!(eq.mode(), EqChannelMode::MidSide);
+        assert_eq!(eq.width(), 1.5);
+        assert_eq!(eq.primary().config().bands[1].gain_db, 3.0);
+        assert_eq!(eq.secondary().config().bands[2].gain_db, -4.0);
+
+        // A new band layout resets the secondary config too
+        state.set_band_count(5);
+        assert_eq!(state.get_secondary_eq_band_gain(2), 0.0);
+        state.apply_master_eq(&mut eq);
+        assert_eq!(eq.secondary().band_count(), 5);
+    }
+
+    #[test]
+    fn test_auto_preamp_state() {
+        let state = AudioProcessingState::new();
+        assert!(!state.auto_preamp());
+
+        let counter = state.eq_update_counter();
+        state.set_auto_preamp(true);
+        assert!(state.auto_preamp());
+        assert!(state.eq_update_counter() > counter);
+
+        let mut eq = StereoEqualizer::new(48000.0);
+        state.set_eq_band_gain(0, 6.0);
+        state.apply_master_eq(&mut eq);
+        let preamp = state.master_preamp_db();
+        assert!(preamp < -5.0, "preamp {}", preamp);
+        assert_eq!(eq.primary().config().master_gain_db, preamp);
+        // The secondary config is flat, so it needs none
+        assert_eq!(eq.secondary().config().master_gain_db, 0.0);
+
+        state.set_auto_preamp(false);
+        assert_eq!(state.master_preamp_db(), 0.0);
+        state.apply_master_eq(&mut eq);
+        assert_eq!(eq.primary().config().master_gain_db, 0.0);
+
+        // A manual preamp applies to both configs, until auto preamp takes over
+        state.set_manual_preamp(-6.4);
+        state.apply_master_eq(&mut eq);
+        assert_eq!(state.master_preamp_db(), -6.4);
+        assert_eq!(eq.primary().config().master_gain_db, -6.4);
+        assert_eq!(eq.secondary().config().master_gain_db, -6.4);
+        state.set_auto_preamp(true);
+        assert_eq!(state.master_preamp_db(), preamp);
+        assert_eq!(state.manual_preamp(), -6.4);
+    }
+
+    #[test]
+    fn test_output_rate() {
+        let state = AudioProcessingState::new();
+        assert_eq!(state.output_rate(), 48000);
+
+        state.set_output_rate(44100);
+        assert_eq!(state.output_rate(), 44100);
+
+        // DSP built for the negotiated rate
+        let crossfeed = state.new_crossfeed(state.output_rate() as f32);
+        assert_eq!(crossfeed.sample_rate(), 44100.0);
+        let analyzer = state.new_loudness_analyzer(None, state.output_rate() as f32);
+        assert_eq!(analyzer.sample_rate(), 44100.0);
+    }
+
+    #[test]
+    fn test_stream_delays() {
+        let state = AudioProcessingState::new();
+        assert_eq!(state.get_stream_delay("mpv"), 0.0);
+        state.set_stream_delay("mpv", 120.0);
+        state.set_stream_delay("Firefox", 900.0);
+        assert_eq!(state.get_stream_delay("Firefox"), MAX_SYNC_OFFSET_MS);
+        assert_eq!(
+            state.stream_delays_ms(&["mpv", "Spotify"]),
+            vec![120.0, 0.0]
+        );
+
+        // Playing one app early delays the others instead
+        state.set_stream_delay("Firefox", -80.0);
+        assert_eq!(
+            state.stream_delays_ms(&["mpv", "Spotify", "Firefox"]),
+            vec![200.0, 80.0, 0.0]
+        );
+        state.set_stream_delay("Firefox", 0.0);
+        assert_eq!(state.stream_delays_ms(&["Firefox"]), vec![0.0]);
+
+        // The delay line covers the full swing either way
+        let mut delay = state.new_stream_delay(48000.0);
+        assert_eq!(delay.channel_count(), 2);
+        assert!(delay.set_delay_ms(2.0 * MAX_SYNC_OFFSET_MS).is_ok());
+    }
+
+    #[test]
+    fn test_drift_meters() {
+        let state = AudioProcessingState::new();
+        let meter = state.drift_meter("Firefox");
+        let mut compensator = gecko_dsp::DriftCompensator::with_meter(48000.0, 2048.0, meter)
+            .expect("valid parameters");
+        compensator.update(4096.0, 1024);
+
+        // Listed only while the app is captured, and shared with later streams
+        assert!(state.stream_drift().is_empty());
+        state.add_captured_app("Firefox");
+        let streams = state.stream_drift();
+        assert_eq!(streams.len(), 1);
+        assert!(streams[0].1.ratio < 1.0);
+        assert_eq!(state.drift_meter("Firefox").stats(), streams[0].1);
+    }
+
     #[test]
     fn test_stream_config_default() {
         let config = StreamConfig::default();
@@ -658,4 +2099,138 @@ mod tests {
         // And be able to read the new gain
         assert_eq!(state.get_eq_band_gain(3), 12.0);
     }
+
+    #[test]
+    fn test_band_count_change() {
+        let state = AudioProcessingState::new();
+        assert_eq!(state.band_count(), 10);
+
+        state.set_eq_band_gain(5, 6.0);
+        let counter_before = state.eq_update_counter();
+
+        // Switch to 31-band ISO layout: band 30 becomes valid, gains reset to flat
+        assert!(state.set_band_count(31));
+        assert_eq!(state.band_count(), 31);
+        assert!(state.eq_update_counter() > counter_before);
+        assert_eq!(state.get_eq_band_gain(5), 0.0);
+
+        state.set_eq_band_gain(30, 3.0);
+        assert_eq!(state.get_all_eq_gains()[30], 3.0);
+
+        // Back to 3-band tone controls: band 3+ is out of range
+        assert!(state.set_band_count(3));
+        state.set_eq_band_gain(3, 3.0);
+        assert_eq!(state.get_eq_band_gain(3), 0.0);
+
+        // Invalid counts are rejected
+        assert!(!state.set_band_count(0));
+        assert!(!state.set_band_count(MAX_BANDS + 1));
+        assert_eq!(state.band_count(), 3);
+    }
+
+    #[test]
+    fn test_stream_eq_all_pads_to_band_count() {
+        let state = AudioProcessingState::new();
+        state.set_stream_eq_all("Firefox", &[1.0, 2.0, 3.0]);
+
+        let gains = state.get_stream_eq_all("Firefox");
+        assert_eq!(&gains[..3], &[1.0, 2.0, 3.0]);
+        assert!(gains[3..].iter().all(|&g| g == 0.0));
+    }
+
+    #[test]
+    fn test_apply_eq_follows_band_count() {
+        let state = AudioProcessingState::new();
+        let mut eq = Equalizer::new(48000.0);
+
+        state.set_band_count(31);
+        state.set_eq_band_gain(30, 4.0);
+        state.apply_eq(&mut eq);
+        assert_eq!(eq.band_count(), 31);
+        assert_eq!(eq.config().bands[30].gain_db, 4.0);
+
+        state.set_band_count(3);
+        state.apply_eq(&mut eq);
+        assert_eq!(eq.band_count(), 3);
+    }
+
+    #[test]
+    fn test_apply_eq_flags_rejected_updates() {
+        let state = AudioProcessingState::new();
+        // The 31-band layout reaches 20 kHz, above Nyquist at 36 kHz
+        let mut eq = Equalizer::new(36000.0);
+
+        state.apply_eq(&mut eq);
+        assert!(!state.take_eq_error());
+
+        state.set_band_count(31);
+        state.apply_eq(&mut eq);
+        assert!(state.take_eq_error());
+        // Taking the flag clears it
+        assert!(!state.take_eq_error());
+    }
+
+    #[test]
+    fn test_band_params_reach_equalizer() {
+        let state = AudioProcessingState::new();
+        let mut eq = Equalizer::new(48000.0);
+        let counter = state.eq_update_counter();
+
+        let mut params = state.get_eq_band_params(4).unwrap();
+        params.frequency = 700.0;
+        params.q = 3.0;
+        params.enabled = false;
+        state.set_eq_band_params(4, params);
+        assert_ne!(state.eq_update_counter(), counter);
+
+        state.apply_eq(&mut eq);
+        assert_eq!(eq.config().bands[4].params(), params);
+
+        // Invalid shapes and out-of-range bands are ignored
+        state.set_eq_band_params(4, BandParams { q: -1.0, ..params });
+        state.set_eq_band_params(12, params);
+        assert_eq!(state.get_eq_band_params(4), Some(params));
+        assert_eq!(state.get_eq_band_params(12), None);
+
+        // A band count change restores the standard layout
+        state.set_band_count(10);
+        assert_eq!(state.get_eq_band_params(4), Some(gecko_dsp::band_layout(10, 4).params()));
+    }
+
+    #[test]
+    fn test_stream_band_params() {
+        let state = AudioProcessingState::new();
+        let defaults = state.get_stream_band_params_all("Firefox");
+        assert_eq!(defaults[0], gecko_dsp::band_layout(10, 0).params());
+
+        let params = BandParams {
+            frequency: 90.0,
+            q: 0.7,
+            band_type: gecko_dsp::BandType::Peaking,
+            slope: gecko_dsp::FilterSlope::Db12,
+            enabled: true,
+            dynamics: None,
+        };
+        state.set_stream_band_params("Firefox", 0, params);
+        assert_eq!(state.get_stream_band_params_all("Firefox")[0], params);
+        assert_eq!(state.get_stream_band_params_all("Spotify")[0], defaults[0]);
+    }
+
+    #[test]
+    fn test_channel_count_and_meters() {
+        let state = AudioProcessingState::new();
+        assert_eq!(state.channel_count(), 2);
+        assert!(!state.set_channel_count(0));
+        assert!(!state.set_channel_count(9));
+        assert!(state.set_channel_count(6));
+        assert_eq!(state.channel_count(), 6);
+
+        // Stereo: even samples are left, odd are right
+        state.update_meters(&[0.5, -0.25, 0.1, 0.2], 2);
+        assert_eq!(state.peaks(), (0.5, 0.25));
+
+        // 5.1: center (index 2) reaches both meters, rear right only the right one
+        state.update_meters(&[0.0, 0.0, 0.4, 0.0, 0.0, 0.9], 6);
+        assert_eq!(state.peaks(), (0.4, 0.9));
+    }
 }
@@ -0,0 +1,671 @@
+//! Processor chains for the per-app pipeline
+//!
+//! Builds the master and per-app [`ProcessorChain`]s from their
+//! [`ChainConfig`]s. The engine-bound stages (EQ, crossfeed, volume, auto
+//! leveling and the output stage) keep following `AudioProcessingState` and
+//! the app's shared atomics between buffers, so only editing the chain itself
+//! needs a rebuild. Rebuilt chains reach the audio thread through a
+//! [`ChainSender`] and are crossfaded in there.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+
+use gecko_dsp::{
+    AtomicBandParams, AudioProcessor, AutoLeveler, ChainConfig, ChainSender, Crossfeed, Equalizer,
+    Limiter, OutputStage, PhaseMode, ProcessContext, ProcessorChain, ProcessorKind,
+    StereoEqualizer, MAX_BANDS,
+};
+
+use super::audio_stream::AudioProcessingState;
+
+/// Longest buffer (interleaved samples) a chain crossfades in one piece
+pub(super) const CHAIN_BUFFER_SIZE: usize = 48000;
+
+/// Per-app controls shared by the PipeWire thread and the app's chain
+///
+/// The chain's stages read these between buffers; a rebuilt chain gets the
+/// same atomics, so edits made during a swap are not lost.
+#[derive(Clone)]
+pub(super) struct AppControls {
+    /// EQ gains in dB (f32 bits), sized for MAX_BANDS
+    pub eq_gains: Arc<[AtomicU32; MAX_BANDS]>,
+    /// EQ band shapes (frequency, Q, type, enabled)
+    pub band_params: Arc<[AtomicBandParams; MAX_BANDS]>,
+    /// Auto preamp in dB (f32 bits), kept current by `refresh_preamp`
+    pub preamp: Arc<AtomicU32>,
+    /// Bumped after changing gains, shapes or the preamp
+    pub eq_update_counter: Arc<AtomicU32>,
+    /// Whether the app's EQ is bypassed
+    pub bypassed: Arc<AtomicBool>,
+    /// Volume (0.0 - 2.0, f32 bits)
+    pub volume: Arc<AtomicU32>,
+    /// Whether the app is auto-leveled
+    pub auto_level: Arc<AtomicBool>,
+    /// Whether the app's EQ runs linear phase (read when its chain is built)
+    pub linear_phase: Arc<AtomicBool>,
+}
+
+impl AppControls {
+    /// Controls starting from the state saved for `app_name`
+    pub fn new(audio_state: &AudioProcessingState, app_name: &str) -> Self {
+        let gains = audio_state.get_stream_eq_all(app_name);
+        let params = audio_state.get_stream_band_params_all(app_name);
+        let controls = Self {
+            eq_gains: Arc::new(gains.map(|gain_db| AtomicU32::new(gain_db.to_bits()))),
+            band_params: Arc::new(params.map(AtomicBandParams::new)),
+            preamp: Arc::new(AtomicU32::new(0.0_f32.to_bits())),
+            eq_update_counter: Arc::new(AtomicU32::new(0)),
+            bypassed: Arc::new(AtomicBool::new(audio_state.is_stream_bypassed(app_name))),
+            volume: Arc::new(AtomicU32::new(
+                audio_state.get_stream_volume(app_name).to_bits(),
+            )),
+            auto_level: Arc::new(AtomicBool::new(audio_state.is_stream_auto_level(app_name))),
+            linear_phase: Arc::new(AtomicBool::new(
+                audio_state.stream_phase_mode(app_name) == PhaseMode::Linear,
+            )),
+        };
+        controls.refresh_preamp(audio_state, app_name);
+        controls
+    }
+
+    /// Recompute the app's auto preamp from its current gains and shapes
+    ///
+    /// Runs the preamp search, so this belongs on the PipeWire thread: call
+    /// it after editing the controls and before bumping `eq_update_counter`.
+    pub fn refresh_preamp(&self, audio_state: &AudioProcessingState, app_name: &str) {
+        let band_count = audio_state.band_count();
+        let params: Vec<_> =
+            self.band_params[..band_count].iter().map(AtomicBandParams::load).collect();
+        let gains: Vec<_> = self.eq_gains[..band_count]
+            .iter()
+            .map(|gain| f32::from_bits(gain.load(Ordering::Relaxed)))
+            .collect();
+        let preamp_db = audio_state.preamp_for(&params, &gains);
+        self.preamp.store(preamp_db.to_bits(), Ordering::Relaxed);
+        audio_state.set_stream_preamp(app_name, preamp_db);
+    }
+}
+
+/// PipeWire-thread end of a running chain
+pub(super) struct ChainHandle {
+    sender: ChainSender,
+    /// Rate negotiated for the stream, stored by `param_changed`
+    negotiated_rate: Arc<AtomicU32>,
+    /// Rate of the last chain sent (or the initial one)
+    built_rate: u32,
+    channels: usize,
+}
+
+impl ChainHandle {
+    /// `negotiated_rate` holds the rate the initial chain was built for
+    pub fn new(sender: ChainSender, negotiated_rate: Arc<AtomicU32>, channels: usize) -> Self {
+        let built_rate = negotiated_rate.load(Ordering::Relaxed);
+        Self {
+            sender,
+            negotiated_rate,
+            built_rate,
+            channels,
+        }
+    }
+
+    /// Sample rate to build replacement chains for
+    pub fn sample_rate(&self) -> u32 {
+        self.negotiated_rate.load(Ordering::Relaxed)
+    }
+
+    /// Whether the stream negotiated a rate the running chain isn't built for
+    pub fn needs_rebuild(&self) -> bool {
+        self.sample_rate() != self.built_rate
+    }
+
+    /// Interleaved channel count to build replacement chains for
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Queue `chain` to be crossfaded in by the audio thread
+    pub fn send(&mut self, chain: ProcessorChain) -> bool {
+        let rate = chain.context().sample_rate as u32;
+        if self.sender.send(chain).is_err() {
+            tracing::warn!("Audio thread is not picking up processor chains, dropping update");
+            return false;
+        }
+        self.built_rate = rate;
+        true
+    }
+}
+
+/// Build the master chain described by `config`
+///
+/// Allocates; call outside the process callback.
+pub(super) fn build_master_chain(
+    config: &ChainConfig,
+    audio_state: &Arc<AudioProcessingState>,
+    sample_rate: u32,
+    channels: usize,
+) -> ProcessorChain {
+    let mut chain = ProcessorChain::new(sample_rate as f32, channels, CHAIN_BUFFER_SIZE);
+    for slot in &config.processors {
+        let processor: Box<dyn AudioProcessor> = match slot.kind {
+            ProcessorKind::Equalizer => Box::new(MasterEq::new(audio_state, sample_rate, channels)),
+            ProcessorKind::Crossfeed => Box::new(MasterCrossfeed {
+                crossfeed: audio_state.new_crossfeed(sample_rate as f32),
+                audio_state: Arc::clone(audio_state),
+            }),
+            ProcessorKind::Volume => Box::new(MasterVolume {
+                audio_state: Arc::clone(audio_state),
+            }),
+            ProcessorKind::OutputStage => {
+                match OutputStageProcessor::new(audio_state, sample_rate, channels) {
+                    Some(processor) => Box::new(processor),
+                    None => continue,
+                }
+            }
+            ProcessorKind::Convolver => {
+                match audio_state.new_convolver(None, sample_rate as f32, channels) {
+                    Some(convolver) => Box::new(convolver),
+                    None => continue,
+                }
+            }
+            _ => match build_standalone(&slot.kind, chain.context(), "master") {
+                Some(processor) => processor,
+                None => continue,
+            },
+        };
+        chain.add_boxed(processor);
+        chain.set_bypassed(chain.len() - 1, slot.bypassed);
+    }
+    audio_state.set_gain_reduction_meters(None, chain.gain_reduction_meters());
+    chain
+}
+
+/// Build `app_name`'s chain described by `config`
+///
+/// Allocates; call outside the process callback.
+pub(super) fn build_app_chain(
+    config: &ChainConfig,
+    app_name: &str,
+    controls: &AppControls,
+    audio_state: &Arc<AudioProcessingState>,
+    sample_rate: u32,
+    channels: usize,
+) -> ProcessorChain {
+    let mut chain = ProcessorChain::new(sample_rate as f32, channels, CHAIN_BUFFER_SIZE);
+    for slot in &config.processors {
+        let processor: Box<dyn AudioProcessor> = match slot.kind {
+            ProcessorKind::Equalizer => {
+                Box::new(AppEq::new(controls, audio_state, sample_rate, channels))
+            }
+            ProcessorKind::AutoLevel => Box::new(AppAutoLevel {
+                leveler: audio_state.new_auto_leveler(sample_rate as f32),
+                enabled: Arc::clone(&controls.auto_level),
+                audio_state: Arc::clone(audio_state),
+            }),
+            ProcessorKind::Volume => Box::new(AppVolume {
+                volume: Arc::clone(&controls.volume),
+            }),
+            ProcessorKind::Convolver => {
+                match audio_state.new_convolver(Some(app_name), sample_rate as f32, channels) {
+                    Some(convolver) => Box::new(convolver),
+                    None => continue,
+                }
+            }
+            _ => match build_standalone(&slot.kind, chain.context(), "app") {
+                Some(processor) => processor,
+                None => continue,
+            },
+        };
+        chain.add_boxed(processor);
+        chain.set_bypassed(chain.len() - 1, slot.bypassed);
+    }
+    audio_state.set_gain_reduction_meters(Some(app_name), chain.gain_reduction_meters());
+    chain
+}
+
+/// Build a processor that needs no engine state, logging why if it can't be
+fn build_standalone(
+    kind: &ProcessorKind,
+    context: &ProcessContext,
+    scope: &str,
+) -> Option<Box<dyn AudioProcessor>> {
+    match kind.build(context) {
+        Some(Ok(processor)) => Some(processor),
+        Some(Err(e)) => {
+            tracing::warn!("Skipping {} in {} chain: {:?}", kind.name(), scope, e);
+            None
+        }
+        None => {
+            tracing::warn!("{} is not available in the {} chain", kind.name(), scope);
+            None
+        }
+    }
+}
+
+/// Master EQ (linked, left/right or mid/side), following the engine's EQ state
+struct MasterEq {
+    equalizer: StereoEqualizer,
+    audio_state: Arc<AudioProcessingState>,
+    /// Local copy of the master EQ update counter
+    last_counter: u32,
+}
+
+impl MasterEq {
+    fn new(audio_state: &Arc<AudioProcessingState>, sample_rate: u32, channels: usize) -> Self {
+        let mut equalizer = StereoEqualizer::new(sample_rate as f32);
+        let _ = equalizer.set_channel_count(channels);
+        equalizer.set_phase_mode(audio_state.master_phase_mode());
+        // Start from the current gains; the counter only signals later changes
+        let last_counter = audio_state.eq_update_counter();
+        audio_state.apply_master_eq(&mut equalizer);
+        Self {
+            equalizer,
+            audio_state: Arc::clone(audio_state),
+            last_counter,
+        }
+    }
+}
+
+impl AudioProcessor for MasterEq {
+    fn process(&mut self, buffer: &mut [f32], _context: &ProcessContext) {
+        let counter = self.audio_state.eq_update_counter();
+        if counter != self.last_counter {
+            self.audio_state.apply_master_eq(&mut self.equalizer);
+            self.last_counter = counter;
+        }
+        if !self.audio_state.bypassed.load(Ordering::Relaxed) {
+            self.equalizer.process_block(buffer);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.equalizer.reset();
+    }
+
+    fn name(&self) -> &'static str {
+        "Equalizer"
+    }
+
+    fn latency_samples(&self) -> usize {
+        self.equalizer.latency_samples()
+    }
+}
+
+/// Headphone crossfeed, on for outputs that have it enabled
+struct MasterCrossfeed {
+    crossfeed: Crossfeed,
+    audio_state: Arc<AudioProcessingState>,
+}
+
+impl AudioProcessor for MasterCrossfeed {
+    fn process(&mut self, buffer: &mut [f32], _context: &ProcessContext) {
+        if !self.audio_state.bypassed.load(Ordering::Relaxed) {
+            self.audio_state
+                .process_crossfeed(&mut self.crossfeed, buffer);
+        }
+    }
+
+    fn reset(&mut self) {
+        AudioProcessor::reset(&mut self.crossfeed);
+    }
+
+    fn name(&self) -> &'static str {
+        "Crossfeed"
+    }
+}
+
+/// Master volume
+struct MasterVolume {
+    audio_state: Arc<AudioProcessingState>,
+}
+
+impl AudioProcessor for MasterVolume {
+    fn process(&mut self, buffer: &mut [f32], _context: &ProcessContext) {
+        let volume = self.audio_state.master_volume();
+        for sample in buffer.iter_mut() {
+            *sample *= volume;
+        }
+    }
+
+    fn reset(&mut self) {}
+
+    fn name(&self) -> &'static str {
+        "Volume"
+    }
+}
+
+/// Soft clipper or lookahead limiter, whichever is selected
+struct OutputStageProcessor {
+    /// Used when the limiter is the selected stage (buffers allocated up front)
+    limiter: Limiter,
+    audio_state: Arc<AudioProcessingState>,
+}
+
+impl OutputStageProcessor {
+    fn new(
+        audio_state: &Arc<AudioProcessingState>,
+        sample_rate: u32,
+        channels: usize,
+    ) -> Option<Self> {
+        match Limiter::new(sample_rate as f32, audio_state.limiter_settings()) {
+            Ok(mut limiter) => {
+                let _ = limiter.set_channel_count(channels);
+                Some(Self {
+                    limiter,
+                    audio_state: Arc::clone(audio_state),
+                })
+            }
+            Err(e) => {
+                tracing::warn!("Failed to build limiter at {} Hz: {:?}", sample_rate, e);
+                None
+            }
+        }
+    }
+}
+
+impl AudioProcessor for OutputStageProcessor {
+    fn process(&mut self, buffer: &mut [f32], _context: &ProcessContext) {
+        self.audio_state
+            .process_output_stage(&mut self.limiter, buffer);
+    }
+
+    fn reset(&mut self) {
+        AudioProcessor::reset(&mut self.limiter);
+    }
+
+    fn name(&self) -> &'static str {
+        "Output Stage"
+    }
+
+    fn latency_samples(&self) -> usize {
+        match self.audio_state.output_stage() {
+            OutputStage::Limiter => self.limiter.latency_samples(),
+            OutputStage::SoftClip => 0,
+        }
+    }
+}
+
+/// An app's EQ, following its shared gains and band shapes
+struct AppEq {
+    equalizer: Equalizer,
+    controls: AppControls,
+    audio_state: Arc<AudioProcessingState>,
+    /// Local copy of the app's EQ update counter
+    last_counter: u32,
+}
+
+impl AppEq {
+    fn new(
+        controls: &AppControls,
+        audio_state: &Arc<AudioProcessingState>,
+        sample_rate: u32,
+        channels: usize,
+    ) -> Self {
+        let mut equalizer = Equalizer::new(sample_rate as f32);
+        if let Err(e) = equalizer.set_channel_count(channels) {
+            tracing::warn!("Failed to set {} EQ channels: {:?}", channels, e);
+        }
+        if controls.linear_phase.load(Ordering::Relaxed) {
+            equalizer.set_phase_mode(PhaseMode::Linear);
+        }
+        let mut app_eq = Self {
+            equalizer,
+            controls: controls.clone(),
+            audio_state: Arc::clone(audio_state),
+            last_counter: controls.eq_update_counter.load(Ordering::Relaxed),
+        };
+        app_eq.apply_controls();
+        // A new chain starts at the app's EQ rather than ramping in from flat
+        app_eq.equalizer.finish_smoothing();
+        app_eq
+    }
+
+    /// Re-layout without allocating if the band count changed, then apply
+    /// band shapes, gains and the preamp
+    fn apply_controls(&mut self) {
+        let band_count = self.audio_state.band_count();
+        if self.equalizer.band_count() != band_count
+            && self.equalizer.set_band_count(band_count).is_err()
+        {
+            self.audio_state.flag_eq_error();
+        }
+        for band in 0..band_count {
+            let params = self.controls.band_params[band].load();
+            if self.equalizer.config().bands[band].params() != params
+                && self.equalizer.set_band_params(band, params).is_err()
+            {
+                self.audio_state.flag_eq_error();
+            }
+        }
+        for band in 0..band_count {
+            let gain_db = f32::from_bits(self.controls.eq_gains[band].load(Ordering::Relaxed));
+            if self.equalizer.set_band_gain(band, gain_db).is_err() {
+                self.audio_state.flag_eq_error();
+            }
+        }
+        let preamp_db = f32::from_bits(self.controls.preamp.load(Ordering::Relaxed));
+        self.equalizer.set_master_gain_db(preamp_db);
+    }
+}
+
+impl AudioProcessor for AppEq {
+    fn process(&mut self, buffer: &mut [f32], _context: &ProcessContext) {
+        let counter = self.controls.eq_update_counter.load(Ordering::Relaxed);
+        if counter != self.last_counter {
+            self.apply_controls();
+            self.last_counter = counter;
+        }
+
+        if !self.controls.bypassed.load(Ordering::Relaxed) {
+            self.equalizer.process_block(buffer);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.equalizer.reset();
+    }
+
+    fn name(&self) -> &'static str {
+        "Equalizer"
+    }
+
+    fn latency_samples(&self) -> usize {
+        self.equalizer.latency_samples()
+    }
+}
+
+/// Auto-leveling toward the shared target, when enabled for the app
+struct AppAutoLevel {
+    leveler: AutoLeveler,
+    enabled: Arc<AtomicBool>,
+    audio_state: Arc<AudioProcessingState>,
+}
+
+impl AudioProcessor for AppAutoLevel {
+    fn process(&mut self, buffer: &mut [f32], _context: &ProcessContext) {
+        let enabled = self.enabled.load(Ordering::Relaxed);
+        self.audio_state
+            .process_auto_level(&mut self.leveler, enabled, buffer);
+    }
+
+    fn reset(&mut self) {
+        AudioProcessor::reset(&mut self.leveler);
+    }
+
+    fn name(&self) -> &'static str {
+        "Auto Level"
+    }
+}
+
+/// Per-app volume (0.0 - 2.0, default 1.0)
+struct AppVolume {
+    volume: Arc<AtomicU32>,
+}
+
+impl AudioProcessor for AppVolume {
+    fn process(&mut self, buffer: &mut [f32], _context: &ProcessContext) {
+        let volume = f32::from_bits(self.volume.load(Ordering::Relaxed));
+        // Only apply if volume differs from unity gain
+        if (volume - 1.0).abs() > 0.001 {
+            for sample in buffer.iter_mut() {
+                *sample *= volume;
+            }
+        }
+    }
+
+    fn reset(&mut self) {}
+
+    fn name(&self) -> &'static str {
+        "Volume"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gecko_dsp::{LimiterSettings, ProcessorSlot};
+
+    fn state() -> Arc<AudioProcessingState> {
+        Arc::new(AudioProcessingState::new())
+    }
+
+    #[test]
+    fn test_default_chains_build_every_stage() {
+        let audio_state = state();
+        let master = build_master_chain(&ChainConfig::master_default(), &audio_state, 48000, 2);
+        assert_eq!(
+            master.names().collect::<Vec<_>>(),
+            ["Equalizer", "Crossfeed", "Volume", "Output Stage"]
+        );
+
+        let controls = AppControls::new(&audio_state, "Firefox");
+        let app = build_app_chain(
+            &ChainConfig::app_default(),
+            "Firefox",
+            &controls,
+            &audio_state,
+            48000,
+            2,
+        );
+        assert_eq!(
+            app.names().collect::<Vec<_>>(),
+            ["Equalizer", "Auto Level", "Volume"]
+        );
+    }
+
+    #[test]
+    fn test_master_chain_follows_engine_state() {
+        let audio_state = state();
+        let mut config = ChainConfig {
+            processors: vec![ProcessorSlot::new(ProcessorKind::Volume)],
+        };
+        config
+            .insert(1, ProcessorKind::Gain { gain_db: -6.0 })
+            .unwrap();
+        let mut chain = build_master_chain(&config, &audio_state, 48000, 2);
+
+        // Volume changes apply without rebuilding
+        audio_state.set_master_volume(0.5);
+        let mut buffer = [1.0_f32; 8];
+        chain.process(&mut buffer);
+        let expected = 0.5 * 10.0_f32.powf(-6.0 / 20.0);
+        assert!(buffer.iter().all(|s| (s - expected).abs() < 1e-6));
+
+        // A bypassed slot is skipped
+        config.set_bypassed(0, true).unwrap();
+        let mut chain = build_master_chain(&config, &audio_state, 48000, 2);
+        let mut buffer = [1.0_f32; 8];
+        chain.process(&mut buffer);
+        assert!(buffer
+            .iter()
+            .all(|s| (s - 10.0_f32.powf(-6.0 / 20.0)).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_app_volume_is_shared_with_rebuilt_chains() {
+        let audio_state = state();
+        audio_state.set_stream_volume("mpv", 2.0);
+        let controls = AppControls::new(&audio_state, "mpv");
+        let config = ChainConfig {
+            processors: vec![ProcessorSlot::new(ProcessorKind::Volume)],
+        };
+        let mut first = build_app_chain(&config, "mpv", &controls, &audio_state, 48000, 2);
+        let mut second = build_app_chain(&config, "mpv", &controls, &audio_state, 48000, 2);
+
+        controls.volume.store(0.25_f32.to_bits(), Ordering::Relaxed);
+        for chain in [&mut first, &mut second] {
+            let mut buffer = [1.0_f32; 4];
+            chain.process(&mut buffer);
+            assert_eq!(buffer, [0.25; 4]);
+        }
+    }
+
+    #[test]
+    fn test_output_stage_latency_follows_selection() {
+        let audio_state = state();
+        let config = ChainConfig {
+            processors: vec![ProcessorSlot::new(ProcessorKind::OutputStage)],
+        };
+        let chain = build_master_chain(&config, &audio_state, 48000, 2);
+        assert_eq!(chain.latency_samples(), 0);
+
+        audio_state
+            .set_limiter_settings(LimiterSettings::default())
+            .unwrap();
+        audio_state.set_output_stage(OutputStage::Limiter);
+        assert_eq!(chain.latency_samples(), Limiter::latency_at(48000.0));
+    }
+
+    #[test]
+    fn test_built_chains_publish_gain_reduction() {
+        let audio_state = state();
+        let mut config = ChainConfig::master_default();
+        build_master_chain(&config, &audio_state, 48000, 2);
+        assert_eq!(audio_state.master_gain_reduction(), None);
+
+        config
+            .insert(1, ProcessorKind::Compressor(Default::default()))
+            .unwrap();
+        let mut chain = build_master_chain(&config, &audio_state, 48000, 2);
+        let mut buffer = [0.9_f32; 9600];
+        chain.process(&mut buffer);
+        assert!(audio_state.master_gain_reduction().unwrap().db > 0.0);
+    }
+
+    #[test]
+    fn test_convolver_follows_loaded_ir() {
+        let audio_state = state();
+        let config = ChainConfig {
+            processors: vec![ProcessorSlot::new(ProcessorKind::Convolver)],
+        };
+        audio_state.set_master_chain(config.clone()).unwrap();
+        let chain = build_master_chain(&config, &audio_state, 48000, 2);
+        assert!(chain.is_empty());
+        assert_eq!(audio_state.output_latency_samples(48000.0), 0);
+
+        // A unit impulse recorded at 44.1 kHz, resampled while building
+        let ir = gecko_dsp::ImpulseResponse::new(44100.0, vec![vec![1.0]]).unwrap();
+        audio_state.set_impulse_response(None, Some(ir));
+        let chain = build_master_chain(&config, &audio_state, 48000, 2);
+        assert_eq!(chain.names().collect::<Vec<_>>(), ["Convolver"]);
+        assert_eq!(chain.latency_samples(), gecko_dsp::IR_BLOCK);
+        assert_eq!(audio_state.output_latency_samples(48000.0), gecko_dsp::IR_BLOCK);
+
+        // Apps have their own IR
+        let controls = AppControls::new(&audio_state, "mpv");
+        let app = build_app_chain(&config, "mpv", &controls, &audio_state, 48000, 2);
+        assert!(app.is_empty());
+    }
+
+    #[test]
+    fn test_scope_mismatched_kinds_are_skipped() {
+        let audio_state = state();
+        let config = ChainConfig {
+            processors: vec![
+                ProcessorSlot::new(ProcessorKind::AutoLevel),
+                ProcessorSlot::new(ProcessorKind::Volume),
+            ],
+        };
+        let chain = build_master_chain(&config, &audio_state, 48000, 2);
+        assert_eq!(chain.names().collect::<Vec<_>>(), ["Volume"]);
+    }
+}
@@ -16,6 +16,7 @@
 //! ```
 
 use crate::VirtualSinkConfig;
+use gecko_dsp::BandParams;
 
 /// Commands sent from the main thread to the PipeWire thread
 ///
@@ -75,6 +76,18 @@ pub enum PwCommand {
     /// Note: Fire-and-forget, no response expected (processed via try_recv)
     UpdateEqBand { band: usize, gain_db: f32 },
 
+    /// Update master EQ band shape (frequency, Q, type, enabled)
+    /// Note: Fire-and-forget, no response expected
+    UpdateEqBandParams { band: usize, params: BandParams },
+
+    /// Change the number of EQ bands for master and all per-app EQs
+    /// Note: Fire-and-forget, gains reset to flat until re-sent
+    SetEqBandCount(usize),
+
+    /// Recompute every per-app EQ preamp after auto preamp was toggled
+    /// Note: Fire-and-forget, the master preamp is already updated
+    RefreshAppPreamps,
+
     /// Set master volume
     /// Note: Fire-and-forget, no response expected
     SetVolume(f32),
@@ -135,12 +148,23 @@ pub enum PwCommand {
     UpdateAppEqBand {
         /// Application name
         app_name: String,
-        /// Band index (0-9)
+        /// Band index (0 to band count - 1)
         band: usize,
         /// Gain in dB (-24 to +24)
         gain_db: f32,
     },
 
+    /// Update EQ band shape for a specific application
+    /// Note: Fire-and-forget, no response expected
+    UpdateAppBandParams {
+        /// Application name
+        app_name: String,
+        /// Band index (0 to band count - 1)
+        band: usize,
+        /// New frequency, Q, type and enable state
+        params: BandParams,
+    },
+
     /// Set bypass state for a specific application
     /// When bypassed, app audio passes through without EQ processing
     SetAppBypass {
@@ -150,6 +174,48 @@ pub enum PwCommand {
         bypassed: bool,
     },
 
+    /// Enable or disable auto-leveling for a specific application
+    SetAppAutoLevel {
+        /// Application name
+        app_name: String,
+        /// Whether to level this app toward the shared target
+        enabled: bool,
+    },
+
+    /// Set a per-app A/V sync offset in ms (±MAX_SYNC_OFFSET_MS)
+    /// Negative offsets delay every other app instead
+    SetAppDelay {
+        /// Application name
+        app_name: String,
+        /// Offset in milliseconds (positive delays this app)
+        delay_ms: f32,
+    },
+
+    /// Replace the master chain (`app_name: None`) or an app's processor chain
+    /// The new chain is crossfaded in by the audio thread
+    SetProcessorChain {
+        /// Application name, or None for the master chain
+        app_name: Option<String>,
+        /// Validated chain description
+        chain: gecko_dsp::ChainConfig,
+    },
+
+    /// Switch the master EQ (`app_name: None`) or an app's EQ between
+    /// minimum and linear phase; its chain is rebuilt and crossfaded in
+    SetPhaseMode {
+        /// Application name, or None for the master EQ
+        app_name: Option<String>,
+        /// Minimum (biquads) or linear phase (FIR, adds latency)
+        mode: gecko_dsp::PhaseMode,
+    },
+
+    /// The master's (`app_name: None`) or an app's impulse response changed
+    /// (already in shared state); its chain is rebuilt and crossfaded in
+    SetImpulseResponse {
+        /// Application name, or None for the master chain
+        app_name: Option<String>,
+    },
+
     /// Set per-app volume (0.0 - 2.0, where 1.0 is unity gain)
     /// This is applied after per-app EQ and before mixing
     SetAppVolume {
@@ -26,6 +26,8 @@ mod state;
 #[cfg(feature = "pipewire")]
 mod audio_stream;
 #[cfg(feature = "pipewire")]
+mod chain;
+#[cfg(feature = "pipewire")]
 mod filter;
 #[cfg(feature = "pipewire")]
 mod thread;
@@ -35,7 +37,9 @@ pub use audio_stream::{AudioFormat, AudioProcessingState, StreamConfig};
 #[cfg(feature = "pipewire")]
 pub use filter::FilterState;
 
-pub use state::{PipeWireState, PortDirection, PwLinkInfo, PwNodeInfo, PwPortInfo};
+pub use state::{
+    pair_ports_by_channel, PipeWireState, PortDirection, PwLinkInfo, PwNodeInfo, PwPortInfo,
+};
 
 use crate::error::PlatformError;
 use crate::traits::*;
@@ -155,6 +159,8 @@ use std::time::Duration;
 #[cfg(feature = "pipewire")]
 use crossbeam_channel::{bounded, Receiver};
 #[cfg(feature = "pipewire")]
+use gecko_dsp::BandParams;
+#[cfg(feature = "pipewire")]
 use pipewire as pw;
 
 #[cfg(feature = "pipewire")]
@@ -427,6 +433,79 @@ impl PipeWireBackend {
         let _ = self.command_tx.send(PwCommand::UpdateEqBand { band, gain_db });
     }
 
+    /// Update master EQ band shape (fire-and-forget, real-time safe)
+    ///
+    /// Invalid shapes are ignored; validate with `BandParams::validate` first.
+    pub fn update_eq_band_params(&self, band: usize, params: BandParams) {
+        let _ = self.command_tx.send(PwCommand::UpdateEqBandParams { band, params });
+    }
+
+    /// Set how the master EQ treats the front left/right pair
+    pub fn set_eq_channel_mode(&self, mode: gecko_dsp::EqChannelMode) {
+        self.audio_state.set_eq_channel_mode(mode);
+    }
+
+    /// Set master stereo width (0.0 mono, 1.0 unchanged, up to 2.0)
+    pub fn set_stereo_width(&self, width: f32) -> Result<(), gecko_dsp::DspError> {
+        self.audio_state.set_stereo_width(width)
+    }
+
+    /// Update secondary (right or side) master EQ band gain
+    pub fn update_secondary_eq_band(&self, band: usize, gain_db: f32) {
+        self.audio_state.set_secondary_eq_band_gain(band, gain_db);
+    }
+
+    /// Update secondary (right or side) master EQ band shape
+    ///
+    /// Invalid shapes are ignored; validate with `BandParams::validate` first.
+    pub fn update_secondary_eq_band_params(&self, band: usize, params: BandParams) {
+        self.audio_state.set_secondary_eq_band_params(band, params);
+    }
+
+    /// Enable or disable automatic preamp for the master and per-app EQs
+    pub fn set_auto_preamp(&self, enabled: bool) {
+        self.audio_state.set_auto_preamp(enabled);
+        let _ = self.command_tx.send(PwCommand::RefreshAppPreamps);
+    }
+
+    /// Set the master EQ preamp (dB) used while auto preamp is off
+    pub fn set_manual_preamp(&self, preamp_db: f32) {
+        self.audio_state.set_manual_preamp(preamp_db);
+    }
+
+    /// Preamp (dB) the master EQ applies, or an app's EQ when `stream_id` is given
+    ///
+    /// The master EQ uses the manual preamp while auto preamp is off; app EQs
+    /// use 0 dB then.
+    pub fn eq_preamp_db(&self, stream_id: Option<&str>) -> f32 {
+        match stream_id {
+            Some(stream_id) => self.audio_state.get_stream_preamp(stream_id),
+            None => self.audio_state.master_preamp_db(),
+        }
+    }
+
+    /// Set the interleaved channel count of the per-app pipeline (e.g. 6 for 5.1)
+    ///
+    /// Per-app sinks, their capture streams, the mixer and the output stream
+    /// created afterwards use this layout, so call it before streaming starts.
+    pub fn set_channel_count(&self, channels: u32) -> Result<(), PlatformError> {
+        if self.audio_state.set_channel_count(channels as usize) {
+            Ok(())
+        } else {
+            Err(PlatformError::FeatureNotAvailable(format!(
+                "{} channel layout",
+                channels
+            )))
+        }
+    }
+
+    /// Change the EQ band count for master and all per-app EQs (fire-and-forget)
+    ///
+    /// All gains reset to flat; re-send band gains for the new layout afterwards.
+    pub fn set_eq_band_count(&self, band_count: usize) {
+        let _ = self.command_tx.send(PwCommand::SetEqBandCount(band_count));
+    }
+
     /// Update per-app EQ band gain (fire-and-forget, real-time safe)
     ///
     /// This is TRUE per-app EQ - each app has its own independent EQ instance
@@ -434,17 +513,11 @@ impl PipeWireBackend {
     ///
     /// # Arguments
     /// * `stream_id` - Stream ID (format: "pid:name" or just "name")
-    /// * `band` - EQ band index (0-9)
+    /// * `band` - EQ band index (0 to band count - 1)
     /// * `gain_db` - Gain in dB (-24 to +24)
     pub fn update_stream_eq_band(&self, stream_id: &str, band: usize, gain_db: f32) {
-        // Extract app name from stream_id (format: "pid:name" or just "name")
         // The app_captures HashMap is keyed by app name, not stream ID
-        let app_name = if stream_id.contains(':') {
-            // Format is "pid:name" - take everything after the first colon
-            stream_id.split_once(':').map(|(_, name)| name).unwrap_or(stream_id)
-        } else {
-            stream_id
-        };
+        let app_name = crate::app_name_from_stream_id(stream_id);
 
         // Update shared state so future streams pick it up
         self.audio_state.set_stream_eq_offset(app_name, band, gain_db);
@@ -457,6 +530,25 @@ impl PipeWireBackend {
         });
     }
 
+    /// Update per-app EQ band shape (fire-and-forget, real-time safe)
+    ///
+    /// # Arguments
+    /// * `stream_id` - Stream ID (format: "pid:name" or just "name")
+    /// * `band` - EQ band index (0 to band count - 1)
+    /// * `params` - New frequency, Q, type and enable state
+    pub fn update_stream_band_params(&self, stream_id: &str, band: usize, params: BandParams) {
+        let app_name = crate::app_name_from_stream_id(stream_id);
+
+        // Update shared state so future streams pick it up
+        self.audio_state.set_stream_band_params(app_name, band, params);
+
+        let _ = self.command_tx.send(PwCommand::UpdateAppBandParams {
+            app_name: app_name.to_string(),
+            band,
+            params,
+        });
+    }
+
     /// Set bypass state for a specific application (fire-and-forget, real-time safe)
     ///
     /// When bypassed, the app's audio passes through without EQ processing.
@@ -475,6 +567,126 @@ impl PipeWireBackend {
         });
     }
 
+    /// Enable or disable auto-leveling for a specific application (fire-and-forget)
+    ///
+    /// A leveled app's gain drifts slowly toward the shared target loudness,
+    /// before its per-app volume is applied.
+    ///
+    /// # Arguments
+    /// * `app_name` - Application name (e.g., "Firefox", "Spotify")
+    /// * `enabled` - Whether to level this app
+    pub fn set_app_auto_level(&self, app_name: &str, enabled: bool) {
+        // Update shared state so future streams pick it up
+        self.audio_state.set_stream_auto_level(app_name, enabled);
+
+        let _ = self.command_tx.send(PwCommand::SetAppAutoLevel {
+            app_name: app_name.to_string(),
+            enabled,
+        });
+    }
+
+    /// Set the shared auto-leveling target, limits and speed
+    pub fn set_auto_level_settings(
+        &self,
+        settings: gecko_dsp::AutoLevelSettings,
+    ) -> Result<(), gecko_dsp::DspError> {
+        self.audio_state.set_auto_level_settings(settings)
+    }
+
+    /// Set a per-app A/V sync offset (fire-and-forget)
+    ///
+    /// Positive offsets delay the app's audio, up to MAX_SYNC_OFFSET_MS.
+    /// Negative offsets play it early by delaying every other app instead.
+    /// Changes are crossfaded, so they can be made while audio plays.
+    ///
+    /// # Arguments
+    /// * `app_name` - Application name (e.g., "Firefox", "mpv")
+    /// * `delay_ms` - Offset in milliseconds, clamped to ±MAX_SYNC_OFFSET_MS
+    pub fn set_app_delay(&self, app_name: &str, delay_ms: f32) {
+        // Update shared state so future streams pick it up
+        self.audio_state.set_stream_delay(app_name, delay_ms);
+
+        let _ = self.command_tx.send(PwCommand::SetAppDelay {
+            app_name: app_name.to_string(),
+            delay_ms,
+        });
+    }
+
+    /// Replace the master (`app_name: None`) or an app's processor chain
+    ///
+    /// The chain is validated for its scope and kept for streams created
+    /// later. Running streams build the new chain off the audio thread and
+    /// crossfade to it, so stages can be added, removed, reordered or
+    /// bypassed while audio plays.
+    pub fn set_processor_chain(
+        &self,
+        app_name: Option<&str>,
+        chain: gecko_dsp::ChainConfig,
+    ) -> Result<(), gecko_dsp::DspError> {
+        match app_name {
+            Some(app_name) => self.audio_state.set_stream_chain(app_name, chain.clone())?,
+            None => self.audio_state.set_master_chain(chain.clone())?,
+        }
+
+        let _ = self.command_tx.send(PwCommand::SetProcessorChain {
+            app_name: app_name.map(str::to_string),
+            chain,
+        });
+        Ok(())
+    }
+
+    /// Run the master (`app_name: None`) or an app's EQ minimum or linear phase
+    ///
+    /// The chain is rebuilt off the audio thread (linear phase allocates its
+    /// FIR) and crossfaded in.
+    pub fn set_phase_mode(&self, app_name: Option<&str>, mode: gecko_dsp::PhaseMode) {
+        match app_name {
+            Some(app_name) => self.audio_state.set_stream_phase_mode(app_name, mode),
+            None => self.audio_state.set_master_phase_mode(mode),
+        }
+
+        let _ = self.command_tx.send(PwCommand::SetPhaseMode {
+            app_name: app_name.map(str::to_string),
+            mode,
+        });
+    }
+
+    /// Load (`Some`) or clear (`None`) the impulse response the master's
+    /// (`app_name: None`) or an app's Convolver stage plays
+    ///
+    /// Resamples the IR to the output rate here, so the PipeWire thread only
+    /// builds the convolver before the chain is crossfaded in.
+    pub fn set_impulse_response(
+        &self,
+        app_name: Option<&str>,
+        ir: Option<gecko_dsp::ImpulseResponse>,
+    ) -> Result<(), gecko_dsp::DspError> {
+        let rate = self.audio_state.output_rate() as f32;
+        let ir = ir.map(|ir| ir.resampled(rate)).transpose()?;
+        self.audio_state.set_impulse_response(app_name, ir);
+
+        let _ = self.command_tx.send(PwCommand::SetImpulseResponse {
+            app_name: app_name.map(str::to_string),
+        });
+        Ok(())
+    }
+
+    /// Phase mode of the master (`app_name: None`) or an app's EQ
+    pub fn phase_mode(&self, app_name: Option<&str>) -> gecko_dsp::PhaseMode {
+        match app_name {
+            Some(app_name) => self.audio_state.stream_phase_mode(app_name),
+            None => self.audio_state.master_phase_mode(),
+        }
+    }
+
+    /// Current master (`app_name: None`) or per-app processor chain
+    pub fn processor_chain(&self, app_name: Option<&str>) -> gecko_dsp::ChainConfig {
+        match app_name {
+            Some(app_name) => self.audio_state.stream_chain(app_name),
+            None => self.audio_state.master_chain(),
+        }
+    }
+
     /// Set per-app volume (fire-and-forget, real-time safe)
     ///
     /// This volume is applied after per-app EQ and before mixing.
@@ -612,6 +824,77 @@ impl PipeWireBackend {
         self.audio_state.set_soft_clip_enabled(enabled);
     }
 
+    /// Select the final output stage (soft clipper or lookahead limiter)
+    pub fn set_output_stage(&self, stage: gecko_dsp::OutputStage) {
+        self.audio_state.set_output_stage(stage);
+    }
+
+    /// Set the lookahead limiter's ceiling and release
+    pub fn set_limiter_settings(
+        &self,
+        settings: gecko_dsp::LimiterSettings,
+    ) -> Result<(), gecko_dsp::DspError> {
+        self.audio_state.set_limiter_settings(settings)
+    }
+
+    /// Turn headphone crossfeed on the master output on (`Some`) or off (`None`)
+    pub fn set_crossfeed(
+        &self,
+        settings: Option<gecko_dsp::CrossfeedSettings>,
+    ) -> Result<(), gecko_dsp::DspError> {
+        self.audio_state.set_crossfeed(settings)
+    }
+
+    /// Latency (samples at the output rate) added by the master chain, plus
+    /// an app's chain when `app_name` is given
+    ///
+    /// Covers the linear-phase EQs, the convolvers and the limiter.
+    pub fn output_latency_samples(&self, app_name: Option<&str>) -> usize {
+        let rate = self.audio_state.output_rate() as f32;
+        let app_latency = app_name.map_or(0, |app_name| {
+            self.audio_state.stream_latency_samples(app_name, rate)
+        });
+        self.audio_state.output_latency_samples(rate) + app_latency
+    }
+
+    /// Sample rate (Hz) negotiated with the output device
+    pub fn output_rate(&self) -> u32 {
+        self.audio_state.output_rate()
+    }
+
+    /// Loudness of the master output
+    pub fn get_loudness(&self) -> gecko_dsp::LoudnessStats {
+        self.audio_state.master_loudness()
+    }
+
+    /// Loudness of each captured app (per-app mode)
+    pub fn get_stream_loudness(&self) -> Vec<(String, gecko_dsp::LoudnessStats)> {
+        self.audio_state.stream_loudness()
+    }
+
+    /// Compressor gain reduction in the master chain since the last call
+    ///
+    /// `None` while the chain has no compressor.
+    pub fn get_gain_reduction(&self) -> Option<gecko_dsp::GainReduction> {
+        self.audio_state.master_gain_reduction()
+    }
+
+    /// Compressor gain reduction of each captured app with a compressor in
+    /// its chain, since the last call (per-app mode)
+    pub fn get_stream_gain_reduction(&self) -> Vec<(String, gecko_dsp::GainReduction)> {
+        self.audio_state.stream_gain_reduction()
+    }
+
+    /// Clock drift compensation of each captured app against the output (per-app mode)
+    pub fn get_stream_drift(&self) -> Vec<(String, gecko_dsp::DriftStats)> {
+        self.audio_state.stream_drift()
+    }
+
+    /// Restart integrated loudness, range and true peak (one app, or everything)
+    pub fn reset_loudness(&self, app_name: Option<&str>) {
+        self.audio_state.reset_loudness(app_name);
+    }
+
     /// Get current peak levels (left, right) from the audio processing state
     pub fn get_peaks(&self) -> (f32, f32) {
         self.audio_state.peaks()
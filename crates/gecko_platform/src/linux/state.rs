@@ -173,6 +173,39 @@ impl PipeWireState {
     }
 }
 
+/// Pair output ports with input ports for linking, one link per channel
+///
+/// Ports are matched by channel name (FL→FL, FC→FC, ...), so layouts of
+/// different sizes link their shared speakers. A single output port (mono
+/// source) is fanned out to every input. If no channel names match, ports
+/// are paired in ID order.
+pub fn pair_ports_by_channel(outputs: &[&PwPortInfo], inputs: &[&PwPortInfo]) -> Vec<(u32, u32)> {
+    let mut outputs = outputs.to_vec();
+    let mut inputs = inputs.to_vec();
+    outputs.sort_by_key(|p| p.id);
+    inputs.sort_by_key(|p| p.id);
+
+    if outputs.len() == 1 {
+        return inputs.iter().map(|input| (outputs[0].id, input.id)).collect();
+    }
+
+    let by_channel: Vec<(u32, u32)> = outputs
+        .iter()
+        .filter_map(|output| {
+            inputs
+                .iter()
+                .find(|input| input.channel == output.channel)
+                .map(|input| (output.id, input.id))
+        })
+        .collect();
+
+    if by_channel.is_empty() {
+        outputs.iter().zip(&inputs).map(|(o, i)| (o.id, i.id)).collect()
+    } else {
+        by_channel
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -265,4 +298,48 @@ mod tests {
         assert_eq!(firefox_nodes.len(), 1);
         assert_eq!(firefox_nodes[0].id, 1);
     }
+
+    fn port(id: u32, direction: PortDirection, channel: &str) -> PwPortInfo {
+        PwPortInfo {
+            id,
+            node_id: 0,
+            name: format!("port_{}", channel),
+            direction,
+            channel: channel.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_pair_ports_by_channel() {
+        // Stereo app into a 5.1 sink: only the front pair links
+        let outputs = [port(11, PortDirection::Output, "FR"), port(10, PortDirection::Output, "FL")];
+        let inputs: Vec<PwPortInfo> = ["FL", "FR", "FC", "LFE", "RL", "RR"]
+            .iter()
+            .enumerate()
+            .map(|(i, ch)| port(20 + i as u32, PortDirection::Input, ch))
+            .collect();
+        let out_refs: Vec<&PwPortInfo> = outputs.iter().collect();
+        let in_refs: Vec<&PwPortInfo> = inputs.iter().collect();
+        assert_eq!(pair_ports_by_channel(&out_refs, &in_refs), vec![(10, 20), (11, 21)]);
+
+        // 5.1 monitor into 5.1 capture: every channel links to its twin
+        let monitors: Vec<PwPortInfo> = inputs
+            .iter()
+            .map(|p| port(p.id + 10, PortDirection::Output, &p.channel))
+            .collect();
+        let mon_refs: Vec<&PwPortInfo> = monitors.iter().collect();
+        let pairs = pair_ports_by_channel(&mon_refs, &in_refs);
+        assert_eq!(pairs.len(), 6);
+        assert!(pairs.iter().all(|(o, i)| o - 10 == *i));
+
+        // Mono source fans out to every input
+        let mono = [port(5, PortDirection::Output, "MONO")];
+        let mono_refs: Vec<&PwPortInfo> = mono.iter().collect();
+        assert_eq!(pair_ports_by_channel(&mono_refs, &in_refs[..2]), vec![(5, 20), (5, 21)]);
+
+        // Unlabelled ports pair in ID order
+        let unknown = [port(8, PortDirection::Output, "AUX0"), port(7, PortDirection::Output, "AUX1")];
+        let unknown_refs: Vec<&PwPortInfo> = unknown.iter().collect();
+        assert_eq!(pair_ports_by_channel(&unknown_refs, &in_refs[..2]), vec![(7, 20), (8, 21)]);
+    }
 }
@@ -10,12 +10,18 @@
 //! - A playback stream writing to the default output device
 //! - DSP processing (EQ) happens in the capture callback
 //! - A ring buffer transfers audio from capture to playback
+//!
+//! In per-app mode every sink, capture stream, ring buffer, the mixer and the
+//! output stream share one interleaved channel layout (stereo, 5.1, 7.1, ...)
+//! taken from `AudioProcessingState::channel_count()`, so surround audio is
+//! never folded down inside Gecko.
 
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
+use std::time::Instant;
 
 use crossbeam_channel::Sender;
 use pipewire as pw;
@@ -26,16 +32,18 @@ use pw::spa::utils::dict::DictRef;
 use pw::stream::{Stream, StreamFlags, StreamListener};
 
 use super::audio_stream::AudioProcessingState;
+use super::chain::{build_app_chain, build_master_chain, AppControls, ChainHandle, CHAIN_BUFFER_SIZE};
 use super::message::{PwCommand, PwResponse};
-use super::state::{PipeWireState, PortDirection, PwClientInfo, PwLinkInfo, PwNodeInfo, PwPortInfo};
+use super::state::{
+    pair_ports_by_channel, PipeWireState, PortDirection, PwClientInfo, PwLinkInfo, PwNodeInfo, PwPortInfo,
+};
 
 /// User data passed to playback stream callback
 /// Must be separate struct since it's owned by the stream listener
 struct PlaybackUserData {
     /// Ring buffer consumer (reads audio to play) - for legacy single-stream mode
     consumer: rtrb::Consumer<f32>,
-    /// Shared state for peaks reporting and master EQ
-    #[allow(dead_code)] // Will be used when master EQ is applied in playback callback
+    /// Shared state for peaks reporting, master EQ and the negotiated output rate
     audio_state: Arc<AudioProcessingState>,
     /// Master EQ processor (applied after mixing in per-app mode)
     #[allow(dead_code)] // Planned for master EQ feature
@@ -59,14 +67,37 @@ struct MixingPlaybackUserData {
     app_consumers_state: Arc<AppConsumersState>,
     /// Shared state for volume, bypass, peaks, and master EQ
     audio_state: Arc<AudioProcessingState>,
-    /// Master EQ processor (applied after mixing)
-    master_eq: gecko_dsp::Equalizer,
-    /// Local copy of master EQ update counter
-    last_master_eq_counter: u32,
+    /// Master processor chain (EQ, crossfeed, volume, output stage, ...)
+    chain: gecko_dsp::ChainReceiver,
+    /// Rate negotiated with the output device (shared with the master ChainHandle)
+    negotiated_rate: Arc<AtomicU32>,
     /// Pre-allocated mixing buffer to avoid allocations in callback
     mix_buffer: Vec<f32>,
     /// Pre-allocated read buffer for each app
     read_buffer: Vec<f32>,
+    /// Interleaved channel count negotiated for this stream
+    channels: usize,
+    /// Loudness analyzer for the master output (publishes to audio_state)
+    loudness: gecko_dsp::LoudnessAnalyzer,
+    /// Sample rate the loudness analyzer is set up for
+    sample_rate: u32,
+}
+
+impl MixingPlaybackUserData {
+    /// Pick up a rate `param_changed` stored (called at the top of `process`)
+    ///
+    /// The PipeWire thread sends a chain rebuilt for the new rate through
+    /// the chain swap (see `rebuild_chains_for_rate`).
+    ///
+    /// # Real-time Safety
+    /// No allocations.
+    fn follow_rate(&mut self) {
+        let rate = self.negotiated_rate.load(Ordering::Relaxed);
+        if rate != self.sample_rate {
+            let _ = self.loudness.set_sample_rate(rate as f32);
+            self.sample_rate = rate;
+        }
+    }
 }
 
 /// User data passed to capture stream callback
@@ -79,6 +110,27 @@ struct CaptureUserData {
     audio_state: Arc<AudioProcessingState>,
     /// Local copy of the EQ update counter to detect changes
     last_eq_update_counter: u32,
+    /// Converts to the playback rate when the two streams differ
+    resampler: CaptureResampler,
+    /// Rate negotiated for the capture stream, stored by `param_changed`
+    negotiated_rate: AtomicU32,
+    /// Sample rate the EQ and resampler are set up for
+    sample_rate: u32,
+}
+
+impl CaptureUserData {
+    /// Pick up a rate `param_changed` stored (called at the top of `process`)
+    ///
+    /// # Real-time Safety
+    /// No allocations (this EQ always runs minimum phase).
+    fn follow_rate(&mut self) {
+        let rate = self.negotiated_rate.load(Ordering::Relaxed);
+        if rate != self.sample_rate {
+            let _ = self.equalizer.set_sample_rate(rate as f32);
+            self.resampler.set_input_rate(rate);
+            self.sample_rate = rate;
+        }
+    }
 }
 
 // === Per-App Audio Types ===
@@ -116,15 +168,14 @@ struct AppCaptureState {
     stream: Stream,
     /// Stream listener (must stay alive while stream is active)
     listener: StreamListener<AppCaptureUserData>,
-    /// Per-app EQ gains (shared with callback via Arc)
-    eq_gains: Arc<[std::sync::atomic::AtomicU32; 10]>,
-    /// EQ update counter (shared with callback)
-    eq_update_counter: Arc<std::sync::atomic::AtomicU32>,
-    /// Whether this app's EQ is bypassed (shared with callback)
-    bypassed: Arc<std::sync::atomic::AtomicBool>,
-    /// Per-app volume (0.0 - 2.0, stored as f32 bits in AtomicU32)
-    /// Default is 1.0 (unity gain). Values > 1.0 amplify, < 1.0 attenuate.
-    volume: Arc<std::sync::atomic::AtomicU32>,
+    /// Per-app EQ gains and shapes, bypass, volume and auto-level toggle
+    /// (shared with the callback's chain via Arc)
+    controls: AppControls,
+    /// Sends rebuilt processor chains to the callback
+    chain: ChainHandle,
+    /// Delay applied to this app in ms (f32 bits, shared with callback)
+    /// Set by `update_app_delays` from every captured app's sync offset
+    delay_ms: Arc<std::sync::atomic::AtomicU32>,
 }
 
 /// User data for per-app capture stream callbacks
@@ -133,18 +184,43 @@ struct AppCaptureUserData {
     /// Ring buffer producer (writes to app's own buffer)
     /// Each app has its own SPSC ring buffer for lock-free operation
     producer: rtrb::Producer<f32>,
-    /// Per-app EQ processor
-    equalizer: gecko_dsp::Equalizer,
-    /// Per-app EQ gains (stored as atomic u32 bits for lock-free access)
-    eq_gains: Arc<[std::sync::atomic::AtomicU32; 10]>,
-    /// Counter for detecting EQ changes
-    eq_update_counter: Arc<std::sync::atomic::AtomicU32>,
-    /// Local copy of the EQ update counter
-    last_eq_update_counter: u32,
-    /// Whether EQ is bypassed for this app
-    bypassed: Arc<std::sync::atomic::AtomicBool>,
-    /// Per-app volume (0.0 - 2.0, stored as f32 bits in AtomicU32)
-    volume: Arc<std::sync::atomic::AtomicU32>,
+    /// Per-app processor chain (EQ, auto-level, volume, ...)
+    chain: gecko_dsp::ChainReceiver,
+    /// Rate negotiated with the app's sink (shared with the app's ChainHandle)
+    negotiated_rate: Arc<AtomicU32>,
+    /// Shared processing state (chain config, loudness and delay settings)
+    audio_state: Arc<AudioProcessingState>,
+    /// Loudness analyzer for this app after EQ and volume (publishes to audio_state)
+    loudness: gecko_dsp::LoudnessAnalyzer,
+    /// Delay applied to this app in ms (f32 bits)
+    delay_ms: Arc<std::sync::atomic::AtomicU32>,
+    /// A/V sync delay line, at the capture rate
+    delay: gecko_dsp::Delay,
+    /// Converts to the output rate when this app's sink runs at another rate
+    resampler: CaptureResampler,
+    /// Application name (keys this app's loudness meter)
+    app_name: String,
+    /// Sample rate the DSP above is set up for
+    sample_rate: u32,
+}
+
+impl AppCaptureUserData {
+    /// Pick up a rate `param_changed` stored (called at the top of `process`)
+    ///
+    /// The PipeWire thread sends a chain rebuilt for the new rate through
+    /// the chain swap (see `rebuild_chains_for_rate`).
+    ///
+    /// # Real-time Safety
+    /// No allocations. The delay line's history is cleared.
+    fn follow_rate(&mut self) {
+        let rate = self.negotiated_rate.load(Ordering::Relaxed);
+        if rate != self.sample_rate {
+            self.resampler.set_input_rate(rate);
+            let _ = self.loudness.set_sample_rate(rate as f32);
+            let _ = self.delay.set_sample_rate(rate as f32);
+            self.sample_rate = rate;
+        }
+    }
 }
 
 /// Shared state for per-app consumers accessible by the mixer
@@ -158,7 +234,7 @@ struct AppConsumersState {
     /// Current consumers indexed by slot (fixed size for lock-free access)
     /// Each slot is Option<(app_name, consumer)>
     /// Using parking_lot::RwLock for now - playback reads, main thread writes
-    consumers: parking_lot::RwLock<Vec<(String, rtrb::Consumer<f32>)>>,
+    consumers: parking_lot::RwLock<Vec<(String, rtrb::Consumer<f32>, Arc<ReadClock>)>>,
 }
 
 #[allow(dead_code)] // Scaffolded for per-app EQ mixing feature
@@ -169,14 +245,60 @@ impl AppConsumersState {
         }
     }
 
-    fn add_consumer(&self, app_name: String, consumer: rtrb::Consumer<f32>) {
+    fn add_consumer(
+        &self,
+        app_name: String,
+        consumer: rtrb::Consumer<f32>,
+        read_clock: Arc<ReadClock>,
+    ) {
         let mut guard = self.consumers.write();
-        guard.push((app_name, consumer));
+        guard.push((app_name, consumer, read_clock));
     }
 
     fn remove_consumer(&self, app_name: &str) {
         let mut guard = self.consumers.write();
-        guard.retain(|(name, _)| name != app_name);
+        guard.retain(|(name, _, _)| name != app_name);
+    }
+}
+
+/// When the mixer last read an app's ring buffer, and how much it took
+///
+/// The mixer drains each ring a whole buffer at a time on the output clock.
+/// The app's capture stream uses this to tell how much of the ring has played
+/// out since, so its drift compensation sees a smooth fill level.
+struct ReadClock {
+    epoch: Instant,
+    /// Nanoseconds from `epoch` to the last read (0 before the first)
+    last_read_ns: AtomicU64,
+    /// Samples taken by the last read
+    last_read_samples: AtomicU32,
+}
+
+impl ReadClock {
+    fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            last_read_ns: AtomicU64::new(0),
+            last_read_samples: AtomicU32::new(0),
+        }
+    }
+
+    /// Record a read of `samples` (called by the mixer)
+    fn mark(&self, samples: usize) {
+        let now = self.epoch.elapsed().as_nanos() as u64;
+        self.last_read_samples.store(samples as u32, Ordering::Relaxed);
+        self.last_read_ns.store(now.max(1), Ordering::Release);
+    }
+
+    /// Seconds since the last read and the samples it took, None before the first
+    fn last_read(&self) -> Option<(f64, usize)> {
+        let last = self.last_read_ns.load(Ordering::Acquire);
+        if last == 0 {
+            return None;
+        }
+        let since = (self.epoch.elapsed().as_nanos() as u64).saturating_sub(last);
+        let samples = self.last_read_samples.load(Ordering::Relaxed) as usize;
+        Some((since as f64 * 1e-9, samples))
     }
 }
 
@@ -287,6 +409,9 @@ struct LocalState {
 
     /// Mixing playback listener
     mixing_playback_listener: Option<StreamListener<MixingPlaybackUserData>>,
+
+    /// Sends rebuilt master chains to the mixing playback callback
+    master_chain: Option<ChainHandle>,
 }
 
 
@@ -314,6 +439,219 @@ fn get_prop_u32(props: Option<&DictRef>, key: &str) -> Option<u32> {
     get_prop(props, key).and_then(|s| s.parse().ok())
 }
 
+/// Comma-separated speaker positions for sink properties (e.g. "FL,FR,FC,LFE,RL,RR")
+fn audio_position_prop(channels: u32) -> String {
+    crate::channel_positions(channels).unwrap_or(&["FL", "FR"]).join(",")
+}
+
+/// SPA channel id for a PipeWire speaker position name
+fn spa_channel(position: &str) -> u32 {
+    use pw::spa::sys;
+    match position {
+        "MONO" => sys::SPA_AUDIO_CHANNEL_MONO,
+        "FL" => sys::SPA_AUDIO_CHANNEL_FL,
+        "FR" => sys::SPA_AUDIO_CHANNEL_FR,
+        "FC" => sys::SPA_AUDIO_CHANNEL_FC,
+        "LFE" => sys::SPA_AUDIO_CHANNEL_LFE,
+        "RL" => sys::SPA_AUDIO_CHANNEL_RL,
+        "RR" => sys::SPA_AUDIO_CHANNEL_RR,
+        "RC" => sys::SPA_AUDIO_CHANNEL_RC,
+        "SL" => sys::SPA_AUDIO_CHANNEL_SL,
+        "SR" => sys::SPA_AUDIO_CHANNEL_SR,
+        _ => sys::SPA_AUDIO_CHANNEL_UNKNOWN,
+    }
+}
+
+/// Serialized EnumFormat params for F32LE with `channels` positioned channels
+///
+/// Positions are set explicitly so PipeWire maps speakers by name instead of
+/// guessing a layout (or downmixing) from the channel count.
+///
+/// The rate is left open so the stream runs at the graph rate; the negotiated
+/// rate arrives in `param_changed` (see `negotiated_rate`).
+fn audio_format_bytes(channels: u32) -> Vec<u8> {
+    let mut audio_info = pw::spa::param::audio::AudioInfoRaw::new();
+    audio_info.set_format(pw::spa::param::audio::AudioFormat::F32LE);
+    audio_info.set_channels(channels);
+    if let Some(positions) = crate::channel_positions(channels) {
+        let mut position = [0u32; 64];
+        for (slot, name) in position.iter_mut().zip(positions) {
+            *slot = spa_channel(name);
+        }
+        audio_info.set_position(position);
+    }
+
+    pw::spa::pod::serialize::PodSerializer::serialize(
+        std::io::Cursor::new(Vec::new()),
+        &pw::spa::pod::Value::Object(pw::spa::pod::Object {
+            type_: pw::spa::utils::SpaTypes::ObjectParamFormat.as_raw(),
+            id: pw::spa::param::ParamType::EnumFormat.as_raw(),
+            properties: audio_info.into(),
+        }),
+    )
+    .expect("Failed to serialize audio params")
+    .0
+    .into_inner()
+}
+
+/// Sample rate of a stream's negotiated format
+///
+/// Returns `None` unless `param` is a raw audio Format (the other params
+/// `param_changed` reports, and a cleared format, are ignored).
+fn negotiated_rate(id: u32, param: Option<&Pod>) -> Option<u32> {
+    use pw::spa::param::format::{MediaSubtype, MediaType};
+
+    let param = param?;
+    if id != pw::spa::param::ParamType::Format.as_raw() {
+        return None;
+    }
+    let (media_type, media_subtype) = pw::spa::param::format_utils::parse_format(param).ok()?;
+    if media_type != MediaType::Audio || media_subtype != MediaSubtype::Raw {
+        return None;
+    }
+    let mut audio_info = pw::spa::param::audio::AudioInfoRaw::new();
+    audio_info.parse(param).ok()?;
+    Some(audio_info.rate()).filter(|&rate| rate > 0)
+}
+
+/// `node.latency` for a ~21 ms quantum (1024 frames at 48 kHz) at `rate`
+fn node_latency(rate: u32) -> String {
+    let quantum = (1024 * u64::from(rate) / 48000).max(1).next_power_of_two();
+    format!("{}/{}", quantum, rate)
+}
+
+/// Frames converted per resampler call in capture callbacks
+const RESAMPLE_CHUNK_FRAMES: usize = 256;
+
+/// Largest output/input rate ratio a capture stream's scratch buffer holds
+const MAX_UPSAMPLE_RATIO: usize = 16;
+
+/// Brings a capture stream's audio to the output rate on its way into a ring buffer
+///
+/// Both streams normally follow the graph rate, in which case audio passes
+/// straight through. The scratch buffer is allocated here, not in the callback.
+struct CaptureResampler {
+    resampler: gecko_dsp::Resampler,
+    /// One converted chunk, interleaved
+    buffer: Vec<f32>,
+    /// Clock drift compensation against the mixer (per-app captures only)
+    drift: Option<RingDrift>,
+}
+
+impl CaptureResampler {
+    fn new(channels: usize, rate: u32) -> Self {
+        let mut resampler = gecko_dsp::Resampler::new(rate as f32, rate as f32)
+            .expect("negotiated sample rates are positive");
+        let _ = resampler.set_channel_count(channels);
+        Self {
+            resampler,
+            buffer: vec![0.0; (RESAMPLE_CHUNK_FRAMES * MAX_UPSAMPLE_RATIO + 1) * channels],
+            drift: None,
+        }
+    }
+
+    /// Hold the ring buffer at a target latency against the mixer's clock
+    ///
+    /// `read_clock` is shared with the mixer; `meter` reports the drift.
+    fn with_drift_compensation(
+        mut self,
+        read_clock: Arc<ReadClock>,
+        meter: gecko_dsp::DriftMeter,
+    ) -> Self {
+        let rate = self.resampler.output_rate();
+        let compensator = gecko_dsp::DriftCompensator::with_meter(rate, 0.0, meter)
+            .expect("negotiated sample rates are positive");
+        self.drift = Some(RingDrift {
+            compensator,
+            read_clock,
+        });
+        self
+    }
+
+    /// Follow the rate the capture stream negotiated
+    fn set_input_rate(&mut self, rate: u32) {
+        let output_rate = self.resampler.output_rate();
+        if let Err(e) = self.resampler.set_rates(rate as f32, output_rate) {
+            tracing::warn!("Failed to resample capture from {} Hz: {:?}", rate, e);
+        }
+    }
+
+    /// Write capture-rate `samples` to `producer`, converted to `output_rate`
+    ///
+    /// # Real-time Safety
+    /// No allocations. An output rate change redesigns the kernel once.
+    fn write(&mut self, samples: &[f32], output_rate: u32, producer: &mut rtrb::Producer<f32>) {
+        if self.resampler.output_rate() != output_rate as f32 {
+            let input_rate = self.resampler.input_rate();
+            let _ = self.resampler.set_rates(input_rate, output_rate as f32);
+            if let Some(drift) = &mut self.drift {
+                let _ = drift.compensator.set_sample_rate(output_rate as f32);
+            }
+        }
+        if self.resampler.is_passthrough() {
+            write_to_ring(producer, samples);
+        } else {
+            let chunk_len = RESAMPLE_CHUNK_FRAMES * self.resampler.channel_count();
+            for chunk in samples.chunks(chunk_len) {
+                let written = self.resampler.process_interleaved(chunk, &mut self.buffer);
+                write_to_ring(producer, &self.buffer[..written]);
+            }
+        }
+
+        if let Some(drift) = &mut self.drift {
+            let channels = self.resampler.channel_count();
+            let scale = self.resampler.output_rate() / self.resampler.input_rate();
+            let frames = (samples.len() / channels) as f32 * scale;
+            if let Some(ratio) = drift.update(producer, channels, frames) {
+                let _ = self.resampler.set_drift_ratio(ratio);
+            }
+        }
+    }
+}
+
+/// A per-app ring buffer's drift compensator and the mixer's read clock
+struct RingDrift {
+    compensator: gecko_dsp::DriftCompensator,
+    read_clock: Arc<ReadClock>,
+}
+
+impl RingDrift {
+    /// Feed the ring's fill level after writing `frames` (at the output rate)
+    ///
+    /// Holds one write plus one read of audio in the ring. Returns None while
+    /// the mixer isn't reading, so a paused output doesn't look like drift.
+    fn update(
+        &mut self,
+        producer: &rtrb::Producer<f32>,
+        channels: usize,
+        frames: f32,
+    ) -> Option<f64> {
+        let (since_read, read_samples) = self.read_clock.last_read()?;
+        let read_frames = (read_samples / channels) as f32;
+        let rate = self.compensator.sample_rate() as f64;
+        let drained = since_read * rate;
+        if drained > 4.0 * read_frames as f64 {
+            return None;
+        }
+
+        let stored = producer.buffer().capacity() - producer.slots();
+        let _ = self.compensator.set_target_frames(frames + read_frames);
+        let fill = (stored / channels) as f64 - drained;
+        Some(self.compensator.update(fill, frames as usize))
+    }
+}
+
+/// Copy interleaved samples into a ring buffer (dropped if it is full)
+fn write_to_ring(producer: &mut rtrb::Producer<f32>, samples: &[f32]) {
+    if let Ok(mut write_chunk) = producer.write_chunk(samples.len()) {
+        let (first, second) = write_chunk.as_mut_slices();
+        let (head, tail) = samples.split_at(first.len());
+        first.copy_from_slice(head);
+        second.copy_from_slice(tail);
+        write_chunk.commit_all();
+    }
+}
+
 /// Process pending app sinks - create virtual sinks for newly detected apps
 ///
 /// This is called from the main loop to create per-app sinks for applications
@@ -321,6 +659,7 @@ fn get_prop_u32(props: Option<&DictRef>, key: &str) -> Option<u32> {
 fn process_pending_app_sinks(local: &mut LocalState, core: &pw::core::Core) {
     // Take the pending list to process
     let pending: Vec<String> = local.pending_app_sinks.drain(..).collect();
+    let channels = local.audio_state.as_ref().map_or(2, |state| state.channel_count()) as u32;
 
     for app_name in pending {
         // Skip if we already have a sink for this app (might have been created by command)
@@ -336,9 +675,8 @@ fn process_pending_app_sinks(local: &mut LocalState, core: &pw::core::Core) {
             "factory.name" => "support.null-audio-sink",
             "node.name" => sink_name.as_str(),
             "media.class" => "Audio/Sink",
-            "audio.channels" => "2",
-            "audio.rate" => "48000",
-            "audio.position" => "FL,FR",
+            "audio.channels" => channels.to_string().as_str(),
+            "audio.position" => audio_position_prop(channels).as_str(),
             "node.pause-on-idle" => "false",
             "node.always-process" => "true",
             "audio.volume" => "1.0",
@@ -389,6 +727,7 @@ const MAX_RELINK_RETRIES: u32 = 50;
 fn process_pending_app_capture_links(local: &mut LocalState, core: &pw::core::Core) -> Vec<(String, u32)> {
     // Process each pending app
     let pending: Vec<String> = local.pending_app_capture_links.clone();
+    let channels = local.audio_state.as_ref().map_or(2, |state| state.channel_count());
     let mut completed = Vec::new();
     let mut gave_up = Vec::new();
 
@@ -449,45 +788,39 @@ fn process_pending_app_capture_links(local: &mut LocalState, core: &pw::core::Co
         // Found app nodes - reset retry counter
         local.pending_app_link_retries.remove(app_name);
 
-        // Find sink input ports
-        let mut sink_inputs: Vec<u32> = local
+        // Find sink input ports (one per channel of the pipeline layout)
+        let sink_inputs: Vec<&PwPortInfo> = local
             .ports
-            .iter()
-            .filter(|(_, p)| p.node_id == sink_node_id && matches!(p.direction, PortDirection::Input))
-            .map(|(id, _)| *id)
+            .values()
+            .filter(|p| p.node_id == sink_node_id && matches!(p.direction, PortDirection::Input))
             .collect();
-        sink_inputs.sort();
 
-        if sink_inputs.len() < 2 {
+        if sink_inputs.len() < channels {
             tracing::debug!(
-                "Sink '{}' doesn't have enough input ports yet ({}/2)",
+                "Sink '{}' doesn't have enough input ports yet ({}/{})",
                 sink_name,
-                sink_inputs.len()
+                sink_inputs.len(),
+                channels
             );
             continue;
         }
 
         // Link each app node to the sink
         let mut links_created = 0;
+        let mut links_expected = 0;
         for app_node_id in &app_nodes {
             // Find app output ports
-            let mut app_outputs: Vec<u32> = local
+            let app_outputs: Vec<&PwPortInfo> = local
                 .ports
-                .iter()
-                .filter(|(_, p)| p.node_id == *app_node_id && matches!(p.direction, PortDirection::Output))
-                .map(|(id, _)| *id)
+                .values()
+                .filter(|p| p.node_id == *app_node_id && matches!(p.direction, PortDirection::Output))
                 .collect();
-            app_outputs.sort();
-
-            if app_outputs.len() < 2 {
-                continue;
-            }
-
-            // Create links: FL->FL, FR->FR
-            for i in 0..2 {
-                let out_port = app_outputs[i];
-                let in_port = sink_inputs[i];
 
+            // Create links by channel: FL->FL, FR->FR, FC->FC, ...
+            // A stereo app in a surround sink only feeds the front pair
+            let pairs = pair_ports_by_channel(&app_outputs, &sink_inputs);
+            links_expected += pairs.len();
+            for (out_port, in_port) in pairs {
                 // Check if link already exists
                 let exists = local
                     .links
@@ -523,7 +856,7 @@ fn process_pending_app_capture_links(local: &mut LocalState, core: &pw::core::Co
             }
         }
 
-        if links_created >= 2 {
+        if links_expected > 0 && links_created >= links_expected {
             tracing::debug!(
                 "App '{}' linked to its Gecko sink ({} links)",
                 app_name,
@@ -597,6 +930,10 @@ fn create_mixing_playback_stream(
 
     tracing::debug!("Creating mixing playback stream (target={:?})", playback_target);
 
+    // Build for the last known output rate; param_changed corrects it once negotiated
+    let sample_rate = audio_state.output_rate();
+    let latency = node_latency(sample_rate);
+
     // Create playback stream properties
     let playback_props = if let Some(target_id) = playback_target {
         properties! {
@@ -607,7 +944,7 @@ fn create_mixing_playback_stream(
             "node.description" => "Gecko Mixed Output",
             "target.object" => target_id.to_string(),
             "node.dont-reconnect" => "true",
-            "node.latency" => "1024/48000",
+            "node.latency" => latency.as_str(),
         }
     } else {
         properties! {
@@ -617,7 +954,7 @@ fn create_mixing_playback_stream(
             "node.name" => "Gecko Playback",
             "node.description" => "Gecko Mixed Output",
             "node.dont-reconnect" => "true",
-            "node.latency" => "1024/48000",
+            "node.latency" => latency.as_str(),
         }
     };
 
@@ -629,17 +966,13 @@ fn create_mixing_playback_stream(
         }
     };
 
-    // Create master EQ
-    let mut master_eq = gecko_dsp::Equalizer::new(48000.0);
-    
-    // CRITICAL: Apply initial Master EQ gains immediately!
-    // The atomic counter check in the callback might miss the initial state if counters match (both 0).
-    let initial_gains = audio_state.get_all_eq_gains();
-    for (band, &gain_db) in initial_gains.iter().enumerate() {
-        if gain_db.abs() > 0.001 {
-            let _ = master_eq.set_band_gain(band, gain_db);
-        }
-    }
+    // Build the master chain for the pipeline layout (EQ starts at the
+    // current gains, the limiter's lookahead is allocated here)
+    let channels = audio_state.channel_count();
+    let master_chain = build_master_chain(&audio_state.master_chain(), &audio_state, sample_rate, channels);
+    let (chain_sender, chain) = gecko_dsp::chain_channel(master_chain, CHAIN_BUFFER_SIZE);
+    let negotiated_rate = Arc::new(AtomicU32::new(sample_rate));
+    let chain_handle = ChainHandle::new(chain_sender, Arc::clone(&negotiated_rate), channels);
 
     // Pre-allocate buffers (max expected buffer size)
     const MAX_BUFFER_SIZE: usize = 48000; // ~1 second
@@ -650,10 +983,13 @@ fn create_mixing_playback_stream(
     let user_data = MixingPlaybackUserData {
         app_consumers_state,
         audio_state: Arc::clone(&audio_state),
-        master_eq,
-        last_master_eq_counter: 0,
+        chain,
+        negotiated_rate,
         mix_buffer,
         read_buffer,
+        channels,
+        loudness: audio_state.new_loudness_analyzer(None, sample_rate as f32),
+        sample_rate,
     };
 
     // Set up mixing playback callback
@@ -662,21 +998,20 @@ fn create_mixing_playback_stream(
         .state_changed(|_stream, _user_data, old, new| {
             tracing::debug!("Mixing playback stream state: {:?} -> {:?}", old, new);
         })
-        .process(|stream, user_data| {
-            // Mixing playback callback - read from all app consumers, mix, apply master EQ
-
-            // Check if master EQ needs updating
-            let current_counter = user_data.audio_state.eq_update_counter();
-            if current_counter != user_data.last_master_eq_counter {
-                let gains = user_data.audio_state.get_all_eq_gains();
-                for (band, gain_db) in gains.iter().enumerate() {
-                    if let Err(e) = user_data.master_eq.set_band_gain(band, *gain_db) {
-                        tracing::warn!("Failed to apply master EQ band {}: {:?}", band, e);
-                    }
-                }
-                user_data.last_master_eq_counter = current_counter;
+        .param_changed(|_stream, user_data, id, param| {
+            // Runs while `process` may be running: only store the rate, the
+            // PipeWire thread rebuilds the chain and `process` follows it
+            if let Some(rate) = negotiated_rate(id, param) {
+                tracing::debug!("Mixing playback negotiated {} Hz", rate);
+                // Capture streams resample to this rate
+                user_data.audio_state.set_output_rate(rate);
+                user_data.negotiated_rate.store(rate, Ordering::Relaxed);
             }
+        })
+        .process(|stream, user_data| {
+            user_data.follow_rate();
 
+            // Mixing playback callback - read from all app consumers, mix, run the master chain
             if let Some(mut buffer) = stream.dequeue_buffer() {
                 let datas = buffer.datas_mut();
                 if let Some(data) = datas.first_mut() {
@@ -712,7 +1047,7 @@ fn create_mixing_playback_stream(
                         // NOTE: Using try_write() because read_chunk requires &mut Consumer
                         // If locked (unlikely), skip this cycle to avoid blocking the audio callback
                         if let Some(mut guard) = user_data.app_consumers_state.consumers.try_write() {
-                            for (_app_name, consumer) in guard.iter_mut() {
+                            for (_app_name, consumer, read_clock) in guard.iter_mut() {
                                 // Try to read from this consumer
                                 if let Ok(chunk) = consumer.read_chunk(sample_count) {
                                     let slices = chunk.as_slices();
@@ -726,6 +1061,7 @@ fn create_mixing_playback_stream(
                                         }
                                     }
                                     chunk.commit_all();
+                                    read_clock.mark(sample_count);
                                 }
                             }
                         }
@@ -735,38 +1071,17 @@ fn create_mixing_playback_stream(
                             *sample = user_data.mix_buffer[i];
                         }
 
-                        // Apply master EQ if not bypassed
-                        if !user_data.audio_state.bypassed.load(Ordering::Relaxed) {
-                            user_data.master_eq.process_interleaved(samples);
-                        }
-
-                        // Apply master volume
-                        let volume = user_data.audio_state.master_volume();
-                        for sample in samples.iter_mut() {
-                            *sample *= volume;
-                        }
+                        // Run the master chain (by default EQ and crossfeed unless
+                        // bypassed, volume, then the soft clipper or limiter);
+                        // a newly sent chain is crossfaded in here
+                        user_data.chain.process(samples);
 
-                        // Apply soft clipping to prevent harsh digital distortion
-                        // This smoothly limits peaks that exceed the threshold
-                        user_data.audio_state.soft_clip_buffer(samples);
-
-                        // Calculate and store peak levels (after soft clipping)
-                        let mut peak_l = 0.0_f32;
-                        let mut peak_r = 0.0_f32;
-                        for (i, sample) in samples.iter().enumerate() {
-                            if i % 2 == 0 {
-                                peak_l = peak_l.max(sample.abs());
-                            } else {
-                                peak_r = peak_r.max(sample.abs());
-                            }
-                        }
-                        user_data.audio_state.set_peaks(peak_l, peak_r);
+                        // Measure loudness of the final output
+                        user_data.loudness.process_interleaved(samples);
 
-                        // Push samples to spectrum analyzer for FFT visualization
-                        // This is lock-free and won't block the audio callback
-                        for chunk in samples.chunks_exact(2) {
-                            user_data.audio_state.push_spectrum_sample(chunk[0], chunk[1]);
-                        }
+                        // Calculate peak levels (after the output stage) and feed the
+                        // spectrum analyzer; lock-free, surround folds onto L/R
+                        user_data.audio_state.update_meters(samples, user_data.channels);
 
                         // Update chunk metadata
                         let chunk = data.chunk_mut();
@@ -787,23 +1102,8 @@ fn create_mixing_playback_stream(
         }
     };
 
-    // Build audio format params
-    let mut audio_info = pw::spa::param::audio::AudioInfoRaw::new();
-    audio_info.set_format(pw::spa::param::audio::AudioFormat::F32LE);
-    audio_info.set_rate(48000);
-    audio_info.set_channels(2);
-
-    let audio_params_bytes: Vec<u8> = pw::spa::pod::serialize::PodSerializer::serialize(
-        std::io::Cursor::new(Vec::new()),
-        &pw::spa::pod::Value::Object(pw::spa::pod::Object {
-            type_: pw::spa::utils::SpaTypes::ObjectParamFormat.as_raw(),
-            id: pw::spa::param::ParamType::EnumFormat.as_raw(),
-            properties: audio_info.into(),
-        }),
-    )
-    .expect("Failed to serialize audio params")
-    .0
-    .into_inner();
+    // Build audio format params (pipeline layout, positioned channels)
+    let audio_params_bytes = audio_format_bytes(channels as u32);
 
     let audio_pod = Pod::from_bytes(&audio_params_bytes).expect("Failed to create audio Pod");
     let mut params = [audio_pod];
@@ -829,6 +1129,7 @@ fn create_mixing_playback_stream(
     // Store in local state
     local.mixing_playback_stream = Some(playback_stream);
     local.mixing_playback_listener = Some(listener);
+    local.master_chain = Some(chain_handle);
 
     tracing::debug!("Mixing playback stream created and active");
     true
@@ -877,6 +1178,7 @@ fn process_completed_app_links(
         ) {
             local.app_captures.insert(app_name.clone(), capture_state);
             local.per_app_mode = true;
+            update_app_delays(local);
             // Queue for monitor->capture link creation (like pending_capture_links for legacy)
             local.pending_app_monitor_links.push(app_name.clone());
             tracing::debug!("Started per-app capture for '{}' (pending monitor links)", app_name);
@@ -903,41 +1205,34 @@ fn create_app_capture_stream(
         sink_node_id
     );
 
-    // Create ring buffer for this app's audio
-    const RING_BUFFER_SIZE: usize = 48000 * 2; // 1 second stereo
-    let (producer, consumer) = rtrb::RingBuffer::new(RING_BUFFER_SIZE);
-
-    // Create EQ processor for this app
-    let mut eq = gecko_dsp::Equalizer::new(48000.0);
-
-    // Create atomic EQ gains (initialized from shared state if available)
-    let initial_eq = audio_state.get_stream_eq_all(app_name);
-    let eq_gains: Arc<[std::sync::atomic::AtomicU32; 10]> = Arc::new(std::array::from_fn(|i| {
-        std::sync::atomic::AtomicU32::new(initial_eq[i].to_bits())
-    }));
-    let eq_gains_for_callback = Arc::clone(&eq_gains);
+    // Interleaved layout shared by this app's sink, capture stream and the mixer
+    let channels = audio_state.channel_count();
 
-    // CRITICAL: Apply initial EQ gains to the DSP instance immediately!
-    // The atomic values alone won't update the DSP state until the next change triggers a counter mismatch.
-    for (band, &gain_db) in initial_eq.iter().enumerate() {
-        if gain_db.abs() > 0.001 {
-            let _ = eq.set_band_gain(band, gain_db);
-        }
-    }
+    // Audio enters the ring buffer at the output rate; the capture side
+    // starts there too and follows its own rate once negotiated
+    let sample_rate = audio_state.output_rate();
 
-    // Create EQ update counter (shared with callback)
-    let eq_update_counter = Arc::new(std::sync::atomic::AtomicU32::new(0));
-    let eq_update_counter_for_callback = Arc::clone(&eq_update_counter);
+    // Create ring buffer for this app's audio (1 second at the pipeline layout)
+    let (producer, consumer) = rtrb::RingBuffer::new(sample_rate as usize * channels);
+    let read_clock = Arc::new(ReadClock::new());
 
-    // Create bypass flag (shared with callback)
-    let initial_bypass = audio_state.is_stream_bypassed(app_name);
-    let bypassed = Arc::new(std::sync::atomic::AtomicBool::new(initial_bypass));
-    let bypassed_for_callback = Arc::clone(&bypassed);
+    // Build this app's chain; its EQ starts at the saved gains rather than
+    // ramping in from flat
+    let controls = AppControls::new(audio_state, app_name);
+    let app_chain = build_app_chain(
+        &audio_state.stream_chain(app_name),
+        app_name,
+        &controls,
+        audio_state,
+        sample_rate,
+        channels,
+    );
+    let (chain_sender, chain) = gecko_dsp::chain_channel(app_chain, CHAIN_BUFFER_SIZE);
+    let negotiated_rate = Arc::new(AtomicU32::new(sample_rate));
 
-    // Create per-app volume (initialize from shared state if available)
-    let initial_volume = audio_state.get_stream_volume(app_name);
-    let volume = Arc::new(std::sync::atomic::AtomicU32::new(initial_volume.to_bits()));
-    let volume_for_callback = Arc::clone(&volume);
+    // Create delay (set with every other app's by update_app_delays once added)
+    let delay_ms = Arc::new(std::sync::atomic::AtomicU32::new(0.0_f32.to_bits()));
+    let delay_ms_for_callback = Arc::clone(&delay_ms);
 
     // Create capture stream properties
     let stream_name = format!("Gecko Capture - {}", app_name);
@@ -965,12 +1260,16 @@ fn create_app_capture_stream(
     // Create user data for the callback (owns the producer)
     let user_data = AppCaptureUserData {
         producer,
-        equalizer: eq,
-        eq_gains: eq_gains_for_callback,
-        eq_update_counter: eq_update_counter_for_callback,
-        last_eq_update_counter: 0,
-        bypassed: bypassed_for_callback,
-        volume: volume_for_callback,
+        chain,
+        negotiated_rate: Arc::clone(&negotiated_rate),
+        audio_state: Arc::clone(audio_state),
+        loudness: audio_state.new_loudness_analyzer(Some(app_name), sample_rate as f32),
+        delay_ms: delay_ms_for_callback,
+        delay: audio_state.new_stream_delay(sample_rate as f32),
+        resampler: CaptureResampler::new(channels, sample_rate)
+            .with_drift_compensation(Arc::clone(&read_clock), audio_state.drift_meter(app_name)),
+        app_name: app_name.to_string(),
+        sample_rate,
     };
 
     // Set up capture stream listener with process callback
@@ -985,23 +1284,18 @@ fn create_app_capture_stream(
                 new
             );
         })
-        .process(|stream, user_data| {
-            // Per-app capture callback - read input, apply per-app EQ, write to ring buffer
-
-            // Check if EQ settings have been updated
-            let current_counter = user_data.eq_update_counter.load(Ordering::Relaxed);
-            if current_counter != user_data.last_eq_update_counter {
-                // Apply updated EQ gains
-                for band in 0..10 {
-                    let gain_bits = user_data.eq_gains[band].load(Ordering::Relaxed);
-                    let gain_db = f32::from_bits(gain_bits);
-                    if let Err(e) = user_data.equalizer.set_band_gain(band, gain_db) {
-                        tracing::warn!("Failed to apply EQ band {}: {:?}", band, e);
-                    }
-                }
-                user_data.last_eq_update_counter = current_counter;
+        .param_changed(|_stream, user_data, id, param| {
+            // Runs while `process` may be running: only store the rate, the
+            // PipeWire thread rebuilds the chain and `process` follows it
+            if let Some(rate) = negotiated_rate(id, param) {
+                tracing::debug!("Capture for '{}' negotiated {} Hz", user_data.app_name, rate);
+                user_data.negotiated_rate.store(rate, Ordering::Relaxed);
             }
+        })
+        .process(|stream, user_data| {
+            user_data.follow_rate();
 
+            // Per-app capture callback - read input, run the app's chain, write to ring buffer
             if let Some(mut buffer) = stream.dequeue_buffer() {
                 let datas = buffer.datas_mut();
                 if let Some(data) = datas.first_mut() {
@@ -1022,34 +1316,24 @@ fn create_app_capture_stream(
                             )
                         };
 
-                        // Apply per-app EQ if not bypassed
-                        if !user_data.bypassed.load(Ordering::Relaxed) {
-                            user_data.equalizer.process_interleaved(samples);
-                        }
+                        // Run the app's chain: by default EQ (unless bypassed), then
+                        // auto-leveling before volume so the app's volume still trims
+                        // relative to the other apps. A newly sent chain is crossfaded in.
+                        user_data.chain.process(samples);
 
-                        // Apply per-app volume (0.0 - 2.0, default 1.0)
-                        // This multiplies each sample by the volume factor
-                        let volume_bits = user_data.volume.load(Ordering::Relaxed);
-                        let volume = f32::from_bits(volume_bits);
-                        if (volume - 1.0).abs() > 0.001 {
-                            // Only apply if volume differs from unity gain
-                            for sample in samples.iter_mut() {
-                                *sample *= volume;
-                            }
-                        }
+                        // Measure this app's loudness as it enters the mix
+                        user_data.loudness.process_interleaved(samples);
 
-                        // Write to ring buffer for mixing
-                        if let Ok(mut write_chunk) = user_data.producer.write_chunk(samples.len()) {
-                            let (first, second) = write_chunk.as_mut_slices();
-                            let mut idx = 0;
-                            for dst in first.iter_mut().chain(second.iter_mut()) {
-                                if idx < samples.len() {
-                                    *dst = samples[idx];
-                                    idx += 1;
-                                }
-                            }
-                            write_chunk.commit(idx);
+                        // Hold back for A/V sync (crossfaded when the delay changes)
+                        let delay_ms = f32::from_bits(user_data.delay_ms.load(Ordering::Relaxed));
+                        if delay_ms != user_data.delay.delay_ms() {
+                            let _ = user_data.delay.set_delay_ms(delay_ms);
                         }
+                        user_data.delay.process_interleaved(samples);
+
+                        // Write to ring buffer for mixing, at the output rate
+                        let output_rate = user_data.audio_state.output_rate();
+                        user_data.resampler.write(samples, output_rate, &mut user_data.producer);
                     }
                 }
             }
@@ -1064,23 +1348,8 @@ fn create_app_capture_stream(
         }
     };
 
-    // Build audio format params
-    let mut audio_info = pw::spa::param::audio::AudioInfoRaw::new();
-    audio_info.set_format(pw::spa::param::audio::AudioFormat::F32LE);
-    audio_info.set_rate(48000);
-    audio_info.set_channels(2);
-
-    let audio_params_bytes: Vec<u8> = pw::spa::pod::serialize::PodSerializer::serialize(
-        std::io::Cursor::new(Vec::new()),
-        &pw::spa::pod::Value::Object(pw::spa::pod::Object {
-            type_: pw::spa::utils::SpaTypes::ObjectParamFormat.as_raw(),
-            id: pw::spa::param::ParamType::EnumFormat.as_raw(),
-            properties: audio_info.into(),
-        }),
-    )
-    .expect("Failed to serialize audio params")
-    .0
-    .into_inner();
+    // Build audio format params (pipeline layout, positioned channels)
+    let audio_params_bytes = audio_format_bytes(channels as u32);
 
     let audio_pod = Pod::from_bytes(&audio_params_bytes).expect("Failed to create audio Pod");
     let mut params = [audio_pod];
@@ -1107,23 +1376,107 @@ fn create_app_capture_stream(
     tracing::debug!("Created per-app capture stream for '{}'", app_name);
 
     // Add consumer to the shared state so the mixer can read from it
-    app_consumers_state.add_consumer(app_name.to_string(), consumer);
+    app_consumers_state.add_consumer(app_name.to_string(), consumer, read_clock);
 
     // Register this app in the shared state so the engine can emit discovery events
     audio_state.add_captured_app(app_name);
 
-    // Return the capture state (EQ gains/counter/bypass/volume are shared via Arc)
+    // Return the capture state (controls and the chain are shared with the callback)
     Some(AppCaptureState {
         app_name: app_name.to_string(),
         stream: capture_stream,
         listener,
-        eq_gains,
-        eq_update_counter,
-        bypassed,
-        volume,
+        controls,
+        chain: ChainHandle::new(chain_sender, negotiated_rate, channels),
+        delay_ms,
     })
 }
 
+/// Send chains rebuilt for a newly negotiated rate to the streams that need them
+///
+/// `param_changed` only records the rate; building here keeps allocation off
+/// the audio thread, and the callbacks crossfade to the new chain.
+fn rebuild_chains_for_rate(local: &mut LocalState) {
+    let Some(audio_state) = local.audio_state.clone() else {
+        return;
+    };
+    if let Some(handle) = local.master_chain.as_mut().filter(|handle| handle.needs_rebuild()) {
+        let rebuilt = build_master_chain(
+            &audio_state.master_chain(),
+            &audio_state,
+            handle.sample_rate(),
+            handle.channels(),
+        );
+        handle.send(rebuilt);
+    }
+    for capture in local.app_captures.values_mut() {
+        if !capture.chain.needs_rebuild() {
+            continue;
+        }
+        let rebuilt = build_app_chain(
+            &audio_state.stream_chain(&capture.app_name),
+            &capture.app_name,
+            &capture.controls,
+            &audio_state,
+            capture.chain.sample_rate(),
+            capture.chain.channels(),
+        );
+        capture.chain.send(rebuilt);
+    }
+}
+
+/// Rebuild the master (`app_name: None`) or a captured app's chain from shared
+/// state and queue it for the callback to crossfade to
+///
+/// Allocates; runs on the PipeWire thread.
+fn rebuild_chain(local: &mut LocalState, app_name: Option<&str>) {
+    let Some(audio_state) = local.audio_state.clone() else {
+        return;
+    };
+    match app_name {
+        None => {
+            if let Some(handle) = local.master_chain.as_mut() {
+                let rebuilt = build_master_chain(
+                    &audio_state.master_chain(),
+                    &audio_state,
+                    handle.sample_rate(),
+                    handle.channels(),
+                );
+                handle.send(rebuilt);
+            }
+        }
+        Some(app_name) => {
+            if let Some(capture) = local.app_captures.get_mut(app_name) {
+                let rebuilt = build_app_chain(
+                    &audio_state.stream_chain(app_name),
+                    app_name,
+                    &capture.controls,
+                    &audio_state,
+                    capture.chain.sample_rate(),
+                    capture.chain.channels(),
+                );
+                capture.chain.send(rebuilt);
+            }
+        }
+    }
+}
+
+/// Push each captured app's delay to its capture callback
+///
+/// A negative sync offset delays every other app, so this runs whenever an
+/// offset changes or an app starts or stops being captured.
+fn update_app_delays(local: &LocalState) {
+    if let Some(ref audio_state) = local.audio_state {
+        let names: Vec<&str> = local.app_captures.keys().map(String::as_str).collect();
+        let delays = audio_state.stream_delays_ms(&names);
+        for (name, delay_ms) in names.iter().zip(delays) {
+            if let Some(capture) = local.app_captures.get(*name) {
+                capture.delay_ms.store(delay_ms.to_bits(), Ordering::Release);
+            }
+        }
+    }
+}
+
 /// Try to rewire capture links from wrong source to Gecko Audio monitor.
 ///
 /// WirePlumber auto-connects our capture stream to the default source (e.g. microphone),
@@ -1271,6 +1624,7 @@ fn try_create_capture_links(local: &LocalState, core: &pw::core::Core) -> usize
 /// Returns a list of app names that had their links successfully created.
 fn try_create_per_app_capture_links(local: &LocalState, core: &pw::core::Core) -> Vec<String> {
     let mut completed = Vec::new();
+    let channels = local.audio_state.as_ref().map_or(2, |state| state.channel_count());
 
     for app_name in &local.pending_app_monitor_links {
         // Find the per-app sink node (Gecko-{app_name})
@@ -1301,34 +1655,32 @@ fn try_create_per_app_capture_links(local: &LocalState, core: &pw::core::Core) -
         };
 
         // Find monitor ports on the per-app sink (output ports with "monitor" in name)
-        let mut monitor_ports: Vec<u32> = local.ports
-            .iter()
-            .filter(|(_, p)| {
+        let monitor_ports: Vec<&PwPortInfo> = local.ports
+            .values()
+            .filter(|p| {
                 p.node_id == sink_id
                     && matches!(p.direction, PortDirection::Output)
                     && p.name.contains("monitor")
             })
-            .map(|(id, _)| *id)
             .collect();
-        monitor_ports.sort();
 
         // Find input ports on the per-app capture stream
-        let mut input_ports: Vec<u32> = local.ports
-            .iter()
-            .filter(|(_, p)| {
+        let input_ports: Vec<&PwPortInfo> = local.ports
+            .values()
+            .filter(|p| {
                 p.node_id == capture_id
                     && matches!(p.direction, PortDirection::Input)
             })
-            .map(|(id, _)| *id)
             .collect();
-        input_ports.sort();
 
-        if monitor_ports.len() < 2 || input_ports.len() < 2 {
+        if monitor_ports.len() < channels || input_ports.len() < channels {
             tracing::debug!(
-                "Waiting for per-app ports for '{}': monitor={}/2, input={}/2",
+                "Waiting for per-app ports for '{}': monitor={}/{}, input={}/{}",
                 app_name,
                 monitor_ports.len(),
-                input_ports.len()
+                channels,
+                input_ports.len(),
+                channels
             );
             continue;
         }
@@ -1340,12 +1692,11 @@ fn try_create_per_app_capture_links(local: &LocalState, core: &pw::core::Core) -
             capture_id
         );
 
-        // Create links: monitor_FL -> input_FL, monitor_FR -> input_FR
+        // Create links by channel: monitor_FL -> input_FL, monitor_FR -> input_FR, ...
+        let pairs = pair_ports_by_channel(&monitor_ports, &input_ports);
+        let links_expected = pairs.len();
         let mut links_created = 0;
-        for i in 0..2 {
-            let source = monitor_ports[i];
-            let dest = input_ports[i];
-
+        for (source, dest) in pairs {
             // Check if link already exists
             let exists = local.links.values().any(|l| {
                 l.output_port == source && l.input_port == dest
@@ -1384,7 +1735,7 @@ fn try_create_per_app_capture_links(local: &LocalState, core: &pw::core::Core) -
             }
         }
 
-        if links_created >= 2 {
+        if links_expected >= channels && links_created >= links_expected {
             tracing::debug!("Per-app capture links created for '{}'", app_name);
             completed.push(app_name.clone());
         }
@@ -1793,6 +2144,16 @@ pub fn pipewire_thread_main(
                     local.pending_app_monitor_links.retain(|a| a != &app_name);
                 }
             }
+
+            // Audio callbacks only flag rejected EQ updates; report them here
+            if let Some(ref audio_state) = local.audio_state {
+                if audio_state.take_eq_error() {
+                    tracing::warn!("An EQ update was rejected by the audio thread");
+                }
+            }
+
+            // Streams that renegotiated their rate get a chain built for it
+            rebuild_chains_for_rate(&mut local);
         }
     }
 
@@ -1823,7 +2184,7 @@ fn handle_command(
                 "media.class" => "Audio/Sink",
                 "audio.channels" => config.channels.to_string().as_str(),
                 "audio.rate" => config.sample_rate.to_string().as_str(),
-                "audio.position" => "FL,FR",
+                "audio.position" => audio_position_prop(config.channels).as_str(),
                 "node.pause-on-idle" => "false", // Prevent suspension when no apps are playing
                 "node.always-process" => "true", // Keep processing to ensure monitor output
                 "audio.volume" => "1.0", // Force full volume
@@ -1967,14 +2328,17 @@ fn handle_command(
                 }
             };
 
+            // Build for the last known output rate; param_changed corrects it once negotiated
+            let sample_rate = audio_state.output_rate();
+            let latency = node_latency(sample_rate);
+
             // Create ring buffer for audio transfer
             // We need a large buffer because some systems (like this one) request huge chunks (341ms)
-            // 1 second buffer = 48000 * 2 (stereo) = 96000 samples
-            const RING_BUFFER_SIZE: usize = 48000 * 2; 
-            let (producer, consumer) = rtrb::RingBuffer::new(RING_BUFFER_SIZE);
+            // 1 second buffer at the output rate, stereo
+            let (producer, consumer) = rtrb::RingBuffer::new(sample_rate as usize * 2);
 
             // Initialize EQ processor
-            let eq = gecko_dsp::Equalizer::new(48000.0);
+            let eq = gecko_dsp::Equalizer::new(sample_rate as f32);
 
             // Note: capture_target_id will be updated after we find the actual node ID
             local.playback_target_id = playback_target;
@@ -1998,7 +2362,7 @@ fn handle_command(
                     "node.description" => "Gecko Playback Stream",
                     "target.object" => target_id.to_string(),
                     "node.dont-reconnect" => "true",
-                    "node.latency" => "1024/48000",
+                    "node.latency" => latency.as_str(),
                     "stream.props" => "{ volume = 1.0 }", // Force volume
                 }
             } else {
@@ -2009,7 +2373,7 @@ fn handle_command(
                     "node.name" => "Gecko Playback",
                     "node.description" => "Gecko Playback Stream",
                     "node.dont-reconnect" => "true",
-                    "node.latency" => "1024/48000",
+                    "node.latency" => latency.as_str(),
                     "stream.props" => "{ volume = 1.0 }", // Force volume
                 }
             };
@@ -2062,7 +2426,7 @@ fn handle_command(
             let playback_user_data = PlaybackUserData {
                 consumer,
                 audio_state: Arc::clone(&audio_state),
-                master_eq: gecko_dsp::Equalizer::new(48000.0),
+                master_eq: gecko_dsp::Equalizer::new(sample_rate as f32),
                 last_master_eq_counter: 0,
             };
 
@@ -2072,6 +2436,12 @@ fn handle_command(
                     tracing::debug!("Playback stream state: {:?} -> {:?}", old, new);
                     let _ = (stream, user_data); // Suppress unused warnings
                 })
+                .param_changed(|_stream, user_data, id, param| {
+                    // The capture side resamples to this rate
+                    if let Some(rate) = negotiated_rate(id, param) {
+                        user_data.audio_state.set_output_rate(rate);
+                    }
+                })
                 .process(|stream, user_data| {
                     // Playback callback - read from ring buffer and output
                     static PLAYBACK_CALL_COUNT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
@@ -2145,6 +2515,9 @@ fn handle_command(
                 equalizer: eq,
                 audio_state: Arc::clone(&audio_state),
                 last_eq_update_counter: 0, // Will be updated on first callback if needed
+                resampler: CaptureResampler::new(2, sample_rate),
+                negotiated_rate: AtomicU32::new(sample_rate),
+                sample_rate,
             };
 
             let capture_listener = capture_stream
@@ -2153,7 +2526,15 @@ fn handle_command(
                     tracing::debug!("Capture stream state: {:?} -> {:?}", old, new);
                     let _ = (stream, user_data);
                 })
+                .param_changed(|_stream, user_data, id, param| {
+                    // Runs while `process` may be running: only store the rate
+                    if let Some(rate) = negotiated_rate(id, param) {
+                        user_data.negotiated_rate.store(rate, Ordering::Relaxed);
+                    }
+                })
                 .process(|stream, user_data| {
+                    user_data.follow_rate();
+
                     // Capture callback - read input, process DSP, write to ring buffer
                     static CAPTURE_CALL_COUNT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
                     let count = CAPTURE_CALL_COUNT.fetch_add(1, Ordering::Relaxed);
@@ -2166,12 +2547,7 @@ fn handle_command(
                     let current_eq_counter = user_data.audio_state.eq_update_counter();
                     if current_eq_counter != user_data.last_eq_update_counter {
                         // EQ settings changed - apply all band gains to our local equalizer
-                        let gains = user_data.audio_state.get_all_eq_gains();
-                        for (band, gain_db) in gains.iter().enumerate() {
-                            if let Err(e) = user_data.equalizer.set_band_gain(band, *gain_db) {
-                                tracing::warn!("Failed to apply EQ band {}: {:?}", band, e);
-                            }
-                        }
+                        user_data.audio_state.apply_eq(&mut user_data.equalizer);
                         user_data.last_eq_update_counter = current_eq_counter;
                         tracing::debug!("Applied EQ update (counter={})", current_eq_counter);
                     }
@@ -2201,7 +2577,7 @@ fn handle_command(
 
                                 // Apply DSP processing (EQ) if not bypassed
                                 if !user_data.audio_state.bypassed.load(Ordering::Relaxed) {
-                                    user_data.equalizer.process_interleaved(samples);
+                                    user_data.equalizer.process_block(samples);
                                 }
 
                                 // Apply master volume
@@ -2222,18 +2598,13 @@ fn handle_command(
                                 }
                                 user_data.audio_state.set_peaks(peak_l, peak_r);
 
-                                // Write to ring buffer for playback
-                                if let Ok(mut write_chunk) = user_data.producer.write_chunk(samples.len()) {
-                                    let (first, second) = write_chunk.as_mut_slices();
-                                    let mut idx = 0;
-                                    for dst in first.iter_mut().chain(second.iter_mut()) {
-                                        if idx < samples.len() {
-                                            *dst = samples[idx];
-                                            idx += 1;
-                                        }
-                                    }
-                                    write_chunk.commit(idx);
-                                }
+                                // Write to ring buffer for playback, at the playback rate
+                                let output_rate = user_data.audio_state.output_rate();
+                                user_data.resampler.write(
+                                    samples,
+                                    output_rate,
+                                    &mut user_data.producer,
+                                );
                             }
                         }
                     }
@@ -2252,11 +2623,10 @@ fn handle_command(
                 }
             };
 
-            // Build audio format params for F32LE stereo at 48kHz
+            // Build audio format params for F32LE stereo at the graph rate
             // PipeWire requires format params to negotiate audio parameters
             let mut audio_info = pw::spa::param::audio::AudioInfoRaw::new();
             audio_info.set_format(pw::spa::param::audio::AudioFormat::F32LE);
-            audio_info.set_rate(48000);
             audio_info.set_channels(2);
 
             // Serialize the audio info into a pod for format negotiation
@@ -2484,6 +2854,7 @@ fn handle_command(
                 }
                 local.mixing_playback_listener = None;
                 local.mixing_playback_stream = None;
+                local.master_chain = None;
 
                 // Clear stale "Gecko Playback" node from local registry to avoid confusion
                 let old_playback_node_id: Option<u32> = local.nodes
@@ -2530,6 +2901,10 @@ fn handle_command(
                     }
                 };
 
+                // Build for the last known output rate; param_changed corrects it once negotiated
+                let sample_rate = audio_state.output_rate();
+                let latency = node_latency(sample_rate);
+
                 // Create new mixing playback stream with target specified by NAME
                 // PipeWire resolves the name to the current node ID at connection time
                 let playback_props = properties! {
@@ -2540,7 +2915,7 @@ fn handle_command(
                     "node.description" => "Gecko Mixed Output",
                     "target.object" => target_name.as_str(),
                     "node.dont-reconnect" => "true",
-                    "node.latency" => "1024/48000",
+                    "node.latency" => latency.as_str(),
                 };
 
                 let playback_stream = match Stream::new(core, "gecko-mixing-playback", playback_props) {
@@ -2554,12 +2929,14 @@ fn handle_command(
                     }
                 };
 
-                // Create master EQ for the new stream
-                let mut master_eq = gecko_dsp::Equalizer::new(48000.0);
-                let initial_gains = audio_state.get_all_eq_gains();
-                for (band, gain_db) in initial_gains.iter().enumerate() {
-                    let _ = master_eq.set_band_gain(band, *gain_db);
-                }
+                // Build the master chain for the new stream (pipeline layout)
+                let channels = audio_state.channel_count();
+                let master_chain =
+                    build_master_chain(&audio_state.master_chain(), &audio_state, sample_rate, channels);
+                let (chain_sender, chain) = gecko_dsp::chain_channel(master_chain, CHAIN_BUFFER_SIZE);
+                let negotiated_rate = Arc::new(AtomicU32::new(sample_rate));
+                let chain_handle =
+                    ChainHandle::new(chain_sender, Arc::clone(&negotiated_rate), channels);
 
                 // Create user data for mixing callback
                 // Note: Buffer sizes must match MAX_BUFFER_SIZE (48000) used in the main StartStreaming handler
@@ -2568,10 +2945,13 @@ fn handle_command(
                 let user_data = MixingPlaybackUserData {
                     app_consumers_state,
                     audio_state: Arc::clone(&audio_state),
-                    master_eq,
-                    last_master_eq_counter: audio_state.eq_update_counter(),
+                    chain,
+                    negotiated_rate,
                     mix_buffer: vec![0.0f32; MAX_BUFFER_SIZE],
                     read_buffer: vec![0.0f32; MAX_BUFFER_SIZE],
+                    channels,
+                    loudness: audio_state.new_loudness_analyzer(None, sample_rate as f32),
+                    sample_rate,
                 };
 
                 // Set up mixing playback callback (duplicated from create_mixing_playback_stream)
@@ -2582,21 +2962,20 @@ fn handle_command(
                     .state_changed(|_stream, _user_data, old, new| {
                         tracing::debug!("Mixing playback stream state: {:?} -> {:?}", old, new);
                     })
-                    .process(|stream, user_data| {
-                        // Mixing playback callback - read from all app consumers, mix, apply master EQ
-
-                        // Check if master EQ needs updating
-                        let current_counter = user_data.audio_state.eq_update_counter();
-                        if current_counter != user_data.last_master_eq_counter {
-                            let gains = user_data.audio_state.get_all_eq_gains();
-                            for (band, gain_db) in gains.iter().enumerate() {
-                                if let Err(e) = user_data.master_eq.set_band_gain(band, *gain_db) {
-                                    tracing::warn!("Failed to apply master EQ band {}: {:?}", band, e);
-                                }
-                            }
-                            user_data.last_master_eq_counter = current_counter;
+                    .param_changed(|_stream, user_data, id, param| {
+                        // Runs while `process` may be running: only store the rate, the
+                        // PipeWire thread rebuilds the chain and `process` follows it
+                        if let Some(rate) = negotiated_rate(id, param) {
+                            tracing::debug!("Mixing playback negotiated {} Hz", rate);
+                            // Capture streams resample to this rate
+                            user_data.audio_state.set_output_rate(rate);
+                            user_data.negotiated_rate.store(rate, Ordering::Relaxed);
                         }
+                    })
+                    .process(|stream, user_data| {
+                        user_data.follow_rate();
 
+                        // Mixing playback callback - read from all app consumers, mix, run the master chain
                         if let Some(mut buffer) = stream.dequeue_buffer() {
                             let datas = buffer.datas_mut();
                             if let Some(data) = datas.first_mut() {
@@ -2625,7 +3004,7 @@ fn handle_command(
 
                                     // Read from all app consumers and mix
                                     if let Some(mut guard) = user_data.app_consumers_state.consumers.try_write() {
-                                        for (_app_name, consumer) in guard.iter_mut() {
+                                        for (_app_name, consumer, read_clock) in guard.iter_mut() {
                                             if let Ok(chunk) = consumer.read_chunk(sample_count) {
                                                 let slices = chunk.as_slices();
                                                 let mut idx = 0;
@@ -2636,6 +3015,7 @@ fn handle_command(
                                                     }
                                                 }
                                                 chunk.commit_all();
+                                                read_clock.mark(sample_count);
                                             }
                                         }
                                     }
@@ -2645,34 +3025,13 @@ fn handle_command(
                                         *sample = user_data.mix_buffer[i];
                                     }
 
-                                    // Apply master EQ if not bypassed
-                                    if !user_data.audio_state.bypassed.load(Ordering::Relaxed) {
-                                        user_data.master_eq.process_interleaved(samples);
-                                    }
-
-                                    // Apply master volume
-                                    let volume = user_data.audio_state.master_volume();
-                                    for sample in samples.iter_mut() {
-                                        *sample *= volume;
-                                    }
+                                    // Run the master chain (crossfades in a newly sent one)
+                                    user_data.chain.process(samples);
+                                    user_data.loudness.process_interleaved(samples);
 
-                                    // Calculate and store peak levels
-                                    let mut peak_l = 0.0_f32;
-                                    let mut peak_r = 0.0_f32;
-                                    for (i, sample) in samples.iter().enumerate() {
-                                        if i % 2 == 0 {
-                                            peak_l = peak_l.max(sample.abs());
-                                        } else {
-                                            peak_r = peak_r.max(sample.abs());
-                                        }
-                                    }
-                                    user_data.audio_state.set_peaks(peak_l, peak_r);
-
-                                    // Push samples to spectrum analyzer for FFT visualization
-                                    // This is lock-free and won't block the audio callback
-                                    for chunk in samples.chunks_exact(2) {
-                                        user_data.audio_state.push_spectrum_sample(chunk[0], chunk[1]);
-                                    }
+                                    // Calculate peak levels and feed the spectrum analyzer
+                                    // (lock-free, surround folds onto L/R)
+                                    user_data.audio_state.update_meters(samples, user_data.channels);
 
                                     // Update chunk metadata
                                     let chunk = data.chunk_mut();
@@ -2686,23 +3045,8 @@ fn handle_command(
                     .register()
                     .expect("Failed to register mixing playback listener");
 
-                // Build audio format params (F32LE stereo @ 48kHz)
-                let mut audio_info = pw::spa::param::audio::AudioInfoRaw::new();
-                audio_info.set_format(pw::spa::param::audio::AudioFormat::F32LE);
-                audio_info.set_rate(48000);
-                audio_info.set_channels(2);
-
-                let audio_params_bytes: Vec<u8> = pw::spa::pod::serialize::PodSerializer::serialize(
-                    std::io::Cursor::new(Vec::new()),
-                    &pw::spa::pod::Value::Object(pw::spa::pod::Object {
-                        type_: pw::spa::utils::SpaTypes::ObjectParamFormat.as_raw(),
-                        id: pw::spa::param::ParamType::EnumFormat.as_raw(),
-                        properties: audio_info.into(),
-                    }),
-                )
-                .expect("Failed to serialize audio params")
-                .0
-                .into_inner();
+                // Build audio format params (F32LE, pipeline layout)
+                let audio_params_bytes = audio_format_bytes(channels as u32);
 
                 let audio_pod = Pod::from_bytes(&audio_params_bytes).expect("Failed to create audio Pod");
                 let mut params = [audio_pod];
@@ -2736,6 +3080,7 @@ fn handle_command(
                 // Store in local state
                 local.mixing_playback_stream = Some(playback_stream);
                 local.mixing_playback_listener = Some(listener);
+                local.master_chain = Some(chain_handle);
 
                 tracing::debug!("Successfully switched mixing playback target to '{}'", target_name);
                 let _ = response_tx.send(PwResponse::PlaybackTargetSwitched { response_id });
@@ -2835,9 +3180,12 @@ fn handle_command(
                 }
             };
 
-            // Create new ring buffer
-            const RING_BUFFER_SIZE: usize = 48000 * 2;
-            let (producer, consumer) = rtrb::RingBuffer::new(RING_BUFFER_SIZE);
+            // Build for the last known output rate; param_changed corrects it once negotiated
+            let sample_rate = audio_state.output_rate();
+            let latency = node_latency(sample_rate);
+
+            // Create new ring buffer (1 second at the output rate, stereo)
+            let (producer, consumer) = rtrb::RingBuffer::new(sample_rate as usize * 2);
 
             // Clear the old target ID since we're using name-based targeting now
             local.playback_target_id = None;
@@ -2853,7 +3201,7 @@ fn handle_command(
                 "node.description" => "Gecko Playback Stream",
                 "target.object" => target_name.as_str(),
                 "node.dont-reconnect" => "true",
-                "node.latency" => "1024/48000",
+                "node.latency" => latency.as_str(),
                 "stream.props" => "{ volume = 1.0 }",
             };
 
@@ -2899,7 +3247,7 @@ fn handle_command(
             let playback_user_data = PlaybackUserData {
                 consumer,
                 audio_state: Arc::clone(&audio_state),
-                master_eq: gecko_dsp::Equalizer::new(48000.0),
+                master_eq: gecko_dsp::Equalizer::new(sample_rate as f32),
                 last_master_eq_counter: 0,
             };
 
@@ -2909,6 +3257,11 @@ fn handle_command(
                     tracing::debug!("Playback stream state: {:?} -> {:?}", old, new);
                     let _ = (stream, user_data);
                 })
+                .param_changed(|_stream, user_data, id, param| {
+                    if let Some(rate) = negotiated_rate(id, param) {
+                        user_data.audio_state.set_output_rate(rate);
+                    }
+                })
                 .process(|stream, user_data| {
                     if let Some(mut buffer) = stream.dequeue_buffer() {
                         let datas = buffer.datas_mut();
@@ -2955,8 +3308,11 @@ fn handle_command(
             let capture_user_data = CaptureUserData {
                 producer,
                 audio_state: Arc::clone(&audio_state),
-                equalizer: gecko_dsp::Equalizer::new(48000.0),
+                equalizer: gecko_dsp::Equalizer::new(sample_rate as f32),
                 last_eq_update_counter: 0,
+                resampler: CaptureResampler::new(2, sample_rate),
+                negotiated_rate: AtomicU32::new(sample_rate),
+                sample_rate,
             };
 
             let capture_listener = capture_stream
@@ -2965,7 +3321,15 @@ fn handle_command(
                     tracing::debug!("Capture stream state: {:?} -> {:?}", old, new);
                     let _ = (stream, user_data);
                 })
+                .param_changed(|_stream, user_data, id, param| {
+                    // Runs while `process` may be running: only store the rate
+                    if let Some(rate) = negotiated_rate(id, param) {
+                        user_data.negotiated_rate.store(rate, Ordering::Relaxed);
+                    }
+                })
                 .process(|stream, user_data| {
+                    user_data.follow_rate();
+
                     // Debug: Log counter values periodically to diagnose EQ sync
                     static SWITCH_CAPTURE_CALLS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
                     let call_count = SWITCH_CAPTURE_CALLS.fetch_add(1, Ordering::Relaxed);
@@ -2973,10 +3337,7 @@ fn handle_command(
                     // Check if EQ settings have been updated via the shared state
                     let current_eq_counter = user_data.audio_state.eq_update_counter();
                     if current_eq_counter != user_data.last_eq_update_counter {
-                        let gains = user_data.audio_state.get_all_eq_gains();
-                        for (band, gain_db) in gains.iter().enumerate() {
-                            let _ = user_data.equalizer.set_band_gain(band, *gain_db);
-                        }
+                        user_data.audio_state.apply_eq(&mut user_data.equalizer);
                         user_data.last_eq_update_counter = current_eq_counter;
                         tracing::debug!(
                             "[SwitchCapture] Applied EQ update (counter={})",
@@ -3013,7 +3374,7 @@ fn handle_command(
 
                                 // Apply DSP processing (EQ) if not bypassed
                                 if !user_data.audio_state.bypassed.load(Ordering::Relaxed) {
-                                    user_data.equalizer.process_interleaved(samples);
+                                    user_data.equalizer.process_block(samples);
                                 }
 
                                 // Apply master volume
@@ -3034,18 +3395,13 @@ fn handle_command(
                                 }
                                 user_data.audio_state.set_peaks(peak_l, peak_r);
 
-                                // Write to ring buffer for playback
-                                if let Ok(mut write_chunk) = user_data.producer.write_chunk(samples.len()) {
-                                    let (first, second) = write_chunk.as_mut_slices();
-                                    let mut idx = 0;
-                                    for dst in first.iter_mut().chain(second.iter_mut()) {
-                                        if idx < samples.len() {
-                                            *dst = samples[idx];
-                                            idx += 1;
-                                        }
-                                    }
-                                    write_chunk.commit(idx);
-                                }
+                                // Write to ring buffer for playback, at the playback rate
+                                let output_rate = user_data.audio_state.output_rate();
+                                user_data.resampler.write(
+                                    samples,
+                                    output_rate,
+                                    &mut user_data.producer,
+                                );
                             }
                         }
                     }
@@ -3053,10 +3409,9 @@ fn handle_command(
                 .register()
                 .expect("Failed to register capture listener");
 
-            // Build audio format params for F32LE stereo at 48kHz
+            // Build audio format params for F32LE stereo at the graph rate
             let mut audio_info = pw::spa::param::audio::AudioInfoRaw::new();
             audio_info.set_format(pw::spa::param::audio::AudioFormat::F32LE);
-            audio_info.set_rate(48000);
             audio_info.set_channels(2);
 
             let audio_params_bytes: Vec<u8> = pw::spa::pod::serialize::PodSerializer::serialize(
@@ -3142,6 +3497,52 @@ fn handle_command(
             }
         }
 
+        PwCommand::UpdateEqBandParams { band, params } => {
+            // Fire-and-forget: same path as gains, the callback re-syncs on counter change
+            let local = local_state.borrow();
+            if let Some(ref state) = local.audio_state {
+                state.set_eq_band_params(band, params);
+                tracing::debug!("Queued EQ band {} shape update: {:?}", band, params);
+            }
+        }
+
+        PwCommand::SetEqBandCount(band_count) => {
+            // Fire-and-forget: switch master and every per-app EQ to a new band layout
+            // Gains reset to flat; the engine re-sends the remapped curves afterwards
+            let local = local_state.borrow();
+            if let Some(ref state) = local.audio_state {
+                if !state.set_band_count(band_count) {
+                    tracing::warn!("Ignoring invalid EQ band count {}", band_count);
+                    return;
+                }
+            }
+            let layout = gecko_dsp::band_layout_params(band_count);
+            for (app_name, capture) in &local.app_captures {
+                for gain in capture.controls.eq_gains.iter() {
+                    gain.store(0.0_f32.to_bits(), Ordering::Release);
+                }
+                for (slot, params) in capture.controls.band_params.iter().zip(layout) {
+                    slot.store(params);
+                }
+                if let Some(ref state) = local.audio_state {
+                    capture.controls.refresh_preamp(state, app_name);
+                }
+                capture.controls.eq_update_counter.fetch_add(1, Ordering::Release);
+            }
+            tracing::debug!("Switched EQ to {} bands", band_count);
+        }
+
+        PwCommand::RefreshAppPreamps => {
+            // Fire-and-forget: the peak search runs here, never in the capture callbacks
+            let local = local_state.borrow();
+            if let Some(ref state) = local.audio_state {
+                for (app_name, capture) in &local.app_captures {
+                    capture.controls.refresh_preamp(state, app_name);
+                    capture.controls.eq_update_counter.fetch_add(1, Ordering::Release);
+                }
+            }
+        }
+
         PwCommand::SetVolume(volume) => {
             // Fire-and-forget: update volume via atomic
             let local = local_state.borrow();
@@ -3352,14 +3753,14 @@ fn handle_command(
             }
 
             // Create a virtual sink named "Gecko-{AppName}"
+            let channels = local.audio_state.as_ref().map_or(2, |state| state.channel_count()) as u32;
             let sink_name = format!("Gecko-{}", app_name);
             let props = properties! {
                 "factory.name" => "support.null-audio-sink",
                 "node.name" => sink_name.as_str(),
                 "media.class" => "Audio/Sink",
-                "audio.channels" => "2",
-                "audio.rate" => "48000",
-                "audio.position" => "FL,FR",
+                "audio.channels" => channels.to_string().as_str(),
+                "audio.position" => audio_position_prop(channels).as_str(),
                 "node.pause-on-idle" => "false",
                 "node.always-process" => "true",
                 "audio.volume" => "1.0",
@@ -3416,6 +3817,7 @@ fn handle_command(
                     let _ = capture.stream.disconnect();
                     tracing::debug!("Stopped capture for '{}' before sink destruction", app_name);
                 }
+                update_app_delays(&local);
             }
 
             // Remove the sink (dropping the proxy destroys the PipeWire object)
@@ -3465,11 +3867,15 @@ fn handle_command(
             let local = local_state.borrow();
 
             if let Some(capture) = local.app_captures.get(&app_name) {
-                if band < 10 {
+                let band_count = local.audio_state.as_ref().map_or(gecko_dsp::EQ_BANDS.len(), |s| s.band_count());
+                if band < band_count {
                     // Store gain as atomic u32 bits for lock-free access in audio callback
-                    capture.eq_gains[band].store(gain_db.to_bits(), Ordering::Release);
+                    capture.controls.eq_gains[band].store(gain_db.to_bits(), Ordering::Release);
+                    if let Some(ref state) = local.audio_state {
+                        capture.controls.refresh_preamp(state, &app_name);
+                    }
                     // Increment counter to signal callback that gains have changed
-                    capture.eq_update_counter.fetch_add(1, Ordering::Release);
+                    capture.controls.eq_update_counter.fetch_add(1, Ordering::Release);
                     tracing::debug!(
                         "Updated EQ band {} = {:.1}dB for app '{}'",
                         band,
@@ -3487,13 +3893,37 @@ fn handle_command(
             }
         }
 
+        PwCommand::UpdateAppBandParams { app_name, band, params } => {
+            // Update per-app band shape via atomic shared state
+            let local = local_state.borrow();
+
+            if let Some(capture) = local.app_captures.get(&app_name) {
+                let band_count = local.audio_state.as_ref().map_or(gecko_dsp::EQ_BANDS.len(), |s| s.band_count());
+                if band < band_count && params.validate().is_ok() {
+                    capture.controls.band_params[band].store(params);
+                    if let Some(ref state) = local.audio_state {
+                        capture.controls.refresh_preamp(state, &app_name);
+                    }
+                    capture.controls.eq_update_counter.fetch_add(1, Ordering::Release);
+                    tracing::debug!("Updated EQ band {} shape for app '{}': {:?}", band, app_name, params);
+                } else {
+                    tracing::warn!("Invalid EQ band {} shape for app '{}'", band, app_name);
+                }
+            } else {
+                tracing::debug!(
+                    "App '{}' not found in captures (may not be streaming yet)",
+                    app_name
+                );
+            }
+        }
+
         PwCommand::SetAppBypass { app_name, bypassed } => {
             // Update per-app bypass state via atomic shared state
             // When bypassed, the capture callback passes audio through without EQ processing
             let local = local_state.borrow();
 
             if let Some(capture) = local.app_captures.get(&app_name) {
-                capture.bypassed.store(bypassed, Ordering::Release);
+                capture.controls.bypassed.store(bypassed, Ordering::Release);
                 tracing::debug!(
                     "Set bypass = {} for app '{}'",
                     bypassed,
@@ -3507,6 +3937,90 @@ fn handle_command(
             }
         }
 
+        PwCommand::SetAppAutoLevel { app_name, enabled } => {
+            // The capture callback levels toward the shared target while enabled
+            let local = local_state.borrow();
+
+            if let Some(capture) = local.app_captures.get(&app_name) {
+                capture.controls.auto_level.store(enabled, Ordering::Release);
+                tracing::debug!("Set auto-level = {} for app '{}'", enabled, app_name);
+            } else {
+                tracing::debug!(
+                    "App '{}' not found in captures (may not be streaming yet)",
+                    app_name
+                );
+            }
+        }
+
+        PwCommand::SetAppDelay { app_name, delay_ms } => {
+            // The offset is already in shared state; every app's delay may move
+            let local = local_state.borrow();
+            update_app_delays(&local);
+            tracing::debug!("Set sync offset = {:.0} ms for app '{}'", delay_ms, app_name);
+        }
+
+        PwCommand::SetProcessorChain { app_name, chain } => {
+            // The config is already in shared state; rebuild off the audio thread
+            // and let the callback crossfade to it
+            let mut local = local_state.borrow_mut();
+            let local = &mut *local;
+            let Some(audio_state) = local.audio_state.clone() else {
+                return;
+            };
+            match app_name {
+                None => {
+                    if let Some(handle) = local.master_chain.as_mut() {
+                        let rebuilt = build_master_chain(
+                            &chain,
+                            &audio_state,
+                            handle.sample_rate(),
+                            handle.channels(),
+                        );
+                        handle.send(rebuilt);
+                    }
+                    tracing::debug!("Set master processor chain ({} stages)", chain.processors.len());
+                }
+                Some(app_name) => {
+                    if let Some(capture) = local.app_captures.get_mut(&app_name) {
+                        let rebuilt = build_app_chain(
+                            &chain,
+                            &app_name,
+                            &capture.controls,
+                            &audio_state,
+                            capture.chain.sample_rate(),
+                            capture.chain.channels(),
+                        );
+                        capture.chain.send(rebuilt);
+                    }
+                    tracing::debug!(
+                        "Set processor chain for app '{}' ({} stages)",
+                        app_name,
+                        chain.processors.len()
+                    );
+                }
+            }
+        }
+
+        PwCommand::SetPhaseMode { app_name, mode } => {
+            // The mode is already in shared state; switching allocates the FIR,
+            // so rebuild the EQ's chain here and let the callback crossfade to it
+            let mut local = local_state.borrow_mut();
+            let capture = app_name.as_ref().and_then(|name| local.app_captures.get(name));
+            if let Some(capture) = capture {
+                let linear = mode == gecko_dsp::PhaseMode::Linear;
+                capture.controls.linear_phase.store(linear, Ordering::Relaxed);
+            }
+            rebuild_chain(&mut local, app_name.as_deref());
+            tracing::debug!("Set EQ phase mode for {:?} to {:?}", app_name, mode);
+        }
+
+        PwCommand::SetImpulseResponse { app_name } => {
+            // The IR is already in shared state (resampled by the backend);
+            // building its convolver allocates, so do it here
+            rebuild_chain(&mut local_state.borrow_mut(), app_name.as_deref());
+            tracing::debug!("Rebuilt chain for {:?} with its impulse response", app_name);
+        }
+
         PwCommand::SetAppVolume { app_name, volume } => {
             // Update per-app volume via atomic shared state
             // Volume is applied after EQ and before mixing (in the capture callback)
@@ -3515,7 +4029,7 @@ fn handle_command(
             if let Some(capture) = local.app_captures.get(&app_name) {
                 // Clamp volume to valid range and store as atomic u32 bits
                 let clamped_volume = volume.clamp(0.0, 2.0);
-                capture.volume.store(clamped_volume.to_bits(), Ordering::Release);
+                capture.controls.volume.store(clamped_volume.to_bits(), Ordering::Release);
                 
                 // Also update shared state so it persists if stream is recreated
                 if let Some(ref state) = local.audio_state {
@@ -10,15 +10,13 @@
 //!       ↓
 //! Mix all app audio
 //!       ↓
-//! DSP Processing (EQ, Volume)
-//!       ↓
-//! Soft Clip
+//! Master processor chain (EQ, Crossfeed, Volume, Soft Clip by default)
 //!       ↓
 //! Output to Speakers (via cpal)
 //! ```
 
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, AtomicUsize, Ordering};
 use std::sync::Arc;
 
 // Debug counters for audio callback (static so they persist across callbacks)
@@ -28,10 +26,18 @@ static TOTAL_SAMPLES_MIXED: AtomicUsize = AtomicUsize::new(0);
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Device, SampleFormat, Stream, StreamConfig};
 use parking_lot::{Mutex, RwLock};
-use tracing::{debug, error};
-
-use gecko_dsp::{Equalizer, SoftClipper, SpectrumAnalyzer, NUM_BINS};
-
+use tracing::{debug, error, warn};
+
+use gecko_dsp::{
+    band_layout_params, AtomicBandParams, BandParams, ChainConfig, ChainScope, ChainSender,
+    CompressorMeter, Convolver, Crossfeed, CrossfeedSettings, DspError, GainReduction,
+    ImpulseResponse, IR_BLOCK,
+    EqChannelMode, EqConfig, Equalizer, Limiter, LimiterSettings, LoudnessAnalyzer, LoudnessMeter,
+    LoudnessStats, OutputStage, PhaseMode, ProcessorKind, SoftClipper,
+    SpectrumAnalyzer, StereoEqualizer, EQ_BANDS, MAX_BANDS, MAX_CHANNELS, NUM_BINS,
+};
+
+use super::chain::{build_master_chain, CHAIN_BUFFER_SIZE};
 use super::process_tap::AudioRingBuffer;
 use crate::error::PlatformError;
 
@@ -111,7 +117,7 @@ impl AudioMixer {
     ///
     /// Called from the UI thread to update per-app EQ settings.
     pub fn set_app_eq_band(&self, app_name: &str, band: usize, gain_db: f32) {
-        if band >= 10 {
+        if band >= MAX_BANDS {
             return;
         }
 
@@ -128,7 +134,9 @@ impl AudioMixer {
     }
 
     /// Set all EQ bands for a specific app at once
-    pub fn set_app_eq_bands(&self, app_name: &str, gains: &[f32; 10]) {
+    ///
+    /// Values beyond the app equalizer's band count are ignored.
+    pub fn set_app_eq_bands(&self, app_name: &str, gains: &[f32]) {
         let mut eqs = self.app_equalizers.lock();
 
         // Create Equalizer if it doesn't exist
@@ -145,22 +153,28 @@ impl AudioMixer {
 
     /// Get EQ band gain for a specific app (returns 0.0 if app not found)
     pub fn get_app_eq_band(&self, app_name: &str, band: usize) -> f32 {
-        if band >= 10 {
-            return 0.0;
-        }
-
         let eqs = self.app_equalizers.lock();
         eqs.get(app_name)
-            .map(|eq| eq.config().get_gains()[band])
+            .and_then(|eq| eq.config().bands.get(band).map(|b| b.gain_db))
             .unwrap_or(0.0)
     }
 
-    /// Get all EQ bands for a specific app (returns [0.0; 10] if app not found)
-    pub fn get_app_eq_bands(&self, app_name: &str) -> [f32; 10] {
+    /// Get all EQ bands for a specific app (returns a flat 10-band curve if app not found)
+    pub fn get_app_eq_bands(&self, app_name: &str) -> Vec<f32> {
         let eqs = self.app_equalizers.lock();
         eqs.get(app_name)
             .map(|eq| eq.config().get_gains())
-            .unwrap_or([0.0; 10])
+            .unwrap_or_else(|| vec![0.0; EQ_BANDS.len()])
+    }
+
+    /// Switch every per-app equalizer to the standard layout for `band_count` bands
+    ///
+    /// Called from the UI thread; gains reset to flat.
+    pub fn set_band_count(&self, band_count: usize) {
+        let mut eqs = self.app_equalizers.lock();
+        for eq in eqs.values_mut() {
+            let _ = eq.set_band_count(band_count);
+        }
     }
 
     /// Add a new audio source (Process Tap capture)
@@ -276,16 +290,29 @@ impl AudioMixer {
                     // Sync EQ gains from AudioProcessingState to the Equalizer
                     // This ensures UI changes are reflected in the per-app EQ
                     if let Some(s) = state {
+                        let band_count = s.band_count();
+                        if eq.band_count() != band_count {
+                            let _ = eq.set_band_count(band_count);
+                        }
+                        if let Some(shapes) = s.get_app_band_params(&source.app_name) {
+                            // Only rebuild coefficients for shapes that changed
+                            for (band, &params) in shapes.iter().enumerate().take(band_count) {
+                                if eq.config().bands[band].params() != params {
+                                    let _ = eq.set_band_params(band, params);
+                                }
+                            }
+                        }
                         if let Some(gains) = s.get_app_eq_gains(&source.app_name) {
                             // Apply all gains - this is cheap if values haven't changed
-                            for (band, &gain_db) in gains.iter().enumerate() {
+                            for (band, &gain_db) in gains.iter().enumerate().take(band_count) {
                                 let _ = eq.set_band_gain(band, gain_db);
                             }
                         }
+                        eq.set_master_gain_db(s.get_app_preamp(&source.app_name));
                     }
 
                     // Process audio through per-app EQ (in-place)
-                    eq.process_interleaved(&mut source_buffer[..samples_read]);
+                    eq.process_block(&mut source_buffer[..samples_read]);
                 }
                 // If lock unavailable, skip per-app EQ for this buffer (inaudible glitch)
 
@@ -327,12 +354,22 @@ pub struct AudioProcessingState {
     /// Whether the output stream is running
     pub running: AtomicBool,
 
-    /// Master EQ band gains (10 bands, stored as u32 bits interpreted as f32)
-    master_eq_gains: [AtomicU32; 10],
+    /// Number of active EQ bands (1 to MAX_BANDS), shared by master and per-app EQs
+    band_count: AtomicUsize,
+
+    /// Master EQ band gains (stored as u32 bits interpreted as f32)
+    /// Sized for MAX_BANDS; only the first `band_count` entries are used
+    master_eq_gains: [AtomicU32; MAX_BANDS],
 
-    /// Per-app EQ offsets (app_name → [10 bands of offset_db])
+    /// Master EQ band shapes (frequency, Q, type, enabled) for UI reads
+    master_band_params: [AtomicBandParams; MAX_BANDS],
+
+    /// Per-app EQ band shapes (app_name → shape per band)
+    app_band_params: RwLock<std::collections::HashMap<String, [BandParams; MAX_BANDS]>>,
+
+    /// Per-app EQ offsets (app_name → [MAX_BANDS bands of offset_db])
     /// These offsets are ADDED to master EQ to get final gains
-    app_eq_offsets: RwLock<std::collections::HashMap<String, [f32; 10]>>,
+    app_eq_offsets: RwLock<std::collections::HashMap<String, [f32; MAX_BANDS]>>,
 
     /// Per-app volume (app_name → volume 0.0-2.0)
     app_volumes: RwLock<std::collections::HashMap<String, f32>>,
@@ -349,12 +386,53 @@ pub struct AudioProcessingState {
     /// Whether soft clipping is enabled
     soft_clip_enabled: AtomicBool,
 
+    /// Final output stage (OutputStage as u8): soft clipper or limiter
+    output_stage: AtomicU8,
+
+    /// Limiter ceiling (dBTP) and release (ms), as f32 bits
+    /// The output callback owns the Limiter and picks these up between buffers
+    limiter_ceiling_bits: AtomicU32,
+    limiter_release_bits: AtomicU32,
+
+    /// Cancel the peak EQ boost with a matching preamp (master and per-app EQs)
+    auto_preamp: AtomicBool,
+
+    /// Master preamp (dB) set by the user, as f32 bits; used while auto preamp is off
+    manual_preamp_bits: AtomicU32,
+
+    /// Preamp (dB, f32 bits) the master EQ's primary config applies, for UI reads
+    /// Set together with the equalizer whenever its response changes
+    master_preamp_bits: AtomicU32,
+
+    /// Per-app preamps (app_name → dB), computed when the app's EQ changes
+    /// The mixer applies these instead of searching for the peak itself
+    app_preamps: RwLock<std::collections::HashMap<String, f32>>,
+
+    /// Headphone crossfeed settings for the output (`None` = off, e.g. speakers)
+    crossfeed: RwLock<Option<CrossfeedSettings>>,
+
+    /// Processor chain run by the output stream after mixing
+    master_chain: RwLock<ChainConfig>,
+
     /// Master EQ processor (Mutex for &mut access in audio callback)
     /// Uses try_lock() in callback to avoid blocking - skips EQ if locked
-    equalizer: Mutex<Equalizer>,
+    /// The primary config holds the master gains; the secondary one is only
+    /// heard in left/right and mid/side modes
+    equalizer: Mutex<StereoEqualizer>,
 
     /// Sample rate for EQ (needed if we recreate the equalizer)
     sample_rate: AtomicU32,
+
+    /// Loudness (LUFS) of the master output, published by the output callback
+    /// TODO: per-app loudness needs analyzers in the mixer
+    master_loudness: LoudnessMeter,
+
+    /// Gain reduction meters of the master chain's compressor stages (one per
+    /// band), replaced on each rebuild
+    master_gain_reduction: RwLock<Vec<Vec<CompressorMeter>>>,
+
+    /// Impulse response for the master chain's convolver (`None` skips it)
+    master_ir: RwLock<Option<ImpulseResponse>>,
 }
 
 impl AudioProcessingState {
@@ -374,7 +452,10 @@ impl AudioProcessingState {
             peak_left_bits: AtomicU32::new(0.0_f32.to_bits()),
             peak_right_bits: AtomicU32::new(0.0_f32.to_bits()),
             running: AtomicBool::new(false),
+            band_count: AtomicUsize::new(EQ_BANDS.len()),
             master_eq_gains,
+            master_band_params: band_layout_params(EQ_BANDS.len()).map(AtomicBandParams::new),
+            app_band_params: RwLock::new(std::collections::HashMap::new()),
             app_eq_offsets: RwLock::new(std::collections::HashMap::new()),
             app_volumes: RwLock::new(std::collections::HashMap::new()),
             app_bypassed: RwLock::new(std::collections::HashMap::new()),
@@ -383,9 +464,21 @@ impl AudioProcessingState {
             // Soft clipper: -3dB threshold
             soft_clipper: RwLock::new(SoftClipper::new(-3.0)),
             soft_clip_enabled: AtomicBool::new(true),
+            output_stage: AtomicU8::new(OutputStage::SoftClip as u8),
+            limiter_ceiling_bits: AtomicU32::new(LimiterSettings::default().ceiling_db.to_bits()),
+            limiter_release_bits: AtomicU32::new(LimiterSettings::default().release_ms.to_bits()),
+            auto_preamp: AtomicBool::new(false),
+            manual_preamp_bits: AtomicU32::new(0.0_f32.to_bits()),
+            master_preamp_bits: AtomicU32::new(0.0_f32.to_bits()),
+            app_preamps: RwLock::new(std::collections::HashMap::new()),
+            crossfeed: RwLock::new(None),
+            master_chain: RwLock::new(ChainConfig::master_default()),
             // Master EQ processor
-            equalizer: Mutex::new(Equalizer::new(sample_rate)),
+            equalizer: Mutex::new(StereoEqualizer::new(sample_rate)),
             sample_rate: AtomicU32::new(sample_rate.to_bits()),
+            master_loudness: LoudnessMeter::default(),
+            master_gain_reduction: RwLock::new(Vec::new()),
+            master_ir: RwLock::new(None),
         }
     }
 
@@ -419,41 +512,107 @@ impl AudioProcessingState {
         self.bypassed.load(Ordering::Relaxed)
     }
 
+    /// Number of active EQ bands
+    pub fn band_count(&self) -> usize {
+        self.band_count.load(Ordering::Acquire)
+    }
+
+    /// Change the number of EQ bands (1 to MAX_BANDS)
+    ///
+    /// Master gains and per-app offsets reset to flat and band shapes to the
+    /// standard layout; the caller re-applies them for the new layout.
+    /// Returns false if the count is invalid.
+    pub fn set_band_count(&self, band_count: usize) -> bool {
+        if band_count == 0 || band_count > MAX_BANDS {
+            return false;
+        }
+
+        for gain in &self.master_eq_gains {
+            gain.store(0.0_f32.to_bits(), Ordering::Relaxed);
+        }
+        for (slot, params) in self.master_band_params.iter().zip(band_layout_params(band_count)) {
+            slot.store(params);
+        }
+        self.app_eq_offsets.write().clear();
+        self.app_band_params.write().clear();
+        self.app_preamps.write().clear();
+
+        // Blocking lock is fine here: this runs on the UI/engine thread and
+        // the callback only ever holds the lock for one buffer
+        let mut eq = self.equalizer.lock();
+        if eq.set_band_count(band_count).is_err() {
+            return false;
+        }
+        self.refresh_master_preamps(&mut eq);
+        self.band_count.store(band_count, Ordering::Release);
+        true
+    }
+
     /// Set master EQ band gain
     ///
     /// Updates both the atomic storage (for UI sync) and the actual Equalizer.
     pub fn set_eq_band(&self, band: usize, gain_db: f32) {
-        if band < 10 {
+        if band < self.band_count() {
             // Store in atomic for UI reads
             self.master_eq_gains[band].store(gain_db.to_bits(), Ordering::Relaxed);
 
             // Update the actual Equalizer (blocks briefly if audio callback is processing)
             if let Some(mut eq) = self.equalizer.try_lock() {
                 // Ignore errors from invalid gains - they'll be clamped anyway
-                let _ = eq.set_band_gain(band, gain_db);
+                let _ = eq.primary_mut().set_band_gain(band, gain_db);
+                self.refresh_master_preamps(&mut eq);
             }
         }
     }
 
+    /// Set master EQ band shape (frequency, Q, type, enabled)
+    ///
+    /// Invalid shapes are ignored; callers validate first to report errors.
+    pub fn set_eq_band_params(&self, band: usize, params: BandParams) {
+        if band < self.band_count() && params.validate().is_ok() {
+            self.master_band_params[band].store(params);
+
+            if let Some(mut eq) = self.equalizer.try_lock() {
+                let _ = eq.primary_mut().set_band_params(band, params);
+                self.refresh_master_preamps(&mut eq);
+            }
+        }
+    }
+
+    /// Get master EQ band shape
+    pub fn get_eq_band_params(&self, band: usize) -> Option<BandParams> {
+        if band < self.band_count() {
+            Some(self.master_band_params[band].load())
+        } else {
+            None
+        }
+    }
+
     /// Get master EQ band gain
     pub fn get_eq_band(&self, band: usize) -> f32 {
-        if band < 10 {
+        if band < self.band_count() {
             f32::from_bits(self.master_eq_gains[band].load(Ordering::Relaxed))
         } else {
             0.0
         }
     }
 
-    /// Get all master EQ gains
-    pub fn get_eq_gains(&self) -> [f32; 10] {
-        core::array::from_fn(|i| f32::from_bits(self.master_eq_gains[i].load(Ordering::Relaxed)))
+    /// Get all master EQ gains (one per active band)
+    pub fn get_eq_gains(&self) -> Vec<f32> {
+        self.master_eq_gains
+            .iter()
+            .take(self.band_count())
+            .map(|g| f32::from_bits(g.load(Ordering::Relaxed)))
+            .collect()
     }
 
     /// Set all EQ bands at once
     ///
-    /// More efficient than calling set_eq_band 10 times since it only
-    /// locks the Equalizer once.
-    pub fn set_all_eq_bands(&self, gains: &[f32; 10]) {
+    /// More efficient than calling set_eq_band once per band since it only
+    /// locks the Equalizer once. Values beyond the band count are ignored.
+    pub fn set_all_eq_bands(&self, gains: &[f32]) {
+        let gains = &gains[..gains.len().min(self.band_count())];
+
         // Store in atomics
         for (i, &gain) in gains.iter().enumerate() {
             self.master_eq_gains[i].store(gain.to_bits(), Ordering::Relaxed);
@@ -462,9 +621,150 @@ impl AudioProcessingState {
         // Update the Equalizer
         if let Some(mut eq) = self.equalizer.try_lock() {
             for (i, &gain) in gains.iter().enumerate() {
-                let _ = eq.set_band_gain(i, gain);
+                let _ = eq.primary_mut().set_band_gain(i, gain);
+            }
+            self.refresh_master_preamps(&mut eq);
+        }
+    }
+
+    /// Switch the master EQ between minimum and linear phase
+    ///
+    /// Linear phase allocates its FIR here (the callback skips the EQ while
+    /// the lock is held) and adds [`StereoEqualizer::latency_samples`].
+    pub fn set_phase_mode(&self, mode: PhaseMode) {
+        self.equalizer.lock().set_phase_mode(mode);
+    }
+
+    /// Phase mode of the master EQ
+    pub fn phase_mode(&self) -> PhaseMode {
+        self.equalizer.lock().primary().phase_mode()
+    }
+
+    /// Delay (samples) the master EQ adds (0 in minimum phase)
+    ///
+    /// Takes the EQ lock; don't call from the audio callback.
+    pub fn eq_latency_samples(&self) -> usize {
+        self.equalizer.lock().latency_samples()
+    }
+
+    /// Set how the master EQ treats the front left/right pair
+    pub fn set_eq_channel_mode(&self, mode: EqChannelMode) {
+        self.equalizer.lock().set_mode(mode);
+    }
+
+    /// Current master EQ channel mode
+    pub fn eq_channel_mode(&self) -> EqChannelMode {
+        self.equalizer.lock().mode()
+    }
+
+    /// Set master stereo width (0.0 mono, 1.0 unchanged, up to MAX_STEREO_WIDTH)
+    pub fn set_stereo_width(&self, width: f32) -> Result<(), DspError> {
+        self.equalizer.lock().set_width(width)
+    }
+
+    /// Set secondary (right or side) master EQ band gain
+    pub fn set_secondary_eq_band(&self, band: usize, gain_db: f32) {
+        if band < self.band_count() {
+            if let Some(mut eq) = self.equalizer.try_lock() {
+                let _ = eq.secondary_mut().set_band_gain(band, gain_db);
+                self.refresh_master_preamps(&mut eq);
+            }
+        }
+    }
+
+    /// Set secondary (right or side) master EQ band shape
+    ///
+    /// Invalid shapes are ignored; callers validate first to report errors.
+    pub fn set_secondary_eq_band_params(&self, band: usize, params: BandParams) {
+        if band < self.band_count() && params.validate().is_ok() {
+            if let Some(mut eq) = self.equalizer.try_lock() {
+                let _ = eq.secondary_mut().set_band_params(band, params);
+                self.refresh_master_preamps(&mut eq);
+            }
+        }
+    }
+
+    /// Enable or disable automatic preamp for the master and per-app EQs
+    ///
+    /// Per-app EQs pick their new preamp up on their next mix.
+    pub fn set_auto_preamp(&self, enabled: bool) {
+        self.auto_preamp.store(enabled, Ordering::Relaxed);
+        self.refresh_master_preamps(&mut self.equalizer.lock());
+        // Every app with EQ settings has an entry
+        let apps: Vec<String> = self.app_preamps.read().keys().cloned().collect();
+        for app_name in apps {
+            self.refresh_app_preamp(&app_name);
+        }
+    }
+
+    /// Whether automatic preamp is enabled
+    pub fn auto_preamp(&self) -> bool {
+        self.auto_preamp.load(Ordering::Relaxed)
+    }
+
+    /// Set the master preamp (dB) applied while auto preamp is off
+    pub fn set_manual_preamp(&self, preamp_db: f32) {
+        self.manual_preamp_bits.store(preamp_db.to_bits(), Ordering::Relaxed);
+        self.refresh_master_preamps(&mut self.equalizer.lock());
+    }
+
+    /// Preamp (dB) the master EQ applies, or an app's EQ when `app_name` is given
+    ///
+    /// The master EQ uses the manual preamp while auto preamp is off; app EQs
+    /// use 0 dB then.
+    pub fn eq_preamp_db(&self, app_name: Option<&str>) -> f32 {
+        match app_name {
+            Some(app_name) => self.get_app_preamp(app_name),
+            None => f32::from_bits(self.master_preamp_bits.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Preamp (dB) for an app's EQ (0 dB if the app has no EQ settings)
+    pub fn get_app_preamp(&self, app_name: &str) -> f32 {
+        self.app_preamps.read().get(app_name).copied().unwrap_or(0.0)
+    }
+
+    /// Recompute the master preamps while the caller holds the equalizer lock
+    ///
+    /// The peak search runs here on the control thread; the output callback
+    /// only ramps to the resulting master gain.
+    fn refresh_master_preamps(&self, eq: &mut StereoEqualizer) {
+        let sample_rate = self.sample_rate();
+        let auto_preamp = self.auto_preamp();
+        let manual_db = f32::from_bits(self.manual_preamp_bits.load(Ordering::Relaxed));
+        let preamp_db = |eq: &Equalizer| {
+            if auto_preamp {
+                eq.config().auto_preamp_db(sample_rate)
+            } else {
+                manual_db
+            }
+        };
+        let primary_db = preamp_db(eq.primary());
+        let secondary_db = preamp_db(eq.secondary());
+        eq.primary_mut().set_master_gain_db(primary_db);
+        eq.secondary_mut().set_master_gain_db(secondary_db);
+        self.master_preamp_bits.store(primary_db.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Recompute an app's preamp from its stored gains and shapes
+    fn refresh_app_preamp(&self, app_name: &str) {
+        let band_count = self.band_count();
+        let mut preamp_db = 0.0;
+        if self.auto_preamp() {
+            let gains = self.get_app_eq_gains(app_name).unwrap_or([0.0; MAX_BANDS]);
+            let shapes = self
+                .get_app_band_params(app_name)
+                .unwrap_or_else(|| band_layout_params(band_count));
+            if let Ok(mut config) = EqConfig::with_band_count(band_count) {
+                let bands = gains[..band_count].iter().zip(&shapes);
+                for (band, (&gain_db, &params)) in bands.enumerate() {
+                    let _ = config.set_band_params(band, params);
+                    let _ = config.set_band_gain(band, gain_db);
+                }
+                preamp_db = config.auto_preamp_db(self.sample_rate());
             }
         }
+        self.app_preamps.write().insert(app_name.to_string(), preamp_db);
     }
 
     /// Process audio through the EQ
@@ -501,20 +801,46 @@ impl AudioProcessingState {
 
     /// Set per-app EQ offset
     pub fn set_app_eq_offset(&self, app_name: &str, band: usize, offset_db: f32) {
-        if band < 10 {
+        if band < self.band_count() {
             let mut offsets = self.app_eq_offsets.write();
-            let gains = offsets.entry(app_name.to_string()).or_insert([0.0; 10]);
+            let gains = offsets.entry(app_name.to_string()).or_insert([0.0; MAX_BANDS]);
             gains[band] = offset_db;
+            drop(offsets);
+            self.refresh_app_preamp(app_name);
         }
     }
 
     /// Get per-app EQ gains (returns None if app has no EQ settings)
     ///
     /// Used by the mixer to sync EQ settings to per-app Equalizers.
-    pub fn get_app_eq_gains(&self, app_name: &str) -> Option<[f32; 10]> {
+    /// Returns a fixed-size array (no allocation); only the first
+    /// `band_count()` entries are meaningful.
+    pub fn get_app_eq_gains(&self, app_name: &str) -> Option<[f32; MAX_BANDS]> {
         self.app_eq_offsets.read().get(app_name).copied()
     }
 
+    /// Set per-app EQ band shape
+    pub fn set_app_band_params(&self, app_name: &str, band: usize, params: BandParams) {
+        let band_count = self.band_count();
+        if band < band_count && params.validate().is_ok() {
+            let mut shapes = self.app_band_params.write();
+            let entry = shapes
+                .entry(app_name.to_string())
+                .or_insert_with(|| band_layout_params(band_count));
+            entry[band] = params;
+            drop(shapes);
+            self.refresh_app_preamp(app_name);
+        }
+    }
+
+    /// Get per-app EQ band shapes (returns None if app has no edited shapes)
+    ///
+    /// Returns a fixed-size array (no allocation); only the first
+    /// `band_count()` entries are meaningful.
+    pub fn get_app_band_params(&self, app_name: &str) -> Option<[BandParams; MAX_BANDS]> {
+        self.app_band_params.read().get(app_name).copied()
+    }
+
     /// Set per-app volume
     pub fn set_app_volume(&self, app_name: &str, volume: f32) {
         let mut volumes = self.app_volumes.write();
@@ -586,6 +912,191 @@ impl AudioProcessingState {
             }
         }
     }
+
+    /// Choose the final stage: soft clipper or true-peak limiter
+    pub fn set_output_stage(&self, stage: OutputStage) {
+        self.output_stage.store(stage as u8, Ordering::Relaxed);
+    }
+
+    /// Current final stage
+    pub fn output_stage(&self) -> OutputStage {
+        if self.output_stage.load(Ordering::Relaxed) == OutputStage::Limiter as u8 {
+            OutputStage::Limiter
+        } else {
+            OutputStage::SoftClip
+        }
+    }
+
+    /// Set the limiter ceiling and release (validated; `enabled` is ignored)
+    pub fn set_limiter_settings(&self, settings: LimiterSettings) -> Result<(), DspError> {
+        settings.validate()?;
+        self.limiter_ceiling_bits.store(settings.ceiling_db.to_bits(), Ordering::Relaxed);
+        self.limiter_release_bits.store(settings.release_ms.to_bits(), Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Current limiter settings
+    pub fn limiter_settings(&self) -> LimiterSettings {
+        LimiterSettings {
+            ceiling_db: f32::from_bits(self.limiter_ceiling_bits.load(Ordering::Relaxed)),
+            release_ms: f32::from_bits(self.limiter_release_bits.load(Ordering::Relaxed)),
+            enabled: true,
+        }
+    }
+
+    /// Latency (samples) the master chain adds to the output stream
+    ///
+    /// From the linear-phase EQ, the convolver and the limiter, when they are
+    /// in the chain.
+    pub fn output_latency_samples(&self) -> usize {
+        let stage_latency = |kind: &ProcessorKind| match kind {
+            ProcessorKind::Equalizer => self.eq_latency_samples(),
+            ProcessorKind::OutputStage if self.output_stage() == OutputStage::Limiter => {
+                Limiter::latency_at(self.sample_rate())
+            }
+            ProcessorKind::Convolver if self.has_impulse_response() => IR_BLOCK,
+            _ => 0,
+        };
+        self.master_chain
+            .read()
+            .processors
+            .iter()
+            .filter(|slot| !slot.bypassed)
+            .map(|slot| stage_latency(&slot.kind))
+            .sum()
+    }
+
+    /// Run the selected final stage over an interleaved buffer
+    ///
+    /// `limiter` is owned by the output callback; it picks up settings
+    /// changes here. The soft clipper keeps its own enable switch.
+    pub fn process_output_stage(&self, limiter: &mut Limiter, buffer: &mut [f32]) {
+        match self.output_stage() {
+            OutputStage::Limiter => {
+                let settings = self.limiter_settings();
+                if *limiter.settings() != settings {
+                    let _ = limiter.set_settings(settings);
+                }
+                limiter.process_interleaved(buffer);
+            }
+            OutputStage::SoftClip => self.apply_soft_clip(buffer),
+        }
+    }
+
+    /// Turn headphone crossfeed on (`Some`) or off (`None`)
+    pub fn set_crossfeed(
+        &self,
+        settings: Option<CrossfeedSettings>,
+    ) -> Result<(), gecko_dsp::DspError> {
+        if let Some(ref settings) = settings {
+            settings.validate()?;
+        }
+        *self.crossfeed.write() = settings;
+        Ok(())
+    }
+
+    /// Current crossfeed settings, or `None` when crossfeed is off
+    pub fn crossfeed(&self) -> Option<CrossfeedSettings> {
+        *self.crossfeed.read()
+    }
+
+    /// Build a crossfeed for an output stream (call when building a stream)
+    pub fn new_crossfeed(&self, sample_rate: f32, channels: usize) -> Crossfeed {
+        let mut crossfeed = Crossfeed::new(sample_rate, CrossfeedSettings::default())
+            .expect("output sample rate is positive");
+        let _ = crossfeed.set_channel_count(channels.clamp(1, MAX_CHANNELS));
+        crossfeed
+    }
+
+    /// Crossfeed a buffer with the stream's processor, picking up setting changes
+    pub fn process_crossfeed(&self, crossfeed: &mut Crossfeed, buffer: &mut [f32]) {
+        let settings = self.crossfeed();
+        if let Some(settings) = settings {
+            if *crossfeed.settings() != settings {
+                let _ = crossfeed.set_settings(settings);
+            }
+        }
+        crossfeed.set_enabled(settings.is_some());
+        crossfeed.process_interleaved(buffer);
+    }
+
+    /// Set the master processor chain (validated)
+    ///
+    /// Output streams built from now on start with it; a running stream
+    /// switches through [`AudioOutputStream::set_master_chain`].
+    pub fn set_master_chain(&self, chain: ChainConfig) -> Result<(), DspError> {
+        chain.validate(ChainScope::Master)?;
+        *self.master_chain.write() = chain;
+        Ok(())
+    }
+
+    /// Current master processor chain
+    pub fn master_chain(&self) -> ChainConfig {
+        self.master_chain.read().clone()
+    }
+
+    /// Load (`Some`) or clear (`None`) the master convolver's impulse response
+    ///
+    /// Resampled to the output rate here; takes effect when the master chain
+    /// is next built (see `AudioOutputStream::set_master_chain`).
+    pub fn set_impulse_response(&self, ir: Option<ImpulseResponse>) -> Result<(), DspError> {
+        let ir = ir.map(|ir| ir.resampled(self.sample_rate())).transpose()?;
+        *self.master_ir.write() = ir;
+        Ok(())
+    }
+
+    /// Whether an impulse response is loaded
+    pub fn has_impulse_response(&self) -> bool {
+        self.master_ir.read().is_some()
+    }
+
+    /// Build a convolver for the loaded impulse response (`None` while none is)
+    ///
+    /// Allocates; call when building a chain.
+    pub fn new_convolver(&self, sample_rate: f32, channels: usize) -> Option<Convolver> {
+        let ir = self.master_ir.read().clone()?;
+        let mut convolver = match Convolver::new(&ir, sample_rate) {
+            Ok(convolver) => convolver,
+            Err(e) => {
+                warn!("Skipping convolver: {:?}", e);
+                return None;
+            }
+        };
+        let _ = convolver.set_channel_count(channels.clamp(1, MAX_CHANNELS));
+        Some(convolver)
+    }
+
+    /// Loudness analyzer publishing to the master meter (call when building a stream)
+    pub fn new_loudness_analyzer(&self, sample_rate: f32, channels: usize) -> LoudnessAnalyzer {
+        let mut analyzer = LoudnessAnalyzer::with_meter(sample_rate, self.master_loudness.clone())
+            .expect("output sample rate is positive");
+        let weights = [1.0; MAX_CHANNELS];
+        let _ = analyzer.set_channel_weights(&weights[..channels.clamp(1, MAX_CHANNELS)]);
+        analyzer
+    }
+
+    /// Loudness of the master output
+    pub fn master_loudness(&self) -> LoudnessStats {
+        self.master_loudness.stats()
+    }
+
+    /// Point the gain reduction readout at the compressors of a freshly built
+    /// master chain
+    pub fn set_gain_reduction_meters(&self, meters: Vec<Vec<CompressorMeter>>) {
+        *self.master_gain_reduction.write() = meters;
+    }
+
+    /// Gain reduction in the master chain since the previous call
+    ///
+    /// `None` while the chain has no compressor.
+    pub fn master_gain_reduction(&self) -> Option<GainReduction> {
+        GainReduction::take(&self.master_gain_reduction.read())
+    }
+
+    /// Restart integrated loudness, range and true peak of the master output
+    pub fn reset_loudness(&self) {
+        self.master_loudness.request_reset();
+    }
 }
 
 impl Default for AudioProcessingState {
@@ -612,6 +1123,9 @@ pub struct AudioOutputStream {
 
     /// Audio mixer for combining multiple sources
     mixer: Arc<AudioMixer>,
+
+    /// Sends rebuilt master chains to the output callback
+    chain_sender: ChainSender,
 }
 
 impl AudioOutputStream {
@@ -647,7 +1161,7 @@ impl AudioOutputStream {
         );
 
         // Create the output stream based on sample format
-        let stream = match sample_format {
+        let (stream, chain_sender) = match sample_format {
             SampleFormat::F32 => Self::build_stream::<f32>(
                 &device,
                 &config,
@@ -688,6 +1202,7 @@ impl AudioOutputStream {
             config,
             state,
             mixer,
+            chain_sender,
         })
     }
 
@@ -704,14 +1219,37 @@ impl AudioOutputStream {
         &self.mixer
     }
 
+    /// Replace the master processor chain while audio plays
+    ///
+    /// The chain is built here and crossfaded in by the output callback.
+    pub fn set_master_chain(&mut self, chain: ChainConfig) -> Result<(), DspError> {
+        self.state.set_master_chain(chain.clone())?;
+        let rebuilt = build_master_chain(
+            &chain,
+            &self.state,
+            self.config.sample_rate.0 as f32,
+            self.config.channels as usize,
+        );
+        if self.chain_sender.send(rebuilt).is_err() {
+            warn!("Output callback is not picking up processor chains, dropping update");
+        }
+        Ok(())
+    }
+
     /// Build the output stream for a specific sample format
+    ///
+    /// Also returns the sender for replacing the stream's master chain.
     fn build_stream<T: cpal::SizedSample + cpal::FromSample<f32>>(
         device: &Device,
         config: &StreamConfig,
         state: Arc<AudioProcessingState>,
         mixer: Arc<AudioMixer>,
-    ) -> Result<Stream, PlatformError> {
+    ) -> Result<(Stream, ChainSender), PlatformError> {
         let channels = config.channels as usize;
+        let sample_rate = config.sample_rate.0 as f32;
+        let mut loudness = state.new_loudness_analyzer(sample_rate, channels);
+        let master_chain = build_master_chain(&state.master_chain(), &state, sample_rate, channels);
+        let (chain_sender, mut chain) = gecko_dsp::chain_channel(master_chain, CHAIN_BUFFER_SIZE);
 
         // Error callback
         let err_fn = |err| error!("Audio output error: {}", err);
@@ -734,18 +1272,14 @@ impl AudioOutputStream {
 
             // If we got audio, process it
             if samples_read > 0 && !state.is_bypassed() {
-                // Apply EQ (10-band parametric equalizer)
-                // Uses try_lock() internally - if UI is updating EQ, skip for this buffer
-                state.process_eq(&mut process_buffer);
-
-                // Apply master volume
-                let volume = state.master_volume();
-                for sample in process_buffer.iter_mut() {
-                    *sample *= volume;
-                }
+                // Run the master chain: by default EQ (skipped for this buffer if
+                // the UI holds it), crossfeed when enabled for this output, master
+                // volume and the output stage (soft clipper or limiter). A newly
+                // sent chain is crossfaded in.
+                chain.process(&mut process_buffer);
 
-                // Apply soft clipping (prevents harsh digital distortion)
-                state.apply_soft_clip(&mut process_buffer);
+                // Measure loudness of the final output
+                loudness.process_interleaved(&process_buffer);
 
                 // Track peaks (L/R from interleaved stereo)
                 let mut peak_l = 0.0f32;
@@ -784,7 +1318,7 @@ impl AudioOutputStream {
             .build_output_stream(config, data_callback, err_fn, None)
             .map_err(|e| PlatformError::Internal(format!("Failed to build output stream: {}", e)))?;
 
-        Ok(stream)
+        Ok((stream, chain_sender))
     }
 
     /// Get the sample rate
@@ -868,6 +1402,26 @@ mod tests {
         assert!((state.get_eq_band(5) - (-2.0)).abs() < 0.001);
     }
 
+    #[test]
+    fn test_band_count_change() {
+        let state = AudioProcessingState::new();
+        state.set_eq_band(3, 4.0);
+        state.set_eq_band(20, 4.0); // Ignored with 10 bands
+        assert_eq!(state.get_eq_gains().len(), 10);
+
+        assert!(state.set_band_count(31));
+        assert_eq!(state.get_eq_gains().len(), 31);
+        // Gains reset to flat on layout change
+        assert!(state.get_eq_band(3).abs() < 0.001);
+
+        state.set_eq_band(20, 4.0);
+        assert!((state.get_eq_band(20) - 4.0).abs() < 0.001);
+
+        assert!(!state.set_band_count(0));
+        assert!(!state.set_band_count(MAX_BANDS + 1));
+        assert_eq!(state.band_count(), 31);
+    }
+
     #[test]
     fn test_app_volume() {
         let state = AudioProcessingState::new();
@@ -946,6 +1500,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_band_params() {
+        let state = AudioProcessingState::new();
+        let mut params = state.get_eq_band_params(2).unwrap();
+        params.frequency = 180.0;
+        params.band_type = gecko_dsp::BandType::HighShelf;
+        state.set_eq_band_params(2, params);
+        assert_eq!(state.get_eq_band_params(2), Some(params));
+        assert_eq!(state.equalizer.lock().primary().config().bands[2].params(), params);
+
+        // Invalid shapes are ignored
+        state.set_eq_band_params(2, BandParams { frequency: 0.0, ..params });
+        assert_eq!(state.get_eq_band_params(2), Some(params));
+
+        assert!(state.get_app_band_params("Firefox").is_none());
+        state.set_app_band_params("Firefox", 1, params);
+        assert_eq!(state.get_app_band_params("Firefox").unwrap()[1], params);
+
+        // Band count change restores the standard layout
+        state.set_band_count(10);
+        assert_eq!(state.get_eq_band_params(2), Some(gecko_dsp::band_layout(10, 2).params()));
+        assert!(state.get_app_band_params("Firefox").is_none());
+    }
+
     // =========================================================================
     // Per-App EQ Tests for AudioMixer
     // =========================================================================
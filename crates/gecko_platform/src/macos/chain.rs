@@ -0,0 +1,181 @@
+//! Master processor chain for the macOS output stream
+//!
+//! Builds a [`ProcessorChain`] from the master [`ChainConfig`]. The
+//! engine-bound stages keep following `AudioProcessingState` between
+//! buffers, so only editing the chain itself needs a rebuild.
+//!
+//! There are no per-app chains on macOS: the mixer sums the captured apps
+//! before this chain runs, so the engine rejects them.
+
+use std::sync::Arc;
+
+use gecko_dsp::{
+    AudioProcessor, ChainConfig, Crossfeed, Limiter, OutputStage, ProcessContext, ProcessorChain,
+    ProcessorKind,
+};
+use tracing::warn;
+
+use super::audio_output::AudioProcessingState;
+
+/// Longest buffer (interleaved samples) a chain crossfades in one piece
+pub(super) const CHAIN_BUFFER_SIZE: usize = 48000;
+
+/// Build the master chain described by `config`
+///
+/// Allocates; call outside the audio callback.
+pub(super) fn build_master_chain(
+    config: &ChainConfig,
+    state: &Arc<AudioProcessingState>,
+    sample_rate: f32,
+    channels: usize,
+) -> ProcessorChain {
+    let mut chain = ProcessorChain::new(sample_rate, channels, CHAIN_BUFFER_SIZE);
+    for slot in &config.processors {
+        let processor: Box<dyn AudioProcessor> = match slot.kind {
+            ProcessorKind::Equalizer => Box::new(MasterEq {
+                state: Arc::clone(state),
+            }),
+            ProcessorKind::Crossfeed => Box::new(MasterCrossfeed {
+                crossfeed: state.new_crossfeed(sample_rate, channels),
+                state: Arc::clone(state),
+            }),
+            ProcessorKind::Volume => Box::new(MasterVolume {
+                state: Arc::clone(state),
+            }),
+            ProcessorKind::OutputStage => {
+                match OutputStageProcessor::new(state, sample_rate, channels) {
+                    Some(processor) => Box::new(processor),
+                    None => continue,
+                }
+            }
+            ProcessorKind::Convolver => match state.new_convolver(sample_rate, channels) {
+                Some(convolver) => Box::new(convolver),
+                None => continue,
+            },
+            _ => match slot.kind.build(chain.context()) {
+                Some(Ok(processor)) => processor,
+                Some(Err(e)) => {
+                    warn!("Skipping {} in master chain: {:?}", slot.kind.name(), e);
+                    continue;
+                }
+                None => {
+                    warn!("{} is not available in the master chain", slot.kind.name());
+                    continue;
+                }
+            },
+        };
+        chain.add_boxed(processor);
+        chain.set_bypassed(chain.len() - 1, slot.bypassed);
+    }
+    state.set_gain_reduction_meters(chain.gain_reduction_meters());
+    chain
+}
+
+/// Master EQ held by the processing state (skipped while the UI holds it)
+struct MasterEq {
+    state: Arc<AudioProcessingState>,
+}
+
+impl AudioProcessor for MasterEq {
+    fn process(&mut self, buffer: &mut [f32], _context: &ProcessContext) {
+        self.state.process_eq(buffer);
+    }
+
+    fn reset(&mut self) {
+        self.state.reset_eq();
+    }
+
+    fn name(&self) -> &'static str {
+        "Equalizer"
+    }
+
+    fn latency_samples(&self) -> usize {
+        self.state.eq_latency_samples()
+    }
+}
+
+/// Headphone crossfeed, on for outputs that have it enabled
+struct MasterCrossfeed {
+    crossfeed: Crossfeed,
+    state: Arc<AudioProcessingState>,
+}
+
+impl AudioProcessor for MasterCrossfeed {
+    fn process(&mut self, buffer: &mut [f32], _context: &ProcessContext) {
+        self.state.process_crossfeed(&mut self.crossfeed, buffer);
+    }
+
+    fn reset(&mut self) {
+        AudioProcessor::reset(&mut self.crossfeed);
+    }
+
+    fn name(&self) -> &'static str {
+        "Crossfeed"
+    }
+}
+
+/// Master volume
+struct MasterVolume {
+    state: Arc<AudioProcessingState>,
+}
+
+impl AudioProcessor for MasterVolume {
+    fn process(&mut self, buffer: &mut [f32], _context: &ProcessContext) {
+        let volume = self.state.master_volume();
+        for sample in buffer.iter_mut() {
+            *sample *= volume;
+        }
+    }
+
+    fn reset(&mut self) {}
+
+    fn name(&self) -> &'static str {
+        "Volume"
+    }
+}
+
+/// Soft clipper or lookahead limiter, whichever is selected
+struct OutputStageProcessor {
+    /// Used when the limiter is the selected stage (buffers allocated up front)
+    limiter: Limiter,
+    state: Arc<AudioProcessingState>,
+}
+
+impl OutputStageProcessor {
+    fn new(state: &Arc<AudioProcessingState>, sample_rate: f32, channels: usize) -> Option<Self> {
+        match Limiter::new(sample_rate, state.limiter_settings()) {
+            Ok(mut limiter) => {
+                let _ = limiter.set_channel_count(channels);
+                Some(Self {
+                    limiter,
+                    state: Arc::clone(state),
+                })
+            }
+            Err(e) => {
+                warn!("Failed to build limiter at {} Hz: {:?}", sample_rate, e);
+                None
+            }
+        }
+    }
+}
+
+impl AudioProcessor for OutputStageProcessor {
+    fn process(&mut self, buffer: &mut [f32], _context: &ProcessContext) {
+        self.state.process_output_stage(&mut self.limiter, buffer);
+    }
+
+    fn reset(&mut self) {
+        AudioProcessor::reset(&mut self.limiter);
+    }
+
+    fn name(&self) -> &'static str {
+        "Output Stage"
+    }
+
+    fn latency_samples(&self) -> usize {
+        match self.state.output_stage() {
+            OutputStage::Limiter => self.limiter.latency_samples(),
+            OutputStage::SoftClip => 0,
+        }
+    }
+}
@@ -23,6 +23,7 @@
 
 // Make submodules public so they can be accessed from Tauri commands
 pub mod audio_output;
+mod chain;
 pub mod coreaudio;
 pub mod process_tap;
 pub mod process_tap_ffi;
@@ -115,8 +116,11 @@ pub struct CoreAudioBackend {
     /// Global bypass state
     bypassed: bool,
 
-    /// Per-app EQ gains (app_name -> [10 bands])
-    app_eq_gains: HashMap<String, [f32; 10]>,
+    /// Number of EQ bands shared by master and per-app EQs
+    band_count: usize,
+
+    /// Per-app EQ gains (app_name -> one gain per band)
+    app_eq_gains: HashMap<String, Vec<f32>>,
 
     /// Per-app volume (app_name -> volume 0.0-2.0)
     app_volumes: HashMap<String, f32>,
@@ -155,6 +159,7 @@ impl CoreAudioBackend {
             shutdown: Arc::new(AtomicBool::new(false)),
             master_volume: 1.0,
             bypassed: false,
+            band_count: gecko_dsp::EQ_BANDS.len(),
             app_eq_gains: HashMap::new(),
             app_volumes: HashMap::new(),
             app_bypassed: HashMap::new(),
@@ -271,10 +276,28 @@ impl CoreAudioBackend {
         trace!("Master EQ band {} set to {}dB", band, gain_db);
     }
 
+    /// Change the number of EQ bands (1 to MAX_BANDS)
+    ///
+    /// Applies to master and all per-app EQs. Gains reset to flat; the
+    /// caller re-sends them for the new layout.
+    pub fn set_eq_band_count(&mut self, band_count: usize) {
+        if !self.processing_state.set_band_count(band_count) {
+            warn!("Invalid EQ band count {}", band_count);
+            return;
+        }
+        self.band_count = band_count;
+        self.app_eq_gains.clear();
+        trace!("EQ band count set to {}", band_count);
+    }
+
     /// Update per-app EQ band
     pub fn update_stream_eq_band(&mut self, app_name: &str, band: usize, gain_db: f32) {
-        if band < 10 {
-            let gains = self.app_eq_gains.entry(app_name.to_string()).or_insert([0.0; 10]);
+        if band < self.band_count {
+            let band_count = self.band_count;
+            let gains = self
+                .app_eq_gains
+                .entry(app_name.to_string())
+                .or_insert_with(|| vec![0.0; band_count]);
             gains[band] = gain_db;
             trace!("App '{}' EQ band {} set to {}dB", app_name, band, gain_db);
         }
@@ -570,6 +593,24 @@ mod tests {
         assert!((gains[5] - (-2.0)).abs() < 0.001);
     }
 
+    #[test]
+    fn test_per_app_eq_band_count() {
+        let mut backend = CoreAudioBackend::new().unwrap();
+        backend.update_stream_eq_band("Firefox", 20, 3.0);
+        assert!(!backend.app_eq_gains.contains_key("Firefox"));
+
+        backend.set_eq_band_count(31);
+        assert_eq!(backend.processing_state.band_count(), 31);
+        backend.update_stream_eq_band("Firefox", 20, 3.0);
+        let gains = backend.app_eq_gains.get("Firefox").unwrap();
+        assert_eq!(gains.len(), 31);
+        assert!((gains[20] - 3.0).abs() < 0.001);
+
+        // Invalid counts are rejected without touching state
+        backend.set_eq_band_count(0);
+        assert_eq!(backend.band_count, 31);
+    }
+
     #[test]
     fn test_active_tap_count() {
         let backend = CoreAudioBackend::new().unwrap();
@@ -100,6 +100,26 @@ impl Default for VirtualSinkConfig {
     }
 }
 
+/// Speaker positions for an interleaved layout of `channels` channels
+///
+/// Uses PipeWire's channel names in their standard interleaving order,
+/// e.g. 6 channels (5.1) is FL, FR, FC, LFE, RL, RR.
+/// Returns `None` for counts without a standard layout.
+pub fn channel_positions(channels: u32) -> Option<&'static [&'static str]> {
+    let positions: &'static [&'static str] = match channels {
+        1 => &["MONO"],
+        2 => &["FL", "FR"],
+        3 => &["FL", "FR", "LFE"],
+        4 => &["FL", "FR", "RL", "RR"],
+        5 => &["FL", "FR", "FC", "RL", "RR"],
+        6 => &["FL", "FR", "FC", "LFE", "RL", "RR"],
+        7 => &["FL", "FR", "FC", "LFE", "RC", "SL", "SR"],
+        8 => &["FL", "FR", "FC", "LFE", "RL", "RR", "SL", "SR"],
+        _ => return None,
+    };
+    Some(positions)
+}
+
 /// Trait for platform-specific audio routing backends
 ///
 /// Each platform (Linux/Windows/macOS) implements this trait to provide
@@ -168,6 +188,15 @@ mod tests {
         assert!(!config.persistent);
     }
 
+    #[test]
+    fn test_channel_positions() {
+        assert_eq!(channel_positions(2), Some(&["FL", "FR"][..]));
+        assert_eq!(channel_positions(6).unwrap().join(","), "FL,FR,FC,LFE,RL,RR");
+        assert_eq!(channel_positions(8).unwrap().len(), 8);
+        assert!(channel_positions(0).is_none());
+        assert!(channel_positions(9).is_none());
+    }
+
     #[test]
     fn test_application_info_serialization() {
         let app = ApplicationInfo {
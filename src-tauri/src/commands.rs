@@ -1,8 +1,12 @@
 //! Tauri Commands - Called from the frontend via invoke()
 
-use crate::{AppState, AudioStreamInfo, BandInfo, DeviceInfo};
-use gecko_core::{DeviceType, GeckoSettings, UserPreset, EQ_BANDS};
-use gecko_dsp::PRESETS;
+use crate::{AppState, AudioStreamInfo, BandInfo, DeviceInfo, EqResponse};
+use gecko_core::{app_name_from_stream_id, DeviceType, GeckoSettings, UserPreset, EQ_BANDS};
+use gecko_dsp::{
+    band_layout_frequency, band_layout_params, log_frequencies, AutoLevelSettings, BandParams,
+    CrossfeedPreset, ChainConfig, ChainScope, CrossfeedSettings, EqChannelMode, EqConfig,
+    LimiterSettings, OutputStage, PhaseMode, ProcessorKind, MAX_BANDS, MIN_RESPONSE_DB, PRESETS,
+};
 use tauri::{AppHandle, State};
 use tauri_plugin_autostart::ManagerExt;
 
@@ -110,6 +114,9 @@ pub fn init_engine(state: State<AppState>) -> Result<(), String> {
                 // NOTE: Don't apply saved master_volume - it syncs from PipeWire sink volume
                 // The system retains volume state across app restarts
                 let _ = engine.set_bypass(settings.bypassed);
+                let _ = engine.set_limiter_settings(settings.ui_settings.limiter);
+                let _ = engine.set_output_stage(settings.ui_settings.output_stage);
+                let _ = engine.set_eq_band_count(settings.band_count());
                 for (i, gain) in settings.master_eq.iter().enumerate() {
                     let _ = engine.set_band_gain(i, *gain);
                 }
@@ -123,15 +130,77 @@ pub fn init_engine(state: State<AppState>) -> Result<(), String> {
                     }
                 }
 
+                // Apply band shapes (frequency, Q, type, enabled)
+                for (i, params) in settings.band_params().into_iter().enumerate() {
+                    let _ = engine.set_band_params(i, params);
+                }
+                for app_name in settings.app_band_params.keys() {
+                    for (i, params) in settings.app_band_params(app_name).into_iter().enumerate() {
+                        let _ = engine.set_stream_band_params(app_name.clone(), i, params);
+                    }
+                }
+
+                // Apply channel mode, secondary (right or side) config and stereo width
+                let _ = engine.set_eq_channel_mode(settings.eq_channel_mode);
+                for (i, gain) in settings.secondary_gains().into_iter().enumerate() {
+                    let _ = engine.set_secondary_band_gain(i, gain);
+                }
+                for (i, params) in settings.secondary_band_params().into_iter().enumerate() {
+                    let _ = engine.set_secondary_band_params(i, params);
+                }
+                let _ = engine.set_stereo_width(settings.stereo_width);
+                let _ = engine.set_eq_preamp(settings.master_preamp_db);
+                let _ = engine.set_auto_preamp(settings.auto_preamp);
+
                 // Apply per-app volume settings
                 for (app_name, volume) in &settings.app_volumes {
                     let _ = engine.set_stream_volume(app_name.clone(), *volume);
                 }
 
+                // Apply per-app A/V sync offsets
+                for (app_name, delay_ms) in &settings.app_delays {
+                    let _ = engine.set_app_delay(app_name.clone(), *delay_ms);
+                }
+
                 // Apply per-app bypass settings
                 for app_name in &settings.bypassed_apps {
                     let _ = engine.set_app_bypass(app_name.clone(), true);
                 }
+
+                // Apply auto-leveling settings
+                let _ = engine.set_auto_level_settings(settings.auto_level);
+                for app_name in &settings.auto_level_apps {
+                    let _ = engine.set_app_auto_level(app_name.clone(), true);
+                }
+
+                // Apply per-device crossfeed (the engine picks the current output's)
+                for (device_name, crossfeed) in &settings.crossfeed_devices {
+                    let _ = engine.set_crossfeed(device_name.clone(), Some(*crossfeed));
+                }
+
+                // Apply processor chains
+                if settings.master_chain != ChainConfig::master_default() {
+                    let _ = engine.set_processor_chain(None, settings.master_chain.clone());
+                }
+                for (app_name, chain) in &settings.app_chains {
+                    let _ = engine.set_processor_chain(Some(app_name.clone()), chain.clone());
+                }
+
+                // Apply EQ phase modes
+                if settings.master_phase_mode != PhaseMode::Minimum {
+                    let _ = engine.set_phase_mode(None, settings.master_phase_mode);
+                }
+                for (app_name, &mode) in &settings.app_phase_modes {
+                    let _ = engine.set_phase_mode(Some(app_name.clone()), mode);
+                }
+
+                // Apply impulse responses
+                if let Some(ref path) = settings.master_ir_path {
+                    let _ = engine.load_impulse_response(None, path);
+                }
+                for (app_name, path) in &settings.app_ir_paths {
+                    let _ = engine.load_impulse_response(Some(app_name.clone()), path);
+                }
             }
             
             *engine_guard = Some(engine);
@@ -201,6 +270,250 @@ pub fn set_band_gain(state: State<AppState>, band: usize, gain_db: f32) -> Resul
     }
 }
 
+/// Set master EQ band shape (frequency, Q, filter type, enabled, dynamics)
+#[tauri::command]
+pub fn set_band_params(state: State<AppState>, band: usize, params: BandParams) -> Result<(), String> {
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+
+    if let Some(ref engine) = *engine_guard {
+        engine.set_band_params(band, params).map_err(|e| e.to_string())?;
+
+        // Persist to settings
+        let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+        settings.set_band_params(band, params).map_err(|e| e.to_string())?;
+        let _ = settings.save();
+        Ok(())
+    } else {
+        Err("Engine not initialized".into())
+    }
+}
+
+/// Set how the master EQ treats the front left/right pair
+///
+/// `linked` uses the master bands for both channels; `left_right` and
+/// `mid_side` add a secondary config for the right or side signal.
+#[tauri::command]
+pub fn set_eq_channel_mode(state: State<AppState>, mode: EqChannelMode) -> Result<(), String> {
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+
+    if let Some(ref engine) = *engine_guard {
+        engine.set_eq_channel_mode(mode).map_err(|e| e.to_string())?;
+
+        // Persist to settings
+        if let Ok(mut settings) = state.settings.lock() {
+            settings.eq_channel_mode = mode;
+            let _ = settings.save();
+        }
+        Ok(())
+    } else {
+        Err("Engine not initialized".into())
+    }
+}
+
+/// Set secondary (right or side) master EQ band gain
+#[tauri::command]
+pub fn set_secondary_band_gain(state: State<AppState>, band: usize, gain_db: f32) -> Result<(), String> {
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+
+    if let Some(ref engine) = *engine_guard {
+        engine.set_secondary_band_gain(band, gain_db).map_err(|e| e.to_string())?;
+
+        // Persist to settings
+        if let Ok(mut settings) = state.settings.lock() {
+            if band < settings.band_count() {
+                settings.secondary_eq = settings.secondary_gains();
+                settings.secondary_eq[band] = gain_db;
+                let _ = settings.save();
+            }
+        }
+        Ok(())
+    } else {
+        Err("Engine not initialized".into())
+    }
+}
+
+/// Set secondary (right or side) master EQ band shape
+#[tauri::command]
+pub fn set_secondary_band_params(state: State<AppState>, band: usize, params: BandParams) -> Result<(), String> {
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+
+    if let Some(ref engine) = *engine_guard {
+        engine.set_secondary_band_params(band, params).map_err(|e| e.to_string())?;
+
+        // Persist to settings
+        let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+        settings.set_secondary_band_params(band, params).map_err(|e| e.to_string())?;
+        let _ = settings.save();
+        Ok(())
+    } else {
+        Err("Engine not initialized".into())
+    }
+}
+
+/// Set master stereo width (0.0 mono, 1.0 unchanged, up to 2.0)
+#[tauri::command]
+pub fn set_stereo_width(state: State<AppState>, width: f32) -> Result<(), String> {
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+
+    if let Some(ref engine) = *engine_guard {
+        engine.set_stereo_width(width).map_err(|e| e.to_string())?;
+
+        // Persist to settings
+        if let Ok(mut settings) = state.settings.lock() {
+            settings.stereo_width = width;
+            let _ = settings.save();
+        }
+        Ok(())
+    } else {
+        Err("Engine not initialized".into())
+    }
+}
+
+/// Enable or disable automatic preamp for the master and per-app EQs
+///
+/// Each EQ is lowered by its largest boost; `get_eq_preamp` reports the result
+#[tauri::command]
+pub fn set_auto_preamp(state: State<AppState>, enabled: bool) -> Result<(), String> {
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+
+    if let Some(ref engine) = *engine_guard {
+        engine.set_auto_preamp(enabled).map_err(|e| e.to_string())?;
+
+        // Persist to settings
+        if let Ok(mut settings) = state.settings.lock() {
+            settings.auto_preamp = enabled;
+            let _ = settings.save();
+        }
+        Ok(())
+    } else {
+        Err("Engine not initialized".into())
+    }
+}
+
+/// Set the master EQ preamp (dB, ±24), used while auto preamp is off
+#[tauri::command]
+pub fn set_eq_preamp(state: State<AppState>, preamp_db: f32) -> Result<(), String> {
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+
+    if let Some(ref engine) = *engine_guard {
+        engine.set_eq_preamp(preamp_db).map_err(|e| e.to_string())?;
+
+        // Persist to settings
+        if let Ok(mut settings) = state.settings.lock() {
+            settings.master_preamp_db = preamp_db;
+            let _ = settings.save();
+        }
+        Ok(())
+    } else {
+        Err("Engine not initialized".into())
+    }
+}
+
+/// Preamp (dB) applied to the master EQ, or to an app's EQ when `app_name` is given
+///
+/// Reports what the running equalizer applies: the manual preamp (master) or
+/// 0 dB (apps) while auto preamp is off, and 0 dB while the engine is not initialized.
+#[tauri::command]
+pub fn get_eq_preamp(state: State<AppState>, app_name: Option<String>) -> Result<f32, String> {
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+
+    if let Some(ref engine) = *engine_guard {
+        engine.eq_preamp_db(app_name).map_err(|e| e.to_string())
+    } else {
+        Ok(0.0)
+    }
+}
+
+/// Set per-app EQ band shape (frequency, Q, filter type, enabled, dynamics)
+///
+/// stream_id format matches `set_stream_band_gain`; persisted by app name
+#[tauri::command]
+pub fn set_stream_band_params(state: State<AppState>, stream_id: String, band: usize, params: BandParams) -> Result<(), String> {
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+
+    if let Some(ref engine) = *engine_guard {
+        engine.set_stream_band_params(stream_id.clone(), band, params).map_err(|e| e.to_string())?;
+
+        let app_name = app_name_from_stream_id(&stream_id);
+        let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+        settings.set_app_band_params(app_name, band, params).map_err(|e| e.to_string())?;
+        let _ = settings.save();
+        Ok(())
+    } else {
+        Err("Engine not initialized".into())
+    }
+}
+
+/// Get band shapes for the master EQ, or for an app when `app_name` is given
+#[tauri::command]
+pub fn get_band_params(state: State<AppState>, app_name: Option<String>) -> Result<Vec<BandParams>, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    Ok(match app_name {
+        Some(name) => settings.app_band_params(&name),
+        None => settings.band_params(),
+    })
+}
+
+/// Frequency response of the master EQ, or of an app's EQ followed by the master EQ
+///
+/// Evaluated from the real filter coefficients at the output rate, at
+/// `points` log-spaced frequencies (default 256) from 20 Hz to 20 kHz, so
+/// the UI can draw the curve that is actually applied. Outside linked mode
+/// there is one curve per channel (left/right or mid/side).
+#[tauri::command]
+pub fn get_eq_response(state: State<AppState>, app_name: Option<String>, points: Option<usize>) -> Result<EqResponse, String> {
+    let sample_rate = eq_sample_rate(&state)?;
+    let frequencies = log_frequencies(20.0, 20000.0_f32.min(sample_rate / 2.0), points.unwrap_or(256).clamp(2, 4096));
+
+    // Include any auto preamp so the curve matches what is heard
+    let response = |mut config: EqConfig| {
+        config.apply_auto_preamp(sample_rate);
+        config.frequency_response(sample_rate, &frequencies)
+    };
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let mode = settings.eq_channel_mode;
+    let master = settings.master_eq_config().and_then(response).map_err(|e| e.to_string())?;
+    let mut curves = vec![master];
+    if mode != EqChannelMode::Linked {
+        let mut secondary = settings
+            .secondary_eq_config()
+            .and_then(response)
+            .map_err(|e| e.to_string())?;
+        if mode == EqChannelMode::MidSide {
+            // Width scales the decoded side signal
+            let width_db = 20.0 * settings.stereo_width.log10();
+            for magnitude in &mut secondary.magnitude_db {
+                *magnitude = (*magnitude + width_db).max(MIN_RESPONSE_DB);
+            }
+        }
+        curves.push(secondary);
+    }
+
+    // Per-app EQs are linked and run before the master EQ
+    if let Some(name) = app_name {
+        let app = settings.app_eq_config(&name).and_then(response).map_err(|e| e.to_string())?;
+        curves = curves
+            .iter()
+            .map(|master| app.cascade(master))
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(EqResponse {
+        mode,
+        curves,
+        stereo_width: settings.stereo_width,
+    })
+}
+
+/// Sample rate the EQ runs at: the negotiated output rate, or the default before the engine exists
+fn eq_sample_rate(state: &State<AppState>) -> Result<f32, String> {
+    Ok(match *state.engine.lock().map_err(|e| e.to_string())? {
+        Some(ref engine) => engine.output_rate().map_err(|e| e.to_string())? as f32,
+        None => gecko_core::StreamConfig::default().sample_rate as f32,
+    })
+}
+
 /// Set per-app EQ band gain (TRUE per-app EQ, applied BEFORE mixing)
 ///
 /// This is TRUE per-app EQ - each app has its own independent Equalizer instance.
@@ -215,25 +528,16 @@ pub fn set_stream_band_gain(state: State<AppState>, stream_id: String, band: usi
         engine.set_stream_band_gain(stream_id.clone(), band, gain_db).map_err(|e| e.to_string())?;
 
         // Extract app name from stream_id for settings persistence
-        // Format varies by platform:
-        //   - Linux: "PID:Name" (name is after colon)
-        //   - macOS: "Name:PID" (name is before colon)
-        #[cfg(target_os = "linux")]
-        let app_name = stream_id.split(':').nth(1).unwrap_or(&stream_id).to_string();
-
-        #[cfg(target_os = "macos")]
-        let app_name = stream_id.split(':').next().unwrap_or(&stream_id).to_string();
-
-        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
-        let app_name = stream_id.clone();
+        let app_name = app_name_from_stream_id(&stream_id).to_string();
         
         // Persist to settings
         if let Ok(mut settings) = state.settings.lock() {
-            let eq = settings.app_eq.entry(app_name).or_insert_with(|| vec![0.0; 10]);
+            let band_count = settings.band_count();
+            let eq = settings.app_eq.entry(app_name).or_insert_with(|| vec![0.0; band_count]);
             if band < eq.len() {
                 eq[band] = gain_db;
-            } else if band < 10 {
-                eq.resize(10, 0.0);
+            } else if band < band_count {
+                eq.resize(band_count, 0.0);
                 eq[band] = gain_db;
             }
             // Optimization: Don't save to disk here to avoid I/O blocking during slider moves
@@ -272,6 +576,58 @@ pub fn set_app_bypass(state: State<AppState>, app_name: String, bypassed: bool)
     }
 }
 
+/// Enable or disable auto-leveling for a specific application
+///
+/// A leveled app's gain drifts slowly toward the shared target loudness,
+/// within the configured max boost and cut. Its volume slider still applies on top.
+#[tauri::command]
+pub fn set_app_auto_level(state: State<AppState>, app_name: String, enabled: bool) -> Result<(), String> {
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+
+    if let Some(ref engine) = *engine_guard {
+        engine.set_app_auto_level(app_name.clone(), enabled).map_err(|e| e.to_string())?;
+
+        // Persist to settings
+        if let Ok(mut settings) = state.settings.lock() {
+            if enabled {
+                settings.auto_level_apps.insert(app_name);
+            } else {
+                settings.auto_level_apps.remove(&app_name);
+            }
+            let _ = settings.save();
+        }
+        Ok(())
+    } else {
+        Err("Engine not initialized".into())
+    }
+}
+
+/// Set a per-app A/V sync offset in milliseconds (-500 to 500)
+///
+/// Positive values delay the app's audio (e.g. a video player on Bluetooth
+/// headphones); negative values play it early by delaying every other app.
+#[tauri::command]
+pub fn set_app_delay(state: State<AppState>, app_name: String, delay_ms: f32) -> Result<(), String> {
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+
+    if let Some(ref engine) = *engine_guard {
+        engine.set_app_delay(app_name.clone(), delay_ms).map_err(|e| e.to_string())?;
+
+        // Persist to settings
+        if let Ok(mut settings) = state.settings.lock() {
+            if delay_ms == 0.0 {
+                settings.app_delays.remove(&app_name);
+            } else {
+                settings.app_delays.insert(app_name, delay_ms);
+            }
+            let _ = settings.save();
+        }
+        Ok(())
+    } else {
+        Err("Engine not initialized".into())
+    }
+}
+
 /// Set per-app volume (0.0 - 2.0, where 1.0 is unity gain)
 ///
 /// This volume is applied after per-app EQ and before mixing.
@@ -285,17 +641,7 @@ pub fn set_stream_volume(state: State<AppState>, stream_id: String, volume: f32)
         engine.set_stream_volume(stream_id.clone(), volume).map_err(|e| e.to_string())?;
 
         // Extract app name from stream_id for settings persistence
-        // Format varies by platform:
-        //   - Linux: "PID:Name" (name is after colon)
-        //   - macOS: "Name:PID" (name is before colon)
-        #[cfg(target_os = "linux")]
-        let app_name = stream_id.split(':').nth(1).unwrap_or(&stream_id).to_string();
-
-        #[cfg(target_os = "macos")]
-        let app_name = stream_id.split(':').next().unwrap_or(&stream_id).to_string();
-
-        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
-        let app_name = stream_id.clone();
+        let app_name = app_name_from_stream_id(&stream_id).to_string();
 
         // Persist to settings
         if let Ok(mut settings) = state.settings.lock() {
@@ -515,15 +861,14 @@ pub fn list_audio_streams() -> Result<Vec<AudioStreamInfo>, String> {
     }
 }
 
-/// Get EQ band information
+/// Get EQ band information for a band count (defaults to the 10-band layout)
 #[tauri::command]
-pub fn get_eq_bands() -> Vec<BandInfo> {
-    EQ_BANDS
-        .iter()
-        .enumerate()
-        .map(|(i, &freq)| BandInfo {
+pub fn get_eq_bands(band_count: Option<usize>) -> Vec<BandInfo> {
+    let band_count = band_count.unwrap_or(EQ_BANDS.len()).clamp(1, MAX_BANDS);
+    (0..band_count)
+        .map(|i| BandInfo {
             index: i,
-            frequency: freq,
+            frequency: band_layout_frequency(band_count, i),
             gain_db: 0.0,
             enabled: true,
         })
@@ -558,6 +903,10 @@ pub fn get_settings(state: State<AppState>) -> Result<GeckoSettings, String> {
 /// Save settings
 #[tauri::command]
 pub fn save_settings(state: State<AppState>, settings: GeckoSettings) -> Result<(), String> {
+    if settings.master_eq.is_empty() || settings.master_eq.len() > MAX_BANDS {
+        return Err("Invalid band count".into());
+    }
+
     let mut current_settings = state.settings.lock().map_err(|e| e.to_string())?;
     *current_settings = settings.clone();
     
@@ -571,11 +920,57 @@ pub fn save_settings(state: State<AppState>, settings: GeckoSettings) -> Result<
         
         // Apply bypass
         let _ = engine.set_bypass(settings.bypassed);
+
+        // Apply output stage
+        let _ = engine.set_limiter_settings(settings.ui_settings.limiter);
+        let _ = engine.set_output_stage(settings.ui_settings.output_stage);
+
+        // Apply auto-leveling target and limits
+        let _ = engine.set_auto_level_settings(settings.auto_level);
+
+        // Apply per-device crossfeed
+        for (device_name, crossfeed) in &settings.crossfeed_devices {
+            let _ = engine.set_crossfeed(device_name.clone(), Some(*crossfeed));
+        }
+
+        // Apply processor chains
+        let _ = engine.set_processor_chain(None, settings.master_chain.clone());
+        for (app_name, chain) in &settings.app_chains {
+            let _ = engine.set_processor_chain(Some(app_name.clone()), chain.clone());
+        }
+
+        // Apply EQ phase modes
+        let _ = engine.set_phase_mode(None, settings.master_phase_mode);
+        for (app_name, &mode) in &settings.app_phase_modes {
+            let _ = engine.set_phase_mode(Some(app_name.clone()), mode);
+        }
+
+        // Apply impulse responses
+        if let Some(ref path) = settings.master_ir_path {
+            let _ = engine.load_impulse_response(None, path);
+        }
+        for (app_name, path) in &settings.app_ir_paths {
+            let _ = engine.load_impulse_response(Some(app_name.clone()), path);
+        }
         
         // Apply EQ
+        let _ = engine.set_eq_band_count(settings.band_count());
         for (i, gain) in settings.master_eq.iter().enumerate() {
             let _ = engine.set_band_gain(i, *gain);
         }
+        for (i, params) in settings.band_params().into_iter().enumerate() {
+            let _ = engine.set_band_params(i, params);
+        }
+        let _ = engine.set_eq_channel_mode(settings.eq_channel_mode);
+        for (i, gain) in settings.secondary_gains().into_iter().enumerate() {
+            let _ = engine.set_secondary_band_gain(i, gain);
+        }
+        for (i, params) in settings.secondary_band_params().into_iter().enumerate() {
+            let _ = engine.set_secondary_band_params(i, params);
+        }
+        let _ = engine.set_stereo_width(settings.stereo_width);
+        let _ = engine.set_eq_preamp(settings.master_preamp_db);
+        let _ = engine.set_auto_preamp(settings.auto_preamp);
     }
     
     Ok(())
@@ -604,7 +999,7 @@ pub fn get_presets(state: State<AppState>) -> Result<Vec<(String, Vec<f32>, bool
 /// Save current EQ as user preset
 #[tauri::command]
 pub fn save_preset(state: State<AppState>, name: String, gains: Vec<f32>) -> Result<(), String> {
-    if gains.len() != 10 {
+    if gains.is_empty() || gains.len() > MAX_BANDS {
         return Err("Invalid gain count".into());
     }
     
@@ -615,13 +1010,17 @@ pub fn save_preset(state: State<AppState>, name: String, gains: Vec<f32>) -> Res
         return Err("Cannot overwrite built-in preset".into());
     }
     
-    let mut gains_arr = [0.0; 10];
-    gains_arr.copy_from_slice(&gains);
-    
+    // Only shapes that differ from the standard layout are stored, so the
+    // preset can still be interpolated onto another band count
+    let shapes = settings.band_params();
+    let standard = &band_layout_params(shapes.len())[..shapes.len()];
+    let custom = settings.band_count() == gains.len() && shapes != standard;
     let preset = UserPreset {
         name: name.clone(),
-        gains: gains_arr,
+        band_params: if custom { shapes } else { Vec::new() },
+        gains,
         created_at: chrono::Utc::now(),
+        preamp_db: settings.master_preamp_db,
     };
     
     // Update or append
@@ -652,23 +1051,55 @@ pub fn delete_preset(state: State<AppState>, name: String) -> Result<(), String>
 }
 
 /// Apply a preset (convenience to set all bands and update settings)
+///
+/// Presets saved with a different band count are interpolated onto the
+/// current layout. User presets with custom band shapes (e.g. imported from
+/// EqualizerAPO) also restore those shapes; they can't be interpolated, so
+/// they require the band count they were saved with. The preset's preamp
+/// (0 dB for built-in presets) becomes the master preamp.
 #[tauri::command]
 pub fn apply_preset(state: State<AppState>, name: String, gains: Vec<f32>) -> Result<(), String> {
-    if gains.len() != 10 {
+    if gains.is_empty() || gains.len() > MAX_BANDS {
         return Err("Invalid gain count".into());
     }
     
     let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+
+    let user_preset = settings.user_presets.iter().find(|p| p.name == name);
+    let preamp_db = user_preset.map_or(0.0, |p| p.preamp_db);
+    let band_params = user_preset
+        .and_then(UserPreset::custom_band_params)
+        .map(<[BandParams]>::to_vec);
+    if let Some(ref shapes) = band_params {
+        if shapes.len() != settings.band_count() {
+            return Err(format!(
+                "Preset '{}' has {} custom bands; switch the EQ to {} bands to apply it",
+                name,
+                shapes.len(),
+                shapes.len()
+            ));
+        }
+        settings.master_band_params = shapes.clone();
+    }
     
     // Update active preset tracking
     settings.active_preset = Some(name);
     
+    // Map preset gains onto the current band layout
+    let gains = if gains.len() == settings.band_count() {
+        gains
+    } else {
+        let preset_freqs: Vec<f32> = (0..gains.len())
+            .map(|i| band_layout_frequency(gains.len(), i))
+            .collect();
+        let mut config = EqConfig::with_band_count(settings.band_count()).map_err(|e| e.to_string())?;
+        config.set_gains_interpolated(&preset_freqs, &gains);
+        config.get_gains()
+    };
+
     // Update EQ values in settings
-    for (i, &gain) in gains.iter().enumerate() {
-        if i < 10 {
-            settings.master_eq[i] = gain;
-        }
-    }
+    settings.master_eq.copy_from_slice(&gains);
+    settings.master_preamp_db = preamp_db;
     
     // Save settings
     settings.save().map_err(|e| e.to_string())?;
@@ -676,14 +1107,106 @@ pub fn apply_preset(state: State<AppState>, name: String, gains: Vec<f32>) -> Re
     // Apply to engine
     let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
     if let Some(ref engine) = *engine_guard {
+        if let Some(shapes) = band_params {
+            for (i, params) in shapes.into_iter().enumerate() {
+                let _ = engine.set_band_params(i, params);
+            }
+        }
         for (i, &gain) in gains.iter().enumerate() {
             let _ = engine.set_band_gain(i, gain);
         }
+        engine.set_eq_preamp(preamp_db).map_err(|e| e.to_string())?;
     }
     
     Ok(())
 }
 
+/// Import an EqualizerAPO / AutoEQ parametric config as a user preset
+///
+/// Filters map onto the current band count; fails if the file has more
+/// filters than bands. Returns warnings about lines that were skipped.
+#[tauri::command]
+pub fn import_apo_preset(state: State<AppState>, name: String, text: String) -> Result<Vec<String>, String> {
+    if PRESETS.iter().any(|(n, _)| *n == name) {
+        return Err("Cannot overwrite built-in preset".into());
+    }
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let (preset, warnings) = UserPreset::from_apo(&name, &text, settings.band_count()).map_err(|e| e.to_string())?;
+
+    if let Some(existing) = settings.user_presets.iter_mut().find(|p| p.name == name) {
+        *existing = preset;
+    } else {
+        settings.user_presets.push(preset);
+    }
+
+    settings.save().map_err(|e| e.to_string())?;
+    Ok(warnings)
+}
+
+/// Export a user preset, or the current master EQ when `name` is None,
+/// as an EqualizerAPO parametric config
+///
+/// The current EQ is exported with the preamp in effect: the running
+/// equalizer's, or the one the saved settings give before the engine starts.
+#[tauri::command]
+pub fn export_apo_preset(state: State<AppState>, name: Option<String>) -> Result<String, String> {
+    let preamp_db = match name {
+        Some(_) => 0.0,
+        None => current_preamp_db(&state)?,
+    };
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+
+    let preset = match name {
+        Some(name) => settings
+            .user_presets
+            .iter()
+            .find(|p| p.name == name)
+            .cloned()
+            .ok_or_else(|| format!("User preset '{}' not found", name))?,
+        None => UserPreset {
+            name: "Current".to_string(),
+            gains: settings.master_eq.clone(),
+            created_at: chrono::Utc::now(),
+            band_params: settings.band_params(),
+            preamp_db,
+        },
+    };
+
+    preset.to_apo().map_err(|e| e.to_string())
+}
+
+/// Master preamp (dB) in effect: read back from the engine, or derived from settings
+fn current_preamp_db(state: &State<AppState>) -> Result<f32, String> {
+    if let Some(ref engine) = *state.engine.lock().map_err(|e| e.to_string())? {
+        return engine.eq_preamp_db(None).map_err(|e| e.to_string());
+    }
+    let sample_rate = eq_sample_rate(state)?;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let mut config = settings.master_eq_config().map_err(|e| e.to_string())?;
+    config.apply_auto_preamp(sample_rate);
+    Ok(config.master_gain_db)
+}
+
+/// Change the number of EQ bands (1-31)
+///
+/// Master and per-app curves are interpolated onto the new layout and
+/// persisted; the engine remaps its own curves the same way. Returns the new
+/// master gains.
+#[tauri::command]
+pub fn set_eq_band_count(state: State<AppState>, band_count: usize) -> Result<Vec<f32>, String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.set_band_count(band_count).map_err(|e| e.to_string())?;
+    settings.save().map_err(|e| e.to_string())?;
+
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+    if let Some(ref engine) = *engine_guard {
+        engine.set_eq_band_count(band_count).map_err(|e| e.to_string())?;
+    }
+
+    Ok(settings.master_eq.clone())
+}
+
 /// Get platform capabilities
 #[tauri::command]
 pub fn get_platform_info() -> serde_json::Value {
@@ -731,6 +1254,377 @@ pub fn set_soft_clip(state: State<AppState>, enabled: bool) -> Result<(), String
     Ok(())
 }
 
+/// Select the final output stage: "soft_clip" or "limiter"
+///
+/// Returns the latency the stage adds, in milliseconds.
+#[tauri::command]
+pub fn set_output_stage(state: State<AppState>, stage: OutputStage) -> Result<f32, String> {
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+
+    // Commands run in order, so the latency reflects the new stage
+    let latency_ms = match *engine_guard {
+        Some(ref engine) => {
+            engine.set_output_stage(stage).map_err(|e| e.to_string())?;
+            let latency = engine.output_latency_samples(None).map_err(|e| e.to_string())?;
+            let sample_rate = engine.output_rate().map_err(|e| e.to_string())?;
+            latency as f32 * 1000.0 / sample_rate as f32
+        }
+        None => 0.0,
+    };
+
+    // Persist to settings
+    if let Ok(mut settings) = state.settings.lock() {
+        settings.ui_settings.output_stage = stage;
+        let _ = settings.save();
+    }
+
+    Ok(latency_ms)
+}
+
+/// Run the master EQ (`app_name` omitted) or an app's EQ "minimum" or "linear" phase
+///
+/// Returns the latency that EQ's output now has, in milliseconds.
+#[tauri::command]
+pub fn set_phase_mode(
+    state: State<AppState>,
+    app_name: Option<String>,
+    mode: PhaseMode,
+) -> Result<f32, String> {
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+
+    // Commands run in order, so the latency reflects the new mode
+    let latency_ms = match *engine_guard {
+        Some(ref engine) => {
+            engine.set_phase_mode(app_name.clone(), mode).map_err(|e| e.to_string())?;
+            let latency = engine
+                .output_latency_samples(app_name.clone())
+                .map_err(|e| e.to_string())?;
+            let sample_rate = engine.output_rate().map_err(|e| e.to_string())?;
+            latency as f32 * 1000.0 / sample_rate as f32
+        }
+        None => 0.0,
+    };
+
+    // Persist to settings
+    if let Ok(mut settings) = state.settings.lock() {
+        match app_name {
+            Some(app_name) if mode == PhaseMode::Minimum => {
+                settings.app_phase_modes.remove(&app_name);
+            }
+            Some(app_name) => {
+                settings.app_phase_modes.insert(app_name, mode);
+            }
+            None => settings.master_phase_mode = mode,
+        }
+        let _ = settings.save();
+    }
+
+    Ok(latency_ms)
+}
+
+/// Load a WAV impulse response for the master (`app_name` omitted) or an app's
+/// Convolver stage
+///
+/// Heard wherever that chain has a Convolver stage.
+#[tauri::command]
+pub fn load_impulse_response(
+    state: State<AppState>,
+    app_name: Option<String>,
+    path: String,
+) -> Result<(), String> {
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+
+    if let Some(ref engine) = *engine_guard {
+        engine
+            .load_impulse_response(app_name.clone(), &path)
+            .map_err(|e| e.to_string())?;
+    } else {
+        // Still check the file so a bad one isn't persisted
+        gecko_dsp::ImpulseResponse::from_wav_file(&path).map_err(|e| e.to_string())?;
+    }
+
+    // Persist to settings
+    if let Ok(mut settings) = state.settings.lock() {
+        let path = std::path::PathBuf::from(path);
+        match app_name {
+            Some(app_name) => {
+                settings.app_ir_paths.insert(app_name, path);
+            }
+            None => settings.master_ir_path = Some(path),
+        }
+        let _ = settings.save();
+    }
+
+    Ok(())
+}
+
+/// Remove the master (`app_name` omitted) or an app's impulse response
+#[tauri::command]
+pub fn clear_impulse_response(
+    state: State<AppState>,
+    app_name: Option<String>,
+) -> Result<(), String> {
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+
+    if let Some(ref engine) = *engine_guard {
+        engine
+            .clear_impulse_response(app_name.clone())
+            .map_err(|e| e.to_string())?;
+    }
+
+    // Persist to settings
+    if let Ok(mut settings) = state.settings.lock() {
+        match app_name {
+            Some(app_name) => {
+                settings.app_ir_paths.remove(&app_name);
+            }
+            None => settings.master_ir_path = None,
+        }
+        let _ = settings.save();
+    }
+
+    Ok(())
+}
+
+/// Restart integrated loudness, loudness range and true peak
+///
+/// Resets one app, or the master output and every app when `app_name` is omitted.
+#[tauri::command]
+pub fn reset_loudness(state: State<AppState>, app_name: Option<String>) -> Result<(), String> {
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+
+    if let Some(ref engine) = *engine_guard {
+        engine.reset_loudness(app_name).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Set the lookahead limiter's ceiling (dBTP, -24 to 0) and release (ms)
+#[tauri::command]
+pub fn set_limiter_settings(
+    state: State<AppState>,
+    ceiling_db: f32,
+    release_ms: f32,
+) -> Result<(), String> {
+    let limiter = LimiterSettings {
+        ceiling_db,
+        release_ms,
+        enabled: true,
+    };
+    limiter.validate().map_err(|e| e.to_string())?;
+
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+    if let Some(ref engine) = *engine_guard {
+        engine.set_limiter_settings(limiter).map_err(|e| e.to_string())?;
+    }
+
+    // Persist to settings
+    if let Ok(mut settings) = state.settings.lock() {
+        settings.ui_settings.limiter = limiter;
+        let _ = settings.save();
+    }
+
+    Ok(())
+}
+
+/// Set the auto-leveling target (LUFS), max boost/cut (dB) and speed (dB/s)
+///
+/// Shared by every app with auto-leveling enabled.
+#[tauri::command]
+pub fn set_auto_level_settings(
+    state: State<AppState>,
+    target_lufs: f32,
+    max_boost_db: f32,
+    max_cut_db: f32,
+    speed_db_per_s: f32,
+) -> Result<(), String> {
+    let auto_level = AutoLevelSettings {
+        target_lufs,
+        max_boost_db,
+        max_cut_db,
+        speed_db_per_s,
+    };
+    auto_level.validate().map_err(|e| e.to_string())?;
+
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+    if let Some(ref engine) = *engine_guard {
+        engine.set_auto_level_settings(auto_level).map_err(|e| e.to_string())?;
+    }
+
+    // Persist to settings
+    if let Ok(mut settings) = state.settings.lock() {
+        settings.auto_level = auto_level;
+        let _ = settings.save();
+    }
+
+    Ok(())
+}
+
+/// Get the crossfeed presets as (name, cutoff Hz, feed dB)
+#[tauri::command]
+pub fn get_crossfeed_presets() -> Vec<(String, f32, f32)> {
+    CrossfeedPreset::ALL
+        .iter()
+        .map(|preset| {
+            let settings = preset.settings();
+            (preset.name().to_string(), settings.cutoff_hz, settings.feed_db)
+        })
+        .collect()
+}
+
+/// Enable or disable headphone crossfeed for an output device
+///
+/// `device_name` comes from the `OutputDeviceChanged` event. Crossfeed is kept
+/// per device so it follows headphones and stays off for speakers.
+#[tauri::command]
+pub fn set_crossfeed(
+    state: State<AppState>,
+    device_name: String,
+    enabled: bool,
+    cutoff_hz: f32,
+    feed_db: f32,
+) -> Result<(), String> {
+    let crossfeed = CrossfeedSettings { cutoff_hz, feed_db };
+    if enabled {
+        crossfeed.validate().map_err(|e| e.to_string())?;
+    }
+    let crossfeed = enabled.then_some(crossfeed);
+
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+    if let Some(ref engine) = *engine_guard {
+        engine.set_crossfeed(device_name.clone(), crossfeed).map_err(|e| e.to_string())?;
+    }
+
+    // Persist to settings
+    if let Ok(mut settings) = state.settings.lock() {
+        match crossfeed {
+            Some(crossfeed) => settings.crossfeed_devices.insert(device_name, crossfeed),
+            None => settings.crossfeed_devices.remove(&device_name),
+        };
+        let _ = settings.save();
+    }
+
+    Ok(())
+}
+
+/// Get the processor chain for an app, or the master chain for `None`
+#[tauri::command]
+pub fn get_processor_chain(
+    state: State<AppState>,
+    app_name: Option<String>,
+) -> Result<ChainConfig, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    Ok(match app_name {
+        Some(app_name) => settings
+            .app_chains
+            .get(&app_name)
+            .cloned()
+            .unwrap_or_else(ChainConfig::app_default),
+        None => settings.master_chain.clone(),
+    })
+}
+
+/// Replace the processor chain for an app, or the master chain for `None`
+#[tauri::command]
+pub fn set_processor_chain(
+    state: State<AppState>,
+    app_name: Option<String>,
+    chain: ChainConfig,
+) -> Result<ChainConfig, String> {
+    edit_processor_chain(&state, app_name, |current| {
+        *current = chain;
+        Ok(())
+    })
+}
+
+/// Insert a processor at `index` of a chain (the end when `index` is its length)
+#[tauri::command]
+pub fn add_processor(
+    state: State<AppState>,
+    app_name: Option<String>,
+    index: usize,
+    kind: ProcessorKind,
+) -> Result<ChainConfig, String> {
+    edit_processor_chain(&state, app_name, |chain| chain.insert(index, kind))
+}
+
+/// Remove the processor at `index` of a chain
+#[tauri::command]
+pub fn remove_processor(
+    state: State<AppState>,
+    app_name: Option<String>,
+    index: usize,
+) -> Result<ChainConfig, String> {
+    edit_processor_chain(&state, app_name, |chain| chain.remove(index).map(|_| ()))
+}
+
+/// Move a processor within a chain so it ends up at `to`
+#[tauri::command]
+pub fn move_processor(
+    state: State<AppState>,
+    app_name: Option<String>,
+    from: usize,
+    to: usize,
+) -> Result<ChainConfig, String> {
+    edit_processor_chain(&state, app_name, |chain| chain.move_processor(from, to))
+}
+
+/// Bypass or re-enable the processor at `index` of a chain
+#[tauri::command]
+pub fn set_processor_bypass(
+    state: State<AppState>,
+    app_name: Option<String>,
+    index: usize,
+    bypassed: bool,
+) -> Result<ChainConfig, String> {
+    edit_processor_chain(&state, app_name, |chain| chain.set_bypassed(index, bypassed))
+}
+
+/// Apply `edit` to a stored chain, send it to the engine and persist it
+///
+/// Returns the new chain; on any error nothing changes.
+fn edit_processor_chain(
+    state: &State<AppState>,
+    app_name: Option<String>,
+    edit: impl FnOnce(&mut ChainConfig) -> Result<(), gecko_dsp::DspError>,
+) -> Result<ChainConfig, String> {
+    let scope = if app_name.is_some() {
+        ChainScope::App
+    } else {
+        ChainScope::Master
+    };
+    let engine_guard = state.engine.lock().map_err(|e| e.to_string())?;
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let mut chain = match app_name {
+        Some(ref app_name) => settings.app_chains.get(app_name).cloned(),
+        None => Some(settings.master_chain.clone()),
+    }
+    .unwrap_or_else(|| ChainConfig::default_for(scope));
+    edit(&mut chain).map_err(|e| e.to_string())?;
+    chain.validate(scope).map_err(|e| e.to_string())?;
+
+    if let Some(ref engine) = *engine_guard {
+        engine
+            .set_processor_chain(app_name.clone(), chain.clone())
+            .map_err(|e| e.to_string())?;
+    }
+
+    // Persist to settings
+    match app_name {
+        Some(app_name) if chain == ChainConfig::app_default() => {
+            settings.app_chains.remove(&app_name);
+        }
+        Some(app_name) => {
+            settings.app_chains.insert(app_name, chain.clone());
+        }
+        None => settings.master_chain = chain.clone(),
+    }
+    let _ = settings.save();
+
+    Ok(chain)
+}
+
 // ============================================================================
 // macOS-specific commands
 // ============================================================================
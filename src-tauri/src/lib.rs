@@ -152,6 +152,18 @@ pub struct AudioLevels {
     pub right: f32,
 }
 
+/// EQ frequency response for the frontend
+///
+/// Linked: one curve for every channel. Left/right: the left then the right
+/// curve. Mid/side: the mid then the side curve, the side including the
+/// stereo width.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EqResponse {
+    pub mode: gecko_dsp::EqChannelMode,
+    pub curves: Vec<gecko_dsp::FrequencyResponse>,
+    pub stereo_width: f32,
+}
+
 /// Engine state for the frontend
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EngineState {
@@ -254,8 +266,21 @@ pub fn run() {
             commands::stop_engine,
             commands::is_engine_running,
             commands::set_band_gain,
+            commands::set_band_params,
+            commands::set_eq_channel_mode,
+            commands::set_secondary_band_gain,
+            commands::set_secondary_band_params,
+            commands::set_stereo_width,
+            commands::set_auto_preamp,
+            commands::set_eq_preamp,
+            commands::get_eq_preamp,
+            commands::set_stream_band_params,
+            commands::get_band_params,
+            commands::get_eq_response,
             commands::set_stream_band_gain,
             commands::set_app_bypass,
+            commands::set_app_auto_level,
+            commands::set_app_delay,
             commands::set_stream_volume,
             commands::set_master_volume,
             commands::set_dsp_volume,
@@ -264,6 +289,7 @@ pub fn run() {
             commands::list_devices,
             commands::list_audio_streams,
             commands::get_eq_bands,
+            commands::set_eq_band_count,
             commands::poll_events,
             commands::get_platform_info,
             commands::get_settings,
@@ -272,9 +298,26 @@ pub fn run() {
             commands::save_preset,
             commands::delete_preset,
             commands::apply_preset,
+            commands::import_apo_preset,
+            commands::export_apo_preset,
             commands::get_autostart,
             commands::set_autostart,
             commands::set_soft_clip,
+            commands::set_output_stage,
+            commands::set_limiter_settings,
+            commands::set_phase_mode,
+            commands::load_impulse_response,
+            commands::clear_impulse_response,
+            commands::reset_loudness,
+            commands::set_auto_level_settings,
+            commands::get_crossfeed_presets,
+            commands::set_crossfeed,
+            commands::get_processor_chain,
+            commands::set_processor_chain,
+            commands::add_processor,
+            commands::remove_processor,
+            commands::move_processor,
+            commands::set_processor_bypass,
             // macOS-specific commands
             commands::get_macos_audio_info,
             commands::check_screen_recording_permission,
@@ -316,12 +359,21 @@ mod tests {
 
     #[test]
     fn test_eq_bands_info() {
-        let bands = commands::get_eq_bands();
+        let bands = commands::get_eq_bands(None);
         assert_eq!(bands.len(), 10);
         assert_eq!(bands[0].frequency, 31.0);
         assert_eq!(bands[9].frequency, 16000.0);
     }
 
+    #[test]
+    fn test_eq_bands_info_custom_count() {
+        assert_eq!(commands::get_eq_bands(Some(3)).len(), 3);
+        let bands = commands::get_eq_bands(Some(31));
+        assert_eq!(bands.len(), 31);
+        assert_eq!(bands[0].frequency, 20.0);
+        assert_eq!(bands[30].frequency, 20000.0);
+    }
+
     #[test]
     fn test_platform_info() {
         let info = commands::get_platform_info();
@@ -330,7 +382,7 @@ mod tests {
 
     #[test]
     fn test_eq_bands_frequencies_complete() {
-        let bands = commands::get_eq_bands();
+        let bands = commands::get_eq_bands(None);
         // Verify all 10-band EQ frequencies match spec
         let expected: Vec<f32> = vec![31.0, 62.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0];
         for (i, band) in bands.iter().enumerate() {